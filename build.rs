@@ -0,0 +1,35 @@
+// Records the target triple and profile that Criterion.rs (and therefore the benchmarks using it)
+// is compiled for, for the per-target output directories of `--per-target-output`, and the version
+// of the compiler, for the metadata of the results and the features it supports.
+fn main() {
+    for (var, name) in [
+        ("TARGET", "CRITERION_TARGET"),
+        ("PROFILE", "CRITERION_PROFILE"),
+    ] {
+        let value = std::env::var(var).unwrap_or_else(|_| "unknown".to_owned());
+        println!("cargo:rustc-env={}={}", name, value);
+    }
+
+    let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let version = std::process::Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_owned())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=CRITERION_RUSTC_VERSION={}", version);
+
+    // Inline assembly, which reads the cycle counter on AArch64, needs Rust 1.59, past the MSRV.
+    println!("cargo:rustc-check-cfg=cfg(criterion_asm)");
+    let minor = version
+        .split_whitespace()
+        .nth(1)
+        .and_then(|version| version.split('.').nth(1))
+        .and_then(|minor| minor.parse::<u32>().ok());
+    if matches!(minor, Some(minor) if minor >= 59) {
+        println!("cargo:rustc-cfg=criterion_asm");
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,197 @@
+//! Sample collection for [Criterion.rs](https://github.com/bheisler/criterion.rs) without the
+//! standard library.
+//!
+//! This crate measures benchmarks the way Criterion.rs does: a warm-up to estimate the time per
+//! iteration, then a series of samples whose iteration counts follow the same sampling plan. It
+//! only needs `core` and `alloc`, so it runs on embedded devices, in kernels and in other `no_std`
+//! environments. The statistics, reports and plots stay on the host: print the [`Samples`] (eg.
+//! over a serial port), read them back with [`Samples::parse_all`] and pass them to
+//! `Criterion::bench_samples` to analyze them like any other benchmark.
+//!
+//! ```
+//! use criterion_core::{collect, Clock, Config};
+//!
+//! /// A clock counting CPU cycles, eg. read from the DWT cycle counter on Cortex-M.
+//! struct Cycles;
+//! impl Clock for Cycles {
+//!     type Instant = u64;
+//!     fn now(&self) -> u64 {
+//!         // read the cycle counter
+//!         # thread_local!(static START: std::time::Instant = std::time::Instant::now());
+//!         # START.with(|start| start.elapsed().as_nanos() as u64)
+//!     }
+//!     fn elapsed(&self, start: &u64) -> u64 {
+//!         self.now() - start
+//!     }
+//! }
+//!
+//! let config = Config {
+//!     sample_size: 20,
+//!     warm_up_time: 10_000_000,
+//!     measurement_time: 100_000_000,
+//!     ..Config::default()
+//! };
+//! let samples = collect(&Cycles, "fib 16", &config, || (0..16u64).product::<u64>());
+//! // eg. `writeln!(uart, "{}", samples)`
+//! # assert_eq!(samples.iters.len(), 20);
+//! ```
+
+#![no_std]
+#![warn(missing_docs)]
+
+extern crate alloc;
+
+mod samples;
+pub mod sampling;
+
+use alloc::string::ToString;
+
+pub use crate::samples::{ParseError, Samples};
+pub use crate::sampling::{Method, SamplingMode};
+
+/// A source of time stamps. The unit is up to the clock, eg. nanoseconds or CPU cycles, but the
+/// host has to analyze the samples with a measurement of the same unit (`WallTime` for
+/// nanoseconds).
+pub trait Clock {
+    /// A point in time.
+    type Instant;
+
+    /// Returns the current time.
+    fn now(&self) -> Self::Instant;
+
+    /// Returns the time elapsed since `start`.
+    fn elapsed(&self, start: &Self::Instant) -> u64;
+}
+
+/// How to measure a benchmark. All times are in the unit of the [`Clock`].
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    /// Number of samples to take. At least 10 for the host-side analysis.
+    pub sample_size: u64,
+    /// How long to run the benchmark for before measuring it.
+    pub warm_up_time: u64,
+    /// How long the samples should take in all.
+    pub measurement_time: u64,
+    /// How to choose the iteration counts of the samples.
+    pub sampling_mode: SamplingMode,
+}
+impl Default for Config {
+    /// The defaults of Criterion.rs: 100 samples, a warm-up of 3 s and a measurement of 5 s, in
+    /// nanoseconds.
+    fn default() -> Config {
+        Config {
+            sample_size: 100,
+            warm_up_time: 3_000_000_000,
+            measurement_time: 5_000_000_000,
+            sampling_mode: SamplingMode::Auto,
+        }
+    }
+}
+
+/// A function that is opaque to the optimizer, used to prevent the compiler from optimizing away
+/// computations in a benchmark.
+///
+/// This variant is stable-compatible, but it may cause some performance overhead or fail to
+/// prevent code from being eliminated.
+pub fn black_box<T>(dummy: T) -> T {
+    unsafe {
+        let ret = core::ptr::read_volatile(&dummy);
+        core::mem::forget(dummy);
+        ret
+    }
+}
+
+/// Runs `routine` repeatedly for `config.warm_up_time`, then measures it and returns the samples.
+/// The output of `routine` is passed to [`black_box`] so that it is not optimized away. Like with
+/// `Bencher::iter`, the time to drop it is included in the measurement.
+pub fn collect<C, O, R>(clock: &C, id: &str, config: &Config, mut routine: R) -> Samples
+where
+    C: Clock,
+    R: FnMut() -> O,
+{
+    assert!(config.sample_size > 0);
+
+    let mut run = |iters: u64| {
+        let start = clock.now();
+        for _ in 0..iters {
+            black_box(routine());
+        }
+        clock.elapsed(&start)
+    };
+
+    // Double the iteration count until the warm-up time has passed, like Criterion.rs does.
+    let mut iters = 1u64;
+    let mut total_iters = 0u64;
+    let mut elapsed = 0u64;
+    loop {
+        elapsed = elapsed.saturating_add(run(iters));
+        total_iters += iters;
+        if elapsed > config.warm_up_time {
+            break;
+        }
+        iters = iters.wrapping_mul(2);
+    }
+    // A clock too coarse to see a single iteration still needs a non-zero estimate.
+    let met = (elapsed as f64 / total_iters as f64).max(f64::MIN_POSITIVE);
+
+    let method =
+        config
+            .sampling_mode
+            .choose(met, config.sample_size, config.measurement_time as f64);
+    let iters = sampling::iteration_counts(
+        method,
+        met,
+        config.sample_size,
+        config.measurement_time as f64,
+    );
+    let values = iters.iter().map(|&iters| run(iters)).collect();
+    Samples {
+        id: id.to_string(),
+        method,
+        iters,
+        values,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::cell::Cell;
+
+    /// A clock which only advances when told to.
+    struct FakeClock(Cell<u64>);
+    impl Clock for FakeClock {
+        type Instant = u64;
+        fn now(&self) -> u64 {
+            self.0.get()
+        }
+        fn elapsed(&self, start: &u64) -> u64 {
+            self.now() - start
+        }
+    }
+
+    #[test]
+    fn collect_follows_the_sampling_plan() {
+        let config = Config {
+            sample_size: 10,
+            warm_up_time: 1_000,
+            measurement_time: 100_000,
+            sampling_mode: SamplingMode::Linear,
+        };
+        // Every iteration takes 10 units.
+        let clock = FakeClock(Cell::new(0));
+        let samples = collect(&clock, "id", &config, || clock.0.set(clock.0.get() + 10));
+
+        assert_eq!(samples.id, "id");
+        assert_eq!(samples.method, Method::Linear);
+        assert_eq!(samples.iters.len(), 10);
+        assert_eq!(samples.values.len(), 10);
+        // d + 2d + ... + 10d iterations of 10 units fill the 100 000 units of the measurement.
+        let d = 182;
+        assert_eq!(
+            samples.iters,
+            (1..=10).map(|i| i * d).collect::<alloc::vec::Vec<_>>()
+        );
+        assert_eq!(samples.values[0], d * 10);
+    }
+}
@@ -0,0 +1,159 @@
+//! The samples of a benchmark and their text format.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::sampling::Method;
+
+const HEADER: &str = "criterion-samples";
+const FOOTER: &str = "criterion-samples-end";
+
+/// The samples of one benchmark: the number of iterations in each sample and the time (or other
+/// measured value) they took, in the unit of the clock used.
+///
+/// `Display` writes the samples in a line-based text format, which [`Samples::parse_all`] reads
+/// back on the host, eg. from the log of a serial port:
+///
+/// ```text
+/// criterion-samples linear fib 20
+/// 10 1520
+/// 20 3016
+/// ...
+/// criterion-samples-end
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Samples {
+    /// ID of the benchmark. It must fit on one line.
+    pub id: String,
+    /// How the iteration counts were chosen.
+    pub method: Method,
+    /// Number of iterations in each sample.
+    pub iters: Vec<u64>,
+    /// Time taken by each sample, in the unit of the clock.
+    pub values: Vec<u64>,
+}
+
+/// Error returned by [`Samples::parse_all`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line number, starting at 1.
+    pub line: usize,
+    /// What was wrong with the line.
+    pub message: &'static str,
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl fmt::Display for Samples {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {} {}", HEADER, self.method.name(), self.id)?;
+        for (iters, value) in self.iters.iter().zip(&self.values) {
+            writeln!(f, "{} {}", iters, value)?;
+        }
+        writeln!(f, "{}", FOOTER)
+    }
+}
+
+impl Samples {
+    /// Reads all the samples in `text`, in the format written by `Display`. Lines outside of the
+    /// samples, such as other log output, are ignored, as is leading and trailing whitespace.
+    pub fn parse_all(text: &str) -> Result<Vec<Samples>, ParseError> {
+        let mut all = Vec::new();
+        let mut current: Option<Samples> = None;
+        for (index, line) in text.lines().enumerate() {
+            let error = |message| ParseError {
+                line: index + 1,
+                message,
+            };
+            let line = line.trim();
+            match current.as_mut() {
+                None => {
+                    let header = match line.strip_prefix(HEADER) {
+                        Some(rest) if rest.starts_with(' ') => rest.trim_start(),
+                        _ => continue,
+                    };
+                    let (method, id) = header
+                        .split_once(' ')
+                        .ok_or_else(|| error("expected a sampling method and an ID"))?;
+                    let method = Method::from_name(method)
+                        .ok_or_else(|| error("unknown sampling method"))?;
+                    current = Some(Samples {
+                        id: id.trim().to_string(),
+                        method,
+                        iters: Vec::new(),
+                        values: Vec::new(),
+                    });
+                }
+                Some(_) if line == FOOTER => {
+                    let samples = current.take().unwrap();
+                    if samples.iters.is_empty() {
+                        return Err(error("no samples"));
+                    }
+                    all.push(samples);
+                }
+                Some(samples) => {
+                    let mut numbers = line.split_whitespace().map(str::parse::<u64>);
+                    match (numbers.next(), numbers.next(), numbers.next()) {
+                        (Some(Ok(iters)), Some(Ok(value)), None) if iters > 0 => {
+                            samples.iters.push(iters);
+                            samples.values.push(value);
+                        }
+                        _ => return Err(error("expected an iteration count and a value")),
+                    }
+                }
+            }
+        }
+        match current {
+            Some(_) => Err(ParseError {
+                line: text.lines().count(),
+                message: "missing criterion-samples-end",
+            }),
+            None => Ok(all),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::format;
+    use alloc::vec;
+
+    #[test]
+    fn samples_round_trip_through_text() {
+        let samples = Samples {
+            id: "group/fib 20".to_string(),
+            method: Method::Linear,
+            iters: vec![10, 20],
+            values: vec![1520, 3016],
+        };
+        let text = format!("boot ok\n{}other output\n", samples);
+        assert_eq!(
+            text,
+            "boot ok\ncriterion-samples linear group/fib 20\n10 1520\n20 3016\ncriterion-samples-end\nother output\n"
+        );
+        assert_eq!(Samples::parse_all(&text), Ok(vec![samples]));
+    }
+
+    #[test]
+    fn malformed_samples_are_rejected() {
+        let error = |text| Samples::parse_all(text).unwrap_err();
+        assert_eq!(error("criterion-samples sideways id\n").line, 1);
+        assert_eq!(
+            error("criterion-samples flat id\n10\ncriterion-samples-end\n").line,
+            2
+        );
+        assert_eq!(
+            error("criterion-samples flat id\ncriterion-samples-end\n").message,
+            "no samples"
+        );
+        assert_eq!(
+            error("criterion-samples flat id\n10 20\n").message,
+            "missing criterion-samples-end"
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! The sampling plan: how many iterations to run in each sample.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// How to choose the iteration counts of the samples. See `criterion::SamplingMode`, which this
+/// mirrors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplingMode {
+    /// Choose between `Linear` and `Flat` based on the iteration time measured during the warm-up.
+    Auto,
+    /// Scale the iteration count linearly: `d, 2d, 3d, ...`.
+    Linear,
+    /// Use the same iteration count for all samples.
+    Flat,
+}
+
+/// The sampling method actually used, ie. `SamplingMode` without `Auto`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// The iteration counts are `d, 2d, 3d, ...`.
+    Linear,
+    /// All samples have the same iteration count.
+    Flat,
+}
+impl Method {
+    /// Name of the method in the samples format, `linear` or `flat`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Method::Linear => "linear",
+            Method::Flat => "flat",
+        }
+    }
+
+    /// Parses the name returned by [`Method::name`].
+    pub fn from_name(name: &str) -> Option<Method> {
+        match name {
+            "linear" => Some(Method::Linear),
+            "flat" => Some(Method::Flat),
+            _ => None,
+        }
+    }
+}
+
+/// `x.ceil()`, which is not available without the standard library, for the non-negative values
+/// used here.
+fn ceil(x: f64) -> f64 {
+    let truncated = x as u64 as f64;
+    if truncated < x {
+        truncated + 1.0
+    } else {
+        truncated
+    }
+}
+
+impl SamplingMode {
+    /// Chooses the sampling method for a benchmark taking `warmup_mean_execution_time` per
+    /// iteration, to take `sample_count` samples in `target_time` (in the same unit).
+    pub fn choose(
+        &self,
+        warmup_mean_execution_time: f64,
+        sample_count: u64,
+        target_time: f64,
+    ) -> Method {
+        match self {
+            SamplingMode::Linear => Method::Linear,
+            SamplingMode::Flat => Method::Flat,
+            SamplingMode::Auto => {
+                // Estimate execution time with linear sampling
+                let total_runs = sample_count * (sample_count + 1) / 2;
+                let d = ceil(target_time / warmup_mean_execution_time / total_runs as f64) as u64;
+                let expected_time = total_runs as f64 * d as f64 * warmup_mean_execution_time;
+
+                if expected_time > (2.0 * target_time) {
+                    Method::Flat
+                } else {
+                    Method::Linear
+                }
+            }
+        }
+    }
+}
+
+/// Returns the iteration counts of `sample_count` samples which take about `target_time` in all,
+/// for a benchmark taking `warmup_mean_execution_time` per iteration (in the same unit). Every
+/// sample has at least one iteration, so the samples take longer than `target_time` if the
+/// benchmark is too slow for it.
+pub fn iteration_counts(
+    method: Method,
+    warmup_mean_execution_time: f64,
+    sample_count: u64,
+    target_time: f64,
+) -> Vec<u64> {
+    let n = sample_count;
+    let met = warmup_mean_execution_time;
+    match method {
+        Method::Linear => {
+            // Solve: [d + 2*d + 3*d + ... + n*d] * met = target_time
+            let total_runs = n * (n + 1) / 2;
+            let d = (ceil(target_time / met / total_runs as f64) as u64).max(1);
+            (1..(n + 1)).map(|a| a * d).collect()
+        }
+        Method::Flat => {
+            let time_per_sample = target_time / (n as f64);
+            // This is pretty simplistic; we could do something smarter to fit into the allotted
+            // time.
+            let iterations_per_sample = (ceil(time_per_sample / met) as u64).max(1);
+            vec![iterations_per_sample; n as usize]
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn iteration_counts_fill_the_target_time() {
+        // 10 samples of 1 time unit per iteration in 550 units: 10 + 20 + ... + 100 iterations.
+        let linear = iteration_counts(Method::Linear, 1.0, 10, 550.0);
+        assert_eq!(linear, (1..=10).map(|i| i * 10).collect::<Vec<_>>());
+        let flat = iteration_counts(Method::Flat, 1.0, 10, 550.0);
+        assert_eq!(flat, vec![55; 10]);
+        // Too slow for the target time: one iteration per sample at least.
+        assert_eq!(iteration_counts(Method::Flat, 100.0, 10, 10.0), vec![1; 10]);
+    }
+
+    #[test]
+    fn auto_prefers_flat_for_slow_benchmarks() {
+        assert_eq!(SamplingMode::Auto.choose(1.0, 100, 5e9), Method::Linear);
+        assert_eq!(SamplingMode::Auto.choose(1e9, 100, 5e9), Method::Flat);
+        assert_eq!(SamplingMode::Flat.choose(1.0, 100, 5e9), Method::Flat);
+    }
+
+    #[test]
+    fn ceil_rounds_up() {
+        assert_eq!(ceil(0.0), 0.0);
+        assert_eq!(ceil(2.0), 2.0);
+        assert_eq!(ceil(2.1), 3.0);
+    }
+}
@@ -65,6 +65,25 @@ impl Matrix {
     pub fn nrows(&self) -> usize {
         self.nrows
     }
+
+    /// The rows of the matrix as text, one line per row with its values separated by spaces
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        let row_len = self.ncols * mem::size_of::<f64>();
+        for row in self.bytes.chunks(row_len) {
+            let values: Vec<String> = row
+                .chunks(mem::size_of::<f64>())
+                .map(|bytes| {
+                    let mut le = [0; 8];
+                    le.copy_from_slice(bytes);
+                    f64::from_bits(u64::from_le_bytes(le)).to_string()
+                })
+                .collect();
+            text.push_str(&values.join(" "));
+            text.push('\n');
+        }
+        text
+    }
 }
 
 /// Data that can serve as a row of the data matrix
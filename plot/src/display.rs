@@ -100,8 +100,10 @@ impl Display<&'static str> for PointType {
         match *self {
             PointType::Circle => "6",
             PointType::FilledCircle => "7",
+            PointType::FilledInvertedTriangle => "11",
             PointType::FilledSquare => "5",
             PointType::FilledTriangle => "9",
+            PointType::InvertedTriangle => "10",
             PointType::Plus => "1",
             PointType::Square => "4",
             PointType::Star => "3",
@@ -124,6 +126,8 @@ impl Display<&'static str> for Terminal {
     fn display(&self) -> &'static str {
         match *self {
             Terminal::Svg => "svg dynamic",
+            Terminal::Png => "pngcairo",
+            Terminal::Pdf => "pdfcairo",
         }
     }
 }
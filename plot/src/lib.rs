@@ -402,14 +402,19 @@ pub mod prelude;
 pub mod proxy;
 pub mod traits;
 
+/// Pixels per inch of the sizes given to the PDF terminal, the resolution of CSS pixels
+const PIXELS_PER_INCH: f64 = 96.0;
+
 /// Plot container
 #[derive(Clone)]
 pub struct Figure {
     alpha: Option<f64>,
     axes: map::axis::Map<axis::Properties>,
+    background: Option<Color>,
     box_width: Option<f64>,
     font: Option<Cow<'static, str>>,
     font_size: Option<f64>,
+    foreground: Option<Color>,
     key: Option<key::Properties>,
     output: Cow<'static, Path>,
     plots: Vec<Plot>,
@@ -425,9 +430,11 @@ impl Figure {
         Figure {
             alpha: None,
             axes: map::axis::Map::new(),
+            background: None,
             box_width: None,
             font: None,
             font_size: None,
+            foreground: None,
             key: None,
             output: Cow::Borrowed(Path::new("output.plot")),
             plots: Vec::new(),
@@ -442,6 +449,13 @@ impl Figure {
     // don't have it defined.
     #[allow(clippy::all)]
     fn script(&self) -> Vec<u8> {
+        self.script_reading(None)
+    }
+
+    /// The script of the figure. With a `data_file`, the plots read their data from the blocks of
+    /// that file, in order, instead of from the binary data appended to the script.
+    #[allow(clippy::all)]
+    fn script_reading(&self, data_file: Option<&Path>) -> Vec<u8> {
         let mut s = String::new();
 
         s.push_str(&format!(
@@ -449,6 +463,18 @@ impl Figure {
             self.output.display().to_string().replace('\'', "''")
         ));
 
+        if let Some(color) = self.foreground {
+            // Set before the title, labels and key, which keep their color when they are set. The
+            // grid keeps its color while it is unset, until the axes show their gridlines.
+            let color = color.display();
+            s.push_str(&format!("set border lc rgb '{}'\n", color));
+            s.push_str(&format!("set grid lc rgb '{}'\nunset grid\n", color));
+            s.push_str(&format!("set tics textcolor rgb '{}'\n", color));
+            for label in &["title", "xlabel", "ylabel", "x2label", "y2label", "key"] {
+                s.push_str(&format!("set {} textcolor rgb '{}'\n", label, color));
+            }
+        }
+
         if let Some(width) = self.box_width {
             s.push_str(&format!("set boxwidth {}\n", width))
         }
@@ -476,7 +502,17 @@ impl Figure {
         s.push_str(&format!("set terminal {} dashed", self.terminal.display()));
 
         if let Some((width, height)) = self.size {
-            s.push_str(&format!(" size {}, {}", width, height))
+            match self.terminal {
+                // The PDF terminal measures its size in inches rather than pixels
+                Terminal::Pdf => s.push_str(&format!(
+                    " size {}in, {}in",
+                    width as f64 / PIXELS_PER_INCH,
+                    height as f64 / PIXELS_PER_INCH
+                )),
+                Terminal::Svg | Terminal::Png => {
+                    s.push_str(&format!(" size {}, {}", width, height))
+                }
+            }
         }
 
         if let Some(ref name) = self.font {
@@ -487,10 +523,15 @@ impl Figure {
             }
         }
 
+        if let Some(color) = self.background {
+            s.push_str(&format!(" background rgb '{}'", color.display()))
+        }
+
         // TODO This removes the crossbars from the ends of error bars, but should be configurable
         s.push_str("\nunset bars\n");
 
         let mut is_first_plot = true;
+        let mut index = 0;
         for plot in &self.plots {
             let data = plot.data();
 
@@ -505,10 +546,20 @@ impl Figure {
                 s.push_str(", ");
             }
 
-            s.push_str(&format!(
-                "'-' binary endian=little record={} format='%float64' using ",
-                data.nrows()
-            ));
+            match data_file {
+                Some(path) => {
+                    s.push_str(&format!(
+                        "'{}' index {} using ",
+                        path.display().to_string().replace('\'', "''"),
+                        index
+                    ));
+                    index += 1;
+                }
+                None => s.push_str(&format!(
+                    "'-' binary endian=little record={} format='%float64' using ",
+                    data.nrows()
+                )),
+            }
 
             let mut is_first_col = true;
             for col in 0..data.ncols() {
@@ -525,6 +576,10 @@ impl Figure {
         }
 
         let mut buffer = s.into_bytes();
+        if data_file.is_some() {
+            buffer.push(b'\n');
+            return buffer;
+        }
         let mut is_first = true;
         for plot in &self.plots {
             if is_first {
@@ -568,6 +623,28 @@ impl Figure {
         File::create(path)?.write_all(&self.script())?;
         Ok(self)
     }
+
+    /// Saves the figure as an editable script at `script_path`, which reads the data of the plots
+    /// from a text file saved at `data_path`, one block per plot separated by two blank lines.
+    /// Running `gnuplot` on the script renders the figure again.
+    pub fn export(&self, script_path: &Path, data_path: &Path) -> io::Result<&Figure> {
+        use std::io::Write;
+
+        let mut data = String::new();
+        for plot in self
+            .plots
+            .iter()
+            .filter(|plot| !plot.data().bytes().is_empty())
+        {
+            if !data.is_empty() {
+                data.push_str("\n\n");
+            }
+            data.push_str(&plot.data().text());
+        }
+        File::create(data_path)?.write_all(data.as_bytes())?;
+        File::create(script_path)?.write_all(&self.script_reading(Some(data_path)))?;
+        Ok(self)
+    }
 }
 
 impl Configure<Axis> for Figure {
@@ -608,6 +685,16 @@ impl Configure<Key> for Figure {
     }
 }
 
+impl Set<Background> for Figure {
+    /// Changes the background color
+    ///
+    /// **Note** By default, the background is white
+    fn set(&mut self, background: Background) -> &mut Figure {
+        self.background = Some(background.0);
+        self
+    }
+}
+
 impl Set<BoxWidth> for Figure {
     /// Changes the box width of all the box related plots (bars, candlesticks, etc)
     ///
@@ -634,6 +721,14 @@ impl Set<Font> for Figure {
     }
 }
 
+impl Set<Foreground> for Figure {
+    /// Changes the color of the text, the border and the gridlines
+    fn set(&mut self, foreground: Foreground) -> &mut Figure {
+        self.foreground = Some(foreground.0);
+        self
+    }
+}
+
 impl Set<FontSize> for Figure {
     /// Changes the size of the font
     ///
@@ -692,6 +787,10 @@ impl Default for Figure {
     }
 }
 
+/// Background color of the figure
+#[derive(Clone, Copy)]
+pub struct Background(pub Color);
+
 /// Box width for box-related plots: bars, candlesticks, etc
 #[derive(Clone, Copy)]
 pub struct BoxWidth(pub f64);
@@ -703,6 +802,10 @@ pub struct Font(Cow<'static, str>);
 #[derive(Clone, Copy)]
 pub struct FontSize(pub f64);
 
+/// Color of the text, the border and the gridlines of the figure
+#[derive(Clone, Copy)]
+pub struct Foreground(pub Color);
+
 /// The key or legend
 #[derive(Clone, Copy)]
 pub struct Key;
@@ -844,8 +947,10 @@ pub enum LineType {
 pub enum PointType {
     Circle,
     FilledCircle,
+    FilledInvertedTriangle,
     FilledSquare,
     FilledTriangle,
+    InvertedTriangle,
     Plus,
     Square,
     Star,
@@ -867,10 +972,14 @@ pub enum Scale {
 pub struct ScaleFactor(pub f64);
 
 /// Output terminal
-#[allow(missing_docs)]
 #[derive(Clone, Copy)]
 pub enum Terminal {
+    /// SVG images
     Svg,
+    /// PNG images, rendered with cairo
+    Png,
+    /// PDF documents, rendered with cairo
+    Pdf,
 }
 
 /// Not public version of `std::default::Default`, used to not leak default constructors into the
@@ -1050,6 +1159,51 @@ trait ScaleFactorTrait {
 
 #[cfg(test)]
 mod test {
+    use std::path::Path;
+
+    use super::curve::Curve::Lines;
+    use super::traits::Plot as _;
+    use super::{Background, Color, Figure, Foreground, Set, Size, Terminal};
+
+    #[test]
+    fn pdf_size_in_inches() {
+        let mut figure = Figure::new();
+        figure.set(Terminal::Pdf).set(Size(960, 540));
+        let script = String::from_utf8(figure.script()).unwrap();
+        assert!(script.contains("set terminal pdfcairo dashed size 10in, 5.625in"));
+    }
+
+    #[test]
+    fn colors() {
+        let mut figure = Figure::new();
+        figure
+            .set(Background(Color::Rgb(30, 30, 30)))
+            .set(Foreground(Color::White));
+        let script = String::from_utf8(figure.script()).unwrap();
+        assert!(script.contains("set terminal svg dynamic dashed background rgb '#1e1e1e'"));
+        assert!(script.contains("set title textcolor rgb 'white'"));
+    }
+
+    #[test]
+    fn export_reads_data_file() {
+        let mut figure = Figure::new();
+        figure
+            .plot(
+                Lines {
+                    x: &[1., 2.],
+                    y: &[3., 4.5],
+                },
+                |l| l,
+            )
+            .plot(Lines { x: &[5.], y: &[6.] }, |l| l);
+        let script = figure.script_reading(Some(Path::new("plot.dat")));
+        let script = String::from_utf8(script).unwrap();
+        assert!(script.contains("plot 'plot.dat' index 0 using 1:2 "));
+        assert!(script.contains(", 'plot.dat' index 1 using 1:2 "));
+        assert!(!script.contains("binary"));
+        assert_eq!(figure.plots[0].data().text(), "1 3\n2 4.5\n");
+    }
+
     #[test]
     fn version() {
         if let Ok(version) = super::version() {
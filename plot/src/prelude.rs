@@ -8,6 +8,6 @@ pub use crate::key::{Boxed, Horizontal, Justification, Order, Position, Stacked,
 pub use crate::proxy::{Font, Label, Output, Title};
 pub use crate::traits::{Configure, Plot, Set};
 pub use crate::{
-    Axes, Axis, BoxWidth, Color, Figure, FontSize, Grid, Key, LineType, LineWidth, Opacity,
-    PointSize, PointType, Range, Scale, ScaleFactor, Size, Terminal, TicLabels,
+    Axes, Axis, Background, BoxWidth, Color, Figure, FontSize, Foreground, Grid, Key, LineType,
+    LineWidth, Opacity, PointSize, PointType, Range, Scale, ScaleFactor, Size, Terminal, TicLabels,
 };
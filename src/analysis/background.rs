@@ -0,0 +1,152 @@
+//! Analyzing benchmarks in the background (`--background-analysis`).
+//!
+//! Bootstrapping the statistics of a benchmark takes a while, and suites of many short benchmarks
+//! can spend most of their time on it. With background analysis, the bootstrapping runs on a
+//! dedicated rayon pool while the next benchmarks are set up and warmed up, and the results are
+//! saved and reported, in order, once they are ready.
+//!
+//! To keep the workers from disturbing the measurements, they pause while any benchmark is taking
+//! its samples: the bootstrap loops check [`pause_while_measuring`] before every resample, and the
+//! sampling holds a [`measuring`] guard. Warm-ups are not guarded, since they only estimate the
+//! iteration time.
+//!
+//! Without the `rayon` feature, the analysis always runs inline.
+
+#[cfg(feature = "rayon")]
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::{Condvar, Mutex};
+
+use once_cell::sync::Lazy;
+
+/// Number of benchmarks currently taking samples, on any thread.
+static MEASURING: AtomicUsize = AtomicUsize::new(0);
+static GATE: Lazy<(Mutex<()>, Condvar)> = Lazy::new(|| (Mutex::new(()), Condvar::new()));
+
+#[cfg(feature = "rayon")]
+static POOL: Lazy<rayon::ThreadPool> = Lazy::new(|| {
+    rayon::ThreadPoolBuilder::new()
+        .thread_name(|i| format!("criterion-analysis-{}", i))
+        .start_handler(|_| WORKER.with(|worker| worker.set(true)))
+        .build()
+        .expect("unable to start the background analysis threads")
+});
+
+#[cfg(feature = "rayon")]
+thread_local! {
+    // Only the background workers pause; the analysis of other threads, eg. of the groups run
+    // concurrently with `--jobs`, must not wait for their neighbours. (`const` initializers of
+    // thread-locals need a newer compiler than the MSRV.)
+    #[allow(clippy::missing_const_for_thread_local)]
+    static WORKER: Cell<bool> = Cell::new(false);
+}
+
+/// Keeps the background workers paused until dropped.
+pub(crate) struct Measuring(());
+impl Drop for Measuring {
+    fn drop(&mut self) {
+        let _lock = GATE.0.lock().unwrap_or_else(|e| e.into_inner());
+        MEASURING.fetch_sub(1, Ordering::SeqCst);
+        GATE.1.notify_all();
+    }
+}
+
+/// Pauses the background workers while the samples are taken.
+pub(crate) fn measuring() -> Measuring {
+    MEASURING.fetch_add(1, Ordering::SeqCst);
+    Measuring(())
+}
+
+/// Blocks while samples are being taken, if called on a background worker.
+#[cfg(feature = "rayon")]
+pub(crate) fn pause_while_measuring() {
+    if MEASURING.load(Ordering::Relaxed) == 0 || !WORKER.with(Cell::get) {
+        return;
+    }
+    let mut lock = GATE.0.lock().unwrap_or_else(|e| e.into_inner());
+    while MEASURING.load(Ordering::SeqCst) > 0 {
+        lock = GATE.1.wait(lock).unwrap_or_else(|e| e.into_inner());
+    }
+}
+
+/// The result of a computation running inline or in the background.
+pub(crate) enum Job<T> {
+    Done(std::thread::Result<T>),
+    #[cfg_attr(not(feature = "rayon"), allow(dead_code))]
+    Running(Receiver<std::thread::Result<T>>),
+}
+impl<T: Send + 'static> Job<T> {
+    /// Runs `f` right away.
+    pub fn inline<F: FnOnce() -> T>(f: F) -> Job<T> {
+        Job::Done(Ok(f()))
+    }
+
+    /// Runs `f` on the background pool, or right away without the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn spawn<F: FnOnce() -> T + Send + 'static>(f: F) -> Job<T> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        POOL.spawn(move || {
+            // A panic is passed on to the thread waiting for the result rather than aborting.
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+            let _ = sender.send(result);
+        });
+        Job::Running(receiver)
+    }
+
+    /// Runs `f` on the background pool, or right away without the `rayon` feature.
+    #[cfg(not(feature = "rayon"))]
+    pub fn spawn<F: FnOnce() -> T + Send + 'static>(f: F) -> Job<T> {
+        Job::inline(f)
+    }
+
+    /// Returns true if the result is available.
+    pub fn is_done(&mut self) -> bool {
+        if let Job::Running(receiver) = self {
+            match receiver.try_recv() {
+                Ok(result) => *self = Job::Done(result),
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => unreachable!("the job always sends a result"),
+            }
+        }
+        true
+    }
+
+    /// Waits for the result. Panics if the computation panicked.
+    pub fn wait(self) -> T {
+        let result = match self {
+            Job::Done(result) => result,
+            Job::Running(receiver) => receiver.recv().expect("the job always sends a result"),
+        };
+        result.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn jobs_return_their_results() {
+        let job = Job::spawn(|| (1..=10).sum::<u32>());
+        let inline = Job::inline(|| 2);
+        assert_eq!(job.wait(), 55);
+        assert_eq!(inline.wait(), 2);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn workers_pause_while_measuring() {
+        let guard = measuring();
+        let mut job = Job::spawn(|| {
+            pause_while_measuring();
+            7
+        });
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!job.is_done());
+        // Other threads are not paused.
+        pause_while_measuring();
+        drop(guard);
+        assert_eq!(job.wait(), 7);
+    }
+}
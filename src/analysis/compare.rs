@@ -1,81 +1,215 @@
+use std::path::Path;
+
 use crate::stats::univariate::Sample;
 use crate::stats::univariate::{self, mixed};
-use crate::stats::Distribution;
+use crate::stats::{Distribution, Tails};
 
-use crate::benchmark::BenchmarkConfig;
 use crate::error::Result;
 use crate::estimate::{
     build_change_estimates, ChangeDistributions, ChangeEstimates, ChangePointEstimates, Estimates,
+    Statistic,
 };
+use crate::manifest::Manifest;
 use crate::measurement::Measurement;
-use crate::report::BenchmarkId;
-use crate::{fs, Criterion, SavedSample};
+use crate::report::{BenchmarkId, ComparisonData};
+use crate::{fs, ComparisonTest, Criterion, SavedSample};
 
-// Common comparison procedure
-#[cfg_attr(feature = "cargo-clippy", allow(clippy::type_complexity))]
-pub(crate) fn common<M: Measurement>(
-    id: &BenchmarkId,
-    avg_times: &Sample<f64>,
-    config: &BenchmarkConfig,
-    criterion: &Criterion<M>,
-) -> Result<(
-    f64,
-    Distribution<f64>,
-    ChangeEstimates,
-    ChangeDistributions,
-    Vec<f64>,
-    Vec<f64>,
-    Vec<f64>,
-    Estimates,
-)> {
-    let mut sample_file = criterion.output_directory.clone();
-    sample_file.push(id.as_directory_name());
-    sample_file.push(&criterion.baseline_directory);
-    sample_file.push("sample.json");
-    let sample: SavedSample = fs::load(&sample_file)?;
+/// File in the `change` directory of a benchmark which records the `SignificantChange` of the last
+/// comparison, or `null` if it was not significant.
+pub(crate) const SIGNIFICANCE_FILE: &str = "significance.json";
+
+/// Noise thresholds per metric, given with `--compare-threshold`, eg. `time=3%,throughput=5%`.
+/// They replace the configured noise threshold when comparing against the baseline.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct CompareThresholds {
+    /// Largest change in time which is considered noise.
+    pub time: Option<f64>,
+    /// Largest change in throughput which is considered noise, for benchmarks with a throughput.
+    pub throughput: Option<f64>,
+}
+impl CompareThresholds {
+    /// Parses a comma-separated list of `METRIC=THRESHOLD`, where the threshold is either a
+    /// percentage (`3%`) or a fraction (`0.03`).
+    pub fn parse(spec: &str) -> std::result::Result<CompareThresholds, String> {
+        let mut thresholds = CompareThresholds::default();
+        for part in spec.split(',') {
+            let (metric, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected METRIC=THRESHOLD, found {:?}", part))?;
+            let value = value.trim();
+            let threshold = match value.strip_suffix('%') {
+                Some(percent) => percent.trim().parse::<f64>().map(|p| p / 100.0),
+                None => value.parse::<f64>(),
+            }
+            .map_err(|_| format!("invalid threshold {:?} for {}", value, metric.trim()))?;
+            if !(0.0..1.0).contains(&threshold) {
+                return Err(format!(
+                    "the threshold for {} must be at least 0% and less than 100%",
+                    metric.trim()
+                ));
+            }
+            match metric.trim() {
+                "time" => thresholds.time = Some(threshold),
+                "throughput" => thresholds.throughput = Some(threshold),
+                other => {
+                    return Err(format!(
+                        "unknown metric {:?}; the metrics measured are time and throughput",
+                        other
+                    ))
+                }
+            }
+        }
+        Ok(thresholds)
+    }
+
+    /// Returns the noise threshold for the relative change in time of a benchmark, or `default`
+    /// if no threshold applies to it. A drop in throughput by `t` is an increase in time by
+    /// `t / (1 - t)`; if both metrics have a threshold, the stricter one is used so that exceeding
+    /// either budget counts as a regression.
+    pub fn noise_threshold(&self, default: f64, has_throughput: bool) -> f64 {
+        let throughput = self
+            .throughput
+            .filter(|_| has_throughput)
+            .map(|t| t / (1.0 - t));
+        match (self.time, throughput) {
+            (Some(time), Some(throughput)) => time.min(throughput),
+            (Some(threshold), None) | (None, Some(threshold)) => threshold,
+            (None, None) => default,
+        }
+    }
+}
+
+/// The sample and estimates of the baseline a benchmark is compared to.
+pub(crate) struct Baseline {
+    pub iters: Vec<f64>,
+    pub times: Vec<f64>,
+    pub avg_times: Vec<f64>,
+    pub estimates: Estimates,
+    /// The outlier policy recorded in the baseline's manifest, if it has one.
+    pub outlier_policy: Option<String>,
+}
+
+/// The change of a benchmark compared to its baseline.
+pub(crate) struct Change {
+    /// The probability of a change at least this large if there was no change of performance.
+    pub p_value: f64,
+    /// The test statistic: the t score for the mean, the relative change for the median, the z
+    /// score of the U statistic for the Mann-Whitney test.
+    pub t_value: f64,
+    /// The distribution of the test statistic if both samples come from the same population.
+    pub t_distribution: Distribution<f64>,
+    pub relative_estimates: ChangeEstimates,
+    pub relative_distributions: ChangeDistributions,
+}
+
+/// Loads the baseline of the benchmark.
+pub(crate) fn load<M: Measurement>(id: &BenchmarkId, criterion: &Criterion<M>) -> Result<Baseline> {
+    load_from(&criterion.baseline_dir(id))
+}
+
+/// Loads the baseline saved in `base_dir`.
+pub(crate) fn load_from(base_dir: &Path) -> Result<Baseline> {
+    let sample: SavedSample = fs::load(&base_dir.join("sample.json"))?;
     let SavedSample { iters, times, .. } = sample;
 
-    let mut estimates_file = criterion.output_directory.clone();
-    estimates_file.push(id.as_directory_name());
-    estimates_file.push(&criterion.baseline_directory);
-    estimates_file.push("estimates.json");
-    let base_estimates: Estimates = fs::load(&estimates_file)?;
+    let estimates: Estimates = fs::load(&base_dir.join("estimates.json"))?;
+    let outlier_policy = fs::load::<Manifest, _>(&base_dir.join("manifest.json"))
+        .ok()
+        .map(|manifest| manifest.config.outlier_policy);
 
-    let base_avg_times: Vec<f64> = iters
+    let avg_times: Vec<f64> = iters
         .iter()
         .zip(times.iter())
         .map(|(iters, elapsed)| elapsed / iters)
         .collect();
-    let base_avg_time_sample = Sample::new(&base_avg_times);
+    Ok(Baseline {
+        iters,
+        times,
+        avg_times,
+        estimates,
+        outlier_policy,
+    })
+}
+
+// Common comparison procedure. `statistic` is the mean or the median, whichever is compared.
+pub(crate) fn compute(
+    avg_times: &Sample<f64>,
+    base_avg_time_sample: &Sample<f64>,
+    nresamples: usize,
+    cl: f64,
+    statistic: Statistic,
+    test: ComparisonTest,
+) -> Change {
+    // Two constant samples, eg. of a deterministic measurement such as a count of allocations, have
+    // no bootstrap distribution of the t score, but Welch's t-test decides them with certainty.
+    let constant = |sample: &Sample<f64>| sample.var(None) == 0.0;
+    if test == ComparisonTest::Welch || (constant(avg_times) && constant(base_avg_time_sample)) {
+        return welch_test(avg_times, base_avg_time_sample, cl);
+    }
+    let (t_value, t_distribution) = match (test, statistic) {
+        (ComparisonTest::MannWhitney, _) => {
+            mann_whitney_test(avg_times, base_avg_time_sample, nresamples)
+        }
+        (ComparisonTest::Bootstrap, Statistic::Median) => {
+            median_test(avg_times, base_avg_time_sample, nresamples)
+        }
+        (_, _) => t_test(avg_times, base_avg_time_sample, nresamples),
+    };
+    let p_value = match test {
+        ComparisonTest::MannWhitney => 2.0 * normal_tail(t_value.abs()),
+        _ => t_distribution.p_value(t_value, &Tails::Two),
+    };
+    let (relative_estimates, relative_distributions) =
+        estimates(avg_times, base_avg_time_sample, nresamples, cl);
+    Change {
+        p_value,
+        t_value,
+        t_distribution,
+        relative_estimates,
+        relative_distributions,
+    }
+}
 
+/// Saves the estimates of the change, and whether it is significant, in the `change` directory of
+/// the benchmark.
+pub(crate) fn save<M: Measurement>(
+    id: &BenchmarkId,
+    criterion: &Criterion<M>,
+    comparison: &ComparisonData,
+) {
     let mut change_dir = criterion.output_directory.clone();
     change_dir.push(id.as_directory_name());
     change_dir.push("change");
-    fs::mkdirp(&change_dir)?;
-    let (t_statistic, t_distribution) = t_test(avg_times, base_avg_time_sample, config);
+    log_if_err!(fs::mkdirp(&change_dir));
+    log_if_err!(fs::save(
+        &comparison.relative_estimates,
+        &change_dir.join("estimates.json")
+    ));
+    log_if_err!(fs::save(
+        &comparison.significant_change(),
+        &change_dir.join(SIGNIFICANCE_FILE)
+    ));
+}
 
-    let (estimates, relative_distributions) =
-        estimates(id, avg_times, base_avg_time_sample, config, criterion);
-    Ok((
-        t_statistic,
-        t_distribution,
-        estimates,
-        relative_distributions,
-        iters,
-        times,
-        base_avg_times.clone(),
-        base_estimates,
-    ))
+/// Removes the significance of the last change of the benchmark, which does not describe a run
+/// that had no baseline to compare against.
+pub(crate) fn forget_significance<M: Measurement>(id: &BenchmarkId, criterion: &Criterion<M>) {
+    let path = criterion
+        .output_directory
+        .join(id.as_directory_name())
+        .join("change")
+        .join(SIGNIFICANCE_FILE);
+    if path.exists() {
+        log_if_err!(fs::remove_file(&path));
+    }
 }
 
 // Performs a two sample t-test
 fn t_test(
     avg_times: &Sample<f64>,
     base_avg_times: &Sample<f64>,
-    config: &BenchmarkConfig,
+    nresamples: usize,
 ) -> (f64, Distribution<f64>) {
-    let nresamples = config.nresamples;
-
     let t_statistic = avg_times.t(base_avg_times);
     let t_distribution = elapsed!(
         "Bootstrapping the T distribution",
@@ -97,13 +231,267 @@ fn t_test(
     (t_statistic, t_distribution)
 }
 
+// Bootstraps the relative change of the median when both samples are drawn from their union, which
+// is its distribution if the populations have the same median.
+fn median_test(
+    avg_times: &Sample<f64>,
+    base_avg_times: &Sample<f64>,
+    nresamples: usize,
+) -> (f64, Distribution<f64>) {
+    fn change(a: &Sample<f64>, b: &Sample<f64>) -> f64 {
+        a.percentiles().median() / b.percentiles().median() - 1.
+    }
+
+    let distribution = elapsed!(
+        "Bootstrapping the distribution of the change of the median",
+        mixed::bootstrap(
+            avg_times,
+            base_avg_times,
+            nresamples,
+            |a, b| (change(a, b),)
+        )
+    )
+    .0;
+    let distribution = Distribution::from(
+        distribution
+            .iter()
+            .filter(|a| a.is_finite())
+            .cloned()
+            .collect::<Vec<_>>()
+            .into_boxed_slice(),
+    );
+
+    (change(avg_times, base_avg_times), distribution)
+}
+
+// Computes the z score of the Mann-Whitney U statistic, and bootstraps its distribution when both
+// samples are drawn from their union for the plot of the test.
+fn mann_whitney_test(
+    avg_times: &Sample<f64>,
+    base_avg_times: &Sample<f64>,
+    nresamples: usize,
+) -> (f64, Distribution<f64>) {
+    let distribution = elapsed!(
+        "Bootstrapping the distribution of the Mann-Whitney z score",
+        mixed::bootstrap(avg_times, base_avg_times, nresamples, |a, b| {
+            (a.mann_whitney_z(b),)
+        })
+    )
+    .0;
+
+    (avg_times.mann_whitney_z(base_avg_times), distribution)
+}
+
+// The probability that a standard normal variable is larger than `z`, for `z >= 0`, with
+// approximation 7.1.26 of Abramowitz and Stegun for erfc, which is accurate to 1.5e-7.
+fn normal_tail(z: f64) -> f64 {
+    let x = z / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    poly * (-x * x).exp() / 2.0
+}
+
+/// The number of quantiles of the t distribution which stand in for the bootstrap distributions
+/// with Welch's t-test.
+const QUANTILES: usize = 1000;
+
+// Performs Welch's t-test without any bootstrap. The p-value is that of the Student t distribution
+// with the Welch-Satterthwaite degrees of freedom, and the relative changes of the mean and the
+// median have the standard errors of the delta method, from those of the means and, assuming the
+// times are about normal, of the medians. Their distributions, and that of the t score, are evenly
+// spaced quantiles of that t distribution, so that the plots and the confidence intervals are
+// built from them as usual.
+fn welch_test(avg_times: &Sample<f64>, base_avg_times: &Sample<f64>, cl: f64) -> Change {
+    fn moments(sample: &Sample<f64>) -> (f64, f64, f64, f64) {
+        let n = sample.len() as f64;
+        let mean = sample.mean();
+        let median = sample.percentiles().median();
+        let mean_var = sample.var(Some(mean)) / n;
+        // The asymptotic variance of the median of a normal sample is pi / 2 times that of its
+        // mean.
+        let median_var =
+            std::f64::consts::FRAC_PI_2 * sample.median_abs_dev(Some(median)).powi(2) / n;
+        (mean, mean_var, median, median_var)
+    }
+
+    let (mean, mean_var, median, median_var) = moments(avg_times);
+    let (base_mean, base_mean_var, base_median, base_median_var) = moments(base_avg_times);
+
+    let variance = mean_var + base_mean_var;
+    let t_value = if variance > 0.0 {
+        (mean - base_mean) / variance.sqrt()
+    } else {
+        // Two constant samples: any difference is certain.
+        match mean.partial_cmp(&base_mean) {
+            Some(std::cmp::Ordering::Greater) => f64::INFINITY,
+            Some(std::cmp::Ordering::Less) => f64::NEG_INFINITY,
+            _ => 0.0,
+        }
+    };
+    let (n, base_n) = (avg_times.len() as f64, base_avg_times.len() as f64);
+    let df = if variance > 0.0 {
+        variance * variance
+            / (mean_var * mean_var / (n - 1.0) + base_mean_var * base_mean_var / (base_n - 1.0))
+    } else {
+        n + base_n - 2.0
+    };
+    let p_value = (2.0 * student_tail(t_value.abs(), df)).min(1.0);
+
+    let quantiles = student_quantiles(df);
+    let scaled = |point: f64, se: f64| {
+        Distribution::from(
+            quantiles
+                .iter()
+                .map(|q| point + se * q)
+                .collect::<Vec<_>>()
+                .into_boxed_slice(),
+        )
+    };
+    // The delta method: the relative variance of a ratio is the sum of those of its terms.
+    let mean_ratio = mean / base_mean;
+    let median_ratio = median / base_median;
+    let mean_se = mean_ratio * (mean_var / mean.powi(2) + base_mean_var / base_mean.powi(2)).sqrt();
+    let median_se =
+        median_ratio * (median_var / median.powi(2) + base_median_var / base_median.powi(2)).sqrt();
+    let relative_distributions = ChangeDistributions {
+        mean: scaled(mean_ratio - 1., mean_se),
+        median: scaled(median_ratio - 1., median_se),
+    };
+    let points = ChangePointEstimates {
+        mean: mean_ratio - 1.,
+        median: median_ratio - 1.,
+    };
+    let relative_estimates = build_change_estimates(&relative_distributions, &points, cl);
+
+    Change {
+        p_value,
+        t_value,
+        t_distribution: scaled(0.0, 1.0),
+        relative_estimates,
+        relative_distributions,
+    }
+}
+
+// The natural logarithm of the gamma function of `x > 0`, with the Lanczos approximation, which is
+// accurate to 2e-10.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        1.208_650_973_866_179e-3,
+        -5.395_239_384_953e-6,
+    ];
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let series = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .fold(1.000_000_000_190_015, |series, (i, c)| {
+            series + c / (x + 1.0 + i as f64)
+        });
+    -tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+// The regularized incomplete beta function `I_x(a, b)`, from its continued fraction evaluated with
+// the modified Lentz method.
+fn incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    fn continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+        const TINY: f64 = 1e-300;
+        let mut c = 1.0;
+        let mut d = 1.0 - (a + b) * x / (a + 1.0);
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        d = 1.0 / d;
+        let mut fraction = d;
+        for m in 1..300 {
+            let m = m as f64;
+            // The even and odd steps of the fraction.
+            for &numerator in &[
+                m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+                -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+            ] {
+                d = 1.0 + numerator * d;
+                if d.abs() < TINY {
+                    d = TINY;
+                }
+                c = 1.0 + numerator / c;
+                if c.abs() < TINY {
+                    c = TINY;
+                }
+                d = 1.0 / d;
+                fraction *= d * c;
+            }
+            if (d * c - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        fraction
+    }
+
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * continued_fraction(x, a, b) / a
+    } else {
+        1.0 - front * continued_fraction(1.0 - x, b, a) / b
+    }
+}
+
+// The probability that a variable of the Student t distribution with `df` degrees of freedom is
+// larger than `t`, for `t >= 0`.
+fn student_tail(t: f64, df: f64) -> f64 {
+    if t.is_infinite() {
+        return 0.0;
+    }
+    incomplete_beta(df / (df + t * t), df / 2.0, 0.5) / 2.0
+}
+
+// The quantiles of the Student t distribution with `df` degrees of freedom at the middles of
+// `QUANTILES` equal slices of probability, in increasing order, found by bisection.
+fn student_quantiles(df: f64) -> Vec<f64> {
+    let upper: Vec<f64> = (0..QUANTILES / 2)
+        .map(|i| {
+            let tail = (i as f64 + 0.5) / QUANTILES as f64;
+            let mut high = 1.0;
+            while student_tail(high, df) > tail {
+                high *= 2.0;
+            }
+            let mut low = 0.0;
+            for _ in 0..60 {
+                let middle = (low + high) / 2.0;
+                if student_tail(middle, df) > tail {
+                    low = middle;
+                } else {
+                    high = middle;
+                }
+            }
+            (low + high) / 2.0
+        })
+        .collect();
+    upper
+        .iter()
+        .map(|q| -q)
+        .chain(upper.iter().rev().cloned())
+        .collect()
+}
+
 // Estimates the relative change in the statistics of the population
-fn estimates<M: Measurement>(
-    id: &BenchmarkId,
+fn estimates(
     avg_times: &Sample<f64>,
     base_avg_times: &Sample<f64>,
-    config: &BenchmarkConfig,
-    criterion: &Criterion<M>,
+    nresamples: usize,
+    cl: f64,
 ) -> (ChangeEstimates, ChangeDistributions) {
     fn stats(a: &Sample<f64>, b: &Sample<f64>) -> (f64, f64) {
         (
@@ -112,9 +500,6 @@ fn estimates<M: Measurement>(
         )
     }
 
-    let cl = config.confidence_level;
-    let nresamples = config.nresamples;
-
     let (dist_mean, dist_median) = elapsed!(
         "Bootstrapping the relative statistics",
         univariate::bootstrap(avg_times, base_avg_times, nresamples, stats)
@@ -130,14 +515,203 @@ fn estimates<M: Measurement>(
 
     let estimates = build_change_estimates(&distributions, &points, cl);
 
-    {
-        log_if_err!({
-            let mut estimates_path = criterion.output_directory.clone();
-            estimates_path.push(id.as_directory_name());
-            estimates_path.push("change");
-            estimates_path.push("estimates.json");
-            fs::save(&estimates, &estimates_path)
-        });
-    }
     (estimates, distributions)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::estimate::{ConfidenceInterval, Estimate};
+
+    #[test]
+    fn compare_thresholds() {
+        let thresholds = CompareThresholds::parse("time=3%, throughput=0.2").unwrap();
+        assert_eq!(thresholds.time, Some(0.03));
+        assert_eq!(thresholds.throughput, Some(0.2));
+        assert_eq!(thresholds.noise_threshold(0.01, false), 0.03);
+        assert_eq!(thresholds.noise_threshold(0.01, true), 0.03);
+
+        let thresholds = CompareThresholds::parse("throughput=20%").unwrap();
+        assert!((thresholds.noise_threshold(0.01, true) - 0.25).abs() < 1e-12);
+        assert_eq!(thresholds.noise_threshold(0.01, false), 0.01);
+
+        assert!(CompareThresholds::parse("allocs=0").is_err());
+        assert!(CompareThresholds::parse("time").is_err());
+        assert!(CompareThresholds::parse("time=fast").is_err());
+        assert!(CompareThresholds::parse("throughput=100%").is_err());
+    }
+
+    fn estimates() -> Estimates {
+        let estimate = Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: 0.95,
+                lower_bound: 100.0,
+                upper_bound: 106.0,
+            },
+            point_estimate: 103.0,
+            standard_error: 1.0,
+        };
+        Estimates {
+            mean: estimate.clone(),
+            median: estimate.clone(),
+            median_abs_dev: estimate.clone(),
+            slope: None,
+            std_dev: estimate,
+            minimum: None,
+            percentiles: vec![],
+        }
+    }
+
+    #[test]
+    fn median_comparison_ignores_a_heavy_tail() {
+        let base: Vec<f64> = (0..50).map(|i| 100.0 + (i % 7) as f64).collect();
+        // The same times, but one sample in ten is fifty times slower.
+        let new: Vec<f64> = base
+            .iter()
+            .enumerate()
+            .map(|(i, &t)| if i % 10 == 0 { t * 50.0 } else { t })
+            .collect();
+        let baseline = Baseline {
+            iters: vec![1.0; base.len()],
+            times: base.clone(),
+            avg_times: base,
+            estimates: estimates(),
+            outlier_policy: None,
+        };
+
+        let test = ComparisonTest::Bootstrap;
+        let mean = compute(
+            Sample::new(&new),
+            Sample::new(&baseline.avg_times),
+            1000,
+            0.95,
+            Statistic::Mean,
+            test,
+        );
+        assert!(mean.relative_estimates.mean.point_estimate > 1.0);
+
+        let median = compute(
+            Sample::new(&new),
+            Sample::new(&baseline.avg_times),
+            1000,
+            0.95,
+            Statistic::Median,
+            test,
+        );
+        assert!(median.relative_estimates.median.point_estimate.abs() < 0.05);
+        assert_eq!(
+            median.t_value,
+            median.relative_estimates.median.point_estimate
+        );
+        assert!(median.p_value > 0.05);
+    }
+
+    #[test]
+    fn mann_whitney_test() {
+        assert!((normal_tail(0.0) - 0.5).abs() < 1e-6);
+        assert!((2.0 * normal_tail(1.959_964) - 0.05).abs() < 1e-6);
+
+        let base: Vec<f64> = (0..40).map(|i| 100.0 + (i % 8) as f64).collect();
+        let baseline = Baseline {
+            iters: vec![1.0; base.len()],
+            times: base.clone(),
+            avg_times: base.clone(),
+            estimates: estimates(),
+            outlier_policy: None,
+        };
+        let compute = |times: &[f64]| {
+            compute(
+                Sample::new(times),
+                Sample::new(&baseline.avg_times),
+                1000,
+                0.95,
+                Statistic::Mean,
+                ComparisonTest::MannWhitney,
+            )
+        };
+
+        // Each value shifted up by 4: slower, with many ties.
+        let slower: Vec<f64> = base.iter().map(|t| t + 4.0).collect();
+        let change = compute(&slower);
+        assert!(change.t_value > 0.0);
+        assert!(change.p_value < 0.001);
+
+        // The same ranks but one huge outlier: no significant change.
+        let mut outlier = base.clone();
+        outlier[0] = 1e6;
+        let change = compute(&outlier);
+        assert!(change.p_value > 0.5);
+        assert_eq!(change.t_distribution.len(), 1000);
+    }
+
+    #[test]
+    fn welch_test() {
+        // Critical values of the t distribution: 12.706 for 1 and 2.228 for 10 degrees of freedom.
+        assert!((2.0 * student_tail(12.706_2, 1.0) - 0.05).abs() < 1e-5);
+        assert!((2.0 * student_tail(2.228_14, 10.0) - 0.05).abs() < 1e-5);
+        assert!((student_tail(0.0, 5.0) - 0.5).abs() < 1e-12);
+        let quantiles = student_quantiles(10.0);
+        assert_eq!(quantiles.len(), QUANTILES);
+        assert!(quantiles.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!((quantiles[QUANTILES - 1] + quantiles[0]).abs() < 1e-12);
+
+        let base: Vec<f64> = (0..40).map(|i| 100.0 + (i % 8) as f64).collect();
+        let compute = |times: &[f64]| {
+            compute(
+                Sample::new(times),
+                Sample::new(&base),
+                1000,
+                0.95,
+                Statistic::Mean,
+                ComparisonTest::Welch,
+            )
+        };
+
+        let slower: Vec<f64> = base.iter().map(|t| t * 1.05).collect();
+        let change = compute(&slower);
+        assert!(change.t_value > 0.0);
+        assert!(change.p_value < 0.001);
+        let mean = &change.relative_estimates.mean;
+        assert!((mean.point_estimate - 0.05).abs() < 1e-12);
+        // About as wide as the bootstrap's.
+        let bootstrap = super::compute(
+            Sample::new(&slower),
+            Sample::new(&base),
+            10000,
+            0.95,
+            Statistic::Mean,
+            ComparisonTest::Bootstrap,
+        );
+        let width =
+            |e: &Estimate| e.confidence_interval.upper_bound - e.confidence_interval.lower_bound;
+        assert!((width(mean) / width(&bootstrap.relative_estimates.mean) - 1.0).abs() < 0.2);
+
+        // The same times in another order: no change at all.
+        let mut shuffled = base.clone();
+        shuffled.reverse();
+        let change = compute(&shuffled);
+        assert_eq!(change.t_value, 0.0);
+        assert!((change.p_value - 1.0).abs() < 1e-12);
+        assert_eq!(change.t_distribution.len(), QUANTILES);
+    }
+
+    #[test]
+    fn constant_samples() {
+        // Constant samples, which have no bootstrap distribution, are decided with certainty.
+        let constant = |value: f64| vec![value; 20];
+        let bootstrap = |times: &[f64]| {
+            compute(
+                Sample::new(times),
+                Sample::new(&constant(1024.0)),
+                1000,
+                0.95,
+                Statistic::Mean,
+                ComparisonTest::Bootstrap,
+            )
+        };
+        assert!((bootstrap(&constant(1024.0)).p_value - 1.0).abs() < 1e-12);
+        let change = bootstrap(&constant(2048.0));
+        assert_eq!(change.p_value, 0.0);
+        assert_eq!(change.relative_estimates.mean.point_estimate, 1.0);
+    }
+}
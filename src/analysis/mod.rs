@@ -1,25 +1,44 @@
 use std::path::Path;
+use std::sync::Arc;
 
-use crate::stats::bivariate::regression::Slope;
 use crate::stats::bivariate::Data;
+use crate::stats::rand_util;
 use crate::stats::univariate::outliers::tukey;
 use crate::stats::univariate::Sample;
-use crate::stats::{Distribution, Tails};
+use crate::stats::Distribution;
 
 use crate::benchmark::BenchmarkConfig;
 use crate::connection::OutgoingMessage;
+use crate::correction;
+use crate::drift::Drift;
 use crate::estimate::{
-    build_estimates, ConfidenceInterval, Distributions, Estimate, Estimates, PointEstimates,
+    build_estimates, ConfidenceInterval, Distributions, Estimate, Estimates, PercentileEstimate,
+    PointEstimates,
 };
 use crate::fs;
+use crate::heap::HeapProfile;
+use crate::history;
+use crate::manifest::Manifest;
 use crate::measurement::Measurement;
-use crate::report::{BenchmarkId, Report, ReportContext};
+use crate::metadata;
+use crate::missing_baseline;
+use crate::report::{BenchmarkId, ComparisonData, Report, ReportContext};
+use crate::resume;
+use crate::retention;
 use crate::routine::Routine;
-use crate::{Baseline, Criterion, SavedSample, Throughput};
+use crate::spill::Values;
+use crate::stop;
+use crate::streaming::{self, Moments};
+use crate::{
+    ActualSamplingMode, Baseline, Criterion, MissingBaselinePolicy, RegressionMethod, SavedSample,
+    Throughput,
+};
+
+use self::background::Job;
 
 macro_rules! elapsed {
     ($msg:expr, $block:expr) => {{
-        let start = ::std::time::Instant::now();
+        let start = crate::clock::Instant::now();
         let out = $block;
         let elapsed = &start.elapsed();
 
@@ -33,9 +52,46 @@ macro_rules! elapsed {
     }};
 }
 
+mod background;
 mod compare;
+mod offline;
+
+pub(crate) use self::background::measuring;
+#[cfg(feature = "rayon")]
+pub(crate) use self::background::pause_while_measuring;
+pub(crate) use self::compare::{CompareThresholds, SIGNIFICANCE_FILE};
+pub(crate) use self::offline::compare_baselines;
+
+/// A benchmark which has been measured, with its analysis running inline or in the background.
+/// [`Pending::finish`] saves and reports the results once the analysis is done.
+pub(crate) struct Pending {
+    id: BenchmarkId,
+    config: BenchmarkConfig,
+    report_context: ReportContext,
+    throughput: Option<Throughput>,
+    sampling_mode: ActualSamplingMode,
+    // Shared with the analysis job, so that spilled samples are not copied into memory.
+    iters: Arc<Values>,
+    times: Arc<Values>,
+    heap: Option<HeapProfile>,
+    manifest: Manifest,
+    create_baseline: bool,
+    /// Marks the benchmark as completed for `--resume`.
+    config_hash: String,
+    job: Job<Computed>,
+}
 
-// Common analysis procedure
+/// The statistics computed from the samples, which don't need the `Criterion` struct.
+struct Computed {
+    distributions: Distributions,
+    estimates: Estimates,
+    comparison: Option<(compare::Baseline, compare::Change)>,
+    drift: Option<Drift>,
+}
+
+// Common analysis procedure. Measures the benchmark and starts the analysis of the results, in the
+// background if enabled. Returns `None` if there is nothing to analyze.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn common<M: Measurement, T: ?Sized>(
     id: &BenchmarkId,
     routine: &mut dyn Routine<M, T>,
@@ -44,23 +100,38 @@ pub(crate) fn common<M: Measurement, T: ?Sized>(
     report_context: &ReportContext,
     parameter: &T,
     throughput: Option<Throughput>,
-) {
+    config_hash: String,
+) -> Option<Pending> {
     criterion.report.benchmark_start(id, report_context);
 
-    if let Baseline::CompareStrict = criterion.baseline {
-        if !base_dir_exists(
-            id,
-            &criterion.baseline_directory,
-            &criterion.output_directory,
-        ) {
-            panic!(
-                "Baseline '{base}' must exist before comparison is allowed; try --save-baseline {base}",
-                base=criterion.baseline_directory,
-            );
+    let mut create_baseline = false;
+    if let Some(policy) = criterion.missing_baseline_policy() {
+        if !criterion.baseline_dir(id).exists() {
+            missing_baseline::record(&criterion.output_directory, id.as_title(), policy);
+            match policy {
+                MissingBaselinePolicy::Error => {
+                    eprintln!(
+                        "Error: Baseline '{}' does not exist for {}; skipping it",
+                        criterion.baseline_name(),
+                        id.as_title()
+                    );
+                    return None;
+                }
+                MissingBaselinePolicy::WarnAndRun => {
+                    eprintln!(
+                        "Warning: Baseline '{}' does not exist for {}; running it without a comparison",
+                        criterion.baseline_name(),
+                        id.as_title()
+                    );
+                }
+                MissingBaselinePolicy::AutoCreate => create_baseline = true,
+            }
         }
     }
 
     let (sampling_mode, iters, times);
+    let mut moments = None;
+    let mut heap = None;
     if let Some(baseline) = &criterion.load_baseline {
         let mut sample_path = criterion.output_directory.clone();
         sample_path.push(id.as_directory_name());
@@ -75,11 +146,17 @@ pub(crate) fn common<M: Measurement, T: ?Sized>(
             ),
             Ok(samples) => {
                 sampling_mode = samples.sampling_mode;
-                iters = samples.iters.into_boxed_slice();
-                times = samples.times.into_boxed_slice();
+                iters = Values::from(samples.iters);
+                times = Values::from(samples.times);
             }
         }
     } else {
+        if let Some(iterations) = criterion.heap_profile {
+            criterion
+                .report
+                .heap_profile(id, report_context, iterations);
+            heap = crate::heap::profile(routine, &criterion.measurement, parameter, iterations);
+        }
         let sample = routine.sample(
             &criterion.measurement,
             id,
@@ -91,6 +168,7 @@ pub(crate) fn common<M: Measurement, T: ?Sized>(
         sampling_mode = sample.0;
         iters = sample.1;
         times = sample.2;
+        moments = sample.3;
 
         if let Some(conn) = &criterion.connection {
             conn.send(&OutgoingMessage::MeasurementComplete {
@@ -100,12 +178,13 @@ pub(crate) fn common<M: Measurement, T: ?Sized>(
                 plot_config: (&report_context.plot_config).into(),
                 sampling_method: sampling_mode.into(),
                 benchmark_config: config.into(),
+                labels: &metadata::labels(&criterion.labels),
             })
             .unwrap();
 
             conn.serve_value_formatter(criterion.measurement.formatter())
                 .unwrap();
-            return;
+            return None;
         }
     }
 
@@ -118,168 +197,390 @@ pub(crate) fn common<M: Measurement, T: ?Sized>(
             that your routine is correctly measured.",
             id.as_title()
         );
-        return;
+        return None;
     }
 
-    let avg_times = iters
-        .iter()
-        .zip(times.iter())
-        .map(|(&iters, &elapsed)| elapsed / iters)
-        .collect::<Vec<f64>>();
-    let avg_times = Sample::new(&avg_times);
+    let mut manifest = Manifest::new(std::any::type_name::<M>(), config, sampling_mode);
+    manifest.labels = metadata::labels(&criterion.labels);
+    manifest.streamed_samples = moments.as_ref().map(|moments| moments.count);
 
-    if criterion.should_save_baseline() {
-        log_if_err!({
-            let mut new_dir = criterion.output_directory.clone();
-            new_dir.push(id.as_directory_name());
-            new_dir.push("new");
-            fs::mkdirp(&new_dir)
-        });
+    // The baseline is read now, in case the next benchmark replaces it.
+    let baseline = if criterion.baseline_dir(id).exists() {
+        compare::load(id, criterion)
+            .map_err(|e| crate::error::log_error(&e))
+            .ok()
+    } else {
+        None
+    };
+    let policy = format!("{:?}", config.outlier_policy);
+    match baseline.as_ref().and_then(|b| b.outlier_policy.as_ref()) {
+        Some(saved) if *saved != policy => eprintln!(
+            "Note: The baseline of {} was saved with the {} outlier policy; both samples are compared with the {} policy.",
+            id.as_title(),
+            saved,
+            policy
+        ),
+        _ => {}
     }
 
-    let data = Data::new(&iters, &times);
-    let labeled_sample = tukey::classify(avg_times);
-    if criterion.should_save_baseline() {
-        log_if_err!({
-            let mut tukey_file = criterion.output_directory.to_owned();
-            tukey_file.push(id.as_directory_name());
-            tukey_file.push("new");
-            tukey_file.push("tukey.json");
-            fs::save(&labeled_sample.fences(), &tukey_file)
-        });
-    }
-    let (mut distributions, mut estimates) = estimates(avg_times, config);
-    if sampling_mode.is_linear() {
-        let (distribution, slope) = regression(&data, config);
+    let (iters, times) = (Arc::new(iters), Arc::new(times));
+    let job = {
+        let (iters, times) = (Arc::clone(&iters), Arc::clone(&times));
+        let (config, linear) = (config.clone(), sampling_mode.is_linear());
+        let compute = move || compute(&iters, &times, linear, &config, baseline, moments.as_ref());
+        if criterion.background_analysis {
+            Job::spawn(compute)
+        } else {
+            Job::inline(compute)
+        }
+    };
+
+    Some(Pending {
+        id: id.clone(),
+        config: config.clone(),
+        report_context: report_context.clone(),
+        throughput,
+        sampling_mode,
+        iters,
+        times,
+        heap,
+        manifest,
+        create_baseline,
+        config_hash,
+        job,
+    })
+}
+
+/// Bootstraps the statistics of the samples and of their change compared to the baseline. The slope
+/// is only fitted if the samples were taken with linear sampling. With streaming statistics, the
+/// samples are the reservoir and the statistics are extrapolated to all the `moments`.
+fn compute(
+    iters: &[f64],
+    times: &[f64],
+    linear: bool,
+    config: &BenchmarkConfig,
+    baseline: Option<compare::Baseline>,
+    moments: Option<&Moments>,
+) -> Computed {
+    enter_span!("analysis");
+    let _seed = rand_util::seed_bootstraps(config.bootstrap_seed);
+    let (nresamples, confidence_level) = (config.nresamples, config.confidence_level);
+
+    // The order of the samples is diagnosed before the outlier policy leaves any of them out.
+    let drift = Drift::new(&average_times(iters, times));
+    let (iters, times) = config.outlier_policy.apply(iters, times);
+    let avg_times = average_times(&iters, &times);
+    let avg_times = Sample::new(&avg_times);
+
+    let (mut distributions, mut estimates) = estimates(avg_times, nresamples, confidence_level);
+    if linear {
+        let data = Data::new(&iters, &times);
+        let (distribution, slope) = fit_slope(
+            &data,
+            config.regression_method,
+            nresamples,
+            confidence_level,
+        );
 
         estimates.slope = Some(slope);
         distributions.slope = Some(distribution);
     }
+    if let Some(moments) = moments {
+        streaming::extrapolate(
+            &mut estimates,
+            &mut distributions,
+            moments,
+            avg_times.len(),
+            config.regression_method,
+            confidence_level,
+        );
+    }
+    if config.minimum_time {
+        let (distribution, minimum) = minimum_estimate(avg_times, nresamples, confidence_level);
 
-    if criterion.should_save_baseline() {
-        log_if_err!({
-            let mut sample_file = criterion.output_directory.clone();
-            sample_file.push(id.as_directory_name());
-            sample_file.push("new");
-            sample_file.push("sample.json");
-            fs::save(
-                &SavedSample {
-                    sampling_mode,
-                    iters: data.x().as_ref().to_vec(),
-                    times: data.y().as_ref().to_vec(),
-                },
-                &sample_file,
-            )
-        });
-        log_if_err!({
-            let mut estimates_file = criterion.output_directory.clone();
-            estimates_file.push(id.as_directory_name());
-            estimates_file.push("new");
-            estimates_file.push("estimates.json");
-            fs::save(&estimates, &estimates_file)
-        });
+        estimates.minimum = Some(minimum);
+        distributions.minimum = Some(distribution);
     }
+    estimates.percentiles = config
+        .percentiles
+        .iter()
+        .map(|&percentile| percentile_estimate(avg_times, percentile, nresamples, confidence_level))
+        .collect();
+
+    let comparison = baseline.map(|baseline| {
+        // The baseline's sample is treated with the same policy, whatever it was saved with.
+        let (base_iters, base_times) = config
+            .outlier_policy
+            .apply(&baseline.iters, &baseline.times);
+        let base_avg_times = average_times(&base_iters, &base_times);
+        let change = compare::compute(
+            avg_times,
+            Sample::new(&base_avg_times),
+            nresamples,
+            confidence_level,
+            config.comparison_estimator.statistic(),
+            config.comparison_test,
+        );
+        (baseline, change)
+    });
 
-    let compare_data = if base_dir_exists(
-        id,
-        &criterion.baseline_directory,
-        &criterion.output_directory,
-    ) {
-        let result = compare::common(id, avg_times, config, criterion);
-        match result {
-            Ok((
-                t_value,
-                t_distribution,
-                relative_estimates,
-                relative_distributions,
-                base_iter_counts,
-                base_sample_times,
-                base_avg_times,
-                base_estimates,
-            )) => {
-                let p_value = t_distribution.p_value(t_value, &Tails::Two);
-                Some(crate::report::ComparisonData {
-                    p_value,
-                    t_distribution,
-                    t_value,
-                    relative_estimates,
-                    relative_distributions,
-                    significance_threshold: config.significance_level,
-                    noise_threshold: config.noise_threshold,
-                    base_iter_counts,
-                    base_sample_times,
-                    base_avg_times,
-                    base_estimates,
-                })
-            }
-            Err(e) => {
-                crate::error::log_error(&e);
-                None
-            }
+    Computed {
+        distributions,
+        estimates,
+        comparison,
+        drift,
+    }
+}
+
+fn average_times(iters: &[f64], times: &[f64]) -> Vec<f64> {
+    iters
+        .iter()
+        .zip(times.iter())
+        .map(|(&iters, &elapsed)| elapsed / iters)
+        .collect()
+}
+
+/// Combines the change compared to the baseline with the thresholds which decide whether it is
+/// significant.
+fn comparison_data<M: Measurement>(
+    baseline: compare::Baseline,
+    change: compare::Change,
+    config: &BenchmarkConfig,
+    criterion: &Criterion<M>,
+    has_throughput: bool,
+) -> ComparisonData {
+    ComparisonData {
+        p_value: change.p_value,
+        t_distribution: change.t_distribution,
+        t_value: change.t_value,
+        relative_estimates: change.relative_estimates,
+        relative_distributions: change.relative_distributions,
+        significance_threshold: config.significance_level,
+        noise_threshold: criterion
+            .compare_thresholds
+            .noise_threshold(config.noise_threshold, has_throughput),
+        statistic: config.comparison_estimator.statistic(),
+        test: config.comparison_test,
+        base_iter_counts: baseline.iters,
+        base_sample_times: baseline.times,
+        base_avg_times: baseline.avg_times,
+        base_estimates: baseline.estimates,
+    }
+}
+
+impl Pending {
+    /// Returns true if the analysis is done, so that [`Pending::finish`] won't block.
+    pub fn is_done(&mut self) -> bool {
+        self.job.is_done()
+    }
+
+    /// Waits for the analysis, then saves and reports the results. Returns true if the results were
+    /// analyzed and saved.
+    pub fn finish<M: Measurement>(self, criterion: &Criterion<M>) -> bool {
+        let Pending {
+            id,
+            config,
+            report_context,
+            throughput,
+            sampling_mode,
+            iters,
+            times,
+            heap,
+            manifest,
+            create_baseline,
+            config_hash,
+            job,
+        } = self;
+        let id = &id;
+        let Computed {
+            distributions,
+            estimates,
+            comparison,
+            drift,
+        } = job.wait();
+
+        let avg_times = iters
+            .iter()
+            .zip(times.iter())
+            .map(|(&iters, &elapsed)| elapsed / iters)
+            .collect::<Vec<f64>>();
+        let avg_times = Sample::new(&avg_times);
+
+        if criterion.should_save_baseline() {
+            log_if_err!({
+                let mut new_dir = criterion.output_directory.clone();
+                new_dir.push(id.as_directory_name());
+                new_dir.push("new");
+                fs::mkdirp(&new_dir)
+            });
         }
-    } else {
-        None
-    };
 
-    let measurement_data = crate::report::MeasurementData {
-        data: Data::new(&*iters, &*times),
-        avg_times: labeled_sample,
-        absolute_estimates: estimates,
-        distributions,
-        comparison: compare_data,
-        throughput,
-    };
+        let data = Data::new(&iters, &times);
+        let labeled_sample = tukey::classify(avg_times);
+        if criterion.should_save_baseline() {
+            log_if_err!({
+                let mut tukey_file = criterion.output_directory.to_owned();
+                tukey_file.push(id.as_directory_name());
+                tukey_file.push("new");
+                tukey_file.push("tukey.json");
+                fs::save(&labeled_sample.fences(), &tukey_file)
+            });
+        }
 
-    criterion.report.measurement_complete(
-        id,
-        report_context,
-        &measurement_data,
-        criterion.measurement.formatter(),
-    );
+        if criterion.should_save_baseline() {
+            log_if_err!({
+                let mut sample_file = criterion.output_directory.clone();
+                sample_file.push(id.as_directory_name());
+                sample_file.push("new");
+                sample_file.push("sample.json");
+                fs::save(
+                    &SavedSample {
+                        sampling_mode,
+                        iters: data.x().as_ref().to_vec(),
+                        times: data.y().as_ref().to_vec(),
+                    },
+                    &sample_file,
+                )
+            });
+            log_if_err!({
+                let mut estimates_file = criterion.output_directory.clone();
+                estimates_file.push(id.as_directory_name());
+                estimates_file.push("new");
+                estimates_file.push("estimates.json");
+                fs::save(&estimates, &estimates_file)
+            });
+            if let Some(heap) = &heap {
+                log_if_err!({
+                    let mut heap_file = criterion.output_directory.clone();
+                    heap_file.push(id.as_directory_name());
+                    heap_file.push("new");
+                    heap_file.push("heap.json");
+                    fs::save(heap, &heap_file)
+                });
+            }
+            log_if_err!({
+                let mut manifest_file = criterion.output_directory.clone();
+                manifest_file.push(id.as_directory_name());
+                manifest_file.push("new");
+                manifest_file.push("manifest.json");
+                fs::save(&manifest, &manifest_file)
+            });
+            log_if_err!(history::record(
+                &criterion.output_directory,
+                id,
+                estimates.typical().point_estimate
+            ));
+        }
 
-    if criterion.should_save_baseline() {
-        log_if_err!({
-            let mut benchmark_file = criterion.output_directory.clone();
-            benchmark_file.push(id.as_directory_name());
-            benchmark_file.push("new");
-            benchmark_file.push("benchmark.json");
-            fs::save(&id, &benchmark_file)
+        let compare_data = comparison.map(|(baseline, change)| {
+            comparison_data(baseline, change, &config, criterion, throughput.is_some())
         });
-    }
+        match &compare_data {
+            Some(comparison) => compare::save(id, criterion, comparison),
+            None => compare::forget_significance(id, criterion),
+        }
 
-    if criterion.connection.is_none() {
-        if let Baseline::Save = criterion.baseline {
-            copy_new_dir_to_base(
-                id.as_directory_name(),
-                &criterion.baseline_directory,
+        let measurement_data = crate::report::MeasurementData {
+            data: Data::new(&iters, &times),
+            avg_times: labeled_sample,
+            absolute_estimates: estimates,
+            distributions,
+            comparison: compare_data,
+            throughput,
+            manifest,
+            heap,
+            drift,
+        };
+
+        criterion.report.measurement_complete(
+            id,
+            &report_context,
+            &measurement_data,
+            &criterion.formatter(),
+        );
+        let regressed =
+            matches!(&measurement_data.comparison, Some(comparison) if comparison.is_regression());
+        if let (Some(correction), Some(comparison)) =
+            (criterion.correction, &measurement_data.comparison)
+        {
+            correction::record(
                 &criterion.output_directory,
+                id.as_title(),
+                correction,
+                comparison.p_value,
+                comparison.significance_threshold,
+                comparison.change_beyond_noise(),
             );
         }
-    }
-}
 
-fn base_dir_exists(id: &BenchmarkId, baseline: &str, output_directory: &Path) -> bool {
-    let mut base_dir = output_directory.to_owned();
-    base_dir.push(id.as_directory_name());
-    base_dir.push(baseline);
-    base_dir.exists()
+        if criterion.should_save_baseline() {
+            log_if_err!({
+                let mut benchmark_file = criterion.output_directory.clone();
+                benchmark_file.push(id.as_directory_name());
+                benchmark_file.push("new");
+                benchmark_file.push("benchmark.json");
+                fs::save(&id, &benchmark_file)
+            });
+        }
+
+        if criterion.connection.is_none() && create_baseline {
+            let new_dir = criterion
+                .output_directory
+                .join(id.as_directory_name())
+                .join("new");
+            copy_new_dir_to_base(&new_dir, &criterion.baseline_dir(id));
+        }
+
+        if criterion.connection.is_none() {
+            if let Baseline::Save = criterion.baseline {
+                let new_dir = criterion
+                    .output_directory
+                    .join(id.as_directory_name())
+                    .join("new");
+                copy_new_dir_to_base(&new_dir, &criterion.baseline_dir(id));
+                // Only the baselines in the output directory are subject to the retention policy.
+                if let (Some(keep), None) = (criterion.keep_baselines, &criterion.external_baseline)
+                {
+                    log_if_err!(retention::apply(
+                        &criterion.output_directory.join(id.as_directory_name()),
+                        keep,
+                        &criterion.baseline_directory
+                    ));
+                }
+            }
+        }
+
+        if criterion.connection.is_none() {
+            resume::mark_completed(&criterion.output_directory, id, &config_hash);
+        }
+
+        if criterion.fail_fast && regressed {
+            eprintln!(
+                "Error: {} has regressed; stopping the run (--fail-fast).",
+                id
+            );
+            stop::stop(&criterion.output_directory);
+        }
+
+        true
+    }
 }
 
-// Performs a simple linear regression on the sample
-fn regression(
+// Performs a linear regression on the sample
+fn fit_slope(
     data: &Data<'_, f64, f64>,
-    config: &BenchmarkConfig,
+    method: RegressionMethod,
+    nresamples: usize,
+    cl: f64,
 ) -> (Distribution<f64>, Estimate) {
-    let cl = config.confidence_level;
-
     let distribution = elapsed!(
         "Bootstrapped linear regression",
-        data.bootstrap(config.nresamples, |d| (Slope::fit(&d).0,))
+        data.bootstrap(nresamples, |d| (method.fit(&d),))
     )
     .0;
 
-    let point = Slope::fit(data);
-    let (lb, ub) = distribution.confidence_interval(config.confidence_level);
+    let point = method.fit(data);
+    let (lb, ub) = distribution.confidence_interval(cl);
     let se = distribution.std_dev(None);
 
     (
@@ -290,14 +591,14 @@ fn regression(
                 lower_bound: lb,
                 upper_bound: ub,
             },
-            point_estimate: point.0,
+            point_estimate: point,
             standard_error: se,
         },
     )
 }
 
 // Estimates the statistics of the population from the sample
-fn estimates(avg_times: &Sample<f64>, config: &BenchmarkConfig) -> (Distributions, Estimates) {
+fn estimates(avg_times: &Sample<f64>, nresamples: usize, cl: f64) -> (Distributions, Estimates) {
     fn stats(sample: &Sample<f64>) -> (f64, f64, f64, f64) {
         let mean = sample.mean();
         let std_dev = sample.std_dev(Some(mean));
@@ -307,9 +608,6 @@ fn estimates(avg_times: &Sample<f64>, config: &BenchmarkConfig) -> (Distribution
         (mean, std_dev, median, mad)
     }
 
-    let cl = config.confidence_level;
-    let nresamples = config.nresamples;
-
     let (mean, std_dev, median, mad) = stats(avg_times);
     let points = PointEstimates {
         mean,
@@ -329,6 +627,7 @@ fn estimates(avg_times: &Sample<f64>, config: &BenchmarkConfig) -> (Distribution
         median: dist_median,
         median_abs_dev: dist_mad,
         std_dev: dist_stddev,
+        minimum: None,
     };
 
     let estimates = build_estimates(&distributions, &points, cl);
@@ -336,11 +635,81 @@ fn estimates(avg_times: &Sample<f64>, config: &BenchmarkConfig) -> (Distribution
     (distributions, estimates)
 }
 
-fn copy_new_dir_to_base(id: &str, baseline: &str, output_directory: &Path) {
-    let root_dir = Path::new(output_directory).join(id);
-    let base_dir = root_dir.join(baseline);
-    let new_dir = root_dir.join("new");
+// Estimates the minimum of the population from the sample. The ordinary bootstrap of an extreme is
+// degenerate, since most resamples contain the minimum of the sample, so the minima of resamples of
+// `m`, half of the `n` measurements, are bootstrapped instead (the m-out-of-n bootstrap). Assuming
+// the times have a density near their minimum, the minimum of `k` measurements exceeds that of the
+// population by an amount proportional to `1 / k`, and the excess of the minimum of a resample of
+// `m` over that of the sample is distributed like that of the minimum of `m` measurements over the
+// population's. So the minimum of the sample exceeds that of the population by `m / n` times as
+// much, which gives the distribution of the minimum of the population. The minimum of the sample
+// is an upper bound.
+fn minimum_estimate(
+    avg_times: &Sample<f64>,
+    nresamples: usize,
+    cl: f64,
+) -> (Distribution<f64>, Estimate) {
+    let n = avg_times.len();
+    let m = (n / 2).max(2);
+    let minimum = avg_times.min();
+    let subsample_minima = elapsed!(
+        "Bootstrapping the minimum.",
+        avg_times.bootstrap(nresamples, |resample| (Sample::new(&resample[..m]).min(),))
+    )
+    .0;
+    let excess = m as f64 / n as f64;
+    let distribution = Distribution::from(
+        subsample_minima
+            .iter()
+            .map(|&subsample_minimum| minimum - excess * (subsample_minimum - minimum))
+            .collect(),
+    );
+    let (lb, ub) = distribution.confidence_interval(cl);
+    let se = distribution.std_dev(None);
+
+    (
+        distribution,
+        Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: cl,
+                lower_bound: lb,
+                upper_bound: ub.max(minimum),
+            },
+            point_estimate: minimum,
+            standard_error: se,
+        },
+    )
+}
+
+// Estimates a percentile of the population from the sample
+fn percentile_estimate(
+    avg_times: &Sample<f64>,
+    percentile: f64,
+    nresamples: usize,
+    cl: f64,
+) -> PercentileEstimate {
+    let distribution = elapsed!(
+        "Bootstrapping a percentile.",
+        avg_times.bootstrap(nresamples, |sample| (sample.percentiles().at(percentile),))
+    )
+    .0;
+    let (lb, ub) = distribution.confidence_interval(cl);
+
+    PercentileEstimate {
+        percentile,
+        estimate: Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: cl,
+                lower_bound: lb,
+                upper_bound: ub,
+            },
+            point_estimate: avg_times.percentiles().at(percentile),
+            standard_error: distribution.std_dev(None),
+        },
+    }
+}
 
+fn copy_new_dir_to_base(new_dir: &Path, base_dir: &Path) {
     if !new_dir.exists() {
         return;
     };
@@ -365,6 +734,38 @@ fn copy_new_dir_to_base(id: &str, baseline: &str, output_directory: &Path) {
         &new_dir.join("benchmark.json"),
         &base_dir.join("benchmark.json")
     ));
+    try_else_return!(fs::cp(
+        &new_dir.join("manifest.json"),
+        &base_dir.join("manifest.json")
+    ));
     #[cfg(feature = "csv_output")]
     try_else_return!(fs::cp(&new_dir.join("raw.csv"), &base_dir.join("raw.csv")));
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn minimum_interval_covers_the_minimum() {
+        // Uniform times in [0, 1), whose minimum is 0: the minimum of 100 of them exceeds it by an
+        // exponential amount of mean 1 / 100, which is below 3.7 / 100 with 97.5% probability.
+        let _seed = rand_util::seed_bootstraps(Some(7));
+        let n = 100;
+        let trials = 200;
+        let (mut covered, mut width) = (0, 0.0);
+        for trial in 0..trials {
+            let mut rng = rand_util::seeded_rng(trial, 0);
+            let times: Vec<f64> = (0..n).map(|_| rng.rand_float()).collect();
+            let (_, estimate) = minimum_estimate(Sample::new(&times), 1000, 0.95);
+            let interval = &estimate.confidence_interval;
+            if interval.lower_bound <= 0.0 && interval.upper_bound >= 0.0 {
+                covered += 1;
+            }
+            width += (estimate.point_estimate - interval.lower_bound) * n as f64;
+        }
+        let (coverage, width) = (covered as f64 / trials as f64, width / trials as f64);
+        assert!(coverage > 0.9, "coverage {}", coverage);
+        assert!(width > 2.5 && width < 5.0, "width {}", width);
+    }
+}
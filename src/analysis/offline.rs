@@ -0,0 +1,180 @@
+//! Comparison of two saved baselines without running any benchmark (`--compare`).
+//!
+//! The second baseline of each benchmark is compared to the first with the statistics of a normal
+//! run, as if it had just been measured. The results are printed and exported by the reports which
+//! only describe them: the command-line output, the JSON report and the JUnit report. The reports
+//! which write the results of the run to the output directory are left out, since nothing was run.
+
+use crate::error::{self, Result};
+use crate::fs;
+use crate::manifest::Manifest;
+use crate::measurement::Measurement;
+use crate::report::{MeasurementData, Report, ReportContext};
+use crate::stats::bivariate::Data;
+use crate::stats::univariate::outliers::tukey;
+use crate::stats::univariate::Sample;
+use crate::{Criterion, PlotConfiguration, SavedSample};
+
+use super::{compare, comparison_data, compute, Computed};
+
+/// The outcome of `compare_baselines`.
+pub(crate) struct Summary {
+    /// The number of benchmarks saved in both baselines.
+    pub compared: usize,
+    pub regressed: usize,
+}
+
+/// Compares the baseline `baseline_b` of every benchmark in the output directory which matches the
+/// filter to its baseline `baseline_a`, and reports the results.
+pub(crate) fn compare_baselines<M: Measurement>(
+    criterion: &Criterion<M>,
+    baseline_a: &str,
+    baseline_b: &str,
+) -> Result<Summary> {
+    let report_context = ReportContext {
+        output_directory: criterion.output_directory.clone(),
+        plot_directory: criterion.plot_directory(),
+        plot_config: criterion.plot_config(&PlotConfiguration::default()),
+        defer_plots: criterion.defer_plots,
+        report_cache: criterion.report_cache,
+        plot_format: criterion.report_plot_format(),
+        percentiles: criterion.config.percentiles.clone(),
+    };
+    let mut reports: Vec<&dyn Report> = vec![];
+    if criterion.report.cli_enabled {
+        reports.push(&criterion.report.cli);
+    }
+    if let Some(json) = &criterion.report.json {
+        reports.push(json);
+    }
+    if let Some(junit) = &criterion.report.junit {
+        reports.push(junit);
+    }
+
+    let mut ids = fs::list_existing_benchmarks(&criterion.output_directory)?;
+    ids.sort_unstable_by_key(|id| id.id().to_owned());
+    let mut summary = Summary {
+        compared: 0,
+        regressed: 0,
+    };
+    for id in ids.iter().filter(|id| criterion.filter_matches(id)) {
+        let directory = criterion.output_directory.join(id.as_directory_name());
+        let (dir_a, dir_b) = (directory.join(baseline_a), directory.join(baseline_b));
+        if !dir_a.is_dir() || !dir_b.is_dir() {
+            continue;
+        }
+        let loaded = compare::load_from(&dir_a).and_then(|baseline| {
+            let sample: SavedSample = fs::load(&dir_b.join("sample.json"))?;
+            Ok((baseline, sample))
+        });
+        let (baseline, sample) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                error::log_error(&e);
+                continue;
+            }
+        };
+
+        let config = &criterion.config;
+        let Computed {
+            distributions,
+            estimates,
+            comparison,
+            drift,
+        } = compute(
+            &sample.iters,
+            &sample.times,
+            sample.sampling_mode.is_linear(),
+            config,
+            Some(baseline),
+            None,
+        );
+        let comparison = comparison.map(|(baseline, change)| {
+            comparison_data(baseline, change, config, criterion, id.throughput.is_some())
+        });
+
+        let avg_times: Vec<f64> = sample
+            .iters
+            .iter()
+            .zip(sample.times.iter())
+            .map(|(iters, elapsed)| elapsed / iters)
+            .collect();
+        let manifest = fs::load(&dir_b.join("manifest.json")).unwrap_or_else(|_| {
+            Manifest::new(std::any::type_name::<M>(), config, sample.sampling_mode)
+        });
+        let measurement_data = MeasurementData {
+            data: Data::new(&sample.iters, &sample.times),
+            avg_times: tukey::classify(Sample::new(&avg_times)),
+            absolute_estimates: estimates,
+            distributions,
+            comparison,
+            throughput: id.throughput.clone(),
+            manifest,
+            heap: None,
+            drift,
+        };
+        for report in &reports {
+            report.measurement_complete(
+                id,
+                &report_context,
+                &measurement_data,
+                &criterion.formatter(),
+            );
+        }
+
+        summary.compared += 1;
+        if matches!(&measurement_data.comparison, Some(comparison) if comparison.is_regression()) {
+            summary.regressed += 1;
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::junit_report::JunitReport;
+    use std::path::Path;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn short_benchmark(output_directory: &Path) -> Criterion {
+        Criterion::default()
+            .output_directory(output_directory)
+            .warm_up_time(Duration::from_millis(250))
+            .measurement_time(Duration::from_millis(500))
+            .sample_size(10)
+            .nresamples(2000)
+            .noise_threshold(0.5)
+            .without_plots()
+    }
+
+    #[test]
+    fn second_baseline_is_compared_to_the_first() {
+        let dir = tempdir().unwrap();
+        // `slow` is much slower in the second baseline, which is a regression beyond any noise.
+        for (baseline, work) in [("main", 1_000u64), ("feature", 100_000)].iter() {
+            let mut c = short_benchmark(dir.path()).save_baseline((*baseline).to_owned());
+            if *baseline == "main" {
+                c.bench_function("only_main", |b| b.iter(|| crate::black_box(1u64)));
+            }
+            c.bench_function("slow", |b| {
+                b.iter(|| (0..crate::black_box(*work)).sum::<u64>())
+            });
+        }
+
+        let junit = dir.path().join("junit.xml");
+        let mut c = short_benchmark(dir.path());
+        c.report.cli_enabled = false;
+        c.report.junit = Some(JunitReport::new(junit.clone()));
+        let summary = compare_baselines(&c, "main", "feature").unwrap();
+        assert_eq!(summary.compared, 1);
+        assert_eq!(summary.regressed, 1);
+        let xml = std::fs::read_to_string(&junit).unwrap();
+        assert!(xml.contains("<testcase classname=\"slow\" name=\"slow\""));
+        assert!(xml.contains("<failure type=\"regression\""));
+        assert!(!xml.contains("only_main"));
+
+        assert_eq!(compare_baselines(&c, "main", "other").unwrap().compared, 0);
+    }
+}
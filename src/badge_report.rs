@@ -0,0 +1,178 @@
+//! The badge of each benchmark group, `badge.svg` in the group's directory of the output
+//! directory, eg. `parse: 1.24 µs ▼3%`, in the style of shields.io, for embedding in a README.
+//!
+//! The badge shows the mean of the group's benchmark and its change compared to the baseline; for a
+//! group of several benchmarks, the geometric means of their means and changes. It is red if a
+//! benchmark of the group has regressed, green if one has improved and none has regressed, and grey
+//! otherwise (see `ComparisonData::significant_change`). Since every group of a run constructs its
+//! own `Criterion` struct, the results are kept in a process-wide static, keyed by output directory
+//! and group, and the badge is rewritten after each benchmark.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::error::Result;
+use crate::format;
+use crate::fs;
+use crate::measurement::ValueFormatter;
+use crate::report::{
+    make_filename_safe, BenchmarkId, MeasurementData, Report, ReportContext, SignificantChange,
+};
+
+const LABEL_COLOR: &str = "#555";
+const REGRESSED_COLOR: &str = "#e05d44";
+const IMPROVED_COLOR: &str = "#4c1";
+const UNCHANGED_COLOR: &str = "#9f9f9f";
+/// The approximate width of a character of the badge's font, in pixels.
+const CHAR_WIDTH: usize = 7;
+const PADDING: usize = 10;
+
+struct Measured {
+    output_directory: PathBuf,
+    group_id: String,
+    id: String,
+    mean: f64,
+    /// The ratio of the mean to that of the baseline, if there is one.
+    ratio: Option<f64>,
+    change: Option<SignificantChange>,
+}
+
+static RESULTS: Lazy<Mutex<Vec<Measured>>> = Lazy::new(|| Mutex::new(vec![]));
+
+pub(crate) struct BadgeReport;
+impl BadgeReport {
+    fn write(&self, output_directory: &Path, group_id: &str, svg: &str) -> Result<()> {
+        let directory = output_directory.join(make_filename_safe(group_id));
+        fs::mkdirp(&directory)?;
+        fs::save_string(svg, &directory.join("badge.svg"))
+    }
+}
+
+fn geometric_mean(values: &[f64]) -> f64 {
+    (values.iter().map(|value| value.ln()).sum::<f64>() / values.len() as f64).exp()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders a flat badge of the label and the message on a background of the color.
+fn render(label: &str, message: &str, color: &str) -> String {
+    let label_width = label.chars().count() * CHAR_WIDTH + PADDING;
+    let message_width = message.chars().count() * CHAR_WIDTH + PADDING;
+    let width = label_width + message_width;
+    let (label, message) = (escape(label), escape(message));
+
+    let mut svg = String::new();
+    write!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"20\" role=\"img\" \
+         aria-label=\"{}: {}\"><title>{}: {}</title>",
+        width, label, message, label, message
+    )
+    .unwrap();
+    svg.push_str(
+        "<linearGradient id=\"s\" x2=\"0\" y2=\"100%\"><stop offset=\"0\" stop-color=\"#bbb\" \
+         stop-opacity=\".1\"/><stop offset=\"1\" stop-opacity=\".1\"/></linearGradient>",
+    );
+    write!(
+        svg,
+        "<clipPath id=\"r\"><rect width=\"{}\" height=\"20\" rx=\"3\" fill=\"#fff\"/></clipPath>\
+         <g clip-path=\"url(#r)\"><rect width=\"{}\" height=\"20\" fill=\"{}\"/>\
+         <rect x=\"{}\" width=\"{}\" height=\"20\" fill=\"{}\"/>\
+         <rect width=\"{}\" height=\"20\" fill=\"url(#s)\"/></g>",
+        width, label_width, LABEL_COLOR, label_width, message_width, color, width
+    )
+    .unwrap();
+    svg.push_str(
+        "<g fill=\"#fff\" text-anchor=\"middle\" \
+         font-family=\"Verdana,Geneva,DejaVu Sans,sans-serif\" font-size=\"11\">",
+    );
+    for (x, text) in [
+        (label_width as f64 / 2.0, &label),
+        (label_width as f64 + message_width as f64 / 2.0, &message),
+    ]
+    .iter()
+    {
+        write!(
+            svg,
+            "<text x=\"{}\" y=\"15\" fill=\"#010101\" fill-opacity=\".3\">{}</text>\
+             <text x=\"{}\" y=\"14\">{}</text>",
+            x, text, x, text
+        )
+        .unwrap();
+    }
+    svg.push_str("</g></svg>\n");
+    svg
+}
+
+/// Renders the badge of the results of a group.
+fn group_badge(group_id: &str, results: &[&Measured], formatter: &dyn ValueFormatter) -> String {
+    let means: Vec<f64> = results.iter().map(|result| result.mean).collect();
+    let mut mean = [geometric_mean(&means)];
+    let unit = formatter.scale_values(mean[0], &mut mean);
+    let mut message = format!("{} {}", format::significant(mean[0], 3), unit);
+
+    let ratios: Vec<f64> = results.iter().filter_map(|result| result.ratio).collect();
+    if !ratios.is_empty() {
+        let change = (geometric_mean(&ratios) - 1.0) * 1e2;
+        write!(
+            message,
+            " {}{}%",
+            if change > 0.0 { '▲' } else { '▼' },
+            format::significant(change.abs(), 2)
+        )
+        .unwrap();
+    }
+
+    let changes = || results.iter().filter_map(|result| result.change);
+    let color = if changes().any(|change| change == SignificantChange::Regressed) {
+        REGRESSED_COLOR
+    } else if changes().any(|change| change == SignificantChange::Improved) {
+        IMPROVED_COLOR
+    } else {
+        UNCHANGED_COLOR
+    };
+    render(group_id, &message, color)
+}
+
+impl Report for BadgeReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &dyn ValueFormatter,
+    ) {
+        let comparison = measurements.comparison.as_ref();
+        let mut results = RESULTS.lock().unwrap();
+        // A benchmark run again in the same process replaces its previous result.
+        results.retain(|result| {
+            result.output_directory != context.output_directory || result.id != id.as_title()
+        });
+        results.push(Measured {
+            output_directory: context.output_directory.clone(),
+            group_id: id.group_id.clone(),
+            id: id.as_title().to_owned(),
+            mean: measurements.absolute_estimates.mean.point_estimate,
+            ratio: comparison.map(|comparison| 1.0 + comparison.change().point_estimate),
+            change: comparison.and_then(|comparison| comparison.significant_change()),
+        });
+
+        let group: Vec<&Measured> = results
+            .iter()
+            .filter(|result| {
+                result.output_directory == context.output_directory
+                    && result.group_id == id.group_id
+            })
+            .collect();
+        let svg = group_badge(&id.group_id, &group, formatter);
+        log_if_err!(self.write(&context.output_directory, &id.group_id, &svg));
+    }
+}
@@ -1,8 +1,9 @@
+use crate::clock::Instant;
 use std::iter::IntoIterator;
 use std::time::Duration;
-use std::time::Instant;
 
 use crate::black_box;
+use crate::hooks;
 use crate::measurement::{Measurement, WallTime};
 use crate::BatchSize;
 
@@ -83,11 +84,11 @@ impl<'a, M: Measurement> Bencher<'a, M> {
     {
         self.iterated = true;
         let time_start = Instant::now();
-        let start = self.measurement.start();
+        let start = self.start_batch(self.iters);
         for _ in 0..self.iters {
             black_box(routine());
         }
-        self.value = self.measurement.end(start);
+        self.value = self.end_batch(start, self.iters);
         self.elapsed_time = time_start.elapsed();
     }
 
@@ -134,7 +135,9 @@ impl<'a, M: Measurement> Bencher<'a, M> {
     {
         self.iterated = true;
         let time_start = Instant::now();
+        hooks::enter(self.iters);
         self.value = routine(self.iters);
+        hooks::exit(self.iters);
         self.elapsed_time = time_start.elapsed();
     }
 
@@ -248,9 +251,9 @@ impl<'a, M: Measurement> Bencher<'a, M> {
             for _ in 0..self.iters {
                 let input = black_box(setup());
 
-                let start = self.measurement.start();
+                let start = self.start_batch(1);
                 let output = routine(input);
-                let end = self.measurement.end(start);
+                let end = self.end_batch(start, 1);
                 self.value = self.measurement.add(&self.value, &end);
 
                 drop(black_box(output));
@@ -264,9 +267,9 @@ impl<'a, M: Measurement> Bencher<'a, M> {
                 let inputs = black_box((0..batch_size).map(|_| setup()).collect::<Vec<_>>());
                 let mut outputs = Vec::with_capacity(batch_size as usize);
 
-                let start = self.measurement.start();
+                let start = self.start_batch(batch_size);
                 outputs.extend(inputs.into_iter().map(&mut routine));
-                let end = self.measurement.end(start);
+                let end = self.end_batch(start, batch_size);
                 self.value = self.measurement.add(&self.value, &end);
 
                 black_box(outputs);
@@ -338,9 +341,9 @@ impl<'a, M: Measurement> Bencher<'a, M> {
             for _ in 0..self.iters {
                 let mut input = black_box(setup());
 
-                let start = self.measurement.start();
+                let start = self.start_batch(1);
                 let output = routine(&mut input);
-                let end = self.measurement.end(start);
+                let end = self.end_batch(start, 1);
                 self.value = self.measurement.add(&self.value, &end);
 
                 drop(black_box(output));
@@ -355,9 +358,9 @@ impl<'a, M: Measurement> Bencher<'a, M> {
                 let mut inputs = black_box((0..batch_size).map(|_| setup()).collect::<Vec<_>>());
                 let mut outputs = Vec::with_capacity(batch_size as usize);
 
-                let start = self.measurement.start();
+                let start = self.start_batch(batch_size);
                 outputs.extend(inputs.iter_mut().map(&mut routine));
-                let end = self.measurement.end(start);
+                let end = self.end_batch(start, batch_size);
                 self.value = self.measurement.add(&self.value, &end);
 
                 black_box(outputs);
@@ -378,6 +381,20 @@ impl<'a, M: Measurement> Bencher<'a, M> {
         self.iterated = false;
     }
 
+    /// Starts measuring a batch of `iters` iterations, right after calling the batch hooks.
+    fn start_batch(&self, iters: u64) -> M::Intermediate {
+        hooks::enter(iters);
+        self.measurement.start()
+    }
+
+    /// Ends the measurement of a batch of `iters` iterations, right before calling the batch
+    /// hooks.
+    fn end_batch(&self, start: M::Intermediate, iters: u64) -> M::Value {
+        let value = self.measurement.end(start);
+        hooks::exit(iters);
+        value
+    }
+
     /// Convert this bencher into an AsyncBencher, which enables async/await support.
     #[cfg(feature = "async")]
     pub fn to_async<'b, A: AsyncExecutor>(&'b mut self, runner: A) -> AsyncBencher<'a, 'b, A, M> {
@@ -440,11 +457,11 @@ impl<'a, 'b, A: AsyncExecutor, M: Measurement> AsyncBencher<'a, 'b, A, M> {
         runner.block_on(async {
             b.iterated = true;
             let time_start = Instant::now();
-            let start = b.measurement.start();
+            let start = b.start_batch(b.iters);
             for _ in 0..b.iters {
                 black_box(routine().await);
             }
-            b.value = b.measurement.end(start);
+            b.value = b.end_batch(start, b.iters);
             b.elapsed_time = time_start.elapsed();
         });
     }
@@ -498,7 +515,9 @@ impl<'a, 'b, A: AsyncExecutor, M: Measurement> AsyncBencher<'a, 'b, A, M> {
         runner.block_on(async {
             b.iterated = true;
             let time_start = Instant::now();
+            hooks::enter(b.iters);
             b.value = routine(b.iters).await;
+            hooks::exit(b.iters);
             b.elapsed_time = time_start.elapsed();
         })
     }
@@ -630,9 +649,9 @@ impl<'a, 'b, A: AsyncExecutor, M: Measurement> AsyncBencher<'a, 'b, A, M> {
                 for _ in 0..b.iters {
                     let input = black_box(setup());
 
-                    let start = b.measurement.start();
+                    let start = b.start_batch(1);
                     let output = routine(input).await;
-                    let end = b.measurement.end(start);
+                    let end = b.end_batch(start, 1);
                     b.value = b.measurement.add(&b.value, &end);
 
                     drop(black_box(output));
@@ -646,12 +665,12 @@ impl<'a, 'b, A: AsyncExecutor, M: Measurement> AsyncBencher<'a, 'b, A, M> {
                     let inputs = black_box((0..batch_size).map(|_| setup()).collect::<Vec<_>>());
                     let mut outputs = Vec::with_capacity(batch_size as usize);
 
-                    let start = b.measurement.start();
+                    let start = b.start_batch(batch_size);
                     // Can't use .extend here like the sync version does
                     for input in inputs {
                         outputs.push(routine(input).await);
                     }
-                    let end = b.measurement.end(start);
+                    let end = b.end_batch(start, batch_size);
                     b.value = b.measurement.add(&b.value, &end);
 
                     black_box(outputs);
@@ -728,9 +747,9 @@ impl<'a, 'b, A: AsyncExecutor, M: Measurement> AsyncBencher<'a, 'b, A, M> {
                 for _ in 0..b.iters {
                     let mut input = black_box(setup());
 
-                    let start = b.measurement.start();
+                    let start = b.start_batch(1);
                     let output = routine(&mut input).await;
-                    let end = b.measurement.end(start);
+                    let end = b.end_batch(start, 1);
                     b.value = b.measurement.add(&b.value, &end);
 
                     drop(black_box(output));
@@ -745,12 +764,12 @@ impl<'a, 'b, A: AsyncExecutor, M: Measurement> AsyncBencher<'a, 'b, A, M> {
                     let inputs = black_box((0..batch_size).map(|_| setup()).collect::<Vec<_>>());
                     let mut outputs = Vec::with_capacity(batch_size as usize);
 
-                    let start = b.measurement.start();
+                    let start = b.start_batch(batch_size);
                     // Can't use .extend here like the sync version does
                     for mut input in inputs {
                         outputs.push(routine(&mut input).await);
                     }
-                    let end = b.measurement.end(start);
+                    let end = b.end_batch(start, batch_size);
                     b.value = b.measurement.add(&b.value, &end);
 
                     black_box(outputs);
@@ -1,19 +1,56 @@
-use crate::{PlotConfiguration, SamplingMode};
+use crate::{
+    ComparisonTest, Estimator, OutlierPolicy, PlotConfiguration, RegressionMethod, SamplingMode,
+};
 use std::time::Duration;
 
 // TODO: Move the benchmark config stuff to a separate module for easier use.
 
 /// Struct containing all of the configuration options for a benchmark.
+///
+/// See the corresponding methods of [`Criterion`](crate::Criterion) for the meaning of each
+/// option.
+#[derive(Clone)]
+#[non_exhaustive]
 pub struct BenchmarkConfig {
+    /// Confidence level of the confidence intervals.
     pub confidence_level: f64,
+    /// Target time to spend measuring.
     pub measurement_time: Duration,
+    /// Relative changes smaller than this are not reported as changes.
     pub noise_threshold: f64,
+    /// Number of bootstrap resamples.
     pub nresamples: usize,
+    /// Number of samples.
     pub sample_size: usize,
+    /// Significance level of the comparison with the baseline.
     pub significance_level: f64,
+    /// Time to spend warming up.
     pub warm_up_time: Duration,
+    /// How the iteration counts of the samples are chosen.
     pub sampling_mode: SamplingMode,
+    /// Whether to stop sampling as soon as the results are precise enough.
     pub quick_mode: bool,
+    /// How the slope of linear samples is fitted.
+    pub regression_method: RegressionMethod,
+    /// The statistic compared against the baseline.
+    pub comparison_estimator: Estimator,
+    /// The test of the significance of the change compared to the baseline.
+    pub comparison_test: ComparisonTest,
+    /// The percentiles of the average times to estimate, between 0 and 100.
+    pub percentiles: Vec<f64>,
+    /// Whether the minimum iteration time is the headline statistic.
+    pub minimum_time: bool,
+    /// How the outliers of the average times are treated before the analysis.
+    pub outlier_policy: OutlierPolicy,
+    /// The relative half-width of the 95% confidence interval of the mean at which sampling
+    /// stops, instead of after `sample_size` samples.
+    pub target_precision: Option<f64>,
+    /// The longest time to spend measuring with `target_precision`.
+    pub max_measurement_time: Duration,
+    /// Whether the statistics are computed online, keeping only a reservoir of the samples.
+    pub streaming_statistics: bool,
+    /// The seed of the bootstrap, or `None` to seed it randomly.
+    pub bootstrap_seed: Option<u64>,
 }
 
 /// Struct representing a partially-complete per-benchmark configuration.
@@ -28,6 +65,15 @@ pub(crate) struct PartialBenchmarkConfig {
     pub(crate) warm_up_time: Option<Duration>,
     pub(crate) sampling_mode: Option<SamplingMode>,
     pub(crate) quick_mode: Option<bool>,
+    pub(crate) regression_method: Option<RegressionMethod>,
+    pub(crate) comparison_estimator: Option<Estimator>,
+    pub(crate) comparison_test: Option<ComparisonTest>,
+    pub(crate) percentiles: Option<Vec<f64>>,
+    pub(crate) minimum_time: Option<bool>,
+    pub(crate) outlier_policy: Option<OutlierPolicy>,
+    pub(crate) target_precision: Option<f64>,
+    pub(crate) max_measurement_time: Option<Duration>,
+    pub(crate) streaming_statistics: Option<bool>,
     pub(crate) plot_config: PlotConfiguration,
 }
 
@@ -45,6 +91,25 @@ impl PartialBenchmarkConfig {
             warm_up_time: self.warm_up_time.unwrap_or(defaults.warm_up_time),
             sampling_mode: self.sampling_mode.unwrap_or(defaults.sampling_mode),
             quick_mode: self.quick_mode.unwrap_or(defaults.quick_mode),
+            regression_method: self.regression_method.unwrap_or(defaults.regression_method),
+            comparison_estimator: self
+                .comparison_estimator
+                .unwrap_or(defaults.comparison_estimator),
+            comparison_test: self.comparison_test.unwrap_or(defaults.comparison_test),
+            percentiles: self
+                .percentiles
+                .clone()
+                .unwrap_or_else(|| defaults.percentiles.clone()),
+            minimum_time: self.minimum_time.unwrap_or(defaults.minimum_time),
+            outlier_policy: self.outlier_policy.unwrap_or(defaults.outlier_policy),
+            target_precision: self.target_precision.or(defaults.target_precision),
+            max_measurement_time: self
+                .max_measurement_time
+                .unwrap_or(defaults.max_measurement_time),
+            streaming_statistics: self
+                .streaming_statistics
+                .unwrap_or(defaults.streaming_statistics),
+            bootstrap_seed: defaults.bootstrap_seed,
         }
     }
 }
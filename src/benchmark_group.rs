@@ -1,12 +1,24 @@
 use crate::analysis;
-use crate::benchmark::PartialBenchmarkConfig;
+use crate::benchmark::{BenchmarkConfig, PartialBenchmarkConfig};
 use crate::connection::OutgoingMessage;
+use crate::environment;
+use crate::format;
+use crate::fs;
 use crate::measurement::Measurement;
+use crate::noise_floor;
+use crate::progress;
+use crate::ranking;
 use crate::report::BenchmarkId as InternalBenchmarkId;
 use crate::report::Report;
 use crate::report::ReportContext;
-use crate::routine::{Function, Routine};
-use crate::{Bencher, Criterion, Mode, PlotConfiguration, SamplingMode, Throughput};
+use crate::resume;
+use crate::routine::{Function, Replay, Routine};
+use crate::stop;
+use crate::{
+    Bencher, ComparisonTest, Criterion, Estimator, ListFormat, Mode, OutlierPolicy,
+    PlotConfiguration, RegressionMethod, Samples, SamplingMode, Throughput,
+};
+use std::collections::HashMap;
 use std::time::Duration;
 
 /// Structure used to group together a set of related benchmarks, along with custom configuration
@@ -79,9 +91,17 @@ pub struct BenchmarkGroup<'a, M: Measurement> {
     criterion: &'a mut Criterion<M>,
     group_name: String,
     all_ids: Vec<InternalBenchmarkId>,
+    /// The IDs of the benchmarks which matched the filters, and were run (or resumed) this time.
+    run_ids: Vec<InternalBenchmarkId>,
     any_matched: bool,
     partial_config: PartialBenchmarkConfig,
     throughput: Option<Throughput>,
+    /// Per-benchmark (significance level, noise threshold) overrides.
+    thresholds: HashMap<BenchmarkId, (Option<f64>, Option<f64>)>,
+    tags: Vec<String>,
+    benchmark_tags: HashMap<BenchmarkId, Vec<String>>,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
 }
 impl<'a, M: Measurement> BenchmarkGroup<'a, M> {
     /// Changes the size of the sample for this benchmark
@@ -216,6 +236,88 @@ impl<'a, M: Measurement> BenchmarkGroup<'a, M> {
         self
     }
 
+    /// Changes the significance level and noise threshold used when comparing the given benchmark
+    /// in this group against its baseline, overriding the values of the group. This is useful
+    /// when a single benchmark is much noisier than the others in its group.
+    ///
+    /// The thresholds which were used are shown on the benchmark's HTML report and saved in its
+    /// `manifest.json`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the significance level is outside the `(0, 1)` range or the noise threshold is
+    /// negative.
+    pub fn thresholds_for<ID: IntoBenchmarkId>(
+        &mut self,
+        id: ID,
+        significance_level: f64,
+        noise_threshold: f64,
+    ) -> &mut Self {
+        let id = id.into_benchmark_id();
+        self.significance_level_for(id.clone(), significance_level)
+            .noise_threshold_for(id, noise_threshold)
+    }
+
+    /// Changes the noise threshold used when comparing the given benchmark in this group against
+    /// its baseline, overriding the noise threshold of the group. See
+    /// [`thresholds_for`](Self::thresholds_for).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the threshold is negative.
+    pub fn noise_threshold_for<ID: IntoBenchmarkId>(
+        &mut self,
+        id: ID,
+        threshold: f64,
+    ) -> &mut Self {
+        assert!(threshold >= 0.0);
+
+        self.thresholds.entry(id.into_benchmark_id()).or_default().1 = Some(threshold);
+        self
+    }
+
+    /// Changes the significance level used when comparing the given benchmark in this group
+    /// against its baseline, overriding the significance level of the group. See
+    /// [`thresholds_for`](Self::thresholds_for).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the significance level is outside the `(0, 1)` range.
+    pub fn significance_level_for<ID: IntoBenchmarkId>(&mut self, id: ID, sl: f64) -> &mut Self {
+        assert!(sl > 0.0 && sl < 1.0);
+
+        self.thresholds.entry(id.into_benchmark_id()).or_default().0 = Some(sl);
+        self
+    }
+
+    /// Attaches a tag to the benchmarks in this group, eg. `group.tag("slow").tag("io")`. Tags
+    /// can be used to select benchmarks with the `--tag` and `--exclude-tag` command-line options,
+    /// and are shown in the `--list` output and on the HTML report.
+    pub fn tag<S: Into<String>>(&mut self, tag: S) -> &mut Self {
+        let tag = tag.into();
+        if !self.tags.contains(&tag) {
+            self.tags.push(tag);
+        }
+        self
+    }
+
+    /// Attaches a tag to a single benchmark in this group, in addition to the tags of the group.
+    pub fn tag_benchmark<ID: IntoBenchmarkId, S: Into<String>>(
+        &mut self,
+        id: ID,
+        tag: S,
+    ) -> &mut Self {
+        let tag = tag.into();
+        let tags = self
+            .benchmark_tags
+            .entry(id.into_benchmark_id())
+            .or_default();
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+        self
+    }
+
     /// Changes the plot configuration for this benchmark group.
     pub fn plot_config(&mut self, new_config: PlotConfiguration) -> &mut Self {
         self.partial_config.plot_config = new_config;
@@ -235,14 +337,90 @@ impl<'a, M: Measurement> BenchmarkGroup<'a, M> {
         self
     }
 
+    /// Set the method used to fit the slope of the iteration times for this benchmark group, see
+    /// `Criterion::regression_method`.
+    pub fn regression_method(&mut self, method: RegressionMethod) -> &mut Self {
+        self.partial_config.regression_method = Some(method);
+        self
+    }
+
+    /// Set the statistic compared against the baseline for this benchmark group, see
+    /// `Criterion::comparison_estimator`.
+    pub fn comparison_estimator(&mut self, estimator: Estimator) -> &mut Self {
+        self.partial_config.comparison_estimator = Some(estimator);
+        self
+    }
+
+    /// Set the test of the significance of the change compared to the baseline for this benchmark
+    /// group, see `Criterion::comparison_test`.
+    pub fn comparison_test(&mut self, test: ComparisonTest) -> &mut Self {
+        self.partial_config.comparison_test = Some(test);
+        self
+    }
+
+    /// Set the percentiles of the average times estimated for this benchmark group, see
+    /// `Criterion::percentiles`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a percentile is outside the range from 0 to 100.
+    pub fn percentiles(&mut self, percentiles: &[f64]) -> &mut Self {
+        assert!(percentiles.iter().all(|p| (0.0..=100.0).contains(p)));
+
+        self.partial_config.percentiles = Some(percentiles.to_vec());
+        self
+    }
+
+    /// Set whether the minimum iteration time is the headline statistic of this benchmark group,
+    /// see `Criterion::minimum_time`.
+    pub fn minimum_time(&mut self, enabled: bool) -> &mut Self {
+        self.partial_config.minimum_time = Some(enabled);
+        self
+    }
+
+    /// Set the treatment of the outliers of the average times for this benchmark group, see
+    /// `Criterion::outlier_policy`.
+    pub fn outlier_policy(&mut self, policy: OutlierPolicy) -> &mut Self {
+        self.partial_config.outlier_policy = Some(policy);
+        self
+    }
+
+    /// Set the precision at which sampling stops for this benchmark group, see
+    /// `Criterion::target_precision`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the precision isn't positive or the maximum time is zero.
+    pub fn target_precision(&mut self, precision: f64, max_time: Duration) -> &mut Self {
+        assert!(precision > 0.0);
+        assert!(max_time.as_nanos() > 0);
+
+        self.partial_config.target_precision = Some(precision);
+        self.partial_config.max_measurement_time = Some(max_time);
+        self
+    }
+
+    /// Set whether the statistics of this benchmark group are computed online, keeping only a
+    /// reservoir of the samples, see `Criterion::streaming_statistics`.
+    pub fn streaming_statistics(&mut self, enabled: bool) -> &mut Self {
+        self.partial_config.streaming_statistics = Some(enabled);
+        self
+    }
+
     pub(crate) fn new(criterion: &mut Criterion<M>, group_name: String) -> BenchmarkGroup<'_, M> {
         BenchmarkGroup {
             criterion,
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!("group", name = %group_name),
             group_name,
             all_ids: vec![],
+            run_ids: vec![],
             any_matched: false,
             partial_config: PartialBenchmarkConfig::default(),
             throughput: None,
+            thresholds: HashMap::new(),
+            tags: vec![],
+            benchmark_tags: HashMap::new(),
         }
     }
 
@@ -302,24 +480,78 @@ impl<'a, M: Measurement> BenchmarkGroup<'a, M> {
         self
     }
 
+    /// Analyzes samples collected elsewhere, eg. on an embedded device with the `criterion-core`
+    /// crate, as if they had been measured in this benchmark group. The ID of the samples is
+    /// ignored in favor of `id`. The values have to be in the unit of the group's measurement, eg.
+    /// nanoseconds for `WallTime`.
+    ///
+    /// The results are reported, saved and compared to the baseline like those of any other
+    /// benchmark. The timing settings of the group have no effect, since the samples have already
+    /// been taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are fewer than two samples or if the numbers of iteration counts and values
+    /// differ.
+    pub fn bench_samples<ID: IntoBenchmarkId>(&mut self, id: ID, samples: &Samples) -> &mut Self {
+        assert!(
+            samples.iters.len() >= 2 && samples.iters.len() == samples.values.len(),
+            "Samples must have at least two iteration counts and as many values."
+        );
+        self.run_routine(id.into_benchmark_id(), &(), &mut Replay::new(samples));
+        self
+    }
+
     fn run_bench<F, PF, I>(&mut self, id: BenchmarkId, input: &I, f: F, p_f: PF)
     where
         F: FnMut(&mut Bencher<'_, M>, &I),
         PF: FnMut(&mut Bencher<'_, M>, &I),
         I: ?Sized,
     {
-        let config = self.partial_config.to_complete(&self.criterion.config);
+        self.run_routine(id, input, &mut Function::new(f, p_f));
+    }
+
+    fn run_routine<I: ?Sized>(
+        &mut self,
+        id: BenchmarkId,
+        input: &I,
+        routine: &mut dyn Routine<M, I>,
+    ) {
+        #[cfg(feature = "tracing")]
+        let _group_span = self.span.clone().entered();
+
+        let mut config = self.partial_config.to_complete(&self.criterion.config);
+        if let Some(&(significance_level, noise_threshold)) = self.thresholds.get(&id) {
+            config.significance_level = significance_level.unwrap_or(config.significance_level);
+            config.noise_threshold = noise_threshold.unwrap_or(config.noise_threshold);
+        }
         let report_context = ReportContext {
             output_directory: self.criterion.output_directory.clone(),
-            plot_config: self.partial_config.plot_config.clone(),
+            plot_directory: self.criterion.plot_directory(),
+            plot_config: self.criterion.plot_config(&self.partial_config.plot_config),
+            defer_plots: self.criterion.defer_plots,
+            report_cache: self.criterion.report_cache,
+            plot_format: self.criterion.report_plot_format(),
+            percentiles: config.percentiles.clone(),
         };
 
+        let mut tags = self.tags.clone();
+        if let Some(benchmark_tags) = self.benchmark_tags.get(&id) {
+            tags.extend(
+                benchmark_tags
+                    .iter()
+                    .filter(|tag| !self.tags.contains(tag))
+                    .cloned(),
+            );
+        }
         let mut id = InternalBenchmarkId::new(
             self.group_name.clone(),
             id.function_name,
             id.parameter,
             self.throughput.clone(),
         );
+        id.tags = tags;
+        self.criterion.apply_config_overrides(id.id(), &mut config);
 
         assert!(
             !self.all_ids.contains(&id),
@@ -334,12 +566,24 @@ impl<'a, M: Measurement> BenchmarkGroup<'a, M> {
         id.ensure_title_unique(&self.criterion.all_titles);
         self.criterion.all_titles.insert(id.as_title().to_owned());
 
-        let do_run = self.criterion.filter_matches(id.id());
+        enter_span!("benchmark", id = %id);
+
+        let do_run = self.criterion.filter_matches(&id);
         self.any_matched |= do_run;
-        let mut func = Function::new(f, p_f);
 
         match &self.criterion.mode {
             Mode::Benchmark => {
+                if do_run && self.criterion.check_env {
+                    if let Some(findings) = environment::check_once() {
+                        self.criterion.report.environment(&findings);
+                        if self.criterion.strict_env && findings.iter().any(|f| f.warning.is_some())
+                        {
+                            eprintln!("Error: refusing to run the benchmarks with --strict-env until the warnings above are fixed.");
+                            stop::stop(&self.criterion.output_directory);
+                        }
+                    }
+                }
+                let do_run = do_run && !stop::is_stopped(&self.criterion.output_directory);
                 if let Some(conn) = &self.criterion.connection {
                     if do_run {
                         conn.send(&OutgoingMessage::BeginningBenchmark { id: (&id).into() })
@@ -349,36 +593,91 @@ impl<'a, M: Measurement> BenchmarkGroup<'a, M> {
                             .unwrap();
                     }
                 }
-                if do_run {
-                    analysis::common(
+                if do_run && self.criterion.noise_floor && noise_floor::calibrate_start() {
+                    if let Some(noise_floor) = noise_floor::current() {
+                        self.criterion.report.noise_floor(&noise_floor);
+                    }
+                }
+                let output_directory = &self.criterion.output_directory;
+                let config_hash = resume::config_hash(
+                    &id,
+                    &describe_config(&config, self.throughput.as_ref()),
+                    std::any::type_name::<M>(),
+                );
+                if do_run
+                    && self.criterion.connection.is_none()
+                    && resume::is_completed(
+                        output_directory,
+                        &id,
+                        &config_hash,
+                        self.criterion.resume,
+                    )
+                {
+                    println!("{}: already completed, skipping (--resume)", id);
+                    progress::benchmark_finished();
+                } else if do_run {
+                    // Report what has been analyzed in the background so far, before this
+                    // benchmark's progress is shown.
+                    self.criterion.finish_analyses(false);
+                    progress::benchmark_started(config.warm_up_time + config.measurement_time);
+                    let pending = analysis::common(
                         &id,
-                        &mut func,
+                        routine,
                         &config,
                         self.criterion,
                         &report_context,
                         input,
                         self.throughput.clone(),
+                        config_hash,
                     );
+                    progress::benchmark_finished();
+                    if let Some(pending) = pending {
+                        self.criterion.pending_analyses.get_mut().push_back(pending);
+                        if !self.criterion.background_analysis {
+                            self.criterion.finish_analyses(true);
+                        }
+                    }
                 }
             }
-            Mode::List => {
+            Mode::List(format) => {
                 if do_run {
-                    println!("{}: bench", id);
+                    list_benchmark(&id, *format);
                 }
             }
             Mode::Test => {
                 if do_run {
                     // In test mode, run the benchmark exactly once, then exit.
                     self.criterion.report.test_start(&id, &report_context);
-                    func.test(&self.criterion.measurement, input);
+                    routine.test(&self.criterion.measurement, input);
                     self.criterion.report.test_pass(&id, &report_context);
                 }
             }
+            Mode::DryRun => {
+                if do_run {
+                    // Printed before running so that a panicking routine is easy to identify.
+                    println!(
+                        "{}: {}",
+                        id,
+                        describe_config(&config, self.throughput.as_ref())
+                    );
+                    routine.test(&self.criterion.measurement, input);
+                    println!("{}: ok", id);
+                }
+            }
+            Mode::Count => {
+                if do_run {
+                    progress::benchmark_discovered();
+                    #[cfg(feature = "interactive")]
+                    crate::interactive::benchmark_discovered(&id, &self.criterion.output_directory);
+                }
+            }
             &Mode::Profile(duration) => {
                 if do_run {
-                    func.profile(
+                    let duration = self.criterion.resolve_profile_time(id.id(), duration);
+                    routine.profile(
                         &self.criterion.measurement,
                         &id,
+                        &config,
                         self.criterion,
                         &report_context,
                         duration,
@@ -388,6 +687,9 @@ impl<'a, M: Measurement> BenchmarkGroup<'a, M> {
             }
         }
 
+        if do_run {
+            self.run_ids.push(id.clone());
+        }
         self.all_ids.push(id);
     }
 
@@ -399,11 +701,70 @@ impl<'a, M: Measurement> BenchmarkGroup<'a, M> {
         ::std::mem::drop(self);
     }
 }
+/// Describes the settings a benchmark would be run with, for `--dry-run`.
+fn describe_config(config: &BenchmarkConfig, throughput: Option<&Throughput>) -> String {
+    let mut description = format!(
+        "sample size {}, warm-up {}, measurement {}, sampling mode {:?}, {} resamples, \
+         confidence level {}, significance level {}, noise threshold {}",
+        config.sample_size,
+        format::time(config.warm_up_time.as_nanos() as f64),
+        format::time(config.measurement_time.as_nanos() as f64),
+        config.sampling_mode,
+        config.nresamples,
+        config.confidence_level,
+        config.significance_level,
+        config.noise_threshold,
+    );
+    if config.quick_mode {
+        description.push_str(", quick mode");
+    }
+    if let Some(throughput) = throughput {
+        description.push_str(&format!(", throughput {:?}", throughput));
+    }
+    description
+}
+
+/// A benchmark in the `--list --format=json` output.
+#[derive(Serialize)]
+struct ListedBenchmark<'a> {
+    id: &'a str,
+    group: &'a str,
+    function: Option<&'a str>,
+    value: Option<&'a str>,
+    tags: &'a [String],
+    throughput: Option<&'a Throughput>,
+}
+
+fn list_benchmark(id: &InternalBenchmarkId, format: ListFormat) {
+    match format {
+        ListFormat::Pretty if !id.tags.is_empty() => {
+            println!("{}: bench [{}]", id, id.tags.join(", "))
+        }
+        ListFormat::Pretty | ListFormat::Terse => println!("{}: bench", id),
+        ListFormat::Json => {
+            let listed = ListedBenchmark {
+                id: id.id(),
+                group: &id.group_id,
+                function: id.function_id.as_deref(),
+                value: id.value_str.as_deref(),
+                tags: &id.tags,
+                throughput: id.throughput.as_ref(),
+            };
+            println!("{}", serde_json::to_string(&listed).unwrap());
+        }
+    }
+}
+
 impl<'a, M: Measurement> Drop for BenchmarkGroup<'a, M> {
     fn drop(&mut self) {
         // I don't really like having a bunch of non-trivial code in drop, but this is the only way
         // to really write linear types like this in Rust...
-        if let Some(conn) = &mut self.criterion.connection {
+        #[cfg(feature = "tracing")]
+        let _group_span = self.span.clone().entered();
+
+        // cargo-criterion is not told about the groups run only to count their benchmarks.
+        let counting = matches!(self.criterion.mode, Mode::Count);
+        if let Some(conn) = self.criterion.connection.as_mut().filter(|_| !counting) {
             conn.send(&OutgoingMessage::FinishedBenchmarkGroup {
                 group: &self.group_name,
             })
@@ -413,19 +774,54 @@ impl<'a, M: Measurement> Drop for BenchmarkGroup<'a, M> {
                 .unwrap();
         }
 
+        // The results must not outlive the group, since the `Criterion` struct may be dropped
+        // without a final summary, and the summary needs them.
+        self.criterion.finish_analyses(true);
+
         if self.all_ids.len() > 1 && self.any_matched && self.criterion.mode.is_benchmark() {
+            enter_span!("summary");
             let report_context = ReportContext {
                 output_directory: self.criterion.output_directory.clone(),
-                plot_config: self.partial_config.plot_config.clone(),
+                plot_directory: self.criterion.plot_directory(),
+                plot_config: self.criterion.plot_config(&self.partial_config.plot_config),
+                defer_plots: self.criterion.defer_plots,
+                report_cache: self.criterion.report_cache,
+                plot_format: self.criterion.report_plot_format(),
+                percentiles: self
+                    .partial_config
+                    .to_complete(&self.criterion.config)
+                    .percentiles,
             };
 
+            let rankings = ranking::rank(&self.criterion.output_directory, &self.run_ids);
+            if !rankings.is_empty() {
+                log_if_err!({
+                    let group_id =
+                        InternalBenchmarkId::new(self.group_name.clone(), None, None, None);
+                    let mut ranking_file = self.criterion.output_directory.clone();
+                    ranking_file.push(group_id.as_directory_name());
+                    ranking_file.push("ranking.json");
+                    fs::save(&rankings, &ranking_file)
+                });
+                self.criterion.report.rankings(
+                    &report_context,
+                    &rankings,
+                    &self.criterion.formatter(),
+                );
+            }
+
             self.criterion.report.summarize(
                 &report_context,
                 &self.all_ids,
-                self.criterion.measurement.formatter(),
+                &self.criterion.formatter(),
             );
         }
-        if self.any_matched {
+        // The counting pass prints nothing, and the JSON list is one object per line.
+        let quiet_mode = matches!(
+            self.criterion.mode,
+            Mode::Count | Mode::List(ListFormat::Json)
+        );
+        if self.any_matched && !quiet_mode {
             self.criterion.report.group_separator();
         }
     }
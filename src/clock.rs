@@ -0,0 +1,80 @@
+//! Clocks which also work in web browsers.
+//!
+//! On `wasm32-unknown-unknown`, `std::time::Instant::now` and `SystemTime::now` panic, since the
+//! standard library has no clock to read there. Criterion.rs uses these wrappers instead: they are
+//! the standard clocks on every other target, and on `wasm32-unknown-unknown` they read the clocks
+//! of the JavaScript environment: `performance.now()` if available, or else `Date.now()`.
+//!
+//! Note that browsers round `performance.now()` to between 5 µs and 1 ms to mitigate timing
+//! attacks. Benchmarks still measure accurately, since enough iterations are run for each sample
+//! to make the rounding negligible, but the warm-up estimates are coarser.
+
+use std::time::Duration;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use std::time::Instant;
+
+/// Time elapsed since the Unix epoch, for time stamps and seeds.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(crate) fn since_epoch() -> Duration {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// A point in time of the monotonic clock of the JavaScript environment, in milliseconds.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+#[derive(Debug, Clone, Copy)]
+pub struct Instant {
+    millis: f64,
+}
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+impl Instant {
+    pub fn now() -> Instant {
+        Instant { millis: js::now() }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().duration_since(*self)
+    }
+
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        Duration::from_secs_f64((self.millis - earlier.millis).max(0.0) / 1e3)
+    }
+}
+
+/// Time elapsed since the Unix epoch, for time stamps and seeds.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub(crate) fn since_epoch() -> Duration {
+    Duration::from_secs_f64(js_sys::Date::now() / 1e3)
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+mod js {
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen::JsCast;
+
+    #[wasm_bindgen]
+    extern "C" {
+        type Performance;
+
+        #[wasm_bindgen(method)]
+        fn now(this: &Performance) -> f64;
+    }
+
+    thread_local! {
+        // Web workers and Node.js have `performance` as well, but other environments may not.
+        static PERFORMANCE: Option<Performance> =
+            js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("performance"))
+                .ok()
+                .filter(|performance| performance.is_object())
+                .map(JsCast::unchecked_into);
+    }
+
+    pub fn now() -> f64 {
+        PERFORMANCE.with(|performance| match performance {
+            Some(performance) => performance.now(),
+            None => js_sys::Date::now(),
+        })
+    }
+}
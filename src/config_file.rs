@@ -0,0 +1,377 @@
+//! Configuration file support (`criterion.toml`).
+//!
+//! Large projects often want to configure their benchmarks outside of the code. Criterion.rs looks
+//! for a `criterion.toml` in the package directory and in the workspace directory; settings in the
+//! package's file take precedence over the workspace's. The file can set defaults for the whole
+//! run and override settings for the benchmarks whose ID matches a glob pattern:
+//!
+//! ```toml
+//! [defaults]
+//! sample-size = 50
+//! warm-up-time = 1.0      # seconds
+//! measurement-time = 3.0  # seconds
+//! noise-threshold = 0.02
+//! plotting-backend = "plotters"
+//! output-directory = "target/benchmarks" # relative to the directory containing this file
+//! per-target-output = true # see `--per-target-output`
+//!
+//! [[benchmark]]
+//! pattern = "parsing/*"
+//! sample-size = 10
+//! ```
+//!
+//! Defaults from the file replace the values configured in code, and command-line options replace
+//! both. Per-benchmark overrides are applied last, on top of any group settings.
+
+use std::path::{Path, PathBuf};
+
+use crate::benchmark::BenchmarkConfig;
+use crate::error::{Error, Result};
+use crate::PlottingBackend;
+
+const FILE_NAME: &str = "criterion.toml";
+
+/// Benchmark settings which can be given both as defaults and per benchmark.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct Settings {
+    pub sample_size: Option<usize>,
+    /// In seconds.
+    pub warm_up_time: Option<f64>,
+    /// In seconds.
+    pub measurement_time: Option<f64>,
+    pub nresamples: Option<usize>,
+    pub noise_threshold: Option<f64>,
+    pub confidence_level: Option<f64>,
+    pub significance_level: Option<f64>,
+}
+impl Settings {
+    /// Overwrites the values of `config` with the values set here.
+    pub fn apply(&self, config: &mut BenchmarkConfig) {
+        if let Some(sample_size) = self.sample_size {
+            config.sample_size = sample_size;
+        }
+        if let Some(warm_up_time) = self.warm_up_time {
+            config.warm_up_time = std::time::Duration::from_secs_f64(warm_up_time);
+        }
+        if let Some(measurement_time) = self.measurement_time {
+            config.measurement_time = std::time::Duration::from_secs_f64(measurement_time);
+        }
+        if let Some(nresamples) = self.nresamples {
+            config.nresamples = nresamples;
+        }
+        if let Some(noise_threshold) = self.noise_threshold {
+            config.noise_threshold = noise_threshold;
+        }
+        if let Some(confidence_level) = self.confidence_level {
+            config.confidence_level = confidence_level;
+        }
+        if let Some(significance_level) = self.significance_level {
+            config.significance_level = significance_level;
+        }
+    }
+
+    /// Replaces the values not set here with those set in `other`.
+    fn or(self, other: Settings) -> Settings {
+        Settings {
+            sample_size: self.sample_size.or(other.sample_size),
+            warm_up_time: self.warm_up_time.or(other.warm_up_time),
+            measurement_time: self.measurement_time.or(other.measurement_time),
+            nresamples: self.nresamples.or(other.nresamples),
+            noise_threshold: self.noise_threshold.or(other.noise_threshold),
+            confidence_level: self.confidence_level.or(other.confidence_level),
+            significance_level: self.significance_level.or(other.significance_level),
+        }
+    }
+
+    /// Checks the values against the same limits as the builder methods and command-line options.
+    fn validate(&self) -> std::result::Result<(), String> {
+        let positive_time = |time: Option<f64>| !matches!(time, Some(t) if t <= 0.0);
+        let fraction = |value: Option<f64>| !matches!(value, Some(v) if v <= 0.0 || v >= 1.0);
+        if matches!(self.sample_size, Some(n) if n < 10) {
+            return Err("sample-size must be at least 10".to_owned());
+        }
+        if !positive_time(self.warm_up_time) || !positive_time(self.measurement_time) {
+            return Err("warm-up-time and measurement-time must be positive".to_owned());
+        }
+        if self.nresamples == Some(0) {
+            return Err("nresamples must be positive".to_owned());
+        }
+        if matches!(self.noise_threshold, Some(t) if t < 0.0) {
+            return Err("noise-threshold must not be negative".to_owned());
+        }
+        if !fraction(self.confidence_level) || !fraction(self.significance_level) {
+            return Err(
+                "confidence-level and significance-level must be between 0 and 1".to_owned(),
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct Defaults {
+    #[serde(flatten)]
+    settings: Settings,
+    plotting_backend: Option<String>,
+    output_directory: Option<PathBuf>,
+    per_target_output: Option<bool>,
+}
+
+/// Settings for the benchmarks whose ID matches `pattern`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Override {
+    /// Glob pattern matched against the full benchmark ID (eg. `group/function/parameter`). `*`
+    /// matches any sequence of characters, including `/`, and `?` matches any single character.
+    pub pattern: String,
+    #[serde(flatten)]
+    pub settings: Settings,
+}
+impl Override {
+    pub fn matches(&self, id: &str) -> bool {
+        glob_matches(&self.pattern, id)
+    }
+
+    /// Parses an override given on the command line as `PATTERN:KEY=VALUE,KEY=VALUE`, using the
+    /// same keys as the `[[benchmark]]` tables, eg. `crypto/*:measurement-time=20,sample-size=200`.
+    pub fn parse_cli(arg: &str) -> std::result::Result<Override, String> {
+        let (pattern, settings) = arg
+            .rsplit_once(':')
+            .ok_or_else(|| "expected PATTERN:KEY=VALUE,...".to_owned())?;
+        let mut table = String::new();
+        for setting in settings.split(',') {
+            let (key, value) = setting
+                .split_once('=')
+                .ok_or_else(|| format!("expected KEY=VALUE, found {:?}", setting))?;
+            table.push_str(&format!("{} = {}\n", key.trim(), value.trim()));
+        }
+        let settings: Settings = toml::from_str(&table).map_err(|e| e.to_string())?;
+        settings.validate()?;
+        Ok(Override {
+            pattern: pattern.to_owned(),
+            settings,
+        })
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct File {
+    #[serde(default)]
+    defaults: Defaults,
+    #[serde(default)]
+    benchmark: Vec<Override>,
+}
+
+/// The merged contents of all configuration files which apply to this benchmark executable.
+#[derive(Debug, Default)]
+pub(crate) struct ConfigFile {
+    pub defaults: Settings,
+    pub plotting_backend: Option<PlottingBackend>,
+    pub output_directory: Option<PathBuf>,
+    pub per_target_output: Option<bool>,
+    /// In order of precedence, lowest first.
+    pub overrides: Vec<Override>,
+}
+
+impl ConfigFile {
+    /// Finds and loads the configuration files, starting from the package directory (or the
+    /// current directory if not run by Cargo) up to the workspace directory, which is recognized by
+    /// its `Cargo.lock`. Returns `None` if there are none.
+    pub fn find() -> Result<Option<ConfigFile>> {
+        let start = std::env::var_os("CARGO_MANIFEST_DIR")
+            .map(PathBuf::from)
+            .or_else(|| std::env::current_dir().ok());
+        let start = match start {
+            Some(start) => start,
+            None => return Ok(None),
+        };
+
+        let mut paths = vec![];
+        for dir in start.ancestors() {
+            let path = dir.join(FILE_NAME);
+            if path.is_file() {
+                paths.push(path);
+            }
+            if dir.join("Cargo.lock").is_file() {
+                break;
+            }
+        }
+        if paths.is_empty() {
+            return Ok(None);
+        }
+
+        // Merge the outermost file first so that the files closer to the package take precedence.
+        let mut config = ConfigFile::default();
+        for path in paths.iter().rev() {
+            config = config.merge(ConfigFile::load(path)?);
+        }
+        Ok(Some(config))
+    }
+
+    fn load(path: &Path) -> Result<ConfigFile> {
+        let contents = std::fs::read_to_string(path).map_err(|inner| Error::AccessError {
+            inner,
+            path: path.to_owned(),
+        })?;
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        ConfigFile::parse(&contents, dir).map_err(|message| Error::ConfigError {
+            path: path.to_owned(),
+            message,
+        })
+    }
+
+    /// Parses the contents of a configuration file located in `dir`.
+    fn parse(contents: &str, dir: &Path) -> std::result::Result<ConfigFile, String> {
+        let file: File = toml::from_str(contents).map_err(|e| e.to_string())?;
+
+        file.defaults.settings.validate()?;
+        for o in &file.benchmark {
+            o.settings
+                .validate()
+                .map_err(|e| format!("in benchmark override {:?}: {}", o.pattern, e))?;
+        }
+        let plotting_backend = match file.defaults.plotting_backend.as_deref() {
+            Some("gnuplot") => Some(PlottingBackend::Gnuplot),
+            Some("plotters") => Some(PlottingBackend::Plotters),
+            Some("vega") => Some(PlottingBackend::Vega),
+            Some("terminal") => Some(PlottingBackend::Terminal),
+            Some(other) => {
+                return Err(format!(
+                    "unknown plotting-backend {:?}, expected \"gnuplot\", \"plotters\", \"vega\" or \"terminal\"",
+                    other
+                ))
+            }
+            None => None,
+        };
+
+        Ok(ConfigFile {
+            defaults: file.defaults.settings,
+            plotting_backend,
+            output_directory: file.defaults.output_directory.map(|p| dir.join(p)),
+            per_target_output: file.defaults.per_target_output,
+            overrides: file.benchmark,
+        })
+    }
+
+    /// Merges `other` into this configuration; the values in `other` take precedence.
+    fn merge(mut self, other: ConfigFile) -> ConfigFile {
+        self.overrides.extend(other.overrides);
+        ConfigFile {
+            defaults: other.defaults.or(self.defaults),
+            plotting_backend: other.plotting_backend.or(self.plotting_backend),
+            output_directory: other.output_directory.or(self.output_directory),
+            per_target_output: other.per_target_output.or(self.per_target_output),
+            overrides: self.overrides,
+        }
+    }
+}
+
+/// Matches `text` against a glob `pattern` where `*` matches any sequence of characters, including
+/// `/`, and `?` matches any single character.
+pub(crate) fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // Classic backtracking wildcard match; only the position of the last `*` needs remembering.
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn glob() {
+        assert!(glob_matches("parsing/*", "parsing/json/10"));
+        assert!(glob_matches("*/json/*", "parsing/json/10"));
+        assert!(glob_matches("parsing/json/1?", "parsing/json/10"));
+        assert!(glob_matches("*", ""));
+        assert!(!glob_matches("parsing/*", "printing/json"));
+        assert!(!glob_matches("parsing/json/1?", "parsing/json/1"));
+        assert!(!glob_matches("parsing", "parsing/json"));
+    }
+
+    #[test]
+    fn parse_and_merge() {
+        let workspace = ConfigFile::parse(
+            r#"
+            [defaults]
+            sample-size = 50
+            noise-threshold = 0.05
+            output-directory = "bench-results"
+
+            [[benchmark]]
+            pattern = "parsing/*"
+            sample-size = 10
+            "#,
+            Path::new("/workspace"),
+        )
+        .unwrap();
+        let package = ConfigFile::parse(
+            r#"
+            [defaults]
+            sample-size = 20
+            plotting-backend = "plotters"
+            "#,
+            Path::new("/workspace/package"),
+        )
+        .unwrap();
+
+        let config = workspace.merge(package);
+        assert_eq!(config.defaults.sample_size, Some(20));
+        assert_eq!(config.defaults.noise_threshold, Some(0.05));
+        assert!(matches!(
+            config.plotting_backend,
+            Some(PlottingBackend::Plotters)
+        ));
+        assert_eq!(
+            config.output_directory,
+            Some(PathBuf::from("/workspace/bench-results"))
+        );
+        assert_eq!(config.overrides.len(), 1);
+        assert!(config.overrides[0].matches("parsing/json"));
+        assert_eq!(config.overrides[0].settings.sample_size, Some(10));
+    }
+
+    #[test]
+    fn parse_cli_overrides() {
+        let o = Override::parse_cli("crypto/*:measurement-time=20, sample-size=200").unwrap();
+        assert!(o.matches("crypto/aes/128"));
+        assert_eq!(o.settings.measurement_time, Some(20.0));
+        assert_eq!(o.settings.sample_size, Some(200));
+
+        assert!(Override::parse_cli("crypto/*").is_err());
+        assert!(Override::parse_cli("crypto/*:sample-size").is_err());
+        assert!(Override::parse_cli("crypto/*:sample-sise=200").is_err());
+        assert!(Override::parse_cli("crypto/*:sample-size=5").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_invalid_files() {
+        let dir = Path::new(".");
+        assert!(ConfigFile::parse("[defaults]\nsample-sise = 50", dir).is_err());
+        assert!(ConfigFile::parse("[defaults]\nsample-size = 5", dir).is_err());
+        assert!(ConfigFile::parse("[defaults]\nplotting-backend = \"svg\"", dir).is_err());
+        assert!(ConfigFile::parse("[[benchmark]]\nsample-size = 50", dir).is_err());
+    }
+}
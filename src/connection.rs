@@ -1,6 +1,7 @@
 use crate::report::BenchmarkId as InternalBenchmarkId;
 use crate::Throughput;
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::io::{Read, Write};
 use std::mem::size_of;
@@ -10,6 +11,7 @@ use std::net::TcpStream;
 pub enum MessageError {
     Deserialization(ciborium::de::Error<std::io::Error>),
     Serialization(ciborium::ser::Error<std::io::Error>),
+    Json(serde_json::Error),
     Io(std::io::Error),
 }
 impl From<ciborium::de::Error<std::io::Error>> for MessageError {
@@ -22,6 +24,11 @@ impl From<ciborium::ser::Error<std::io::Error>> for MessageError {
         MessageError::Serialization(other)
     }
 }
+impl From<serde_json::Error> for MessageError {
+    fn from(other: serde_json::Error) -> Self {
+        MessageError::Json(other)
+    }
+}
 impl From<std::io::Error> for MessageError {
     fn from(other: std::io::Error) -> Self {
         MessageError::Io(other)
@@ -40,6 +47,11 @@ impl std::fmt::Display for MessageError {
                 "Failed to serialize message to Criterion.rs benchmark:\n{}",
                 error
             ),
+            MessageError::Json(error) => write!(
+                f,
+                "Failed to encode or decode JSON message to Criterion.rs benchmark:\n{}",
+                error
+            ),
             MessageError::Io(error) => write!(
                 f,
                 "Failed to read or write message to Criterion.rs benchmark:\n{}",
@@ -53,6 +65,7 @@ impl std::error::Error for MessageError {
         match self {
             MessageError::Deserialization(err) => Some(err),
             MessageError::Serialization(err) => Some(err),
+            MessageError::Json(err) => Some(err),
             MessageError::Io(err) => Some(err),
         }
     }
@@ -69,17 +82,34 @@ const BENCHMARK_HELLO_SIZE: usize = 9 //BENCHMARK_MAGIC_NUMBER.len() // magic nu
     + size_of::<u16>() // protocol version
     + size_of::<u16>(); // protocol format
 const PROTOCOL_VERSION: u16 = 1;
-const PROTOCOL_FORMAT: u16 = 1;
+
+/// The encoding of the messages, which follow their length as a big-endian `u32`. cargo-criterion
+/// reads CBOR; other runners, eg. external report generators, can ask for JSON by setting
+/// `CARGO_CRITERION_PROTOCOL_FORMAT=json`. The format is announced in the benchmark-hello.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProtocolFormat {
+    Cbor = 1,
+    Json = 2,
+}
+impl ProtocolFormat {
+    fn from_env() -> ProtocolFormat {
+        match std::env::var("CARGO_CRITERION_PROTOCOL_FORMAT").as_deref() {
+            Ok("json") => ProtocolFormat::Json,
+            _ => ProtocolFormat::Cbor,
+        }
+    }
+}
 
 #[derive(Debug)]
 struct InnerConnection {
     socket: TcpStream,
+    format: ProtocolFormat,
     receive_buffer: Vec<u8>,
     send_buffer: Vec<u8>,
     // runner_version: [u8; 3],
 }
 impl InnerConnection {
-    pub fn new(mut socket: TcpStream) -> Result<Self, std::io::Error> {
+    pub fn new(mut socket: TcpStream, format: ProtocolFormat) -> Result<Self, std::io::Error> {
         // read the runner-hello
         let mut hello_buf = [0u8; RUNNER_HELLO_SIZE];
         socket.read_exact(&mut hello_buf)?;
@@ -105,12 +135,13 @@ impl InnerConnection {
         i += 3;
         hello_buf[i..i + 2].clone_from_slice(&PROTOCOL_VERSION.to_be_bytes());
         i += 2;
-        hello_buf[i..i + 2].clone_from_slice(&PROTOCOL_FORMAT.to_be_bytes());
+        hello_buf[i..i + 2].clone_from_slice(&(format as u16).to_be_bytes());
 
         socket.write_all(&hello_buf)?;
 
         Ok(InnerConnection {
             socket,
+            format,
             receive_buffer: vec![],
             send_buffer: vec![],
             // runner_version,
@@ -124,13 +155,19 @@ impl InnerConnection {
         let length = u32::from_be_bytes(length_buf);
         self.receive_buffer.resize(length as usize, 0u8);
         self.socket.read_exact(&mut self.receive_buffer)?;
-        let value = ciborium::de::from_reader(&self.receive_buffer[..])?;
+        let value = match self.format {
+            ProtocolFormat::Cbor => ciborium::de::from_reader(&self.receive_buffer[..])?,
+            ProtocolFormat::Json => serde_json::from_slice(&self.receive_buffer)?,
+        };
         Ok(value)
     }
 
     pub fn send(&mut self, message: &OutgoingMessage) -> Result<(), MessageError> {
         self.send_buffer.truncate(0);
-        ciborium::ser::into_writer(message, &mut self.send_buffer)?;
+        match self.format {
+            ProtocolFormat::Cbor => ciborium::ser::into_writer(message, &mut self.send_buffer)?,
+            ProtocolFormat::Json => serde_json::to_writer(&mut self.send_buffer, message)?,
+        }
         let size = u32::try_from(self.send_buffer.len()).unwrap();
         let length_buf = size.to_be_bytes();
         self.socket.write_all(&length_buf)?;
@@ -148,7 +185,7 @@ pub struct Connection {
 impl Connection {
     pub fn new(socket: TcpStream) -> Result<Self, std::io::Error> {
         Ok(Connection {
-            inner: RefCell::new(InnerConnection::new(socket)?),
+            inner: RefCell::new(InnerConnection::new(socket, ProtocolFormat::from_env())?),
         })
     }
 
@@ -241,6 +278,8 @@ pub enum IncomingMessage {
 }
 
 /// Enum defining the messages we can send
+// The messages are only built to be serialized, so the size of the largest doesn't matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Serialize)]
 pub enum OutgoingMessage<'a> {
     BeginningBenchmarkGroup {
@@ -272,6 +311,8 @@ pub enum OutgoingMessage<'a> {
         plot_config: PlotConfiguration,
         sampling_method: SamplingMethod,
         benchmark_config: BenchmarkConfig,
+        /// The labels of the run, including the detected metadata, see `Criterion::label`.
+        labels: &'a BTreeMap<String, String>,
     },
     // value formatter responses
     FormattedValue {
@@ -293,6 +334,7 @@ pub struct RawBenchmarkId {
     function_id: Option<String>,
     value_str: Option<String>,
     throughput: Vec<Throughput>,
+    tags: Vec<String>,
 }
 impl From<&InternalBenchmarkId> for RawBenchmarkId {
     fn from(other: &InternalBenchmarkId) -> RawBenchmarkId {
@@ -301,6 +343,7 @@ impl From<&InternalBenchmarkId> for RawBenchmarkId {
             function_id: other.function_id.clone(),
             value_str: other.value_str.clone(),
             throughput: other.throughput.iter().cloned().collect(),
+            tags: other.tags.clone(),
         }
     }
 }
@@ -330,6 +373,12 @@ pub struct PlotConfiguration {
     y_grid_major: bool,
     x_grid_minor: bool,
     x_grid_major: bool,
+    speedup: bool,
+    speedup_id: String,
+    relative: bool,
+    relative_id: String,
+    /// The labels of the tics, if they are formatted with `PlotConfiguration::tic_formatter`.
+    tic_labels: Option<Vec<String>>,
 }
 impl From<&crate::PlotConfiguration> for PlotConfiguration {
     fn from(other: &crate::PlotConfiguration) -> Self {
@@ -343,6 +392,11 @@ impl From<&crate::PlotConfiguration> for PlotConfiguration {
             y_grid_minor: other.y_grid_minor,
             x_grid_minor: other.x_grid_minor,
             x_grid_major: other.x_grid_major,
+            speedup: other.speedup,
+            speedup_id: other.speedup_id.clone(),
+            relative: other.relative,
+            relative_id: other.relative_id.clone(),
+            tic_labels: other.tics.iter().map(|&tic| other.tic_label(tic)).collect(),
         }
     }
 }
@@ -399,3 +453,45 @@ impl From<crate::ActualSamplingMode> for SamplingMethod {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn messages_are_sent_as_json_when_requested() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        // Plays the runner: sends its hello, then reads the benchmark's hello and one message.
+        let runner = std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            socket.write_all(RUNNER_MAGIC_NUMBER.as_bytes()).unwrap();
+            socket.write_all(&[1, 4, 0]).unwrap();
+            let mut hello = [0u8; BENCHMARK_HELLO_SIZE];
+            socket.read_exact(&mut hello).unwrap();
+            let mut length = [0u8; 4];
+            socket.read_exact(&mut length).unwrap();
+            let mut message = vec![0u8; u32::from_be_bytes(length) as usize];
+            socket.read_exact(&mut message).unwrap();
+            (hello, message)
+        });
+
+        let socket = TcpStream::connect(address).unwrap();
+        let mut connection = InnerConnection::new(socket, ProtocolFormat::Json).unwrap();
+        let mut id = InternalBenchmarkId::new("group".to_owned(), Some("f".to_owned()), None, None);
+        id.tags = vec!["fast".to_owned()];
+        connection
+            .send(&OutgoingMessage::BeginningBenchmark { id: (&id).into() })
+            .unwrap();
+
+        let (hello, message) = runner.join().unwrap();
+        assert_eq!(&hello[..BENCHMARK_MAGIC_NUMBER.len()], b"Criterion");
+        assert_eq!(&hello[BENCHMARK_HELLO_SIZE - 4..], &[0, 1, 0, 2]);
+        let message: serde_json::Value = serde_json::from_slice(&message).unwrap();
+        let id = &message["BeginningBenchmark"]["id"];
+        assert_eq!(id["group_id"], "group");
+        assert_eq!(id["function_id"], "f");
+        assert_eq!(id["tags"][0], "fast");
+    }
+}
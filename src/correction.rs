@@ -0,0 +1,178 @@
+//! Correction of the p-values of all the comparisons of a run for multiple comparisons
+//! (`Criterion::multiple_comparison_correction`, `--correction`).
+//!
+//! Each comparison is tested at its significance level, so a suite of hundreds of benchmarks is
+//! all but certain to report a few changes which are only noise. With a correction, every
+//! comparison against the baseline is recorded here and the final summary adjusts their p-values
+//! and lists the changes which remain significant and those which don't. The verdicts printed as
+//! each benchmark completes, and `--fail-fast`, still use the uncorrected p-values, since the
+//! number of comparisons is only known at the end. Since every group constructs its own
+//! `Criterion` struct, the record is a process-wide static, keyed by output directory.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::report::SignificantChange;
+use crate::Correction;
+
+struct Compared {
+    output_directory: PathBuf,
+    id: String,
+    correction: Correction,
+    p_value: f64,
+    significance_level: f64,
+    /// The direction of the change if it is larger than the noise threshold, whatever its p-value.
+    change: Option<SignificantChange>,
+}
+
+static COMPARED: Lazy<Mutex<Vec<Compared>>> = Lazy::new(|| Mutex::new(vec![]));
+
+/// Records the comparison of the benchmark with the given ID against its baseline.
+pub(crate) fn record(
+    output_directory: &Path,
+    id: &str,
+    correction: Correction,
+    p_value: f64,
+    significance_level: f64,
+    change: Option<SignificantChange>,
+) {
+    let mut compared = COMPARED.lock().unwrap();
+    // A benchmark run again in the same process replaces its previous comparison.
+    compared.retain(|c| c.output_directory != output_directory || c.id != id);
+    compared.push(Compared {
+        output_directory: output_directory.to_owned(),
+        id: id.to_owned(),
+        correction,
+        p_value,
+        significance_level,
+        change,
+    });
+}
+
+/// Adjusts the p-values of a family of comparisons for the correction. The adjusted p-values are
+/// in the same order and can be checked against the significance level as usual. NaN p-values,
+/// which can't be significant, are left out of the family and returned unadjusted.
+fn adjust(p_values: &[f64], correction: Correction) -> Vec<f64> {
+    let m = p_values.iter().filter(|p| !p.is_nan()).count() as f64;
+    match correction {
+        Correction::Bonferroni => p_values
+            .iter()
+            .map(|&p| if p.is_nan() { p } else { (p * m).min(1.0) })
+            .collect(),
+        Correction::BenjaminiHochberg => {
+            let mut order: Vec<usize> = (0..p_values.len())
+                .filter(|&i| !p_values[i].is_nan())
+                .collect();
+            order.sort_by(|&a, &b| p_values[a].partial_cmp(&p_values[b]).unwrap());
+            let mut adjusted = p_values.to_vec();
+            let mut smallest: f64 = 1.0;
+            for (rank, &i) in order.iter().enumerate().rev() {
+                smallest = smallest.min(p_values[i] * m / (rank + 1) as f64);
+                adjusted[i] = smallest;
+            }
+            adjusted
+        }
+    }
+}
+
+/// Prints the changes compared in the given output directory which remain significant after the
+/// correction and those which don't, and forgets the comparisons.
+pub(crate) fn summarize(output_directory: &Path) {
+    let mut all = COMPARED.lock().unwrap();
+    let (compared, others): (Vec<Compared>, Vec<Compared>) = all
+        .drain(..)
+        .partition(|c| c.output_directory == output_directory);
+    *all = others;
+    let correction = match compared.first() {
+        Some(first) => first.correction,
+        None => return,
+    };
+
+    let p_values: Vec<f64> = compared.iter().map(|c| c.p_value).collect();
+    let adjusted = adjust(&p_values, correction);
+    let mut kept = vec![];
+    let mut dropped = vec![];
+    for (c, &adjusted) in compared.iter().zip(adjusted.iter()) {
+        let change = match c.change {
+            Some(change) if c.p_value < c.significance_level => change,
+            _ => continue,
+        };
+        let line = format!(
+            "  {}: {} (p = {:.4}, adjusted p = {:.4})",
+            c.id,
+            match change {
+                SignificantChange::Improved => "improved",
+                SignificantChange::Regressed => "regressed",
+            },
+            c.p_value,
+            adjusted
+        );
+        if adjusted < c.significance_level {
+            kept.push(line);
+        } else {
+            dropped.push(line);
+        }
+    }
+
+    println!(
+        "{} correction of {} comparison(s): {} of {} change(s) remain significant.",
+        correction.name(),
+        compared.len(),
+        kept.len(),
+        kept.len() + dropped.len()
+    );
+    for line in kept {
+        println!("{}", line);
+    }
+    if !dropped.is_empty() {
+        println!("Not significant after the correction:");
+        for line in dropped {
+            println!("{}", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_all_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!((a - e).abs() < 1e-12, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn p_values_are_adjusted() {
+        let p_values = [0.01, 0.04, 0.03, 0.005, 0.5];
+        assert_all_close(
+            &adjust(&p_values, Correction::Bonferroni),
+            &[0.05, 0.2, 0.15, 0.025, 1.0],
+        );
+        // Sorted: 0.005, 0.01, 0.03, 0.04, 0.5 times 5 / rank, then the running minimum from the
+        // largest down.
+        assert_all_close(
+            &adjust(&p_values, Correction::BenjaminiHochberg),
+            &[0.025, 0.05, 0.05, 0.025, 0.5],
+        );
+        assert!(adjust(&[], Correction::BenjaminiHochberg).is_empty());
+    }
+
+    #[test]
+    fn nan_p_values_are_left_out() {
+        let family = [0.01, f64::NAN, 0.02];
+        for &(correction, expected) in [
+            (Correction::Bonferroni, [0.02, 0.04]),
+            (Correction::BenjaminiHochberg, [0.02, 0.02]),
+        ]
+        .iter()
+        {
+            let adjusted = adjust(&family, correction);
+            assert!(adjusted[1].is_nan());
+            assert_all_close(&[adjusted[0], adjusted[2]], &expected);
+        }
+    }
+}
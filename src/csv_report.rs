@@ -1,10 +1,13 @@
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::fs;
 use crate::measurement::ValueFormatter;
 use crate::report::{BenchmarkId, MeasurementData, Report, ReportContext};
 use crate::Throughput;
 use csv::Writer;
+use std::cell::RefCell;
+use std::fs::File;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Serialize)]
 struct CsvRow<'a> {
@@ -35,6 +38,9 @@ impl<W: Write> CsvReportWriter<W> {
         let value = id.value_str.as_deref();
         let (throughput_num, throughput_type) = match id.throughput {
             Some(Throughput::Bytes(bytes)) => (Some(format!("{}", bytes)), Some("bytes")),
+            Some(Throughput::BytesDecimal(bytes)) => {
+                (Some(format!("{}", bytes)), Some("bytes_decimal"))
+            }
             Some(Throughput::Elements(elems)) => (Some(format!("{}", elems)), Some("elements")),
             None => (None, None),
         };
@@ -88,3 +94,142 @@ impl Report for FileCsvReport {
         log_if_err!(self.write_file(&path, id, measurements, formatter));
     }
 }
+
+/// A row of `samples.csv` of `--export-csv`: one sample of one benchmark.
+#[derive(Serialize)]
+struct SampleRow<'a> {
+    id: &'a str,
+    group: &'a str,
+    function: Option<&'a str>,
+    parameter: Option<&'a str>,
+    iteration_count: u64,
+    measured_value: f64,
+    unit: &'a str,
+}
+
+/// A row of `summary.csv` of `--export-csv`: one estimate of one benchmark.
+#[derive(Serialize)]
+struct EstimateRow<'a> {
+    id: &'a str,
+    group: &'a str,
+    function: Option<&'a str>,
+    parameter: Option<&'a str>,
+    statistic: &'static str,
+    point_estimate: f64,
+    lower_bound: f64,
+    upper_bound: f64,
+    unit: &'a str,
+    /// The labels of the run, as `KEY=VALUE` separated by `;`.
+    labels: &'a str,
+}
+
+/// Writes the samples and the estimates of all the benchmarks of the run to `samples.csv` and
+/// `summary.csv` in one directory, one row per sample or estimate, for `--export-csv`.
+pub(crate) struct CsvExport {
+    dir: PathBuf,
+    /// The writers of the samples and the summary, created when the first benchmark is reported.
+    writers: RefCell<Option<(Writer<File>, Writer<File>)>>,
+}
+impl CsvExport {
+    pub(crate) fn new(dir: PathBuf) -> CsvExport {
+        CsvExport {
+            dir,
+            writers: RefCell::new(None),
+        }
+    }
+
+    fn write(
+        &self,
+        id: &BenchmarkId,
+        measurements: &MeasurementData<'_>,
+        formatter: &dyn ValueFormatter,
+    ) -> Result<()> {
+        let mut writers = self.writers.borrow_mut();
+        if writers.is_none() {
+            fs::mkdirp(&self.dir)?;
+            *writers = Some((
+                Writer::from_path(self.dir.join("samples.csv"))?,
+                Writer::from_path(self.dir.join("summary.csv"))?,
+            ));
+        }
+        let (samples, summary) = writers.as_mut().unwrap();
+
+        let title = id.as_title();
+        let group = id.group_id.as_str();
+        let function = id.function_id.as_deref();
+        let parameter = id.value_str.as_deref();
+
+        let mut values: Vec<f64> = measurements.data.y().as_ref().into();
+        let unit = formatter.scale_for_machines(&mut values);
+        for (&count, measured_value) in measurements.iter_counts().iter().zip(values) {
+            samples.serialize(SampleRow {
+                id: title,
+                group,
+                function,
+                parameter,
+                iteration_count: count as u64,
+                measured_value,
+                unit,
+            })?;
+        }
+
+        let labels = measurements
+            .manifest
+            .labels
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(";");
+        // The estimates are per iteration, in the unit `scale_for_machines` chose for them.
+        let estimates = &measurements.absolute_estimates;
+        let statistics = [
+            ("mean", Some(&estimates.mean)),
+            ("median", Some(&estimates.median)),
+            ("std_dev", Some(&estimates.std_dev)),
+            ("median_abs_dev", Some(&estimates.median_abs_dev)),
+            ("slope", estimates.slope.as_ref()),
+        ];
+        for (statistic, estimate) in statistics.iter() {
+            if let Some(estimate) = estimate {
+                let ci = &estimate.confidence_interval;
+                let mut values = [estimate.point_estimate, ci.lower_bound, ci.upper_bound];
+                let unit = formatter.scale_for_machines(&mut values);
+                summary.serialize(EstimateRow {
+                    id: title,
+                    group,
+                    function,
+                    parameter,
+                    statistic,
+                    point_estimate: values[0],
+                    lower_bound: values[1],
+                    upper_bound: values[2],
+                    unit,
+                    labels: &labels,
+                })?;
+            }
+        }
+
+        // Flushed after each benchmark, so that the files are complete if the run is stopped.
+        samples.flush().map_err(|inner| Error::AccessError {
+            path: self.dir.join("samples.csv"),
+            inner,
+        })?;
+        summary.flush().map_err(|inner| Error::AccessError {
+            path: self.dir.join("summary.csv"),
+            inner,
+        })?;
+        Ok(())
+    }
+}
+
+impl Report for CsvExport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &dyn ValueFormatter,
+    ) {
+        log_if_err!(self.write(id, measurements, formatter));
+    }
+}
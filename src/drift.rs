@@ -0,0 +1,202 @@
+//! Diagnostics of the order of the samples of a benchmark: monotonic drift and autocorrelation.
+//!
+//! The statistics assume that the samples are independent and identically distributed. Thermal
+//! throttling, memory fragmentation or a cache which slowly warms up make the times drift over the
+//! run instead, and periodic background work makes consecutive samples alike. Either way the
+//! confidence intervals are too narrow, so the analysis checks the average times of the samples in
+//! the order they were measured and warns about such runs.
+//!
+//! The drift is tested with the Mann-Kendall test, which only compares the order of the times, and
+//! its size is the difference between the medians of the last and the first third of the samples,
+//! relative to the median of all of them, so that a few outliers don't make a drift. The
+//! autocorrelation is that of consecutive samples.
+
+/// The z score beyond which a trend or an autocorrelation is reported, for a p-value of 0.001.
+const Z_THRESHOLD: f64 = 3.29;
+/// The smallest relative drift over the run which is reported.
+const MIN_DRIFT: f64 = 0.1;
+/// The smallest autocorrelation which is reported, however significant.
+const MIN_AUTOCORRELATION: f64 = 0.8;
+
+/// The order diagnostics of the samples of a benchmark.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Drift {
+    /// The average time of each sample, in the order they were measured.
+    pub series: Vec<f64>,
+    /// The trend line through the medians of the first and last thirds of the series, as its
+    /// value at the first and at the last sample.
+    pub trend: (f64, f64),
+    /// The z score of the Mann-Kendall test; positive if the times increase.
+    pub trend_z: f64,
+    /// The change of the median from the first to the last third of the series, relative to its
+    /// median.
+    pub relative_drift: f64,
+    /// The correlation of consecutive samples.
+    pub autocorrelation: f64,
+}
+impl Drift {
+    /// Diagnoses the samples, given as their average times in the order they were measured.
+    /// Returns `None` for fewer than six samples.
+    pub fn new(avg_times: &[f64]) -> Option<Drift> {
+        let n = avg_times.len();
+        if n < 6 {
+            return None;
+        }
+        let third = n / 3;
+        let first = median(&avg_times[..third]);
+        let last = median(&avg_times[n - third..]);
+        // The line through both medians, placed at the middle samples of their thirds.
+        let slope = (last - first) / (n - third) as f64;
+        let start = first - slope * (third - 1) as f64 / 2.0;
+
+        Some(Drift {
+            series: avg_times.to_vec(),
+            trend: (start, start + slope * (n - 1) as f64),
+            trend_z: mann_kendall(avg_times),
+            relative_drift: (last - first) / median(avg_times),
+            autocorrelation: autocorrelation(avg_times),
+        })
+    }
+
+    /// Whether the times drift significantly, and by at least 10%, over the run.
+    pub fn is_drifting(&self) -> bool {
+        self.trend_z.abs() > Z_THRESHOLD && self.relative_drift.abs() >= MIN_DRIFT
+    }
+
+    /// Whether consecutive samples are strongly and significantly correlated.
+    pub fn is_autocorrelated(&self) -> bool {
+        let z = self.autocorrelation * (self.series.len() as f64).sqrt();
+        z > Z_THRESHOLD && self.autocorrelation >= MIN_AUTOCORRELATION
+    }
+
+    /// Whether the samples violate the assumption of independence.
+    pub fn is_suspect(&self) -> bool {
+        self.is_drifting() || self.is_autocorrelated()
+    }
+
+    /// The warnings about the samples, without the `Warning:` prefix.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = vec![];
+        if self.is_drifting() {
+            warnings.push(format!(
+                "the times drifted {} by {:.1}% over the run (Mann-Kendall z = {:.1}), eg. due to \
+                 thermal throttling; the samples are not independent, so the confidence intervals \
+                 are too narrow.",
+                if self.relative_drift > 0.0 {
+                    "up"
+                } else {
+                    "down"
+                },
+                self.relative_drift.abs() * 100.0,
+                self.trend_z
+            ));
+        }
+        if self.is_autocorrelated() {
+            warnings.push(format!(
+                "consecutive samples are strongly correlated (autocorrelation {:.2}), eg. due to \
+                 periodic background work; the samples are not independent, so the confidence \
+                 intervals are too narrow.",
+                self.autocorrelation
+            ));
+        }
+        warnings
+    }
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let n = sorted.len();
+    (sorted[(n - 1) / 2] + sorted[n / 2]) / 2.0
+}
+
+/// The z score of the Mann-Kendall trend test of the series, with the continuity correction.
+fn mann_kendall(series: &[f64]) -> f64 {
+    let n = series.len() as f64;
+    let mut s: f64 = 0.0;
+    for (i, &earlier) in series.iter().enumerate() {
+        for &later in &series[i + 1..] {
+            if later > earlier {
+                s += 1.0;
+            } else if later < earlier {
+                s -= 1.0;
+            }
+        }
+    }
+    let variance = n * (n - 1.0) * (2.0 * n + 5.0) / 18.0;
+    if s == 0.0 {
+        0.0
+    } else {
+        (s - s.signum()) / variance.sqrt()
+    }
+}
+
+/// The lag-1 autocorrelation of the series.
+fn autocorrelation(series: &[f64]) -> f64 {
+    let mean = series.iter().sum::<f64>() / series.len() as f64;
+    let variance: f64 = series.iter().map(|x| (x - mean) * (x - mean)).sum();
+    if variance == 0.0 {
+        return 0.0;
+    }
+    let covariance: f64 = series
+        .windows(2)
+        .map(|pair| (pair[0] - mean) * (pair[1] - mean))
+        .sum();
+    covariance / variance
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A deterministic series of noise in [-1, 1] without any order.
+    fn noise(n: usize) -> Vec<f64> {
+        let mut state = 12345u64;
+        (0..n)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn independent_samples_are_not_suspect() {
+        let times: Vec<f64> = noise(100).iter().map(|x| 100.0 + x).collect();
+        let drift = Drift::new(&times).unwrap();
+        assert!(!drift.is_suspect(), "{:?}", drift.warnings());
+        assert!(drift.warnings().is_empty());
+        assert!(Drift::new(&times[..5]).is_none());
+    }
+
+    #[test]
+    fn drift_is_detected() {
+        // 20% slower by the end of the run, with a few huge outliers.
+        let times: Vec<f64> = noise(99)
+            .iter()
+            .enumerate()
+            .map(|(i, x)| 100.0 + x + i as f64 / 5.0 + if i % 10 == 3 { 1e4 } else { 0.0 })
+            .collect();
+        let drift = Drift::new(&times).unwrap();
+        assert!(drift.is_drifting());
+        assert!(drift.trend_z > Z_THRESHOLD);
+        assert!(drift.relative_drift > 0.1 && drift.relative_drift < 0.14);
+        assert!(drift.warnings()[0].starts_with("the times drifted up by"));
+    }
+
+    #[test]
+    fn autocorrelation_is_detected() {
+        // Alternating blocks of twenty fast and twenty slow samples.
+        let times: Vec<f64> = noise(100)
+            .iter()
+            .enumerate()
+            .map(|(i, x)| 100.0 + x + if (i / 20) % 2 == 0 { 0.0 } else { 5.0 })
+            .collect();
+        let drift = Drift::new(&times).unwrap();
+        assert!(!drift.is_drifting());
+        assert!(drift.is_autocorrelated());
+        assert!(drift.warnings()[0].starts_with("consecutive samples are strongly correlated"));
+    }
+}
@@ -0,0 +1,264 @@
+//! Pre-flight check of the machine's configuration (`--check-env` and `--strict-env`).
+//!
+//! Frequency scaling, turbo boost, SMT siblings, address-space randomization, background work and
+//! battery power saving all add noise to the measurements, or make them differ from one run to the
+//! next. This module inspects the settings which are readable without special privileges (currently
+//! only on Linux, through `/sys` and `/proc`) and suggests how to fix the ones which are not suited
+//! to benchmarking. The findings are recorded in the manifest of every benchmark run afterwards.
+
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+/// The result of one check.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Finding {
+    /// What was checked, eg. `CPU frequency governor`.
+    pub name: String,
+    /// The observed setting, eg. `powersave`.
+    pub value: String,
+    /// How to fix the setting, if it is not suited to benchmarking.
+    pub warning: Option<String>,
+}
+impl Finding {
+    fn ok(name: &str, value: String) -> Finding {
+        Finding {
+            name: name.to_owned(),
+            value,
+            warning: None,
+        }
+    }
+
+    fn warn(name: &str, value: String, warning: &str) -> Finding {
+        Finding {
+            name: name.to_owned(),
+            value,
+            warning: Some(warning.to_owned()),
+        }
+    }
+}
+
+static FINDINGS: Lazy<Mutex<Option<Vec<Finding>>>> = Lazy::new(|| Mutex::new(None));
+
+fn read(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|contents| contents.trim().to_owned())
+}
+
+/// Checks the frequency governors of all CPUs.
+fn governor(governors: &[String]) -> Option<Finding> {
+    const NAME: &str = "CPU frequency governor";
+    if governors.is_empty() {
+        return None;
+    }
+    let mut distinct: Vec<&str> = governors.iter().map(String::as_str).collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    let value = distinct.join(", ");
+    Some(if distinct == ["performance"] {
+        Finding::ok(NAME, value)
+    } else {
+        Finding::warn(
+            NAME,
+            value,
+            "set the governor to performance, eg. with `sudo cpupower frequency-set --governor performance`",
+        )
+    })
+}
+
+/// Checks turbo boost, given the contents of `intel_pstate/no_turbo` or of `cpufreq/boost`.
+fn turbo(no_turbo: Option<&str>, boost: Option<&str>) -> Option<Finding> {
+    const NAME: &str = "Turbo boost";
+    match (no_turbo, boost) {
+        (Some("0"), _) => Some(Finding::warn(
+            NAME,
+            "enabled".to_owned(),
+            "disable it with `echo 1 | sudo tee /sys/devices/system/cpu/intel_pstate/no_turbo`",
+        )),
+        (None, Some("1")) => Some(Finding::warn(
+            NAME,
+            "enabled".to_owned(),
+            "disable it with `echo 0 | sudo tee /sys/devices/system/cpu/cpufreq/boost`",
+        )),
+        (Some(_), _) | (None, Some(_)) => Some(Finding::ok(NAME, "disabled".to_owned())),
+        (None, None) => None,
+    }
+}
+
+/// Checks simultaneous multithreading, given the contents of `smt/active`.
+fn smt(active: &str) -> Finding {
+    const NAME: &str = "Simultaneous multithreading";
+    if active == "1" {
+        Finding::warn(
+            NAME,
+            "active".to_owned(),
+            "disable it with `echo off | sudo tee /sys/devices/system/cpu/smt/control`, or make sure nothing runs on the sibling of the benchmarking core",
+        )
+    } else {
+        Finding::ok(NAME, "inactive".to_owned())
+    }
+}
+
+/// Checks address-space layout randomization, given the contents of `randomize_va_space`.
+fn aslr(randomize_va_space: &str) -> Finding {
+    const NAME: &str = "Address-space layout randomization";
+    if randomize_va_space == "0" {
+        Finding::ok(NAME, "disabled".to_owned())
+    } else {
+        Finding::warn(
+            NAME,
+            "enabled".to_owned(),
+            "run the benchmarks with `setarch -R`, or disable it with `echo 0 | sudo tee /proc/sys/kernel/randomize_va_space`",
+        )
+    }
+}
+
+/// Checks the background load, given the contents of `/proc/loadavg`. Since no benchmark has run
+/// yet, a load average of one or more means at least one CPU's worth of other work.
+fn load(loadavg: &str) -> Option<Finding> {
+    const NAME: &str = "Background load";
+    let load: f64 = loadavg.split_whitespace().next()?.parse().ok()?;
+    let value = format!("{:.2} (1-minute load average)", load);
+    Some(if load < 1.0 {
+        Finding::ok(NAME, value)
+    } else {
+        Finding::warn(
+            NAME,
+            value,
+            "close other programs or wait for background jobs to finish",
+        )
+    })
+}
+
+/// Checks whether the machine runs on battery, given the `(type, online)` of each power supply.
+fn power(supplies: &[(String, Option<String>)]) -> Option<Finding> {
+    const NAME: &str = "Power source";
+    let has_battery = supplies.iter().any(|(kind, _)| kind == "Battery");
+    let mains: Vec<&Option<String>> = supplies
+        .iter()
+        .filter(|(kind, _)| kind == "Mains")
+        .map(|(_, online)| online)
+        .collect();
+    if !has_battery || mains.is_empty() {
+        return None;
+    }
+    Some(
+        if mains.iter().any(|online| online.as_deref() == Some("1")) {
+            Finding::ok(NAME, "AC".to_owned())
+        } else {
+            Finding::warn(
+                NAME,
+                "battery".to_owned(),
+                "plug in the charger; power saving on battery lowers and varies the CPU frequency",
+            )
+        },
+    )
+}
+
+/// Runs all checks which are supported on this machine.
+fn check() -> Vec<Finding> {
+    let cpu = Path::new("/sys/devices/system/cpu");
+    let mut findings = vec![];
+
+    let mut governors = vec![];
+    if let Ok(entries) = fs::read_dir(cpu) {
+        for entry in entries.filter_map(Result::ok) {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let is_cpu = name.len() > 3
+                && name.starts_with("cpu")
+                && name[3..].chars().all(|c| c.is_ascii_digit());
+            if is_cpu {
+                if let Some(governor) = read(&entry.path().join("cpufreq/scaling_governor")) {
+                    governors.push(governor);
+                }
+            }
+        }
+    }
+    findings.extend(governor(&governors));
+    findings.extend(turbo(
+        read(&cpu.join("intel_pstate/no_turbo")).as_deref(),
+        read(&cpu.join("cpufreq/boost")).as_deref(),
+    ));
+    findings.extend(read(&cpu.join("smt/active")).map(|active| smt(&active)));
+    findings
+        .extend(read(Path::new("/proc/sys/kernel/randomize_va_space")).map(|value| aslr(&value)));
+    findings.extend(read(Path::new("/proc/loadavg")).and_then(|loadavg| load(&loadavg)));
+
+    let mut supplies = vec![];
+    if let Ok(entries) = fs::read_dir("/sys/class/power_supply") {
+        for entry in entries.filter_map(Result::ok) {
+            if let Some(kind) = read(&entry.path().join("type")) {
+                supplies.push((kind, read(&entry.path().join("online"))));
+            }
+        }
+    }
+    findings.extend(power(&supplies));
+
+    findings
+}
+
+/// Runs the checks, unless they have already been run by an earlier group. Returns the findings if
+/// the checks were run.
+pub(crate) fn check_once() -> Option<Vec<Finding>> {
+    let mut findings = FINDINGS.lock().unwrap();
+    if findings.is_some() {
+        return None;
+    }
+    *findings = Some(check());
+    findings.clone()
+}
+
+/// Returns the findings of the checks run in this session, or an empty list if there were none.
+pub(crate) fn current() -> Vec<Finding> {
+    FINDINGS.lock().unwrap().clone().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn governor_must_be_performance() {
+        let governors =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|&name| name.to_owned()).collect() };
+        let finding = governor(&governors(&["performance", "performance"])).unwrap();
+        assert_eq!(finding.value, "performance");
+        assert!(finding.warning.is_none());
+
+        let finding = governor(&governors(&["powersave", "performance"])).unwrap();
+        assert_eq!(finding.value, "performance, powersave");
+        assert!(finding.warning.is_some());
+
+        assert!(governor(&[]).is_none());
+    }
+
+    #[test]
+    fn turbo_and_power() {
+        assert!(turbo(Some("0"), None).unwrap().warning.is_some());
+        assert!(turbo(Some("1"), Some("1")).unwrap().warning.is_none());
+        assert!(turbo(None, Some("1")).unwrap().warning.is_some());
+        assert!(turbo(None, None).is_none());
+
+        let supply =
+            |kind: &str, online: Option<&str>| (kind.to_owned(), online.map(str::to_owned));
+        let on_battery = [supply("Mains", Some("0")), supply("Battery", None)];
+        assert_eq!(power(&on_battery).unwrap().value, "battery");
+        let on_ac = [supply("Mains", Some("1")), supply("Battery", None)];
+        assert!(power(&on_ac).unwrap().warning.is_none());
+        assert!(power(&[supply("Mains", Some("1"))]).is_none());
+    }
+
+    #[test]
+    fn load_is_parsed() {
+        let finding = load("0.52 0.58 0.59 1/467 12345").unwrap();
+        assert!(finding.warning.is_none());
+        assert!(load("3.10 2.00 1.00 4/467 12345")
+            .unwrap()
+            .warning
+            .is_some());
+        assert!(load("").is_none());
+    }
+}
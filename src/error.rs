@@ -22,9 +22,16 @@ pub enum Error {
         path: PathBuf,
         inner: SerdeError,
     },
+    ConfigError {
+        path: PathBuf,
+        message: String,
+    },
     #[cfg(feature = "csv_output")]
     /// This API requires the following crate features to be activated: csv_output
     CsvError(CsvError),
+    #[cfg(feature = "sqlite")]
+    /// This API requires the following crate features to be activated: sqlite
+    SqliteError(rusqlite::Error),
 }
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -40,8 +47,13 @@ impl fmt::Display for Error {
                 "Failed to read or write file {:?} due to serialization error: {}",
                 path, inner
             ),
+            Error::ConfigError { path, message } => {
+                write!(f, "Invalid configuration file {:?}: {}", path, message)
+            }
             #[cfg(feature = "csv_output")]
             Error::CsvError(inner) => write!(f, "CSV error: {}", inner),
+            #[cfg(feature = "sqlite")]
+            Error::SqliteError(inner) => write!(f, "SQLite error: {}", inner),
         }
     }
 }
@@ -51,8 +63,11 @@ impl StdError for Error {
             Error::AccessError { .. } => "AccessError",
             Error::CopyError { .. } => "CopyError",
             Error::SerdeError { .. } => "SerdeError",
+            Error::ConfigError { .. } => "ConfigError",
             #[cfg(feature = "csv_output")]
             Error::CsvError(_) => "CsvError",
+            #[cfg(feature = "sqlite")]
+            Error::SqliteError(_) => "SqliteError",
         }
     }
 
@@ -61,8 +76,11 @@ impl StdError for Error {
             Error::AccessError { inner, .. } => Some(inner),
             Error::CopyError { inner, .. } => Some(inner),
             Error::SerdeError { inner, .. } => Some(inner),
+            Error::ConfigError { .. } => None,
             #[cfg(feature = "csv_output")]
             Error::CsvError(inner) => Some(inner),
+            #[cfg(feature = "sqlite")]
+            Error::SqliteError(inner) => Some(inner),
         }
     }
 }
@@ -74,6 +92,13 @@ impl From<CsvError> for Error {
     }
 }
 
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for Error {
+    fn from(other: rusqlite::Error) -> Error {
+        Error::SqliteError(other)
+    }
+}
+
 pub type Result<T> = ::std::result::Result<T, Error>;
 
 pub(crate) fn log_error(e: &Error) {
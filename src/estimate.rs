@@ -67,6 +67,8 @@ pub fn build_estimates(
         median_abs_dev: to_estimate(points.median_abs_dev, &distributions.median_abs_dev),
         slope: None,
         std_dev: to_estimate(points.std_dev, &distributions.std_dev),
+        minimum: None,
+        percentiles: vec![],
     }
 }
 
@@ -109,10 +111,19 @@ pub struct Estimates {
     pub median_abs_dev: Estimate,
     pub slope: Option<Estimate>,
     pub std_dev: Estimate,
+    /// The minimum iteration time, which is the typical estimate with `Criterion::minimum_time`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<Estimate>,
+    /// The percentiles of `Criterion::percentiles`, in the order they were given.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub percentiles: Vec<PercentileEstimate>,
 }
 impl Estimates {
     pub fn typical(&self) -> &Estimate {
-        self.slope.as_ref().unwrap_or(&self.mean)
+        self.minimum
+            .as_ref()
+            .or(self.slope.as_ref())
+            .unwrap_or(&self.mean)
     }
     pub fn get(&self, stat: Statistic) -> Option<&Estimate> {
         match stat {
@@ -126,16 +137,28 @@ impl Estimates {
     }
 }
 
+/// A percentile of the average times, eg. the 99th, with its bootstrapped confidence interval.
+#[derive(Clone, PartialEq, Deserialize, Serialize, Debug)]
+pub struct PercentileEstimate {
+    /// The percentile, between 0 and 100.
+    pub percentile: f64,
+    pub estimate: Estimate,
+}
+
 pub struct Distributions {
     pub mean: Distribution<f64>,
     pub median: Distribution<f64>,
     pub median_abs_dev: Distribution<f64>,
     pub slope: Option<Distribution<f64>>,
     pub std_dev: Distribution<f64>,
+    pub minimum: Option<Distribution<f64>>,
 }
 impl Distributions {
     pub fn typical(&self) -> &Distribution<f64> {
-        self.slope.as_ref().unwrap_or(&self.mean)
+        self.minimum
+            .as_ref()
+            .or(self.slope.as_ref())
+            .unwrap_or(&self.mean)
     }
     pub fn get(&self, stat: Statistic) -> Option<&Distribution<f64>> {
         match stat {
@@ -20,6 +20,19 @@ pub fn time(ns: f64) -> String {
     }
 }
 
+/// Formats a number of bytes in binary units, eg. `1.5000 KiB`.
+pub fn bytes(bytes: f64) -> String {
+    if bytes < 1024.0 {
+        format!("{} B", short(bytes))
+    } else if bytes < 1024.0 * 1024.0 {
+        format!("{} KiB", short(bytes / 1024.0))
+    } else if bytes < 1024.0 * 1024.0 * 1024.0 {
+        format!("{} MiB", short(bytes / (1024.0 * 1024.0)))
+    } else {
+        format!("{} GiB", short(bytes / (1024.0 * 1024.0 * 1024.0)))
+    }
+}
+
 pub fn short(n: f64) -> String {
     if n < 10.0 {
         format!("{:.4}", n)
@@ -34,6 +47,17 @@ pub fn short(n: f64) -> String {
     }
 }
 
+/// Formats `n` with the given number of significant digits, without using scientific notation.
+pub fn significant(n: f64, digits: usize) -> String {
+    let digits = digits.max(1) as i32;
+    if n == 0.0 || !n.is_finite() {
+        return format!("{:.*}", (digits - 1) as usize, n);
+    }
+    let magnitude = n.abs().log10().floor() as i32;
+    let decimals = (digits - 1 - magnitude).max(0) as usize;
+    format!("{:.*}", decimals, n)
+}
+
 fn signed_short(n: f64) -> String {
     let n_abs = n.abs();
 
@@ -72,6 +96,22 @@ pub fn iter_count(iterations: u64) -> String {
     }
 }
 
+pub fn eta(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 60 * 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!(
+            "{}h {:02}m {:02}s",
+            secs / 3600,
+            (secs / 60) % 60,
+            secs % 60
+        )
+    }
+}
+
 pub fn integer(n: f64) -> String {
     format!("{}", n as u64)
 }
@@ -101,4 +141,27 @@ mod test {
             float *= 2.0;
         }
     }
+
+    #[test]
+    fn significant_digits() {
+        assert_eq!("1.235", significant(1.23456, 4));
+        assert_eq!("123.5", significant(123.456, 4));
+        assert_eq!("12346", significant(12345.6, 4));
+        assert_eq!("0.001235", significant(0.00123456, 4));
+        assert_eq!("0.000", significant(0.0, 4));
+    }
+
+    #[test]
+    fn eta_units() {
+        assert_eq!("7s", eta(7.2));
+        assert_eq!("5m 02s", eta(302.0));
+        assert_eq!("2h 00m 01s", eta(7201.0));
+    }
+
+    #[test]
+    fn bytes_units() {
+        assert_eq!("64.000 B", bytes(64.0));
+        assert_eq!("1.5000 KiB", bytes(1536.0));
+        assert_eq!("2.0000 MiB", bytes(2.0 * 1024.0 * 1024.0));
+    }
 }
@@ -1,26 +1,45 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::ffi::OsStr;
 use std::fs::{self, File};
-use std::io::Read;
-use std::path::Path;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 use walkdir::{DirEntry, WalkDir};
 
 use crate::error::{Error, Result};
 use crate::report::BenchmarkId;
 
+/// Loads a JSON file. If the file does not exist but a gzipped version of it (see `gzip`) does,
+/// that is loaded instead.
 pub fn load<A, P: ?Sized>(path: &P) -> Result<A>
 where
     A: DeserializeOwned,
     P: AsRef<Path>,
 {
     let path = path.as_ref();
-    let mut f = File::open(path).map_err(|inner| Error::AccessError {
-        inner,
-        path: path.to_owned(),
-    })?;
+    let gz_path = gz_path(path);
     let mut string = String::new();
-    let _ = f.read_to_string(&mut string);
+    if !path.exists() && gz_path.exists() {
+        let f = File::open(&gz_path).map_err(|inner| Error::AccessError {
+            inner,
+            path: gz_path.clone(),
+        })?;
+        GzDecoder::new(f)
+            .read_to_string(&mut string)
+            .map_err(|inner| Error::AccessError {
+                inner,
+                path: gz_path.clone(),
+            })?;
+    } else {
+        let mut f = File::open(path).map_err(|inner| Error::AccessError {
+            inner,
+            path: path.to_owned(),
+        })?;
+        let _ = f.read_to_string(&mut string);
+    }
     let result: A = serde_json::from_str(string.as_str()).map_err(|inner| Error::SerdeError {
         inner,
         path: path.to_owned(),
@@ -85,6 +104,44 @@ where
     Ok(())
 }
 
+fn gz_path(path: &Path) -> PathBuf {
+    let mut gz_path = path.as_os_str().to_owned();
+    gz_path.push(".gz");
+    PathBuf::from(gz_path)
+}
+
+/// Compresses the given file to `<path>.gz` and removes the original.
+pub fn gzip(path: &Path) -> Result<()> {
+    let gz_path = gz_path(path);
+    let access_error = |inner, path: &Path| Error::AccessError {
+        inner,
+        path: path.to_owned(),
+    };
+
+    let mut input = File::open(path).map_err(|inner| access_error(inner, path))?;
+    let output = File::create(&gz_path).map_err(|inner| access_error(inner, &gz_path))?;
+    let mut encoder = GzEncoder::new(output, Compression::default());
+    io::copy(&mut input, &mut encoder)
+        .and_then(|_| encoder.finish())
+        .map_err(|inner| access_error(inner, &gz_path))?;
+    fs::remove_file(path).map_err(|inner| access_error(inner, path))?;
+    Ok(())
+}
+
+pub fn remove_file(path: &Path) -> Result<()> {
+    fs::remove_file(path).map_err(|inner| Error::AccessError {
+        inner,
+        path: path.to_owned(),
+    })
+}
+
+pub fn remove_dir_all(path: &Path) -> Result<()> {
+    fs::remove_dir_all(path).map_err(|inner| Error::AccessError {
+        inner,
+        path: path.to_owned(),
+    })
+}
+
 pub fn list_existing_benchmarks<P>(directory: &P) -> Result<Vec<BenchmarkId>>
 where
     P: AsRef<Path>,
@@ -0,0 +1,378 @@
+//! Heap profiling (`--heap-profile`).
+//!
+//! With `--heap-profile <ITERATIONS>`, every benchmark is run for the given number of iterations
+//! before it is measured, with every allocation counted, in the style of
+//! [dhat](https://docs.rs/dhat). The totals and peaks are saved as `new/heap.json` in the
+//! benchmark's output directory and shown in the reports. The phase is separate from the timing
+//! run, so counting does not affect the measurements.
+//!
+//! Allocations can only be counted by the global allocator, so the benchmark binary has to install
+//! [`CountingAllocator`]:
+//!
+//! ```
+//! use criterion::heap::CountingAllocator;
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: CountingAllocator = CountingAllocator::system();
+//! ```
+//!
+//! Outside of the heap-profiling phase, the allocator only adds a check of a flag to every
+//! allocation.
+//!
+//! The allocator can also be the measurement of the benchmarks, to track their allocations like
+//! their time, see [`Allocations`].
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicUsize, Ordering};
+
+use crate::measurement::{Measurement, ValueFormatter};
+use crate::routine::Routine;
+use crate::{black_box, format, Throughput};
+
+static COUNTING: AtomicBool = AtomicBool::new(false);
+static TOTAL_BLOCKS: AtomicUsize = AtomicUsize::new(0);
+static TOTAL_BYTES: AtomicUsize = AtomicUsize::new(0);
+// Memory allocated before the phase may be freed during it, so the live counts can be negative.
+static LIVE_BLOCKS: AtomicIsize = AtomicIsize::new(0);
+static LIVE_BYTES: AtomicIsize = AtomicIsize::new(0);
+static PEAK_BLOCKS: AtomicIsize = AtomicIsize::new(0);
+static PEAK_BYTES: AtomicIsize = AtomicIsize::new(0);
+static WARNED: AtomicBool = AtomicBool::new(false);
+// The allocations measured by `Allocations`, which never reset, so that they can be measured as the
+// difference between two readings.
+static MEASURING: AtomicBool = AtomicBool::new(false);
+static MEASURED_BLOCKS: AtomicUsize = AtomicUsize::new(0);
+static MEASURED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// Global allocator which forwards to another allocator (by default the system allocator) and
+/// counts the allocations made during the heap-profiling phase of `--heap-profile`. See the
+/// [module documentation](self).
+///
+/// All threads are counted, including any started by the benchmark.
+pub struct CountingAllocator<A = System> {
+    inner: A,
+}
+impl CountingAllocator<System> {
+    /// Counts the allocations made with the system allocator.
+    pub const fn system() -> CountingAllocator<System> {
+        CountingAllocator { inner: System }
+    }
+}
+impl<A> CountingAllocator<A> {
+    /// Counts the allocations made with `inner`, eg. a faster allocator than the system's.
+    pub const fn new(inner: A) -> CountingAllocator<A> {
+        CountingAllocator { inner }
+    }
+}
+
+fn record_allocation(size: usize) {
+    if MEASURING.load(Ordering::Relaxed) {
+        MEASURED_BLOCKS.fetch_add(1, Ordering::Relaxed);
+        MEASURED_BYTES.fetch_add(size, Ordering::Relaxed);
+    }
+    if !COUNTING.load(Ordering::Relaxed) {
+        return;
+    }
+    TOTAL_BLOCKS.fetch_add(1, Ordering::Relaxed);
+    TOTAL_BYTES.fetch_add(size, Ordering::Relaxed);
+    let blocks = LIVE_BLOCKS.fetch_add(1, Ordering::Relaxed) + 1;
+    let bytes = LIVE_BYTES.fetch_add(size as isize, Ordering::Relaxed) + size as isize;
+    PEAK_BLOCKS.fetch_max(blocks, Ordering::Relaxed);
+    PEAK_BYTES.fetch_max(bytes, Ordering::Relaxed);
+}
+
+/// Whether any allocation is counted, by the heap-profiling phase or by `Allocations`.
+fn counting() -> bool {
+    COUNTING.load(Ordering::Relaxed) || MEASURING.load(Ordering::Relaxed)
+}
+
+fn record_deallocation(size: usize) {
+    LIVE_BLOCKS.fetch_sub(1, Ordering::Relaxed);
+    LIVE_BYTES.fetch_sub(size as isize, Ordering::Relaxed);
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() && counting() {
+            record_allocation(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        if COUNTING.load(Ordering::Relaxed) {
+            record_deallocation(layout.size());
+        }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() && counting() {
+            record_allocation(layout.size());
+        }
+        ptr
+    }
+
+    // Counted like dhat does: as the allocation of a new block and the deallocation of the old one.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() && counting() {
+            if COUNTING.load(Ordering::Relaxed) {
+                record_deallocation(layout.size());
+            }
+            record_allocation(new_size);
+        }
+        new_ptr
+    }
+}
+
+fn reset() {
+    for counter in [&TOTAL_BLOCKS, &TOTAL_BYTES] {
+        counter.store(0, Ordering::Relaxed);
+    }
+    for counter in [&LIVE_BLOCKS, &LIVE_BYTES, &PEAK_BLOCKS, &PEAK_BYTES] {
+        counter.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Starts counting, or returns false if `CountingAllocator` is not the global allocator.
+fn start() -> bool {
+    reset();
+    COUNTING.store(true, Ordering::SeqCst);
+    drop(black_box(Box::new(0u64)));
+    if TOTAL_BLOCKS.load(Ordering::SeqCst) == 0 {
+        COUNTING.store(false, Ordering::SeqCst);
+        return false;
+    }
+    reset();
+    true
+}
+
+/// What [`Allocations`] counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Counted {
+    Blocks,
+    Bytes,
+}
+
+/// A measurement of the allocations made by each iteration of the benchmarks, instead of their
+/// time: either the number of blocks allocated or their total size in bytes. The measurements are
+/// analyzed, compared against the baseline and reported like times, so that a benchmark which
+/// suddenly allocates more is reported as a regression. Reallocations count as the allocation of a
+/// new block, like in the heap profiles; deallocations are not counted.
+///
+/// This requires [`CountingAllocator`] to be the global allocator:
+///
+/// ```no_run
+/// use criterion::heap::{Allocations, CountingAllocator};
+/// use criterion::{criterion_group, Criterion};
+///
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator::system();
+///
+/// fn bench(c: &mut Criterion<Allocations>) {
+///     c.bench_function("collect", |b| b.iter(|| (0..100u64).collect::<Vec<_>>()));
+/// }
+///
+/// criterion_group! {
+///     name = benches;
+///     config = Criterion::default().with_measurement(Allocations::bytes().unwrap());
+///     targets = bench
+/// }
+/// ```
+///
+/// The allocations of all threads are counted, so the benchmarks should not be run concurrently
+/// with `--jobs`. Only the benchmarked routine is measured: the setup of `iter_batched` and the
+/// drop of its outputs are not.
+#[derive(Debug)]
+pub struct Allocations {
+    counted: Counted,
+}
+impl Allocations {
+    /// Measures the number of blocks allocated. Returns an error if [`CountingAllocator`] is not
+    /// the global allocator.
+    pub fn blocks() -> Result<Allocations, String> {
+        Allocations::new(Counted::Blocks)
+    }
+
+    /// Measures the number of bytes allocated. Returns an error if [`CountingAllocator`] is not
+    /// the global allocator.
+    pub fn bytes() -> Result<Allocations, String> {
+        Allocations::new(Counted::Bytes)
+    }
+
+    fn new(counted: Counted) -> Result<Allocations, String> {
+        MEASURING.store(true, Ordering::SeqCst);
+        let before = MEASURED_BLOCKS.load(Ordering::SeqCst);
+        drop(black_box(Box::new(0u64)));
+        if MEASURED_BLOCKS.load(Ordering::SeqCst) == before {
+            return Err(
+                "measuring allocations requires criterion::heap::CountingAllocator as the global allocator"
+                    .to_owned(),
+            );
+        }
+        Ok(Allocations { counted })
+    }
+
+    fn read(&self) -> u64 {
+        match self.counted {
+            Counted::Blocks => MEASURED_BLOCKS.load(Ordering::SeqCst) as u64,
+            Counted::Bytes => MEASURED_BYTES.load(Ordering::SeqCst) as u64,
+        }
+    }
+}
+impl Measurement for Allocations {
+    type Intermediate = u64;
+    type Value = u64;
+
+    fn start(&self) -> Self::Intermediate {
+        self.read()
+    }
+    fn end(&self, i: Self::Intermediate) -> Self::Value {
+        self.read().saturating_sub(i)
+    }
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+    fn zero(&self) -> Self::Value {
+        0
+    }
+    fn to_f64(&self, val: &Self::Value) -> f64 {
+        *val as f64
+    }
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &self.counted
+    }
+}
+
+/// Formats the blocks without scaling them, and the bytes in binary multiples, B to GiB.
+impl ValueFormatter for Counted {
+    fn scale_values(&self, typical_value: f64, values: &mut [f64]) -> &'static str {
+        match self {
+            Counted::Blocks => "allocs",
+            Counted::Bytes => {
+                let (denominator, unit) = if typical_value < 1024.0 {
+                    (1.0, "B")
+                } else if typical_value < 1024.0 * 1024.0 {
+                    (1024.0, "KiB")
+                } else if typical_value < 1024.0 * 1024.0 * 1024.0 {
+                    (1024.0 * 1024.0, "MiB")
+                } else {
+                    (1024.0 * 1024.0 * 1024.0, "GiB")
+                };
+                for val in values {
+                    *val /= denominator;
+                }
+                unit
+            }
+        }
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        let count = match *throughput {
+            Throughput::Bytes(bytes) | Throughput::BytesDecimal(bytes) => bytes,
+            Throughput::Elements(elements) => elements,
+        };
+        for val in values {
+            *val /= count as f64;
+        }
+        match (self, throughput) {
+            (Counted::Blocks, Throughput::Elements(_)) => "allocs/elem",
+            (Counted::Blocks, _) => "allocs/B",
+            (Counted::Bytes, Throughput::Elements(_)) => "B/elem",
+            (Counted::Bytes, _) => "B/B",
+        }
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        match self {
+            Counted::Blocks => "allocs",
+            Counted::Bytes => "B",
+        }
+    }
+}
+
+/// Allocations made during the heap-profiling phase of a benchmark.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct HeapProfile {
+    pub iterations: u64,
+    /// Number and total size of the blocks allocated.
+    pub total_blocks: u64,
+    pub total_bytes: u64,
+    /// Largest number and total size of the blocks allocated during the phase which were alive at
+    /// the same time, including the few bytes used by Criterion.rs to record the measurement.
+    pub peak_blocks: u64,
+    pub peak_bytes: u64,
+}
+impl HeapProfile {
+    pub fn blocks_per_iteration(&self) -> f64 {
+        self.total_blocks as f64 / self.iterations as f64
+    }
+
+    pub fn bytes_per_iteration(&self) -> f64 {
+        self.total_bytes as f64 / self.iterations as f64
+    }
+
+    /// One-line summary, eg. `2.0000 allocs (64.000 B) per iteration, peak 1.5000 KiB in 3 blocks`.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} allocs ({}) per iteration, peak {} in {} blocks",
+            format::short(self.blocks_per_iteration()),
+            format::bytes(self.bytes_per_iteration()),
+            format::bytes(self.peak_bytes as f64),
+            self.peak_blocks
+        )
+    }
+}
+
+/// Runs the routine for `iterations` iterations while counting the allocations made. Returns
+/// `None`, with a warning the first time, if `CountingAllocator` is not the global allocator.
+pub(crate) fn profile<M: Measurement, T: ?Sized>(
+    routine: &mut dyn Routine<M, T>,
+    measurement: &M,
+    parameter: &T,
+    iterations: u64,
+) -> Option<HeapProfile> {
+    // Allocations made only by the first iteration, eg. of lazily initialized statics, are not
+    // representative of the others.
+    routine.test(measurement, parameter);
+    if !start() {
+        if !WARNED.swap(true, Ordering::Relaxed) {
+            eprintln!("Warning: --heap-profile requires criterion::heap::CountingAllocator as the global allocator; skipping heap profiling.");
+        }
+        return None;
+    }
+    // Every call of the routine makes a few allocations of its own, eg. for the measurements. They
+    // cancel out in the difference between the totals of one and of `iterations + 1` iterations.
+    routine.bench(measurement, &[1], parameter);
+    let single = counts(1);
+    reset();
+    routine.bench(measurement, &[iterations + 1], parameter);
+    let total = counts(iterations);
+    COUNTING.store(false, Ordering::SeqCst);
+
+    Some(HeapProfile {
+        total_blocks: total.total_blocks.saturating_sub(single.total_blocks),
+        total_bytes: total.total_bytes.saturating_sub(single.total_bytes),
+        ..total
+    })
+}
+
+fn counts(iterations: u64) -> HeapProfile {
+    let load = |counter: &AtomicUsize| counter.load(Ordering::SeqCst) as u64;
+    let load_peak = |counter: &AtomicIsize| counter.load(Ordering::SeqCst).max(0) as u64;
+    HeapProfile {
+        iterations,
+        total_blocks: load(&TOTAL_BLOCKS),
+        total_bytes: load(&TOTAL_BYTES),
+        peak_blocks: load_peak(&PEAK_BLOCKS),
+        peak_bytes: load_peak(&PEAK_BYTES),
+    }
+}
@@ -0,0 +1,58 @@
+//! Per-benchmark record of the typical point estimate of every run, stored in
+//! `<output directory>/<benchmark>/history.json`. Used to draw the sparkline on the HTML
+//! benchmark pages.
+
+use std::path::{Path, PathBuf};
+
+use crate::clock;
+use crate::error::Result;
+use crate::fs;
+use crate::report::BenchmarkId;
+
+/// Only this many of the most recent runs are kept so the file doesn't grow without bound.
+const MAX_ENTRIES: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct HistoryEntry {
+    /// Seconds since the Unix epoch at which the run finished.
+    pub timestamp: u64,
+    /// Typical point estimate (slope or mean) of the run, in the measurement's unit.
+    pub point_estimate: f64,
+}
+
+fn history_path(output_directory: &Path, id: &BenchmarkId) -> PathBuf {
+    let mut path = output_directory.to_owned();
+    path.push(id.as_directory_name());
+    path.push("history.json");
+    path
+}
+
+/// Loads the stored history for the given benchmark, oldest run first. Returns an empty list if
+/// there is no history yet.
+pub(crate) fn load(output_directory: &Path, id: &BenchmarkId) -> Vec<HistoryEntry> {
+    let path = history_path(output_directory, id);
+    if !path.is_file() {
+        return vec![];
+    }
+    match fs::load(&path) {
+        Ok(history) => history,
+        Err(e) => {
+            crate::error::log_error(&e);
+            vec![]
+        }
+    }
+}
+
+/// Appends the given point estimate to the benchmark's history.
+pub(crate) fn record(output_directory: &Path, id: &BenchmarkId, point_estimate: f64) -> Result<()> {
+    let mut history = load(output_directory, id);
+    let timestamp = clock::since_epoch().as_secs();
+    history.push(HistoryEntry {
+        timestamp,
+        point_estimate,
+    });
+    if history.len() > MAX_ENTRIES {
+        history.drain(..history.len() - MAX_ENTRIES);
+    }
+    fs::save(&history, &history_path(output_directory, id))
+}
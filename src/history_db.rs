@@ -0,0 +1,255 @@
+//! A database of the results of every run, in a local SQLite file, for trend plots and long-term
+//! regression hunting (`Criterion::history_database`, `--history-db`).
+//!
+//! Each benchmark adds a row to the `estimates` table as soon as it has been analyzed: its ID,
+//! the time stamp, the git commit (the `commit` label of the run, see `Criterion::label`), the
+//! unit and the estimates of the mean and median with their confidence intervals, the standard
+//! deviation and, for linear sampling, the slope. Unlike `history.json`, the rows are never
+//! dropped, and the file can be shared by several output directories or checkouts.
+//!
+//! The file can be queried with any SQLite client, or with [`HistoryDatabase`].
+//!
+//! This module requires the `sqlite` feature.
+
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+
+use crate::clock;
+use crate::error::Result;
+use crate::fs;
+use crate::measurement::ValueFormatter;
+use crate::report::{BenchmarkId, MeasurementData, Report, ReportContext};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS estimates (
+    id INTEGER PRIMARY KEY,
+    benchmark TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    commit_hash TEXT,
+    unit TEXT NOT NULL,
+    mean REAL NOT NULL,
+    mean_lower_bound REAL NOT NULL,
+    mean_upper_bound REAL NOT NULL,
+    median REAL NOT NULL,
+    median_lower_bound REAL NOT NULL,
+    median_upper_bound REAL NOT NULL,
+    std_dev REAL NOT NULL,
+    slope REAL
+);
+CREATE INDEX IF NOT EXISTS estimates_by_benchmark ON estimates (benchmark, timestamp);
+";
+
+fn open(path: &Path) -> rusqlite::Result<Connection> {
+    let connection = Connection::open(path)?;
+    connection.execute_batch(SCHEMA)?;
+    Ok(connection)
+}
+
+/// The results of a benchmark in one run, as recorded in the database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    /// Seconds since the Unix epoch at which the benchmark was analyzed.
+    pub timestamp: u64,
+    /// The git commit the run was made at, if it was known.
+    pub commit: Option<String>,
+    /// The unit of the estimates, eg. `ns` for the default `WallTime` measurement.
+    pub unit: String,
+    /// The point estimate of the mean and its confidence interval.
+    pub mean: (f64, f64, f64),
+    /// The point estimate of the median and its confidence interval.
+    pub median: (f64, f64, f64),
+    /// The point estimate of the standard deviation.
+    pub std_dev: f64,
+    /// The slope of the linear regression, for linear sampling.
+    pub slope: Option<f64>,
+}
+
+/// Queries a database written by `Criterion::history_database`.
+pub struct HistoryDatabase {
+    connection: Connection,
+}
+impl HistoryDatabase {
+    /// Opens the database at `path`, creating it if it doesn't exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> rusqlite::Result<HistoryDatabase> {
+        Ok(HistoryDatabase {
+            connection: open(path.as_ref())?,
+        })
+    }
+
+    /// The IDs of all the benchmarks in the database, in alphabetical order.
+    pub fn benchmarks(&self) -> rusqlite::Result<Vec<String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT DISTINCT benchmark FROM estimates ORDER BY benchmark")?;
+        let rows = statement.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// All the recorded runs of the benchmark with the ID `benchmark`, eg. `group/function`,
+    /// oldest first.
+    pub fn history(&self, benchmark: &str) -> rusqlite::Result<Vec<Run>> {
+        let mut statement = self.connection.prepare(
+            "SELECT timestamp, commit_hash, unit, mean, mean_lower_bound, mean_upper_bound, \
+             median, median_lower_bound, median_upper_bound, std_dev, slope \
+             FROM estimates WHERE benchmark = ?1 ORDER BY timestamp, id",
+        )?;
+        let rows = statement.query_map([benchmark], |row| {
+            Ok(Run {
+                timestamp: row.get::<_, i64>(0)? as u64,
+                commit: row.get(1)?,
+                unit: row.get(2)?,
+                mean: (row.get(3)?, row.get(4)?, row.get(5)?),
+                median: (row.get(6)?, row.get(7)?, row.get(8)?),
+                std_dev: row.get(9)?,
+                slope: row.get(10)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// The most recent run of the benchmark with the ID `benchmark` at `commit`, if there is one.
+    pub fn at_commit(&self, benchmark: &str, commit: &str) -> rusqlite::Result<Option<Run>> {
+        Ok(self
+            .history(benchmark)?
+            .into_iter()
+            .rev()
+            .find(|run| run.commit.as_deref() == Some(commit)))
+    }
+
+    /// The number of runs recorded for the benchmark with the ID `benchmark`.
+    pub fn count(&self, benchmark: &str) -> rusqlite::Result<usize> {
+        let count: i64 = self.connection.query_row(
+            "SELECT COUNT(*) FROM estimates WHERE benchmark = ?1",
+            [benchmark],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+}
+
+/// Adds the results of every benchmark to the database at `path`.
+pub(crate) struct HistoryDatabaseReport {
+    path: PathBuf,
+    /// The connection to the database, opened when the first benchmark is reported.
+    connection: RefCell<Option<Connection>>,
+}
+impl HistoryDatabaseReport {
+    pub(crate) fn new(path: PathBuf) -> HistoryDatabaseReport {
+        HistoryDatabaseReport {
+            path,
+            connection: RefCell::new(None),
+        }
+    }
+
+    fn record(
+        &self,
+        id: &BenchmarkId,
+        measurements: &MeasurementData<'_>,
+        formatter: &dyn ValueFormatter,
+    ) -> Result<()> {
+        let mut connection = self.connection.borrow_mut();
+        if connection.is_none() {
+            if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::mkdirp(&parent)?;
+            }
+            *connection = Some(open(&self.path)?);
+        }
+        let connection = connection.as_ref().unwrap();
+
+        let estimates = &measurements.absolute_estimates;
+        let mut values = [
+            estimates.mean.point_estimate,
+            estimates.mean.confidence_interval.lower_bound,
+            estimates.mean.confidence_interval.upper_bound,
+            estimates.median.point_estimate,
+            estimates.median.confidence_interval.lower_bound,
+            estimates.median.confidence_interval.upper_bound,
+            estimates.std_dev.point_estimate,
+            estimates
+                .slope
+                .as_ref()
+                .map_or(f64::NAN, |slope| slope.point_estimate),
+        ];
+        let unit = formatter.scale_for_machines(&mut values);
+        let slope = Some(values[7]).filter(|slope| !slope.is_nan());
+        connection.execute(
+            "INSERT INTO estimates (benchmark, timestamp, commit_hash, unit, mean, \
+             mean_lower_bound, mean_upper_bound, median, median_lower_bound, median_upper_bound, \
+             std_dev, slope) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                id.as_title(),
+                clock::since_epoch().as_secs() as i64,
+                measurements.manifest.labels.get("commit"),
+                unit,
+                values[0],
+                values[1],
+                values[2],
+                values[3],
+                values[4],
+                values[5],
+                values[6],
+                slope,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+impl Report for HistoryDatabaseReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &dyn ValueFormatter,
+    ) {
+        log_if_err!(self.record(id, measurements, formatter));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Criterion;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn every_run_is_recorded_with_its_commit() {
+        let dir = tempdir().unwrap();
+        let database = dir.path().join("history.sqlite");
+        for commit in ["aaaa", "bbbb"].iter() {
+            let mut c = Criterion::default()
+                .output_directory(dir.path())
+                .warm_up_time(Duration::from_millis(100))
+                .measurement_time(Duration::from_millis(200))
+                .sample_size(10)
+                .nresamples(1000)
+                .without_plots()
+                .label("commit", *commit)
+                .history_database(&database);
+            c.bench_function("sum", |b| {
+                b.iter(|| (0..crate::black_box(100u64)).sum::<u64>())
+            });
+        }
+
+        let db = HistoryDatabase::open(&database).unwrap();
+        assert_eq!(db.benchmarks().unwrap(), vec!["sum".to_owned()]);
+        assert_eq!(db.count("sum").unwrap(), 2);
+        let history = db.history("sum").unwrap();
+        assert_eq!(history[0].commit.as_deref(), Some("aaaa"));
+        assert_eq!(history[1].commit.as_deref(), Some("bbbb"));
+        assert_eq!(history[1].unit, "ns");
+        let (mean, lower, upper) = history[1].mean;
+        assert!(lower <= mean && mean <= upper);
+        assert!(history[1].slope.is_some());
+        assert_eq!(
+            db.at_commit("sum", "aaaa").unwrap(),
+            Some(history[0].clone())
+        );
+        assert_eq!(db.at_commit("sum", "cccc").unwrap(), None);
+        assert!(db.history("other").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+//! Hooks around the measured region of each batch of iterations (`Criterion::with_batch_hooks`).
+//!
+//! Profilers and tracing tools capture the whole process, including the warm-up, the setup of
+//! `iter_batched` and the analysis. Batch hooks are called right before the measurement of every
+//! batch of iterations starts and right after it ends, outside the timed region, so that such
+//! tools can mark Criterion.rs's measurement windows in their captures, eg. with a Tracy zone, a
+//! VTune ITT task or an NVTX range:
+//!
+//! ```
+//! use criterion::hooks::callbacks;
+//! use criterion::Criterion;
+//!
+//! let criterion = Criterion::default().with_batch_hooks(callbacks(
+//!     |id: &str, iters: u64| {
+//!         // eg. nvtxRangePushA(id)
+//!     },
+//!     |id: &str, iters: u64| {
+//!         // eg. nvtxRangePop()
+//!     },
+//! ));
+//! ```
+//!
+//! A batch is everything measured at once: a whole sample for `Bencher::iter`, or each batch of
+//! inputs for `Bencher::iter_batched` (each iteration with `BatchSize::PerIteration`). For
+//! `Bencher::iter_custom`, the hooks are called around the call to the routine. The hooks are only
+//! called while the samples are taken, not during the warm-up.
+//!
+//! With the `tracing` feature, [`TracingSpan`] enters a `measured_batch` span around each batch.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Called around the measured region of every batch of iterations. See the
+/// [module documentation](self).
+pub trait BatchHooks {
+    /// Called right before the measurement of a batch of `iters` iterations of the benchmark `id`
+    /// starts.
+    fn enter(&mut self, id: &str, iters: u64);
+
+    /// Called right after the measurement of the batch has ended.
+    fn exit(&mut self, id: &str, iters: u64);
+}
+
+/// Batch hooks calling a pair of closures, see [`callbacks`].
+pub struct Callbacks<E, X> {
+    enter: E,
+    exit: X,
+}
+impl<E: FnMut(&str, u64), X: FnMut(&str, u64)> BatchHooks for Callbacks<E, X> {
+    fn enter(&mut self, id: &str, iters: u64) {
+        (self.enter)(id, iters)
+    }
+
+    fn exit(&mut self, id: &str, iters: u64) {
+        (self.exit)(id, iters)
+    }
+}
+
+/// Returns batch hooks which call `enter` before and `exit` after each batch, with the ID of the
+/// benchmark and the number of iterations in the batch.
+pub fn callbacks<E, X>(enter: E, exit: X) -> Callbacks<E, X>
+where
+    E: FnMut(&str, u64),
+    X: FnMut(&str, u64),
+{
+    Callbacks { enter, exit }
+}
+
+/// Enters a `measured_batch` span, with the fields `benchmark` and `iters`, around each batch.
+#[cfg(feature = "tracing")]
+#[derive(Default)]
+pub struct TracingSpan {
+    entered: Option<tracing::span::EnteredSpan>,
+}
+#[cfg(feature = "tracing")]
+impl BatchHooks for TracingSpan {
+    fn enter(&mut self, id: &str, iters: u64) {
+        self.entered = Some(tracing::info_span!("measured_batch", benchmark = id, iters).entered());
+    }
+
+    fn exit(&mut self, _id: &str, _iters: u64) {
+        self.entered = None;
+    }
+}
+
+/// The batch hooks of a `Criterion` struct.
+pub(crate) type Hooks = Rc<RefCell<Vec<Box<dyn BatchHooks>>>>;
+
+struct Active {
+    id: String,
+    hooks: Hooks,
+}
+
+thread_local! {
+    // The timing loops don't know which benchmark they are measuring, so the hooks and the ID are
+    // installed for the thread while the samples are taken.
+    #[allow(clippy::missing_const_for_thread_local)]
+    static ACTIVE: RefCell<Option<Active>> = RefCell::new(None);
+}
+
+/// Calls the hooks around the batches measured on this thread until dropped.
+pub(crate) struct Activation(());
+impl Drop for Activation {
+    fn drop(&mut self) {
+        ACTIVE.with(|active| *active.borrow_mut() = None);
+    }
+}
+
+/// Calls `hooks` around the batches of the benchmark `id` until the returned guard is dropped.
+pub(crate) fn activate(id: &str, hooks: &Hooks) -> Option<Activation> {
+    if hooks.borrow().is_empty() {
+        return None;
+    }
+    ACTIVE.with(|active| {
+        *active.borrow_mut() = Some(Active {
+            id: id.to_owned(),
+            hooks: hooks.clone(),
+        })
+    });
+    Some(Activation(()))
+}
+
+pub(crate) fn enter(iters: u64) {
+    ACTIVE.with(|active| {
+        if let Some(active) = &*active.borrow() {
+            for hooks in active.hooks.borrow_mut().iter_mut() {
+                hooks.enter(&active.id, iters);
+            }
+        }
+    });
+}
+
+pub(crate) fn exit(iters: u64) {
+    ACTIVE.with(|active| {
+        if let Some(active) = &*active.borrow() {
+            // In reverse, so that hooks which nest, like spans, are exited in order.
+            for hooks in active.hooks.borrow_mut().iter_mut().rev() {
+                hooks.exit(&active.id, iters);
+            }
+        }
+    });
+}
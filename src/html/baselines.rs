@@ -0,0 +1,285 @@
+//! The comparison of two saved baselines (`--compare-baselines`).
+//!
+//! Every benchmark saved in both baselines gets a row in a table, with the mean of each baseline,
+//! the change of the mean and the probability densities of the two samples overlaid, so that two
+//! branches can be compared from their saved results without running anything. The densities are
+//! drawn as inline SVG, so the page doesn't depend on a plotting backend.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tinytemplate::TinyTemplate;
+
+use super::format_value;
+use crate::error::Result;
+use crate::estimate::Estimates;
+use crate::format;
+use crate::fs;
+use crate::kde;
+use crate::manifest::Manifest;
+use crate::report::{make_filename_safe, BenchmarkId};
+use crate::stats::univariate::Sample;
+use crate::SavedSample;
+
+const WIDTH: f64 = 320.0;
+const HEIGHT: f64 = 80.0;
+/// The number of points of each density curve.
+const KDE_POINTS: usize = 100;
+/// The colors of the first and the second baseline, those of the base and the new sample in the
+/// comparison plots.
+const COLORS: [&str; 2] = ["#E31A1C", "#1F78B4"];
+
+#[derive(Serialize)]
+struct Row {
+    id: String,
+    /// The path of the benchmark's page, relative to the comparison page, if it exists.
+    path: Option<String>,
+    mean_a: String,
+    mean_b: String,
+    change: String,
+    /// `slower` or `faster` if the confidence intervals of the means don't overlap.
+    change_class: &'static str,
+    densities: String,
+}
+
+#[derive(Serialize)]
+struct Context<'a> {
+    baseline_a: &'a str,
+    baseline_b: &'a str,
+    color_a: &'static str,
+    color_b: &'static str,
+    rows: Vec<Row>,
+    dark_theme: bool,
+}
+
+/// The saved results of a benchmark in one baseline.
+struct Saved {
+    estimates: Estimates,
+    manifest: Option<Manifest>,
+    /// The time of each iteration of each sample.
+    avg_times: Vec<f64>,
+}
+impl Saved {
+    fn load(directory: &Path) -> Option<Saved> {
+        let estimates = fs::load(&directory.join("estimates.json")).ok()?;
+        let sample: SavedSample = fs::load(&directory.join("sample.json")).ok()?;
+        Some(Saved {
+            estimates,
+            manifest: fs::load(&directory.join("manifest.json")).ok(),
+            avg_times: sample
+                .iters
+                .iter()
+                .zip(sample.times.iter())
+                .map(|(iters, time)| time / iters)
+                .collect(),
+        })
+    }
+
+    fn format_mean(&self) -> String {
+        let mean = &self.estimates.mean;
+        let ci = &mean.confidence_interval;
+        let manifest = self.manifest.as_ref();
+        format!(
+            "{} [{} {}]",
+            format_value(mean.point_estimate, manifest),
+            format_value(ci.lower_bound, manifest),
+            format_value(ci.upper_bound, manifest)
+        )
+    }
+}
+
+/// Draws the densities of the samples of both baselines, on the same axes, as an SVG image.
+fn densities(a: &Saved, b: &Saved) -> String {
+    let samples = [Sample::new(&a.avg_times), Sample::new(&b.avg_times)];
+    let min = samples[0].min().min(samples[1].min());
+    let max = samples[0].max().max(samples[1].max());
+    let margin = (max - min) * 0.1;
+    let range = (min - margin, max + margin);
+    let curves: Vec<_> = samples
+        .iter()
+        .map(|sample| kde::sweep(sample, kde::Bandwidth::Silverman, KDE_POINTS, Some(range)))
+        .collect();
+    let top = curves
+        .iter()
+        .flat_map(|(_, ys)| ys.iter().cloned())
+        .fold(0.0, f64::max);
+    let x = |value: f64| (value - range.0) / (range.1 - range.0) * WIDTH;
+    let y = |density: f64| HEIGHT - density / top * (HEIGHT - 2.0);
+
+    let mut svg = format!(
+        "<svg width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        WIDTH, HEIGHT, WIDTH, HEIGHT
+    );
+    for (((xs, ys), saved), color) in curves.iter().zip([a, b].iter()).zip(COLORS.iter()) {
+        let points: Vec<String> = xs
+            .iter()
+            .zip(ys.iter())
+            .map(|(&value, &density)| format!("{:.1},{:.1}", x(value), y(density)))
+            .collect();
+        write!(
+            svg,
+            "<polyline points=\"{}\" fill=\"{}\" fill-opacity=\"0.25\" stroke=\"{}\"/>",
+            points.join(" "),
+            color,
+            color
+        )
+        .unwrap();
+        let mean = x(saved.estimates.mean.point_estimate);
+        write!(
+            svg,
+            "<line x1=\"{:.1}\" y1=\"0\" x2=\"{:.1}\" y2=\"{}\" stroke=\"{}\" stroke-dasharray=\"3,2\"/>",
+            mean, mean, HEIGHT, color
+        )
+        .unwrap();
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn row(output_directory: &Path, id: &BenchmarkId, a: &Saved, b: &Saved) -> Row {
+    let mean_a = &a.estimates.mean;
+    let mean_b = &b.estimates.mean;
+    let change = mean_b.point_estimate / mean_a.point_estimate - 1.0;
+    let change_class =
+        if mean_b.confidence_interval.lower_bound > mean_a.confidence_interval.upper_bound {
+            "slower"
+        } else if mean_b.confidence_interval.upper_bound < mean_a.confidence_interval.lower_bound {
+            "faster"
+        } else {
+            ""
+        };
+    let page = Path::new(&id.as_directory_name())
+        .join("report")
+        .join("index.html");
+    Row {
+        id: id.as_title().to_owned(),
+        path: if output_directory.join(&page).is_file() {
+            Some(format!("../{}/report/index.html", id.as_directory_name()))
+        } else {
+            None
+        },
+        mean_a: a.format_mean(),
+        mean_b: b.format_mean(),
+        change: format::change(change, true).trim().to_owned(),
+        change_class,
+        densities: densities(a, b),
+    }
+}
+
+/// Writes the comparison of the baselines `baseline_a` and `baseline_b` of the benchmarks in
+/// `output_directory` to its `report` directory. Returns the path of the page and the number of
+/// benchmarks saved in both baselines.
+pub(crate) fn compare(
+    output_directory: &Path,
+    baseline_a: &str,
+    baseline_b: &str,
+    dark_theme: bool,
+) -> Result<(PathBuf, usize)> {
+    let mut ids = fs::list_existing_benchmarks(&output_directory)?;
+    ids.sort_unstable_by_key(|id| id.id().to_owned());
+    let rows: Vec<Row> = ids
+        .iter()
+        .filter_map(|id| {
+            let directory = output_directory.join(id.as_directory_name());
+            let a = Saved::load(&directory.join(baseline_a))?;
+            let b = Saved::load(&directory.join(baseline_b))?;
+            Some(row(output_directory, id, &a, &b))
+        })
+        .collect();
+    let count = rows.len();
+
+    let mut templates = TinyTemplate::new();
+    templates
+        .add_template("baselines", include_str!("baselines.html.tt"))
+        .expect("Unable to parse baselines template");
+    let context = Context {
+        baseline_a,
+        baseline_b,
+        color_a: COLORS[0],
+        color_b: COLORS[1],
+        rows,
+        dark_theme,
+    };
+    let text = templates
+        .render("baselines", &context)
+        .expect("Failed to render baselines template");
+
+    let report_directory = output_directory.join("report");
+    fs::mkdirp(&report_directory)?;
+    let path = report_directory.join(format!(
+        "{}_vs_{}.html",
+        make_filename_safe(baseline_a),
+        make_filename_safe(baseline_b)
+    ));
+    fs::save_string(&text, &path)?;
+    Ok((path, count))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::estimate::{ConfidenceInterval, Estimate};
+    use crate::ActualSamplingMode;
+    use tempfile::tempdir;
+
+    fn estimate(point_estimate: f64) -> Estimate {
+        Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: 0.95,
+                lower_bound: point_estimate * 0.99,
+                upper_bound: point_estimate * 1.01,
+            },
+            point_estimate,
+            standard_error: 0.0,
+        }
+    }
+
+    fn save_baseline(output_directory: &Path, id: &BenchmarkId, baseline: &str, time: f64) {
+        let directory = output_directory.join(id.as_directory_name()).join(baseline);
+        fs::mkdirp(&directory).unwrap();
+        let estimates = Estimates {
+            mean: estimate(time),
+            median: estimate(time),
+            median_abs_dev: estimate(0.0),
+            slope: None,
+            std_dev: estimate(time * 0.01),
+            minimum: None,
+            percentiles: vec![],
+        };
+        fs::save(&estimates, &directory.join("estimates.json")).unwrap();
+        let sample = SavedSample {
+            sampling_mode: ActualSamplingMode::Flat,
+            iters: vec![10.0; 5],
+            times: (0..5).map(|i| (time + i as f64) * 10.0).collect(),
+        };
+        fs::save(&sample, &directory.join("sample.json")).unwrap();
+    }
+
+    #[test]
+    fn benchmarks_saved_in_both_baselines_are_compared() {
+        let dir = tempdir().unwrap();
+        let both = BenchmarkId::new("group".to_owned(), Some("both".to_owned()), None, None);
+        let only_main = BenchmarkId::new("group".to_owned(), Some("main".to_owned()), None, None);
+        for id in [&both, &only_main].iter() {
+            let new = dir.path().join(id.as_directory_name()).join("new");
+            fs::mkdirp(&new).unwrap();
+            fs::save(id, &new.join("benchmark.json")).unwrap();
+            save_baseline(dir.path(), id, "main", 100.0);
+        }
+        save_baseline(dir.path(), &both, "feature/x", 150.0);
+
+        let (path, count) = compare(dir.path(), "main", "feature/x", false).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(
+            path,
+            dir.path().join("report").join("main_vs_feature_x.html")
+        );
+        let page = std::fs::read_to_string(&path).unwrap();
+        assert!(page.contains("group/both"));
+        assert!(!page.contains("group/main"));
+        assert!(page.contains("+50.000%"));
+        assert!(page.contains("class=\"number slower\""));
+        assert_eq!(page.matches("<polyline").count(), 2);
+    }
+}
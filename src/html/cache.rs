@@ -0,0 +1,98 @@
+//! Skipping summary pages whose inputs have not changed.
+//!
+//! Every benchmark group regenerates the summary pages of all its benchmarks at the end of each
+//! run, even if only a few benchmarks were run, eg. because of a filter. The summaries of the
+//! unchanged ones would come out the same, so each summary page records a hash of everything it is
+//! generated from (the samples, the page context, the plot configuration and the version of
+//! Criterion.rs) in `report/inputs.hash`, and is only regenerated when the hash changes or one of
+//! its files is missing. `--no-report-cache` regenerates every page.
+//!
+//! The pages of the individual benchmarks are only generated when the benchmark is run, which
+//! always changes their inputs.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::fs;
+
+const HASH_FILE: &str = "inputs.hash";
+
+/// Hashes the inputs of a page.
+pub(super) struct Inputs(DefaultHasher);
+impl Inputs {
+    pub fn new() -> Inputs {
+        let mut hasher = DefaultHasher::new();
+        // The templates and plots may change between versions.
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        Inputs(hasher)
+    }
+
+    pub fn add<T: Hash + ?Sized>(&mut self, value: &T) {
+        value.hash(&mut self.0);
+    }
+
+    pub fn add_values(&mut self, values: &[f64]) {
+        values.len().hash(&mut self.0);
+        for value in values {
+            value.to_bits().hash(&mut self.0);
+        }
+    }
+
+    pub fn add_serialized<S: Serialize>(&mut self, value: &S) {
+        // Serializing to JSON only fails for maps with non-string keys.
+        serde_json::to_vec(value)
+            .expect("Unable to serialize the report context")
+            .hash(&mut self.0);
+    }
+
+    pub fn finish(&self) -> String {
+        format!("{:016x}", self.0.finish())
+    }
+}
+
+/// Returns true if the page in `report_dir` was generated from inputs with the given hash and
+/// all of its `files` still exist.
+pub(super) fn is_fresh(report_dir: &Path, hash: &str, files: &[&Path]) -> bool {
+    match std::fs::read_to_string(report_dir.join(HASH_FILE)) {
+        Ok(recorded) => recorded == hash && files.iter().all(|file| file.is_file()),
+        Err(_) => false,
+    }
+}
+
+/// Records the hash of the inputs of the page just generated in `report_dir`.
+pub(super) fn record(report_dir: &Path, hash: &str) {
+    log_if_err!(fs::save_string(hash, &report_dir.join(HASH_FILE)));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn hash(values: &[f64], title: &str) -> String {
+        let mut inputs = Inputs::new();
+        inputs.add_values(values);
+        inputs.add(title);
+        inputs.finish()
+    }
+
+    #[test]
+    fn pages_are_fresh_while_their_inputs_are_unchanged() {
+        let dir = tempdir().unwrap();
+        let page = dir.path().join("index.html");
+        let hash = hash(&[1.0, 2.0], "group");
+        assert_eq!(hash, self::hash(&[1.0, 2.0], "group"));
+        assert_ne!(hash, self::hash(&[1.0, 2.5], "group"));
+        assert_ne!(hash, self::hash(&[1.0, 2.0], "other"));
+
+        assert!(!is_fresh(dir.path(), &hash, &[&page]));
+        record(dir.path(), &hash);
+        assert!(!is_fresh(dir.path(), &hash, &[&page]));
+        std::fs::write(&page, "").unwrap();
+        assert!(is_fresh(dir.path(), &hash, &[&page]));
+        assert!(!is_fresh(dir.path(), &self::hash(&[], "group"), &[&page]));
+    }
+}
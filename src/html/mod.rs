@@ -1,21 +1,36 @@
-use crate::report::{make_filename_safe, BenchmarkId, MeasurementData, Report, ReportContext};
+pub(crate) mod baselines;
+mod cache;
+
+use crate::analysis::SIGNIFICANCE_FILE;
+use crate::report::{
+    make_filename_safe, BenchmarkId, MeasurementData, Report, ReportContext, SignificantChange,
+};
 use crate::stats::bivariate::regression::Slope;
 
-use crate::estimate::Estimate;
+use crate::drift::Drift;
+use crate::estimate::{self, Estimate, Estimates};
 use crate::format;
 use crate::fs;
-use crate::measurement::ValueFormatter;
-use crate::plot::{PlotContext, PlotData, Plotter};
-use crate::SavedSample;
+use crate::heap::HeapProfile;
+use crate::history::{self, HistoryEntry};
+use crate::manifest::Manifest;
+use crate::measurement::{ValueFormatter, WallTime};
+use crate::noise_floor;
+use crate::plot::{violin_height, PlotContext, PlotData, Plotter, VEGA_SIZE};
+use crate::ranking::Ranking;
+use crate::{PlotFormat, PlotSelection, SavedSample, SummaryPlot, Theme};
 use criterion_plot::Size;
 use serde::Serialize;
 use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::collections::{BTreeSet, HashMap};
-use std::path::{Path, PathBuf};
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
 use tinytemplate::TinyTemplate;
 
 const THUMBNAIL_SIZE: Option<Size> = Some(Size(450, 300));
+const SPARKLINE_RUNS: usize = 20;
+const SPARKLINE_SIZE: Size = Size(240, 40);
 
 fn debug_context<S: Serialize>(path: &Path, context: &S) {
     if crate::debug_enabled() {
@@ -36,7 +51,24 @@ struct Context {
 
     thumbnail_width: usize,
     thumbnail_height: usize,
-
+    /// Extension of the plot files.
+    plot_ext: &'static str,
+    /// Prefix of the links to the plots, see `plot_dir`.
+    plot_dir: String,
+    /// Whether the plots are interactive pages, embedded in frames instead of images.
+    interactive: bool,
+    /// Whether the page is styled in the colors of `Theme::Dark`.
+    dark_theme: bool,
+    /// Whether the PDF and the regression or iteration times plots were drawn, see
+    /// `Criterion::plots`.
+    pdf_plot: bool,
+    regression_plot: bool,
+
+    /// The warnings of the drift diagnostics.
+    drift_warnings: Vec<String>,
+
+    /// The minimum iteration time, with `Criterion::minimum_time`.
+    minimum: Option<ConfidenceInterval>,
     slope: Option<ConfidenceInterval>,
     r2: ConfidenceInterval,
     mean: ConfidenceInterval,
@@ -44,10 +76,77 @@ struct Context {
     median: ConfidenceInterval,
     mad: ConfidenceInterval,
     throughput: Option<ConfidenceInterval>,
+    /// The percentiles of `Criterion::percentiles`, eg. `p99`.
+    percentiles: Vec<PercentileRow>,
 
     additional_plots: Vec<Plot>,
+    /// Files written by the profiler, if the benchmark was run with `--profile-time`.
+    profiles: Vec<Plot>,
+    /// Summary of the heap-profiling phase, if the benchmark was run with `--heap-profile`.
+    allocations: Vec<ManifestRow>,
 
     comparison: Option<Comparison>,
+    sparkline: Option<Sparkline>,
+    manifest: Vec<ManifestRow>,
+}
+
+/// Small line chart of the typical estimate over the last few runs. The current run is the
+/// last point.
+#[derive(Serialize)]
+struct Sparkline {
+    width: usize,
+    height: usize,
+    points: String,
+    current_x: String,
+    current_y: String,
+    runs: usize,
+    min: String,
+    max: String,
+}
+impl Sparkline {
+    fn new(history: &[HistoryEntry], formatter: &dyn ValueFormatter) -> Option<Sparkline> {
+        let history = &history[history.len().saturating_sub(SPARKLINE_RUNS)..];
+        if history.len() < 2 {
+            return None;
+        }
+
+        let values: Vec<f64> = history.iter().map(|entry| entry.point_estimate).collect();
+        let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let Size(width, height) = SPARKLINE_SIZE;
+        // Leave room for the marker on the current run.
+        let margin = 3.0;
+        let x_step = (width as f64 - 2.0 * margin) / (values.len() - 1) as f64;
+        let y_range = if max > min { max - min } else { 1.0 };
+        let coords: Vec<(f64, f64)> = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let x = margin + i as f64 * x_step;
+                let y = height as f64
+                    - margin
+                    - (value - min) / y_range * (height as f64 - 2.0 * margin);
+                (x, y)
+            })
+            .collect();
+        let points = coords
+            .iter()
+            .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let (current_x, current_y) = coords[coords.len() - 1];
+
+        Some(Sparkline {
+            width,
+            height,
+            points,
+            current_x: format!("{:.1}", current_x),
+            current_y: format!("{:.1}", current_y),
+            runs: values.len(),
+            min: formatter.format_value(min),
+            max: formatter.format_value(max),
+        })
+    }
 }
 
 #[derive(Serialize)]
@@ -58,14 +157,15 @@ struct IndividualBenchmark {
 }
 impl IndividualBenchmark {
     fn from_id(
-        output_directory: &Path,
+        plot_directory: &Path,
         path_prefix: &str,
         id: &BenchmarkId,
+        plot_format: PlotFormat,
     ) -> IndividualBenchmark {
-        let mut regression_path = PathBuf::from(output_directory);
+        let mut regression_path = PathBuf::from(plot_directory);
         regression_path.push(id.as_directory_name());
         regression_path.push("report");
-        regression_path.push("regression.svg");
+        regression_path.push(format!("regression.{}", plot_format.extension()));
 
         IndividualBenchmark {
             name: id.as_title().to_owned(),
@@ -81,13 +181,121 @@ struct SummaryContext {
 
     thumbnail_width: usize,
     thumbnail_height: usize,
+    /// Extension of the plot files.
+    plot_ext: &'static str,
+    /// Prefix of the links to the plots, see `plot_dir`.
+    plot_dir: String,
+    /// Whether the plots are interactive pages, embedded in frames instead of images.
+    interactive: bool,
+    /// Whether the page is styled in the colors of `Theme::Dark`.
+    dark_theme: bool,
+    plot_width: usize,
+    plot_height: usize,
+    violin_height: usize,
+    /// Whether the thumbnails of the benchmarks were drawn, see `Criterion::plots`.
+    pdf_plots: bool,
+    regression_plots: bool,
 
     violin_plot: Option<String>,
+    box_plot: Option<String>,
     line_chart: Option<String>,
+    /// Whether the speedup chart was drawn; the table of the speedups is always shown.
+    speedup_plot: bool,
+
+    rankings: Vec<RankingTable>,
+    /// The function the speedups are relative to, with `PlotConfiguration::speedup`.
+    speedup_reference: Option<String>,
+    speedups: Vec<SpeedupRow>,
 
     benchmarks: Vec<IndividualBenchmark>,
 }
 
+#[derive(Serialize)]
+struct SpeedupRow {
+    function_id: String,
+    speedup: String,
+    inputs: usize,
+}
+
+/// Returns the geometric mean of the speedups of each function of `data` over the function
+/// `reference`, across the inputs both were run with, and the number of those inputs.
+fn mean_speedups(data: &[&(&BenchmarkId, Vec<f64>)], reference: &str) -> Vec<(String, f64, usize)> {
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+    let reference_means: HashMap<_, _> = data
+        .iter()
+        .filter(|&&&(id, _)| id.function_id.as_deref() == Some(reference))
+        .map(|&&(id, ref values)| (&id.value_str, mean(values)))
+        .collect();
+
+    // The logarithms of the speedups of each function, in the order of the data.
+    let mut log_speedups: Vec<(&String, Vec<f64>)> = vec![];
+    for &&(id, ref values) in data {
+        let function_id = match &id.function_id {
+            Some(function_id) if function_id != reference => function_id,
+            _ => continue,
+        };
+        let reference_mean = match reference_means.get(&id.value_str) {
+            Some(&reference_mean) => reference_mean,
+            None => continue,
+        };
+        let log_speedup = (reference_mean / mean(values)).ln();
+        match log_speedups.iter_mut().find(|(f, _)| *f == function_id) {
+            Some((_, logs)) => logs.push(log_speedup),
+            None => log_speedups.push((function_id, vec![log_speedup])),
+        }
+    }
+    log_speedups
+        .into_iter()
+        .map(|(function_id, logs)| {
+            let speedup = mean(&logs).exp();
+            (function_id.clone(), speedup, logs.len())
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct RankingTable {
+    title: String,
+    rows: Vec<RankingRow>,
+}
+impl RankingTable {
+    fn new(ranking: &Ranking, formatter: &dyn ValueFormatter) -> RankingTable {
+        RankingTable {
+            title: ranking.title(),
+            rows: ranking
+                .entries
+                .iter()
+                .map(|entry| RankingRow {
+                    rank: entry.rank,
+                    function_id: entry.function_id.clone(),
+                    mean: ConfidenceInterval {
+                        lower: formatter.format_value(entry.lower_bound),
+                        point: formatter.format_value(entry.mean),
+                        upper: formatter.format_value(entry.upper_bound),
+                    },
+                    ratio: format!("{:.2}x", entry.ratio),
+                    verdict: if entry.rank == 1 {
+                        "Winner".to_owned()
+                    } else if entry.tied_with_winner {
+                        "Tied with winner".to_owned()
+                    } else {
+                        String::new()
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RankingRow {
+    rank: usize,
+    function_id: String,
+    mean: ConfidenceInterval,
+    ratio: String,
+    verdict: String,
+}
+
 #[derive(Serialize)]
 struct ConfidenceInterval {
     lower: String,
@@ -95,6 +303,12 @@ struct ConfidenceInterval {
     point: String,
 }
 
+#[derive(Serialize)]
+struct PercentileRow {
+    name: String,
+    interval: ConfidenceInterval,
+}
+
 #[derive(Serialize)]
 struct Plot {
     name: String,
@@ -107,6 +321,132 @@ impl Plot {
             url: url.to_owned(),
         }
     }
+
+    /// Links to the plot file `stem` in the given format.
+    fn image(name: &str, stem: &str, format: PlotFormat) -> Plot {
+        Plot::new(name, &format!("{}.{}", stem, format.extension()))
+    }
+}
+
+#[derive(Serialize)]
+struct ManifestRow {
+    name: String,
+    value: String,
+}
+impl ManifestRow {
+    fn new(name: &str, value: String) -> ManifestRow {
+        ManifestRow {
+            name: name.to_owned(),
+            value,
+        }
+    }
+
+    fn allocations(heap: &HeapProfile) -> Vec<ManifestRow> {
+        vec![
+            ManifestRow::new("Iterations", heap.iterations.to_string()),
+            ManifestRow::new(
+                "Allocations per iteration",
+                format::short(heap.blocks_per_iteration()),
+            ),
+            ManifestRow::new(
+                "Bytes allocated per iteration",
+                format::bytes(heap.bytes_per_iteration()),
+            ),
+            ManifestRow::new("Peak live blocks", heap.peak_blocks.to_string()),
+            ManifestRow::new("Peak live bytes", format::bytes(heap.peak_bytes as f64)),
+        ]
+    }
+
+    fn rows(manifest: &Manifest) -> Vec<ManifestRow> {
+        let config = &manifest.config;
+        let mut rows = vec![
+            ManifestRow::new("Criterion.rs version", manifest.criterion_version.clone()),
+            ManifestRow::new("Measurement", manifest.measurement.clone()),
+            ManifestRow::new("Sample size", config.sample_size.to_string()),
+            ManifestRow::new("Warm-up time", format::time(config.warm_up_time_secs * 1e9)),
+            ManifestRow::new(
+                "Measurement time",
+                format::time(config.measurement_time_secs * 1e9),
+            ),
+            ManifestRow::new(
+                "Sampling mode",
+                format!(
+                    "{} ({:?})",
+                    config.sampling_mode, config.actual_sampling_mode
+                ),
+            ),
+            ManifestRow::new("Regression method", config.regression_method.clone()),
+            ManifestRow::new("Compared estimator", config.comparison_estimator.clone()),
+            ManifestRow::new("Comparison test", config.comparison_test.clone()),
+            ManifestRow::new("Bootstrap resamples", config.nresamples.to_string()),
+            ManifestRow::new(
+                "Bootstrap seed",
+                match manifest.bootstrap_seed {
+                    Some(seed) => seed.to_string(),
+                    None => "random".to_owned(),
+                },
+            ),
+            ManifestRow::new(
+                "Confidence level",
+                format!("{:.2}", config.confidence_level),
+            ),
+            ManifestRow::new(
+                "Significance level",
+                format!("{:.2}", config.significance_level),
+            ),
+            ManifestRow::new("Noise threshold", format!("{:.2}", config.noise_threshold)),
+            ManifestRow::new("Quick mode", config.quick_mode.to_string()),
+            ManifestRow::new("Minimum time", config.minimum_time.to_string()),
+            ManifestRow::new("Outlier policy", config.outlier_policy.clone()),
+            ManifestRow::new(
+                "Shuffle seed",
+                match manifest.shuffle_seed {
+                    Some(seed) => seed.to_string(),
+                    None => "not shuffled".to_owned(),
+                },
+            ),
+            ManifestRow::new("Arguments", manifest.args.join(" ")),
+        ];
+        if let Some(count) = manifest.streamed_samples {
+            rows.push(ManifestRow::new(
+                "Streaming statistics",
+                format!(
+                    "{} of {} samples kept",
+                    count.min(crate::streaming::RESERVOIR_SIZE as u64),
+                    count
+                ),
+            ));
+        }
+        if let Some(precision) = config.target_precision {
+            rows.push(ManifestRow::new(
+                "Target precision",
+                format!(
+                    "±{:.1}% of the mean, within {:.1} s",
+                    precision * 100.0,
+                    config.max_measurement_time_secs.unwrap_or_default()
+                ),
+            ));
+        }
+        if !config.percentiles.is_empty() {
+            let percentiles: Vec<String> = config.percentiles.iter().map(f64::to_string).collect();
+            rows.push(ManifestRow::new("Percentiles", percentiles.join(", ")));
+        }
+        for (key, value) in &manifest.labels {
+            rows.push(ManifestRow::new(&format!("Label {}", key), value.clone()));
+        }
+        for finding in &manifest.environment {
+            let value = match &finding.warning {
+                Some(warning) => format!("{} (warning: {})", finding.value, warning),
+                None => finding.value.clone(),
+            };
+            rows.push(ManifestRow::new(&finding.name, value));
+        }
+        for (name, value) in &manifest.env {
+            let value = value.clone().unwrap_or_else(|| "(unset)".to_owned());
+            rows.push(ManifestRow::new(name, value));
+        }
+        rows
+    }
 }
 
 #[derive(Serialize)]
@@ -114,21 +454,142 @@ struct Comparison {
     p_value: String,
     inequality: String,
     significance_level: String,
+    noise_threshold: String,
+    statistic: String,
     explanation: String,
 
     change: ConfidenceInterval,
     thrpt_change: Option<ConfidenceInterval>,
     additional_plots: Vec<Plot>,
+    noise_floor: Option<String>,
+}
+
+/// Links to the files in the profile directory of a benchmark, relative to its report.
+fn profile_links(profile_directory: &Path) -> Vec<Plot> {
+    let mut names: Vec<String> = match std::fs::read_dir(profile_directory) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_file())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect(),
+        Err(_) => return vec![],
+    };
+    names.sort();
+    names
+        .iter()
+        .map(|name| Plot::new(name, &format!("../profile/{}", name)))
+        .collect()
 }
 
+/// Returns the link to the report in `path`, relative to the output directory, if it exists. The
+/// link always uses `/`, so that it works in browsers on every platform and wherever the output
+/// directory is moved.
 fn if_exists(output_directory: &Path, path: &Path) -> Option<String> {
-    let report_path = path.join("report/index.html");
+    let report_path = path.join("report").join("index.html");
     if PathBuf::from(output_directory).join(&report_path).is_file() {
-        Some(report_path.to_string_lossy().to_string())
+        let components: Vec<_> = report_path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect();
+        Some(components.join("/"))
     } else {
         None
     }
 }
+
+/// The prefix of the links from the report page of `id` to its plots: empty when the plots are
+/// next to the page, else the URL of the directory they were written to instead (see
+/// `Criterion::plot_output_dir`), relative to the page when both directories share a root and a
+/// `file://` URL otherwise, e.g. when they are on different drives on Windows.
+fn plot_dir(context: &ReportContext, id: &BenchmarkId) -> String {
+    if context.plot_directory == context.output_directory {
+        return String::new();
+    }
+    let page = absolute(
+        &context
+            .output_directory
+            .join(id.as_directory_name())
+            .join("report"),
+    );
+    let plots = absolute(
+        &context
+            .plot_directory
+            .join(id.as_directory_name())
+            .join("report"),
+    );
+    let page = normalized_components(&page);
+    let plots = normalized_components(&plots);
+    let common = page
+        .iter()
+        .zip(&plots)
+        .take_while(|(page, plots)| page == plots)
+        .count();
+
+    let mut url = String::new();
+    if common == 0 {
+        url.push_str("file://");
+        for component in &plots {
+            match component {
+                Component::Prefix(prefix) => {
+                    url.push('/');
+                    url.push_str(&prefix.as_os_str().to_string_lossy().replace('\\', "/"));
+                }
+                Component::RootDir => {}
+                _ => {
+                    url.push('/');
+                    url.push_str(&encode_path_segment(component.as_os_str()));
+                }
+            }
+        }
+        url.push('/');
+    } else {
+        for _ in common..page.len() {
+            url.push_str("../");
+        }
+        for component in &plots[common..] {
+            url.push_str(&encode_path_segment(component.as_os_str()));
+            url.push('/');
+        }
+    }
+    url
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::env::current_dir()
+        .map(|cwd| cwd.join(path))
+        .unwrap_or_else(|_| path.to_owned())
+}
+
+/// Resolves the `.` and `..` components of `path` without touching the file system.
+fn normalized_components(path: &Path) -> Vec<Component<'_>> {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(components.last(), Some(Component::Normal(_))) => {
+                components.pop();
+            }
+            _ => components.push(component),
+        }
+    }
+    components
+}
+
+/// Percent-encodes every byte of `segment` outside the unreserved characters of RFC 3986, so that
+/// spaces, `#` and `?` in directory names don't break the link.
+fn encode_path_segment(segment: &OsStr) -> String {
+    segment
+        .to_string_lossy()
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
 #[derive(Serialize, Debug)]
 struct ReportLink<'a> {
     name: &'a str,
@@ -174,103 +635,92 @@ impl<'a> ReportLink<'a> {
     }
 }
 
-#[derive(Serialize)]
-struct BenchmarkValueGroup<'a> {
-    value: Option<ReportLink<'a>>,
-    benchmarks: Vec<ReportLink<'a>>,
+/// Formats a value measured by the measurement of `manifest`. Only wall-clock times are known to
+/// be in nanoseconds; other measurements, or an unknown one, are shown in their own unit.
+fn format_value(value: f64, manifest: Option<&Manifest>) -> String {
+    match manifest {
+        Some(manifest) if manifest.measurement == std::any::type_name::<WallTime>() => {
+            format::time(value)
+        }
+        _ => format::short(value),
+    }
 }
 
+/// A row of the table of the index page: a benchmark with links to its page and to the summaries
+/// of its group, function and value, and its mean and change. Each value comes with a key for
+/// sorting the table, empty if the benchmark has no results.
 #[derive(Serialize)]
-struct BenchmarkGroup<'a> {
-    group_report: ReportLink<'a>,
-
-    function_ids: Option<Vec<ReportLink<'a>>>,
-    values: Option<Vec<ReportLink<'a>>>,
-
-    individual_links: Vec<BenchmarkValueGroup<'a>>,
+struct IndexRow<'a> {
+    benchmark: ReportLink<'a>,
+    group: ReportLink<'a>,
+    function: Option<ReportLink<'a>>,
+    value: Option<ReportLink<'a>>,
+    /// The tags of the benchmark, space-separated for the tag filter.
+    tags: String,
+    mean: String,
+    mean_key: String,
+    change: String,
+    change_key: String,
 }
-impl<'a> BenchmarkGroup<'a> {
-    fn new(output_directory: &Path, ids: &[&'a BenchmarkId]) -> BenchmarkGroup<'a> {
-        let group_id = &ids[0].group_id;
-        let group_report = ReportLink::group(output_directory, group_id);
-
-        let mut function_ids = Vec::with_capacity(ids.len());
-        let mut values = Vec::with_capacity(ids.len());
-        let mut individual_links = HashMap::with_capacity(ids.len());
-
-        for id in ids.iter() {
-            let function_id = id.function_id.as_deref();
-            let value = id.value_str.as_deref();
-
-            let individual_link = ReportLink::individual(output_directory, id);
-
-            function_ids.push(function_id);
-            values.push(value);
-
-            individual_links.insert((function_id, value), individual_link);
-        }
-
-        fn parse_opt(os: &Option<&str>) -> Option<f64> {
-            os.and_then(|s| s.parse::<f64>().ok())
-        }
-
-        // If all of the value strings can be parsed into a number, sort/dedupe
-        // numerically. Otherwise sort lexicographically.
-        if values.iter().all(|os| parse_opt(os).is_some()) {
-            values.sort_unstable_by(|v1, v2| {
-                let num1 = parse_opt(v1);
-                let num2 = parse_opt(v2);
-
-                num1.partial_cmp(&num2).unwrap_or(Ordering::Less)
-            });
-            values.dedup_by_key(|os| parse_opt(os).unwrap());
-        } else {
-            values.sort_unstable();
-            values.dedup();
-        }
-
-        // Sort and dedupe functions by name.
-        function_ids.sort_unstable();
-        function_ids.dedup();
-
-        let mut value_groups = Vec::with_capacity(values.len());
-        for value in values.iter() {
-            let row = function_ids
-                .iter()
-                .filter_map(|f| individual_links.remove(&(*f, *value)))
-                .collect::<Vec<_>>();
-            value_groups.push(BenchmarkValueGroup {
-                value: value.map(|s| ReportLink::value(output_directory, group_id, s)),
-                benchmarks: row,
-            });
-        }
-
-        let function_ids = function_ids
-            .into_iter()
-            .map(|os| os.map(|s| ReportLink::function(output_directory, group_id, s)))
-            .collect::<Option<Vec<_>>>();
-        let values = values
-            .into_iter()
-            .map(|os| os.map(|s| ReportLink::value(output_directory, group_id, s)))
-            .collect::<Option<Vec<_>>>();
+impl<'a> IndexRow<'a> {
+    fn new(output_directory: &Path, id: &'a BenchmarkId) -> IndexRow<'a> {
+        let directory = output_directory.join(id.as_directory_name());
+        let estimates: Option<Estimates> = fs::load(&directory.join("new/estimates.json")).ok();
+        let manifest: Option<Manifest> = fs::load(&directory.join("new/manifest.json")).ok();
+        let change: Option<estimate::ChangeEstimates> =
+            fs::load(&directory.join("change/estimates.json")).ok();
+
+        let (mean, mean_key) = match (&estimates, &manifest) {
+            (Some(estimates), Some(manifest)) => {
+                let mean = estimates.mean.point_estimate;
+                (format_value(mean, Some(manifest)), mean.to_string())
+            }
+            _ => ("-".to_owned(), String::new()),
+        };
+        let (change, change_key) = match change {
+            Some(change) => {
+                let change = change.mean.point_estimate;
+                (
+                    format::change(change, true).trim().to_owned(),
+                    change.to_string(),
+                )
+            }
+            None => ("-".to_owned(), String::new()),
+        };
 
-        BenchmarkGroup {
-            group_report,
-            function_ids,
-            values,
-            individual_links: value_groups,
+        IndexRow {
+            benchmark: ReportLink::individual(output_directory, id),
+            group: ReportLink::group(output_directory, &id.group_id),
+            function: id.function_id.as_deref().map(|function_id| {
+                ReportLink::function(output_directory, &id.group_id, function_id)
+            }),
+            value: id
+                .value_str
+                .as_deref()
+                .map(|value_str| ReportLink::value(output_directory, &id.group_id, value_str)),
+            tags: id.tags.join(" "),
+            mean,
+            mean_key,
+            change,
+            change_key,
         }
     }
 }
 
 #[derive(Serialize)]
 struct IndexContext<'a> {
-    groups: Vec<BenchmarkGroup<'a>>,
+    benchmarks: Vec<IndexRow<'a>>,
+    tags: Vec<&'a str>,
+    noise_floor: Option<String>,
+    /// Whether the page is styled in the colors of `Theme::Dark`.
+    dark_theme: bool,
 }
 
 pub struct Html {
     templates: TinyTemplate<'static>,
     plotter: RefCell<Box<dyn Plotter>>,
+    /// Rankings of the group currently being summarized, see `Report::rankings`.
+    rankings: RefCell<Vec<Ranking>>,
 }
 impl Html {
     pub(crate) fn new(plotter: Box<dyn Plotter>) -> Html {
@@ -289,7 +739,11 @@ impl Html {
             .expect("Unable to parse summary_report template");
 
         let plotter = RefCell::new(plotter);
-        Html { templates, plotter }
+        Html {
+            templates,
+            plotter,
+            rankings: RefCell::new(vec![]),
+        }
     }
 }
 impl Report for Html {
@@ -306,6 +760,12 @@ impl Report for Html {
             report_dir.push("report");
             fs::mkdirp(&report_dir)
         });
+        try_else_return!({
+            let mut plot_dir = report_context.plot_directory.clone();
+            plot_dir.push(id.as_directory_name());
+            plot_dir.push("report");
+            fs::mkdirp(&plot_dir)
+        });
 
         let typical_estimate = &measurements.absolute_estimates.typical();
 
@@ -324,15 +784,30 @@ impl Report for Html {
             self.generate_plots(id, report_context, formatter, measurements)
         }
 
-        let mut additional_plots = vec![
-            Plot::new("Typical", "typical.svg"),
-            Plot::new("Mean", "mean.svg"),
-            Plot::new("Std. Dev.", "SD.svg"),
-            Plot::new("Median", "median.svg"),
-            Plot::new("MAD", "MAD.svg"),
-        ];
-        if measurements.absolute_estimates.slope.is_some() {
-            additional_plots.push(Plot::new("Slope", "slope.svg"));
+        let plot_format = report_context.plot_format;
+        let plots = report_context.plot_config.plots;
+        let mut additional_plots = vec![];
+        if plots.distributions {
+            additional_plots.extend(vec![
+                Plot::image("Typical", "typical", plot_format),
+                Plot::image("Mean", "mean", plot_format),
+                Plot::image("Std. Dev.", "SD", plot_format),
+                Plot::image("Median", "median", plot_format),
+                Plot::image("MAD", "MAD", plot_format),
+            ]);
+            if measurements.absolute_estimates.slope.is_some() {
+                additional_plots.push(Plot::image("Slope", "slope", plot_format));
+            }
+        }
+        if plots.cdf {
+            additional_plots.push(Plot::image("CDF", "cdf", plot_format));
+        }
+        let suspect_drift = measurements
+            .drift
+            .as_ref()
+            .filter(|drift| drift.is_suspect());
+        if plots.drift && suspect_drift.is_some() {
+            additional_plots.push(Plot::image("Drift", "drift", plot_format));
         }
 
         let throughput = measurements
@@ -346,6 +821,13 @@ impl Report for Html {
                 point: formatter.format_throughput(thr, typical_estimate.point_estimate),
             });
 
+        // The R² is that of the line through the slope, or of the mean of flat samples, even if the
+        // typical estimate is the minimum.
+        let fit = measurements
+            .absolute_estimates
+            .slope
+            .as_ref()
+            .unwrap_or(&measurements.absolute_estimates.mean);
         let context = Context {
             title: id.as_title().to_owned(),
             confidence: format!(
@@ -355,7 +837,20 @@ impl Report for Html {
 
             thumbnail_width: THUMBNAIL_SIZE.unwrap().0,
             thumbnail_height: THUMBNAIL_SIZE.unwrap().1,
+            plot_ext: plot_format.extension(),
+            plot_dir: plot_dir(report_context, id),
+            interactive: plot_format == PlotFormat::Html,
+            dark_theme: report_context.plot_config.theme == Theme::Dark,
+            pdf_plot: plots.pdf,
+            regression_plot: plots.regression,
+
+            drift_warnings: suspect_drift.map(Drift::warnings).unwrap_or_default(),
 
+            minimum: measurements
+                .absolute_estimates
+                .minimum
+                .as_ref()
+                .map(time_interval),
             slope: measurements
                 .absolute_estimates
                 .slope
@@ -366,25 +861,47 @@ impl Report for Html {
             mad: time_interval(&measurements.absolute_estimates.median_abs_dev),
             std_dev: time_interval(&measurements.absolute_estimates.std_dev),
             throughput,
+            percentiles: measurements
+                .absolute_estimates
+                .percentiles
+                .iter()
+                .map(|percentile| PercentileRow {
+                    name: format!("p{}", percentile.percentile),
+                    interval: time_interval(&percentile.estimate),
+                })
+                .collect(),
 
             r2: ConfidenceInterval {
                 lower: format!(
                     "{:0.7}",
-                    Slope(typical_estimate.confidence_interval.lower_bound).r_squared(&data)
+                    Slope(fit.confidence_interval.lower_bound).r_squared(&data)
                 ),
                 upper: format!(
                     "{:0.7}",
-                    Slope(typical_estimate.confidence_interval.upper_bound).r_squared(&data)
-                ),
-                point: format!(
-                    "{:0.7}",
-                    Slope(typical_estimate.point_estimate).r_squared(&data)
+                    Slope(fit.confidence_interval.upper_bound).r_squared(&data)
                 ),
+                point: format!("{:0.7}", Slope(fit.point_estimate).r_squared(&data)),
             },
 
             additional_plots,
+            profiles: profile_links(
+                &report_context
+                    .output_directory
+                    .join(id.as_directory_name())
+                    .join("profile"),
+            ),
+            allocations: measurements
+                .heap
+                .as_ref()
+                .map(ManifestRow::allocations)
+                .unwrap_or_default(),
 
-            comparison: self.comparison(measurements),
+            comparison: self.comparison(measurements, plot_format, plots),
+            sparkline: Sparkline::new(
+                &history::load(&report_context.output_directory, id),
+                formatter,
+            ),
+            manifest: ManifestRow::rows(&measurements.manifest),
         };
 
         let mut report_path = report_context.output_directory.clone();
@@ -418,6 +935,14 @@ impl Report for Html {
         }
 
         let group_id = all_ids[0].group_id.clone();
+        let rankings = self.rankings.take();
+        let ranking_tables = |value_str: Option<&String>| -> Vec<RankingTable> {
+            rankings
+                .iter()
+                .filter(|ranking| value_str.is_none() || ranking.value_str.as_ref() == value_str)
+                .map(|ranking| RankingTable::new(ranking, formatter))
+                .collect()
+        };
 
         let data = self.load_summary_data(&context.output_directory, &all_ids);
 
@@ -467,6 +992,7 @@ impl Report for Html {
                     &*samples_with_function,
                     context,
                     formatter,
+                    vec![],
                     false,
                 );
             }
@@ -488,6 +1014,7 @@ impl Report for Html {
                     &*samples_with_value,
                     context,
                     formatter,
+                    ranking_tables(Some(value_str)),
                     false,
                 );
             }
@@ -519,9 +1046,20 @@ impl Report for Html {
             &*(all_data),
             context,
             formatter,
+            ranking_tables(None),
             true,
         );
-        self.plotter.borrow_mut().wait();
+        self.finish_plots(context);
+    }
+
+    fn rankings(
+        &self,
+        _context: &ReportContext,
+        rankings: &[Ranking],
+        _formatter: &dyn ValueFormatter,
+    ) {
+        // Rendered on the summary pages, which are generated by `summarize` right after this.
+        *self.rankings.borrow_mut() = rankings.to_vec();
     }
 
     fn final_summary(&self, report_context: &ReportContext) {
@@ -533,26 +1071,29 @@ impl Report for Html {
         let mut found_ids = try_else_return!(fs::list_existing_benchmarks(&output_directory));
         found_ids.sort_unstable_by_key(|id| id.id().to_owned());
 
-        // Group IDs by group id
-        let mut id_groups: HashMap<&str, Vec<&BenchmarkId>> = HashMap::new();
-        for id in found_ids.iter() {
-            id_groups
-                .entry(&id.group_id)
-                .or_insert_with(Vec::new)
-                .push(id);
-        }
-
-        let mut groups = id_groups
-            .into_iter()
-            .map(|(_, group)| BenchmarkGroup::new(output_directory, &group))
-            .collect::<Vec<BenchmarkGroup<'_>>>();
-        groups.sort_unstable_by_key(|g| g.group_report.name);
+        let benchmarks = found_ids
+            .iter()
+            .map(|id| IndexRow::new(output_directory, id))
+            .collect();
 
         try_else_return!(fs::mkdirp(&output_directory.join("report")));
 
         let report_path = output_directory.join("report").join("index.html");
 
-        let context = IndexContext { groups };
+        let mut tags: Vec<&str> = found_ids
+            .iter()
+            .flat_map(|id| id.tags.iter().map(String::as_str))
+            .collect();
+        tags.sort_unstable();
+        tags.dedup();
+
+        let context = IndexContext {
+            benchmarks,
+            tags,
+            noise_floor: noise_floor::current()
+                .map(|noise_floor| noise_floor::format_relative(noise_floor.relative())),
+            dark_theme: report_context.plot_config.theme == Theme::Dark,
+        };
 
         debug_context(&report_path, &context);
 
@@ -564,10 +1105,34 @@ impl Report for Html {
     }
 }
 impl Html {
-    fn comparison(&self, measurements: &MeasurementData<'_>) -> Option<Comparison> {
+    /// Whether the plotting backend can render the plots in `format`.
+    pub(crate) fn supports(&self, format: PlotFormat) -> bool {
+        self.plotter.borrow().supports(format)
+    }
+
+    /// The format of the plots if the one chosen is not supported.
+    pub(crate) fn default_format(&self) -> PlotFormat {
+        self.plotter.borrow().default_format()
+    }
+
+    /// Waits for the plots, or leaves them rendering in the background with `--defer-plots`.
+    fn finish_plots(&self, context: &ReportContext) {
+        if context.defer_plots {
+            self.plotter.borrow_mut().defer();
+        } else {
+            self.plotter.borrow_mut().wait();
+        }
+    }
+
+    fn comparison(
+        &self,
+        measurements: &MeasurementData<'_>,
+        plot_format: PlotFormat,
+        plots: PlotSelection,
+    ) -> Option<Comparison> {
         if let Some(ref comp) = measurements.comparison {
             let different_mean = comp.p_value < comp.significance_threshold;
-            let mean_est = &comp.relative_estimates.mean;
+            let mean_est = comp.change();
             let explanation_str: String;
 
             if !different_mean {
@@ -587,10 +1152,25 @@ impl Html {
                 }
             }
 
+            let mut additional_plots = vec![];
+            if plots.distributions {
+                additional_plots.push(Plot::image("Change in mean", "change/mean", plot_format));
+                additional_plots.push(Plot::image(
+                    "Change in median",
+                    "change/median",
+                    plot_format,
+                ));
+            }
+            if plots.t_test {
+                additional_plots.push(Plot::image("T-Test", "change/t-test", plot_format));
+            }
+
             let comp = Comparison {
                 p_value: format!("{:.2}", comp.p_value),
                 inequality: (if different_mean { "<" } else { ">" }).to_owned(),
                 significance_level: format!("{:.2}", comp.significance_threshold),
+                noise_threshold: format!("±{}%", format::short(comp.noise_threshold * 1e2)),
+                statistic: comp.statistic.to_string(),
                 explanation: explanation_str,
 
                 change: ConfidenceInterval {
@@ -614,11 +1194,10 @@ impl Html {
                     }
                 }),
 
-                additional_plots: vec![
-                    Plot::new("Change in mean", "change/mean.svg"),
-                    Plot::new("Change in median", "change/median.svg"),
-                    Plot::new("T-Test", "change/t-test.svg"),
-                ],
+                additional_plots,
+
+                noise_floor: noise_floor::current()
+                    .map(|noise_floor| noise_floor::format_relative(noise_floor.relative())),
             };
             Some(comp)
         } else {
@@ -633,10 +1212,11 @@ impl Html {
         formatter: &dyn ValueFormatter,
         measurements: &MeasurementData<'_>,
     ) {
+        enter_span!("plotting");
         let plot_ctx = PlotContext {
             id,
             context,
-            size: None,
+            size: context.plot_config.size,
             is_thumbnail: false,
         };
 
@@ -647,30 +1227,44 @@ impl Html {
         };
 
         let plot_ctx_small = plot_ctx.thumbnail(true).size(THUMBNAIL_SIZE);
+        let plots = context.plot_config.plots;
 
-        self.plotter.borrow_mut().pdf(plot_ctx, plot_data);
-        self.plotter.borrow_mut().pdf(plot_ctx_small, plot_data);
-        if measurements.absolute_estimates.slope.is_some() {
-            self.plotter.borrow_mut().regression(plot_ctx, plot_data);
-            self.plotter
-                .borrow_mut()
-                .regression(plot_ctx_small, plot_data);
-        } else {
-            self.plotter
-                .borrow_mut()
-                .iteration_times(plot_ctx, plot_data);
+        if plots.pdf {
+            self.plotter.borrow_mut().pdf(plot_ctx, plot_data);
+            self.plotter.borrow_mut().pdf(plot_ctx_small, plot_data);
+        }
+        if plots.regression {
+            if measurements.absolute_estimates.slope.is_some() {
+                self.plotter.borrow_mut().regression(plot_ctx, plot_data);
+                self.plotter
+                    .borrow_mut()
+                    .regression(plot_ctx_small, plot_data);
+            } else {
+                self.plotter
+                    .borrow_mut()
+                    .iteration_times(plot_ctx, plot_data);
+                self.plotter
+                    .borrow_mut()
+                    .iteration_times(plot_ctx_small, plot_data);
+            }
+        }
+
+        if plots.distributions {
             self.plotter
                 .borrow_mut()
-                .iteration_times(plot_ctx_small, plot_data);
+                .abs_distributions(plot_ctx, plot_data);
+        }
+        if plots.cdf {
+            self.plotter.borrow_mut().cdf(plot_ctx, plot_data);
+        }
+        let suspect_drift = matches!(&measurements.drift, Some(drift) if drift.is_suspect());
+        if plots.drift && suspect_drift {
+            self.plotter.borrow_mut().drift(plot_ctx, plot_data);
         }
-
-        self.plotter
-            .borrow_mut()
-            .abs_distributions(plot_ctx, plot_data);
 
         if let Some(ref comp) = measurements.comparison {
             try_else_return!({
-                let mut change_dir = context.output_directory.clone();
+                let mut change_dir = context.plot_directory.clone();
                 change_dir.push(id.as_directory_name());
                 change_dir.push("report");
                 change_dir.push("change");
@@ -678,7 +1272,7 @@ impl Html {
             });
 
             try_else_return!({
-                let mut both_dir = context.output_directory.clone();
+                let mut both_dir = context.plot_directory.clone();
                 both_dir.push(id.as_directory_name());
                 both_dir.push("report");
                 both_dir.push("both");
@@ -687,30 +1281,38 @@ impl Html {
 
             let comp_data = plot_data.comparison(comp);
 
-            self.plotter.borrow_mut().pdf(plot_ctx, comp_data);
-            self.plotter.borrow_mut().pdf(plot_ctx_small, comp_data);
-            if measurements.absolute_estimates.slope.is_some()
-                && comp.base_estimates.slope.is_some()
-            {
-                self.plotter.borrow_mut().regression(plot_ctx, comp_data);
-                self.plotter
-                    .borrow_mut()
-                    .regression(plot_ctx_small, comp_data);
-            } else {
-                self.plotter
-                    .borrow_mut()
-                    .iteration_times(plot_ctx, comp_data);
+            if plots.pdf {
+                self.plotter.borrow_mut().pdf(plot_ctx, comp_data);
+                self.plotter.borrow_mut().pdf(plot_ctx_small, comp_data);
+            }
+            if plots.regression {
+                if measurements.absolute_estimates.slope.is_some()
+                    && comp.base_estimates.slope.is_some()
+                {
+                    self.plotter.borrow_mut().regression(plot_ctx, comp_data);
+                    self.plotter
+                        .borrow_mut()
+                        .regression(plot_ctx_small, comp_data);
+                } else {
+                    self.plotter
+                        .borrow_mut()
+                        .iteration_times(plot_ctx, comp_data);
+                    self.plotter
+                        .borrow_mut()
+                        .iteration_times(plot_ctx_small, comp_data);
+                }
+            }
+            if plots.t_test {
+                self.plotter.borrow_mut().t_test(plot_ctx, comp_data);
+            }
+            if plots.distributions {
                 self.plotter
                     .borrow_mut()
-                    .iteration_times(plot_ctx_small, comp_data);
+                    .rel_distributions(plot_ctx, comp_data);
             }
-            self.plotter.borrow_mut().t_test(plot_ctx, comp_data);
-            self.plotter
-                .borrow_mut()
-                .rel_distributions(plot_ctx, comp_data);
         }
 
-        self.plotter.borrow_mut().wait();
+        self.finish_plots(context);
     }
 
     fn load_summary_data<'a>(
@@ -736,79 +1338,253 @@ impl Html {
             .collect::<Vec<_>>()
     }
 
+    /// Loads the confidence intervals of the means of the benchmarks from their estimates.
+    fn load_mean_intervals(
+        &self,
+        output_directory: &Path,
+        data: &[&(&BenchmarkId, Vec<f64>)],
+    ) -> Vec<Option<estimate::ConfidenceInterval>> {
+        data.iter()
+            .map(|&&(id, _)| {
+                let path = output_directory
+                    .join(id.as_directory_name())
+                    .join("new")
+                    .join("estimates.json");
+                fs::load::<Estimates, _>(&path)
+                    .ok()
+                    .map(|estimates| estimates.mean.confidence_interval)
+            })
+            .collect()
+    }
+
+    /// Loads the significant changes of the benchmarks from the baseline, recorded by their last
+    /// comparison.
+    fn load_changes(
+        &self,
+        output_directory: &Path,
+        data: &[&(&BenchmarkId, Vec<f64>)],
+    ) -> Vec<Option<SignificantChange>> {
+        data.iter()
+            .map(|&&(id, _)| {
+                let path = output_directory
+                    .join(id.as_directory_name())
+                    .join("change")
+                    .join(SIGNIFICANCE_FILE);
+                fs::load::<Option<SignificantChange>, _>(&path)
+                    .ok()
+                    .flatten()
+            })
+            .collect()
+    }
+
     fn generate_summary(
         &self,
         id: &BenchmarkId,
         data: &[&(&BenchmarkId, Vec<f64>)],
         report_context: &ReportContext,
         formatter: &dyn ValueFormatter,
+        rankings: Vec<RankingTable>,
         full_summary: bool,
     ) {
+        enter_span!("summary_plots", id = %id);
         let plot_ctx = PlotContext {
             id,
             context: report_context,
-            size: None,
+            size: report_context.plot_config.size,
             is_thumbnail: false,
         };
 
+        let report_dir = report_context
+            .output_directory
+            .join(id.as_directory_name())
+            .join("report");
+        try_else_return!(fs::mkdirp(&report_dir), || {});
         try_else_return!(
-            {
-                let mut report_dir = report_context.output_directory.clone();
-                report_dir.push(id.as_directory_name());
-                report_dir.push("report");
-                fs::mkdirp(&report_dir)
-            },
+            fs::mkdirp(
+                &report_context
+                    .plot_directory
+                    .join(id.as_directory_name())
+                    .join("report")
+            ),
             || {}
         );
 
-        self.plotter.borrow_mut().violin(plot_ctx, formatter, data);
-
         let value_types: Vec<_> = data.iter().map(|&&(id, _)| id.value_type()).collect();
-        let mut line_path = None;
+        let mut line_chart = None;
 
         if value_types.iter().all(|x| x == &value_types[0]) {
             if let Some(value_type) = value_types[0] {
                 let values: Vec<_> = data.iter().map(|&&(id, _)| id.as_number()).collect();
                 if values.iter().any(|x| x != &values[0]) {
-                    self.plotter
-                        .borrow_mut()
-                        .line_comparison(plot_ctx, formatter, data, value_type);
-                    line_path = Some(plot_ctx.line_comparison_path());
+                    line_chart = Some(value_type);
                 }
             }
         }
+        let plots = report_context.plot_config.plots;
+        let line_path = line_chart
+            .filter(|_| plots.line_comparison)
+            .map(|_| plot_ctx.line_comparison_path());
+        let (mean_intervals, changes) = if line_chart.is_some() {
+            (
+                self.load_mean_intervals(&report_context.output_directory, data),
+                self.load_changes(&report_context.output_directory, data),
+            )
+        } else {
+            (vec![], vec![])
+        };
+
+        let speedups = if report_context.plot_config.speedup && line_chart.is_some() {
+            mean_speedups(data, &report_context.plot_config.speedup_id)
+        } else {
+            vec![]
+        };
 
         let path_prefix = if full_summary { "../.." } else { "../../.." };
         let benchmarks = data
             .iter()
             .map(|&&(id, _)| {
-                IndividualBenchmark::from_id(&report_context.output_directory, path_prefix, id)
+                IndividualBenchmark::from_id(
+                    &report_context.plot_directory,
+                    path_prefix,
+                    id,
+                    report_context.plot_format,
+                )
             })
             .collect();
 
+        let summary_plot = report_context.plot_config.summary_plot;
+        let summary_plot_path = match summary_plot {
+            SummaryPlot::Violin => plot_ctx.violin_path(),
+            SummaryPlot::BoxPlot => plot_ctx.box_plot_path(),
+        };
+        let summary_plot_name = |plot| {
+            if plots.summary && plot == summary_plot {
+                Some(summary_plot_path.to_string_lossy().into_owned())
+            } else {
+                None
+            }
+        };
+        let (plot_width, plot_height) = report_context.plot_config.size.unwrap_or(VEGA_SIZE);
         let context = SummaryContext {
             group_id: id.as_title().to_owned(),
 
             thumbnail_width: THUMBNAIL_SIZE.unwrap().0,
             thumbnail_height: THUMBNAIL_SIZE.unwrap().1,
-
-            violin_plot: Some(plot_ctx.violin_path().to_string_lossy().into_owned()),
-            line_chart: line_path.map(|p| p.to_string_lossy().into_owned()),
+            plot_ext: report_context.plot_format.extension(),
+            plot_dir: plot_dir(report_context, id),
+            interactive: report_context.plot_format == PlotFormat::Html,
+            dark_theme: report_context.plot_config.theme == Theme::Dark,
+            plot_width,
+            plot_height,
+            violin_height: violin_height(report_context.plot_config.size, data.len()),
+            pdf_plots: plots.pdf,
+            regression_plots: plots.regression,
+
+            violin_plot: summary_plot_name(SummaryPlot::Violin),
+            box_plot: summary_plot_name(SummaryPlot::BoxPlot),
+            line_chart: line_path.as_ref().map(|p| p.to_string_lossy().into_owned()),
+            speedup_plot: plots.line_comparison,
+
+            rankings,
+            speedup_reference: if speedups.is_empty() {
+                None
+            } else {
+                Some(report_context.plot_config.speedup_id.clone())
+            },
+            speedups: speedups
+                .iter()
+                .map(|(function_id, speedup, inputs)| SpeedupRow {
+                    function_id: function_id.clone(),
+                    speedup: format!("{:.2}x", speedup),
+                    inputs: *inputs,
+                })
+                .collect(),
 
             benchmarks,
         };
 
-        let mut report_path = report_context.output_directory.clone();
-        report_path.push(id.as_directory_name());
-        report_path.push("report");
-        report_path.push("index.html");
+        let report_path = report_dir.join("index.html");
         debug_context(&report_path, &context);
 
+        let mut inputs = cache::Inputs::new();
+        inputs.add_serialized(&context);
+        inputs.add(&format!("{:?}", report_context.plot_config));
+        // The Debug output doesn't show the labels of the tic formatter.
+        for &tic in &report_context.plot_config.tics {
+            inputs.add(&report_context.plot_config.tic_label(tic));
+        }
+        inputs.add(formatter.scale_for_machines(&mut []));
+        inputs.add_values(&report_context.percentiles);
+        for &&(id, ref values) in data {
+            inputs.add(id.as_title());
+            inputs.add_values(values);
+        }
+        inputs.add_serialized(&mean_intervals);
+        inputs.add_serialized(&changes);
+        let hash = inputs.finish();
+        let speedup_path = plot_ctx.speedup_path();
+        let mut files = vec![report_path.as_path()];
+        if plots.summary {
+            files.push(&summary_plot_path);
+        }
+        files.extend(line_path.as_deref());
+        if plots.line_comparison && !speedups.is_empty() {
+            files.push(&speedup_path);
+        }
+        if report_context.report_cache && cache::is_fresh(&report_dir, &hash, &files) {
+            info!("Summary of {} is up to date", id.as_title());
+            return;
+        }
+
+        let mut violins = data.to_vec();
+        if report_context.plot_config.violins_by_parameter {
+            // The data is sorted by function, then by value; stable, so that the functions keep
+            // their order at each parameter.
+            violins.sort_by(|&&(a, _), &&(b, _)| match (a.as_number(), b.as_number()) {
+                (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            });
+        }
+        if plots.summary {
+            match summary_plot {
+                SummaryPlot::Violin => self
+                    .plotter
+                    .borrow_mut()
+                    .violin(plot_ctx, formatter, &violins),
+                SummaryPlot::BoxPlot => self
+                    .plotter
+                    .borrow_mut()
+                    .box_plot(plot_ctx, formatter, &violins),
+            }
+        }
+        if let Some(value_type) = line_chart.filter(|_| plots.line_comparison) {
+            self.plotter.borrow_mut().line_comparison(
+                plot_ctx,
+                formatter,
+                data,
+                &mean_intervals,
+                &changes,
+                value_type,
+            );
+        }
+        if plots.line_comparison && !speedups.is_empty() {
+            let speedups: Vec<_> = speedups
+                .into_iter()
+                .map(|(function_id, speedup, _)| (function_id, speedup))
+                .collect();
+            self.plotter
+                .borrow_mut()
+                .speedup_summary(plot_ctx, &speedups);
+        }
+
         let text = self
             .templates
             .render("summary_report", &context)
             .expect("Failed to render summary report template");
         try_else_return!(fs::save_string(&text, &report_path,), || {});
+        cache::record(&report_dir, &hash);
     }
 }
 
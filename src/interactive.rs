@@ -0,0 +1,531 @@
+//! Interactive runner (`--interactive`, requires the `interactive` feature).
+//!
+//! Instead of running the whole suite, `--interactive` first runs every group in a discovery pass,
+//! like the counting pass of `--progress`, to collect the benchmarks which match the filters. It
+//! then reads commands from standard input to search the benchmarks, select some of them, run them
+//! and show their latest results. Every run executes the groups again with the IDs of the selected
+//! benchmarks as the only filter, so they are measured, analyzed and reported exactly as usual,
+//! with the progress display enabled.
+
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use once_cell::sync::Lazy;
+
+use crate::estimate::{ChangeEstimates, Estimate, Estimates};
+use crate::manifest::Manifest;
+use crate::measurement::WallTime;
+use crate::report::BenchmarkId;
+use crate::{format, fs, history, parallel, progress, shuffle};
+
+/// Only this many benchmarks are printed at a time; a search narrows the list down.
+const MAX_LISTED: usize = 40;
+
+const HELP: &str = "\
+Commands:
+  <text>, /<text>     search the benchmarks (fuzzy); `/` forces a search for words like `run`
+  list, l             show all benchmarks again
+  select, s <N...>    add benchmarks of the list to the selection, eg. `s 1 3 5-7` or `s all`
+  unselect, u <N...>  remove benchmarks from the selection
+  clear               empty the selection
+  run, r [N...]       run the given benchmarks, or the selection, or every benchmark listed
+  show, i <N>         show the latest results of a benchmark
+  help, h, ?          show this help
+  quit, q             exit";
+
+struct Benchmark {
+    id: BenchmarkId,
+    output_directory: PathBuf,
+}
+
+#[derive(Default)]
+struct State {
+    /// True while `run` is running the groups to discover benchmarks.
+    discovering: bool,
+    discovered: Vec<Benchmark>,
+    /// Full IDs of the benchmarks to run, while the groups are being run for a `run` command.
+    selection: Option<Vec<String>>,
+}
+
+static STATE: Lazy<Mutex<State>> = Lazy::new(|| Mutex::new(State::default()));
+
+fn state() -> MutexGuard<'static, State> {
+    // A panicking benchmark must not prevent the session from continuing.
+    STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Returns true if `--interactive` was passed. Like the benchmark order, this is needed before any
+/// `Criterion` struct is configured, so it is read from the arguments directly.
+pub(crate) fn is_requested() -> bool {
+    std::env::args().any(|arg| arg == "--interactive")
+}
+
+pub(crate) fn is_discovering() -> bool {
+    state().discovering
+}
+
+pub(crate) fn benchmark_discovered(id: &BenchmarkId, output_directory: &Path) {
+    let mut state = state();
+    if state.discovering {
+        state.discovered.push(Benchmark {
+            id: id.clone(),
+            output_directory: output_directory.to_owned(),
+        });
+    }
+}
+
+/// Full IDs of the benchmarks selected for the current run, if a run was started interactively.
+pub(crate) fn selection() -> Option<Vec<String>> {
+    state().selection.clone()
+}
+
+/// Scores how well `query` matches `candidate` as a case-insensitive subsequence, ignoring
+/// whitespace in the query, or returns `None` if it doesn't match. Consecutive characters and
+/// characters at the start of a word score higher, so that `fibrec` ranks `fib/recursive` above
+/// `fast_fibonacci/iterative_rec`.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut next = 0;
+    for wanted in query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+    {
+        let position = next + candidate[next..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if position > 0 && next == position {
+            score += 4;
+        }
+        if position == 0 || matches!(candidate[position - 1], '/' | '_' | '-' | ' ' | ':' | '.') {
+            score += 2;
+        }
+        next = position + 1;
+    }
+    Some(score)
+}
+
+#[derive(Debug, PartialEq)]
+enum Selection {
+    All,
+    Numbers(Vec<usize>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Search(String),
+    List,
+    Select(Selection),
+    Unselect(Selection),
+    Clear,
+    Run(Option<Selection>),
+    Show(usize),
+    Help,
+    Quit,
+}
+impl Command {
+    fn parse(line: &str) -> Result<Command, String> {
+        let line = line.trim();
+        if let Some(query) = line.strip_prefix('/') {
+            return Ok(Command::Search(query.trim().to_owned()));
+        }
+        let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let command = match word {
+            "" | "l" | "list" => Command::List,
+            "s" | "select" => Command::Select(Command::parse_selection(rest)?),
+            "u" | "unselect" => Command::Unselect(Command::parse_selection(rest)?),
+            "clear" => Command::Clear,
+            "r" | "run" if rest.is_empty() => Command::Run(None),
+            "r" | "run" => Command::Run(Some(Command::parse_selection(rest)?)),
+            "i" | "show" => match rest.parse() {
+                Ok(number) if number > 0 => Command::Show(number),
+                _ => return Err("Expected the number of a benchmark, eg. `show 3`.".to_owned()),
+            },
+            "h" | "help" | "?" => Command::Help,
+            "q" | "quit" | "exit" => Command::Quit,
+            _ => Command::Search(line.to_owned()),
+        };
+        Ok(command)
+    }
+
+    /// Parses `all` or a list of numbers and ranges, eg. `1 3 5-7` or `1,3,5-7`.
+    fn parse_selection(selection: &str) -> Result<Selection, String> {
+        if selection == "all" {
+            return Ok(Selection::All);
+        }
+        let invalid = || {
+            format!(
+                "Invalid selection '{}': expected `all` or numbers such as `1 3 5-7`.",
+                selection
+            )
+        };
+        let mut numbers = vec![];
+        for part in selection
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+        {
+            let (first, last) = part.split_once('-').unwrap_or((part, part));
+            match (first.parse::<usize>(), last.parse::<usize>()) {
+                (Ok(first), Ok(last)) if first > 0 && first <= last => numbers.extend(first..=last),
+                _ => return Err(invalid()),
+            }
+        }
+        if numbers.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Selection::Numbers(numbers))
+    }
+}
+
+struct Session {
+    benchmarks: Vec<Benchmark>,
+    /// Indices of the benchmarks in the current list, which the numbers of the commands refer to.
+    listed: Vec<usize>,
+    selected: BTreeSet<usize>,
+}
+impl Session {
+    fn new(benchmarks: Vec<Benchmark>) -> Session {
+        Session {
+            listed: (0..benchmarks.len()).collect(),
+            benchmarks,
+            selected: BTreeSet::new(),
+        }
+    }
+
+    fn search(&mut self, query: &str) {
+        let mut scored: Vec<(i64, usize)> = self
+            .benchmarks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, benchmark)| {
+                fuzzy_score(query, benchmark.id.id()).map(|score| (score, i))
+            })
+            .collect();
+        // Best matches first; equally good matches keep the order in which they were defined.
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        self.listed = scored.into_iter().map(|(_, i)| i).collect();
+    }
+
+    fn print_list(&self) {
+        if self.listed.is_empty() {
+            println!("No benchmarks match; type `list` to show all of them.");
+            return;
+        }
+        for (number, &i) in self.listed.iter().enumerate().take(MAX_LISTED) {
+            let marker = if self.selected.contains(&i) { '*' } else { ' ' };
+            println!("{:>4} {} {}", number + 1, marker, self.benchmarks[i].id);
+        }
+        if self.listed.len() > MAX_LISTED {
+            println!(
+                "     ... and {} more; type some text to search.",
+                self.listed.len() - MAX_LISTED
+            );
+        }
+        if !self.selected.is_empty() {
+            println!("{} selected (*).", self.selected.len());
+        }
+    }
+
+    /// Resolves a selection to indices into `benchmarks`.
+    fn resolve(&self, selection: &Selection) -> Result<Vec<usize>, String> {
+        match selection {
+            Selection::All => Ok(self.listed.clone()),
+            Selection::Numbers(numbers) => numbers
+                .iter()
+                .map(|&number| {
+                    self.listed.get(number - 1).copied().ok_or_else(|| {
+                        format!(
+                            "There is no benchmark {}; the list has {}.",
+                            number,
+                            self.listed.len()
+                        )
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    fn execute(&mut self, command: Command, groups: &[&'static (dyn Fn() + Sync)]) {
+        let result = match command {
+            Command::Search(query) => {
+                self.search(&query);
+                self.print_list();
+                Ok(())
+            }
+            Command::List => {
+                self.listed = (0..self.benchmarks.len()).collect();
+                self.print_list();
+                Ok(())
+            }
+            Command::Select(selection) => self.resolve(&selection).map(|indices| {
+                self.selected.extend(indices);
+                self.print_list();
+            }),
+            Command::Unselect(selection) => self.resolve(&selection).map(|indices| {
+                for i in indices {
+                    self.selected.remove(&i);
+                }
+                self.print_list();
+            }),
+            Command::Clear => {
+                self.selected.clear();
+                self.print_list();
+                Ok(())
+            }
+            Command::Run(selection) => {
+                let indices = match &selection {
+                    Some(selection) => self.resolve(selection),
+                    None if !self.selected.is_empty() => {
+                        Ok(self.selected.iter().copied().collect())
+                    }
+                    None => Ok(self.listed.clone()),
+                };
+                indices.map(|indices| self.run(&indices, groups))
+            }
+            Command::Show(number) => self
+                .resolve(&Selection::Numbers(vec![number]))
+                .map(|indices| show(&self.benchmarks[indices[0]])),
+            Command::Help => {
+                println!("{}", HELP);
+                Ok(())
+            }
+            Command::Quit => Ok(()),
+        };
+        if let Err(e) = result {
+            println!("{}", e);
+        }
+    }
+
+    fn run(&self, indices: &[usize], groups: &[&'static (dyn Fn() + Sync)]) {
+        if indices.is_empty() {
+            println!("Nothing to run.");
+            return;
+        }
+        let ids: Vec<String> = indices
+            .iter()
+            .map(|&i| self.benchmarks[i].id.id().to_owned())
+            .collect();
+        progress::restart(ids.len());
+        state().selection = Some(ids);
+        parallel::run_groups(groups, shuffle::order(groups.len()));
+        state().selection = None;
+
+        println!("Finished; type `show <N>` for the details of a benchmark.");
+        for (number, &i) in self.listed.iter().enumerate() {
+            if indices.contains(&i) {
+                println!("{:>4}   {}", number + 1, summary(&self.benchmarks[i]));
+            }
+        }
+    }
+}
+
+/// Loads the latest results of a benchmark, if it has been run with this output directory.
+fn load_results(benchmark: &Benchmark) -> Option<(Estimates, Option<ChangeEstimates>, Manifest)> {
+    let directory = benchmark
+        .output_directory
+        .join(benchmark.id.as_directory_name());
+    let estimates = fs::load(&directory.join("new").join("estimates.json")).ok()?;
+    let manifest = fs::load(&directory.join("new").join("manifest.json")).ok()?;
+    let change = fs::load(&directory.join("change").join("estimates.json")).ok();
+    Some((estimates, change, manifest))
+}
+
+/// Formats a value of the benchmark's measurement. Only wall-clock times are known to be in
+/// nanoseconds; other measurements are shown in their own unit.
+fn format_value(value: f64, manifest: &Manifest) -> String {
+    if manifest.measurement == std::any::type_name::<WallTime>() {
+        format::time(value)
+    } else {
+        format::short(value)
+    }
+}
+
+fn format_estimate(estimate: &Estimate, manifest: &Manifest) -> String {
+    format!(
+        "{} [{} {}]",
+        format_value(estimate.point_estimate, manifest),
+        format_value(estimate.confidence_interval.lower_bound, manifest),
+        format_value(estimate.confidence_interval.upper_bound, manifest),
+    )
+}
+
+/// One line with the typical estimate and the change of a benchmark.
+fn summary(benchmark: &Benchmark) -> String {
+    match load_results(benchmark) {
+        Some((estimates, change, manifest)) => {
+            let change = change
+                .map(|change| {
+                    format!(
+                        ", change {}",
+                        format::change(change.mean.point_estimate, true)
+                    )
+                })
+                .unwrap_or_default();
+            format!(
+                "{}: {}{}",
+                benchmark.id,
+                format_estimate(estimates.typical(), &manifest),
+                change
+            )
+        }
+        None => format!("{}: no results", benchmark.id),
+    }
+}
+
+fn show(benchmark: &Benchmark) {
+    let (estimates, change, manifest) = match load_results(benchmark) {
+        Some(results) => results,
+        None => {
+            println!("{} has no results yet; run it first.", benchmark.id);
+            return;
+        }
+    };
+    let row = |name: &str, value: String| println!("  {:<17} {}", name, value);
+    println!("{}", benchmark.id);
+    if manifest.measurement != std::any::type_name::<WallTime>() {
+        row("measurement", manifest.measurement.clone());
+    }
+    row(
+        "sample size",
+        format!(
+            "{} ({:?} sampling)",
+            manifest.config.sample_size, manifest.config.actual_sampling_mode
+        ),
+    );
+    if let Some(slope) = &estimates.slope {
+        row("slope", format_estimate(slope, &manifest));
+    }
+    row("mean", format_estimate(&estimates.mean, &manifest));
+    row("median", format_estimate(&estimates.median, &manifest));
+    row("std. dev.", format_estimate(&estimates.std_dev, &manifest));
+    row(
+        "median abs. dev.",
+        format_estimate(&estimates.median_abs_dev, &manifest),
+    );
+    if let Some(change) = change {
+        row(
+            "change (mean)",
+            format!(
+                "{} [{} {}]",
+                format::change(change.mean.point_estimate, true),
+                format::change(change.mean.confidence_interval.lower_bound, true),
+                format::change(change.mean.confidence_interval.upper_bound, true),
+            ),
+        );
+    }
+    let history = history::load(&benchmark.output_directory, &benchmark.id);
+    if history.len() > 1 {
+        let recent: Vec<String> = history
+            .iter()
+            .rev()
+            .take(5)
+            .map(|entry| {
+                format_value(entry.point_estimate, &manifest)
+                    .trim()
+                    .to_owned()
+            })
+            .collect();
+        row("history", format!("{} (latest first)", recent.join(", ")));
+    }
+    let report = benchmark
+        .output_directory
+        .join(benchmark.id.as_directory_name())
+        .join("report")
+        .join("index.html");
+    if report.is_file() {
+        row("report", report.display().to_string());
+    }
+}
+
+/// Runs the interactive session: discovers the benchmarks of the groups, then executes commands
+/// read from standard input until `quit` or the end of the input.
+pub(crate) fn run(groups: &[&'static (dyn Fn() + Sync)]) {
+    state().discovering = true;
+    for group in groups {
+        group();
+    }
+    let benchmarks = {
+        let mut state = state();
+        state.discovering = false;
+        std::mem::take(&mut state.discovered)
+    };
+    if benchmarks.is_empty() {
+        println!("No benchmarks match the filters.");
+        return;
+    }
+
+    println!(
+        "Found {} benchmarks. Type `help` for the list of commands.",
+        benchmarks.len()
+    );
+    let mut session = Session::new(benchmarks);
+    session.print_list();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        io::stdout().flush().unwrap();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break,
+        };
+        match Command::parse(&line) {
+            Ok(Command::Quit) => break,
+            Ok(command) => session.execute(command, groups),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fuzzy_search_ranks_word_starts_and_runs_first() {
+        assert_eq!(fuzzy_score("xyz", "fib/recursive"), None);
+        assert!(fuzzy_score("", "fib/recursive").is_some());
+        assert!(fuzzy_score("FIB rec", "fib/recursive").is_some());
+        assert!(
+            fuzzy_score("fibrec", "fib/recursive")
+                > fuzzy_score("fibrec", "fast_fibonacci/iterative_rec")
+        );
+    }
+
+    #[test]
+    fn commands_are_parsed() {
+        assert_eq!(Command::parse(""), Ok(Command::List));
+        assert_eq!(
+            Command::parse("  fib rec "),
+            Ok(Command::Search("fib rec".to_owned()))
+        );
+        assert_eq!(
+            Command::parse("/run"),
+            Ok(Command::Search("run".to_owned()))
+        );
+        assert_eq!(
+            Command::parse("s 1, 3 5-7"),
+            Ok(Command::Select(Selection::Numbers(vec![1, 3, 5, 6, 7])))
+        );
+        assert_eq!(
+            Command::parse("select all"),
+            Ok(Command::Select(Selection::All))
+        );
+        assert_eq!(Command::parse("run"), Ok(Command::Run(None)));
+        assert_eq!(
+            Command::parse("r 2"),
+            Ok(Command::Run(Some(Selection::Numbers(vec![2]))))
+        );
+        assert_eq!(Command::parse("show 4"), Ok(Command::Show(4)));
+        assert_eq!(Command::parse("q"), Ok(Command::Quit));
+        assert!(Command::parse("s 0").is_err());
+        assert!(Command::parse("s 3-1").is_err());
+        assert!(Command::parse("s").is_err());
+        assert!(Command::parse("show x").is_err());
+    }
+}
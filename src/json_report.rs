@@ -0,0 +1,214 @@
+//! The JSON report (`--message-format=json`, `Criterion::json_output`).
+//!
+//! Each benchmark is reported as soon as it has been analyzed, as one JSON object on its own line,
+//! so that CI tools can read the results as they come without scraping the `estimates.json` files
+//! of the output directory.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::error::{Error, Result};
+use crate::measurement::ValueFormatter;
+use crate::report::{BenchmarkId, MeasurementData, Report, ReportContext, SignificantChange};
+use crate::sink::Estimate;
+use crate::Throughput;
+
+/// Where the JSON objects are written.
+enum Output {
+    Stdout,
+    /// The file is created, or truncated, when the first benchmark is reported.
+    File {
+        path: PathBuf,
+        file: Option<File>,
+    },
+}
+
+pub(crate) struct JsonReport {
+    output: RefCell<Output>,
+}
+impl JsonReport {
+    /// Writes the results to standard output.
+    pub(crate) fn stdout() -> JsonReport {
+        JsonReport {
+            output: RefCell::new(Output::Stdout),
+        }
+    }
+
+    /// Writes the results to the file `path`.
+    pub(crate) fn file(path: PathBuf) -> JsonReport {
+        JsonReport {
+            output: RefCell::new(Output::File { path, file: None }),
+        }
+    }
+
+    fn write_line(&self, line: &str) -> Result<()> {
+        let access_error = |path: &PathBuf, inner| Error::AccessError {
+            path: path.clone(),
+            inner,
+        };
+        match &mut *self.output.borrow_mut() {
+            Output::Stdout => {
+                let stdout = io::stdout();
+                let mut stdout = stdout.lock();
+                writeln!(stdout, "{}", line)
+                    .and_then(|_| stdout.flush())
+                    .map_err(|inner| access_error(&PathBuf::from("<stdout>"), inner))
+            }
+            Output::File { path, file } => {
+                if file.is_none() {
+                    *file = Some(File::create(&path).map_err(|inner| access_error(path, inner))?);
+                }
+                let file = file.as_mut().unwrap();
+                writeln!(file, "{}", line)
+                    .and_then(|_| file.flush())
+                    .map_err(|inner| access_error(path, inner))
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct BenchmarkMessage<'a> {
+    reason: &'static str,
+    id: &'a str,
+    group: &'a str,
+    function: Option<&'a str>,
+    value: Option<&'a str>,
+    /// Unit of the estimates, eg. `ns` for the default wall-clock measurement.
+    unit: &'a str,
+    estimates: Estimates,
+    throughput: Option<ThroughputMessage>,
+    change: Option<ChangeMessage>,
+    outliers: Outliers,
+    /// Total number of iterations measured.
+    iterations: u64,
+    labels: &'a BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct Estimates {
+    mean: Estimate,
+    median: Estimate,
+    std_dev: Estimate,
+    median_abs_dev: Estimate,
+    slope: Option<Estimate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum: Option<Estimate>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    percentiles: Vec<PercentileMessage>,
+}
+
+/// A percentile of `Criterion::percentiles`, between 0 and 100, with its estimate.
+#[derive(Serialize)]
+struct PercentileMessage {
+    percentile: f64,
+    #[serde(flatten)]
+    estimate: Estimate,
+}
+
+#[derive(Serialize)]
+struct ThroughputMessage {
+    /// `bytes`, `bytes_decimal` or `elements`.
+    unit: &'static str,
+    per_iteration: u64,
+}
+impl ThroughputMessage {
+    fn new(throughput: &Throughput) -> ThroughputMessage {
+        let (unit, per_iteration) = match *throughput {
+            Throughput::Bytes(n) => ("bytes", n),
+            Throughput::BytesDecimal(n) => ("bytes_decimal", n),
+            Throughput::Elements(n) => ("elements", n),
+        };
+        ThroughputMessage {
+            unit,
+            per_iteration,
+        }
+    }
+}
+
+/// The relative changes compared to the baseline, eg. `-0.05` for 5% faster.
+#[derive(Serialize)]
+struct ChangeMessage {
+    mean: Estimate,
+    median: Estimate,
+    p_value: f64,
+    /// `improved`, `regressed` or `no_change`.
+    result: &'static str,
+}
+
+#[derive(Serialize)]
+struct Outliers {
+    low_severe: usize,
+    low_mild: usize,
+    high_mild: usize,
+    high_severe: usize,
+}
+
+impl Report for JsonReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &dyn ValueFormatter,
+    ) {
+        let estimates = &measurements.absolute_estimates;
+        // The estimates are in the measurement's raw unit; find the factor by which
+        // `scale_for_machines` converts to the unit it reports.
+        let mut scale = [1.0];
+        let unit = formatter.scale_for_machines(&mut scale);
+        let scaled = |estimate| Estimate::new(estimate, scale[0]);
+        let (low_severe, low_mild, _, high_mild, high_severe) = measurements.avg_times.count();
+        let message = BenchmarkMessage {
+            reason: "benchmark-complete",
+            id: id.as_title(),
+            group: &id.group_id,
+            function: id.function_id.as_deref(),
+            value: id.value_str.as_deref(),
+            unit,
+            estimates: Estimates {
+                mean: scaled(&estimates.mean),
+                median: scaled(&estimates.median),
+                std_dev: scaled(&estimates.std_dev),
+                median_abs_dev: scaled(&estimates.median_abs_dev),
+                slope: estimates.slope.as_ref().map(scaled),
+                minimum: estimates.minimum.as_ref().map(scaled),
+                percentiles: estimates
+                    .percentiles
+                    .iter()
+                    .map(|percentile| PercentileMessage {
+                        percentile: percentile.percentile,
+                        estimate: scaled(&percentile.estimate),
+                    })
+                    .collect(),
+            },
+            throughput: measurements.throughput.as_ref().map(ThroughputMessage::new),
+            change: measurements.comparison.as_ref().map(|comparison| {
+                let change = &comparison.relative_estimates;
+                ChangeMessage {
+                    mean: Estimate::new(&change.mean, 1.0),
+                    median: Estimate::new(&change.median, 1.0),
+                    p_value: comparison.p_value,
+                    result: match comparison.significant_change() {
+                        Some(SignificantChange::Improved) => "improved",
+                        Some(SignificantChange::Regressed) => "regressed",
+                        None => "no_change",
+                    },
+                }
+            }),
+            outliers: Outliers {
+                low_severe,
+                low_mild,
+                high_mild,
+                high_severe,
+            },
+            iterations: measurements.iter_counts().iter().sum::<f64>() as u64,
+            labels: &measurements.manifest.labels,
+        };
+        let line = serde_json::to_string(&message).unwrap();
+        log_if_err!(self.write_line(&line));
+    }
+}
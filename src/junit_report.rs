@@ -0,0 +1,180 @@
+//! The JUnit XML report (`--junit-output`, `Criterion::junit_output`).
+//!
+//! Each benchmark is a test case, which fails if it has regressed compared to the baseline by more
+//! than its noise threshold (see `--noise-threshold` and `--compare-threshold`), so that CI
+//! dashboards which understand JUnit reports show the regressions like failed tests. Since every
+//! group constructs its own `Criterion` struct, the test cases are kept in a process-wide static,
+//! keyed by the path of the report, and the whole file is rewritten after each benchmark. It is
+//! thus complete even if the run is stopped, eg. by `--fail-fast`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::error::{Error, Result};
+use crate::format;
+use crate::measurement::ValueFormatter;
+use crate::report::{BenchmarkId, MeasurementData, Report, ReportContext};
+
+struct TestCase {
+    path: PathBuf,
+    classname: String,
+    name: String,
+    /// The total measured time in seconds, if the measurement is the wall-clock time.
+    time: Option<f64>,
+    /// The message of the failure, if the benchmark has regressed.
+    failure: Option<String>,
+    output: String,
+    /// The labels of the run, see `Criterion::label`.
+    labels: BTreeMap<String, String>,
+}
+
+static CASES: Lazy<Mutex<Vec<TestCase>>> = Lazy::new(|| Mutex::new(vec![]));
+
+pub(crate) struct JunitReport {
+    path: PathBuf,
+}
+impl JunitReport {
+    pub(crate) fn new(path: PathBuf) -> JunitReport {
+        JunitReport { path }
+    }
+
+    fn write(&self, xml: &str) -> Result<()> {
+        std::fs::write(&self.path, xml).map_err(|inner| Error::AccessError {
+            path: self.path.clone(),
+            inner,
+        })
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders the test cases of the report at `path` as a JUnit document.
+fn render(cases: &[TestCase], path: &Path) -> String {
+    let cases: Vec<&TestCase> = cases.iter().filter(|case| case.path == path).collect();
+    let tests = cases.len();
+    let failures = cases.iter().filter(|case| case.failure.is_some()).count();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    writeln!(
+        xml,
+        "<testsuites name=\"criterion\" tests=\"{}\" failures=\"{}\">",
+        tests, failures
+    )
+    .unwrap();
+    writeln!(
+        xml,
+        "  <testsuite name=\"criterion\" tests=\"{}\" failures=\"{}\">",
+        tests, failures
+    )
+    .unwrap();
+    // The labels are those of the run, the same for every case; the last case's are the latest.
+    if let Some(case) = cases.last().filter(|case| !case.labels.is_empty()) {
+        xml.push_str("    <properties>\n");
+        for (key, value) in &case.labels {
+            writeln!(
+                xml,
+                "      <property name=\"{}\" value=\"{}\"/>",
+                escape(key),
+                escape(value)
+            )
+            .unwrap();
+        }
+        xml.push_str("    </properties>\n");
+    }
+    for case in cases {
+        write!(
+            xml,
+            "    <testcase classname=\"{}\" name=\"{}\"",
+            escape(&case.classname),
+            escape(&case.name)
+        )
+        .unwrap();
+        if let Some(time) = case.time {
+            write!(xml, " time=\"{:.6}\"", time).unwrap();
+        }
+        xml.push_str(">\n");
+        if let Some(message) = &case.failure {
+            writeln!(
+                xml,
+                "      <failure type=\"regression\" message=\"{}\"/>",
+                escape(message)
+            )
+            .unwrap();
+        }
+        writeln!(
+            xml,
+            "      <system-out>{}</system-out>",
+            escape(&case.output)
+        )
+        .unwrap();
+        xml.push_str("    </testcase>\n");
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+impl Report for JunitReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &dyn ValueFormatter,
+    ) {
+        let typical = measurements.absolute_estimates.typical();
+        let ci = &typical.confidence_interval;
+        let mut output = format!(
+            "time: [{} {} {}]",
+            formatter.format_value(ci.lower_bound),
+            formatter.format_value(typical.point_estimate),
+            formatter.format_value(ci.upper_bound)
+        );
+        let mut failure = None;
+        if let Some(comparison) = &measurements.comparison {
+            let mean = comparison.change();
+            let change = format!(
+                "change: [{} {} {}] (p = {:.2})",
+                format::change(mean.confidence_interval.lower_bound, true).trim(),
+                format::change(mean.point_estimate, true).trim(),
+                format::change(mean.confidence_interval.upper_bound, true).trim(),
+                comparison.p_value
+            );
+            if comparison.is_regression() {
+                failure = Some(format!(
+                    "Performance has regressed beyond the noise threshold of {}: {}",
+                    format::change(comparison.noise_threshold, false).trim(),
+                    change
+                ));
+            }
+            output.push('\n');
+            output.push_str(&change);
+        }
+
+        let mut total = [measurements.data.y().iter().sum::<f64>()];
+        let time = match formatter.scale_for_machines(&mut total) {
+            "ns" => Some(total[0] * 1e-9),
+            _ => None,
+        };
+
+        let mut cases = CASES.lock().unwrap();
+        cases.push(TestCase {
+            path: self.path.clone(),
+            classname: id.group_id.clone(),
+            name: id.as_title().to_owned(),
+            time,
+            failure,
+            output,
+            labels: measurements.manifest.labels.clone(),
+        });
+        log_if_err!(self.write(&render(&cases, &self.path)));
+    }
+}
@@ -1,18 +1,21 @@
 use crate::stats::univariate::kde::kernel::Gaussian;
-use crate::stats::univariate::kde::{Bandwidth, Kde};
+pub use crate::stats::univariate::kde::Bandwidth;
+use crate::stats::univariate::kde::Kde;
 use crate::stats::univariate::Sample;
 
 pub fn sweep(
     sample: &Sample<f64>,
+    bandwidth: Bandwidth,
     npoints: usize,
     range: Option<(f64, f64)>,
 ) -> (Box<[f64]>, Box<[f64]>) {
-    let (xs, ys, _) = sweep_and_estimate(sample, npoints, range, sample[0]);
+    let (xs, ys, _) = sweep_and_estimate(sample, bandwidth, npoints, range, sample[0]);
     (xs, ys)
 }
 
 pub fn sweep_and_estimate(
     sample: &Sample<f64>,
+    bandwidth: Bandwidth,
     npoints: usize,
     range: Option<(f64, f64)>,
     point_to_estimate: f64,
@@ -20,7 +23,7 @@ pub fn sweep_and_estimate(
     let x_min = sample.min();
     let x_max = sample.max();
 
-    let kde = Kde::new(sample, Gaussian, Bandwidth::Silverman);
+    let kde = Kde::new(sample, Gaussian, bandwidth);
     let h = kde.bandwidth();
 
     let (start, end) = match range {
@@ -54,67 +54,120 @@ mod benchmark;
 #[macro_use]
 mod benchmark_group;
 pub mod async_executor;
+mod badge_report;
 mod bencher;
+mod clock;
+mod config_file;
 mod connection;
+mod correction;
 #[cfg(feature = "csv_output")]
 mod csv_report;
+mod drift;
+mod environment;
 mod error;
 mod estimate;
 mod format;
 mod fs;
+pub mod heap;
+mod history;
+#[cfg(feature = "sqlite")]
+pub mod history_db;
+pub mod hooks;
 mod html;
+#[cfg(feature = "interactive")]
+mod interactive;
+mod json_report;
+mod junit_report;
 mod kde;
 mod macros;
+mod manifest;
+mod markdown_report;
 pub mod measurement;
+mod metadata;
+mod metrics_report;
+mod missing_baseline;
+mod noise_floor;
+mod parallel;
+mod perf_markers;
 mod plot;
 pub mod profiler;
+mod progress;
+mod ranking;
 mod report;
+mod resume;
+mod retention;
 mod routine;
+mod shuffle;
+pub mod sink;
+mod spill;
 mod stats;
+mod stop;
+mod streaming;
+mod terminal_plot;
 
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::default::Default;
 use std::env;
+use std::fmt;
 use std::net::TcpStream;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::{Mutex, MutexGuard};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::Duration;
 
 use criterion_plot::{Version, VersionError};
 use once_cell::sync::Lazy;
 
-use crate::benchmark::BenchmarkConfig;
+use crate::config_file::ConfigFile;
 use crate::connection::Connection;
 use crate::connection::OutgoingMessage;
+use crate::estimate::Statistic;
+use crate::hooks::BatchHooks;
 use crate::html::Html;
-use crate::measurement::{Measurement, WallTime};
+use crate::json_report::JsonReport;
+use crate::junit_report::JunitReport;
+use crate::measurement::{FixedFormatter, Measurement, Timer, Unit, WallTime};
+use crate::metrics_report::MetricsReport;
 #[cfg(feature = "plotters")]
 use crate::plot::PlottersBackend;
-use crate::plot::{Gnuplot, Plotter};
-use crate::profiler::{ExternalProfiler, Profiler};
+use crate::plot::{Gnuplot, Plotter, Vega};
+use crate::profiler::Profiler;
 use crate::report::{BencherReport, CliReport, CliVerbosity, Report, ReportContext, Reports};
+use crate::sink::{CustomReport, ResultSink, SinkReport};
+use crate::stats::bivariate::regression::Slope;
+use crate::stats::bivariate::Data;
+use crate::stats::univariate::outliers::tukey;
+use crate::stats::univariate::Sample;
+use crate::terminal_plot::Charset;
 
 #[cfg(feature = "async")]
 pub use crate::bencher::AsyncBencher;
 pub use crate::bencher::Bencher;
+pub use crate::benchmark::BenchmarkConfig;
 pub use crate::benchmark_group::{BenchmarkGroup, BenchmarkId};
+pub use criterion_core::Samples;
 
 static DEBUG_ENABLED: Lazy<bool> = Lazy::new(|| std::env::var_os("CRITERION_DEBUG").is_some());
 static GNUPLOT_VERSION: Lazy<Result<Version, VersionError>> =
     Lazy::new(|| criterion_plot::version());
-static DEFAULT_PLOTTING_BACKEND: Lazy<PlottingBackend> = Lazy::new(|| match &*GNUPLOT_VERSION {
-    Ok(_) => PlottingBackend::Gnuplot,
-    Err(e) => {
-        match e {
-            VersionError::Exec(_) => println!("Gnuplot not found, using plotters backend"),
-            e => println!(
-                "Gnuplot not found or not usable, using plotters backend\n{}",
-                e
-            ),
-        };
-        PlottingBackend::Plotters
+static DEFAULT_PLOTTING_BACKEND: Lazy<PlottingBackend> = Lazy::new(|| {
+    // Web browsers can neither run gnuplot nor save plots.
+    if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+        return PlottingBackend::None;
+    }
+    match &*GNUPLOT_VERSION {
+        Ok(_) => PlottingBackend::Gnuplot,
+        Err(e) => {
+            match e {
+                VersionError::Exec(_) => println!("Gnuplot not found, using plotters backend"),
+                e => println!(
+                    "Gnuplot not found or not usable, using plotters backend\n{}",
+                    e
+                ),
+            };
+            PlottingBackend::Plotters
+        }
     }
 });
 static CARGO_CRITERION_CONNECTION: Lazy<Option<Mutex<Connection>>> =
@@ -141,6 +194,21 @@ static DEFAULT_OUTPUT_DIRECTORY: Lazy<PathBuf> = Lazy::new(|| {
     }
 });
 
+/// Subdirectory of the output directory used with `--per-target-output`, eg.
+/// `x86_64-unknown-linux-gnu/release`. The profile is taken from the directory containing the
+/// benchmark executable if possible, since build scripts only distinguish `debug` and `release`.
+fn target_subdirectory() -> PathBuf {
+    let profile = env::current_exe().ok().and_then(|exe| {
+        let deps = exe.parent()?;
+        if deps.file_name()? != "deps" {
+            return None;
+        }
+        Some(deps.parent()?.file_name()?.to_owned())
+    });
+    let profile = profile.unwrap_or_else(|| env!("CRITERION_PROFILE").into());
+    Path::new(env!("CRITERION_TARGET")).join(profile)
+}
+
 fn debug_enabled() -> bool {
     *DEBUG_ENABLED
 }
@@ -258,10 +326,12 @@ impl BatchSize {
 #[derive(Debug, Clone, Copy)]
 pub enum Baseline {
     /// CompareLenient compares against a previous saved version of the baseline.
-    /// If a previous baseline does not exist, the benchmark is run as normal but no comparison occurs.
+    /// If a previous baseline does not exist, the benchmark is run as normal but no comparison
+    /// occurs (see [`MissingBaselinePolicy`]).
     CompareLenient,
     /// CompareStrict compares against a previous saved version of the baseline.
-    /// If a previous baseline does not exist, a panic occurs.
+    /// If a previous baseline does not exist, the benchmark is skipped and the run fails at the end
+    /// (see [`MissingBaselinePolicy`]).
     CompareStrict,
     /// Save writes the benchmark results to the baseline directory,
     /// overwriting any results that were previously there.
@@ -270,6 +340,72 @@ pub enum Baseline {
     Discard,
 }
 
+/// What to do with a benchmark which has no saved baseline when comparing against one, ie. with
+/// [`Baseline::CompareStrict`] or [`Baseline::CompareLenient`]. The benchmarks the policy was
+/// applied to are listed at the end of the run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingBaselinePolicy {
+    /// The benchmark is skipped and the run fails at the end. This is the default for
+    /// `CompareStrict`.
+    Error,
+    /// The benchmark is run without a comparison and a warning is printed. This is the default for
+    /// `CompareLenient`.
+    WarnAndRun,
+    /// The benchmark is run and its results are saved as the baseline, so that later runs can
+    /// compare against it.
+    AutoCreate,
+}
+
+/// The correction of the p-values of all the comparisons of a run for multiple comparisons, see
+/// `Criterion::multiple_comparison_correction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Correction {
+    /// Multiplies each p-value by the number of comparisons, so that the chance of reporting any
+    /// change which is only noise is at most the significance level. It is conservative for large
+    /// suites.
+    Bonferroni,
+    /// The Benjamini-Hochberg procedure, which keeps the expected fraction of reported changes
+    /// which are only noise at most the significance level. It keeps more real changes than
+    /// `Bonferroni`.
+    BenjaminiHochberg,
+}
+impl Correction {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Correction::Bonferroni => "Bonferroni",
+            Correction::BenjaminiHochberg => "Benjamini-Hochberg",
+        }
+    }
+}
+
+/// Enum used to select which benchmarks to run, or to skip.
+#[derive(Debug, Clone)]
+pub enum BenchmarkFilter {
+    /// Matches all benchmarks.
+    AcceptAll,
+    /// Matches the benchmarks whose ID matches the regular expression anywhere.
+    Regex(Regex),
+    /// Matches the benchmark whose full ID (eg. `group/function/parameter`) is exactly the given
+    /// string. This is the behavior of the `--exact` command-line option.
+    Exact(String),
+    /// Matches the benchmarks whose ID contains the given string.
+    Substring(String),
+    /// Matches the benchmarks matched by any of the given filters. This is the behavior of the
+    /// `--benchmarks-from` command-line option.
+    Any(Vec<BenchmarkFilter>),
+}
+impl BenchmarkFilter {
+    fn is_match(&self, id: &str) -> bool {
+        match self {
+            BenchmarkFilter::AcceptAll => true,
+            BenchmarkFilter::Regex(regex) => regex.is_match(id),
+            BenchmarkFilter::Exact(exact) => id == exact,
+            BenchmarkFilter::Substring(substring) => id.contains(&**substring),
+            BenchmarkFilter::Any(filters) => filters.iter().any(|filter| filter.is_match(id)),
+        }
+    }
+}
+
 /// Enum used to select the plotting backend.
 #[derive(Debug, Clone, Copy)]
 pub enum PlottingBackend {
@@ -279,6 +415,16 @@ pub enum PlottingBackend {
     /// Plotting backend which uses the rust 'Plotters' library. This is the default if `gnuplot`
     /// is not installed.
     Plotters,
+    /// Plotting backend which writes interactive HTML charts, drawn in the browser by the
+    /// Vega-Lite library. The charts show the exact values under the cursor, can be zoomed and
+    /// panned, and the functions of the line chart can be toggled from its legend. The library is
+    /// loaded from a CDN when the report is viewed.
+    Vega,
+    /// Plotting backend which writes no files, and draws the plots with text in the terminal
+    /// report instead: a histogram of the sample of every benchmark, and those of all the
+    /// benchmarks of each group over a common range. Useful on CI machines and over SSH. Uses
+    /// ASCII characters if the locale isn't UTF-8.
+    Terminal,
     /// Null plotting backend which outputs nothing,
     None,
 }
@@ -290,9 +436,160 @@ impl PlottingBackend {
             PlottingBackend::Plotters => Some(Box::new(PlottersBackend::default())),
             #[cfg(not(feature = "plotters"))]
             PlottingBackend::Plotters => panic!("Criterion was built without plotters support."),
-            PlottingBackend::None => None,
+            PlottingBackend::Vega => Some(Box::new(Vega)),
+            PlottingBackend::Terminal | PlottingBackend::None => None,
+        }
+    }
+}
+
+/// Enum used to select the file format of the plots, see `Criterion::plot_format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlotFormat {
+    /// PNG images.
+    Png,
+    /// SVG images. This is the default, and the only format supported by the plotters backend.
+    Svg,
+    /// PDF documents, eg. to include the plots in a paper.
+    Pdf,
+    /// Interactive HTML pages, the only format of the `PlottingBackend::Vega` backend.
+    Html,
+}
+impl PlotFormat {
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            PlotFormat::Png => "png",
+            PlotFormat::Svg => "svg",
+            PlotFormat::Pdf => "pdf",
+            PlotFormat::Html => "html",
+        }
+    }
+}
+
+/// The colors of the plots and the HTML report, see `Criterion::plot_theme`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    /// Dark text and axes on a white background. This is the default.
+    Light,
+    /// Light text and axes on a dark background, with a brighter palette for the comparison plots.
+    Dark,
+}
+
+/// The plots drawn for the HTML report, see `Criterion::plots`. Starts from `all`, `none`,
+/// `summary_only` or `per_benchmark_only`, and each kind of plot can then be turned on or off.
+///
+/// ```rust
+/// use criterion::PlotSelection;
+///
+/// // The summary plots of each group, and the PDF of each benchmark.
+/// let selection = PlotSelection::summary_only().pdf(true);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlotSelection {
+    pdf: bool,
+    regression: bool,
+    distributions: bool,
+    cdf: bool,
+    drift: bool,
+    t_test: bool,
+    summary: bool,
+    line_comparison: bool,
+}
+impl Default for PlotSelection {
+    fn default() -> PlotSelection {
+        PlotSelection::all()
+    }
+}
+impl PlotSelection {
+    /// Every plot. This is the default.
+    pub fn all() -> PlotSelection {
+        PlotSelection {
+            pdf: true,
+            regression: true,
+            distributions: true,
+            cdf: true,
+            drift: true,
+            t_test: true,
+            summary: true,
+            line_comparison: true,
+        }
+    }
+
+    /// No plot. The HTML report still shows the statistics of every benchmark.
+    pub fn none() -> PlotSelection {
+        PlotSelection {
+            pdf: false,
+            regression: false,
+            distributions: false,
+            cdf: false,
+            drift: false,
+            t_test: false,
+            summary: false,
+            line_comparison: false,
         }
     }
+
+    /// Only the plots comparing the benchmarks of each group: the violin or box plot, the line
+    /// chart and the speedup chart.
+    pub fn summary_only() -> PlotSelection {
+        PlotSelection::none().summary(true).line_comparison(true)
+    }
+
+    /// Only the plots of each benchmark, which are the slowest to draw for large groups.
+    pub fn per_benchmark_only() -> PlotSelection {
+        PlotSelection::all().summary(false).line_comparison(false)
+    }
+
+    /// Draw the PDF of each benchmark, alone and compared to the baseline.
+    pub fn pdf(mut self, enabled: bool) -> PlotSelection {
+        self.pdf = enabled;
+        self
+    }
+
+    /// Draw the regression of each benchmark, or its iteration times with flat sampling, alone and
+    /// compared to the baseline.
+    pub fn regression(mut self, enabled: bool) -> PlotSelection {
+        self.regression = enabled;
+        self
+    }
+
+    /// Draw the bootstrap distributions of the statistics of each benchmark and of their changes
+    /// from the baseline, the "Additional Plots" of its report.
+    pub fn distributions(mut self, enabled: bool) -> PlotSelection {
+        self.distributions = enabled;
+        self
+    }
+
+    /// Draw the CDF of each benchmark.
+    pub fn cdf(mut self, enabled: bool) -> PlotSelection {
+        self.cdf = enabled;
+        self
+    }
+
+    /// Draw the samples of each benchmark in the order they were measured, with their trend, if
+    /// they drift or are autocorrelated.
+    pub fn drift(mut self, enabled: bool) -> PlotSelection {
+        self.drift = enabled;
+        self
+    }
+
+    /// Draw the t test of each benchmark against the baseline.
+    pub fn t_test(mut self, enabled: bool) -> PlotSelection {
+        self.t_test = enabled;
+        self
+    }
+
+    /// Draw the violin or box plot of each group.
+    pub fn summary(mut self, enabled: bool) -> PlotSelection {
+        self.summary = enabled;
+        self
+    }
+
+    /// Draw the line chart of each group with numeric parameters, and its speedup chart with
+    /// `PlotConfiguration::speedup`.
+    pub fn line_comparison(mut self, enabled: bool) -> PlotSelection {
+        self.line_comparison = enabled;
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -301,11 +598,15 @@ pub(crate) enum Mode {
     /// Run benchmarks normally.
     Benchmark,
     /// List all benchmarks but do not run them.
-    List,
+    List(ListFormat),
     /// Run benchmarks once to verify that they work, but otherwise do not measure them.
     Test,
     /// Iterate benchmarks for a given length of time but do not analyze or report on them.
     Profile(Duration),
+    /// Count the benchmarks which would be run, for the `--progress` display. Prints nothing.
+    Count,
+    /// Print the settings of each benchmark and run it once, without measuring it.
+    DryRun,
 }
 impl Mode {
     pub fn is_benchmark(&self) -> bool {
@@ -313,6 +614,18 @@ impl Mode {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+/// Output format of the `--list` mode, chosen with `--format`.
+pub(crate) enum ListFormat {
+    /// `<id>: bench`, followed by the tags of the benchmark if it has any.
+    Pretty,
+    /// `<id>: bench` only, like libtest's terse format.
+    Terse,
+    /// One JSON object per benchmark and line, with the benchmark's group, function, parameter,
+    /// tags and throughput.
+    Json,
+}
+
 /// The benchmark manager
 ///
 /// `Criterion` lets you configure and execute benchmarks
@@ -329,11 +642,23 @@ impl Mode {
 /// benchmark.
 pub struct Criterion<M: Measurement = WallTime> {
     config: BenchmarkConfig,
-    filter: Option<Regex>,
+    filter: BenchmarkFilter,
+    skip_filters: Vec<BenchmarkFilter>,
+    tag_filters: Vec<String>,
+    excluded_tags: Vec<String>,
+    /// Glob patterns for the parts of the benchmark IDs. A benchmark must match one of the
+    /// patterns of every part for which there are any.
+    group_filters: Vec<String>,
+    function_filters: Vec<String>,
+    parameter_filters: Vec<String>,
     report: Reports,
     output_directory: PathBuf,
     baseline_directory: String,
+    /// Directory outside of the output directory which holds the baseline instead of
+    /// `<output directory>/<benchmark>/<baseline_directory>`, see `--save-baseline-to`.
+    external_baseline: Option<PathBuf>,
     baseline: Baseline,
+    missing_baseline: Option<MissingBaselinePolicy>,
     load_baseline: Option<String>,
     all_directories: HashSet<String>,
     all_titles: HashSet<String>,
@@ -341,6 +666,64 @@ pub struct Criterion<M: Measurement = WallTime> {
     profiler: Box<RefCell<dyn Profiler>>,
     connection: Option<MutexGuard<'static, Connection>>,
     mode: Mode,
+    noise_floor: bool,
+    fail_fast: bool,
+    correction: Option<Correction>,
+    compare_thresholds: analysis::CompareThresholds,
+    check_env: bool,
+    resume: bool,
+    exclusive: bool,
+    per_target_output: bool,
+    plot_overrides: PlotOverrides,
+    strict_env: bool,
+    fixed_unit: Option<Unit>,
+    significant_digits: Option<usize>,
+    keep_baselines: Option<usize>,
+    config_overrides: Vec<config_file::Override>,
+    /// Per-benchmark `--profile-time`, as (glob pattern, time) pairs. The last match wins.
+    profile_time_overrides: Vec<(String, Duration)>,
+    profiler_args: Vec<(String, String)>,
+    /// User-supplied metadata about the run, saved in the manifest of every benchmark.
+    labels: BTreeMap<String, String>,
+    /// Number of iterations of the heap-profiling phase of `--heap-profile`, if enabled.
+    heap_profile: Option<u64>,
+    /// Whether to bootstrap the statistics on a background pool, with `--background-analysis`.
+    background_analysis: bool,
+    /// Benchmarks which have been measured but whose analysis has not been reported yet.
+    pending_analyses: RefCell<VecDeque<analysis::Pending>>,
+    /// Whether to write the samples to disk as they are taken, with `--spill-samples`.
+    spill_samples: bool,
+    /// Whether to leave the gnuplot processes rendering while the next benchmark starts, with
+    /// `--defer-plots`.
+    defer_plots: bool,
+    /// Whether to skip regenerating the summary pages whose inputs have not changed; disabled by
+    /// `--no-report-cache`.
+    report_cache: bool,
+    /// Called around the measured region of each batch of iterations.
+    batch_hooks: hooks::Hooks,
+    /// Format of the plots, if the plotting backend supports it.
+    plot_format: PlotFormat,
+    plot_theme: Theme,
+    export_plot_scripts: bool,
+    plot_selection: PlotSelection,
+    /// Directory the plots are written to instead of the output directory, see
+    /// `Criterion::plot_output_dir`.
+    plot_output_directory: Option<PathBuf>,
+}
+
+/// Reads a newline-separated list of benchmark filters, as used by `--benchmarks-from` and
+/// `--skip-from`, ignoring empty lines and `#` comments. Exits if the file can't be read.
+fn read_filter_list(path: &str) -> Vec<String> {
+    let contents = std::fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("Unable to read benchmark list {}: {}", path, err);
+        std::process::exit(1);
+    });
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_owned)
+        .collect()
 }
 
 /// Returns the Cargo target directory, possibly calling `cargo metadata` to
@@ -374,15 +757,30 @@ impl Default for Criterion {
     /// - Confidence level: 0.95
     /// - Significance level: 0.05
     /// - Plotting: enabled, using gnuplot if available or plotters if gnuplot is not available
+    ///   (disabled on `wasm32-unknown-unknown`)
     /// - No filter
     fn default() -> Criterion {
         let reports = Reports {
             cli_enabled: true,
             cli: CliReport::new(false, false, CliVerbosity::Normal),
             bencher_enabled: false,
-            bencher: BencherReport,
+            bencher: BencherReport::default(),
             html: DEFAULT_PLOTTING_BACKEND.create_plotter().map(Html::new),
             csv_enabled: cfg!(feature = "csv_output"),
+            markdown_enabled: true,
+            badges_enabled: true,
+            #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+            sink: Some(SinkReport::new(sink::ConsoleSink)),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            sink: None,
+            json: None,
+            junit: None,
+            custom: vec![],
+            metrics: vec![],
+            #[cfg(feature = "csv_output")]
+            csv_export: None,
+            #[cfg(feature = "sqlite")]
+            history_db: None,
         };
 
         let mut criterion = Criterion {
@@ -395,22 +793,69 @@ impl Default for Criterion {
                 significance_level: 0.05,
                 warm_up_time: Duration::from_secs(3),
                 sampling_mode: SamplingMode::Auto,
+                regression_method: RegressionMethod::OrdinaryLeastSquares,
+                comparison_estimator: Estimator::Mean,
+                comparison_test: ComparisonTest::Bootstrap,
+                percentiles: vec![],
+                minimum_time: false,
+                outlier_policy: OutlierPolicy::Keep,
+                target_precision: None,
+                max_measurement_time: Duration::from_secs(60),
+                streaming_statistics: false,
+                bootstrap_seed: None,
                 quick_mode: false,
             },
-            filter: None,
+            filter: BenchmarkFilter::AcceptAll,
+            skip_filters: vec![],
+            tag_filters: vec![],
+            excluded_tags: vec![],
+            group_filters: vec![],
+            function_filters: vec![],
+            parameter_filters: vec![],
             report: reports,
             baseline_directory: "base".to_owned(),
+            external_baseline: None,
             baseline: Baseline::Save,
+            missing_baseline: None,
             load_baseline: None,
             output_directory: DEFAULT_OUTPUT_DIRECTORY.clone(),
             all_directories: HashSet::new(),
             all_titles: HashSet::new(),
             measurement: WallTime,
-            profiler: Box::new(RefCell::new(ExternalProfiler)),
+            profiler: profiler::default_profiler(),
             connection: CARGO_CRITERION_CONNECTION
                 .as_ref()
                 .map(|mtx| mtx.lock().unwrap()),
             mode: Mode::Benchmark,
+            noise_floor: false,
+            fail_fast: false,
+            correction: None,
+            compare_thresholds: analysis::CompareThresholds::default(),
+            check_env: false,
+            resume: false,
+            exclusive: false,
+            per_target_output: false,
+            plot_overrides: PlotOverrides::default(),
+            strict_env: false,
+            fixed_unit: None,
+            significant_digits: None,
+            keep_baselines: None,
+            config_overrides: vec![],
+            profile_time_overrides: vec![],
+            profiler_args: vec![],
+            labels: BTreeMap::new(),
+            heap_profile: None,
+            background_analysis: false,
+            pending_analyses: RefCell::new(VecDeque::new()),
+            spill_samples: false,
+            defer_plots: false,
+            report_cache: true,
+            batch_hooks: hooks::Hooks::default(),
+            plot_format: PlotFormat::Svg,
+            plot_theme: Theme::Light,
+            export_plot_scripts: false,
+            plot_selection: PlotSelection::all(),
+            plot_output_directory: None,
         };
 
         if criterion.connection.is_some() {
@@ -418,6 +863,8 @@ impl Default for Criterion {
             criterion.report.cli_enabled = false;
             criterion.report.bencher_enabled = false;
             criterion.report.csv_enabled = false;
+            criterion.report.markdown_enabled = false;
+            criterion.report.badges_enabled = false;
             criterion.report.html = None;
         }
         criterion
@@ -432,9 +879,17 @@ impl<M: Measurement> Criterion<M> {
         Criterion {
             config: self.config,
             filter: self.filter,
+            skip_filters: self.skip_filters,
+            tag_filters: self.tag_filters,
+            excluded_tags: self.excluded_tags,
+            group_filters: self.group_filters,
+            function_filters: self.function_filters,
+            parameter_filters: self.parameter_filters,
             report: self.report,
             baseline_directory: self.baseline_directory,
+            external_baseline: self.external_baseline,
             baseline: self.baseline,
+            missing_baseline: self.missing_baseline,
             load_baseline: self.load_baseline,
             output_directory: self.output_directory,
             all_directories: self.all_directories,
@@ -443,19 +898,141 @@ impl<M: Measurement> Criterion<M> {
             profiler: self.profiler,
             connection: self.connection,
             mode: self.mode,
+            noise_floor: self.noise_floor,
+            fail_fast: self.fail_fast,
+            correction: self.correction,
+            compare_thresholds: self.compare_thresholds,
+            check_env: self.check_env,
+            resume: self.resume,
+            exclusive: self.exclusive,
+            per_target_output: self.per_target_output,
+            plot_overrides: self.plot_overrides,
+            strict_env: self.strict_env,
+            fixed_unit: self.fixed_unit,
+            significant_digits: self.significant_digits,
+            keep_baselines: self.keep_baselines,
+            config_overrides: self.config_overrides,
+            profile_time_overrides: self.profile_time_overrides,
+            profiler_args: self.profiler_args,
+            labels: self.labels,
+            heap_profile: self.heap_profile,
+            background_analysis: self.background_analysis,
+            pending_analyses: self.pending_analyses,
+            spill_samples: self.spill_samples,
+            defer_plots: self.defer_plots,
+            report_cache: self.report_cache,
+            batch_hooks: self.batch_hooks,
+            plot_format: self.plot_format,
+            plot_theme: self.plot_theme,
+            export_plot_scripts: self.export_plot_scripts,
+            plot_selection: self.plot_selection,
+            plot_output_directory: self.plot_output_directory,
         }
     }
 
     #[must_use]
     /// Changes the internal profiler for benchmarks run with this runner. See
     /// the Profiler trait for more details.
-    pub fn with_profiler<P: Profiler + 'static>(self, p: P) -> Criterion<M> {
+    pub fn with_profiler<P: Profiler + 'static>(self, mut p: P) -> Criterion<M> {
+        if !self.profiler_args.is_empty() {
+            p.configure(&self.profiler_args);
+        }
         Criterion {
             profiler: Box::new(RefCell::new(p)),
             ..self
         }
     }
 
+    #[must_use]
+    /// Sends a summary of the results of every benchmark to `sink`, in addition to the usual
+    /// reports. See the [`sink`] module for details. On `wasm32-unknown-unknown`, this replaces the
+    /// default `sink::ConsoleSink`.
+    pub fn with_result_sink<S: ResultSink + 'static>(mut self, sink: S) -> Criterion<M> {
+        self.report.sink = Some(SinkReport::new(sink));
+        self
+    }
+
+    #[must_use]
+    /// Adds `report`, which is told about the progress and the results of every benchmark, in
+    /// addition to the usual reports. Can be called several times to add more reports. See the
+    /// [`sink`] module for details.
+    pub fn add_report(mut self, report: Box<dyn sink::Report>) -> Criterion<M> {
+        self.report.custom.push(CustomReport::new(report));
+        self
+    }
+
+    #[must_use]
+    /// Pushes the point estimates of the mean, median and standard deviation of every benchmark,
+    /// and the change of its mean and median compared to the baseline in percent, to `endpoint`
+    /// as soon as it has been analyzed, for long-term performance dashboards. The metrics are
+    /// keyed by benchmark ID and git commit: the `commit` label (see [`label`](Self::label)) if
+    /// there is one, otherwise the output of `git rev-parse HEAD`. Can be called several times to
+    /// push to several endpoints. Failures to push are printed as warnings. The `--pushgateway`
+    /// and `--statsd` command-line options do the same.
+    pub fn push_metrics(mut self, endpoint: MetricsEndpoint) -> Criterion<M> {
+        self.report.metrics.push(MetricsReport::new(endpoint));
+        self
+    }
+
+    #[must_use]
+    /// Writes the results of every benchmark to the file `path` as soon as it has been analyzed,
+    /// one JSON object per line, with its estimates and their confidence intervals, throughput,
+    /// change compared to the baseline, outlier counts and labels. The file is replaced by each
+    /// run. `--message-format=json` writes the same objects to standard output instead.
+    pub fn json_output(mut self, path: &Path) -> Criterion<M> {
+        self.report.json = Some(JsonReport::file(path.to_owned()));
+        self
+    }
+
+    #[must_use]
+    /// Writes a JUnit XML report to the file `path`, in which each benchmark is a test case that
+    /// fails if it has regressed compared to the baseline by more than its noise threshold (see
+    /// [`noise_threshold`](Self::noise_threshold) and `--compare-threshold`). CI servers like
+    /// Jenkins or GitLab CI then show the regressions like failed tests. The file is rewritten
+    /// after each benchmark with all the benchmarks of the run so far. The `--junit-output`
+    /// command-line option does the same.
+    pub fn junit_output(mut self, path: &Path) -> Criterion<M> {
+        self.report.junit = Some(JunitReport::new(path.to_owned()));
+        self
+    }
+
+    #[must_use]
+    #[cfg(feature = "csv_output")]
+    /// Writes the samples and the estimates of all the benchmarks of the run as CSV files in the
+    /// directory `dir`, for import into pandas, R or a spreadsheet. `samples.csv` has a row per
+    /// sample: the benchmark ID, group, function and parameter, the iteration count, the measured
+    /// value and its unit. `summary.csv` has a row per estimate (mean, median, standard deviation,
+    /// MAD and slope) with its confidence interval. The files are replaced by each run. The
+    /// `--export-csv` command-line option does the same.
+    pub fn export_csv(mut self, dir: &Path) -> Criterion<M> {
+        self.report.csv_export = Some(csv_report::CsvExport::new(dir.to_owned()));
+        self
+    }
+
+    #[must_use]
+    #[cfg(feature = "sqlite")]
+    /// Records the estimates of every benchmark in the SQLite database at `path` as soon as it has
+    /// been analyzed, with the time stamp and the git commit of the run (the `commit` label, see
+    /// [`label`](Self::label)). Unlike the files of the output directory, the database keeps every
+    /// run, for trend plots and long-term regression hunting; query it with
+    /// [`history_db::HistoryDatabase`]. The `--history-db` command-line option does the same.
+    pub fn history_database<P: AsRef<Path>>(mut self, path: P) -> Criterion<M> {
+        self.report.history_db = Some(history_db::HistoryDatabaseReport::new(
+            path.as_ref().to_owned(),
+        ));
+        self
+    }
+
+    #[must_use]
+    /// Calls `hooks` right before and right after the measured region of each batch of
+    /// iterations, eg. to mark the measurement windows in the capture of an external profiler.
+    /// Can be called several times to add more hooks; they are exited in reverse order. See the
+    /// [`hooks`] module for details.
+    pub fn with_batch_hooks<H: BatchHooks + 'static>(self, hooks: H) -> Criterion<M> {
+        self.batch_hooks.borrow_mut().push(Box::new(hooks));
+        self
+    }
+
     #[must_use]
     /// Set the plotting backend. By default, Criterion will use gnuplot if available, or plotters
     /// if not.
@@ -472,6 +1049,10 @@ impl<M: Measurement> Criterion<M> {
         }
 
         self.report.html = backend.create_plotter().map(Html::new);
+        self.report.cli.terminal_plots = match backend {
+            PlottingBackend::Terminal => Some(Charset::detect()),
+            _ => None,
+        };
         self
     }
 
@@ -558,6 +1139,125 @@ impl<M: Measurement> Criterion<M> {
         self
     }
 
+    #[must_use]
+    /// Changes the default method used to fit the slope of the iteration times, both for the
+    /// estimate and for the line of the regression plot. `RegressionMethod::TheilSen` is robust to
+    /// outlier samples.
+    pub fn regression_method(mut self, method: RegressionMethod) -> Criterion<M> {
+        self.config.regression_method = method;
+        self
+    }
+
+    #[must_use]
+    /// Changes the default statistic compared against the baseline. The significance test, the
+    /// noise threshold and the change printed and exported by the reports all use it. The default
+    /// is `Estimator::Mean`; `Estimator::Median` is less flaky for benchmarks whose iteration times
+    /// have heavy tails, where a few slow samples can move the mean a lot.
+    pub fn comparison_estimator(mut self, estimator: Estimator) -> Criterion<M> {
+        self.config.comparison_estimator = estimator;
+        self
+    }
+
+    #[must_use]
+    /// Changes the default hypothesis test whose p-value decides whether the change compared to
+    /// the baseline is significant. `ComparisonTest::MannWhitney` is a nonparametric alternative to
+    /// the default bootstrap test, better suited to skewed timings, and `ComparisonTest::Welch`
+    /// skips the bootstrap of the comparison for speed. Either way, the change checked against the
+    /// noise threshold is that of `comparison_estimator`.
+    pub fn comparison_test(mut self, test: ComparisonTest) -> Criterion<M> {
+        self.config.comparison_test = test;
+        self
+    }
+
+    #[must_use]
+    /// Changes the default percentiles of the average times, between 0 and 100, which are
+    /// estimated alongside the mean and the median, eg. `&[50.0, 90.0, 99.0]`. Tail latency often
+    /// matters more than the mean. Each percentile is bootstrapped for its confidence interval,
+    /// saved in `estimates.json`, printed by the command-line output and shown in the HTML report,
+    /// and the CDF and violin plots mark it. None are estimated by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a percentile is outside the range from 0 to 100.
+    pub fn percentiles(mut self, percentiles: &[f64]) -> Criterion<M> {
+        assert!(percentiles.iter().all(|p| (0.0..=100.0).contains(p)));
+
+        self.config.percentiles = percentiles.to_vec();
+        self
+    }
+
+    #[must_use]
+    /// Makes the minimum iteration time the headline statistic, instead of the slope or the mean:
+    /// the time printed and charted for each benchmark, kept in its history and exported by the
+    /// reports. For CPU-bound microbenchmarks the minimum is the measurement least contaminated by
+    /// noise, such as interrupts and the other processes. Its confidence interval is bootstrapped
+    /// from the minima of resamples of half the sample size; the minimum observed is its upper
+    /// bound. The change compared to the baseline is still that of
+    /// `comparison_estimator`.
+    pub fn minimum_time(mut self, enabled: bool) -> Criterion<M> {
+        self.config.minimum_time = enabled;
+        self
+    }
+
+    #[must_use]
+    /// Changes the default treatment of the outliers of the average times, which are otherwise only
+    /// classified and reported. `OutlierPolicy::Winsorize` clamps them to the inner Tukey fences
+    /// and `OutlierPolicy::Trim` leaves them out, before the estimates and the comparison with the
+    /// baseline are computed. The baseline's saved sample is treated the same way, whatever the
+    /// policy it was saved with, so both sides of the comparison are alike. The policy is recorded
+    /// in `manifest.json`; the saved sample keeps every measurement.
+    pub fn outlier_policy(mut self, policy: OutlierPolicy) -> Criterion<M> {
+        self.config.outlier_policy = policy;
+        self
+    }
+
+    #[must_use]
+    /// Makes the number of samples adapt to the noise of each benchmark: sampling stops as soon as
+    /// the 95% confidence interval of the mean of the average times is within `precision` (eg.
+    /// 0.01 for 1%) of the mean, or once `max_time` has been spent measuring. The iteration counts
+    /// of the samples are planned from `sample_size` and `measurement_time` as usual, and the plan
+    /// is repeated if it runs out, so stable benchmarks stop after a few samples and noisy ones
+    /// get more than `sample_size`. At least 10 samples are taken. The samples are kept in memory
+    /// even with `--spill-samples`. Quick mode takes precedence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the precision isn't positive or the maximum time is zero.
+    pub fn target_precision(mut self, precision: f64, max_time: Duration) -> Criterion<M> {
+        assert!(precision > 0.0);
+        assert!(max_time.as_nanos() > 0);
+
+        self.config.target_precision = Some(precision);
+        self.config.max_measurement_time = max_time;
+        self
+    }
+
+    #[must_use]
+    /// Computes the statistics online, to cap the memory and the analysis time of benchmarks with
+    /// huge sample counts. Each sample is folded into the running mean and variance of the
+    /// average times, and the sums of the least-squares slope, as soon as it is measured, and only
+    /// a uniform random reservoir of 10,000 samples is kept in memory. The reservoir is what is
+    /// saved, plotted, classified for outliers and compared to the baseline; the mean, standard
+    /// deviation and slope are those of all the samples, and the confidence intervals are
+    /// bootstrapped from the reservoir and narrowed to the number of samples. This makes no
+    /// difference for up to 10,000 samples. Target precision takes precedence.
+    pub fn streaming_statistics(mut self, enabled: bool) -> Criterion<M> {
+        self.config.streaming_statistics = enabled;
+        self
+    }
+
+    #[must_use]
+    /// Seeds the random number generator of the bootstrap, which is otherwise seeded from the
+    /// clock, so that the same samples always give the same estimates, confidence intervals and
+    /// p-values, bit for bit, eg. when comparing saved baselines with `--compare` or analyzing a
+    /// baseline again with `--load-baseline`. Each resample is drawn from its own stream of the
+    /// seed, so the results don't depend on the number of threads. The reservoir of
+    /// `streaming_statistics` is seeded too. The seed is recorded in `manifest.json`.
+    pub fn bootstrap_seed(mut self, seed: u64) -> Criterion<M> {
+        self.config.bootstrap_seed = Some(seed);
+        self
+    }
+
     #[must_use]
     /// Changes the default noise threshold for benchmarks run with this runner. The noise threshold
     /// is used to filter out small changes in performance, even if they are statistically
@@ -578,6 +1278,31 @@ impl<M: Measurement> Criterion<M> {
         self
     }
 
+    #[must_use]
+    /// Always display measured values in the given unit, rather than choosing a unit based on the
+    /// magnitude of each value. This makes the results of many benchmarks line up in the
+    /// command-line output and in the reports, and makes them easier to diff.
+    ///
+    /// This only applies to measurements which produce values in nanoseconds, such as the default
+    /// `WallTime` measurement, and is ignored for the others.
+    pub fn fixed_unit(mut self, unit: Unit) -> Criterion<M> {
+        self.fixed_unit = Some(unit);
+        self
+    }
+
+    #[must_use]
+    /// Always display measured values with the given number of significant digits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of digits is zero.
+    pub fn significant_digits(mut self, digits: usize) -> Criterion<M> {
+        assert!(digits > 0);
+
+        self.significant_digits = Some(digits);
+        self
+    }
+
     #[must_use]
     /// Changes the default confidence level for benchmarks run with this runner. The confidence
     /// level is the desired probability that the true runtime lies within the estimated
@@ -625,10 +1350,38 @@ impl<M: Measurement> Criterion<M> {
         self
     }
 
+    #[must_use]
+    /// Marks the benchmark group using this configuration as exclusive: when groups are run
+    /// concurrently with `--jobs`, no other group runs at the same time as this one. Use this for
+    /// benchmarks which use several threads themselves, or which are sensitive to other work
+    /// running on the machine, eg. because they measure memory bandwidth.
+    pub fn exclusive(mut self) -> Criterion<M> {
+        self.exclusive = true;
+        self
+    }
+
+    #[must_use]
+    /// Keep only the `n` most recently saved baselines of each benchmark. Older baselines are
+    /// deleted whenever a baseline is saved, and the raw samples of all kept baselines except the
+    /// current and the most recent one are compressed. By default, every baseline is kept forever.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn keep_baselines(mut self, n: usize) -> Criterion<M> {
+        assert!(n > 0);
+
+        self.keep_baselines = Some(n);
+        self
+    }
+
     #[must_use]
     /// Enables plotting
     pub fn with_plots(mut self) -> Criterion<M> {
         // If running under cargo-criterion then don't re-enable the reports; let it do the reporting.
+        if self.connection.is_none() {
+            self.report.badges_enabled = true;
+        }
         if self.connection.is_none() && self.report.html.is_none() {
             let default_backend = DEFAULT_PLOTTING_BACKEND.create_plotter();
             if let Some(backend) = default_backend {
@@ -641,12 +1394,34 @@ impl<M: Measurement> Criterion<M> {
     }
 
     #[must_use]
-    /// Disables plotting
+    /// Disables plotting, including the badges of the groups
     pub fn without_plots(mut self) -> Criterion<M> {
         self.report.html = None;
+        self.report.badges_enabled = false;
+        self.report.cli.terminal_plots = None;
         self
     }
 
+    #[must_use]
+    /// Applies a preset for fast local iteration, like the `--quick` command-line option:
+    ///
+    /// - Sample size: 10 measurements
+    /// - Warm-up time: 500 ms
+    /// - Measurement time: 1 s
+    /// - Quick mode: benchmarks stop as soon as the significance level is reached (see the
+    ///   "Quick Mode" page of the user guide)
+    /// - Plotting: disabled
+    ///
+    /// The results are much less precise than with the default settings, so this is meant for
+    /// checking the effect of a change rather than for results to be kept.
+    pub fn quick_mode(mut self) -> Criterion<M> {
+        self.config.sample_size = 10;
+        self.config.warm_up_time = Duration::from_millis(500);
+        self.config.measurement_time = Duration::from_secs(1);
+        self.config.quick_mode = true;
+        self.without_plots()
+    }
+
     #[must_use]
     /// Names an explicit baseline and enables overwriting the previous results.
     pub fn save_baseline(mut self, baseline: String) -> Criterion<M> {
@@ -655,6 +1430,36 @@ impl<M: Measurement> Criterion<M> {
         self
     }
 
+    #[must_use]
+    /// Saves the baseline in the given directory rather than in the output directory, comparing
+    /// against the baseline previously saved there (if any) first. The directory can be anywhere,
+    /// eg. in a separate repository or a CI cache, so that it survives `cargo clean`. Each
+    /// benchmark's files are stored in a subdirectory named after the benchmark.
+    pub fn save_baseline_to<P: Into<PathBuf>>(mut self, directory: P) -> Criterion<M> {
+        self.external_baseline = Some(directory.into());
+        self.baseline = Baseline::Save;
+        self
+    }
+
+    #[must_use]
+    /// Compares against the baseline saved in the given directory by
+    /// [`save_baseline_to`](Self::save_baseline_to), without overwriting it. If `strict` is true,
+    /// benchmarks which have no baseline in the directory are an error, as with
+    /// [`Baseline::CompareStrict`].
+    pub fn load_baseline_from<P: Into<PathBuf>>(
+        mut self,
+        directory: P,
+        strict: bool,
+    ) -> Criterion<M> {
+        self.external_baseline = Some(directory.into());
+        self.baseline = if strict {
+            Baseline::CompareStrict
+        } else {
+            Baseline::CompareLenient
+        };
+        self
+    }
+
     #[must_use]
     /// Names an explicit baseline and disables overwriting the previous results.
     pub fn retain_baseline(mut self, baseline: String, strict: bool) -> Criterion<M> {
@@ -667,6 +1472,26 @@ impl<M: Measurement> Criterion<M> {
         self
     }
 
+    #[must_use]
+    /// Changes what happens to the benchmarks which have no saved baseline when comparing against
+    /// one. By default they are an error with `strict` comparisons and run without a comparison
+    /// otherwise.
+    pub fn missing_baseline(mut self, policy: MissingBaselinePolicy) -> Criterion<M> {
+        self.missing_baseline = Some(policy);
+        self
+    }
+
+    #[must_use]
+    /// Corrects the p-values of all the comparisons against the baseline in the run for multiple
+    /// comparisons. At 5% significance, a suite of 200 benchmarks reports about ten changes which
+    /// are only noise every run; with a correction, the final summary lists the changes which
+    /// remain significant once the number of comparisons is taken into account. The verdicts
+    /// printed as each benchmark completes are unchanged.
+    pub fn multiple_comparison_correction(mut self, correction: Correction) -> Criterion<M> {
+        self.correction = Some(correction);
+        self
+    }
+
     #[must_use]
     /// Filters the benchmarks. Only benchmarks with names that contain the
     /// given string will be executed.
@@ -678,7 +1503,76 @@ impl<M: Measurement> Criterion<M> {
                 filter_text, err
             )
         });
-        self.filter = Some(filter);
+        self.filter = BenchmarkFilter::Regex(filter);
+
+        self
+    }
+
+    #[must_use]
+    /// Filters the benchmarks. Only benchmarks matching the given filter will be executed.
+    pub fn with_benchmark_filter(mut self, filter: BenchmarkFilter) -> Criterion<M> {
+        self.filter = filter;
+
+        self
+    }
+
+    #[must_use]
+    /// Skips the benchmarks matching the given filter, even if they match the filter set by
+    /// [`with_filter`](Self::with_filter). Can be called several times to skip several sets of
+    /// benchmarks.
+    pub fn with_skip_filter(mut self, filter: BenchmarkFilter) -> Criterion<M> {
+        self.skip_filters.push(filter);
+
+        self
+    }
+
+    #[must_use]
+    /// Runs only the benchmarks with the given tag (see
+    /// [`BenchmarkGroup::tag`](crate::BenchmarkGroup::tag)). If called several times, benchmarks
+    /// with any of the tags are run.
+    pub fn with_tag_filter<S: Into<String>>(mut self, tag: S) -> Criterion<M> {
+        self.tag_filters.push(tag.into());
+
+        self
+    }
+
+    #[must_use]
+    /// Skips the benchmarks with the given tag, even if they have a tag given to
+    /// [`with_tag_filter`](Self::with_tag_filter).
+    pub fn with_excluded_tag<S: Into<String>>(mut self, tag: S) -> Criterion<M> {
+        self.excluded_tags.push(tag.into());
+
+        self
+    }
+
+    #[must_use]
+    /// Runs only the benchmarks whose group name matches the glob `pattern`, where `*` matches any
+    /// sequence of characters and `?` any single character. If called several times, benchmarks
+    /// matching any of the patterns are run. Unlike [`with_filter`](Self::with_filter), this only
+    /// looks at the group part of the ID, like the `--group` command-line option.
+    pub fn with_group_filter<S: Into<String>>(mut self, pattern: S) -> Criterion<M> {
+        self.group_filters.push(pattern.into());
+
+        self
+    }
+
+    #[must_use]
+    /// Runs only the benchmarks whose function name matches the glob `pattern`, like
+    /// [`with_group_filter`](Self::with_group_filter) does for the group. Benchmarks without a
+    /// function name never match.
+    pub fn with_function_filter<S: Into<String>>(mut self, pattern: S) -> Criterion<M> {
+        self.function_filters.push(pattern.into());
+
+        self
+    }
+
+    #[must_use]
+    /// Runs only the benchmarks whose parameter matches the glob `pattern`, like
+    /// [`with_group_filter`](Self::with_group_filter) does for the group. For example,
+    /// `with_parameter_filter("4096")` runs every function for the input 4096 only. Benchmarks
+    /// without a parameter never match.
+    pub fn with_parameter_filter<S: Into<String>>(mut self, pattern: S) -> Criterion<M> {
+        self.parameter_filters.push(pattern.into());
 
         self
     }
@@ -691,15 +1585,32 @@ impl<M: Measurement> Criterion<M> {
         self
     }
 
-    /// Set the output directory (currently for testing only)
+    /// Sets the directory in which all results are stored: the measurements, baselines, plots and
+    /// HTML reports. Defaults to `$CRITERION_HOME`, or `criterion` in Cargo's target directory.
+    /// The links within the HTML reports are relative, so the directory can be moved or published
+    /// as a whole.
+    ///
+    /// The `output-directory` setting in `criterion.toml` and the `--output-dir` command-line
+    /// option take precedence over this.
     #[must_use]
-    #[doc(hidden)]
     pub fn output_directory(mut self, path: &Path) -> Criterion<M> {
         self.output_directory = path.to_owned();
 
         self
     }
 
+    /// Stores the results in a subdirectory of the output directory named after the target triple
+    /// and Cargo profile, eg. `target/criterion/x86_64-unknown-linux-gnu/release`. This keeps
+    /// benchmarks compiled for different targets or with different profiles from comparing against
+    /// or overwriting each other's baselines. The subdirectory is added when the command-line
+    /// arguments are parsed, so this must be called before
+    /// [`configure_from_args`](Self::configure_from_args).
+    #[must_use]
+    pub fn per_target_output_directory(mut self) -> Criterion<M> {
+        self.per_target_output = true;
+        self
+    }
+
     /// Set the profile time (currently for testing only)
     #[must_use]
     #[doc(hidden)]
@@ -712,19 +1623,230 @@ impl<M: Measurement> Criterion<M> {
         self
     }
 
-    /// Generate the final summary at the end of a run.
-    #[doc(hidden)]
-    pub fn final_summary(&self) {
+    #[must_use]
+    /// Overrides the profile time (see `--profile-time`) for the benchmarks whose full ID matches
+    /// the glob `pattern`, where `*` matches any sequence of characters and `?` any single
+    /// character. If several patterns match a benchmark, the one added last wins.
+    pub fn profile_time_for<S: Into<String>>(mut self, pattern: S, time: Duration) -> Criterion<M> {
+        self.profile_time_overrides.push((pattern.into(), time));
+
+        self
+    }
+
+    #[must_use]
+    /// Passes an argument through to the profiler (see
+    /// [`Profiler::configure`](crate::profiler::Profiler::configure)), like the
+    /// `--profiler-arg KEY=VALUE` command-line option.
+    pub fn profiler_arg<K: Into<String>, V: Into<String>>(
+        mut self,
+        key: K,
+        value: V,
+    ) -> Criterion<M> {
+        self.profiler_args.push((key.into(), value.into()));
+        self.profiler.borrow_mut().configure(&self.profiler_args);
+
+        self
+    }
+
+    #[must_use]
+    /// Runs every benchmark for the given number of iterations before measuring it, counting the
+    /// allocations it makes, like `--heap-profile`. The counts are saved with the results and
+    /// shown in the reports. This requires [`CountingAllocator`](crate::heap::CountingAllocator)
+    /// to be the global allocator; see the [`heap`](crate::heap) module.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of iterations is zero.
+    pub fn heap_profile(mut self, iterations: Option<u64>) -> Criterion<M> {
+        assert!(iterations != Some(0));
+        self.heap_profile = iterations;
+        self
+    }
+
+    #[must_use]
+    /// Bootstraps the statistics of each benchmark in the background while the next benchmarks are
+    /// set up and warmed up, like `--background-analysis`. The results are reported in order once
+    /// they are ready, at the latest at the end of the benchmark group, so this only helps groups
+    /// of several benchmarks. The background workers pause while samples are taken, so they don't
+    /// disturb the measurements. Requires the `rayon` feature; without it, the analysis runs
+    /// inline.
+    pub fn background_analysis(mut self, enabled: bool) -> Criterion<M> {
+        self.background_analysis = enabled;
+        self
+    }
+
+    #[must_use]
+    /// Writes the samples of each benchmark to a temporary file as they are taken and reads them
+    /// back through a memory map for the analysis, like `--spill-samples`, instead of keeping them
+    /// in memory. This bounds the memory used by very large sample sizes, especially with
+    /// [`background_analysis`](Criterion::background_analysis). Memory maps are only used on
+    /// Linux; elsewhere the samples are read back into memory for the analysis.
+    pub fn spill_samples(mut self, enabled: bool) -> Criterion<M> {
+        self.spill_samples = enabled;
+        self
+    }
+
+    #[must_use]
+    /// Leaves the gnuplot processes rendering the plots of each benchmark running at a lower
+    /// priority while the next benchmark is set up and warmed up, like `--defer-plots`, instead of
+    /// waiting for them before moving on. The sampling of the next benchmark waits for them, so
+    /// they never compete with a measurement. This has no effect with the plotters backend, which
+    /// renders the plots in-process.
+    pub fn defer_plots(mut self, enabled: bool) -> Criterion<M> {
+        self.defer_plots = enabled;
+        self
+    }
+
+    #[must_use]
+    /// Changes whether the summary pages of the HTML report whose inputs have not changed since
+    /// the previous run are skipped instead of regenerated, eg. the pages of the benchmarks which
+    /// were not run because of a filter. Enabled by default; `--no-report-cache` disables it.
+    pub fn report_cache(mut self, enabled: bool) -> Criterion<M> {
+        self.report_cache = enabled;
+        self
+    }
+
+    #[must_use]
+    /// Changes the format of the plots, and the links to them in the HTML report. Only the gnuplot
+    /// backend supports formats other than SVG; with the plotters backend, the plots are always
+    /// SVG images. Browsers show PDF plots as links rather than images. Defaults to SVG; the
+    /// `--plot-format` command-line option takes precedence over this.
+    pub fn plot_format(mut self, format: PlotFormat) -> Criterion<M> {
+        self.plot_format = format;
+        self
+    }
+
+    #[must_use]
+    /// Changes the colors of the plots and the HTML report, eg. to `Theme::Dark` to embed the
+    /// results in a documentation site with a dark background. Defaults to `Theme::Light`; the
+    /// `--plot-theme` command-line option takes precedence over this.
+    pub fn plot_theme(mut self, theme: Theme) -> Criterion<M> {
+        self.plot_theme = theme;
+        self
+    }
+
+    #[must_use]
+    /// Saves the gnuplot script of every plot next to it, as a `.gnuplot` file, along with a `.dat`
+    /// text file holding the data of the plot, which the script reads. The plots can then be
+    /// tweaked, eg. for a paper, and rendered again with `gnuplot pdf.gnuplot`. Only the gnuplot
+    /// backend has scripts. Defaults to false.
+    pub fn export_plot_scripts(mut self, enabled: bool) -> Criterion<M> {
+        self.export_plot_scripts = enabled;
+        self
+    }
+
+    #[must_use]
+    /// Chooses the plots drawn for the HTML report. Drawing every plot of every benchmark is slow
+    /// for groups of a hundred benchmarks or more, which may only need their summary plots, see
+    /// `PlotSelection::summary_only`. The report leaves out the plots which aren't drawn. Defaults
+    /// to `PlotSelection::all()`.
+    pub fn plots(mut self, selection: PlotSelection) -> Criterion<M> {
+        self.plot_selection = selection;
+        self
+    }
+
+    #[must_use]
+    /// Writes the plots to `path` instead of the output directory, eg. to put them in a
+    /// documentation tree or an artifacts directory. The plots keep the layout they have in the
+    /// output directory, `<benchmark>/report/<plot>`, while the measurements, baselines and HTML
+    /// reports stay in the output directory; the HTML reports then link to the plots by relative
+    /// path, or by `file://` URL if the two directories have no common root. The
+    /// `--plot-output-dir` command-line option takes precedence over this.
+    pub fn plot_output_dir(mut self, path: &Path) -> Criterion<M> {
+        self.plot_output_directory = Some(path.to_owned());
+        self
+    }
+
+    /// Reports the results of the benchmarks whose analysis is done, in the order the benchmarks
+    /// were measured. With `wait`, waits for the analysis of all of them.
+    pub(crate) fn finish_analyses(&self, wait: bool) {
+        loop {
+            let pending = {
+                let mut pending_analyses = self.pending_analyses.borrow_mut();
+                let ready = match pending_analyses.front_mut() {
+                    Some(pending) => wait || pending.is_done(),
+                    None => false,
+                };
+                if ready {
+                    pending_analyses.pop_front()
+                } else {
+                    None
+                }
+            };
+            match pending {
+                // Not borrowed while finishing.
+                Some(pending) => {
+                    pending.finish(self);
+                }
+                None => return,
+            }
+        }
+    }
+
+    #[must_use]
+    /// Attaches a label to the run, eg. the branch, pull request or machine pool it ran on, like
+    /// the `--label KEY=VALUE` command-line option. Labels are saved in the `manifest.json` of
+    /// every benchmark and included in the reports, so that results can be filtered by them and
+    /// traced back to the environment which produced them. Setting a label again replaces its
+    /// value.
+    ///
+    /// The labels `commit`, `cpu` and `rustc` are detected from the git checkout, the machine
+    /// and the compiler, unless they are set.
+    pub fn label<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Criterion<M> {
+        self.labels.insert(key.into(), value.into());
+
+        self
+    }
+
+    #[must_use]
+    /// Records metadata of the run, like the `--metadata KEY=VALUE` command-line option. The same
+    /// as [`label`](Criterion::label).
+    pub fn metadata<K: Into<String>, V: Into<String>>(self, key: K, value: V) -> Criterion<M> {
+        self.label(key, value)
+    }
+
+    /// Generate the final summary at the end of a run.
+    #[doc(hidden)]
+    pub fn final_summary(&self) {
         if !self.mode.is_benchmark() {
             return;
         }
+        enter_span!("final_summary");
 
         let report_context = ReportContext {
             output_directory: self.output_directory.clone(),
-            plot_config: PlotConfiguration::default(),
+            plot_directory: self.plot_directory(),
+            plot_config: self.plot_config(&PlotConfiguration::default()),
+            defer_plots: self.defer_plots,
+            report_cache: self.report_cache,
+            plot_format: self.report_plot_format(),
+            percentiles: self.config.percentiles.clone(),
         };
 
+        if self.noise_floor {
+            noise_floor::calibrate_end();
+            if let Some(noise_floor) = noise_floor::current() {
+                self.report.noise_floor(&noise_floor);
+                log_if_err!(fs::save(
+                    &noise_floor,
+                    &self.output_directory.join("noise_floor.json")
+                ));
+            }
+        }
+
+        plot::wait_until_quiet();
         self.report.final_summary(&report_context);
+        perf_markers::finish();
+
+        correction::summarize(&self.output_directory);
+        let mut failed = stop::take(&self.output_directory);
+        if missing_baseline::summarize(&self.output_directory) {
+            eprintln!("Error: some benchmarks had no saved baseline to compare against; try --save-baseline or --missing-baseline");
+            failed = true;
+        }
+        if failed {
+            std::process::exit(1);
+        }
     }
 
     /// Configure this criterion struct based on the command-line arguments to
@@ -737,6 +1859,61 @@ impl<M: Measurement> Criterion<M> {
             .arg(Arg::new("FILTER")
                 .help("Skip benchmarks whose names do not contain FILTER.")
                 .index(1))
+            .arg(Arg::new("exact")
+                .long("exact")
+                .help("Match FILTER and --skip patterns against the full benchmark ID exactly, rather than as a regular expression or substring."))
+            .arg(Arg::new("skip")
+                .long("skip")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .number_of_values(1)
+                .value_name("PATTERN")
+                .help("Skip benchmarks whose names contain PATTERN. May be given several times."))
+            .arg(Arg::new("tag")
+                .long("tag")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .number_of_values(1)
+                .help("Run only the benchmarks with this tag. May be given several times to run the benchmarks with any of the tags."))
+            .arg(Arg::new("exclude-tag")
+                .long("exclude-tag")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .number_of_values(1)
+                .value_name("TAG")
+                .help("Skip the benchmarks with this tag. May be given several times."))
+            .arg(Arg::new("group")
+                .long("group")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .number_of_values(1)
+                .value_name("PATTERN")
+                .help("Run only the benchmarks whose group matches the glob PATTERN. May be given several times to run the benchmarks matching any of the patterns."))
+            .arg(Arg::new("function")
+                .long("function")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .number_of_values(1)
+                .value_name("PATTERN")
+                .help("Run only the benchmarks whose function matches the glob PATTERN. May be given several times."))
+            .arg(Arg::new("param")
+                .long("param")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .number_of_values(1)
+                .value_name("PATTERN")
+                .help("Run only the benchmarks whose parameter matches the glob PATTERN, eg. '4096'. May be given several times."))
+            .arg(Arg::new("benchmarks-from")
+                .long("benchmarks-from")
+                .takes_value(true)
+                .value_name("FILE")
+                .conflicts_with("FILTER")
+                .help("Run only the benchmarks matching one of the filters in FILE, one per line. Empty lines and lines starting with '#' are ignored."))
+            .arg(Arg::new("skip-from")
+                .long("skip-from")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Skip the benchmarks matching one of the --skip patterns in FILE, one per line. Empty lines and lines starting with '#' are ignored."))
             .arg(Arg::new("color")
                 .short('c')
                 .long("color")
@@ -753,6 +1930,42 @@ impl<M: Measurement> Criterion<M> {
                 .long("quiet")
                 .conflicts_with("verbose")
                 .help("Print only the benchmark results."))
+            .arg(Arg::new("quiet-unless-changed")
+                .long("quiet-unless-changed")
+                .help("Print results only for benchmarks whose performance has improved or regressed compared to the baseline."))
+            .arg(Arg::new("plot-x-scale")
+                .long("plot-x-scale")
+                .takes_value(true)
+                .possible_values(["linear", "log"])
+                .help("Use this x axis scale for the summary plots of all groups, overriding their PlotConfiguration."))
+            .arg(Arg::new("output-dir")
+                .long("output-dir")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("Store all results (measurements, baselines, plots and HTML reports) in PATH instead of $CRITERION_HOME or target/criterion."))
+            .arg(Arg::new("plot-output-dir")
+                .long("plot-output-dir")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("Write the plots to PATH instead of the output directory. The measurements, baselines and HTML reports stay in the output directory."))
+            .arg(Arg::new("plot-size")
+                .long("plot-size")
+                .takes_value(true)
+                .value_name("WIDTHxHEIGHT")
+                .help("Size of the plots in pixels, eg. 1280x720, overriding their PlotConfiguration. Thumbnails keep their own size."))
+            .arg(Arg::new("plot-format")
+                .long("plot-format")
+                .takes_value(true)
+                .possible_values(["png", "svg", "pdf", "html"])
+                .help("Render the plots in this format. The plotters backend only renders svg and the vega backend only html, whatever the format."))
+            .arg(Arg::new("plot-theme")
+                .long("plot-theme")
+                .takes_value(true)
+                .possible_values(["light", "dark"])
+                .help("Draw the plots and style the HTML report with this theme."))
+            .arg(Arg::new("export-plot-scripts")
+                .long("export-plot-scripts")
+                .help("Save the gnuplot script and the data of every plot next to it, as .gnuplot and .dat files, to render them again."))
             .arg(Arg::new("noplot")
                 .short('n')
                 .long("noplot")
@@ -771,21 +1984,128 @@ impl<M: Measurement> Criterion<M> {
                 .long("baseline")
                 .takes_value(true)
                 .conflicts_with_all(&["save-baseline", "baseline-lenient"])
-                .help("Compare to a named baseline. If any benchmarks do not have the specified baseline this command fails."))
+                .help("Compare to a named baseline. If any benchmarks do not have the specified baseline this command fails, unless --missing-baseline says otherwise."))
             .arg(Arg::new("baseline-lenient")
                 .long("baseline-lenient")
                 .takes_value(true)
                 .conflicts_with_all(&["save-baseline", "baseline"])
                 .help("Compare to a named baseline. If any benchmarks do not have the specified baseline then just those benchmarks are not compared against the baseline while every other benchmark is compared against the baseline."))
+            .arg(Arg::new("save-baseline-to")
+                .long("save-baseline-to")
+                .takes_value(true)
+                .value_name("PATH")
+                .conflicts_with_all(&["discard-baseline", "baseline", "baseline-lenient", "load-baseline-from"])
+                .help("Compare to the baseline saved in the directory PATH, which can be outside of the target directory, then overwrite it."))
+            .arg(Arg::new("load-baseline-from")
+                .long("load-baseline-from")
+                .takes_value(true)
+                .value_name("PATH")
+                .conflicts_with_all(&["discard-baseline", "baseline", "baseline-lenient"])
+                .help("Compare to the baseline saved in the directory PATH by --save-baseline-to, without overwriting it. If any benchmarks do not have a baseline there this command fails, unless --missing-baseline says otherwise."))
+            .arg(Arg::new("correction")
+                .long("correction")
+                .takes_value(true)
+                .value_name("METHOD")
+                .possible_values(["bonferroni", "benjamini-hochberg"])
+                .help("Correct the p-values of all the comparisons against the baseline for multiple comparisons, and list the changes which remain significant at the end of the run. bonferroni controls the chance of any false change, benjamini-hochberg the expected fraction of false changes among those reported."))
+            .arg(Arg::new("missing-baseline")
+                .long("missing-baseline")
+                .takes_value(true)
+                .value_name("POLICY")
+                .possible_values(["error", "warn-and-run", "auto-create"])
+                .help("What to do with benchmarks which have no saved baseline when comparing against one. error = skip them and fail at the end (the default with --baseline), warn-and-run = run them without a comparison (the default with --baseline-lenient), auto-create = run them and save the results as the baseline."))
+            .arg(Arg::new("keep-baselines")
+                .long("keep-baselines")
+                .takes_value(true)
+                .help("Keep only this many of the most recently saved baselines of each benchmark, deleting older ones."))
+            .arg(Arg::new("gc-baselines")
+                .long("gc-baselines")
+                .help(&*format!("Apply the baseline retention policy (--keep-baselines, default {}) to every benchmark in the output directory, then exit without running any benchmarks.", retention::DEFAULT_KEEP)))
+            .arg(Arg::new("compare-baselines")
+                .long("compare-baselines")
+                .takes_value(true)
+                .number_of_values(2)
+                .value_names(&["A", "B"])
+                .help("Write a report page comparing the saved baselines A and B of every benchmark saved in both, then exit without running any benchmarks."))
+            .arg(Arg::new("compare")
+                .long("compare")
+                .takes_value(true)
+                .number_of_values(2)
+                .value_names(&["A", "B"])
+                .conflicts_with("compare-baselines")
+                .help("Compare the saved baseline B of every benchmark saved in both to its baseline A, print the changes and export them to the JSON and JUnit reports, then exit without running any benchmarks."))
             .arg(Arg::new("list")
                 .long("list")
                 .help("List all benchmarks")
                 .conflicts_with_all(&["test", "profile-time"]))
+            .arg(Arg::new("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(["pretty", "terse", "json"])
+                .requires("list")
+                .help("Output format of --list. json prints one JSON object per benchmark and line, with its ID, group, function, parameter, tags and throughput."))
+            .arg(Arg::new("dry-run")
+                .long("dry-run")
+                .conflicts_with_all(&["list", "test", "profile-time"])
+                .help("Print the settings each matching benchmark would be run with, and run its routine once to check that it works, without measuring or saving anything."))
             .arg(Arg::new("profile-time")
                 .long("profile-time")
                 .takes_value(true)
                 .help("Iterate each benchmark for approximately the given number of seconds, doing no analysis and without storing the results. Useful for running the benchmarks in a profiler.")
                 .conflicts_with_all(&["test", "list"]))
+            .arg(Arg::new("profile-time-for")
+                .long("profile-time-for")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .number_of_values(1)
+                .value_name("PATTERN=SECONDS")
+                .requires("profile-time")
+                .help("Override --profile-time for the benchmarks whose ID matches the glob PATTERN (eg. 'slow_group/*=30'). May be given several times; the last matching pattern wins."))
+            .arg(Arg::new("profiler-arg")
+                .long("profiler-arg")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .number_of_values(1)
+                .value_name("KEY=VALUE")
+                .help("Pass an argument through to the profiler used with --profile-time, eg. an output directory or sampling frequency. May be given several times."))
+            .arg(Arg::new("heap-profile")
+                .long("heap-profile")
+                .takes_value(true)
+                .value_name("ITERATIONS")
+                .help("Before measuring each benchmark, run it for ITERATIONS iterations and count its allocations. Requires criterion::heap::CountingAllocator as the global allocator."))
+            .arg(Arg::new("background-analysis")
+                .long("background-analysis")
+                .help("Bootstrap the statistics of each benchmark in the background while the next ones are warmed up. The background threads pause while samples are taken."))
+            .arg(Arg::new("spill-samples")
+                .long("spill-samples")
+                .help("Write the samples of each benchmark to a temporary file as they are taken and memory-map them for the analysis, to bound the memory used by large sample sizes."))
+            .arg(Arg::new("defer-plots")
+                .long("defer-plots")
+                .help("Leave the gnuplot processes rendering while the next benchmark is warmed up, at a lower priority. Sampling waits until they have finished."))
+            .arg(Arg::new("no-report-cache")
+                .long("no-report-cache")
+                .help("Regenerate every summary page of the HTML report, even those whose inputs have not changed since the previous run."))
+            .arg(Arg::new("perf-markers")
+                .long("perf-markers")
+                .takes_value(true)
+                .possible_values(["mmap", "ftrace"])
+                .value_name("METHOD")
+                .help("Emit events marking the warm-up, the measurement and each sample of every benchmark, so that a profile taken with perf record can be cut down to the measured region. 'mmap' needs no privileges; 'ftrace' needs write access to tracefs and perf record -e ftrace:print. Linux only."))
+            .arg(Arg::new("label")
+                .long("label")
+                .visible_alias("metadata")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .number_of_values(1)
+                .value_name("KEY=VALUE")
+                .help("Attach a label to the run, eg. 'branch=main', which is saved with the results and included in the reports. The commit, cpu and rustc labels are detected unless given. May be given several times."))
+            .arg(Arg::new("override")
+                .long("override")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .number_of_values(1)
+                .value_name("PATTERN:KEY=VALUE,...")
+                .help("Override settings for the benchmarks whose ID matches the glob PATTERN, eg. 'crypto/*:measurement-time=20,sample-size=200'. Takes the same keys as the [[benchmark]] tables of criterion.toml. May be given several times; later overrides win."))
             .arg(Arg::new("load-baseline")
                  .long("load-baseline")
                  .takes_value(true)
@@ -808,6 +2128,11 @@ impl<M: Measurement> Criterion<M> {
                 .long("nresamples")
                 .takes_value(true)
                 .help(&*format!("Changes the default number of resamples for this run. [default: {}]", self.config.nresamples)))
+            .arg(Arg::new("bootstrap-seed")
+                .long("bootstrap-seed")
+                .takes_value(true)
+                .value_name("SEED")
+                .help("Seed the random number generator of the bootstrap with this unsigned 64-bit integer, so that the same samples always give the same results."))
             .arg(Arg::new("noise-threshold")
                 .long("noise-threshold")
                 .takes_value(true)
@@ -820,10 +2145,88 @@ impl<M: Measurement> Criterion<M> {
                 .long("significance-level")
                 .takes_value(true)
                 .help(&*format!("Changes the default significance level for this run. [default: {}]", self.config.significance_level)))
+            .arg(Arg::new("percentiles")
+                .long("percentiles")
+                .takes_value(true)
+                .value_name("P,...")
+                .help("Estimate these percentiles of the average times, eg. 50,90,99, alongside the mean and the median."))
+            .arg(Arg::new("minimum-time")
+                .long("minimum-time")
+                .help("Report the minimum iteration time as the headline statistic of each benchmark, instead of the slope or the mean."))
+            .arg(Arg::new("outlier-policy")
+                .long("outlier-policy")
+                .takes_value(true)
+                .value_name("POLICY")
+                .possible_values(["keep", "winsorize", "trim"])
+                .help("How to treat the outliers of the average times before computing the estimates and the comparison with the baseline. keep = use every sample (the default), winsorize = clamp the outliers to the inner Tukey fences, trim = leave them out."))
+            .arg(Arg::new("target-precision")
+                .long("target-precision")
+                .takes_value(true)
+                .value_name("PRECISION")
+                .help("Sample each benchmark until the 95% confidence interval of the mean is within this fraction of the mean, eg. 0.01, instead of taking a fixed number of samples."))
+            .arg(Arg::new("max-measurement-time")
+                .long("max-measurement-time")
+                .takes_value(true)
+                .value_name("SECS")
+                .requires("target-precision")
+                .help(&*format!("The longest time to spend measuring each benchmark with --target-precision, in seconds. [default: {}]", self.config.max_measurement_time.as_secs_f64())))
+            .arg(Arg::new("progress")
+                .long("progress")
+                .help("Show the number of completed benchmarks and an estimate of the remaining time for the whole run."))
+            .arg(Arg::new("interactive")
+                .long("interactive")
+                .conflicts_with_all(&["list", "test", "profile-time", "dry-run"])
+                .help("Search, select and run the benchmarks interactively, and inspect their results. Requires the `interactive` feature."))
+            .arg(Arg::new("shuffle")
+                .long("shuffle")
+                .help("Run the benchmark groups and the functions within each group in random order. The seed is printed so that the order can be reproduced with --shuffle-seed."))
+            .arg(Arg::new("shuffle-seed")
+                .long("shuffle-seed")
+                .takes_value(true)
+                .value_name("SEED")
+                .help("Like --shuffle, but with the given seed (an unsigned 64-bit integer) so that the order is the same as in an earlier run."))
+            .arg(Arg::new("per-target-output")
+                .long("per-target-output")
+                .help("Store the results in a subdirectory of the output directory named after the target triple and Cargo profile, eg. x86_64-unknown-linux-gnu/release, so that builds for different targets or profiles have separate baselines."))
+            .arg(Arg::new("jobs")
+                .long("jobs")
+                .takes_value(true)
+                .value_name("N")
+                .help("Run up to N benchmark groups concurrently. Groups configured with Criterion::exclusive are run on their own. The results are only comparable if each group gets a core to itself; see --reserve-cores."))
+            .arg(Arg::new("reserve-cores")
+                .long("reserve-cores")
+                .takes_value(true)
+                .value_name("CORES")
+                .requires("jobs")
+                .help("Pin the --jobs worker threads to these cores, a comma-separated list of core IDs such as 2,3,4 (Linux only). Reserve the cores for benchmarking, eg. with isolcpus, so that nothing else runs on them."))
+            .arg(Arg::new("noise-floor")
+                .long("noise-floor")
+                .help("Time a fixed spin loop before the first and after the last benchmark to estimate the machine's noise floor."))
+            .arg(Arg::new("timer")
+                .long("timer")
+                .takes_value(true)
+                .value_name("wall|cpu|perf:EVENT|tsc")
+                .help("Select the measurement backend at run time, eg. 'cpu' or 'perf:cycles'. Requires the benchmarks to use criterion::measurement::Timer."))
+            .arg(Arg::new("compare-threshold")
+                .long("compare-threshold")
+                .takes_value(true)
+                .value_name("METRIC=THRESHOLD,...")
+                .help("Noise thresholds per metric for the comparison against the baseline, eg. 'time=3%,throughput=5%', replacing --noise-threshold. The metrics are time and throughput; a benchmark has regressed if it exceeds any of the thresholds which apply to it."))
+            .arg(Arg::new("fail-fast")
+                .long("fail-fast")
+                .help("Stop the run as soon as a benchmark has regressed beyond its noise threshold: the benchmarks left are skipped, and the run fails after the final summary."))
+            .arg(Arg::new("resume")
+                .long("resume")
+                .help("Continue an interrupted run: skip the benchmarks which were already completed by the previous run with the same settings."))
+            .arg(Arg::new("check-env")
+                .long("check-env")
+                .help("Check the machine's configuration (CPU frequency governor, turbo boost, SMT, ASLR, background load and power source) before the first benchmark and warn about settings which add noise. The findings are recorded with the results."))
+            .arg(Arg::new("strict-env")
+                .long("strict-env")
+                .help("Like --check-env, but refuse to run the benchmarks if any check fails."))
             .arg(Arg::new("quick")
                 .long("quick")
-                .conflicts_with("sample-size")
-                .help(&*format!("Benchmark only until the significance level has been reached [default: {}]", self.config.quick_mode)))
+                .help("Apply a preset for fast local iteration: 10 samples, 0.5s warm-up, 1s measurement, no plots, and benchmark only until the significance level has been reached. Other options override the preset."))
             .arg(Arg::new("test")
                 .hide(true)
                 .long("test")
@@ -835,14 +2238,47 @@ impl<M: Measurement> Criterion<M> {
             .arg(Arg::new("plotting-backend")
                  .long("plotting-backend")
                  .takes_value(true)
-                 .possible_values(&["gnuplot", "plotters"])
-                 .help("Set the plotting backend. By default, Criterion.rs will use the gnuplot backend if gnuplot is available, or the plotters backend if it isn't."))
+                 .possible_values(["gnuplot", "plotters", "vega", "terminal"])
+                 .help("Set the plotting backend. By default, Criterion.rs will use the gnuplot backend if gnuplot is available, or the plotters backend if it isn't. The vega backend draws interactive charts, and the terminal backend draws histograms in the terminal output instead of writing plots."))
             .arg(Arg::new("output-format")
                 .long("output-format")
                 .takes_value(true)
                 .possible_values(&["criterion", "bencher"])
                 .default_value("criterion")
                 .help("Change the CLI output format. By default, Criterion.rs will use its own format. If output format is set to 'bencher', Criterion.rs will print output in a format that resembles the 'bencher' crate."))
+            .arg(Arg::new("export-csv")
+                .long("export-csv")
+                .takes_value(true)
+                .value_name("DIR")
+                .help("Write the samples and the estimates of all the benchmarks to DIR/samples.csv and DIR/summary.csv."))
+            .arg(Arg::new("history-db")
+                .long("history-db")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Record the estimates of every benchmark, with the time stamp and the git commit, in the SQLite database FILE. Requires the `sqlite` feature."))
+            .arg(Arg::new("pushgateway")
+                .long("pushgateway")
+                .takes_value(true)
+                .value_name("URL")
+                .help("Push the estimates and changes of each benchmark to the Prometheus pushgateway at URL, eg. http://localhost:9091."))
+            .arg(Arg::new("statsd")
+                .long("statsd")
+                .takes_value(true)
+                .value_name("ADDRESS")
+                .help("Send the estimates and changes of each benchmark to the StatsD server at ADDRESS, eg. localhost:8125."))
+            .arg(Arg::new("junit-output")
+                .long("junit-output")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Write a JUnit XML report to FILE, with a test case per benchmark which fails if it has regressed beyond its noise threshold."))
+            .arg(Arg::new("message-format")
+                .long("message-format")
+                .takes_value(true)
+                .possible_values(["human", "json"])
+                .help("With json, print the results of each benchmark to stdout as one JSON object per line, with its estimates, throughput, change and outliers, instead of the usual output."))
+            .arg(Arg::new("bencher-flatten-ids")
+                .long("bencher-flatten-ids")
+                .help("With --output-format bencher, print benchmark IDs as 'group::function::parameter' with whitespace replaced by underscores, like the names of libtest benchmarks."))
             .arg(Arg::new("nocapture")
                 .long("nocapture")
                 .hide(true)
@@ -870,7 +2306,8 @@ https://bheisler.github.io/criterion.rs/book/faq.html
 ")
             .get_matches();
 
-        if self.connection.is_some() {
+        // The warnings are printed once, in the real run rather than the counting pass.
+        if self.connection.is_some() && !is_discovery_pass() {
             if let Some(color) = matches.value_of("color") {
                 if color != "auto" {
                     eprintln!("Warning: --color will be ignored when running with cargo-criterion. Use `cargo criterion --color {} -- <args>` instead.", color);
@@ -890,6 +2327,18 @@ https://bheisler.github.io/criterion.rs/book/faq.html
                     eprintln!("Warning: --output-format will be ignored when running with cargo-criterion. Use `cargo criterion --output-format {} -- <args>` instead.", format);
                 }
             }
+            if matches.is_present("output-dir") {
+                eprintln!("Warning: --output-dir will be ignored when running with cargo-criterion, which stores the results itself. Set CRITERION_HOME instead.");
+            }
+            if matches.is_present("message-format") {
+                eprintln!("Warning: --message-format will be ignored when running with cargo-criterion. Use `cargo criterion --message-format json -- <args>` instead.");
+            }
+            if matches.is_present("junit-output") {
+                eprintln!("Warning: --junit-output will be ignored when running with cargo-criterion, which does the reporting itself.");
+            }
+            if matches.is_present("plot-output-dir") {
+                eprintln!("Warning: --plot-output-dir will be ignored when running with cargo-criterion, which draws the plots itself.");
+            }
 
             if matches.is_present("baseline")
                 || matches
@@ -897,24 +2346,92 @@ https://bheisler.github.io/criterion.rs/book/faq.html
                     .map(|base| base != "base")
                     .unwrap_or(false)
                 || matches.is_present("load-baseline")
+                || matches.is_present("save-baseline-to")
+                || matches.is_present("load-baseline-from")
             {
                 eprintln!("Error: baselines are not supported when running with cargo-criterion.");
                 std::process::exit(1);
             }
         }
 
+        // Settings from criterion.toml replace those made in code, and are in turn replaced by the
+        // command-line options below.
+        match ConfigFile::find() {
+            Ok(Some(config_file)) => {
+                config_file.defaults.apply(&mut self.config);
+                if let Some(backend) = config_file.plotting_backend {
+                    self = self.plotting_backend(backend);
+                }
+                if let Some(dir) = config_file.output_directory {
+                    self.output_directory = dir;
+                }
+                if let Some(per_target_output) = config_file.per_target_output {
+                    self.per_target_output = per_target_output;
+                }
+                self.config_overrides = config_file.overrides;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        if let Some(dir) = matches.value_of("output-dir") {
+            if self.connection.is_none() {
+                self.output_directory = PathBuf::from(dir);
+            }
+        }
+
+        if let Some(timer) = matches.value_of("timer") {
+            // The backend was selected by `Timer::from_args`; other measurements cannot be changed.
+            let measurement = std::any::type_name::<M>();
+            let is_wall_time = measurement == std::any::type_name::<WallTime>();
+            if measurement != std::any::type_name::<Timer>() && !(timer == "wall" && is_wall_time) {
+                eprintln!(
+                    "Error: --timer requires the benchmarks to use criterion::measurement::Timer, eg. `Criterion::default().with_measurement(Timer::from_args())`, but they use {}.",
+                    measurement
+                );
+                std::process::exit(1);
+            }
+        }
+
+        // Applied after those from criterion.toml, so that they take precedence.
+        if let Some(overrides) = matches.values_of("override") {
+            for arg in overrides {
+                match config_file::Override::parse_cli(arg) {
+                    Ok(config_override) => self.config_overrides.push(config_override),
+                    Err(e) => {
+                        eprintln!("Invalid --override '{}': {}.", arg, e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+
         let bench = matches.is_present("bench");
         let test = matches.is_present("test");
-        let test_mode = match (bench, test) {
+        // Web browsers have no command line, so the benchmarks are run unless configured otherwise.
+        let no_command_line = cfg!(all(target_arch = "wasm32", target_os = "unknown"))
+            && env::args_os().next().is_none();
+        let test_mode = match (bench || no_command_line, test) {
             (true, true) => true,   // cargo bench -- --test should run tests
             (true, false) => false, // cargo bench should run benchmarks
             (false, _) => true,     // cargo test --benches should run tests
         };
 
-        self.mode = if test_mode {
+        self.mode = if is_discovery_pass() {
+            Mode::Count
+        } else if matches.is_present("dry-run") {
+            Mode::DryRun
+        } else if test_mode {
             Mode::Test
         } else if matches.is_present("list") {
-            Mode::List
+            let format = match matches.value_of("format") {
+                Some("terse") => ListFormat::Terse,
+                Some("json") => ListFormat::Json,
+                _ => ListFormat::Pretty,
+            };
+            Mode::List(format)
         } else if matches.is_present("profile-time") {
             let num_seconds = matches.value_of_t_or_exit("profile-time");
 
@@ -923,6 +2440,24 @@ https://bheisler.github.io/criterion.rs/book/faq.html
                 std::process::exit(1);
             }
 
+            if let Some(overrides) = matches.values_of("profile-time-for") {
+                for profile_time_override in overrides {
+                    let (pattern, time) = match profile_time_override.rsplit_once('=') {
+                        Some((pattern, time)) => (pattern, time.parse::<f64>().ok()),
+                        None => (profile_time_override, None),
+                    };
+                    match time {
+                        Some(time) if time >= 1.0 => {
+                            self = self.profile_time_for(pattern, Duration::from_secs_f64(time));
+                        }
+                        _ => {
+                            eprintln!("Invalid --profile-time-for '{}': expected PATTERN=SECONDS with at least one second.", profile_time_override);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+
             Mode::Profile(Duration::from_secs_f64(num_seconds))
         } else {
             Mode::Benchmark
@@ -933,21 +2468,179 @@ https://bheisler.github.io/criterion.rs/book/faq.html
             self.connection = None;
         }
 
+        let exact = matches.is_present("exact");
         if let Some(filter) = matches.value_of("FILTER") {
-            self = self.with_filter(filter);
+            if exact {
+                self = self.with_benchmark_filter(BenchmarkFilter::Exact(filter.to_owned()));
+            } else {
+                self = self.with_filter(filter);
+            }
+        }
+        if let Some(path) = matches.value_of("benchmarks-from") {
+            let filters = read_filter_list(path)
+                .into_iter()
+                .map(|filter| {
+                    if exact {
+                        BenchmarkFilter::Exact(filter)
+                    } else {
+                        Regex::new(&filter)
+                            .map(BenchmarkFilter::Regex)
+                            .unwrap_or_else(|err| {
+                                eprintln!(
+                                    "Unable to parse '{}' in {} as a regular expression: {}",
+                                    filter, path, err
+                                );
+                                std::process::exit(1);
+                            })
+                    }
+                })
+                .collect();
+            self = self.with_benchmark_filter(BenchmarkFilter::Any(filters));
+        }
+        let mut skips: Vec<String> = matches
+            .values_of("skip")
+            .map(|skips| skips.map(str::to_owned).collect())
+            .unwrap_or_default();
+        if let Some(path) = matches.value_of("skip-from") {
+            skips.extend(read_filter_list(path));
+        }
+        for skip in skips {
+            self = self.with_skip_filter(if exact {
+                BenchmarkFilter::Exact(skip)
+            } else {
+                BenchmarkFilter::Substring(skip)
+            });
+        }
+        if let Some(tags) = matches.values_of("tag") {
+            for tag in tags {
+                self = self.with_tag_filter(tag);
+            }
+        }
+        if let Some(tags) = matches.values_of("exclude-tag") {
+            for tag in tags {
+                self = self.with_excluded_tag(tag);
+            }
+        }
+        if let Some(patterns) = matches.values_of("group") {
+            for pattern in patterns {
+                self = self.with_group_filter(pattern);
+            }
+        }
+        if let Some(patterns) = matches.values_of("function") {
+            for pattern in patterns {
+                self = self.with_function_filter(pattern);
+            }
+        }
+        if let Some(patterns) = matches.values_of("param") {
+            for pattern in patterns {
+                self = self.with_parameter_filter(pattern);
+            }
         }
 
         match matches.value_of("plotting-backend") {
             // Use plotting_backend() here to re-use the panic behavior if Gnuplot is not available.
             Some("gnuplot") => self = self.plotting_backend(PlottingBackend::Gnuplot),
             Some("plotters") => self = self.plotting_backend(PlottingBackend::Plotters),
+            Some("vega") => self = self.plotting_backend(PlottingBackend::Vega),
+            Some("terminal") => self = self.plotting_backend(PlottingBackend::Terminal),
             Some(val) => panic!("Unexpected plotting backend '{}'", val),
             None => {}
         }
 
+        match matches.value_of("plot-x-scale") {
+            Some("linear") => self.plot_overrides.x_scale = Some(AxisScale::Linear),
+            Some("log") => self.plot_overrides.x_scale = Some(AxisScale::Logarithmic),
+            _ => {}
+        }
+        match matches.value_of("plot-format") {
+            Some("png") => self = self.plot_format(PlotFormat::Png),
+            Some("svg") => self = self.plot_format(PlotFormat::Svg),
+            Some("pdf") => self = self.plot_format(PlotFormat::Pdf),
+            Some("html") => self = self.plot_format(PlotFormat::Html),
+            Some(val) => panic!("Unexpected plot format '{}'", val),
+            None => {}
+        }
+        match matches.value_of("plot-theme") {
+            Some("light") => self = self.plot_theme(Theme::Light),
+            Some("dark") => self = self.plot_theme(Theme::Dark),
+            _ => {}
+        }
+        if let Some(size) = matches.value_of("plot-size") {
+            match parse_plot_size(size) {
+                Some(size) => self.plot_overrides.size = Some(size),
+                None => {
+                    eprintln!(
+                        "Invalid --plot-size '{}': expected WIDTHxHEIGHT in pixels, eg. 1280x720.",
+                        size
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+
         if matches.is_present("noplot") {
             self = self.without_plots();
         }
+        if matches.is_present("export-plot-scripts") {
+            self = self.export_plot_scripts(true);
+        }
+        if let Some(dir) = matches.value_of("plot-output-dir") {
+            self = self.plot_output_dir(Path::new(dir));
+        }
+
+        self.noise_floor = matches.is_present("noise-floor");
+        self.fail_fast = matches.is_present("fail-fast");
+        if let Some(spec) = matches.value_of("compare-threshold") {
+            match analysis::CompareThresholds::parse(spec) {
+                Ok(thresholds) => self.compare_thresholds = thresholds,
+                Err(e) => {
+                    eprintln!("Invalid --compare-threshold '{}': {}.", spec, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        self.strict_env = matches.is_present("strict-env");
+        self.resume = matches.is_present("resume");
+        self.check_env = matches.is_present("check-env") || self.strict_env;
+
+        if let Some(jobs) = matches.value_of("jobs") {
+            // The groups are scheduled by the parallel module before any group is configured;
+            // this only rejects invalid values.
+            let jobs = match jobs.parse::<usize>() {
+                Ok(jobs) if jobs > 0 => jobs,
+                _ => {
+                    eprintln!("Invalid --jobs '{}': expected a positive integer.", jobs);
+                    std::process::exit(1);
+                }
+            };
+            if let Some(cores) = matches.value_of("reserve-cores") {
+                match parallel::parse_cores(cores) {
+                    Some(ids) if ids.len() >= jobs => {
+                        if let Some(e) = ids.iter().find_map(|&id| parallel::check_core(id).err()) {
+                            eprintln!("Invalid --reserve-cores '{}': {}.", cores, e);
+                            std::process::exit(1);
+                        }
+                        if jobs == 1 && !is_discovery_pass() {
+                            parallel::warn_unpinned();
+                        }
+                    }
+                    _ => {
+                        eprintln!("Invalid --reserve-cores '{}': expected a comma-separated list of at least {} core IDs.", cores, jobs);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            if jobs > 1 && self.connection.is_some() {
+                eprintln!("Error: --jobs is not supported when running with cargo-criterion.");
+                std::process::exit(1);
+            }
+        }
+
+        if matches.is_present("shuffle-seed") {
+            // The seed itself is read by the shuffle module before any group is configured; this
+            // only rejects invalid values.
+            let _: u64 = matches.value_of_t_or_exit("shuffle-seed");
+        }
 
         if let Some(dir) = matches.value_of("save-baseline") {
             self.baseline = Baseline::Save;
@@ -964,18 +2657,52 @@ https://bheisler.github.io/criterion.rs/book/faq.html
             self.baseline = Baseline::CompareLenient;
             self.baseline_directory = dir.to_owned();
         }
+        match matches.value_of("correction") {
+            Some("bonferroni") => {
+                self = self.multiple_comparison_correction(Correction::Bonferroni)
+            }
+            Some("benjamini-hochberg") => {
+                self = self.multiple_comparison_correction(Correction::BenjaminiHochberg)
+            }
+            _ => {}
+        }
+        match matches.value_of("missing-baseline") {
+            Some("error") => self = self.missing_baseline(MissingBaselinePolicy::Error),
+            Some("warn-and-run") => self = self.missing_baseline(MissingBaselinePolicy::WarnAndRun),
+            Some("auto-create") => self = self.missing_baseline(MissingBaselinePolicy::AutoCreate),
+            _ => {}
+        }
+        if let Some(dir) = matches.value_of("save-baseline-to") {
+            self = self.save_baseline_to(dir);
+        }
+        if let Some(dir) = matches.value_of("load-baseline-from") {
+            self = self.load_baseline_from(dir, true);
+        }
 
         if self.connection.is_some() {
             // disable all reports when connected to cargo-criterion; it will do the reporting.
             self.report.cli_enabled = false;
             self.report.bencher_enabled = false;
             self.report.csv_enabled = false;
+            self.report.markdown_enabled = false;
+            self.report.badges_enabled = false;
             self.report.html = None;
+            self.report.json = None;
+            self.report.junit = None;
+            #[cfg(feature = "csv_output")]
+            {
+                self.report.csv_export = None;
+            }
+            #[cfg(feature = "sqlite")]
+            {
+                self.report.history_db = None;
+            }
         } else {
             match matches.value_of("output-format") {
                 Some("bencher") => {
                     self.report.bencher_enabled = true;
                     self.report.cli_enabled = false;
+                    self.report.bencher.flatten_ids = matches.is_present("bencher-flatten-ids");
                 }
                 _ => {
                     let verbose = matches.is_present("verbose");
@@ -987,7 +2714,10 @@ https://bheisler.github.io/criterion.rs/book/faq.html
                         CliVerbosity::Normal
                     };
                     let stdout_isatty = atty::is(atty::Stream::Stdout);
-                    let mut enable_text_overwrite = stdout_isatty && !verbose && !debug_enabled();
+                    // Status lines of concurrently running groups can't overwrite each other.
+                    let concurrent = matches!(matches.value_of("jobs"), Some(jobs) if jobs != "1");
+                    let mut enable_text_overwrite =
+                        stdout_isatty && !verbose && !debug_enabled() && !concurrent;
                     let enable_text_coloring;
                     match matches.value_of("color") {
                         Some("always") => {
@@ -1001,16 +2731,88 @@ https://bheisler.github.io/criterion.rs/book/faq.html
                     };
                     self.report.bencher_enabled = false;
                     self.report.cli_enabled = true;
+                    let terminal_plots = self.report.cli.terminal_plots;
                     self.report.cli =
                         CliReport::new(enable_text_overwrite, enable_text_coloring, verbosity);
+                    self.report.cli.terminal_plots = terminal_plots;
+                    self.report.cli.show_progress = matches.is_present("progress");
+                    self.report.cli.quiet_unless_changed =
+                        matches.is_present("quiet-unless-changed");
                 }
             };
+            if matches.value_of("message-format") == Some("json") {
+                // Standard output only holds the JSON objects.
+                self.report.json = Some(JsonReport::stdout());
+                self.report.cli_enabled = false;
+                self.report.bencher_enabled = false;
+            }
+            if let Some(url) = matches.value_of("pushgateway") {
+                self = self.push_metrics(MetricsEndpoint::Pushgateway(url.to_owned()));
+            }
+            if let Some(address) = matches.value_of("statsd") {
+                self = self.push_metrics(MetricsEndpoint::Statsd(address.to_owned()));
+            }
+            if let Some(path) = matches.value_of("junit-output") {
+                self = self.junit_output(Path::new(path));
+            }
+            if let Some(dir) = matches.value_of("export-csv") {
+                #[cfg(feature = "csv_output")]
+                {
+                    self = self.export_csv(Path::new(dir));
+                }
+                #[cfg(not(feature = "csv_output"))]
+                {
+                    eprintln!(
+                        "Error: --export-csv {} requires the `csv_output` feature of criterion.",
+                        dir
+                    );
+                    std::process::exit(1);
+                }
+            }
+            if let Some(path) = matches.value_of("history-db") {
+                #[cfg(feature = "sqlite")]
+                {
+                    self = self.history_database(path);
+                }
+                #[cfg(not(feature = "sqlite"))]
+                {
+                    eprintln!(
+                        "Error: --history-db {} requires the `sqlite` feature of criterion.",
+                        path
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        if let Some(args) = matches.values_of("profiler-arg") {
+            for arg in args {
+                let (key, value) = arg.split_once('=').unwrap_or((arg, ""));
+                self = self.profiler_arg(key, value);
+            }
+        }
+
+        if let Some(labels) = matches.values_of("label") {
+            for label in labels {
+                match label.split_once('=') {
+                    Some((key, value)) if !key.is_empty() => self = self.label(key, value),
+                    _ => {
+                        eprintln!("Invalid label '{}': expected KEY=VALUE.", label);
+                        std::process::exit(1);
+                    }
+                }
+            }
         }
 
         if let Some(dir) = matches.value_of("load-baseline") {
             self.load_baseline = Some(dir.to_owned());
         }
 
+        // Applied first so that the explicit options below take precedence over the preset.
+        if matches.is_present("quick") {
+            self = self.quick_mode();
+        }
+
         if matches.is_present("sample-size") {
             let num_size = matches.value_of_t_or_exit("sample-size");
 
@@ -1061,18 +2863,290 @@ https://bheisler.github.io/criterion.rs/book/faq.html
 
             self.config.significance_level = num_significance_level;
         }
+        if matches.is_present("minimum-time") {
+            self.config.minimum_time = true;
+        }
+        match matches.value_of("outlier-policy") {
+            Some("keep") => self.config.outlier_policy = OutlierPolicy::Keep,
+            Some("winsorize") => self.config.outlier_policy = OutlierPolicy::Winsorize,
+            Some("trim") => self.config.outlier_policy = OutlierPolicy::Trim,
+            _ => {}
+        }
+        if matches.is_present("bootstrap-seed") {
+            self = self.bootstrap_seed(matches.value_of_t_or_exit("bootstrap-seed"));
+        }
+        if matches.is_present("target-precision") {
+            let precision: f64 = matches.value_of_t_or_exit("target-precision");
+            let max_time = if matches.is_present("max-measurement-time") {
+                let num_seconds: f64 = matches.value_of_t_or_exit("max-measurement-time");
+                Duration::from_secs_f64(num_seconds)
+            } else {
+                self.config.max_measurement_time
+            };
+            self = self.target_precision(precision, max_time);
+        }
+        if let Some(spec) = matches.value_of("percentiles") {
+            let percentiles: Option<Vec<f64>> = spec
+                .split(',')
+                .map(|p| p.trim().parse().ok().filter(|p| (0.0..=100.0).contains(p)))
+                .collect();
+            match percentiles {
+                Some(percentiles) => self.config.percentiles = percentiles,
+                None => {
+                    eprintln!("Invalid --percentiles '{}': expected a comma-separated list of percentiles between 0 and 100, eg. 50,90,99.", spec);
+                    std::process::exit(1);
+                }
+            }
+        }
 
-        if matches.is_present("quick") {
-            self.config.quick_mode = true;
+        if matches.is_present("heap-profile") {
+            let iterations: u64 = matches.value_of_t_or_exit("heap-profile");
+
+            assert!(iterations > 0);
+
+            self.heap_profile = Some(iterations);
+        }
+
+        if matches.is_present("background-analysis") {
+            self.background_analysis = true;
+        }
+        if matches.is_present("spill-samples") {
+            self.spill_samples = true;
+        }
+        if matches.is_present("defer-plots") {
+            self.defer_plots = true;
+        }
+        if matches.is_present("no-report-cache") {
+            self.report_cache = false;
+        }
+
+        if let Some(method) = matches
+            .value_of("perf-markers")
+            .filter(|_| !is_discovery_pass())
+        {
+            let method = perf_markers::Method::from_arg(method).unwrap();
+            if let Err(e) = perf_markers::enable(method) {
+                eprintln!("Warning: unable to enable --perf-markers: {}.", e);
+            }
+        }
+
+        if matches.is_present("keep-baselines") {
+            let keep = matches.value_of_t_or_exit("keep-baselines");
+
+            assert!(keep > 0);
+
+            self.keep_baselines = Some(keep);
+        }
+
+        // cargo-criterion manages its own output directory.
+        if (self.per_target_output || matches.is_present("per-target-output"))
+            && self.connection.is_none()
+        {
+            self.output_directory = self.output_directory.join(target_subdirectory());
+        }
+
+        if matches.is_present("interactive") {
+            if cfg!(not(feature = "interactive")) {
+                eprintln!("Error: --interactive requires the `interactive` feature of criterion.");
+                std::process::exit(1);
+            }
+            if self.connection.is_some() {
+                eprintln!("Error: --interactive cannot be used with cargo-criterion.");
+                std::process::exit(1);
+            }
+        }
+        #[cfg(feature = "interactive")]
+        if let Some(selection) = interactive::selection() {
+            self.filter =
+                BenchmarkFilter::Any(selection.into_iter().map(BenchmarkFilter::Exact).collect());
+            self.report.cli.show_progress = true;
+        }
+
+        // The commands below, which do their work and exit, are left to the real run.
+        if is_discovery_pass() {
+            return self;
+        }
+
+        if let Some(mut baselines) = matches.values_of("compare-baselines") {
+            let baseline_a = baselines.next().unwrap();
+            let baseline_b = baselines.next().unwrap();
+            match html::baselines::compare(
+                &self.output_directory,
+                baseline_a,
+                baseline_b,
+                self.plot_theme == Theme::Dark,
+            ) {
+                Ok((_, 0)) => {
+                    eprintln!(
+                        "Error: no benchmark has both the baselines {} and {}.",
+                        baseline_a, baseline_b
+                    );
+                    std::process::exit(1);
+                }
+                Ok((path, count)) => {
+                    println!("Compared {} benchmarks, see {}", count, path.display());
+                    std::process::exit(0);
+                }
+                Err(e) => {
+                    error::log_error(&e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        if let Some(mut baselines) = matches.values_of("compare") {
+            let baseline_a = baselines.next().unwrap();
+            let baseline_b = baselines.next().unwrap();
+            match analysis::compare_baselines(&self, baseline_a, baseline_b) {
+                Ok(summary) if summary.compared == 0 => {
+                    eprintln!(
+                        "Error: no benchmark has both the baselines {} and {}.",
+                        baseline_a, baseline_b
+                    );
+                    std::process::exit(1);
+                }
+                Ok(summary) => {
+                    if self.report.cli_enabled {
+                        println!(
+                            "Compared {} benchmarks, {} of which regressed.",
+                            summary.compared, summary.regressed
+                        );
+                    }
+                    std::process::exit(0);
+                }
+                Err(e) => {
+                    error::log_error(&e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        if matches.is_present("gc-baselines") {
+            let keep = self.keep_baselines.unwrap_or(retention::DEFAULT_KEEP);
+            match retention::collect_garbage(&self.output_directory, keep, &self.baseline_directory)
+            {
+                Ok(summary) => {
+                    println!(
+                        "Removed {} old baselines and compressed {} raw sample files.",
+                        summary.removed, summary.compressed
+                    );
+                    std::process::exit(0);
+                }
+                Err(e) => {
+                    error::log_error(&e);
+                    std::process::exit(1);
+                }
+            }
         }
 
         self
     }
 
-    fn filter_matches(&self, id: &str) -> bool {
-        match &self.filter {
-            Some(regex) => regex.is_match(id),
-            None => true,
+    fn filter_matches(&self, id: &crate::report::BenchmarkId) -> bool {
+        let has_tag = |wanted: &String| id.tags.contains(wanted);
+        let part_matches = |patterns: &[String], part: Option<&String>| {
+            patterns.is_empty()
+                || matches!(part, Some(part) if patterns
+                    .iter()
+                    .any(|pattern| config_file::glob_matches(pattern, part)))
+        };
+        let full_id = id.id();
+        self.filter.is_match(full_id)
+            && !self.skip_filters.iter().any(|skip| skip.is_match(full_id))
+            && (self.tag_filters.is_empty() || self.tag_filters.iter().any(has_tag))
+            && !self.excluded_tags.iter().any(has_tag)
+            && part_matches(&self.group_filters, Some(&id.group_id))
+            && part_matches(&self.function_filters, id.function_id.as_ref())
+            && part_matches(&self.parameter_filters, id.value_str.as_ref())
+    }
+
+    /// Returns the profile time of the benchmark `id`, taking overrides into account.
+    fn resolve_profile_time(&self, id: &str, default: Duration) -> Duration {
+        self.profile_time_overrides
+            .iter()
+            .rev()
+            .find(|(pattern, _)| config_file::glob_matches(pattern, id))
+            .map_or(default, |&(_, time)| time)
+    }
+
+    /// Applies the per-benchmark settings from criterion.toml and `--override` whose pattern
+    /// matches `id`.
+    fn apply_config_overrides(&self, id: &str, config: &mut BenchmarkConfig) {
+        for config_override in &self.config_overrides {
+            if config_override.matches(id) {
+                config_override.settings.apply(config);
+            }
+        }
+    }
+
+    /// The measurement's formatter, with the configured unit and significant digits applied.
+    pub(crate) fn formatter(&self) -> FixedFormatter<'_> {
+        FixedFormatter {
+            inner: self.measurement.formatter(),
+            unit: self.fixed_unit,
+            significant_digits: self.significant_digits,
+        }
+    }
+
+    /// Returns the format the plots are actually rendered in, SVG if the plotting backend does not
+    /// support the one requested.
+    pub(crate) fn report_plot_format(&self) -> PlotFormat {
+        match &self.report.html {
+            Some(html) if html.supports(self.plot_format) => self.plot_format,
+            Some(html) => html.default_format(),
+            None => PlotFormat::Svg,
+        }
+    }
+
+    /// Returns the directory the plots are written to.
+    pub(crate) fn plot_directory(&self) -> PathBuf {
+        self.plot_output_directory
+            .clone()
+            .unwrap_or_else(|| self.output_directory.clone())
+    }
+
+    /// Returns the given plot configuration with the settings given on the command line and the
+    /// theme of the plots applied.
+    pub(crate) fn plot_config(&self, config: &PlotConfiguration) -> PlotConfiguration {
+        let mut config = self.plot_overrides.apply(config.clone());
+        config.theme = self.plot_theme;
+        config.export_scripts = self.export_plot_scripts;
+        config.plots = self.plot_selection;
+        config
+    }
+
+    /// Returns the directory holding the baseline of the given benchmark.
+    pub(crate) fn baseline_dir(&self, id: &crate::report::BenchmarkId) -> PathBuf {
+        match &self.external_baseline {
+            Some(directory) => directory.join(id.as_directory_name()),
+            None => self
+                .output_directory
+                .join(id.as_directory_name())
+                .join(&self.baseline_directory),
+        }
+    }
+
+    /// Describes the baseline for error messages.
+    pub(crate) fn baseline_name(&self) -> String {
+        match &self.external_baseline {
+            Some(directory) => directory.display().to_string(),
+            None => self.baseline_directory.clone(),
+        }
+    }
+
+    /// Returns the policy for benchmarks which have no saved baseline, or `None` if the baseline
+    /// is not being compared against.
+    pub(crate) fn missing_baseline_policy(&self) -> Option<MissingBaselinePolicy> {
+        match self.baseline {
+            Baseline::CompareStrict => Some(
+                self.missing_baseline
+                    .unwrap_or(MissingBaselinePolicy::Error),
+            ),
+            Baseline::CompareLenient => Some(
+                self.missing_baseline
+                    .unwrap_or(MissingBaselinePolicy::WarnAndRun),
+            ),
+            Baseline::Save | Baseline::Discard => None,
         }
     }
 
@@ -1111,7 +3185,8 @@ https://bheisler.github.io/criterion.rs/book/faq.html
         let group_name = group_name.into();
         assert!(!group_name.is_empty(), "Group name must not be empty.");
 
-        if let Some(conn) = &self.connection {
+        let counting = matches!(self.mode, Mode::Count);
+        if let Some(conn) = self.connection.as_ref().filter(|_| !counting) {
             conn.send(&OutgoingMessage::BeginningBenchmarkGroup { group: &group_name })
                 .unwrap();
         }
@@ -1153,6 +3228,29 @@ where
         self
     }
 
+    /// Analyzes samples collected elsewhere, eg. on an embedded device with the `criterion-core`
+    /// crate, as if they had been measured here. The benchmark is named after the ID of the
+    /// samples. For several sets of samples in one group, see `BenchmarkGroup::bench_samples`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use criterion::{Criterion, Samples};
+    ///
+    /// fn bench(c: &mut Criterion) {
+    ///     // eg. read from a serial port
+    ///     let output = std::fs::read_to_string("device-output.txt").unwrap_or_default();
+    ///     for samples in Samples::parse_all(&output).unwrap() {
+    ///         c.bench_samples(&samples);
+    ///     }
+    /// }
+    /// ```
+    pub fn bench_samples(&mut self, samples: &Samples) -> &mut Criterion<M> {
+        self.benchmark_group(samples.id.clone())
+            .bench_samples(BenchmarkId::no_function(), samples);
+        self
+    }
+
     /// Benchmarks a function with an input. For comparing multiple functions or multiple inputs,
     /// see `benchmark_group`.
     ///
@@ -1231,6 +3329,90 @@ pub enum AxisScale {
     Logarithmic,
 }
 
+/// The plot comparing the distributions of the benchmarks on the summary page of a group, see
+/// `PlotConfiguration::summary_plot`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummaryPlot {
+    /// A violin per benchmark, the kernel density estimate of its sample.
+    Violin,
+
+    /// A box-and-whiskers per benchmark: its median and quartiles, whiskers out to the furthest
+    /// measurements within 1.5 times the interquartile range of the quartiles, and the outliers
+    /// beyond them.
+    BoxPlot,
+}
+
+/// Where the legend of the plots is placed, see `PlotConfiguration::legend_position`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegendPosition {
+    /// Inside the plotting area, in the given corner.
+    Inside(Corner),
+    /// Outside the plotting area, beside the given corner, which leaves the curves uncovered and
+    /// gives long labels room. The plotters backend always draws the legend inside, and the Vega
+    /// backend places it at the top of the left or right side.
+    Outside(Corner),
+}
+
+/// A corner of a plot, see `LegendPosition`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    /// The top left corner.
+    TopLeft,
+    /// The top right corner.
+    TopRight,
+    /// The bottom left corner.
+    BottomLeft,
+    /// The bottom right corner.
+    BottomRight,
+}
+
+/// Method choosing the bandwidth of the kernel density estimates of the measurements, see
+/// `PlotConfiguration::kde_bandwidth`. A smaller bandwidth follows the measurements more closely,
+/// at the cost of a noisier curve.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KdeBandwidth {
+    /// Silverman's rule of thumb, `(4 / 3n)^(1/5)` times the standard deviation of the `n`
+    /// measurements. It suits measurements that are roughly normally distributed, and
+    /// over-smooths multi-modal ones.
+    Silverman,
+
+    /// Scott's rule of thumb, `n^(-1/5)` times the standard deviation of the `n` measurements.
+    /// Slightly narrower than Silverman's rule.
+    Scott,
+
+    /// A fixed bandwidth, in the unit of the measurement (nanoseconds for the default wall-clock
+    /// time), eg. to resolve modes a known distance apart. Must be positive.
+    Fixed(f64),
+}
+
+/// Plot settings given on the command line (`--plot-x-scale` and `--plot-size`), which replace
+/// those of every `PlotConfiguration`.
+#[derive(Debug, Clone, Default)]
+struct PlotOverrides {
+    x_scale: Option<AxisScale>,
+    size: Option<(usize, usize)>,
+}
+impl PlotOverrides {
+    fn apply(&self, mut config: PlotConfiguration) -> PlotConfiguration {
+        if let Some(x_scale) = self.x_scale {
+            config.x_scale = x_scale;
+        }
+        if let Some(size) = self.size {
+            config.size = Some(size);
+        }
+        config
+    }
+}
+
+/// Parses a plot size such as `1280x720`.
+fn parse_plot_size(size: &str) -> Option<(usize, usize)> {
+    let (width, height) = size.split_once('x')?;
+    match (width.parse(), height.parse()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Some((width, height)),
+        _ => None,
+    }
+}
+
 /// Contains the configuration options for the plots generated by a particular benchmark
 /// or benchmark group.
 ///
@@ -1259,6 +3441,34 @@ pub struct PlotConfiguration {
     x_grid_major: bool,
     speedup: bool,
     speedup_id: String,
+    relative: bool,
+    relative_id: String,
+    size: Option<(usize, usize)>,
+    colors: Option<Vec<(u8, u8, u8)>>,
+    violins_by_parameter: bool,
+    tic_formatter: Option<TicFormatter>,
+    line_throughput: bool,
+    summary_plot: SummaryPlot,
+    kde_points: usize,
+    kde_bandwidth: KdeBandwidth,
+    legend_position: Option<LegendPosition>,
+    font_family: Option<String>,
+    font_size: Option<f64>,
+    /// Set from `Criterion::plot_theme`.
+    theme: Theme,
+    /// Set from `Criterion::export_plot_scripts`.
+    export_scripts: bool,
+    /// Set from `Criterion::plots`.
+    plots: PlotSelection,
+}
+
+/// Labels the x tics of the line comparison plot, see `PlotConfiguration::tic_formatter`.
+#[derive(Clone)]
+struct TicFormatter(Arc<dyn Fn(i64) -> String + Send + Sync>);
+impl fmt::Debug for TicFormatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TicFormatter")
+    }
 }
 
 impl Default for PlotConfiguration {
@@ -1275,6 +3485,22 @@ impl Default for PlotConfiguration {
             x_grid_minor: false,
             speedup: false,
             speedup_id: String::new(),
+            relative: false,
+            relative_id: String::new(),
+            size: None,
+            colors: None,
+            violins_by_parameter: false,
+            tic_formatter: None,
+            line_throughput: false,
+            summary_plot: SummaryPlot::Violin,
+            kde_points: 500,
+            kde_bandwidth: KdeBandwidth::Silverman,
+            legend_position: None,
+            font_family: None,
+            font_size: None,
+            theme: Theme::Light,
+            export_scripts: false,
+            plots: PlotSelection::all(),
         }
     }
 }
@@ -1297,7 +3523,8 @@ impl PlotConfiguration {
         self
     }
 
-    /// Set x tics for the summary plots.
+    /// Set x tics for the summary plots. The line comparison plot also labels them with their raw
+    /// values on a second axis above the plot.
     pub fn tics(mut self, tics: Vec<i64>) -> Self {
         self.tics = tics;
         self
@@ -1339,7 +3566,19 @@ impl PlotConfiguration {
         self
     }
 
-    /// Draw plot as comparsion between two measurments.
+    /// Set the size of the plots in pixels, eg. to make room for the labels of groups with many
+    /// functions or long names. Thumbnails keep their own size. By default, the plots are
+    /// 1280x720 pixels with gnuplot and 960x540 with plotters, and the violin plot grows with the
+    /// number of benchmarks in the group.
+    pub fn size(mut self, width: usize, height: usize) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Draw the line comparison plot as the speedup of each function of the group over the function
+    /// `speedup_id`, ie. the ratio of their mean times at every input, with one labelled curve per
+    /// function. The summary page of the group also shows the geometric mean of the speedups of
+    /// each function across the inputs, in a bar chart and a table.
     pub fn speedup(mut self, val: bool, speedup_id: String) -> Self {
         self.speedup = val;
         if self.speedup {
@@ -1347,8 +3586,162 @@ impl PlotConfiguration {
         }
         self
     }
+
+    /// Draw the line comparison plot relative to the function `reference_id`: the mean time of
+    /// every function at each input, as a percentage of the mean time of `reference_id` at the
+    /// same input. Unlike the speedup plot, the reference itself is drawn, as the line at 100%,
+    /// and a function half as fast as the reference is at 200%. Takes precedence over `speedup`
+    /// for the line comparison plot; the summary page still shows the mean speedups.
+    pub fn relative(mut self, val: bool, reference_id: String) -> Self {
+        self.relative = val;
+        if self.relative {
+            self.relative_id = reference_id;
+        }
+        self
+    }
+
+    /// Set the colors, as RGB triples, of the plots comparing several benchmarks: the lines of the
+    /// line chart and the violins or boxes of the summary plot take them in order, starting over
+    /// when there are more benchmarks than colors. In groups with several functions, the violins
+    /// take the color of their function instead, like its line. By default, the line chart and
+    /// the violins of such groups use a built-in palette, and every other violin or box is dark
+    /// blue.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `colors` is empty.
+    pub fn colors(mut self, colors: Vec<(u8, u8, u8)>) -> Self {
+        assert!(!colors.is_empty(), "At least one color is required");
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Use the palette of Okabe and Ito for the plots comparing several benchmarks, whose colors
+    /// stay distinguishable with the common forms of color blindness. See `colors`.
+    pub fn colorblind_palette(self) -> Self {
+        self.colors(COLORBLIND_PALETTE.to_vec())
+    }
+
+    /// Order the violin or box plot by the parameter of the benchmarks, their throughput or numeric
+    /// parameter value, so that the functions are next to each other at each parameter. By
+    /// default, the violins are grouped by function and ordered by parameter within each
+    /// function. Benchmarks without a numeric parameter come last, in their original order.
+    /// Defaults to false.
+    pub fn violins_by_parameter(mut self, val: bool) -> Self {
+        self.violins_by_parameter = val;
+        self
+    }
+
+    /// Set the function labelling the x tics of the line comparison plot, eg. to label element
+    /// counts, message sizes in SI units or thread counts. By default, the tics set with `tics`
+    /// are labelled as sizes in bytes with binary prefixes, where `1Kb` is 1024 bytes. Without
+    /// `tics`, the function labels the tics chosen by the plotters backend; gnuplot keeps its own
+    /// labels.
+    pub fn tic_formatter<F>(mut self, formatter: F) -> Self
+    where
+        F: Fn(i64) -> String + Send + Sync + 'static,
+    {
+        self.tic_formatter = Some(TicFormatter(Arc::new(formatter)));
+        self
+    }
+
+    /// Plot the throughput of each benchmark on the y axis of the line comparison plot, eg. in
+    /// GiB/s or Melem/s, instead of its average time. Takes effect only if every benchmark in the
+    /// group has a `Throughput` of the same kind; otherwise the plot shows the average times.
+    /// Does not apply to speedup and relative plots. Defaults to false.
+    pub fn line_throughput(mut self, val: bool) -> Self {
+        self.line_throughput = val;
+        self
+    }
+
+    /// Set the plot comparing the distributions of the benchmarks on the summary page of the
+    /// group. The violin plot smooths the samples into a density, which can be misleading with
+    /// few samples; a box plot shows their quartiles and outliers as measured. Defaults to
+    /// `SummaryPlot::Violin`.
+    pub fn summary_plot(mut self, plot: SummaryPlot) -> Self {
+        self.summary_plot = plot;
+        self
+    }
+
+    /// Set the number of points at which the kernel density estimates of the PDF, violin and
+    /// distribution plots are evaluated. More points draw narrow peaks more faithfully, at the cost
+    /// of larger plots. Defaults to 500.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` is less than 2.
+    pub fn kde_points(mut self, points: usize) -> Self {
+        assert!(points >= 2, "At least two KDE points are required");
+        self.kde_points = points;
+        self
+    }
+
+    /// Set the method choosing the bandwidth of the kernel density estimates of the measurements
+    /// in the PDF and violin plots. The bootstrap distributions and the t-test plot keep
+    /// Silverman's rule. Defaults to `KdeBandwidth::Silverman`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bandwidth` is a fixed bandwidth that isn't positive.
+    pub fn kde_bandwidth(mut self, bandwidth: KdeBandwidth) -> Self {
+        if let KdeBandwidth::Fixed(h) = bandwidth {
+            assert!(h > 0., "The KDE bandwidth must be positive");
+        }
+        self.kde_bandwidth = bandwidth;
+        self
+    }
+
+    /// Set where the legend of every plot is placed. Placing it outside the plotting area gives
+    /// long function names room, and keeps the legend from covering the curves. By default, each
+    /// plot places its legend where it covers the least, most often in its top right corner,
+    /// outside the plotting area with gnuplot and inside with plotters.
+    pub fn legend_position(mut self, position: LegendPosition) -> Self {
+        self.legend_position = Some(position);
+        self
+    }
+
+    /// Set the font family of the text of the plots, eg. `"Helvetica"` or `"monospace"`. The font
+    /// must be installed where the plots are rendered: on this machine for gnuplot, in the browser
+    /// for the SVG plots of plotters and the Vega charts. By default, gnuplot uses Helvetica, and
+    /// the other backends a sans-serif font.
+    pub fn font_family(mut self, family: String) -> Self {
+        self.font_family = Some(family);
+        self
+    }
+
+    /// Set the size of the text of the plots, in points. The labels, tics and legends are drawn at
+    /// this size, and the titles are scaled in proportion. Smaller text fits longer labels. By
+    /// default, the text is 12 points with gnuplot and plotters, and 10 points with Vega.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` isn't positive.
+    pub fn font_size(mut self, size: f64) -> Self {
+        assert!(size > 0., "The font size must be positive");
+        self.font_size = Some(size);
+        self
+    }
+
+    /// Returns the label of the x tic `tic` given by the `tic_formatter`, if there is one.
+    pub(crate) fn tic_label(&self, tic: i64) -> Option<String> {
+        self.tic_formatter
+            .as_ref()
+            .map(|formatter| (formatter.0)(tic))
+    }
 }
 
+/// The palette of Okabe and Ito, see `PlotConfiguration::colorblind_palette`.
+const COLORBLIND_PALETTE: [(u8, u8, u8); 8] = [
+    (230, 159, 0),
+    (86, 180, 233),
+    (0, 158, 115),
+    (240, 228, 66),
+    (0, 114, 178),
+    (213, 94, 0),
+    (204, 121, 167),
+    (0, 0, 0),
+];
+
 /// This enum allows the user to control how Criterion.rs chooses the iteration count when sampling.
 /// The default is Auto, which will choose a method automatically based on the iteration time during
 /// the warm-up phase.
@@ -1375,32 +3768,157 @@ impl SamplingMode {
         sample_count: u64,
         target_time: f64,
     ) -> ActualSamplingMode {
+        let mode = match self {
+            SamplingMode::Auto => criterion_core::SamplingMode::Auto,
+            SamplingMode::Linear => criterion_core::SamplingMode::Linear,
+            SamplingMode::Flat => criterion_core::SamplingMode::Flat,
+        };
+        mode.choose(warmup_mean_execution_time, sample_count, target_time)
+            .into()
+    }
+}
+
+/// How the slope of the iteration times is fitted when the samples are taken with
+/// `SamplingMode::Linear`. The default is `OrdinaryLeastSquares`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegressionMethod {
+    /// Fit the slope by ordinary least squares. This is the most precise when the samples are
+    /// clean, but a single slow sample can skew it a lot.
+    OrdinaryLeastSquares,
+
+    /// Fit the slope with the Theil-Sen estimator, the median of the slopes between all pairs of
+    /// samples, which is hardly affected by outlier samples. It is slower to bootstrap: the time
+    /// grows with the square of the sample size, up to 100 samples. Beyond that, only a subset of
+    /// the pairs is used.
+    TheilSen,
+}
+impl RegressionMethod {
+    /// Fits the slope of `data` with this method.
+    pub(crate) fn fit(self, data: &Data<'_, f64, f64>) -> f64 {
         match self {
-            SamplingMode::Linear => ActualSamplingMode::Linear,
-            SamplingMode::Flat => ActualSamplingMode::Flat,
-            SamplingMode::Auto => {
-                // Estimate execution time with linear sampling
-                let total_runs = sample_count * (sample_count + 1) / 2;
-                let d =
-                    (target_time / warmup_mean_execution_time / total_runs as f64).ceil() as u64;
-                let expected_ns = total_runs as f64 * d as f64 * warmup_mean_execution_time;
-
-                if expected_ns > (2.0 * target_time) {
-                    ActualSamplingMode::Flat
-                } else {
-                    ActualSamplingMode::Linear
+            RegressionMethod::OrdinaryLeastSquares => Slope::fit(data).0,
+            RegressionMethod::TheilSen => Slope::fit_theil_sen(data).0,
+        }
+    }
+}
+
+/// The statistic compared against the baseline to decide whether performance has changed, see
+/// `Criterion::comparison_estimator`. The default is `Mean`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Estimator {
+    /// Compare the means with Welch's t-test.
+    Mean,
+
+    /// Compare the medians. The significance is given by bootstrapping the relative change of the
+    /// median under the hypothesis that both samples come from the same distribution.
+    Median,
+}
+impl Estimator {
+    pub(crate) fn statistic(self) -> Statistic {
+        match self {
+            Estimator::Mean => Statistic::Mean,
+            Estimator::Median => Statistic::Median,
+        }
+    }
+}
+
+/// The hypothesis test which decides whether the change compared to the baseline is significant,
+/// see `Criterion::comparison_test`. The default is `Bootstrap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComparisonTest {
+    /// Bootstrap the distribution of the statistic of `Criterion::comparison_estimator` if both
+    /// samples come from the same population: the t score of the means (Welch's t-test) or the
+    /// relative change of the medians.
+    Bootstrap,
+
+    /// The Mann-Whitney U test, also known as the Wilcoxon rank-sum test. It only compares the
+    /// ranks of the iteration times, so it doesn't assume that they are normally distributed and
+    /// is hardly swayed by a few very slow samples, which suits skewed timings. The p-value is
+    /// that of the normal approximation of the U statistic, corrected for ties.
+    MannWhitney,
+
+    /// Welch's t-test of the means, with the p-value of the Student t distribution for the
+    /// Welch-Satterthwaite degrees of freedom. Nothing of the comparison is bootstrapped: the
+    /// confidence intervals of the relative changes of the mean and the median are those of their
+    /// standard errors, by the delta method. This makes the comparison much faster, which suits
+    /// suites of thousands of benchmarks, at the cost of assuming that the means are about normally
+    /// distributed. The means are tested even with `Estimator::Median`.
+    Welch,
+}
+
+/// How the outliers of the average times are treated before the estimates and the comparison with
+/// the baseline are computed, see `Criterion::outlier_policy`. The outliers are the samples beyond
+/// the inner Tukey fences, 1.5 interquartile ranges below the first or above the third quartile,
+/// which are reported as mild or severe outliers. The default is `Keep`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutlierPolicy {
+    /// Use every sample, outliers included.
+    Keep,
+
+    /// Replace the average time of each outlier by the fence it lies beyond, so that it still
+    /// counts as a slow (or fast) sample but no longer pulls the mean and the slope.
+    Winsorize,
+
+    /// Leave the outliers out.
+    Trim,
+}
+impl OutlierPolicy {
+    /// Applies the policy to the samples, given as their iteration counts and total times.
+    pub(crate) fn apply(self, iters: &[f64], times: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        if self == OutlierPolicy::Keep {
+            return (iters.to_vec(), times.to_vec());
+        }
+        let avg_times: Vec<f64> = iters.iter().zip(times).map(|(i, t)| t / i).collect();
+        let (_, low, high, _) = tukey::classify(Sample::new(&avg_times)).fences();
+        let mut kept = (
+            Vec::with_capacity(iters.len()),
+            Vec::with_capacity(iters.len()),
+        );
+        for ((&iters, &time), &avg_time) in iters.iter().zip(times).zip(&avg_times) {
+            let outlier = avg_time < low || avg_time > high;
+            match self {
+                OutlierPolicy::Trim if outlier => continue,
+                OutlierPolicy::Winsorize if outlier => {
+                    kept.0.push(iters);
+                    kept.1.push(avg_time.max(low).min(high) * iters);
+                }
+                _ => {
+                    kept.0.push(iters);
+                    kept.1.push(time);
                 }
             }
         }
+        kept
     }
 }
 
+/// Where `Criterion::push_metrics` pushes the results of the benchmarks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetricsEndpoint {
+    /// The URL of a Prometheus pushgateway, eg. `http://localhost:9091`. Only plain HTTP is
+    /// supported. The metrics of each benchmark are pushed to the group `job="criterion"`,
+    /// `commit` of the git commit and `benchmark` of the benchmark ID.
+    Pushgateway(String),
+
+    /// The address of a StatsD server, eg. `localhost:8125`. The metrics are sent as gauges,
+    /// tagged with the benchmark ID and the git commit in the DogStatsD format.
+    Statsd(String),
+}
+
 /// Enum to represent the sampling mode without Auto.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub(crate) enum ActualSamplingMode {
     Linear,
     Flat,
 }
+impl From<criterion_core::Method> for ActualSamplingMode {
+    fn from(method: criterion_core::Method) -> ActualSamplingMode {
+        match method {
+            criterion_core::Method::Linear => ActualSamplingMode::Linear,
+            criterion_core::Method::Flat => ActualSamplingMode::Flat,
+        }
+    }
+}
 impl ActualSamplingMode {
     pub(crate) fn iteration_counts(
         &self,
@@ -1408,19 +3926,22 @@ impl ActualSamplingMode {
         sample_count: u64,
         target_time: &Duration,
     ) -> Vec<u64> {
+        let method = match self {
+            ActualSamplingMode::Linear => criterion_core::Method::Linear,
+            ActualSamplingMode::Flat => criterion_core::Method::Flat,
+        };
+        let n = sample_count;
+        let met = warmup_mean_execution_time;
+        let m_ns = target_time.as_nanos() as f64;
+        let counts = criterion_core::sampling::iteration_counts(method, met, n, m_ns);
+        // With a single iteration per sample (or per step of linear sampling), the plan may take
+        // longer than the target time.
+        let expected_ns = counts.iter().map(|&count| count as f64).sum::<f64>() * met;
         match self {
             ActualSamplingMode::Linear => {
-                let n = sample_count;
-                let met = warmup_mean_execution_time;
-                let m_ns = target_time.as_nanos();
-                // Solve: [d + 2*d + 3*d + ... + n*d] * met = m_ns
-                let total_runs = n * (n + 1) / 2;
-                let d = ((m_ns as f64 / met / total_runs as f64).ceil() as u64).max(1);
-                let expected_ns = total_runs as f64 * d as f64 * met;
-
-                if d == 1 {
+                if counts[0] == 1 {
                     let recommended_sample_size =
-                        ActualSamplingMode::recommend_linear_sample_size(m_ns as f64, met);
+                        ActualSamplingMode::recommend_linear_sample_size(m_ns, met);
                     let actual_time = Duration::from_nanos(expected_ns as u64);
                     eprint!("\nWarning: Unable to complete {} samples in {:.1?}. You may wish to increase target time to {:.1?}",
                             n, target_time, actual_time);
@@ -1434,20 +3955,9 @@ impl ActualSamplingMode {
                         eprintln!(" or enable flat sampling.");
                     }
                 }
-
-                (1..(n + 1) as u64).map(|a| a * d).collect::<Vec<u64>>()
             }
             ActualSamplingMode::Flat => {
-                let n = sample_count;
-                let met = warmup_mean_execution_time;
-                let m_ns = target_time.as_nanos() as f64;
-                let time_per_sample = m_ns / (n as f64);
-                // This is pretty simplistic; we could do something smarter to fit into the allotted time.
-                let iterations_per_sample = ((time_per_sample / met).ceil() as u64).max(1);
-
-                let expected_ns = met * (iterations_per_sample * n) as f64;
-
-                if iterations_per_sample == 1 {
+                if counts[0] == 1 {
                     let recommended_sample_size =
                         ActualSamplingMode::recommend_flat_sample_size(m_ns, met);
                     let actual_time = Duration::from_nanos(expected_ns as u64);
@@ -1460,10 +3970,9 @@ impl ActualSamplingMode {
                         eprintln!(".");
                     }
                 }
-
-                vec![iterations_per_sample; n as usize]
             }
         }
+        counts
     }
 
     fn is_linear(&self) -> bool {
@@ -1515,11 +4024,62 @@ pub(crate) struct SavedSample {
     times: Vec<f64>,
 }
 
+/// Counts the benchmarks in the given groups for the `--progress` display. Used by
+/// `criterion_main!`; should not be called directly.
+#[doc(hidden)]
+pub fn __count_benchmarks(groups: &[&dyn Fn()]) {
+    progress::count_benchmarks(groups);
+}
+
+/// Returns true while the groups are run only to discover their benchmarks, for `--progress` or
+/// `--interactive`.
+fn is_discovery_pass() -> bool {
+    #[cfg(feature = "interactive")]
+    if interactive::is_discovering() {
+        return true;
+    }
+    progress::is_counting()
+}
+
+/// Runs the benchmark groups of `criterion_main!`, in random order if `--shuffle` was passed and
+/// concurrently if `--jobs` was passed, or interactively if `--interactive` was passed. Should not
+/// be called directly.
+#[doc(hidden)]
+pub fn __run_groups(groups: &[&'static (dyn Fn() + Sync)]) {
+    #[cfg(feature = "interactive")]
+    if interactive::is_requested() {
+        interactive::run(groups);
+        return;
+    }
+    parallel::run_groups(groups, shuffle::order(groups.len()));
+}
+
+/// A target function of `criterion_group!`.
+type GroupTarget<M> = dyn Fn(&mut Criterion<M>);
+
+/// Runs the target functions of `criterion_group!`, in random order if `--shuffle` was passed.
+/// Should not be called directly.
+#[doc(hidden)]
+pub fn __run_targets<M: Measurement>(criterion: &mut Criterion<M>, targets: &[&GroupTarget<M>]) {
+    // The discovery pass must not consume the random order of the real run.
+    if is_discovery_pass() {
+        for target in targets {
+            target(criterion);
+        }
+        return;
+    }
+    let _guard = parallel::start_group(criterion.exclusive);
+    for i in shuffle::order(targets.len()) {
+        targets[i](criterion);
+    }
+}
+
 /// Custom-test-framework runner. Should not be called directly.
 #[doc(hidden)]
 pub fn runner(benches: &[&dyn Fn()]) {
-    for bench in benches {
-        bench();
+    progress::count_benchmarks(benches);
+    for i in shuffle::order(benches.len()) {
+        benches[i]();
     }
     Criterion::default().configure_from_args().final_summary();
 }
@@ -67,9 +67,7 @@ macro_rules! criterion_group {
         pub fn $name() {
             let mut criterion: $crate::Criterion<_> = $config
                 .configure_from_args();
-            $(
-                $target(&mut criterion);
-            )+
+            $crate::__run_targets(&mut criterion, &[$(&$target),+]);
         }
     };
     ($name:ident, $( $target:path ),+ $(,)*) => {
@@ -120,9 +118,46 @@ macro_rules! criterion_group {
 macro_rules! criterion_main {
     ( $( $group:path ),+ $(,)* ) => {
         fn main() {
-            $(
-                $group();
-            )+
+            $crate::__count_benchmarks(&[$(&$group),+]);
+            $crate::__run_groups(&[$(&$group),+]);
+
+            $crate::Criterion::default()
+                .configure_from_args()
+                .final_summary();
+        }
+    }
+}
+
+/// Macro which expands to a [wasm-bindgen-test](https://crates.io/crates/wasm-bindgen-test) test
+/// running the benchmarks in the given groups, for benchmarking on `wasm32-unknown-unknown` in a
+/// web browser or in Node.js.
+///
+/// The test is best placed in its own file in the `tests` directory, with `wasm-bindgen-test` as a
+/// dev-dependency:
+///
+/// ```ignore
+/// use criterion::{criterion_group, criterion_wasm_bindgen_test, Criterion};
+///
+/// wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+///
+/// fn bench_method1(c: &mut Criterion) {
+/// }
+///
+/// criterion_group!(benches, bench_method1);
+/// criterion_wasm_bindgen_test!(benches);
+/// ```
+///
+/// The benchmarks are then run with `wasm-pack test --release --chrome` or
+/// `cargo test --release --target wasm32-unknown-unknown` (with `wasm-bindgen-test-runner` as the
+/// runner). Without a command line to pass options on, they are configured in the code, eg. with
+/// `criterion_group!`'s `config`. The results are logged to the console, or passed to the sink
+/// given to `Criterion::with_result_sink`.
+#[macro_export]
+macro_rules! criterion_wasm_bindgen_test {
+    ( $( $group:path ),+ $(,)* ) => {
+        #[wasm_bindgen_test::wasm_bindgen_test]
+        fn criterion_benchmarks() {
+            $crate::__run_groups(&[$(&$group),+]);
 
             $crate::Criterion::default()
                 .configure_from_args()
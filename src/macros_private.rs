@@ -32,16 +32,29 @@ macro_rules! try_else_return {
 
 /// Print an error message to stdout. Format is the same as println! or format!
 macro_rules! error {
-    ($($arg:tt)*) => (
+    ($($arg:tt)*) => ({
+        #[cfg(feature = "tracing")]
+        tracing::error!("{}", format!($($arg)*));
         println!("Criterion.rs ERROR: {}", &format!($($arg)*))
-    )
+    })
 }
 
 /// Print a debug message to stdout. Format is the same as println! or format!
 macro_rules! info {
-    ($($arg:tt)*) => (
+    ($($arg:tt)*) => ({
+        #[cfg(feature = "tracing")]
+        tracing::debug!("{}", format!($($arg)*));
         if $crate::debug_enabled() {
             println!("Criterion.rs DEBUG: {}", &format!($($arg)*))
         }
-    )
+    })
+}
+
+/// Enters a `tracing` span which lasts until the end of the enclosing scope. Arguments are the
+/// same as for `tracing::info_span!`. Expands to nothing unless the `tracing` feature is enabled.
+macro_rules! enter_span {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($($arg)*).entered();
+    };
 }
@@ -0,0 +1,156 @@
+//! Reproducibility manifest, recording everything that influenced a benchmark run. It is saved
+//! next to the other per-benchmark files as `manifest.json` and shown on the HTML report.
+
+use std::collections::BTreeMap;
+
+use crate::benchmark::BenchmarkConfig;
+use crate::clock;
+use crate::environment::{self, Finding};
+use crate::shuffle;
+use crate::{ActualSamplingMode, ComparisonTest, Estimator, OutlierPolicy, RegressionMethod};
+
+/// Environment variables which Criterion.rs reads and which can change the results or where they
+/// are stored.
+const CONSULTED_ENV_VARS: &[&str] = &[
+    "CARGO_CRITERION_PORT",
+    "CARGO_CRITERION_PROTOCOL_FORMAT",
+    "CARGO_MANIFEST_DIR",
+    "CARGO_TARGET_DIR",
+    "CRITERION_DEBUG",
+    "CRITERION_HOME",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Manifest {
+    pub criterion_version: String,
+    /// Seconds since the Unix epoch at which the benchmark was analyzed.
+    pub timestamp: u64,
+    /// Type name of the `Measurement` used, eg. `criterion::measurement::WallTime`.
+    pub measurement: String,
+    pub config: ManifestConfig,
+    /// Seed of the bootstrap random number generator. `None` means it was seeded from the clock.
+    pub bootstrap_seed: Option<u64>,
+    /// Seed of the benchmark order given to or chosen by `--shuffle`. `None` if the benchmarks
+    /// ran in the order in which they are defined.
+    #[serde(default)]
+    pub shuffle_seed: Option<u64>,
+    /// Labels attached to the run with `--label` or `Criterion::label`, and the detected metadata
+    /// (see `metadata::labels`).
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// The number of samples measured with streaming statistics, of which only a reservoir was
+    /// kept. `None` if every sample was kept.
+    #[serde(default)]
+    pub streamed_samples: Option<u64>,
+    /// Findings of `--check-env`, if the environment was checked.
+    #[serde(default)]
+    pub environment: Vec<Finding>,
+    /// Command-line arguments passed to the benchmark executable, without the executable itself.
+    pub args: Vec<String>,
+    /// Values of the consulted environment variables; `None` if unset.
+    pub env: BTreeMap<String, Option<String>>,
+}
+
+/// The resolved `BenchmarkConfig`, plus the sampling mode that was actually used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ManifestConfig {
+    pub confidence_level: f64,
+    pub measurement_time_secs: f64,
+    pub noise_threshold: f64,
+    pub nresamples: usize,
+    pub sample_size: usize,
+    pub significance_level: f64,
+    pub warm_up_time_secs: f64,
+    pub sampling_mode: String,
+    pub actual_sampling_mode: ActualSamplingMode,
+    pub quick_mode: bool,
+    /// Missing from the manifests of older versions, which always used ordinary least squares.
+    #[serde(default = "least_squares")]
+    pub regression_method: String,
+    /// Missing from the manifests of older versions, which always compared the means.
+    #[serde(default = "mean")]
+    pub comparison_estimator: String,
+    /// Missing from the manifests of older versions, which always used the bootstrap test.
+    #[serde(default = "bootstrap")]
+    pub comparison_test: String,
+    #[serde(default)]
+    pub percentiles: Vec<f64>,
+    #[serde(default)]
+    pub minimum_time: bool,
+    /// Missing from the manifests of older versions, which always kept the outliers.
+    #[serde(default = "keep")]
+    pub outlier_policy: String,
+    #[serde(default)]
+    pub target_precision: Option<f64>,
+    #[serde(default)]
+    pub streaming_statistics: bool,
+    /// The longest time spent measuring with `target_precision`; missing if there is none.
+    #[serde(default)]
+    pub max_measurement_time_secs: Option<f64>,
+}
+
+fn least_squares() -> String {
+    format!("{:?}", RegressionMethod::OrdinaryLeastSquares)
+}
+
+fn mean() -> String {
+    format!("{:?}", Estimator::Mean)
+}
+
+fn bootstrap() -> String {
+    format!("{:?}", ComparisonTest::Bootstrap)
+}
+
+fn keep() -> String {
+    format!("{:?}", OutlierPolicy::Keep)
+}
+
+impl Manifest {
+    pub(crate) fn new(
+        measurement: &str,
+        config: &BenchmarkConfig,
+        actual_sampling_mode: ActualSamplingMode,
+    ) -> Manifest {
+        Manifest {
+            criterion_version: env!("CARGO_PKG_VERSION").to_owned(),
+            timestamp: clock::since_epoch().as_secs(),
+            measurement: measurement.to_owned(),
+            config: ManifestConfig {
+                confidence_level: config.confidence_level,
+                measurement_time_secs: config.measurement_time.as_secs_f64(),
+                noise_threshold: config.noise_threshold,
+                nresamples: config.nresamples,
+                sample_size: config.sample_size,
+                significance_level: config.significance_level,
+                warm_up_time_secs: config.warm_up_time.as_secs_f64(),
+                sampling_mode: format!("{:?}", config.sampling_mode),
+                actual_sampling_mode,
+                quick_mode: config.quick_mode,
+                regression_method: format!("{:?}", config.regression_method),
+                comparison_estimator: format!("{:?}", config.comparison_estimator),
+                comparison_test: format!("{:?}", config.comparison_test),
+                percentiles: config.percentiles.clone(),
+                minimum_time: config.minimum_time,
+                outlier_policy: format!("{:?}", config.outlier_policy),
+                target_precision: config.target_precision,
+                streaming_statistics: config.streaming_statistics,
+                max_measurement_time_secs: config
+                    .target_precision
+                    .map(|_| config.max_measurement_time.as_secs_f64()),
+            },
+            bootstrap_seed: config.bootstrap_seed,
+            shuffle_seed: shuffle::seed(),
+            labels: BTreeMap::new(),
+            streamed_samples: None,
+            environment: environment::current(),
+            args: std::env::args().skip(1).collect(),
+            env: CONSULTED_ENV_VARS
+                .iter()
+                .map(|&name| {
+                    let value = std::env::var_os(name).map(|v| v.to_string_lossy().into_owned());
+                    (name.to_owned(), value)
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! The Markdown summary of a run, `summary.md` in the output directory.
+//!
+//! It has a table of the benchmarks of the run with their mean, change compared to the baseline
+//! and significance, which can be pasted into a pull request comment or posted by a bot. Since
+//! every group constructs its own `Criterion` struct, the rows are kept in a process-wide static,
+//! keyed by output directory, and the whole file is rewritten after each benchmark.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::error::{Error, Result};
+use crate::format;
+use crate::fs;
+use crate::measurement::ValueFormatter;
+use crate::report::{BenchmarkId, MeasurementData, Report, ReportContext, SignificantChange};
+
+struct Row {
+    output_directory: PathBuf,
+    id: String,
+    mean: String,
+    change: Option<String>,
+    significance: &'static str,
+    /// The labels of the run, see `Criterion::label`.
+    labels: BTreeMap<String, String>,
+}
+
+static ROWS: Lazy<Mutex<Vec<Row>>> = Lazy::new(|| Mutex::new(vec![]));
+
+pub(crate) struct MarkdownReport;
+impl MarkdownReport {
+    fn write(&self, output_directory: &Path, rows: &[Row]) -> Result<()> {
+        fs::mkdirp(&output_directory)?;
+        let path = output_directory.join("summary.md");
+        std::fs::write(&path, render(rows, output_directory))
+            .map_err(|inner| Error::AccessError { path, inner })
+    }
+}
+
+/// Escapes the characters of `text` which would end a table cell or a code span.
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|").replace('`', "'")
+}
+
+/// Renders the rows of the run in `output_directory` as a Markdown table.
+fn render(rows: &[Row], output_directory: &Path) -> String {
+    let rows: Vec<&Row> = rows
+        .iter()
+        .filter(|row| row.output_directory == output_directory)
+        .collect();
+    let mut markdown = String::from("## Benchmark results\n\n");
+    markdown.push_str("| Benchmark | Mean | Change | Significance |\n");
+    markdown.push_str("|:--|--:|--:|:--|\n");
+    for row in &rows {
+        writeln!(
+            markdown,
+            "| `{}` | {} | {} | {} |",
+            escape(&row.id),
+            row.mean,
+            row.change.as_deref().unwrap_or("-"),
+            row.significance
+        )
+        .unwrap();
+    }
+    markdown.push_str("\n▲ slower, ▼ faster, beyond the noise threshold and significant.\n");
+    // The labels are those of the run; the last row's are the latest.
+    if let Some(row) = rows.last().filter(|row| !row.labels.is_empty()) {
+        let labels: Vec<String> = row
+            .labels
+            .iter()
+            .map(|(key, value)| format!("`{}={}`", escape(key), escape(value)))
+            .collect();
+        writeln!(markdown, "\nLabels: {}", labels.join(", ")).unwrap();
+    }
+    markdown
+}
+
+impl Report for MarkdownReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &dyn ValueFormatter,
+    ) {
+        let mean = &measurements.absolute_estimates.mean;
+        let (change, significance) = match &measurements.comparison {
+            Some(comparison) => {
+                let change = comparison.change().point_estimate;
+                let significance = match comparison.significant_change() {
+                    Some(SignificantChange::Regressed) => "▲ Regressed",
+                    Some(SignificantChange::Improved) => "▼ Improved",
+                    None => "No change",
+                };
+                (
+                    Some(format::change(change, true).trim().to_owned()),
+                    significance,
+                )
+            }
+            None => (None, "No baseline"),
+        };
+
+        let mut rows = ROWS.lock().unwrap();
+        rows.push(Row {
+            output_directory: context.output_directory.clone(),
+            id: id.as_title().to_owned(),
+            mean: formatter.format_value(mean.point_estimate),
+            change,
+            significance,
+            labels: measurements.manifest.labels.clone(),
+        });
+        log_if_err!(self.write(&context.output_directory, &rows));
+    }
+}
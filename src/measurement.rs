@@ -1,11 +1,12 @@
 //! This module defines a set of traits that can be used to plug different measurements (eg.
 //! Unix's Processor Time, CPU or GPU performance counters, etc.) into Criterion.rs. It also
 //! includes the [WallTime](struct.WallTime.html) struct which defines the default wall-clock time
-//! measurement.
+//! measurement, and the [Timer](struct.Timer.html) struct whose backend is selected at run time.
 
-use crate::format::short;
+use crate::clock::Instant;
+use crate::format::{self, short};
 use crate::Throughput;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 /// Trait providing functions to format measured values to string so that they can be displayed on
 /// the command line or in the reports. The functions of this trait take measured values in f64
@@ -63,9 +64,120 @@ pub trait ValueFormatter {
     fn scale_for_machines(&self, values: &mut [f64]) -> &'static str;
 }
 
-/// Trait for all types which define something Criterion.rs can measure. The only measurement
-/// currently provided is [WallTime](struct.WallTime.html), but third party crates or benchmarks
-/// may define more.
+/// A fixed unit for displaying measured values, set with
+/// [`Criterion::fixed_unit`](../struct.Criterion.html#method.fixed_unit). This only applies to
+/// measurements which produce values in nanoseconds, such as [WallTime](struct.WallTime.html), and
+/// is ignored for the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    /// Always display values in picoseconds.
+    Picoseconds,
+    /// Always display values in nanoseconds.
+    Nanoseconds,
+    /// Always display values in microseconds.
+    Microseconds,
+    /// Always display values in milliseconds.
+    Milliseconds,
+    /// Always display values in seconds.
+    Seconds,
+}
+impl Unit {
+    fn factor(self) -> f64 {
+        match self {
+            Unit::Picoseconds => 10f64.powi(3),
+            Unit::Nanoseconds => 10f64.powi(0),
+            Unit::Microseconds => 10f64.powi(-3),
+            Unit::Milliseconds => 10f64.powi(-6),
+            Unit::Seconds => 10f64.powi(-9),
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Unit::Picoseconds => "ps",
+            Unit::Nanoseconds => "ns",
+            Unit::Microseconds => "µs",
+            Unit::Milliseconds => "ms",
+            Unit::Seconds => "s",
+        }
+    }
+}
+
+/// Wraps the measurement's formatter to apply the unit and number of significant digits
+/// configured on the `Criterion` struct, if any.
+pub(crate) struct FixedFormatter<'a> {
+    pub(crate) inner: &'a dyn ValueFormatter,
+    pub(crate) unit: Option<Unit>,
+    pub(crate) significant_digits: Option<usize>,
+}
+impl<'a> FixedFormatter<'a> {
+    /// The fixed unit, unless the measurement's values are not in nanoseconds, e.g. counts of
+    /// allocations or cycles, which the factors of `Unit` would turn into nonsense.
+    fn unit(&self) -> Option<Unit> {
+        self.unit
+            .filter(|_| self.inner.scale_for_machines(&mut []) == "ns")
+    }
+
+    fn is_passthrough(&self) -> bool {
+        self.unit().is_none() && self.significant_digits.is_none()
+    }
+
+    fn number(&self, value: f64) -> String {
+        match self.significant_digits {
+            Some(digits) => format::significant(value, digits),
+            None => short(value),
+        }
+    }
+}
+impl<'a> ValueFormatter for FixedFormatter<'a> {
+    fn format_value(&self, value: f64) -> String {
+        if self.is_passthrough() {
+            return self.inner.format_value(value);
+        }
+        let mut values = [value];
+        let unit = self.scale_values(value, &mut values);
+        format!("{:>6} {}", self.number(values[0]), unit)
+    }
+
+    fn format_throughput(&self, throughput: &Throughput, value: f64) -> String {
+        if self.is_passthrough() {
+            return self.inner.format_throughput(throughput, value);
+        }
+        let mut values = [value];
+        let unit = self.scale_throughputs(value, throughput, &mut values);
+        format!("{:>6} {}", self.number(values[0]), unit)
+    }
+
+    fn scale_values(&self, typical_value: f64, values: &mut [f64]) -> &'static str {
+        match self.unit() {
+            Some(unit) => {
+                for val in values {
+                    *val *= unit.factor();
+                }
+                unit.symbol()
+            }
+            None => self.inner.scale_values(typical_value, values),
+        }
+    }
+
+    fn scale_throughputs(
+        &self,
+        typical_value: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        self.inner
+            .scale_throughputs(typical_value, throughput, values)
+    }
+
+    fn scale_for_machines(&self, values: &mut [f64]) -> &'static str {
+        self.inner.scale_for_machines(values)
+    }
+}
+
+/// Trait for all types which define something Criterion.rs can measure. The measurements
+/// provided are [WallTime](struct.WallTime.html) and [Timer](struct.Timer.html), whose backend is
+/// selected at run time, but third party crates or benchmarks may define more.
 ///
 /// This trait defines two core methods, `start` and `end`. `start` is called at the beginning of
 /// a measurement to produce some intermediate value (for example, the wall-clock time at the start
@@ -237,3 +349,1076 @@ impl Measurement for WallTime {
         &DurationFormatter
     }
 }
+
+/// Reads the CPU's cycle counter, serialized with the surrounding instructions so that neither the
+/// work before a reading nor the work after it is executed out of order across it: the time-stamp
+/// counter on x86_64, between two `lfence`s, and the virtual count of the generic timer on AArch64,
+/// after an `isb`.
+#[cfg(target_arch = "x86_64")]
+fn read_cycle_counter() -> u64 {
+    use core::arch::x86_64::{_mm_lfence, _rdtsc};
+    use std::sync::atomic::{compiler_fence, Ordering};
+
+    compiler_fence(Ordering::SeqCst);
+    // Safety: `lfence` and `rdtsc` are available on every x86_64 CPU.
+    let cycles = unsafe {
+        _mm_lfence();
+        let cycles = _rdtsc();
+        _mm_lfence();
+        cycles
+    };
+    compiler_fence(Ordering::SeqCst);
+    cycles
+}
+#[cfg(all(target_arch = "aarch64", criterion_asm))]
+fn read_cycle_counter() -> u64 {
+    let ticks: u64;
+    // Safety: `cntvct_el0` can be read from user space by every operating system Rust supports on
+    // AArch64. The assembly is not `nomem`, so that the compiler doesn't move memory accesses
+    // across it either.
+    unsafe { core::arch::asm!("isb", "mrs {}, cntvct_el0", out(reg) ticks, options(nostack)) };
+    ticks
+}
+
+/// `Cycles` counts the ticks of the CPU's cycle counter, like the `criterion-cycles-per-byte`
+/// crate, so that kernels which take a few nanoseconds are measured without the quantization of
+/// the wall clock. On x86_64 it reads the time-stamp counter, which ticks at a constant rate, the
+/// nominal frequency of the CPU, whatever its current frequency, so its ticks are not the cycles
+/// executed by the core (`--timer perf:cycles` counts those); on AArch64 it reads the generic
+/// timer, whose frequency is fixed by the platform (eg. 24 MHz or 1 GHz) and is usually lower than
+/// that of the CPU. The counter is read serialized (see `lfence` and `isb`), so that the
+/// measured work neither starts before nor ends after the readings.
+///
+/// Only available on x86_64, and on AArch64 with Rust 1.59 or newer. Values are shown in ticks, and
+/// throughput in ticks per byte or element.
+///
+/// ```no_run
+/// # #[cfg(target_arch = "x86_64")]
+/// # mod cycles {
+/// use criterion::{criterion_group, measurement::Cycles, Criterion};
+///
+/// fn bench(c: &mut Criterion<Cycles>) {
+///     c.bench_function("sum", |b| b.iter(|| (0..100u64).sum::<u64>()));
+/// }
+///
+/// criterion_group! {
+///     name = benches;
+///     config = Criterion::default().with_measurement(Cycles);
+///     targets = bench
+/// }
+/// # }
+/// ```
+#[cfg(any(target_arch = "x86_64", all(target_arch = "aarch64", criterion_asm)))]
+pub struct Cycles;
+#[cfg(any(target_arch = "x86_64", all(target_arch = "aarch64", criterion_asm)))]
+impl Measurement for Cycles {
+    type Intermediate = u64;
+    type Value = u64;
+
+    fn start(&self) -> Self::Intermediate {
+        read_cycle_counter()
+    }
+    fn end(&self, i: Self::Intermediate) -> Self::Value {
+        read_cycle_counter().saturating_sub(i)
+    }
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+    fn zero(&self) -> Self::Value {
+        0
+    }
+    fn to_f64(&self, val: &Self::Value) -> f64 {
+        *val as f64
+    }
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &CYCLES
+    }
+}
+#[cfg(any(target_arch = "x86_64", all(target_arch = "aarch64", criterion_asm)))]
+const CYCLES: CountFormatter = CountFormatter {
+    unit: "ticks",
+    per_byte: "ticks/B",
+    per_element: "ticks/elem",
+};
+
+/// A hardware or software event which can be counted with `--timer perf:<event>`.
+struct PerfEvent {
+    name: &'static str,
+    /// `type` and `config` of the `perf_event_attr`.
+    kind: u32,
+    config: u64,
+    per_byte: &'static str,
+    per_element: &'static str,
+}
+
+const PERF_EVENTS: &[PerfEvent] = &[
+    PerfEvent {
+        name: "cycles",
+        kind: 0,
+        config: 0,
+        per_byte: "cycles/B",
+        per_element: "cycles/elem",
+    },
+    PerfEvent {
+        name: "instructions",
+        kind: 0,
+        config: 1,
+        per_byte: "instructions/B",
+        per_element: "instructions/elem",
+    },
+    PerfEvent {
+        name: "cache-references",
+        kind: 0,
+        config: 2,
+        per_byte: "cache-references/B",
+        per_element: "cache-references/elem",
+    },
+    PerfEvent {
+        name: "cache-misses",
+        kind: 0,
+        config: 3,
+        per_byte: "cache-misses/B",
+        per_element: "cache-misses/elem",
+    },
+    PerfEvent {
+        name: "branches",
+        kind: 0,
+        config: 4,
+        per_byte: "branches/B",
+        per_element: "branches/elem",
+    },
+    PerfEvent {
+        name: "branch-misses",
+        kind: 0,
+        config: 5,
+        per_byte: "branch-misses/B",
+        per_element: "branch-misses/elem",
+    },
+    PerfEvent {
+        name: "page-faults",
+        kind: 1,
+        config: 2,
+        per_byte: "page-faults/B",
+        per_element: "page-faults/elem",
+    },
+    PerfEvent {
+        name: "context-switches",
+        kind: 1,
+        config: 3,
+        per_byte: "context-switches/B",
+        per_element: "context-switches/elem",
+    },
+    PerfEvent {
+        name: "stalled-cycles-frontend",
+        kind: 0,
+        config: 7,
+        per_byte: "stalled-cycles-frontend/B",
+        per_element: "stalled-cycles-frontend/elem",
+    },
+    PerfEvent {
+        name: "stalled-cycles-backend",
+        kind: 0,
+        config: 8,
+        per_byte: "stalled-cycles-backend/B",
+        per_element: "stalled-cycles-backend/elem",
+    },
+    PerfEvent {
+        name: "ref-cycles",
+        kind: 0,
+        config: 9,
+        per_byte: "ref-cycles/B",
+        per_element: "ref-cycles/elem",
+    },
+    PerfEvent {
+        name: "cpu-migrations",
+        kind: 1,
+        config: 4,
+        per_byte: "cpu-migrations/B",
+        per_element: "cpu-migrations/elem",
+    },
+    PerfEvent {
+        name: "minor-faults",
+        kind: 1,
+        config: 5,
+        per_byte: "minor-faults/B",
+        per_element: "minor-faults/elem",
+    },
+    PerfEvent {
+        name: "major-faults",
+        kind: 1,
+        config: 6,
+        per_byte: "major-faults/B",
+        per_element: "major-faults/elem",
+    },
+    // Hardware cache events: the cache, the read operation and the miss result, one byte each.
+    PerfEvent {
+        name: "L1-dcache-load-misses",
+        kind: 3,
+        config: 0x1_0000,
+        per_byte: "L1-dcache-load-misses/B",
+        per_element: "L1-dcache-load-misses/elem",
+    },
+    PerfEvent {
+        name: "LLC-load-misses",
+        kind: 3,
+        config: 0x1_0002,
+        per_byte: "LLC-load-misses/B",
+        per_element: "LLC-load-misses/elem",
+    },
+    PerfEvent {
+        name: "dTLB-load-misses",
+        kind: 3,
+        config: 0x1_0003,
+        per_byte: "dTLB-load-misses/B",
+        per_element: "dTLB-load-misses/elem",
+    },
+];
+
+impl PerfEvent {
+    fn formatter(&self) -> CountFormatter {
+        CountFormatter {
+            unit: self.name,
+            per_byte: self.per_byte,
+            per_element: self.per_element,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn open(&self) -> Result<perf::Counter, String> {
+        perf::Counter::open(self.kind, self.config).map_err(|e| {
+            format!(
+                "unable to count {}: {} (counting may require lowering /proc/sys/kernel/perf_event_paranoid)",
+                self.name, e
+            )
+        })
+    }
+}
+
+/// Finds the perf event with the given name.
+fn perf_event(name: &str) -> Result<&'static PerfEvent, String> {
+    PERF_EVENTS
+        .iter()
+        .find(|event| event.name == name)
+        .ok_or_else(|| {
+            let names: Vec<_> = PERF_EVENTS.iter().map(|event| event.name).collect();
+            format!(
+                "unknown perf event '{}', expected one of {}",
+                name,
+                names.join(", ")
+            )
+        })
+}
+
+/// `PerfCounter` counts a hardware or software event for the benchmarking thread with Linux's
+/// `perf_event_open`, eg. the instructions retired, the cache misses or the mispredicted branches
+/// of each iteration, which are much less noisy than their time. The values are shown with the
+/// name of the event as their unit in the reports and plots, and their throughput as events per
+/// byte or element. Like `--timer perf:<event>`, whose events are the same, but fixed in code:
+///
+/// ```no_run
+/// # #[cfg(target_os = "linux")]
+/// # mod perf {
+/// use criterion::{criterion_group, measurement::PerfCounter, Criterion};
+///
+/// fn bench(c: &mut Criterion<PerfCounter>) {
+///     c.bench_function("sum", |b| b.iter(|| (0..100u64).sum::<u64>()));
+/// }
+///
+/// criterion_group! {
+///     name = benches;
+///     config = Criterion::default().with_measurement(PerfCounter::new("instructions").unwrap());
+///     targets = bench
+/// }
+/// # }
+/// ```
+///
+/// Only user-space events are counted, and only on the thread which created the counter, so the
+/// benchmarks must run on that thread. The kernel may still refuse access depending on
+/// `/proc/sys/kernel/perf_event_paranoid`, and virtual machines often lack hardware counters.
+/// Only available on Linux.
+#[cfg(target_os = "linux")]
+pub struct PerfCounter {
+    counter: perf::Counter,
+    counts: CountFormatter,
+}
+#[cfg(target_os = "linux")]
+impl PerfCounter {
+    /// Opens a counter of the event with the given name: `cycles`, `instructions`,
+    /// `cache-references`, `cache-misses`, `branches`, `branch-misses`, `stalled-cycles-frontend`,
+    /// `stalled-cycles-backend`, `ref-cycles`, `page-faults`, `context-switches`,
+    /// `cpu-migrations`, `minor-faults`, `major-faults`, `L1-dcache-load-misses`,
+    /// `LLC-load-misses` or `dTLB-load-misses`. Returns an error if the event is unknown or can't
+    /// be counted.
+    pub fn new(event: &str) -> Result<PerfCounter, String> {
+        let event = perf_event(event)?;
+        Ok(PerfCounter {
+            counter: event.open()?,
+            counts: event.formatter(),
+        })
+    }
+
+    /// Returns the name of the counted event.
+    pub fn event(&self) -> &'static str {
+        self.counts.unit
+    }
+}
+#[cfg(target_os = "linux")]
+impl Measurement for PerfCounter {
+    type Intermediate = u64;
+    type Value = u64;
+
+    fn start(&self) -> Self::Intermediate {
+        self.counter.read()
+    }
+    fn end(&self, i: Self::Intermediate) -> Self::Value {
+        self.counter.read().saturating_sub(i)
+    }
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+    fn zero(&self) -> Self::Value {
+        0
+    }
+    fn to_f64(&self, val: &Self::Value) -> f64 {
+        *val as f64
+    }
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &self.counts
+    }
+}
+
+/// Formats counts of events, such as CPU cycles, without scaling them. Throughput is shown as the
+/// number of events per byte or element.
+struct CountFormatter {
+    unit: &'static str,
+    per_byte: &'static str,
+    per_element: &'static str,
+}
+impl ValueFormatter for CountFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        self.unit
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        let (count, unit) = match *throughput {
+            Throughput::Bytes(bytes) | Throughput::BytesDecimal(bytes) => (bytes, self.per_byte),
+            Throughput::Elements(elements) => (elements, self.per_element),
+        };
+        for val in values {
+            *val /= count as f64;
+        }
+        unit
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        self.unit
+    }
+}
+
+enum Backend {
+    Wall(Instant),
+    #[cfg(target_os = "linux")]
+    Cpu,
+    #[cfg(target_os = "linux")]
+    Perf(perf::Counter),
+    #[cfg(any(target_arch = "x86_64", all(target_arch = "aarch64", criterion_asm)))]
+    Tsc,
+}
+
+/// A measurement whose backend is chosen at run time, so that the same benchmark executable can
+/// be run with different measurement strategies. Use [`Timer::from_args`] to select the backend
+/// with the `--timer` command-line option:
+///
+/// ```no_run
+/// use criterion::{criterion_group, measurement::Timer, Criterion};
+///
+/// fn bench(c: &mut Criterion<Timer>) {
+///     c.bench_function("sum", |b| b.iter(|| (0..100u64).sum::<u64>()));
+/// }
+///
+/// criterion_group! {
+///     name = benches;
+///     config = Criterion::default().with_measurement(Timer::from_args());
+///     targets = bench
+/// }
+/// ```
+///
+/// The available backends are:
+///
+/// - `wall`: elapsed wall-clock time, like [WallTime](struct.WallTime.html). This is the default.
+/// - `cpu`: CPU time consumed by the benchmarking thread (Linux only).
+/// - `perf:<event>`: a hardware or software event counted by the kernel for the benchmarking
+///   thread, like [PerfCounter](struct.PerfCounter.html), eg. `cycles`, `instructions`,
+///   `cache-misses` or `branch-misses` (Linux only; may require lowering
+///   `/proc/sys/kernel/perf_event_paranoid`).
+/// - `tsc`: ticks of the CPU's cycle counter, like [Cycles](struct.Cycles.html) (x86_64 and
+///   AArch64 only).
+pub struct Timer {
+    backend: Backend,
+    counts: CountFormatter,
+}
+impl Timer {
+    /// Creates a timer with the backend given as `wall`, `cpu`, `perf:<event>` or `tsc`. Returns an
+    /// error if the backend is unknown or not available on this platform.
+    pub fn new(backend: &str) -> Result<Timer, String> {
+        let counts = |unit, per_byte, per_element| CountFormatter {
+            unit,
+            per_byte,
+            per_element,
+        };
+        let (backend, counts) = match backend.split_once(':') {
+            Some(("perf", name)) => {
+                let event = perf_event(name)?;
+                (Timer::perf(event)?, event.formatter())
+            }
+            None if backend == "wall" => (Backend::Wall(Instant::now()), counts("", "", "")),
+            None if backend == "cpu" => (Timer::cpu()?, counts("", "", "")),
+            None if backend == "tsc" => (Timer::tsc()?, counts("ticks", "ticks/B", "ticks/elem")),
+            _ => {
+                return Err(format!(
+                    "unknown timer '{}', expected wall, cpu, perf:<event> or tsc",
+                    backend
+                ))
+            }
+        };
+        Ok(Timer { backend, counts })
+    }
+
+    /// Creates a timer with the backend given by the `--timer` command-line option, or a `wall`
+    /// timer if there is none. Prints an error and exits if the backend is unknown or not
+    /// available on this platform.
+    pub fn from_args() -> Timer {
+        let backend = timer_from_args(std::env::args()).unwrap_or_else(|| "wall".to_owned());
+        match Timer::new(&backend) {
+            Ok(timer) => timer,
+            Err(e) => {
+                eprintln!("Invalid --timer '{}': {}.", backend, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn cpu() -> Result<Backend, String> {
+        Ok(Backend::Cpu)
+    }
+    #[cfg(not(target_os = "linux"))]
+    fn cpu() -> Result<Backend, String> {
+        Err("the cpu timer is only available on Linux".to_owned())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn perf(event: &PerfEvent) -> Result<Backend, String> {
+        event.open().map(Backend::Perf)
+    }
+    #[cfg(not(target_os = "linux"))]
+    fn perf(_event: &PerfEvent) -> Result<Backend, String> {
+        Err("perf events are only available on Linux".to_owned())
+    }
+
+    #[cfg(any(target_arch = "x86_64", all(target_arch = "aarch64", criterion_asm)))]
+    fn tsc() -> Result<Backend, String> {
+        Ok(Backend::Tsc)
+    }
+    #[cfg(not(any(target_arch = "x86_64", all(target_arch = "aarch64", criterion_asm))))]
+    fn tsc() -> Result<Backend, String> {
+        Err("the tsc timer is only available on x86_64 and AArch64".to_owned())
+    }
+
+    /// Reads the current value of the backend's clock or counter.
+    fn read(&self) -> u64 {
+        match &self.backend {
+            Backend::Wall(epoch) => epoch.elapsed().as_nanos() as u64,
+            #[cfg(target_os = "linux")]
+            Backend::Cpu => {
+                // Safety: clock_gettime only writes to the given timespec.
+                let mut time: libc::timespec = unsafe { std::mem::zeroed() };
+                unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) };
+                time.tv_sec as u64 * 1_000_000_000 + time.tv_nsec as u64
+            }
+            #[cfg(target_os = "linux")]
+            Backend::Perf(counter) => counter.read(),
+            #[cfg(any(target_arch = "x86_64", all(target_arch = "aarch64", criterion_asm)))]
+            Backend::Tsc => read_cycle_counter(),
+        }
+    }
+}
+impl Default for Timer {
+    fn default() -> Timer {
+        Timer::new("wall").unwrap()
+    }
+}
+impl Measurement for Timer {
+    type Intermediate = u64;
+    type Value = u64;
+
+    fn start(&self) -> Self::Intermediate {
+        self.read()
+    }
+    fn end(&self, i: Self::Intermediate) -> Self::Value {
+        self.read().saturating_sub(i)
+    }
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+    fn zero(&self) -> Self::Value {
+        0
+    }
+    fn to_f64(&self, val: &Self::Value) -> f64 {
+        *val as f64
+    }
+    fn formatter(&self) -> &dyn ValueFormatter {
+        match self.backend {
+            Backend::Wall(_) => &DurationFormatter,
+            #[cfg(target_os = "linux")]
+            Backend::Cpu => &DurationFormatter,
+            #[allow(unreachable_patterns)]
+            _ => &self.counts,
+        }
+    }
+}
+
+/// Returns the value of the `--timer` option. Like the number of jobs, this is needed before the
+/// `Criterion` struct is configured, so it is read from the arguments directly.
+pub(crate) fn timer_from_args<I: Iterator<Item = String>>(args: I) -> Option<String> {
+    let mut args = args.skip(1);
+    let mut timer = None;
+    while let Some(arg) = args.next() {
+        if arg == "--timer" {
+            timer = args.next();
+        } else if let Some(value) = arg.strip_prefix("--timer=") {
+            timer = Some(value.to_owned());
+        }
+    }
+    timer
+}
+
+/// The directory of the powercap interface of the kernel, which exposes the RAPL counters.
+#[cfg(target_os = "linux")]
+const POWERCAP_DIRECTORY: &str = "/sys/class/powercap";
+
+/// An energy counter of a RAPL domain, in microjoules.
+#[cfg(target_os = "linux")]
+struct EnergyCounter {
+    file: std::fs::File,
+    path: std::path::PathBuf,
+    /// The value past which the counter wraps around to zero.
+    range: u64,
+}
+#[cfg(target_os = "linux")]
+impl EnergyCounter {
+    fn read(&self) -> u64 {
+        use std::os::unix::fs::FileExt;
+
+        // Reading from the start of the file again yields the current value.
+        let mut buffer = [0u8; 32];
+        let read = self
+            .file
+            .read_at(&mut buffer, 0)
+            .unwrap_or_else(|e| panic!("unable to read {}: {}", self.path.display(), e));
+        std::str::from_utf8(&buffer[..read])
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or_else(|| panic!("invalid energy in {}", self.path.display()))
+    }
+}
+
+/// The most packages whose energy `Energy` sums, so that their readings fit in an array.
+#[cfg(target_os = "linux")]
+const MAX_PACKAGES: usize = 16;
+
+/// Formats energies from nanojoules to joules, and shows the average power drawn while measuring.
+#[cfg(target_os = "linux")]
+struct EnergyFormatter {
+    /// The energy in microjoules measured so far.
+    microjoules: std::sync::atomic::AtomicU64,
+    /// The time in nanoseconds measured so far.
+    nanoseconds: std::sync::atomic::AtomicU64,
+}
+#[cfg(target_os = "linux")]
+impl EnergyFormatter {
+    /// Returns the factor which scales microjoules to the unit of the given energy, and the unit.
+    fn scale(microjoules: f64) -> (f64, usize) {
+        if microjoules < 1.0 {
+            (1e3, 0)
+        } else if microjoules < 1e3 {
+            (1.0, 1)
+        } else if microjoules < 1e6 {
+            (1e-3, 2)
+        } else {
+            (1e-6, 3)
+        }
+    }
+}
+#[cfg(target_os = "linux")]
+impl ValueFormatter for EnergyFormatter {
+    fn format_value(&self, value: f64) -> String {
+        let mut values = [value];
+        let unit = self.scale_values(value, &mut values);
+        let microjoules = self.microjoules.load(std::sync::atomic::Ordering::Relaxed) as f64;
+        let nanoseconds = self.nanoseconds.load(std::sync::atomic::Ordering::Relaxed) as f64;
+        if nanoseconds > 0.0 {
+            // Microjoules per nanosecond are kilowatts.
+            let watts = microjoules / nanoseconds * 1e3;
+            format!("{:>6} {} ({} W)", short(values[0]), unit, short(watts))
+        } else {
+            format!("{:>6} {}", short(values[0]), unit)
+        }
+    }
+
+    fn scale_values(&self, typical_value: f64, values: &mut [f64]) -> &'static str {
+        let (factor, unit) = EnergyFormatter::scale(typical_value);
+        for val in values {
+            *val *= factor;
+        }
+        ["nJ", "µJ", "mJ", "J"][unit]
+    }
+
+    fn scale_throughputs(
+        &self,
+        typical_value: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        let (count, units) = match *throughput {
+            Throughput::Bytes(bytes) | Throughput::BytesDecimal(bytes) => {
+                (bytes, ["nJ/B", "µJ/B", "mJ/B", "J/B"])
+            }
+            Throughput::Elements(elements) => {
+                (elements, ["nJ/elem", "µJ/elem", "mJ/elem", "J/elem"])
+            }
+        };
+        let (factor, unit) = EnergyFormatter::scale(typical_value / count as f64);
+        for val in values {
+            *val *= factor / count as f64;
+        }
+        units[unit]
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "µJ"
+    }
+}
+
+/// `Energy` measures the energy consumed by the CPU packages during each iteration, with the RAPL
+/// (Running Average Power Limit) counters of Intel and AMD CPUs, which Linux exposes through the
+/// powercap interface in `/sys/class/powercap`. The energy of all the packages is summed, but not
+/// that of the other zones such as `psys`, which already includes the packages. Values
+/// are shown from nanojoules to joules, followed by the average power drawn while measuring, and
+/// throughput as energy per byte or element.
+///
+/// ```no_run
+/// # #[cfg(target_os = "linux")]
+/// # mod energy {
+/// use criterion::{criterion_group, measurement::Energy, Criterion};
+///
+/// fn bench(c: &mut Criterion<Energy>) {
+///     c.bench_function("sum", |b| b.iter(|| (0..100u64).sum::<u64>()));
+/// }
+///
+/// criterion_group! {
+///     name = benches;
+///     config = Criterion::default().with_measurement(Energy::new().unwrap());
+///     targets = bench
+/// }
+/// # }
+/// ```
+///
+/// The counters measure the whole package, including the work of other processes and of the idle
+/// cores, so the machine should be otherwise idle. They are only updated about every millisecond,
+/// so the samples should take much longer than that. Since 2020, the counters are only readable by
+/// root by default. The power is averaged over everything this measurement has measured so far.
+/// Only available on Linux.
+#[cfg(target_os = "linux")]
+pub struct Energy {
+    counters: Vec<EnergyCounter>,
+    formatter: EnergyFormatter,
+}
+#[cfg(target_os = "linux")]
+impl Energy {
+    /// Opens the energy counters of all the CPU packages. Returns an error if there are none or
+    /// they can't be read.
+    pub fn new() -> Result<Energy, String> {
+        Energy::open(std::path::Path::new(POWERCAP_DIRECTORY))
+    }
+
+    /// Opens the counters of the packages in the powercap directory: the top-level RAPL zones,
+    /// named `intel-rapl:<zone>`, whose `name` is `package-<package>`. Their subzones, eg. of the
+    /// cores, are part of them, and so is the `psys` zone of the whole platform on some machines.
+    fn open(directory: &std::path::Path) -> Result<Energy, String> {
+        let entries = std::fs::read_dir(directory).map_err(|e| {
+            format!(
+                "unable to list {}: {} (RAPL requires an Intel or AMD CPU)",
+                directory.display(),
+                e
+            )
+        })?;
+        let mut zones: Vec<std::path::PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                matches!(name.strip_prefix("intel-rapl:"), Some(zone) if zone.parse::<u32>().is_ok())
+            })
+            .map(|entry| entry.path())
+            .filter(|zone| {
+                matches!(std::fs::read_to_string(zone.join("name")), Ok(name) if name.trim().starts_with("package-"))
+            })
+            .collect();
+        zones.sort();
+        if zones.is_empty() {
+            return Err(format!(
+                "no RAPL package in {} (RAPL requires an Intel or AMD CPU)",
+                directory.display()
+            ));
+        }
+        if zones.len() > MAX_PACKAGES {
+            return Err(format!(
+                "{} RAPL packages in {}, but at most {} are supported",
+                zones.len(),
+                directory.display(),
+                MAX_PACKAGES
+            ));
+        }
+
+        let read = |path: &std::path::Path| {
+            std::fs::read_to_string(path).map_err(|e| {
+                format!(
+                    "unable to read {}: {} (the RAPL counters are only readable by root unless their permissions are relaxed)",
+                    path.display(),
+                    e
+                )
+            })
+        };
+        let counters = zones
+            .iter()
+            .map(|zone| {
+                let path = zone.join("energy_uj");
+                let range_path = zone.join("max_energy_range_uj");
+                let range = read(&range_path)?
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("invalid energy range in {}", range_path.display()))?;
+                read(&path)?;
+                let file = std::fs::File::open(&path)
+                    .map_err(|e| format!("unable to open {}: {}", path.display(), e))?;
+                Ok(EnergyCounter { file, path, range })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Energy {
+            counters,
+            formatter: EnergyFormatter {
+                microjoules: std::sync::atomic::AtomicU64::new(0),
+                nanoseconds: std::sync::atomic::AtomicU64::new(0),
+            },
+        })
+    }
+}
+#[cfg(target_os = "linux")]
+impl Measurement for Energy {
+    type Intermediate = ([u64; MAX_PACKAGES], Instant);
+    type Value = u64;
+
+    fn start(&self) -> Self::Intermediate {
+        // The readings go in an array rather than a vector, so that nothing is allocated while
+        // measuring.
+        let mut energies = [0; MAX_PACKAGES];
+        for (energy, counter) in energies.iter_mut().zip(&self.counters) {
+            *energy = counter.read();
+        }
+        (energies, Instant::now())
+    }
+    fn end(&self, i: Self::Intermediate) -> Self::Value {
+        let (start, started) = i;
+        let energy = self
+            .counters
+            .iter()
+            .zip(start.iter().copied())
+            .map(|(counter, start)| {
+                let end = counter.read();
+                if end >= start {
+                    end - start
+                } else {
+                    // The counter wrapped around.
+                    end + (counter.range - start)
+                }
+            })
+            .sum();
+        let elapsed = started.elapsed();
+        let order = std::sync::atomic::Ordering::Relaxed;
+        self.formatter.microjoules.fetch_add(energy, order);
+        self.formatter
+            .nanoseconds
+            .fetch_add(elapsed.as_nanos() as u64, order);
+        energy
+    }
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+    fn zero(&self) -> Self::Value {
+        0
+    }
+    fn to_f64(&self, val: &Self::Value) -> f64 {
+        *val as f64
+    }
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &self.formatter
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod perf {
+    use std::io;
+
+    /// The first fields of `perf_event_attr`, up to the size of its first version
+    /// (`PERF_ATTR_SIZE_VER0`); the kernel treats the fields added since as zero.
+    #[repr(C)]
+    struct Attr {
+        kind: u32,
+        size: u32,
+        config: u64,
+        sample_period: u64,
+        sample_type: u64,
+        read_format: u64,
+        flags: u64,
+        wakeup_events: u32,
+        bp_type: u32,
+        config1: u64,
+    }
+
+    const EXCLUDE_KERNEL: u64 = 1 << 5;
+    const EXCLUDE_HV: u64 = 1 << 6;
+    const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 8;
+
+    /// A counter of one event for the calling thread, counting from when it is opened.
+    pub(super) struct Counter {
+        fd: libc::c_int,
+    }
+    impl Counter {
+        pub(super) fn open(kind: u32, config: u64) -> io::Result<Counter> {
+            let attr = Attr {
+                kind,
+                size: std::mem::size_of::<Attr>() as u32,
+                config,
+                sample_period: 0,
+                sample_type: 0,
+                read_format: 0,
+                // Only count user space, which does not require special privileges.
+                flags: EXCLUDE_KERNEL | EXCLUDE_HV,
+                wakeup_events: 0,
+                bp_type: 0,
+                config1: 0,
+            };
+            // Safety: the kernel only reads `attr`. A pid of 0 and cpu of -1 count the calling
+            // thread on any CPU, and a group fd of -1 creates a new group.
+            let fd = unsafe {
+                libc::syscall(
+                    libc::SYS_perf_event_open,
+                    &attr as *const Attr,
+                    0 as libc::pid_t,
+                    -1 as libc::c_int,
+                    -1 as libc::c_int,
+                    PERF_FLAG_FD_CLOEXEC,
+                )
+            };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Counter {
+                fd: fd as libc::c_int,
+            })
+        }
+
+        pub(super) fn read(&self) -> u64 {
+            let mut value: u64 = 0;
+            // Safety: reading a counter yields a single u64, which fits in `value`.
+            let read = unsafe {
+                libc::read(
+                    self.fd,
+                    &mut value as *mut u64 as *mut libc::c_void,
+                    std::mem::size_of::<u64>(),
+                )
+            };
+            assert!(
+                read == std::mem::size_of::<u64>() as isize,
+                "unable to read perf counter: {}",
+                io::Error::last_os_error()
+            );
+            value
+        }
+    }
+    impl Drop for Counter {
+        fn drop(&mut self) {
+            // Safety: the fd is owned by this counter and not used afterwards.
+            unsafe { libc::close(self.fd) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn timer_backends() {
+        assert!(Timer::new("wall").is_ok());
+        assert!(Timer::new("sundial").is_err());
+        assert!(Timer::new("perf:cycels").is_err());
+        #[cfg(target_os = "linux")]
+        {
+            let timer = Timer::new("cpu").unwrap();
+            let start = timer.start();
+            let sum: u64 = (0..1_000_000u64).map(crate::black_box).sum();
+            assert!(timer.end(start) > 0 && sum > 0);
+        }
+        #[cfg(any(target_arch = "x86_64", all(target_arch = "aarch64", criterion_asm)))]
+        {
+            let timer = Timer::new("tsc").unwrap();
+            assert_eq!(timer.formatter().scale_for_machines(&mut []), "ticks");
+        }
+
+        let args = |args: &[&str]| -> Vec<String> {
+            std::iter::once("bench")
+                .chain(args.iter().copied())
+                .map(str::to_owned)
+                .collect()
+        };
+        assert_eq!(timer_from_args(args(&["--bench"]).into_iter()), None);
+        assert_eq!(
+            timer_from_args(args(&["--timer", "cpu", "--bench"]).into_iter()),
+            Some("cpu".to_owned())
+        );
+        assert_eq!(
+            timer_from_args(args(&["--timer=perf:cycles"]).into_iter()),
+            Some("perf:cycles".to_owned())
+        );
+    }
+
+    #[test]
+    fn fixed_formatter() {
+        let formatter = |unit, significant_digits| FixedFormatter {
+            inner: &DurationFormatter,
+            unit,
+            significant_digits,
+        };
+        assert_eq!(
+            formatter(Some(Unit::Microseconds), Some(3)).format_value(1234.5),
+            "  1.23 µs"
+        );
+        assert_eq!(
+            formatter(Some(Unit::Microseconds), Some(3)).format_value(12.345),
+            "0.0123 µs"
+        );
+        assert_eq!(
+            formatter(Some(Unit::Seconds), None).format_value(2.5e9),
+            "2.5000 s"
+        );
+        assert_eq!(formatter(None, Some(2)).format_value(1234.5), "   1.2 µs");
+        assert_eq!(
+            formatter(None, None).format_value(1234.5),
+            DurationFormatter.format_value(1234.5)
+        );
+
+        // The unit is ignored for values which are not in nanoseconds.
+        let ticks = CountFormatter {
+            unit: "ticks",
+            per_byte: "ticks/B",
+            per_element: "ticks/elem",
+        };
+        let formatter = FixedFormatter {
+            inner: &ticks,
+            unit: Some(Unit::Microseconds),
+            significant_digits: Some(3),
+        };
+        assert_eq!(formatter.format_value(1234.), "  1234 ticks");
+        let mut values = [1234.];
+        assert_eq!(formatter.scale_values(1234., &mut values), "ticks");
+        assert_eq!(values, [1234.]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn perf_counter() {
+        match PerfCounter::new("sundial") {
+            Err(e) => assert!(e.contains("dTLB-load-misses")),
+            Ok(_) => panic!("counted an unknown event"),
+        }
+        // Virtual machines and strict kernels may refuse hardware counters, but page faults are
+        // counted in software.
+        let counter = match PerfCounter::new("page-faults") {
+            Ok(counter) => counter,
+            Err(e) => return assert!(e.contains("perf_event_paranoid"), "{}", e),
+        };
+        assert_eq!(counter.event(), "page-faults");
+        let start = counter.start();
+        let pages = crate::black_box(vec![1u8; 16 << 20]);
+        assert!(counter.end(start) > 0 && pages[0] == 1);
+        assert_eq!(
+            counter.formatter().scale_for_machines(&mut []),
+            "page-faults"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn energy() {
+        let dir = tempfile::tempdir().unwrap();
+        match Energy::open(dir.path()) {
+            Err(e) => assert!(e.contains("no RAPL package")),
+            Ok(_) => panic!("found a RAPL package in an empty directory"),
+        }
+        // Two packages, a subzone of the first and the platform, which are part of them.
+        for (zone, name, energy) in [
+            ("intel-rapl:0", "package-0", 900),
+            ("intel-rapl:1", "psys", 400),
+            ("intel-rapl:2", "package-1", 100),
+            ("intel-rapl:0:0", "core", 50),
+        ] {
+            let zone = dir.path().join(zone);
+            std::fs::create_dir(&zone).unwrap();
+            std::fs::write(zone.join("name"), format!("{}\n", name)).unwrap();
+            std::fs::write(zone.join("energy_uj"), format!("{}\n", energy)).unwrap();
+            std::fs::write(zone.join("max_energy_range_uj"), "1000\n").unwrap();
+        }
+        let energy = match Energy::open(dir.path()) {
+            Ok(energy) => energy,
+            Err(e) => panic!("{}", e),
+        };
+        let start = energy.start();
+        std::fs::write(dir.path().join("intel-rapl:0/energy_uj"), "100\n").unwrap();
+        std::fs::write(dir.path().join("intel-rapl:1/energy_uj"), "900\n").unwrap();
+        std::fs::write(dir.path().join("intel-rapl:2/energy_uj"), "150\n").unwrap();
+        // The first package wrapped around.
+        assert_eq!(energy.end(start), 200 + 50);
+
+        let formatter = energy.formatter();
+        assert_eq!(formatter.scale_for_machines(&mut []), "µJ");
+        let mut values = [2500.0];
+        assert_eq!(formatter.scale_values(2500.0, &mut values), "mJ");
+        assert_eq!(values, [2.5]);
+        assert!(formatter.format_value(2500.0).ends_with(" W)"));
+        let mut values = [0.5];
+        let unit = formatter.scale_throughputs(0.5, &Throughput::Elements(10), &mut values);
+        assert_eq!((unit, values), ("nJ/elem", [50.0]));
+    }
+
+    #[cfg(any(target_arch = "x86_64", all(target_arch = "aarch64", criterion_asm)))]
+    #[test]
+    fn cycles() {
+        let start = Cycles.start();
+        let sum: u64 = (0..100_000u64).map(crate::black_box).sum();
+        let cycles = Cycles.end(start);
+        assert!(cycles > 0 && sum > 0);
+        let mut values = [2048.0];
+        let unit =
+            Cycles
+                .formatter()
+                .scale_throughputs(2048.0, &Throughput::Bytes(1024), &mut values);
+        assert_eq!((unit, values), ("ticks/B", [2.0]));
+    }
+}
@@ -0,0 +1,66 @@
+//! Metadata of the environment which produced the results: the git commit, the CPU model and the
+//! version of the compiler. They are detected once per process and recorded as the `commit`, `cpu`
+//! and `rustc` labels of every benchmark (see `Criterion::label`), unless the run sets those labels
+//! itself, eg. with `--label commit=$CI_COMMIT_SHA` where the benchmarks don't run in a checkout.
+
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use once_cell::sync::Lazy;
+
+static DETECTED: Lazy<Vec<(&'static str, String)>> = Lazy::new(|| {
+    let rustc = Some(env!("CRITERION_RUSTC_VERSION").to_owned()).filter(|v| !v.is_empty());
+    [
+        ("commit", git_commit()),
+        ("cpu", cpu_model()),
+        ("rustc", rustc),
+    ]
+    .iter()
+    .cloned()
+    .filter_map(|(key, value)| Some((key, value?)))
+    .collect()
+});
+
+/// Runs a command and returns the first line of its output, if it succeeds.
+fn first_line(program: &str, args: &[&str]) -> Option<String> {
+    Command::new(program)
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .and_then(|output| output.lines().next().map(|line| line.trim().to_owned()))
+        .filter(|line| !line.is_empty())
+}
+
+fn git_commit() -> Option<String> {
+    first_line("git", &["rev-parse", "HEAD"])
+}
+
+fn cpu_model() -> Option<String> {
+    if cfg!(target_os = "linux") {
+        let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").ok()?;
+        cpuinfo
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(key, _)| key.trim() == "model name")
+            .map(|(_, value)| value.trim().to_owned())
+    } else if cfg!(target_os = "macos") {
+        first_line("sysctl", &["-n", "machdep.cpu.brand_string"])
+    } else if cfg!(windows) {
+        std::env::var("PROCESSOR_IDENTIFIER").ok()
+    } else {
+        None
+    }
+}
+
+/// Returns the labels of the run, with the detected metadata which they don't override.
+pub(crate) fn labels(labels: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+    let mut labels = labels.clone();
+    for (key, value) in DETECTED.iter() {
+        labels
+            .entry((*key).to_owned())
+            .or_insert_with(|| value.clone());
+    }
+    labels
+}
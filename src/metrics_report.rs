@@ -0,0 +1,291 @@
+//! Pushes the results to a metrics endpoint (`Criterion::push_metrics`, `--pushgateway`,
+//! `--statsd`) after each benchmark, for long-term performance dashboards.
+//!
+//! The metrics are the point estimates of the mean, median and standard deviation, in the unit
+//! of the measurement, and the change of the mean and median compared to the baseline, in
+//! percent. They are labeled with the benchmark ID and the git commit, the `commit` label of the
+//! run (see `Criterion::label`), which is detected from the checkout unless it is set.
+//!
+//! A Prometheus pushgateway replaces all the metrics of a group on each push, so every benchmark
+//! is pushed to a group of its own, whose grouping key includes the benchmark ID, and only its
+//! metrics are sent. StatsD is sent the metrics of each benchmark as gauges over UDP, with the
+//! labels as DogStatsD tags.
+
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+use crate::measurement::ValueFormatter;
+use crate::report::{BenchmarkId, MeasurementData, Report, ReportContext};
+use crate::MetricsEndpoint;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One value to export.
+struct Metric {
+    /// `estimate` or `change_percent`.
+    kind: &'static str,
+    statistic: &'static str,
+    value: f64,
+}
+
+pub(crate) struct MetricsReport {
+    endpoint: MetricsEndpoint,
+}
+impl MetricsReport {
+    pub(crate) fn new(endpoint: MetricsEndpoint) -> MetricsReport {
+        MetricsReport { endpoint }
+    }
+}
+
+fn metrics(measurements: &MeasurementData<'_>, formatter: &dyn ValueFormatter) -> Vec<Metric> {
+    let estimates = &measurements.absolute_estimates;
+    let mut values = [
+        estimates.mean.point_estimate,
+        estimates.median.point_estimate,
+        estimates.std_dev.point_estimate,
+    ];
+    formatter.scale_for_machines(&mut values);
+    let mut metrics: Vec<Metric> = ["mean", "median", "std_dev"]
+        .iter()
+        .zip(values.iter())
+        .map(|(&statistic, &value)| Metric {
+            kind: "estimate",
+            statistic,
+            value,
+        })
+        .collect();
+    if let Some(comparison) = &measurements.comparison {
+        let change = &comparison.relative_estimates;
+        for (statistic, estimate) in [("mean", &change.mean), ("median", &change.median)].iter() {
+            metrics.push(Metric {
+                kind: "change_percent",
+                statistic,
+                value: estimate.point_estimate * 100.0,
+            });
+        }
+    }
+    metrics
+}
+
+/// Escapes a label value of the Prometheus text format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Formats the metrics of a benchmark in the Prometheus text format, one family per kind.
+fn prometheus_text(id: &str, unit: &str, metrics: &[Metric]) -> String {
+    let mut text = String::new();
+    for kind in ["estimate", "change_percent"].iter() {
+        writeln!(text, "# TYPE criterion_{} gauge", kind).unwrap();
+        for metric in metrics.iter().filter(|metric| metric.kind == *kind) {
+            let unit = match metric.kind {
+                "estimate" => format!(",unit=\"{}\"", escape_label(unit)),
+                _ => String::new(),
+            };
+            writeln!(
+                text,
+                "criterion_{}{{benchmark=\"{}\",statistic=\"{}\"{}}} {}",
+                metric.kind,
+                escape_label(id),
+                metric.statistic,
+                unit,
+                metric.value
+            )
+            .unwrap();
+        }
+    }
+    text
+}
+
+/// Formats a label of the grouping key of a pushgateway URL. The value is percent-encoded, unless
+/// it is empty or contains a slash, which the pushgateway only accepts in URL-safe base64.
+fn grouping_label(name: &str, value: &str) -> String {
+    if value.is_empty() || value.contains('/') {
+        const ALPHABET: &[u8; 64] =
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+        let mut encoded = String::new();
+        for chunk in value.as_bytes().chunks(3) {
+            let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &byte)| {
+                bits | u32::from(byte) << (16 - 8 * i)
+            });
+            for i in 0..=chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            }
+        }
+        if encoded.is_empty() {
+            encoded.push('=');
+        }
+        format!("{}@base64/{}", name, encoded)
+    } else {
+        let mut encoded = String::new();
+        for &byte in value.as_bytes() {
+            if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+                encoded.push(byte as char);
+            } else {
+                write!(encoded, "%{:02X}", byte).unwrap();
+            }
+        }
+        format!("{}/{}", name, encoded)
+    }
+}
+
+/// Splits a URL of the form `http://host[:port][/path]` into the address and the path.
+fn parse_http_url(url: &str) -> Option<(String, String)> {
+    let rest = url.strip_prefix("http://")?;
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], rest[slash..].trim_end_matches('/')),
+        None => (rest, ""),
+    };
+    if authority.is_empty() {
+        return None;
+    }
+    let address = if authority.contains(':') {
+        authority.to_owned()
+    } else {
+        format!("{}:80", authority)
+    };
+    Some((address, path.to_owned()))
+}
+
+/// Resolves `address`, failing if it has no IP address.
+fn resolve(address: &str) -> io::Result<Vec<SocketAddr>> {
+    let addresses: Vec<SocketAddr> = address.to_socket_addrs()?.collect();
+    if addresses.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} has no address", address),
+        ));
+    }
+    Ok(addresses)
+}
+
+/// Connects to the first address of `address` that accepts within the timeout.
+fn connect(address: &str) -> io::Result<TcpStream> {
+    let mut error = None;
+    for address in resolve(address)? {
+        match TcpStream::connect_timeout(&address, TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => error = Some(e),
+        }
+    }
+    Err(error.unwrap())
+}
+
+/// Replaces the metrics of the group of the benchmark and the commit on the pushgateway at `url`
+/// with `body`.
+fn push_to_gateway(url: &str, id: &str, commit: &str, body: &str) -> io::Result<()> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let (address, path) =
+        parse_http_url(url).ok_or_else(|| invalid(format!("{} is not an http:// URL", url)))?;
+    let path = format!(
+        "{}/metrics/job/criterion/{}/{}",
+        path,
+        grouping_label("commit", commit),
+        grouping_label("benchmark", id)
+    );
+
+    let mut stream = connect(&address)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    write!(
+        stream,
+        "PUT {} HTTP/1.1\r\nHost: {}\r\nContent-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        address,
+        body.len(),
+        body
+    )?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let status = response.lines().next().unwrap_or_default();
+    match status.split(' ').nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unexpected response '{}'", status),
+        )),
+    }
+}
+
+/// Escapes a DogStatsD tag value.
+fn escape_tag(value: &str) -> String {
+    value.replace(&[',', '|', '#'][..], "_")
+}
+
+/// Sends the metrics of a benchmark to the StatsD server at `address`, as gauges.
+fn send_to_statsd(
+    address: &str,
+    id: &str,
+    unit: &str,
+    commit: &str,
+    metrics: &[Metric],
+) -> io::Result<()> {
+    let mut packet = String::new();
+    for metric in metrics {
+        let unit = match metric.kind {
+            "estimate" => format!(",unit:{}", escape_tag(unit)),
+            _ => String::new(),
+        };
+        writeln!(
+            packet,
+            "criterion.{}.{}:{}|g|#benchmark:{},commit:{}{}",
+            metric.kind,
+            metric.statistic,
+            metric.value,
+            escape_tag(id),
+            escape_tag(commit),
+            unit
+        )
+        .unwrap();
+    }
+    // The socket is bound to the address family of the server.
+    let address = resolve(address)?[0];
+    let local = match address {
+        SocketAddr::V4(_) => "0.0.0.0:0",
+        SocketAddr::V6(_) => "[::]:0",
+    };
+    let socket = UdpSocket::bind(local)?;
+    socket.send_to(packet.trim_end().as_bytes(), address)?;
+    Ok(())
+}
+
+impl Report for MetricsReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &dyn ValueFormatter,
+    ) {
+        let commit = measurements
+            .manifest
+            .labels
+            .get("commit")
+            .map_or("unknown", String::as_str);
+        let metrics = metrics(measurements, formatter);
+        let unit = formatter.scale_for_machines(&mut [1.0]);
+        let id = id.as_title();
+
+        let result = match &self.endpoint {
+            MetricsEndpoint::Pushgateway(url) => {
+                push_to_gateway(url, id, commit, &prometheus_text(id, unit, &metrics))
+                    .map_err(|e| (url, e))
+            }
+            MetricsEndpoint::Statsd(address) => {
+                send_to_statsd(address, id, unit, commit, &metrics).map_err(|e| (address, e))
+            }
+        };
+        if let Err((endpoint, e)) = result {
+            eprintln!(
+                "Warning: failed to push the metrics of {} to {}: {}",
+                id, endpoint, e
+            );
+        }
+    }
+}
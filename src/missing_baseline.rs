@@ -0,0 +1,73 @@
+//! Handling of benchmarks which have no saved baseline to compare against (`--missing-baseline`).
+//!
+//! When comparing against a named baseline, some benchmarks may not have been saved in it, eg.
+//! because they were added since. The policy decides what happens to each of them, and every
+//! benchmark it was applied to is recorded here so that the final summary can list them all at
+//! once. Since every group constructs its own `Criterion` struct, the record is a process-wide
+//! static, keyed by output directory.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::MissingBaselinePolicy;
+
+struct Missing {
+    output_directory: PathBuf,
+    id: String,
+    policy: MissingBaselinePolicy,
+}
+
+static MISSING: Lazy<Mutex<Vec<Missing>>> = Lazy::new(|| Mutex::new(vec![]));
+
+/// Records that the benchmark with the given ID had no baseline and that `policy` was applied.
+pub(crate) fn record(output_directory: &Path, id: &str, policy: MissingBaselinePolicy) {
+    MISSING.lock().unwrap().push(Missing {
+        output_directory: output_directory.to_owned(),
+        id: id.to_owned(),
+        policy,
+    });
+}
+
+/// Prints the benchmarks in the given output directory which had no baseline, grouped by the
+/// policy applied to them, and forgets them. Returns true if any of them failed the run.
+pub(crate) fn summarize(output_directory: &Path) -> bool {
+    let mut all = MISSING.lock().unwrap();
+    let (missing, others): (Vec<Missing>, Vec<Missing>) = all
+        .drain(..)
+        .partition(|m| m.output_directory == output_directory);
+    *all = others;
+
+    let mut failed = false;
+    for (policy, what) in [
+        (MissingBaselinePolicy::Error, "were skipped"),
+        (
+            MissingBaselinePolicy::WarnAndRun,
+            "were run without a comparison",
+        ),
+        (
+            MissingBaselinePolicy::AutoCreate,
+            "were saved as the new baseline",
+        ),
+    ] {
+        let ids: Vec<&str> = missing
+            .iter()
+            .filter(|m| m.policy == policy)
+            .map(|m| m.id.as_str())
+            .collect();
+        if ids.is_empty() {
+            continue;
+        }
+        failed |= policy == MissingBaselinePolicy::Error;
+        eprintln!(
+            "{} benchmark(s) had no saved baseline and {}:",
+            ids.len(),
+            what
+        );
+        for id in ids {
+            eprintln!("  {}", id);
+        }
+    }
+    failed
+}
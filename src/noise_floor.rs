@@ -0,0 +1,145 @@
+//! Machine noise-floor estimation (`--noise-floor`).
+//!
+//! A fixed spin loop is timed repeatedly before the first benchmark and again during the final
+//! summary. Since the work is constant, any variation in its run time is caused by the machine
+//! (frequency scaling, other processes, interrupts...) rather than by the code being benchmarked.
+//! Changes smaller than this noise floor should be treated with suspicion.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+
+use crate::black_box;
+use crate::clock::Instant;
+use crate::format;
+use crate::stats::univariate::Sample;
+
+const SPIN_ITERATIONS: u64 = 100_000;
+const CALIBRATION_TIME: Duration = Duration::from_millis(500);
+
+/// Timing statistics of one calibration run.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct Calibration {
+    /// Median time of one spin loop, in nanoseconds.
+    pub median: f64,
+    /// Median absolute deviation of the spin loop time, relative to the median. This is used
+    /// rather than the standard deviation so that a few preempted samples don't dominate it.
+    pub relative_mad: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct NoiseFloor {
+    pub start: Calibration,
+    pub end: Option<Calibration>,
+}
+impl NoiseFloor {
+    /// Relative change in the median spin loop time between the start and end of the session.
+    pub fn drift(&self) -> Option<f64> {
+        self.end
+            .map(|end| (end.median - self.start.median).abs() / self.start.median)
+    }
+
+    /// The noise floor as a fraction, ie. the largest relative variation observed in the spin
+    /// loop: its spread at either end of the session, or the drift between them.
+    pub fn relative(&self) -> f64 {
+        let mut noise = self.start.relative_mad;
+        if let Some(end) = self.end {
+            noise = noise.max(end.relative_mad);
+        }
+        if let Some(drift) = self.drift() {
+            noise = noise.max(drift);
+        }
+        noise
+    }
+}
+
+/// Formats a relative noise level as eg. `±0.4213%`.
+pub(crate) fn format_relative(relative: f64) -> String {
+    format!("±{}%", format::short(relative * 1e2))
+}
+
+static NOISE_FLOOR: Lazy<Mutex<Option<NoiseFloor>>> = Lazy::new(|| Mutex::new(None));
+
+fn spin() -> u64 {
+    let mut x = 0u64;
+    for i in 0..SPIN_ITERATIONS {
+        x = black_box(x.wrapping_mul(31).wrapping_add(i));
+    }
+    x
+}
+
+fn calibrate() -> Calibration {
+    // Warm up caches and let the CPU clock settle before the samples are taken.
+    for _ in 0..10 {
+        black_box(spin());
+    }
+
+    let mut times = vec![];
+    let start = Instant::now();
+    while start.elapsed() < CALIBRATION_TIME || times.len() < 10 {
+        let sample_start = Instant::now();
+        black_box(spin());
+        times.push(sample_start.elapsed().as_nanos() as f64);
+    }
+
+    let sample = Sample::new(&times);
+    let median = sample.percentiles().median();
+    Calibration {
+        median,
+        relative_mad: sample.median_abs_dev(Some(median)) / median,
+    }
+}
+
+/// Performs the initial calibration, unless it has already been done by an earlier group.
+/// Returns true if a calibration was performed.
+pub(crate) fn calibrate_start() -> bool {
+    let mut noise_floor = NOISE_FLOOR.lock().unwrap();
+    if noise_floor.is_some() {
+        return false;
+    }
+    *noise_floor = Some(NoiseFloor {
+        start: calibrate(),
+        end: None,
+    });
+    true
+}
+
+/// Performs the final calibration. Does nothing if no benchmark was run, and therefore no initial
+/// calibration was done.
+pub(crate) fn calibrate_end() {
+    let mut noise_floor = NOISE_FLOOR.lock().unwrap();
+    if let Some(noise_floor) = noise_floor.as_mut() {
+        noise_floor.end = Some(calibrate());
+    }
+}
+
+/// Returns the noise floor measured so far in this session, if any.
+pub(crate) fn current() -> Option<NoiseFloor> {
+    *NOISE_FLOOR.lock().unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn relative_is_largest_variation() {
+        let mut noise_floor = NoiseFloor {
+            start: Calibration {
+                median: 100.0,
+                relative_mad: 0.01,
+            },
+            end: None,
+        };
+        assert_eq!(noise_floor.drift(), None);
+        assert_eq!(noise_floor.relative(), 0.01);
+
+        noise_floor.end = Some(Calibration {
+            median: 105.0,
+            relative_mad: 0.02,
+        });
+        assert!((noise_floor.drift().unwrap() - 0.05).abs() < 1e-12);
+        assert!((noise_floor.relative() - 0.05).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,239 @@
+//! Running benchmark groups concurrently (`--jobs` and `--reserve-cores`).
+//!
+//! Large suites can be sped up by running several `criterion_group!` groups at the same time, each
+//! on a worker thread pinned to its own core so that the groups disturb each other as little as
+//! possible. Groups whose `Criterion` is marked [`exclusive`](crate::Criterion::exclusive) (eg.
+//! because they measure memory bandwidth or use several threads themselves) are never run
+//! alongside any other group.
+//!
+//! Like the benchmark order, the number of jobs is needed before any `Criterion` struct is
+//! configured, so it is read from the arguments directly. Invalid values are ignored here; they are
+//! reported by `Criterion::configure_from_args`.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use once_cell::sync::Lazy;
+
+/// Held for reading by every running group, and for writing by exclusive groups.
+static GROUP_LOCK: Lazy<RwLock<()>> = Lazy::new(|| RwLock::new(()));
+static WARNED_UNPINNED: AtomicBool = AtomicBool::new(false);
+
+/// Keeps other groups from running concurrently, if the group is exclusive.
+#[allow(dead_code)]
+pub(crate) enum GroupGuard {
+    Shared(RwLockReadGuard<'static, ()>),
+    Exclusive(RwLockWriteGuard<'static, ()>),
+}
+
+/// Waits until the group may run: until no other group is running if it is exclusive, or until no
+/// exclusive group is running otherwise.
+pub(crate) fn start_group(exclusive: bool) -> GroupGuard {
+    // A panicking group must not prevent the others from running.
+    if exclusive {
+        GroupGuard::Exclusive(GROUP_LOCK.write().unwrap_or_else(|e| e.into_inner()))
+    } else {
+        GroupGuard::Shared(GROUP_LOCK.read().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Parses a comma-separated list of core IDs, eg. `2,3,6`.
+pub(crate) fn parse_cores(cores: &str) -> Option<Vec<usize>> {
+    cores
+        .split(',')
+        .map(|core| core.trim().parse().ok())
+        .collect()
+}
+
+/// Returns whether a list of CPUs such as `0-3,6` in sysfs contains the core.
+#[cfg(any(target_os = "linux", test))]
+fn cpu_list_contains(list: &str, core: usize) -> bool {
+    list.split(',').any(|range| {
+        let (first, last) = range.split_once('-').unwrap_or((range, range));
+        match (first.trim().parse::<usize>(), last.trim().parse::<usize>()) {
+            (Ok(first), Ok(last)) => (first..=last).contains(&core),
+            _ => false,
+        }
+    })
+}
+
+/// Returns an error if a thread can't be pinned to the core: if it is beyond the cores which
+/// `sched_setaffinity` can select, or not online.
+#[cfg(target_os = "linux")]
+pub(crate) fn check_core(core: usize) -> Result<(), String> {
+    let max = libc::CPU_SETSIZE as usize;
+    if core >= max {
+        return Err(format!(
+            "core {} is beyond the last supported core, {}",
+            core,
+            max - 1
+        ));
+    }
+    match std::fs::read_to_string("/sys/devices/system/cpu/online") {
+        Ok(online) if !cpu_list_contains(&online, core) => Err(format!(
+            "core {} is not online (the online cores are {})",
+            core,
+            online.trim()
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn check_core(_core: usize) -> Result<(), String> {
+    Ok(())
+}
+
+/// Warns, once, that `--reserve-cores` does nothing with a single job.
+pub(crate) fn warn_unpinned() {
+    if !WARNED_UNPINNED.swap(true, Ordering::Relaxed) {
+        eprintln!("Warning: --reserve-cores has no effect with --jobs 1, which runs the groups on the main thread without pinning it.");
+    }
+}
+
+/// Returns the number of jobs and the cores to pin them to from the command line. Groups are only
+/// run concurrently when benchmarking, not when listing, testing or profiling them.
+fn jobs_from_args<I: Iterator<Item = String>>(args: I) -> (usize, Vec<usize>) {
+    let (mut jobs, mut cores) = (1, vec![]);
+    let mut benchmarking = false;
+    let mut args = args.skip(1);
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_owned(), Some(value.to_owned())),
+            None => (arg.clone(), None),
+        };
+        match &*name {
+            "--bench" => benchmarking = true,
+            "--list" | "--test" | "--profile-time" | "--dry-run" => return (1, vec![]),
+            "--jobs" | "--reserve-cores" => {
+                let value = inline_value.or_else(|| args.next()).unwrap_or_default();
+                if name == "--jobs" {
+                    jobs = value.parse().unwrap_or(1);
+                } else {
+                    cores = parse_cores(&value).unwrap_or_default();
+                }
+            }
+            _ => {}
+        }
+    }
+    if !benchmarking {
+        return (1, vec![]);
+    }
+    (jobs.max(1), cores)
+}
+
+/// Runs the groups in the given order, concurrently if `--jobs` was passed.
+pub(crate) fn run_groups(groups: &[&'static (dyn Fn() + Sync)], order: Vec<usize>) {
+    let (jobs, cores) = jobs_from_args(std::env::args());
+    if jobs == 1 {
+        for i in order {
+            groups[i]();
+        }
+        return;
+    }
+
+    let queue: Arc<Mutex<VecDeque<&'static (dyn Fn() + Sync)>>> =
+        Arc::new(Mutex::new(order.into_iter().map(|i| groups[i]).collect()));
+    let workers: Vec<_> = (0..jobs)
+        .map(|job| {
+            let core = cores.get(job).copied();
+            let queue = Arc::clone(&queue);
+            std::thread::spawn(move || {
+                if let Some(core) = core {
+                    pin_to_core(core);
+                }
+                loop {
+                    let next = queue.lock().unwrap_or_else(|e| e.into_inner()).pop_front();
+                    match next {
+                        Some(group) => group(),
+                        None => break,
+                    }
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        // A panicking group has already printed its message; report it once the others are done.
+        if let Err(panic) = worker.join() {
+            std::panic::resume_unwind(panic);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn pin_to_core(core: usize) {
+    // The group reports the invalid core and exits once it is configured.
+    if let Err(e) = check_core(core) {
+        eprintln!("Warning: unable to pin a benchmark thread: {}", e);
+        return;
+    }
+    // Safety: `cpu_set_t` is a plain bit set for which all zeroes is a valid (empty) value, and
+    // `sched_setaffinity` only reads it. A pid of 0 means the calling thread.
+    let result = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(core, &mut set);
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+    };
+    if result != 0 {
+        eprintln!(
+            "Warning: unable to pin a benchmark thread to core {}: {}",
+            core,
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_to_core(core: usize) {
+    eprintln!(
+        "Warning: --reserve-cores is not supported on this platform; core {} will not be reserved",
+        core
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        std::iter::once("bench")
+            .chain(args.iter().copied())
+            .map(str::to_owned)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn jobs_are_parsed_from_args() {
+        assert_eq!(jobs_from_args(args(&["--bench"])), (1, vec![]));
+        assert_eq!(
+            jobs_from_args(args(&["--bench", "--jobs", "3", "--reserve-cores=2,3,4"])),
+            (3, vec![2, 3, 4])
+        );
+        assert_eq!(jobs_from_args(args(&["--jobs", "3"])), (1, vec![]));
+        assert_eq!(
+            jobs_from_args(args(&["--bench", "--jobs=3", "--list"])),
+            (1, vec![])
+        );
+        assert_eq!(parse_cores("1, 2"), Some(vec![1, 2]));
+        assert_eq!(parse_cores("1,a"), None);
+    }
+
+    #[test]
+    fn cpu_lists() {
+        assert!(cpu_list_contains("0-3,6\n", 0));
+        assert!(cpu_list_contains("0-3,6\n", 3));
+        assert!(cpu_list_contains("0-3,6\n", 6));
+        assert!(!cpu_list_contains("0-3,6\n", 4));
+        assert!(!cpu_list_contains("0-3,6\n", 7));
+        assert!(cpu_list_contains("0", 0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn cores_are_checked() {
+        assert_eq!(check_core(0), Ok(()));
+        assert!(check_core(1024).unwrap_err().contains("beyond"));
+    }
+}
@@ -0,0 +1,282 @@
+//! Phase markers for `perf record` (`--perf-markers`).
+//!
+//! When the benchmarks are run under `perf record`, the profile covers the whole process: the
+//! warm-up, the analysis and the plotting as well as the measurement. With `--perf-markers`, an
+//! event is emitted at the start of the warm-up, at the start of the measurement, before each
+//! sample and at the end of the measurement, so that the profile can be cut down to the measured
+//! region with `perf report --time`.
+//!
+//! Two methods are supported, both Linux-only:
+//!
+//! * `mmap` briefly maps a file named after the marker, eg.
+//!   `criterion-marker.measurement.my_group_fib 20`. `perf record` records the mapping as a
+//!   `PERF_RECORD_MMAP` event, which `perf script --show-mmap-events` prints with its time stamp.
+//!   This requires no privileges.
+//! * `ftrace` writes the marker to the `trace_marker` file of tracefs, which `perf record` records
+//!   as an `ftrace:print` event when asked to with `-e ftrace:print`. This requires write access to
+//!   tracefs, which usually means running as root.
+//!
+//! The markers are emitted outside the timed region, so they don't affect the measurements.
+
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::report::{make_filename_safe, BenchmarkId};
+
+/// The tracefs mount points in which to look for `trace_marker`, newest first.
+const TRACE_MARKERS: [&str; 2] = [
+    "/sys/kernel/tracing/trace_marker",
+    "/sys/kernel/debug/tracing/trace_marker",
+];
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Method {
+    Mmap,
+    Ftrace,
+}
+impl Method {
+    pub fn from_arg(arg: &str) -> Option<Method> {
+        match arg {
+            "mmap" => Some(Method::Mmap),
+            "ftrace" => Some(Method::Ftrace),
+            _ => None,
+        }
+    }
+}
+
+enum Sink {
+    /// Directory in which the marker files are created.
+    Mmap(PathBuf),
+    /// The open `trace_marker` file.
+    Ftrace(File),
+}
+
+struct Markers {
+    sink: Sink,
+    /// Title of the benchmark being measured, set at the start of its warm-up.
+    id: String,
+    measuring: bool,
+    samples: u64,
+    /// Whether the marker files could only be mapped without `PROT_EXEC`; warned about once.
+    non_executable: bool,
+}
+
+// Checked before taking the lock, so that the benchmarks only pay for markers when they are
+// enabled.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+static MARKERS: Lazy<Mutex<Option<Markers>>> = Lazy::new(|| Mutex::new(None));
+
+/// Enables the markers for the rest of the run. Only the first call, ie. from the first benchmark
+/// group, has any effect, so that a failure is only reported once.
+pub(crate) fn enable(method: Method) -> Result<(), String> {
+    if REQUESTED.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    let markers = Markers::new(method)?;
+    *MARKERS.lock().unwrap_or_else(|e| e.into_inner()) = Some(markers);
+    ENABLED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Marks the start of the warm-up of `id`.
+pub(crate) fn warm_up(id: &BenchmarkId) {
+    with_markers(|markers| {
+        markers.id = id.as_title().to_owned();
+        markers.measuring = false;
+        markers.emit("warm-up");
+    });
+}
+
+/// Marks the start of the measurement of the benchmark whose warm-up was last marked.
+pub(crate) fn measurement(id: &BenchmarkId) {
+    with_markers(|markers| {
+        markers.id = id.as_title().to_owned();
+        markers.measuring = true;
+        markers.samples = 0;
+        markers.emit("measurement");
+    });
+}
+
+/// Marks the start of the next sample, if a measurement is in progress.
+pub(crate) fn sample() {
+    with_markers(|markers| {
+        if markers.measuring {
+            let event = format!("sample-{}", markers.samples);
+            markers.samples += 1;
+            markers.emit(&event);
+        }
+    });
+}
+
+/// Marks the end of the measurement.
+pub(crate) fn end() {
+    with_markers(|markers| {
+        if markers.measuring {
+            markers.measuring = false;
+            markers.emit("end");
+        }
+    });
+}
+
+/// Removes the directory of the marker files at the end of the run.
+pub(crate) fn finish() {
+    with_markers(|markers| {
+        if let Sink::Mmap(dir) = &markers.sink {
+            let _ = std::fs::remove_dir(dir);
+        }
+    });
+}
+
+fn with_markers<F: FnOnce(&mut Markers)>(f: F) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut markers = MARKERS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(markers) = markers.as_mut() {
+        f(markers);
+    }
+}
+
+/// Name of the file mapped for the `mmap` method. The event comes first, so that it survives the
+/// truncation of long IDs.
+fn file_name(event: &str, id: &str) -> String {
+    make_filename_safe(&format!("criterion-marker.{}.{}", event, id))
+}
+
+impl Markers {
+    fn new(method: Method) -> Result<Markers, String> {
+        if !cfg!(target_os = "linux") {
+            return Err("perf markers are only supported on Linux".to_owned());
+        }
+        let sink = match method {
+            Method::Mmap => {
+                let dir = std::env::temp_dir()
+                    .join(format!("criterion-perf-markers-{}", std::process::id()));
+                std::fs::create_dir_all(&dir)
+                    .map_err(|e| format!("unable to create {}: {}", dir.display(), e))?;
+                Sink::Mmap(dir)
+            }
+            Method::Ftrace => {
+                let file = TRACE_MARKERS
+                    .iter()
+                    .find_map(|path| std::fs::OpenOptions::new().write(true).open(path).ok())
+                    .ok_or_else(|| {
+                        format!("unable to open {} for writing", TRACE_MARKERS.join(" or "))
+                    })?;
+                Sink::Ftrace(file)
+            }
+        };
+        Ok(Markers {
+            sink,
+            id: String::new(),
+            measuring: false,
+            samples: 0,
+            non_executable: false,
+        })
+    }
+
+    fn emit(&mut self, event: &str) {
+        let result = match &mut self.sink {
+            Sink::Mmap(dir) => {
+                let path = dir.join(file_name(event, &self.id));
+                match map_marker(&path) {
+                    Ok(executable) => {
+                        if !executable && !self.non_executable {
+                            self.non_executable = true;
+                            eprintln!("Warning: the perf marker files in {} could not be mapped as executable; run perf record with --data to record them.", dir.display());
+                        }
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            Sink::Ftrace(file) => {
+                use std::io::Write;
+                // A single write, so that the marker is one event.
+                let line = format!("criterion: {}: {}\n", event, self.id);
+                file.write_all(line.as_bytes())
+            }
+        };
+        if let Err(e) = result {
+            eprintln!(
+                "Warning: unable to emit the perf marker {} for {}: {}",
+                event, self.id, e
+            );
+        }
+    }
+}
+
+/// Maps and unmaps the file at `path`, which the kernel reports to `perf record` as it is mapped.
+/// The file is removed again straight away. Returns whether the file could be mapped as executable;
+/// `perf record` only records the other mappings when run with `--data`.
+#[cfg(target_os = "linux")]
+fn map_marker(path: &std::path::Path) -> std::io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    std::fs::write(path, [0u8])?;
+    let file = File::open(path);
+    let result = file.and_then(|file| {
+        let map = |protection| {
+            // Safety: a new shared mapping of one page of a file we own is created and immediately
+            // unmapped again without being accessed.
+            unsafe {
+                let address = libc::mmap(
+                    std::ptr::null_mut(),
+                    1,
+                    protection,
+                    libc::MAP_SHARED,
+                    file.as_raw_fd(),
+                    0,
+                );
+                if address == libc::MAP_FAILED {
+                    return Err(std::io::Error::last_os_error());
+                }
+                libc::munmap(address, 1);
+                Ok(())
+            }
+        };
+        map(libc::PROT_READ | libc::PROT_EXEC)
+            .map(|()| true)
+            .or_else(|_| map(libc::PROT_READ).map(|()| false))
+    });
+    std::fs::remove_file(path)?;
+    result
+}
+
+#[cfg(not(target_os = "linux"))]
+fn map_marker(_path: &std::path::Path) -> std::io::Result<bool> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "perf markers are only supported on Linux",
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn marker_file_names_are_safe() {
+        assert_eq!(
+            file_name("sample-3", "group/fib 20"),
+            "criterion-marker.sample-3.group_fib 20"
+        );
+        let long = file_name("end", &"x".repeat(200));
+        assert!(long.starts_with("criterion-marker.end."));
+        assert!(long.len() < 200);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn marker_files_are_mapped_and_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(file_name("warm-up", "id"));
+        map_marker(&path).unwrap();
+        assert!(!path.exists());
+    }
+}
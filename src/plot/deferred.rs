@@ -0,0 +1,84 @@
+//! Rendering plots in the background (`--defer-plots`).
+//!
+//! The gnuplot backend renders every plot in a gnuplot child process, and by default the report of
+//! a benchmark waits for all of them before the next benchmark starts. With deferred plots, the
+//! report leaves them running at a lower priority while the next benchmark is set up and warmed
+//! up, and the sampling only starts once they have all finished, so that they never compete with
+//! a measurement for the CPU. The final summary waits for any plots that are still outstanding.
+//!
+//! The plotters backend renders in-process, before the report returns, so there is nothing to
+//! defer.
+
+use std::process::Child;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+/// The nice value of the deferred plot processes, the default of `nice(1)`.
+#[cfg(target_os = "linux")]
+const NICENESS: libc::c_int = 10;
+
+/// Plot processes which are still rendering, on behalf of any thread.
+static OUTSTANDING: Lazy<Mutex<Vec<Child>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Leaves `children` running until the next call to [`wait_until_quiet`].
+pub(crate) fn defer<I: IntoIterator<Item = Child>>(children: I) {
+    let mut outstanding = OUTSTANDING.lock().unwrap_or_else(|e| e.into_inner());
+    for child in children {
+        lower_priority(&child);
+        outstanding.push(child);
+    }
+}
+
+/// Waits for all the deferred plot processes to finish.
+pub(crate) fn wait_until_quiet() {
+    let children: Vec<Child> = {
+        let mut outstanding = OUTSTANDING.lock().unwrap_or_else(|e| e.into_inner());
+        outstanding.drain(..).collect()
+    };
+    if !children.is_empty() {
+        elapsed! {
+            "Waiting for deferred plots",
+            wait_all(children)
+        }
+    }
+}
+
+/// Waits for the gnuplot processes `children`, logging their errors.
+pub(crate) fn wait_all<I: IntoIterator<Item = Child>>(children: I) {
+    for child in children {
+        match child.wait_with_output() {
+            Ok(ref out) if out.status.success() => {}
+            Ok(out) => error!("Error in Gnuplot: {}", String::from_utf8_lossy(&out.stderr)),
+            Err(e) => error!("Got IO error while waiting for Gnuplot to complete: {}", e),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn lower_priority(child: &Child) {
+    // The process may already have finished, in which case there is nothing to do.
+    // Safety: `setpriority` only reads its arguments.
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, child.id() as libc::id_t, NICENESS);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn lower_priority(_child: &Child) {}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn waits_for_deferred_processes() {
+        defer(vec![
+            Command::new("sleep").arg("0.1").spawn().unwrap(),
+            Command::new("true").spawn().unwrap(),
+        ]);
+        wait_until_quiet();
+        assert!(OUTSTANDING.lock().unwrap().is_empty());
+    }
+}
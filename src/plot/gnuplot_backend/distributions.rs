@@ -8,7 +8,7 @@ use criterion_plot::prelude::*;
 use super::*;
 use crate::estimate::Estimate;
 use crate::estimate::Statistic;
-use crate::kde;
+use crate::kde::{self, Bandwidth};
 use crate::measurement::ValueFormatter;
 use crate::report::{BenchmarkId, ComparisonData, MeasurementData, ReportContext};
 
@@ -32,7 +32,12 @@ fn abs_distribution(
     let mut scaled_xs: Vec<f64> = distribution.iter().cloned().collect();
     let _ = formatter.scale_values(typical, &mut scaled_xs);
     let scaled_xs_sample = Sample::new(&scaled_xs);
-    let (kde_xs, ys) = kde::sweep(scaled_xs_sample, KDE_POINTS, Some((start, end)));
+    let (kde_xs, ys) = kde::sweep(
+        scaled_xs_sample,
+        Bandwidth::Silverman,
+        context.plot_config.kde_points,
+        Some((start, end)),
+    );
 
     // interpolate between two points of the KDE sweep to find the Y position at the point estimate.
     let n_point = kde_xs
@@ -64,7 +69,6 @@ fn abs_distribution(
 
     let mut figure = Figure::new();
     figure
-        .set(Font(DEFAULT_FONT))
         .set(size.unwrap_or(SIZE))
         .set(Title(format!(
             "{}: {}",
@@ -118,9 +122,8 @@ fn abs_distribution(
             },
         );
 
-    let path = context.report_path(id, &format!("{}.svg", statistic));
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, &statistic.to_string());
+    draw(&mut figure, path, &context.plot_config)
 }
 
 pub(crate) fn abs_distributions(
@@ -168,7 +171,12 @@ fn rel_distribution(
 
     let start = lb - (ub - lb) / 9.;
     let end = ub + (ub - lb) / 9.;
-    let (xs, ys) = kde::sweep(distribution, KDE_POINTS, Some((start, end)));
+    let (xs, ys) = kde::sweep(
+        distribution,
+        Bandwidth::Silverman,
+        context.plot_config.kde_points,
+        Some((start, end)),
+    );
     let xs_ = Sample::new(&xs);
 
     // interpolate between two points of the KDE sweep to find the Y position at the point estimate.
@@ -219,7 +227,6 @@ fn rel_distribution(
     let mut figure = Figure::new();
 
     figure
-        .set(Font(DEFAULT_FONT))
         .set(size.unwrap_or(SIZE))
         .configure(Axis::LeftY, |a| a.set(Label("Density (a.u.)")))
         .configure(Key, |k| {
@@ -281,9 +288,8 @@ fn rel_distribution(
             },
         );
 
-    let path = context.report_path(id, &format!("change/{}.svg", statistic));
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, &format!("change/{}", statistic));
+    draw(&mut figure, path, &context.plot_config)
 }
 
 pub(crate) fn rel_distributions(
@@ -3,6 +3,7 @@ use std::process::Child;
 use criterion_plot::prelude::*;
 
 use super::*;
+use crate::plot::drift_series;
 use crate::report::{BenchmarkId, ComparisonData, MeasurementData, ReportContext};
 
 use crate::measurement::ValueFormatter;
@@ -20,7 +21,6 @@ fn iteration_times_figure(
 
     let mut figure = Figure::new();
     figure
-        .set(Font(DEFAULT_FONT))
         .set(size.unwrap_or(SIZE))
         .configure(Axis::BottomX, |a| {
             a.configure(Grid::Major, |g| g.show()).set(Label("Sample"))
@@ -58,9 +58,8 @@ pub(crate) fn iteration_times(
             .set(Position::Inside(Vertical::Top, Horizontal::Left))
     });
 
-    let path = context.report_path(id, "iteration_times.svg");
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, "iteration_times");
+    draw(&mut figure, path, &context.plot_config)
 }
 
 pub(crate) fn iteration_times_small(
@@ -73,9 +72,8 @@ pub(crate) fn iteration_times_small(
     let mut figure = iteration_times_figure(formatter, measurements, size);
     figure.configure(Key, |k| k.hide());
 
-    let path = context.report_path(id, "iteration_times_small.svg");
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, "iteration_times_small");
+    draw(&mut figure, path, &context.plot_config)
 }
 
 fn iteration_times_comparison_figure(
@@ -99,7 +97,6 @@ fn iteration_times_comparison_figure(
 
     let mut figure = Figure::new();
     figure
-        .set(Font(DEFAULT_FONT))
         .set(size.unwrap_or(SIZE))
         .configure(Axis::BottomX, |a| {
             a.configure(Grid::Major, |g| g.show()).set(Label("Sample"))
@@ -151,9 +148,8 @@ pub(crate) fn iteration_times_comparison(
     let mut figure = iteration_times_comparison_figure(formatter, measurements, comparison, size);
     figure.set(Title(gnuplot_escape(id.as_title())));
 
-    let path = context.report_path(id, "both/iteration_times.svg");
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, "both/iteration_times");
+    draw(&mut figure, path, &context.plot_config)
 }
 
 pub(crate) fn iteration_times_comparison_small(
@@ -167,7 +163,61 @@ pub(crate) fn iteration_times_comparison_small(
     let mut figure = iteration_times_comparison_figure(formatter, measurements, comparison, size);
     figure.configure(Key, |k| k.hide());
 
-    let path = context.report_path(id, "relative_iteration_times_small.svg");
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, "relative_iteration_times_small");
+    draw(&mut figure, path, &context.plot_config)
+}
+
+pub(crate) fn drift(
+    id: &BenchmarkId,
+    context: &ReportContext,
+    formatter: &dyn ValueFormatter,
+    measurements: &MeasurementData<'_>,
+    size: Option<Size>,
+) -> Option<Child> {
+    let (series, unit) = drift_series(formatter, measurements)?;
+    let n = series.ys.len();
+
+    let mut figure = Figure::new();
+    figure
+        .set(size.unwrap_or(SIZE))
+        .configure(Axis::BottomX, |a| {
+            a.configure(Grid::Major, |g| g.show())
+                .set(Label("Sample, in the order measured"))
+        })
+        .configure(Axis::LeftY, |a| {
+            a.configure(Grid::Major, |g| g.show())
+                .set(Label(format!("Time per Iteration ({})", unit)))
+        })
+        .configure(Key, |k| {
+            k.set(Justification::Left)
+                .set(Order::SampleText)
+                .set(Position::Inside(Vertical::Top, Horizontal::Left))
+        })
+        .plot(
+            Points {
+                x: 1..(n + 1),
+                y: &*series.ys,
+            },
+            |c| {
+                c.set(DARK_BLUE)
+                    .set(Label("Sample"))
+                    .set(PointSize(0.5))
+                    .set(PointType::FilledCircle)
+            },
+        )
+        .plot(
+            Lines {
+                x: &[1.0, n as f64],
+                y: &series.trend,
+            },
+            |c| {
+                c.set(DARK_RED)
+                    .set(LINEWIDTH)
+                    .set(Label(series.trend_label.clone()))
+            },
+        );
+    figure.set(Title(gnuplot_escape(id.as_title())));
+
+    let path = context.plot_path(id, "drift");
+    Some(draw(&mut figure, path, &context.plot_config))
 }
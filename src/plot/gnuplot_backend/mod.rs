@@ -1,5 +1,5 @@
 use std::iter;
-use std::path::Path;
+use std::path::PathBuf;
 use std::process::Child;
 
 use crate::stats::univariate::Sample;
@@ -18,38 +18,89 @@ use self::regression::*;
 use self::summary::*;
 use self::t_test::*;
 
+use crate::estimate::ConfidenceInterval;
 use crate::measurement::ValueFormatter;
-use crate::report::{BenchmarkId, ValueType};
+use crate::report::{BenchmarkId, SignificantChange, ValueType};
 use crate::stats::bivariate::Data;
 
-use super::{PlotContext, PlotData, Plotter};
-use crate::format;
+use super::{
+    deferred, kde_bandwidth, PlotContext, PlotData, Plotter, DARK_BACKGROUND, DARK_FOREGROUND,
+};
+use crate::{format, Corner, LegendPosition, PlotConfiguration, PlotFormat, Theme};
 
 fn gnuplot_escape(string: &str) -> String {
     string.replace('_', "\\_").replace('\'', "''")
 }
 
 static DEFAULT_FONT: &str = "Helvetica";
-static KDE_POINTS: usize = 500;
 static SIZE: Size = Size(1280, 720);
 
 const LINEWIDTH: LineWidth = LineWidth(2.);
 const POINT_SIZE: PointSize = PointSize(0.75);
+/// Size of the markers of significant changes, which stand out from the other points.
+const CHANGE_POINT_SIZE: PointSize = PointSize(1.5);
 
 const DARK_BLUE: Color = Color::Rgb(31, 120, 180);
 const DARK_ORANGE: Color = Color::Rgb(255, 127, 0);
 const DARK_RED: Color = Color::Rgb(227, 26, 28);
+const DARK_GREEN: Color = Color::Rgb(51, 160, 44);
 
-fn debug_script(path: &Path, figure: &Figure) {
-    if crate::debug_enabled() {
-        let mut script_path = path.to_path_buf();
-        script_path.set_extension("gnuplot");
+/// Renders `figure` to `path`, in the format given by its extension, with the colors of the theme,
+/// the fonts and the legend position of `conf`. With `Criterion::export_plot_scripts`, or in debug
+/// mode, the script of the figure and the data of its plots are saved next to it, as `.gnuplot` and
+/// `.dat` files.
+fn draw(figure: &mut Figure, path: PathBuf, conf: &PlotConfiguration) -> Child {
+    let terminal = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("png") => Terminal::Png,
+        Some("pdf") => Terminal::Pdf,
+        _ => Terminal::Svg,
+    };
+    if conf.theme == Theme::Dark {
+        let rgb = |(r, g, b)| Color::Rgb(r, g, b);
+        figure
+            .set(Background(rgb(DARK_BACKGROUND)))
+            .set(Foreground(rgb(DARK_FOREGROUND)));
+    }
+    figure.set(Font(
+        conf.font_family
+            .clone()
+            .unwrap_or_else(|| DEFAULT_FONT.to_owned()),
+    ));
+    if let Some(size) = conf.font_size {
+        figure.set(FontSize(size));
+    }
+    if let Some(legend) = conf.legend_position {
+        figure.configure(Key, |k| k.set(key_position(legend)));
+    }
+    figure.set(terminal).set(Output(path.clone()));
+    if conf.export_scripts || crate::debug_enabled() {
+        let script_path = path.with_extension("gnuplot");
+        let data_path = path.with_extension("dat");
         info!("Writing gnuplot script to {:?}", script_path);
-        let result = figure.save(script_path.as_path());
-        if let Err(e) = result {
-            error!("Failed to write debug output: {}", e);
+        if let Err(e) = figure.export(&script_path, &data_path) {
+            error!("Failed to write the gnuplot script: {}", e);
         }
     }
+    figure.draw().unwrap()
+}
+
+/// The position of the key of the plots for `legend`.
+fn key_position(legend: LegendPosition) -> Position {
+    let (corner, inside) = match legend {
+        LegendPosition::Inside(corner) => (corner, true),
+        LegendPosition::Outside(corner) => (corner, false),
+    };
+    let (vertical, horizontal) = match corner {
+        Corner::TopLeft => (Vertical::Top, Horizontal::Left),
+        Corner::TopRight => (Vertical::Top, Horizontal::Right),
+        Corner::BottomLeft => (Vertical::Bottom, Horizontal::Left),
+        Corner::BottomRight => (Vertical::Bottom, Horizontal::Right),
+    };
+    if inside {
+        Position::Inside(vertical, horizontal)
+    } else {
+        Position::Outside(vertical, horizontal)
+    }
 }
 
 /// Private
@@ -101,6 +152,28 @@ impl Plotter for Gnuplot {
         });
     }
 
+    fn cdf(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>) {
+        let size = ctx.size.map(|(w, h)| Size(w, h));
+        self.process_list.push(cdf(
+            ctx.id,
+            ctx.context,
+            data.formatter,
+            data.measurements,
+            size,
+        ));
+    }
+
+    fn drift(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>) {
+        let size = ctx.size.map(|(w, h)| Size(w, h));
+        self.process_list.extend(drift(
+            ctx.id,
+            ctx.context,
+            data.formatter,
+            data.measurements,
+            size,
+        ));
+    }
+
     fn regression(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>) {
         let size = ctx.size.map(|(w, h)| Size(w, h));
         self.process_list.push(if ctx.is_thumbnail {
@@ -204,6 +277,8 @@ impl Plotter for Gnuplot {
         ctx: PlotContext<'_>,
         formatter: &dyn ValueFormatter,
         all_curves: &[&(&BenchmarkId, Vec<f64>)],
+        mean_intervals: &[Option<ConfidenceInterval>],
+        changes: &[Option<SignificantChange>],
         value_type: ValueType,
     ) {
         let path = ctx.line_comparison_path();
@@ -211,6 +286,8 @@ impl Plotter for Gnuplot {
             formatter,
             ctx.id.as_title(),
             all_curves,
+            mean_intervals,
+            changes,
             &path,
             value_type,
             &ctx.context.plot_config,
@@ -230,20 +307,43 @@ impl Plotter for Gnuplot {
             ctx.id.as_title(),
             all_curves,
             &violin_path,
-            ctx.context.plot_config.x_scale,
+            &ctx.context.plot_config,
+            &ctx.context.percentiles,
+        ));
+    }
+
+    fn box_plot(
+        &mut self,
+        ctx: PlotContext<'_>,
+        formatter: &dyn ValueFormatter,
+        all_curves: &[&(&BenchmarkId, Vec<f64>)],
+    ) {
+        self.process_list.push(box_plot(
+            formatter,
+            ctx.id.as_title(),
+            all_curves,
+            &ctx.box_plot_path(),
+            &ctx.context.plot_config,
+        ));
+    }
+
+    fn supports(&self, _format: PlotFormat) -> bool {
+        true
+    }
+
+    fn speedup_summary(&mut self, ctx: PlotContext<'_>, speedups: &[(String, f64)]) {
+        self.process_list.push(speedup_summary(
+            ctx.id.as_title(),
+            speedups,
+            &ctx.speedup_path(),
+            &ctx.context.plot_config,
         ));
     }
 
     fn wait(&mut self) {
         let start = std::time::Instant::now();
         let child_count = self.process_list.len();
-        for child in self.process_list.drain(..) {
-            match child.wait_with_output() {
-                Ok(ref out) if out.status.success() => {}
-                Ok(out) => error!("Error in Gnuplot: {}", String::from_utf8_lossy(&out.stderr)),
-                Err(e) => error!("Got IO error while waiting for Gnuplot to complete: {}", e),
-            }
-        }
+        deferred::wait_all(self.process_list.drain(..));
         let elapsed = &start.elapsed();
         info!(
             "Waiting for {} gnuplot processes took {}",
@@ -251,4 +351,8 @@ impl Plotter for Gnuplot {
             format::time(elapsed.as_nanos() as f64)
         );
     }
+
+    fn defer(&mut self) {
+        deferred::defer(self.process_list.drain(..));
+    }
 }
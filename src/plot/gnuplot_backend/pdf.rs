@@ -1,6 +1,7 @@
 use super::*;
 use crate::kde;
 use crate::measurement::ValueFormatter;
+use crate::plot::ecdf;
 use crate::report::{BenchmarkId, ComparisonData, MeasurementData, ReportContext};
 use std::process::Child;
 
@@ -33,7 +34,11 @@ pub(crate) fn pdf(
         format!("Iterations (x 10^{})", exponent)
     };
 
-    let (xs, ys) = kde::sweep(scaled_avg_times, KDE_POINTS, None);
+    let conf = &context.plot_config;
+    let bandwidth = kde_bandwidth(conf, |h| {
+        formatter.scale_values(typical, h);
+    });
+    let (xs, ys) = kde::sweep(scaled_avg_times, bandwidth, conf.kde_points, None);
     let (lost, lomt, himt, hist) = avg_times.fences();
     let mut fences = [lost, lomt, himt, hist];
     let _ = formatter.scale_values(typical, &mut fences);
@@ -44,7 +49,6 @@ pub(crate) fn pdf(
 
     let mut figure = Figure::new();
     figure
-        .set(Font(DEFAULT_FONT))
         .set(size.unwrap_or(SIZE))
         .configure(Axis::BottomX, |a| {
             let xs_ = Sample::new(&xs);
@@ -219,9 +223,67 @@ pub(crate) fn pdf(
         );
     figure.set(Title(gnuplot_escape(id.as_title())));
 
-    let path = context.report_path(id, "pdf.svg");
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, "pdf");
+    draw(&mut figure, path, &context.plot_config)
+}
+
+pub(crate) fn cdf(
+    id: &BenchmarkId,
+    context: &ReportContext,
+    formatter: &dyn ValueFormatter,
+    measurements: &MeasurementData<'_>,
+    size: Option<Size>,
+) -> Child {
+    let (ecdf, unit) = ecdf(formatter, measurements);
+    let vertical = &[0., 1.];
+
+    let mut figure = Figure::new();
+    figure
+        .set(size.unwrap_or(SIZE))
+        .configure(Axis::BottomX, |a| {
+            a.set(Label(format!("Average time ({})", unit)))
+                .configure(Grid::Major, |g| g.show())
+        })
+        .configure(Axis::LeftY, |a| {
+            a.set(Label("Cumulative probability"))
+                .set(Range::Limits(0., 1.))
+                .configure(Grid::Major, |g| g.show())
+        })
+        .configure(Key, |k| {
+            k.set(Justification::Left)
+                .set(Order::SampleText)
+                .set(Position::Inside(Vertical::Bottom, Horizontal::Right))
+        })
+        .plot(
+            Steps {
+                x: &*ecdf.xs,
+                y: &*ecdf.ys,
+            },
+            |c| {
+                c.set(DARK_BLUE)
+                    .set(LINEWIDTH)
+                    .set(Label("Empirical CDF"))
+                    .set(LineType::Solid)
+            },
+        );
+    for (x, label, (r, g, b)) in ecdf.percentiles {
+        figure.plot(
+            Lines {
+                x: &[x, x],
+                y: vertical,
+            },
+            |c| {
+                c.set(Color::Rgb(r, g, b))
+                    .set(LINEWIDTH)
+                    .set(LineType::Dash)
+                    .set(Label(label))
+            },
+        );
+    }
+    figure.set(Title(gnuplot_escape(id.as_title())));
+
+    let path = context.plot_path(id, "cdf");
+    draw(&mut figure, path, &context.plot_config)
 }
 
 pub(crate) fn pdf_small(
@@ -238,7 +300,12 @@ pub(crate) fn pdf_small(
     let scaled_avg_times = Sample::new(&scaled_avg_times);
     let mean = scaled_avg_times.mean();
 
-    let (xs, ys, mean_y) = kde::sweep_and_estimate(scaled_avg_times, KDE_POINTS, None, mean);
+    let conf = &context.plot_config;
+    let bandwidth = kde_bandwidth(conf, |h| {
+        formatter.scale_values(typical, h);
+    });
+    let (xs, ys, mean_y) =
+        kde::sweep_and_estimate(scaled_avg_times, bandwidth, conf.kde_points, None, mean);
     let xs_ = Sample::new(&xs);
     let ys_ = Sample::new(&ys);
 
@@ -247,7 +314,6 @@ pub(crate) fn pdf_small(
 
     let mut figure = Figure::new();
     figure
-        .set(Font(DEFAULT_FONT))
         .set(size.unwrap_or(SIZE))
         .configure(Axis::BottomX, |a| {
             a.set(Label(format!("Average time ({})", unit)))
@@ -280,12 +346,12 @@ pub(crate) fn pdf_small(
             |c| c.set(DARK_BLUE).set(LINEWIDTH).set(Label("Mean")),
         );
 
-    let path = context.report_path(id, "pdf_small.svg");
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, "pdf_small");
+    draw(&mut figure, path, &context.plot_config)
 }
 
 fn pdf_comparison_figure(
+    conf: &PlotConfiguration,
     formatter: &dyn ValueFormatter,
     measurements: &MeasurementData<'_>,
     comparison: &ComparisonData,
@@ -307,16 +373,28 @@ fn pdf_comparison_figure(
     let base_mean = scaled_base_avg_times.mean();
     let new_mean = scaled_new_avg_times.mean();
 
-    let (base_xs, base_ys, base_y_mean) =
-        kde::sweep_and_estimate(scaled_base_avg_times, KDE_POINTS, None, base_mean);
-    let (xs, ys, y_mean) =
-        kde::sweep_and_estimate(scaled_new_avg_times, KDE_POINTS, None, new_mean);
+    let bandwidth = kde_bandwidth(conf, |h| {
+        formatter.scale_values(typical, h);
+    });
+    let (base_xs, base_ys, base_y_mean) = kde::sweep_and_estimate(
+        scaled_base_avg_times,
+        bandwidth,
+        conf.kde_points,
+        None,
+        base_mean,
+    );
+    let (xs, ys, y_mean) = kde::sweep_and_estimate(
+        scaled_new_avg_times,
+        bandwidth,
+        conf.kde_points,
+        None,
+        new_mean,
+    );
 
     let zeros = iter::repeat(0);
 
     let mut figure = Figure::new();
     figure
-        .set(Font(DEFAULT_FONT))
         .set(size.unwrap_or(SIZE))
         .configure(Axis::BottomX, |a| {
             a.set(Label(format!("Average time ({})", unit)))
@@ -369,11 +447,16 @@ pub(crate) fn pdf_comparison(
     comparison: &ComparisonData,
     size: Option<Size>,
 ) -> Child {
-    let mut figure = pdf_comparison_figure(formatter, measurements, comparison, size);
+    let mut figure = pdf_comparison_figure(
+        &context.plot_config,
+        formatter,
+        measurements,
+        comparison,
+        size,
+    );
     figure.set(Title(gnuplot_escape(id.as_title())));
-    let path = context.report_path(id, "both/pdf.svg");
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, "both/pdf");
+    draw(&mut figure, path, &context.plot_config)
 }
 
 pub(crate) fn pdf_comparison_small(
@@ -384,9 +467,14 @@ pub(crate) fn pdf_comparison_small(
     comparison: &ComparisonData,
     size: Option<Size>,
 ) -> Child {
-    let mut figure = pdf_comparison_figure(formatter, measurements, comparison, size);
+    let mut figure = pdf_comparison_figure(
+        &context.plot_config,
+        formatter,
+        measurements,
+        comparison,
+        size,
+    );
     figure.configure(Key, |k| k.hide());
-    let path = context.report_path(id, "relative_pdf_small.svg");
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, "relative_pdf_small");
+    draw(&mut figure, path, &context.plot_config)
 }
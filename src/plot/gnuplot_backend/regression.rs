@@ -1,10 +1,12 @@
 use std::process::Child;
 
-use crate::stats::bivariate::regression::Slope;
 use criterion_plot::prelude::*;
 
 use super::*;
-use crate::report::{BenchmarkId, ComparisonData, MeasurementData, ReportContext};
+use crate::plot::change_label;
+use crate::report::{
+    BenchmarkId, ComparisonData, MeasurementData, ReportContext, SignificantChange,
+};
 use crate::stats::bivariate::Data;
 
 use crate::estimate::{ConfidenceInterval, Estimate};
@@ -27,7 +29,7 @@ fn regression_figure(
     let unit = formatter.scale_values(typical, &mut scaled_y);
     let scaled_y = Sample::new(&scaled_y);
 
-    let point_estimate = Slope::fit(&measurements.data).0;
+    let point_estimate = slope_estimate.point_estimate;
     let mut scaled_points = [point_estimate * max_iters, lb * max_iters, ub * max_iters];
     let _ = formatter.scale_values(typical, &mut scaled_points);
     let [point, lb, ub] = scaled_points;
@@ -43,7 +45,6 @@ fn regression_figure(
 
     let mut figure = Figure::new();
     figure
-        .set(Font(DEFAULT_FONT))
         .set(size.unwrap_or(SIZE))
         .configure(Axis::BottomX, |a| {
             a.configure(Grid::Major, |g| g.show())
@@ -108,9 +109,8 @@ pub(crate) fn regression(
             .set(Position::Inside(Vertical::Top, Horizontal::Left))
     });
 
-    let path = context.report_path(id, "regression.svg");
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, "regression");
+    draw(&mut figure, path, &context.plot_config)
 }
 
 pub(crate) fn regression_small(
@@ -123,9 +123,8 @@ pub(crate) fn regression_small(
     let mut figure = regression_figure(formatter, measurements, size);
     figure.configure(Key, |k| k.hide());
 
-    let path = context.report_path(id, "regression_small.svg");
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, "regression_small");
+    draw(&mut figure, path, &context.plot_config)
 }
 
 fn regression_comparison_figure(
@@ -183,7 +182,6 @@ fn regression_comparison_figure(
 
     let mut figure = Figure::new();
     figure
-        .set(Font(DEFAULT_FONT))
         .set(size.unwrap_or(SIZE))
         .configure(Axis::BottomX, |a| {
             a.configure(Grid::Major, |g| g.show())
@@ -239,6 +237,24 @@ fn regression_comparison_figure(
                     .set(LineType::Solid)
             },
         );
+    if let Some(label) = change_label(comparison) {
+        let (point_type, color) = match comparison.significant_change() {
+            Some(SignificantChange::Improved) => (PointType::FilledInvertedTriangle, DARK_GREEN),
+            _ => (PointType::FilledTriangle, DARK_RED),
+        };
+        figure.plot(
+            Points {
+                x: &[max_iters],
+                y: &[point],
+            },
+            |p| {
+                p.set(Label(label))
+                    .set(point_type)
+                    .set(CHANGE_POINT_SIZE)
+                    .set(color)
+            },
+        );
+    }
     figure
 }
 
@@ -255,9 +271,8 @@ pub(crate) fn regression_comparison(
         regression_comparison_figure(formatter, measurements, comparison, base_data, size);
     figure.set(Title(gnuplot_escape(id.as_title())));
 
-    let path = context.report_path(id, "both/regression.svg");
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, "both/regression");
+    draw(&mut figure, path, &context.plot_config)
 }
 
 pub(crate) fn regression_comparison_small(
@@ -273,7 +288,6 @@ pub(crate) fn regression_comparison_small(
         regression_comparison_figure(formatter, measurements, comparison, base_data, size);
     figure.configure(Key, |k| k.hide());
 
-    let path = context.report_path(id, "relative_regression_small.svg");
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, "relative_regression_small");
+    draw(&mut figure, path, &context.plot_config)
 }
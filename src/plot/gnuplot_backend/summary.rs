@@ -1,14 +1,18 @@
-use super::{debug_script, gnuplot_escape};
-use super::{DARK_BLUE, DEFAULT_FONT, KDE_POINTS, LINEWIDTH, POINT_SIZE, SIZE};
+use super::{draw, gnuplot_escape};
+use super::{CHANGE_POINT_SIZE, DARK_BLUE, DARK_GREEN, DARK_RED, LINEWIDTH, POINT_SIZE, SIZE};
+use crate::estimate::ConfidenceInterval;
 use crate::measurement::ValueFormatter;
-use crate::report::{BenchmarkId, ValueType};
+use crate::plot::{
+    box_plots, format_bytes, kde_bandwidth, ratio_curves, violin_ticks, ChangeMarkers, LineScale,
+    ViolinColors, DARK_FOREGROUND, DARK_PALETTE,
+};
+use crate::report::{BenchmarkId, SignificantChange, ValueType};
 use crate::stats::univariate::Sample;
-use crate::AxisScale;
 use crate::{kde, PlotConfiguration};
+use crate::{AxisScale, Theme};
 use criterion_plot::prelude::*;
 use itertools::Itertools;
 use std::cmp::Ordering;
-use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::process::Child;
 
@@ -25,6 +29,27 @@ static COMPARISON_COLORS: [Color; NUM_COLORS] = [
     Color::Rgb(0, 50, 255),
 ];
 
+/// The color of the `i`th curve of a comparison plot, from the configured colors or `defaults`.
+fn palette_color(conf: &PlotConfiguration, i: usize, defaults: &[Color]) -> Color {
+    match &conf.colors {
+        Some(colors) => {
+            let (r, g, b) = colors[i % colors.len()];
+            Color::Rgb(r, g, b)
+        }
+        None => defaults[i % defaults.len()],
+    }
+}
+
+fn comparison_color(conf: &PlotConfiguration, i: usize) -> Color {
+    match conf.theme {
+        Theme::Light => palette_color(conf, i, &COMPARISON_COLORS),
+        Theme::Dark => {
+            let (r, g, b) = DARK_PALETTE[i % DARK_PALETTE.len()];
+            palette_color(conf, i, &[Color::Rgb(r, g, b)])
+        }
+    }
+}
+
 impl AxisScale {
     fn to_gnuplot(self) -> Scale {
         match self {
@@ -34,23 +59,14 @@ impl AxisScale {
     }
 }
 
-fn format_bytes(bytes: i64) -> String {
-    if bytes < 1024 {
-        return format!("{:.0}b", bytes);
-    } else if bytes < 1024 * 1024 {
-        return format!("{:.0}Kb", bytes as f64 / 1024.);
-    } else if bytes < 1024 * 1024 * 1024 {
-        return format!("{:.0}Mb", bytes as f64 / (1024. * 1024.));
-    } else {
-        return format!("{:.0}Gb", bytes as f64 / (1024. * 1024. * 1024.));
-    }
-}
-
 #[cfg_attr(feature = "cargo-clippy", allow(clippy::explicit_counter_loop))]
+#[allow(clippy::too_many_arguments)]
 pub fn line_comparison(
     formatter: &dyn ValueFormatter,
     title: &str,
     all_curves: &[&(&BenchmarkId, Vec<f64>)],
+    mean_intervals: &[Option<ConfidenceInterval>],
+    changes: &[Option<SignificantChange>],
     path: &Path,
     value_type: ValueType,
     conf: &PlotConfiguration,
@@ -72,7 +88,7 @@ pub fn line_comparison(
 
     let mut labels = Vec::<String>::with_capacity(conf.tics.len());
     for val in conf.tics.iter() {
-        labels.push(format_bytes(*val));
+        labels.push(conf.tic_label(*val).unwrap_or_else(|| format_bytes(*val)));
     }
 
     let title_label;
@@ -82,50 +98,61 @@ pub fn line_comparison(
         title_label = format!("{}: Comparsion", gnuplot_escape(title));
     }
 
-    f.set(Font(DEFAULT_FONT))
-        .set(SIZE)
-        .configure(Key, |k| {
-            k.set(Justification::Left)
-                .set(Order::SampleText)
-                .set(Position::Outside(Vertical::Top, Horizontal::Right))
-        })
-        .set(Title(title_label))
-        .configure(Axis::BottomX, |a| {
-            a.set(Label(input_suffix))
-                .set(conf.x_scale.to_gnuplot())
-                .set(TicLabels {
-                    positions: conf.tics.clone(),
-                    labels: labels,
-                })
-                .configure(Grid::Major, |g| {
-                    if conf.x_grid_major {
-                        g.show()
-                    } else {
-                        g.hide()
-                    }
-                })
-                .configure(Grid::Minor, |g| {
-                    if conf.x_grid_minor {
-                        g.show()
-                    } else {
-                        g.hide()
-                    }
-                })
+    f.set(
+        conf.size
+            .map_or(SIZE, |(width, height)| Size(width, height)),
+    )
+    .configure(Key, |k| {
+        k.set(Justification::Left)
+            .set(Order::SampleText)
+            .set(Position::Outside(Vertical::Top, Horizontal::Right))
+    })
+    .set(Title(title_label))
+    .configure(Axis::BottomX, |a| {
+        a.set(Label(input_suffix))
+            .set(conf.x_scale.to_gnuplot())
+            .set(TicLabels {
+                positions: conf.tics.clone(),
+                labels,
+            })
+            .configure(Grid::Major, |g| {
+                if conf.x_grid_major {
+                    g.show()
+                } else {
+                    g.hide()
+                }
+            })
+            .configure(Grid::Minor, |g| {
+                if conf.x_grid_minor {
+                    g.show()
+                } else {
+                    g.hide()
+                }
+            })
+    });
+    if !conf.tics.is_empty() {
+        // The top axis shows the raw values of the tics; without data of its own, gnuplot gives
+        // it the range of the bottom axis.
+        f.configure(Axis::TopX, |a| {
+            a.set(conf.x_scale.to_gnuplot()).set(TicLabels {
+                positions: conf.tics.clone(),
+                labels: conf
+                    .tics
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+            })
         });
+    }
 
     let mut i = 0;
+    let mut markers = ChangeMarkers::default();
 
-    let max = all_curves
-        .iter()
-        .map(|&&(_, ref data)| Sample::new(data).mean())
-        .fold(::std::f64::NAN, f64::max);
-
-    let mut dummy = [1.0];
-    let unit = formatter.scale_values(max, &mut dummy);
-    let y_label = if conf.speedup {
-        String::from("Speedup")
-    } else {
-        format!("Average time ({})", unit)
+    let scale = LineScale::new(formatter, all_curves, conf);
+    let ratios = ratio_curves(title, all_curves, mean_intervals, conf);
+    let y_label = match &ratios {
+        Some((y_label, _)) => gnuplot_escape(y_label),
+        None => scale.y_label(),
     };
 
     f.configure(Axis::LeftY, |a| {
@@ -147,72 +174,91 @@ pub fn line_comparison(
         .set(conf.y_scale.to_gnuplot())
     });
 
-    if conf.speedup {
-        let mut data: BTreeMap<u64, f64> = BTreeMap::new();
-        let mut max = 1.;
-        for (_key, group) in &all_curves.iter().group_by(|&&&(id, _)| &id.function_id) {
-            let tuples: Vec<_> = group
-                .map(|&&(id, ref sample)| {
-                    let id_func = id.function_id.clone().unwrap();
-                    let x = id.as_number().unwrap();
-                    let y = Sample::new(sample).mean();
+    if let Some((_, curves)) = ratios {
+        for (key, ratios) in curves {
+            let function_name = key.map(|string| gnuplot_escape(string));
+            markers.mark(all_curves, changes, key, &ratios.xs, &ratios.ys);
 
-                    (x, y, id_func)
-                })
-                .collect();
-            //tuples.sort_by(|&(ax, _), &(bx, _)| (ax.partial_cmp(&bx).unwrap_or(Ordering::Less)));
-            for (x, y, id) in tuples.iter() {
-                if data.contains_key(&(*x as u64)) {
-                    let val = data[&(*x as u64)];
-                    if *id == conf.speedup_id {
-                        data.insert(*x as u64, *y / val);
-                    } else {
-                        data.insert(*x as u64, val / *y);
+            f.plot(
+                FilledCurve {
+                    x: &ratios.xs,
+                    y1: &ratios.lower,
+                    y2: &ratios.upper,
+                },
+                |c| c.set(comparison_color(conf, i)).set(Opacity(0.25)),
+            )
+            .plot(
+                Lines {
+                    x: &ratios.xs,
+                    y: &ratios.ys,
+                },
+                |c| {
+                    if let Some(name) = function_name {
+                        c.set(Label(name));
                     }
-                } else {
-                    data.insert(*x as u64, *y);
-                }
-                if *y > max {
-                    max = *y;
-                }
-            }
+                    c.set(LINEWIDTH)
+                        .set(LineType::Solid)
+                        .set(comparison_color(conf, i))
+                },
+            )
+            .plot(
+                Points {
+                    x: &ratios.xs,
+                    y: &ratios.ys,
+                },
+                |p| {
+                    p.set(PointType::FilledCircle)
+                        .set(POINT_SIZE)
+                        .set(comparison_color(conf, i))
+                },
+            );
+
+            i += 1;
         }
-        let result: Vec<(f64, f64)> = data.iter().map(|(x, y)| (*x as f64, *y)).collect();
-        let (xs, mut ys): (Vec<_>, Vec<_>) = result.into_iter().unzip();
-        formatter.scale_values(max, &mut ys);
-        let function_name = String::from("Speedup");
-
-        f.plot(Lines { x: &xs, y: &ys }, |c| {
-            c.set(Label(function_name));
-            c.set(LINEWIDTH)
-                .set(LineType::Solid)
-                .set(COMPARISON_COLORS[i % NUM_COLORS])
-        })
-        .plot(Points { x: &xs, y: &ys }, |p| {
-            p.set(PointType::FilledCircle)
-                .set(POINT_SIZE)
-                .set(COMPARISON_COLORS[i % NUM_COLORS])
-        });
     } else {
         // This assumes the curves are sorted. It also assumes that the benchmark IDs all have numeric
         // values or throughputs and that value is sensible (ie. not a mix of bytes and elements
         // or whatnot)
-        for (key, group) in &all_curves.iter().group_by(|&&&(id, _)| &id.function_id) {
+        let curves = all_curves.iter().zip(mean_intervals);
+        for (key, group) in &curves.group_by(|&(&&(id, _), _)| &id.function_id) {
             let mut tuples: Vec<_> = group
-                .map(|&&(id, ref sample)| {
+                .map(|(&&(id, ref sample), interval)| {
                     // Unwrap is fine here because it will only fail if the assumptions above are not true
                     // ie. programmer error.
                     let x = id.as_number().unwrap();
-                    let y = Sample::new(sample).mean();
+                    let mut y = [Sample::new(sample).mean()];
+                    scale.scale(id, &mut y);
+                    let bounds = interval.as_ref().map(|ci| {
+                        let mut bounds = [ci.lower_bound, ci.upper_bound];
+                        scale.scale(id, &mut bounds);
+                        (bounds[0], bounds[1])
+                    });
 
-                    (x, y)
+                    (x, y[0], bounds)
                 })
                 .collect();
-            tuples.sort_by(|&(ax, _), &(bx, _)| (ax.partial_cmp(&bx).unwrap_or(Ordering::Less)));
-            let (xs, mut ys): (Vec<_>, Vec<_>) = tuples.into_iter().unzip();
-            formatter.scale_values(max, &mut ys);
+            tuples
+                .sort_by(|&(ax, _, _), &(bx, _, _)| ax.partial_cmp(&bx).unwrap_or(Ordering::Less));
+            let xs: Vec<_> = tuples.iter().map(|&(x, _, _)| x).collect();
+            let ys: Vec<_> = tuples.iter().map(|&(_, y, _)| y).collect();
+
+            // The band is only drawn if the intervals of all points are known.
+            let bounds: Option<Vec<_>> = tuples.iter().map(|&(_, _, bounds)| bounds).collect();
+            if let Some(bounds) = bounds {
+                let lower: Vec<_> = bounds.iter().map(|&(lower, _)| lower).collect();
+                let upper: Vec<_> = bounds.iter().map(|&(_, upper)| upper).collect();
+                f.plot(
+                    FilledCurve {
+                        x: &xs,
+                        y1: &lower,
+                        y2: &upper,
+                    },
+                    |c| c.set(comparison_color(conf, i)).set(Opacity(0.25)),
+                );
+            }
 
             let function_name = key.as_ref().map(|string| gnuplot_escape(string));
+            markers.mark(all_curves, changes, key.as_ref(), &xs, &ys);
 
             f.plot(Lines { x: &xs, y: &ys }, |c| {
                 if let Some(name) = function_name {
@@ -220,20 +266,50 @@ pub fn line_comparison(
                 }
                 c.set(LINEWIDTH)
                     .set(LineType::Solid)
-                    .set(COMPARISON_COLORS[i % NUM_COLORS])
+                    .set(comparison_color(conf, i))
             })
             .plot(Points { x: &xs, y: &ys }, |p| {
                 p.set(PointType::FilledCircle)
                     .set(POINT_SIZE)
-                    .set(COMPARISON_COLORS[i % NUM_COLORS])
+                    .set(comparison_color(conf, i))
             });
 
             i += 1;
         }
     }
+    plot_change_markers(&mut f, &markers);
 
-    debug_script(&path, &f);
-    f.set(Output(path)).draw().unwrap()
+    draw(&mut f, path, conf)
+}
+
+/// Marks the points of the line comparison plot whose benchmarks changed significantly.
+fn plot_change_markers(f: &mut Figure, markers: &ChangeMarkers) {
+    let series = [
+        (
+            &markers.regressed,
+            "Regressed",
+            PointType::FilledTriangle,
+            DARK_RED,
+        ),
+        (
+            &markers.improved,
+            "Improved",
+            PointType::FilledInvertedTriangle,
+            DARK_GREEN,
+        ),
+    ];
+    for &(points, label, point_type, color) in &series {
+        if points.is_empty() {
+            continue;
+        }
+        let (xs, ys): (Vec<f64>, Vec<f64>) = points.iter().cloned().unzip();
+        f.plot(Points { x: &xs, y: &ys }, |p| {
+            p.set(Label(label))
+                .set(point_type)
+                .set(CHANGE_POINT_SIZE)
+                .set(color)
+        });
+    }
 }
 
 pub fn violin(
@@ -241,16 +317,23 @@ pub fn violin(
     title: &str,
     all_curves: &[&(&BenchmarkId, Vec<f64>)],
     path: &Path,
-    axis_scale: AxisScale,
+    conf: &PlotConfiguration,
+    percentiles: &[f64],
 ) -> Child {
     let path = PathBuf::from(&path);
+    let violin_colors = ViolinColors::new(all_curves);
     let all_curves_vec = all_curves.iter().rev().cloned().collect::<Vec<_>>();
     let all_curves: &[&(&BenchmarkId, Vec<f64>)] = &*all_curves_vec;
 
     let kdes = all_curves
         .iter()
         .map(|&&(_, ref sample)| {
-            let (x, mut y) = kde::sweep(Sample::new(sample), KDE_POINTS, None);
+            let (x, mut y) = kde::sweep(
+                Sample::new(sample),
+                kde_bandwidth(conf, |_| {}),
+                conf.kde_points,
+                None,
+            );
             let y_max = Sample::new(&y).max();
             for y in y.iter_mut() {
                 *y /= y_max;
@@ -281,17 +364,20 @@ pub fn violin(
     let unit = formatter.scale_values((min + max) / 2.0, &mut one);
 
     let tics = || (0..).map(|x| (f64::from(x)) + 0.5);
-    let size = Size(1280, 200 + (25 * all_curves.len()));
+    // By default, the violin plot grows with the number of benchmarks.
+    let size = conf.size.map_or_else(
+        || Size(1280, 200 + (25 * all_curves.len())),
+        |(width, height)| Size(width, height),
+    );
     let mut f = Figure::new();
-    f.set(Font(DEFAULT_FONT))
-        .set(size)
+    f.set(size)
         .set(Title(format!("{}: Violin plot", gnuplot_escape(title))))
         .configure(Axis::BottomX, |a| {
             a.configure(Grid::Major, |g| g.show())
                 .configure(Grid::Minor, |g| g.hide())
                 .set(Range::Limits(0., max as f64 * one[0]))
                 .set(Label(format!("Average time ({})", unit)))
-                .set(axis_scale.to_gnuplot())
+                .set(conf.x_scale.to_gnuplot())
         })
         .configure(Axis::LeftY, |a| {
             a.set(Label("Input"))
@@ -304,8 +390,26 @@ pub fn violin(
                 })
         });
 
-    let mut is_first = true;
-    for (i, &(ref x, ref y)) in kdes.iter().enumerate() {
+    let foreground = match conf.theme {
+        Theme::Light => Color::Black,
+        Theme::Dark => Color::Rgb(DARK_FOREGROUND.0, DARK_FOREGROUND.1, DARK_FOREGROUND.2),
+    };
+    // The curves are placed bottom up, in reverse, and drawn from the top, so that the legend
+    // lists the functions in order.
+    for (i, (x, y)) in kdes.iter().enumerate().rev() {
+        let index = kdes.len() - 1 - i;
+        let color_index = violin_colors.indices[index];
+        let color = if violin_colors.by_function {
+            comparison_color(conf, color_index)
+        } else {
+            palette_color(conf, color_index, &[DARK_BLUE])
+        };
+        let label = match violin_colors.labels[index] {
+            Some(function) => Some(gnuplot_escape(function)),
+            None if !violin_colors.by_function && index == 0 => Some("PDF".to_owned()),
+            None => None,
+        };
+        let ticks = violin_ticks(&all_curves[i].1, percentiles, x, y);
         let i = i as f64 + 0.5;
         let y1: Vec<_> = y.iter().map(|&y| i + y * 0.45).collect();
         let y2: Vec<_> = y.iter().map(|&y| i - y * 0.45).collect();
@@ -313,15 +417,191 @@ pub fn violin(
         let x: Vec<_> = x.iter().map(|&x| x * one[0]).collect();
 
         f.plot(FilledCurve { x, y1, y2 }, |c| {
-            if is_first {
-                is_first = false;
-
-                c.set(DARK_BLUE).set(Label("PDF"))
-            } else {
-                c.set(DARK_BLUE)
+            if let Some(label) = label {
+                c.set(Label(label));
             }
+            c.set(color)
+        });
+        for (tick, height) in ticks {
+            f.plot(
+                Lines {
+                    x: &[tick * one[0]; 2],
+                    y: &[i - height * 0.45, i + height * 0.45],
+                },
+                |c| c.set(foreground).set(LINEWIDTH),
+            );
+        }
+    }
+    draw(&mut f, path, conf)
+}
+
+pub fn box_plot(
+    formatter: &dyn ValueFormatter,
+    title: &str,
+    all_curves: &[&(&BenchmarkId, Vec<f64>)],
+    path: &Path,
+    conf: &PlotConfiguration,
+) -> Child {
+    let path = PathBuf::from(&path);
+    let (boxes, unit) = box_plots(formatter, all_curves);
+    let max = boxes
+        .iter()
+        .flat_map(|stats| stats.outliers.iter().chain(Some(&stats.whisker_high)))
+        .fold(0., |max: f64, &x| max.max(x));
+
+    // Like the violins, the boxes are listed from the top.
+    let n = boxes.len();
+    let position = |i: usize| (n - 1 - i) as f64 + 0.5;
+    let size = conf.size.map_or_else(
+        || Size(1280, 200 + (25 * n)),
+        |(width, height)| Size(width, height),
+    );
+    let mut f = Figure::new();
+    f.set(size)
+        .set(Title(format!("{}: Box plot", gnuplot_escape(title))))
+        .configure(Axis::BottomX, |a| {
+            a.configure(Grid::Major, |g| g.show())
+                .configure(Grid::Minor, |g| g.hide())
+                .set(Range::Limits(0., max * 1.05))
+                .set(Label(format!("Average time ({})", unit)))
+                .set(conf.x_scale.to_gnuplot())
+        })
+        .configure(Axis::LeftY, |a| {
+            a.set(Label("Input"))
+                .set(Range::Limits(0., n as f64))
+                .set(TicLabels {
+                    positions: (0..n).map(position),
+                    labels: all_curves
+                        .iter()
+                        .map(|&&(id, _)| gnuplot_escape(id.as_title())),
+                })
         });
+
+    for (i, stats) in boxes.iter().enumerate() {
+        let color = palette_color(conf, i, &[DARK_BLUE]);
+        let y = position(i);
+        let is_first = i == 0;
+        f.plot(
+            FilledCurve {
+                x: &[stats.q1, stats.q3],
+                y1: &[y + 0.3, y + 0.3],
+                y2: &[y - 0.3, y - 0.3],
+            },
+            |c| {
+                c.set(color).set(Opacity(0.5));
+                if is_first {
+                    c.set(Label("Quartiles"));
+                }
+                c
+            },
+        )
+        .plot(
+            Lines {
+                x: &[stats.median, stats.median],
+                y: &[y - 0.3, y + 0.3],
+            },
+            |c| {
+                c.set(color).set(LINEWIDTH);
+                if is_first {
+                    c.set(Label("Median"));
+                }
+                c
+            },
+        );
+        for &(from, to) in &[
+            (stats.whisker_low, stats.q1),
+            (stats.q3, stats.whisker_high),
+        ] {
+            f.plot(
+                Lines {
+                    x: &[from, to],
+                    y: &[y, y],
+                },
+                |c| c.set(color).set(LINEWIDTH),
+            );
+        }
+        if !stats.outliers.is_empty() {
+            f.plot(
+                Points {
+                    x: &stats.outliers,
+                    y: stats.outliers.iter().map(|_| y),
+                },
+                |c| {
+                    c.set(color).set(PointType::FilledCircle).set(POINT_SIZE);
+                    if is_first {
+                        c.set(Label("Outliers"));
+                    }
+                    c
+                },
+            );
+        }
     }
-    debug_script(&path, &f);
-    f.set(Output(path)).draw().unwrap()
+    draw(&mut f, path, conf)
+}
+
+pub fn speedup_summary(
+    title: &str,
+    speedups: &[(String, f64)],
+    path: &Path,
+    conf: &PlotConfiguration,
+) -> Child {
+    let path = PathBuf::from(path);
+    let max = speedups
+        .iter()
+        .map(|&(_, speedup)| speedup)
+        .fold(1., f64::max);
+    let n = speedups.len() as f64;
+
+    let mut f = Figure::new();
+    f.set(
+        conf.size
+            .map_or(SIZE, |(width, height)| Size(width, height)),
+    )
+    .set(Title(format!(
+        "{}: Geometric mean speedup",
+        gnuplot_escape(title)
+    )))
+    .configure(Key, |k| k.hide())
+    .configure(Axis::BottomX, |a| {
+        a.set(Range::Limits(-0.5, n - 0.5)).set(TicLabels {
+            positions: (0..speedups.len()).map(|i| i as f64),
+            labels: speedups.iter().map(|(name, _)| gnuplot_escape(name)),
+        })
+    })
+    .configure(Axis::LeftY, |a| {
+        a.configure(Grid::Major, |g| g.show())
+            .set(Range::Limits(0., max * 1.1))
+            .set(Label(format!(
+                "Speedup over {}",
+                gnuplot_escape(&conf.speedup_id)
+            )))
+    });
+
+    for (i, &(_, speedup)) in speedups.iter().enumerate() {
+        let x = i as f64;
+        f.plot(
+            FilledCurve {
+                x: &[x - 0.4, x + 0.4],
+                y1: &[0., 0.],
+                y2: &[speedup, speedup],
+            },
+            |c| c.set(comparison_color(conf, i)),
+        );
+    }
+    // The reference itself
+    f.plot(
+        Lines {
+            x: &[-0.5, n - 0.5],
+            y: &[1., 1.],
+        },
+        |c| {
+            let color = match conf.theme {
+                Theme::Light => Color::Black,
+                Theme::Dark => Color::Rgb(DARK_FOREGROUND.0, DARK_FOREGROUND.1, DARK_FOREGROUND.2),
+            };
+            c.set(color).set(LineType::Dash).set(LINEWIDTH)
+        },
+    );
+
+    draw(&mut f, path, conf)
 }
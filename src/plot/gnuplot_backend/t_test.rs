@@ -4,7 +4,8 @@ use std::process::Child;
 use criterion_plot::prelude::*;
 
 use super::*;
-use crate::kde;
+use crate::kde::{self, Bandwidth};
+use crate::plot::test_labels;
 use crate::report::{BenchmarkId, ComparisonData, MeasurementData, ReportContext};
 
 pub(crate) fn t_test(
@@ -15,18 +16,24 @@ pub(crate) fn t_test(
     size: Option<Size>,
 ) -> Child {
     let t = comparison.t_value;
-    let (xs, ys) = kde::sweep(&comparison.t_distribution, KDE_POINTS, None);
+    let labels = test_labels(comparison);
+    let (xs, ys) = kde::sweep(
+        &comparison.t_distribution,
+        Bandwidth::Silverman,
+        context.plot_config.kde_points,
+        None,
+    );
     let zero = iter::repeat(0);
 
     let mut figure = Figure::new();
     figure
-        .set(Font(DEFAULT_FONT))
         .set(size.unwrap_or(SIZE))
         .set(Title(format!(
-            "{}: Welch t test",
-            gnuplot_escape(id.as_title())
+            "{}: {}",
+            gnuplot_escape(id.as_title()),
+            labels.title
         )))
-        .configure(Axis::BottomX, |a| a.set(Label("t score")))
+        .configure(Axis::BottomX, |a| a.set(Label(labels.axis)))
         .configure(Axis::LeftY, |a| a.set(Label("Density")))
         .configure(Key, |k| {
             k.set(Justification::Left)
@@ -41,7 +48,7 @@ pub(crate) fn t_test(
             },
             |c| {
                 c.set(DARK_BLUE)
-                    .set(Label("t distribution"))
+                    .set(Label(labels.distribution))
                     .set(Opacity(0.25))
             },
         )
@@ -54,12 +61,11 @@ pub(crate) fn t_test(
                 c.set(Axes::BottomXRightY)
                     .set(DARK_BLUE)
                     .set(LINEWIDTH)
-                    .set(Label("t statistic"))
+                    .set(Label(labels.statistic))
                     .set(LineType::Solid)
             },
         );
 
-    let path = context.report_path(id, "change/t-test.svg");
-    debug_script(&path, &figure);
-    figure.set(Output(path)).draw().unwrap()
+    let path = context.plot_path(id, "change/t-test");
+    draw(&mut figure, path, &context.plot_config)
 }
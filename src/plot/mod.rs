@@ -1,14 +1,27 @@
+mod deferred;
 mod gnuplot_backend;
 #[cfg(feature = "plotters")]
 mod plotters_backend;
+mod vega_backend;
 
+pub(crate) use deferred::wait_until_quiet;
 pub(crate) use gnuplot_backend::Gnuplot;
 #[cfg(feature = "plotters")]
 pub(crate) use plotters_backend::PlottersBackend;
+pub(crate) use vega_backend::{violin_height, Vega, SIZE as VEGA_SIZE};
 
-use crate::estimate::Statistic;
+use crate::estimate::{ConfidenceInterval, Statistic};
+use crate::format;
+use crate::kde::Bandwidth;
 use crate::measurement::ValueFormatter;
-use crate::report::{BenchmarkId, ComparisonData, MeasurementData, ReportContext, ValueType};
+use crate::report::{
+    BenchmarkId, ComparisonData, MeasurementData, ReportContext, SignificantChange, ValueType,
+};
+use crate::stats::univariate::outliers::tukey;
+use crate::stats::univariate::{self, Sample};
+use crate::{ComparisonTest, KdeBandwidth, PlotConfiguration, PlotFormat, Throughput};
+use itertools::Itertools;
+use std::cmp::Ordering;
 use std::path::PathBuf;
 
 const REPORT_STATS: [Statistic; 7] = [
@@ -21,6 +34,23 @@ const REPORT_STATS: [Statistic; 7] = [
     Statistic::StdDev,
 ];
 const CHANGE_STATS: [Statistic; 2] = [Statistic::Mean, Statistic::Median];
+
+/// The background of the plots with `Theme::Dark`.
+pub(crate) const DARK_BACKGROUND: (u8, u8, u8) = (30, 30, 30);
+/// The color of the text, the axes and the gridlines of the plots with `Theme::Dark`.
+pub(crate) const DARK_FOREGROUND: (u8, u8, u8) = (220, 220, 220);
+/// The default colors of the comparison plots with `Theme::Dark`, which stay readable on its
+/// background.
+pub(crate) const DARK_PALETTE: [(u8, u8, u8); 8] = [
+    (255, 99, 71),
+    (102, 205, 170),
+    (0, 206, 209),
+    (255, 215, 0),
+    (135, 206, 250),
+    (255, 105, 180),
+    (221, 160, 221),
+    (0, 255, 127),
+];
 #[derive(Clone, Copy)]
 pub(crate) struct PlotContext<'a> {
     pub(crate) id: &'a BenchmarkId,
@@ -43,19 +73,19 @@ impl<'a> PlotContext<'a> {
     }
 
     pub fn line_comparison_path(&self) -> PathBuf {
-        let mut path = self.context.output_directory.clone();
-        path.push(self.id.as_directory_name());
-        path.push("report");
-        path.push("lines.svg");
-        path
+        self.context.plot_path(self.id, "lines")
     }
 
     pub fn violin_path(&self) -> PathBuf {
-        let mut path = self.context.output_directory.clone();
-        path.push(self.id.as_directory_name());
-        path.push("report");
-        path.push("violin.svg");
-        path
+        self.context.plot_path(self.id, "violin")
+    }
+
+    pub fn box_plot_path(&self) -> PathBuf {
+        self.context.plot_path(self.id, "box_plot")
+    }
+
+    pub fn speedup_path(&self) -> PathBuf {
+        self.context.plot_path(self.id, "speedup")
     }
 }
 
@@ -73,6 +103,108 @@ impl<'a> PlotData<'a> {
     }
 }
 
+/// Scales the means of the line comparison plot to the unit of its y axis: the average time, or
+/// the throughput with `PlotConfiguration::line_throughput`.
+pub(crate) struct LineScale<'a> {
+    formatter: &'a dyn ValueFormatter,
+    /// The throughput of one unit of input, if the plot shows throughputs.
+    per_unit: Option<Throughput>,
+    /// The typical value choosing the unit: the longest mean time, or the shortest mean time per
+    /// unit of input.
+    typical: f64,
+    unit: &'static str,
+}
+
+impl<'a> LineScale<'a> {
+    /// The throughputs are only plotted if every benchmark has a non-zero throughput of the same
+    /// kind; otherwise the plot falls back to the average time.
+    pub fn new(
+        formatter: &'a dyn ValueFormatter,
+        all_curves: &[&(&BenchmarkId, Vec<f64>)],
+        conf: &PlotConfiguration,
+    ) -> LineScale<'a> {
+        let per_unit = if conf.line_throughput {
+            per_unit_throughput(all_curves.iter().map(|&&(id, _)| id))
+        } else {
+            None
+        };
+        let means = all_curves
+            .iter()
+            .map(|&&(id, ref data)| (id, Sample::new(data).mean()));
+        let mut dummy = [1.0];
+        let (typical, unit) = match per_unit {
+            Some(ref per_unit) => {
+                let typical = means
+                    .map(|(id, mean)| mean / throughput_amount(id))
+                    .fold(f64::NAN, f64::min);
+                let unit = formatter.scale_throughputs(typical, per_unit, &mut dummy);
+                (typical, unit.trim())
+            }
+            None => {
+                let typical = means.map(|(_, mean)| mean).fold(f64::NAN, f64::max);
+                (typical, formatter.scale_values(typical, &mut dummy))
+            }
+        };
+        LineScale {
+            formatter,
+            per_unit,
+            typical,
+            unit,
+        }
+    }
+
+    /// Scales the times `values`, measured for the benchmark `id`, to the unit of the y axis.
+    pub fn scale(&self, id: &BenchmarkId, values: &mut [f64]) {
+        match self.per_unit {
+            Some(ref per_unit) => {
+                let amount = throughput_amount(id);
+                for value in values.iter_mut() {
+                    *value /= amount;
+                }
+                self.formatter
+                    .scale_throughputs(self.typical, per_unit, values);
+            }
+            None => {
+                self.formatter.scale_values(self.typical, values);
+            }
+        }
+    }
+
+    pub fn y_label(&self) -> String {
+        if self.per_unit.is_some() {
+            format!("Throughput ({})", self.unit)
+        } else {
+            format!("Average time ({})", self.unit)
+        }
+    }
+}
+
+/// The throughput of one unit of the inputs of `ids`, if they all have non-zero throughputs of the
+/// same kind.
+fn per_unit_throughput<'b, I: Iterator<Item = &'b BenchmarkId>>(mut ids: I) -> Option<Throughput> {
+    let per_unit = |id: &BenchmarkId| match id.throughput {
+        Some(Throughput::Bytes(n)) if n > 0 => Some(Throughput::Bytes(1)),
+        Some(Throughput::BytesDecimal(n)) if n > 0 => Some(Throughput::BytesDecimal(1)),
+        Some(Throughput::Elements(n)) if n > 0 => Some(Throughput::Elements(1)),
+        _ => None,
+    };
+    let first = per_unit(ids.next()?)?;
+    if ids.all(|id| per_unit(id).as_ref() == Some(&first)) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+fn throughput_amount(id: &BenchmarkId) -> f64 {
+    match id.throughput {
+        Some(Throughput::Bytes(n))
+        | Some(Throughput::BytesDecimal(n))
+        | Some(Throughput::Elements(n)) => n as f64,
+        None => 1.0,
+    }
+}
+
 pub(crate) trait Plotter {
     fn pdf(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>);
 
@@ -84,14 +216,29 @@ pub(crate) trait Plotter {
 
     fn rel_distributions(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>);
 
+    /// Plots the empirical cumulative distribution of the sample, with lines at the percentiles
+    /// of `ecdf`.
+    fn cdf(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>);
+
+    /// Draws the samples in the order they were measured, with their trend. Only called if the
+    /// measurements have drift diagnostics.
+    fn drift(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>);
+
+    /// `mean_intervals` holds the confidence interval of the mean of each of `all_curves`, if it
+    /// is known, to be drawn as a band around the lines. `changes` holds their significant changes
+    /// from the baseline, which are marked on the points.
     fn line_comparison(
         &mut self,
         ctx: PlotContext<'_>,
         formatter: &dyn ValueFormatter,
         all_curves: &[&(&BenchmarkId, Vec<f64>)],
+        mean_intervals: &[Option<ConfidenceInterval>],
+        changes: &[Option<SignificantChange>],
         value_type: ValueType,
     );
 
+    /// Each violin is marked at the percentiles of `ReportContext::percentiles`, see
+    /// `violin_ticks`.
     fn violin(
         &mut self,
         ctx: PlotContext<'_>,
@@ -99,7 +246,549 @@ pub(crate) trait Plotter {
         all_curves: &[&(&BenchmarkId, Vec<f64>)],
     );
 
+    /// The alternative to the violin plot with `SummaryPlot::BoxPlot`, see `box_plots`.
+    fn box_plot(
+        &mut self,
+        ctx: PlotContext<'_>,
+        formatter: &dyn ValueFormatter,
+        all_curves: &[&(&BenchmarkId, Vec<f64>)],
+    );
+
+    /// Bar chart of the geometric mean speedup of each function over the reference function of
+    /// `PlotConfiguration::speedup`.
+    fn speedup_summary(&mut self, ctx: PlotContext<'_>, speedups: &[(String, f64)]);
+
     fn t_test(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>);
 
+    /// Whether the plots can be rendered in `format`.
+    fn supports(&self, format: PlotFormat) -> bool {
+        format == PlotFormat::Svg
+    }
+
+    /// The format of the plots if the one chosen is not supported.
+    fn default_format(&self) -> PlotFormat {
+        PlotFormat::Svg
+    }
+
     fn wait(&mut self);
+
+    /// Leaves the plots rendering in the background until `deferred::wait_until_quiet`, instead of
+    /// waiting for them.
+    fn defer(&mut self) {
+        self.wait();
+    }
+}
+
+/// The curves of the line comparison plot against a reference function, with
+/// `PlotConfiguration::relative` or `PlotConfiguration::speedup`, and the label of their y axis.
+/// Returns None if neither is set, or if the group has no reference function, in which case the
+/// plot shows the measurements.
+pub(crate) fn ratio_curves<'a>(
+    title: &str,
+    all_curves: &[&(&'a BenchmarkId, Vec<f64>)],
+    mean_intervals: &[Option<ConfidenceInterval>],
+    conf: &PlotConfiguration,
+) -> Option<(String, Vec<RatioCurve<'a>>)> {
+    let (mode, reference_id) = if conf.relative {
+        ("relative", &conf.relative_id)
+    } else if conf.speedup {
+        ("speedup", &conf.speedup_id)
+    } else {
+        return None;
+    };
+    let curves = if conf.relative {
+        relative_curves(all_curves, mean_intervals, reference_id)
+    } else {
+        speedup_curves(all_curves, mean_intervals, reference_id)
+    };
+    match curves {
+        Some(curves) if conf.relative => Some((format!("% of {}", reference_id), curves)),
+        Some(curves) => Some((format!("Speedup over {}", reference_id), curves)),
+        None => {
+            error!(
+                "The {} plot of {} needs the function {} to compare the others to",
+                mode, title, reference_id
+            );
+            None
+        }
+    }
+}
+
+/// The inputs, samples and confidence levels of each function of the group, sorted by input.
+fn function_points<'a, 'b>(
+    all_curves: &'b [&(&'a BenchmarkId, Vec<f64>)],
+    mean_intervals: &[Option<ConfidenceInterval>],
+) -> Vec<(Option<&'a String>, Vec<FunctionPoint<'b>>)> {
+    let mut functions = Vec::new();
+    let curves = all_curves.iter().zip(mean_intervals);
+    for (key, group) in &curves.group_by(|&(&&(id, _), _)| &id.function_id) {
+        let mut points: Vec<_> = group
+            .map(|(&&(id, ref sample), interval)| {
+                let x = id.as_number().unwrap();
+                let confidence_level = interval.as_ref().map(|ci| ci.confidence_level);
+
+                (x, &sample[..], confidence_level)
+            })
+            .collect();
+        points.sort_by(|&(ax, _, _), &(bx, _, _)| ax.partial_cmp(&bx).unwrap_or(Ordering::Less));
+        functions.push((key.as_ref(), points));
+    }
+    functions
+}
+
+/// The input of a benchmark, its sample and the confidence level of its analysis.
+type FunctionPoint<'a> = (f64, &'a [f64], Option<f64>);
+
+/// The speedup curves of the line comparison plot with `PlotConfiguration::speedup`: one per
+/// function of the group other than `reference_id`, with its name. Returns None if the group has no
+/// function `reference_id`.
+fn speedup_curves<'a>(
+    all_curves: &[&(&'a BenchmarkId, Vec<f64>)],
+    mean_intervals: &[Option<ConfidenceInterval>],
+    reference_id: &str,
+) -> Option<Vec<RatioCurve<'a>>> {
+    let (reference, others): (Vec<_>, Vec<_>) = function_points(all_curves, mean_intervals)
+        .into_iter()
+        .partition(|(key, _)| key.map(String::as_str) == Some(reference_id));
+
+    let (_, reference) = reference.first()?;
+    Some(
+        others
+            .iter()
+            .map(|(key, other)| {
+                let ratios = ratios(reference, other, |reference, other| reference / other);
+                (*key, ratios)
+            })
+            .collect(),
+    )
+}
+
+/// The curves of the line comparison plot with `PlotConfiguration::relative`: the mean of every
+/// function of the group, `reference_id` included, as a percentage of the mean of `reference_id`
+/// at the same input. Returns None if the group has no function `reference_id`.
+fn relative_curves<'a>(
+    all_curves: &[&(&'a BenchmarkId, Vec<f64>)],
+    mean_intervals: &[Option<ConfidenceInterval>],
+    reference_id: &str,
+) -> Option<Vec<RatioCurve<'a>>> {
+    let functions = function_points(all_curves, mean_intervals);
+    let (_, reference) = functions
+        .iter()
+        .find(|(key, _)| key.map(String::as_str) == Some(reference_id))?;
+    Some(
+        functions
+            .iter()
+            .map(|(key, other)| {
+                let ratios = ratios(reference, other, |reference, other| {
+                    other / reference * 100.
+                });
+                (*key, ratios)
+            })
+            .collect(),
+    )
+}
+
+/// The name of the function of a speedup or relative curve, and the curve.
+pub(crate) type RatioCurve<'a> = (Option<&'a String>, Ratios);
+
+/// Number of resamples for the confidence intervals of the ratios.
+const RATIO_RESAMPLES: usize = 10_000;
+
+/// The inputs of a speedup or relative curve, the ratios and their confidence intervals.
+#[derive(Default)]
+pub(crate) struct Ratios {
+    pub(crate) xs: Vec<f64>,
+    pub(crate) ys: Vec<f64>,
+    pub(crate) lower: Vec<f64>,
+    pub(crate) upper: Vec<f64>,
+}
+
+/// Computes `ratio` of the mean times of `reference` and `other` at every input both were
+/// measured with. The confidence intervals are bootstrapped by resampling both samples, at the
+/// confidence level of the analysis.
+fn ratios(
+    reference: &[FunctionPoint<'_>],
+    other: &[FunctionPoint<'_>],
+    ratio: fn(f64, f64) -> f64,
+) -> Ratios {
+    let mut ratios = Ratios::default();
+    for &(x, sample, confidence_level) in other {
+        let reference = match reference.iter().find(|&&(ref_x, _, _)| ref_x == x) {
+            Some(&(_, reference, _)) => Sample::new(reference),
+            None => continue,
+        };
+        let sample = Sample::new(sample);
+        let (distribution,) = univariate::bootstrap(reference, sample, RATIO_RESAMPLES, |a, b| {
+            (ratio(a.mean(), b.mean()),)
+        });
+        let (lower, upper) = distribution.confidence_interval(confidence_level.unwrap_or(0.95));
+
+        ratios.xs.push(x);
+        ratios.ys.push(ratio(reference.mean(), sample.mean()));
+        ratios.lower.push(lower);
+        ratios.upper.push(upper);
+    }
+    ratios
+}
+
+/// The points of a line comparison plot whose benchmarks changed significantly from the baseline,
+/// as `(x, y)` in the coordinates of the plot.
+#[derive(Default)]
+pub(crate) struct ChangeMarkers {
+    pub(crate) improved: Vec<(f64, f64)>,
+    pub(crate) regressed: Vec<(f64, f64)>,
+}
+
+impl ChangeMarkers {
+    /// Marks the points `xs`, `ys` of the curve of `function_id` whose benchmarks have a
+    /// significant change in `changes`, which has one entry per benchmark of `all_curves`.
+    pub(crate) fn mark(
+        &mut self,
+        all_curves: &[&(&BenchmarkId, Vec<f64>)],
+        changes: &[Option<SignificantChange>],
+        function_id: Option<&String>,
+        xs: &[f64],
+        ys: &[f64],
+    ) {
+        for (&&(id, _), change) in all_curves.iter().zip(changes) {
+            if id.function_id.as_ref() != function_id {
+                continue;
+            }
+            let x = id.as_number().unwrap();
+            let point = match xs.iter().position(|&other| other == x) {
+                Some(i) => (x, ys[i]),
+                None => continue,
+            };
+            match change {
+                Some(SignificantChange::Improved) => self.improved.push(point),
+                Some(SignificantChange::Regressed) => self.regressed.push(point),
+                None => {}
+            }
+        }
+    }
+}
+
+/// The labels of the plot of the hypothesis test of a comparison, which depend on the compared
+/// statistic (see `Criterion::comparison_estimator`).
+pub(crate) struct TestLabels {
+    pub title: &'static str,
+    pub axis: &'static str,
+    pub distribution: &'static str,
+    pub statistic: &'static str,
+}
+
+pub(crate) fn test_labels(comparison: &ComparisonData) -> TestLabels {
+    if comparison.test == ComparisonTest::MannWhitney {
+        return TestLabels {
+            title: "Mann-Whitney U test",
+            axis: "z score",
+            distribution: "z distribution",
+            statistic: "z statistic",
+        };
+    }
+    match comparison.statistic {
+        // Welch's t-test always compares the means.
+        Statistic::Median if comparison.test == ComparisonTest::Bootstrap => TestLabels {
+            title: "Median test",
+            axis: "Relative change of the median",
+            distribution: "Null distribution",
+            statistic: "Observed change",
+        },
+        _ => TestLabels {
+            title: "Welch t test",
+            axis: "t score",
+            distribution: "t distribution",
+            statistic: "t statistic",
+        },
+    }
+}
+
+/// The legend of the marker of a significant change on a comparison plot, with the relative change
+/// of the compared statistic, or None if the change is not significant.
+pub(crate) fn change_label(comparison: &ComparisonData) -> Option<String> {
+    let change = format::change(comparison.change().point_estimate, true);
+    match comparison.significant_change()? {
+        SignificantChange::Improved => Some(format!("Improved ({})", change.trim())),
+        SignificantChange::Regressed => Some(format!("Regressed ({})", change.trim())),
+    }
+}
+
+/// The colors of the violins of the violin plot. The violins of a group with several functions are
+/// colored by function, in the palette of the line comparison plot, and the first violin of each
+/// function holds its name for the legend. Otherwise, each violin takes the next color of
+/// `PlotConfiguration::colors`, or the default one.
+pub(crate) struct ViolinColors<'a> {
+    /// Whether the violins are colored by function.
+    pub(crate) by_function: bool,
+    /// The index of the color of each violin.
+    pub(crate) indices: Vec<usize>,
+    /// The label of each violin in the legend, if it has one.
+    pub(crate) labels: Vec<Option<&'a str>>,
+}
+
+impl<'a> ViolinColors<'a> {
+    pub(crate) fn new(all_curves: &[&(&'a BenchmarkId, Vec<f64>)]) -> ViolinColors<'a> {
+        let mut functions: Vec<Option<&str>> = vec![];
+        let mut indices = vec![];
+        let mut labels = vec![];
+        for &&(id, _) in all_curves {
+            let function = id.function_id.as_deref();
+            match functions.iter().position(|&f| f == function) {
+                Some(index) => {
+                    indices.push(index);
+                    labels.push(None);
+                }
+                None => {
+                    indices.push(functions.len());
+                    labels.push(Some(function.unwrap_or(&id.group_id)));
+                    functions.push(function);
+                }
+            }
+        }
+        if functions.len() > 1 {
+            ViolinColors {
+                by_function: true,
+                indices,
+                labels,
+            }
+        } else {
+            ViolinColors {
+                by_function: false,
+                indices: (0..all_curves.len()).collect(),
+                labels: vec![None; all_curves.len()],
+            }
+        }
+    }
+}
+
+/// The ticks of the violin of `sample` at `percentiles`: the time at each percentile and the
+/// height of the violin there, relative to its widest point, from the points `xs` of its KDE and
+/// their normalized densities `ys`.
+pub(crate) fn violin_ticks(
+    sample: &[f64],
+    percentiles: &[f64],
+    xs: &[f64],
+    ys: &[f64],
+) -> Vec<(f64, f64)> {
+    if percentiles.is_empty() {
+        return vec![];
+    }
+    let sample = Sample::new(sample).percentiles();
+    percentiles
+        .iter()
+        .map(|&p| {
+            let x = sample.at(p);
+            let nearest = xs
+                .iter()
+                .map(|&kde_x| (kde_x - x).abs())
+                .enumerate()
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+                .map_or(0, |(i, _)| i);
+            (x, ys.get(nearest).cloned().unwrap_or(0.0))
+        })
+        .collect()
+}
+
+/// The box of a benchmark in the box plot: the quartiles of its sample, the ends of the whiskers,
+/// at the furthest measurements within the inner Tukey fences, and the outliers beyond them.
+pub(crate) struct BoxStats {
+    pub(crate) whisker_low: f64,
+    pub(crate) q1: f64,
+    pub(crate) median: f64,
+    pub(crate) q3: f64,
+    pub(crate) whisker_high: f64,
+    pub(crate) outliers: Vec<f64>,
+}
+
+impl BoxStats {
+    fn new(sample: &[f64]) -> BoxStats {
+        let sample = Sample::new(sample);
+        let (q1, median, q3) = sample.percentiles().quartiles();
+        let (_, low_fence, high_fence, _) = tukey::classify(sample).fences();
+        let mut stats = BoxStats {
+            whisker_low: q1,
+            q1,
+            median,
+            q3,
+            whisker_high: q3,
+            outliers: vec![],
+        };
+        for &x in sample.iter() {
+            if x < low_fence || x > high_fence {
+                stats.outliers.push(x);
+            } else {
+                stats.whisker_low = stats.whisker_low.min(x);
+                stats.whisker_high = stats.whisker_high.max(x);
+            }
+        }
+        stats
+    }
+}
+
+/// The boxes of the box plot of `all_curves`, scaled to a common unit, and that unit.
+pub(crate) fn box_plots(
+    formatter: &dyn ValueFormatter,
+    all_curves: &[&(&BenchmarkId, Vec<f64>)],
+) -> (Vec<BoxStats>, &'static str) {
+    let mut boxes: Vec<_> = all_curves
+        .iter()
+        .map(|&(_, sample)| BoxStats::new(sample))
+        .collect();
+    let typical = boxes
+        .iter()
+        .map(|stats| stats.whisker_high)
+        .fold(f64::NAN, f64::max);
+    let unit = formatter.scale_values(typical, &mut [1.0]);
+    for stats in &mut boxes {
+        let mut values = [
+            stats.whisker_low,
+            stats.q1,
+            stats.median,
+            stats.q3,
+            stats.whisker_high,
+        ];
+        formatter.scale_values(typical, &mut values);
+        let [whisker_low, q1, median, q3, whisker_high] = values;
+        stats.whisker_low = whisker_low;
+        stats.q1 = q1;
+        stats.median = median;
+        stats.q3 = q3;
+        stats.whisker_high = whisker_high;
+        formatter.scale_values(typical, &mut stats.outliers);
+    }
+    (boxes, unit)
+}
+
+/// The bandwidth of the kernel density estimates of the measurements, set with
+/// `PlotConfiguration::kde_bandwidth`. `scale` scales a fixed bandwidth like the measurements.
+pub(crate) fn kde_bandwidth<F: FnOnce(&mut [f64])>(
+    conf: &PlotConfiguration,
+    scale: F,
+) -> Bandwidth {
+    match conf.kde_bandwidth {
+        KdeBandwidth::Silverman => Bandwidth::Silverman,
+        KdeBandwidth::Scott => Bandwidth::Scott,
+        KdeBandwidth::Fixed(h) => {
+            let mut h = [h];
+            scale(&mut h);
+            Bandwidth::Fixed(h[0])
+        }
+    }
+}
+
+/// The percentiles which the CDF plot marks unless `Criterion::percentiles` is set.
+const CDF_PERCENTILES: [f64; 3] = [50.0, 90.0, 99.0];
+
+/// The colors of the lines of the CDF plot at the percentiles, in turn.
+const PERCENTILE_COLORS: [(u8, u8, u8); 6] = [
+    (255, 127, 0),
+    (227, 26, 28),
+    (106, 61, 154),
+    (51, 160, 44),
+    (31, 120, 180),
+    (177, 89, 40),
+];
+
+/// The empirical cumulative distribution of the average times of a sample, scaled by the
+/// formatter.
+pub(crate) struct Ecdf {
+    /// The sorted average times.
+    pub(crate) xs: Vec<f64>,
+    /// The fraction of the sample at or below each of `xs`.
+    pub(crate) ys: Vec<f64>,
+    /// The time at each of the estimated percentiles, or of `CDF_PERCENTILES` if there are none,
+    /// with its label and color.
+    pub(crate) percentiles: Vec<(f64, String, (u8, u8, u8))>,
+}
+
+/// Computes the empirical CDF of the average times of `measurements`, and the unit of the times.
+pub(crate) fn ecdf(
+    formatter: &dyn ValueFormatter,
+    measurements: &MeasurementData<'_>,
+) -> (Ecdf, &'static str) {
+    let avg_times: &Sample<f64> = &measurements.avg_times;
+    let mut xs = avg_times.to_vec();
+    let estimated = &measurements.absolute_estimates.percentiles;
+    let marked: Vec<f64> = if estimated.is_empty() {
+        CDF_PERCENTILES.to_vec()
+    } else {
+        estimated.iter().map(|p| p.percentile).collect()
+    };
+    let mut percentiles: Vec<f64> = marked
+        .iter()
+        .map(|&p| avg_times.percentiles().at(p))
+        .collect();
+    let unit = formatter.scale_values(avg_times.max(), &mut xs);
+    formatter.scale_values(avg_times.max(), &mut percentiles);
+
+    xs.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Less));
+    let n = xs.len() as f64;
+    let ys = (1..=xs.len()).map(|i| i as f64 / n).collect();
+    let percentiles = marked
+        .iter()
+        .zip(percentiles)
+        .zip(PERCENTILE_COLORS.iter().cycle())
+        .map(|((&p, x), &color)| {
+            let label = format!("p{} = {} {}", p, format::short(x), unit);
+            (x, label, color)
+        })
+        .collect();
+    (
+        Ecdf {
+            xs,
+            ys,
+            percentiles,
+        },
+        unit,
+    )
+}
+
+/// The samples of a benchmark in the order they were measured, and their trend line, scaled by
+/// the formatter.
+pub(crate) struct DriftSeries {
+    /// The time per iteration of each sample.
+    pub(crate) ys: Vec<f64>,
+    /// The trend at the first and at the last sample.
+    pub(crate) trend: [f64; 2],
+    /// The label of the trend line, eg. `Trend: +4.2% over the run`.
+    pub(crate) trend_label: String,
+}
+
+/// Computes the drift series of `measurements`, and the unit of the times. Returns `None` if they
+/// have no drift diagnostics.
+pub(crate) fn drift_series(
+    formatter: &dyn ValueFormatter,
+    measurements: &MeasurementData<'_>,
+) -> Option<(DriftSeries, &'static str)> {
+    let drift = measurements.drift.as_ref()?;
+    let mut ys = drift.series.clone();
+    let mut trend = [drift.trend.0, drift.trend.1];
+    let typical = Sample::new(&ys).max();
+    let unit = formatter.scale_values(typical, &mut ys);
+    formatter.scale_values(typical, &mut trend);
+    let trend_label = format!(
+        "Trend: {} over the run",
+        format::change(drift.relative_drift, true).trim()
+    );
+    Some((
+        DriftSeries {
+            ys,
+            trend,
+            trend_label,
+        },
+        unit,
+    ))
+}
+
+/// Labels the tic `bytes` as a size in bytes, with binary prefixes.
+pub(crate) fn format_bytes(bytes: i64) -> String {
+    if bytes < 1024 {
+        format!("{:.0}b", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.0}Kb", bytes as f64 / 1024.)
+    } else if bytes < 1024 * 1024 * 1024 {
+        format!("{:.0}Mb", bytes as f64 / (1024. * 1024.))
+    } else {
+        format!("{:.0}Gb", bytes as f64 / (1024. * 1024. * 1024.))
+    }
 }
@@ -25,7 +25,12 @@ fn abs_distribution(
     let mut scaled_xs: Vec<f64> = distribution.iter().cloned().collect();
     let _ = formatter.scale_values(typical, &mut scaled_xs);
     let scaled_xs_sample = Sample::new(&scaled_xs);
-    let (kde_xs, ys) = kde::sweep(scaled_xs_sample, KDE_POINTS, Some((start, end)));
+    let (kde_xs, ys) = kde::sweep(
+        scaled_xs_sample,
+        Bandwidth::Silverman,
+        context.plot_config.kde_points,
+        Some((start, end)),
+    );
 
     // interpolate between two points of the KDE sweep to find the Y position at the point estimate.
     let n_point = kde_xs
@@ -54,7 +59,7 @@ fn abs_distribution(
     let kde_xs_sample = Sample::new(&kde_xs);
 
     let path = context.report_path(id, &format!("{}.svg", statistic));
-    let root_area = SVGBackend::new(&path, size.unwrap_or(SIZE)).into_drawing_area();
+    let root_area = svg_area(&path, size.unwrap_or(SIZE), &context.plot_config);
 
     let x_range = plotters::data::fitting_range(kde_xs_sample.iter());
     let mut y_range = plotters::data::fitting_range(ys.iter());
@@ -119,7 +124,10 @@ fn abs_distribution(
 
     chart
         .configure_series_labels()
-        .position(SeriesLabelPosition::UpperRight)
+        .position(legend_position(
+            &context.plot_config,
+            SeriesLabelPosition::UpperRight,
+        ))
         .draw()
         .unwrap();
 }
@@ -168,7 +176,12 @@ fn rel_distribution(
 
     let start = lb - (ub - lb) / 9.;
     let end = ub + (ub - lb) / 9.;
-    let (xs, ys) = kde::sweep(distribution, KDE_POINTS, Some((start, end)));
+    let (xs, ys) = kde::sweep(
+        distribution,
+        Bandwidth::Silverman,
+        context.plot_config.kde_points,
+        Some((start, end)),
+    );
     let xs_ = Sample::new(&xs);
 
     // interpolate between two points of the KDE sweep to find the Y position at the point estimate.
@@ -214,7 +227,7 @@ fn rel_distribution(
     };
     let y_range = plotters::data::fitting_range(ys.iter());
     let path = context.report_path(id, &format!("change/{}.svg", statistic));
-    let root_area = SVGBackend::new(&path, size.unwrap_or(SIZE)).into_drawing_area();
+    let root_area = svg_area(&path, size.unwrap_or(SIZE), &context.plot_config);
 
     let mut chart = ChartBuilder::on(&root_area)
         .margin((5).percent())
@@ -283,7 +296,10 @@ fn rel_distribution(
         });
     chart
         .configure_series_labels()
-        .position(SeriesLabelPosition::UpperRight)
+        .position(legend_position(
+            &context.plot_config,
+            SeriesLabelPosition::UpperRight,
+        ))
         .draw()
         .unwrap();
 }
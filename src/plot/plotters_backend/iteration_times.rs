@@ -1,4 +1,6 @@
 use super::*;
+use crate::plot::drift_series;
+use crate::report::ReportContext;
 
 use std::path::Path;
 
@@ -8,6 +10,7 @@ pub(crate) fn iteration_times_figure(
     formatter: &dyn ValueFormatter,
     measurements: &MeasurementData<'_>,
     size: Option<(u32, u32)>,
+    conf: &PlotConfiguration,
 ) {
     let data = &measurements.avg_times;
     let max_avg_time = data.max();
@@ -16,7 +19,7 @@ pub(crate) fn iteration_times_figure(
     let scaled_y = Sample::new(&scaled_y);
 
     let size = size.unwrap_or(SIZE);
-    let root_area = SVGBackend::new(path, size).into_drawing_area();
+    let root_area = svg_area(path, size, conf);
 
     let mut cb = ChartBuilder::on(&root_area);
     if let Some(title) = title {
@@ -54,7 +57,7 @@ pub(crate) fn iteration_times_figure(
     if title.is_some() {
         chart
             .configure_series_labels()
-            .position(SeriesLabelPosition::UpperLeft)
+            .position(legend_position(conf, SeriesLabelPosition::UpperLeft))
             .draw()
             .unwrap();
     }
@@ -67,6 +70,7 @@ pub(crate) fn iteration_times_comparison_figure(
     measurements: &MeasurementData<'_>,
     comparison: &ComparisonData,
     size: Option<(u32, u32)>,
+    conf: &PlotConfiguration,
 ) {
     let current_data = &measurements.avg_times;
     let base_data = &comparison.base_avg_times;
@@ -82,7 +86,7 @@ pub(crate) fn iteration_times_comparison_figure(
     let scaled_base_y = Sample::new(scaled_base_y);
 
     let size = size.unwrap_or(SIZE);
-    let root_area = SVGBackend::new(path, size).into_drawing_area();
+    let root_area = svg_area(path, size, conf);
 
     let mut cb = ChartBuilder::on(&root_area);
     if let Some(title) = title {
@@ -131,8 +135,70 @@ pub(crate) fn iteration_times_comparison_figure(
     if title.is_some() {
         chart
             .configure_series_labels()
-            .position(SeriesLabelPosition::UpperLeft)
+            .position(legend_position(conf, SeriesLabelPosition::UpperLeft))
             .draw()
             .unwrap();
     }
 }
+
+pub(crate) fn drift_figure(
+    id: &BenchmarkId,
+    context: &ReportContext,
+    formatter: &dyn ValueFormatter,
+    measurements: &MeasurementData<'_>,
+    size: Option<(u32, u32)>,
+) {
+    let (series, unit) = match drift_series(formatter, measurements) {
+        Some(series) => series,
+        None => return,
+    };
+    let path = context.report_path(id, "drift.svg");
+    let root_area = svg_area(&path, size.unwrap_or(SIZE), &context.plot_config);
+
+    let n = series.ys.len();
+    let x_range = (1.0)..((n + 1) as f64);
+    let y_range = plotters::data::fitting_range(series.ys.iter().chain(series.trend.iter()));
+    let mut chart = ChartBuilder::on(&root_area)
+        .margin((5).percent())
+        .caption(id.as_title(), (DEFAULT_FONT, 20))
+        .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
+        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
+        .build_cartesian_2d(x_range, y_range)
+        .unwrap();
+
+    chart
+        .configure_mesh()
+        .x_desc("Sample, in the order measured")
+        .y_desc(format!("Time per Iteration ({})", unit))
+        .x_label_formatter(&|x| pretty_print_float(*x, true))
+        .light_line_style(TRANSPARENT)
+        .draw()
+        .unwrap();
+
+    chart
+        .draw_series(
+            (1..=n)
+                .zip(series.ys.iter())
+                .map(|(x, y)| Circle::new((x as f64, *y), POINT_SIZE, DARK_BLUE.filled())),
+        )
+        .unwrap()
+        .label("Sample")
+        .legend(|(x, y)| Circle::new((x + 10, y), POINT_SIZE, DARK_BLUE.filled()));
+    chart
+        .draw_series(std::iter::once(PathElement::new(
+            vec![(1.0, series.trend[0]), (n as f64, series.trend[1])],
+            DARK_RED.stroke_width(2),
+        )))
+        .unwrap()
+        .label(series.trend_label)
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], DARK_RED));
+
+    chart
+        .configure_series_labels()
+        .position(legend_position(
+            &context.plot_config,
+            SeriesLabelPosition::UpperLeft,
+        ))
+        .draw()
+        .unwrap();
+}
@@ -1,21 +1,23 @@
-use super::{PlotContext, PlotData, Plotter};
+use super::{kde_bandwidth, PlotContext, PlotData, Plotter};
+use crate::estimate::ConfidenceInterval;
 use crate::measurement::ValueFormatter;
-use crate::report::{BenchmarkId, ComparisonData, MeasurementData, ValueType};
+use crate::report::{BenchmarkId, ComparisonData, MeasurementData, SignificantChange, ValueType};
+use crate::{Corner, LegendPosition, PlotConfiguration};
 use plotters::data::float::pretty_print_float;
 use plotters::prelude::*;
 
-use crate::kde;
+use crate::kde::{self, Bandwidth};
 use crate::stats::bivariate::Data;
 use crate::stats::univariate::Sample;
 
 static DEFAULT_FONT: FontFamily = FontFamily::SansSerif;
-static KDE_POINTS: usize = 500;
 static SIZE: (u32, u32) = (960, 540);
 static POINT_SIZE: u32 = 3;
 
 const DARK_BLUE: RGBColor = RGBColor(31, 120, 180);
 const DARK_ORANGE: RGBColor = RGBColor(255, 127, 0);
 const DARK_RED: RGBColor = RGBColor(227, 26, 28);
+const DARK_GREEN: RGBColor = RGBColor(51, 160, 44);
 
 mod distributions;
 mod iteration_times;
@@ -23,6 +25,9 @@ mod pdf;
 mod regression;
 mod summary;
 mod t_test;
+mod theme;
+
+use theme::{svg_area, ThemedSvg};
 
 fn convert_size(size: Option<(usize, usize)>) -> Option<(u32, u32)> {
     if let Some((w, h)) = size {
@@ -30,6 +35,53 @@ fn convert_size(size: Option<(usize, usize)>) -> Option<(u32, u32)> {
     }
     None
 }
+
+/// The position of the legend set by `PlotConfiguration::legend_position`, or `default`. Plotters
+/// has no room for a legend outside of the chart, so those are drawn in the same corner inside.
+fn legend_position(conf: &PlotConfiguration, default: SeriesLabelPosition) -> SeriesLabelPosition {
+    let corner = match conf.legend_position {
+        Some(LegendPosition::Inside(corner)) | Some(LegendPosition::Outside(corner)) => corner,
+        None => return default,
+    };
+    match corner {
+        Corner::TopLeft => SeriesLabelPosition::UpperLeft,
+        Corner::TopRight => SeriesLabelPosition::UpperRight,
+        Corner::BottomLeft => SeriesLabelPosition::LowerLeft,
+        Corner::BottomRight => SeriesLabelPosition::LowerRight,
+    }
+}
+
+/// Marks `points` with triangles pointing up if `direction` is 1, for regressions, or down if it is
+/// -1, for improvements.
+fn draw_change_markers<
+    DB: DrawingBackend,
+    X: Ranged<ValueType = f64>,
+    Y: Ranged<ValueType = f64>,
+>(
+    chart: &mut ChartContext<'_, DB, Cartesian2d<X, Y>>,
+    points: &[(f64, f64)],
+    label: &str,
+    color: RGBColor,
+    direction: i32,
+) {
+    let triangle = move |(x, y): (i32, i32)| {
+        vec![
+            (x, y - 6 * direction),
+            (x - 6, y + 4 * direction),
+            (x + 6, y + 4 * direction),
+        ]
+    };
+    chart
+        .draw_series(
+            points.iter().map(|&point| {
+                EmptyElement::at(point) + Polygon::new(triangle((0, 0)), color.filled())
+            }),
+        )
+        .unwrap()
+        .label(label)
+        .legend(move |(x, y)| Polygon::new(triangle((x + 10, y)), color.filled()));
+}
+
 #[derive(Default)]
 pub struct PlottersBackend;
 
@@ -55,6 +107,7 @@ impl Plotter for PlottersBackend {
                 data.measurements,
                 cmp,
                 convert_size(ctx.size),
+                &ctx.context.plot_config,
             );
             return;
         }
@@ -77,6 +130,26 @@ impl Plotter for PlottersBackend {
         }
     }
 
+    fn cdf(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>) {
+        pdf::cdf(
+            ctx.id,
+            ctx.context,
+            data.formatter,
+            data.measurements,
+            convert_size(ctx.size),
+        );
+    }
+
+    fn drift(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>) {
+        iteration_times::drift_figure(
+            ctx.id,
+            ctx.context,
+            data.formatter,
+            data.measurements,
+            convert_size(ctx.size),
+        );
+    }
+
     fn regression(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>) {
         let (title, path) = match (data.comparison.is_some(), ctx.is_thumbnail) {
             (true, true) => (
@@ -108,6 +181,7 @@ impl Plotter for PlottersBackend {
                 cmp,
                 &base_data,
                 convert_size(ctx.size),
+                &ctx.context.plot_config,
             );
         } else {
             regression::regression_figure(
@@ -116,6 +190,7 @@ impl Plotter for PlottersBackend {
                 data.formatter,
                 data.measurements,
                 convert_size(ctx.size),
+                &ctx.context.plot_config,
             );
         }
     }
@@ -150,6 +225,7 @@ impl Plotter for PlottersBackend {
                 data.measurements,
                 cmp,
                 convert_size(ctx.size),
+                &ctx.context.plot_config,
             );
         } else {
             iteration_times::iteration_times_figure(
@@ -158,6 +234,7 @@ impl Plotter for PlottersBackend {
                 data.formatter,
                 data.measurements,
                 convert_size(ctx.size),
+                &ctx.context.plot_config,
             );
         }
     }
@@ -187,6 +264,8 @@ impl Plotter for PlottersBackend {
         ctx: PlotContext<'_>,
         formatter: &dyn ValueFormatter,
         all_curves: &[&(&BenchmarkId, Vec<f64>)],
+        mean_intervals: &[Option<ConfidenceInterval>],
+        changes: &[Option<SignificantChange>],
         value_type: ValueType,
     ) {
         let path = ctx.line_comparison_path();
@@ -194,9 +273,12 @@ impl Plotter for PlottersBackend {
             formatter,
             ctx.id.as_title(),
             all_curves,
+            mean_intervals,
+            changes,
             &path,
             value_type,
-            ctx.context.plot_config.x_scale,
+            &ctx.context.plot_config,
+            convert_size(ctx.size),
         );
     }
 
@@ -213,7 +295,33 @@ impl Plotter for PlottersBackend {
             ctx.id.as_title(),
             all_curves,
             &violin_path,
-            ctx.context.plot_config.x_scale,
+            &ctx.context.plot_config,
+            &ctx.context.percentiles,
+        );
+    }
+
+    fn box_plot(
+        &mut self,
+        ctx: PlotContext<'_>,
+        formatter: &dyn ValueFormatter,
+        all_curves: &[&(&BenchmarkId, Vec<f64>)],
+    ) {
+        summary::box_plot(
+            formatter,
+            ctx.id.as_title(),
+            all_curves,
+            &ctx.box_plot_path(),
+            &ctx.context.plot_config,
+        );
+    }
+
+    fn speedup_summary(&mut self, ctx: PlotContext<'_>, speedups: &[(String, f64)]) {
+        summary::speedup_summary(
+            ctx.id.as_title(),
+            speedups,
+            &ctx.speedup_path(),
+            &ctx.context.plot_config,
+            convert_size(ctx.size),
         );
     }
 
@@ -225,6 +333,7 @@ impl Plotter for PlottersBackend {
             title,
             data.comparison.unwrap(),
             convert_size(ctx.size),
+            &ctx.context.plot_config,
         );
     }
 
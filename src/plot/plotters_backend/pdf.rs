@@ -1,5 +1,6 @@
 use super::*;
 use crate::measurement::ValueFormatter;
+use crate::plot::ecdf;
 use crate::report::{BenchmarkId, ComparisonData, MeasurementData, ReportContext};
 use plotters::data;
 use plotters::style::RGBAColor;
@@ -12,6 +13,7 @@ pub(crate) fn pdf_comparison_figure(
     measurements: &MeasurementData<'_>,
     comparison: &ComparisonData,
     size: Option<(u32, u32)>,
+    conf: &PlotConfiguration,
 ) {
     let base_avg_times = Sample::new(&comparison.base_avg_times);
     let typical = base_avg_times.max().max(measurements.avg_times.max());
@@ -29,16 +31,29 @@ pub(crate) fn pdf_comparison_figure(
     let base_mean = scaled_base_avg_times.mean();
     let new_mean = scaled_new_avg_times.mean();
 
-    let (base_xs, base_ys, base_y_mean) =
-        kde::sweep_and_estimate(scaled_base_avg_times, KDE_POINTS, None, base_mean);
-    let (xs, ys, y_mean) =
-        kde::sweep_and_estimate(scaled_new_avg_times, KDE_POINTS, None, new_mean);
+    let bandwidth = kde_bandwidth(conf, |h| {
+        formatter.scale_values(typical, h);
+    });
+    let (base_xs, base_ys, base_y_mean) = kde::sweep_and_estimate(
+        scaled_base_avg_times,
+        bandwidth,
+        conf.kde_points,
+        None,
+        base_mean,
+    );
+    let (xs, ys, y_mean) = kde::sweep_and_estimate(
+        scaled_new_avg_times,
+        bandwidth,
+        conf.kde_points,
+        None,
+        new_mean,
+    );
 
     let x_range = data::fitting_range(base_xs.iter().chain(xs.iter()));
     let y_range = data::fitting_range(base_ys.iter().chain(ys.iter()));
 
     let size = size.unwrap_or(SIZE);
-    let root_area = SVGBackend::new(&path, (size.0 as u32, size.1 as u32)).into_drawing_area();
+    let root_area = svg_area(&path, size, conf);
 
     let mut cb = ChartBuilder::on(&root_area);
 
@@ -105,7 +120,11 @@ pub(crate) fn pdf_comparison_figure(
         .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &DARK_BLUE));
 
     if title.is_some() {
-        chart.configure_series_labels().draw().unwrap();
+        chart
+            .configure_series_labels()
+            .position(legend_position(conf, SeriesLabelPosition::MiddleRight))
+            .draw()
+            .unwrap();
     }
 }
 
@@ -123,7 +142,12 @@ pub(crate) fn pdf_small(
     let scaled_avg_times = Sample::new(&scaled_avg_times);
     let mean = scaled_avg_times.mean();
 
-    let (xs, ys, mean_y) = kde::sweep_and_estimate(scaled_avg_times, KDE_POINTS, None, mean);
+    let conf = &context.plot_config;
+    let bandwidth = kde_bandwidth(conf, |h| {
+        formatter.scale_values(typical, h);
+    });
+    let (xs, ys, mean_y) =
+        kde::sweep_and_estimate(scaled_avg_times, bandwidth, conf.kde_points, None, mean);
     let xs_ = Sample::new(&xs);
     let ys_ = Sample::new(&ys);
 
@@ -132,7 +156,7 @@ pub(crate) fn pdf_small(
     let path = context.report_path(id, "pdf_small.svg");
 
     let size = size.unwrap_or(SIZE);
-    let root_area = SVGBackend::new(&path, (size.0 as u32, size.1 as u32)).into_drawing_area();
+    let root_area = svg_area(&path, size, &context.plot_config);
 
     let mut chart = ChartBuilder::on(&root_area)
         .margin((5).percent())
@@ -197,7 +221,11 @@ pub(crate) fn pdf(
         format!("Iterations (x 10^{})", exponent)
     };
 
-    let (xs, ys) = kde::sweep(scaled_avg_times, KDE_POINTS, None);
+    let conf = &context.plot_config;
+    let bandwidth = kde_bandwidth(conf, |h| {
+        formatter.scale_values(typical, h);
+    });
+    let (xs, ys) = kde::sweep(scaled_avg_times, bandwidth, conf.kde_points, None);
     let (lost, lomt, himt, hist) = avg_times.fences();
     let mut fences = [lost, lomt, himt, hist];
     let _ = formatter.scale_values(typical, &mut fences);
@@ -208,7 +236,7 @@ pub(crate) fn pdf(
     let xs_ = Sample::new(&xs);
 
     let size = size.unwrap_or(SIZE);
-    let root_area = SVGBackend::new(&path, (size.0 as u32, size.1 as u32)).into_drawing_area();
+    let root_area = svg_area(&path, size, &context.plot_config);
 
     let range = data::fitting_range(ys.iter());
 
@@ -303,5 +331,73 @@ pub(crate) fn pdf(
         "Mild outliers",
     );
     draw_data_point_series(&|l| l.is_severe(), DARK_RED.to_rgba(), "Severe outliers");
-    chart.configure_series_labels().draw().unwrap();
+    chart
+        .configure_series_labels()
+        .position(legend_position(conf, SeriesLabelPosition::MiddleRight))
+        .draw()
+        .unwrap();
+}
+
+pub(crate) fn cdf(
+    id: &BenchmarkId,
+    context: &ReportContext,
+    formatter: &dyn ValueFormatter,
+    measurements: &MeasurementData<'_>,
+    size: Option<(u32, u32)>,
+) {
+    let (ecdf, unit) = ecdf(formatter, measurements);
+    let path = context.report_path(id, "cdf.svg");
+    let root_area = svg_area(&path, size.unwrap_or(SIZE), &context.plot_config);
+
+    let x_range = data::fitting_range(ecdf.xs.iter());
+    let mut chart = ChartBuilder::on(&root_area)
+        .margin((5).percent())
+        .caption(id.as_title(), (DEFAULT_FONT, 20))
+        .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
+        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
+        .build_cartesian_2d(x_range, 0.0..1.0)
+        .unwrap();
+
+    chart
+        .configure_mesh()
+        .y_desc("Cumulative probability")
+        .x_desc(format!("Average Time ({})", unit))
+        .x_label_formatter(&|&x| pretty_print_float(x, true))
+        .draw()
+        .unwrap();
+
+    // Each step rises at a time of the sample, from the fraction of the sample below it.
+    let mut steps = vec![];
+    let mut below = 0.0;
+    for (&x, &y) in ecdf.xs.iter().zip(&ecdf.ys) {
+        steps.push((x, below));
+        steps.push((x, y));
+        below = y;
+    }
+    chart
+        .draw_series(LineSeries::new(steps, DARK_BLUE.filled().stroke_width(2)))
+        .unwrap()
+        .label("Empirical CDF")
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], DARK_BLUE));
+
+    for (x, label, (r, g, b)) in ecdf.percentiles {
+        let color = RGBColor(r, g, b);
+        chart
+            .draw_series(std::iter::once(PathElement::new(
+                vec![(x, 0.0), (x, 1.0)],
+                color.stroke_width(2),
+            )))
+            .unwrap()
+            .label(label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+
+    chart
+        .configure_series_labels()
+        .position(legend_position(
+            &context.plot_config,
+            SeriesLabelPosition::LowerRight,
+        ))
+        .draw()
+        .unwrap();
 }
@@ -3,7 +3,8 @@ use super::*;
 use std::path::Path;
 
 use crate::estimate::{ConfidenceInterval, Estimate};
-use crate::stats::bivariate::regression::Slope;
+use crate::plot::change_label;
+use crate::report::SignificantChange;
 use crate::stats::bivariate::Data;
 
 pub(crate) fn regression_figure(
@@ -12,6 +13,7 @@ pub(crate) fn regression_figure(
     formatter: &dyn ValueFormatter,
     measurements: &MeasurementData<'_>,
     size: Option<(u32, u32)>,
+    conf: &PlotConfiguration,
 ) {
     let slope_estimate = measurements.absolute_estimates.slope.as_ref().unwrap();
     let slope_dist = measurements.distributions.slope.as_ref().unwrap();
@@ -24,7 +26,7 @@ pub(crate) fn regression_figure(
     let unit = formatter.scale_values(typical, &mut scaled_y);
     let scaled_y = Sample::new(&scaled_y);
 
-    let point_estimate = Slope::fit(&measurements.data).0;
+    let point_estimate = slope_estimate.point_estimate;
     let mut scaled_points = [point_estimate * max_iters, lb * max_iters, ub * max_iters];
     let _ = formatter.scale_values(typical, &mut scaled_points);
     let [point, lb, ub] = scaled_points;
@@ -39,7 +41,7 @@ pub(crate) fn regression_figure(
     };
 
     let size = size.unwrap_or(SIZE);
-    let root_area = SVGBackend::new(path, size).into_drawing_area();
+    let root_area = svg_area(path, size, conf);
 
     let mut cb = ChartBuilder::on(&root_area);
     if let Some(title) = title {
@@ -104,12 +106,13 @@ pub(crate) fn regression_figure(
     if title.is_some() {
         chart
             .configure_series_labels()
-            .position(SeriesLabelPosition::UpperLeft)
+            .position(legend_position(conf, SeriesLabelPosition::UpperLeft))
             .draw()
             .unwrap();
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn regression_comparison_figure(
     title: Option<&str>,
     path: &Path,
@@ -118,6 +121,7 @@ pub(crate) fn regression_comparison_figure(
     comparison: &ComparisonData,
     base_data: &Data<'_, f64, f64>,
     size: Option<(u32, u32)>,
+    conf: &PlotConfiguration,
 ) {
     let data = &measurements.data;
     let max_iters = base_data.x().max().max(data.x().max());
@@ -168,7 +172,7 @@ pub(crate) fn regression_comparison_figure(
     let y_max = point.max(base_point);
 
     let size = size.unwrap_or(SIZE);
-    let root_area = SVGBackend::new(path, size).into_drawing_area();
+    let root_area = svg_area(path, size, conf);
 
     let mut cb = ChartBuilder::on(&root_area);
     if let Some(title) = title {
@@ -224,10 +228,18 @@ pub(crate) fn regression_comparison_figure(
             )
         });
 
+    if let Some(label) = change_label(comparison) {
+        let (color, direction) = match comparison.significant_change() {
+            Some(SignificantChange::Improved) => (DARK_GREEN, -1),
+            _ => (DARK_RED, 1),
+        };
+        draw_change_markers(&mut chart, &[(max_iters, point)], &label, color, direction);
+    }
+
     if title.is_some() {
         chart
             .configure_series_labels()
-            .position(SeriesLabelPosition::UpperLeft)
+            .position(legend_position(conf, SeriesLabelPosition::UpperLeft))
             .draw()
             .unwrap();
     }
@@ -1,11 +1,21 @@
 use super::*;
-use crate::AxisScale;
+use crate::plot::{
+    box_plots, format_bytes, ratio_curves, violin_ticks, BoxStats, ChangeMarkers, LineScale,
+    ViolinColors, DARK_PALETTE,
+};
+use crate::report::SignificantChange;
+use crate::{AxisScale, PlotConfiguration, Theme};
 use itertools::Itertools;
 use plotters::coord::{
-    ranged1d::{AsRangedCoord, ValueFormatter as PlottersValueFormatter},
+    ranged1d::{
+        AsRangedCoord, DefaultFormatting, KeyPointHint, Ranged,
+        ValueFormatter as PlottersValueFormatter,
+    },
     Shift,
 };
+use plotters::style::text_anchor::{HPos, Pos, VPos};
 use std::cmp::Ordering;
+use std::ops::Range;
 use std::path::Path;
 
 const NUM_COLORS: usize = 8;
@@ -20,135 +30,363 @@ static COMPARISON_COLORS: [RGBColor; NUM_COLORS] = [
     RGBColor(0, 255, 127),
 ];
 
+/// The colors of the first `count` curves of a comparison plot, from the configured colors or
+/// `defaults`.
+fn palette_colors(conf: &PlotConfiguration, count: usize, defaults: &[RGBColor]) -> Vec<RGBColor> {
+    match &conf.colors {
+        Some(colors) => colors
+            .iter()
+            .map(|&(r, g, b)| RGBColor(r, g, b))
+            .cycle()
+            .take(count)
+            .collect(),
+        None => defaults.iter().cloned().cycle().take(count).collect(),
+    }
+}
+
+/// The colors of the first `count` curves of a comparison plot, in the palette of the theme.
+fn comparison_colors(conf: &PlotConfiguration, count: usize) -> Vec<RGBColor> {
+    match conf.theme {
+        Theme::Light => palette_colors(conf, count, &COMPARISON_COLORS),
+        Theme::Dark => {
+            let dark: Vec<_> = DARK_PALETTE
+                .iter()
+                .map(|&(r, g, b)| RGBColor(r, g, b))
+                .collect();
+            palette_colors(conf, count, &dark)
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn line_comparison(
     formatter: &dyn ValueFormatter,
     title: &str,
     all_curves: &[&(&BenchmarkId, Vec<f64>)],
+    mean_intervals: &[Option<ConfidenceInterval>],
+    changes: &[Option<SignificantChange>],
     path: &Path,
     value_type: ValueType,
-    axis_scale: AxisScale,
+    conf: &PlotConfiguration,
+    size: Option<(u32, u32)>,
 ) {
-    let (unit, series_data) = line_comparison_series_data(formatter, all_curves);
+    let (y_label, series_data) = match ratio_curves(title, all_curves, mean_intervals, conf) {
+        Some((y_label, curves)) => {
+            let series_data = curves
+                .into_iter()
+                .map(|(name, ratios)| Series {
+                    name,
+                    xs: ratios.xs,
+                    ys: ratios.ys,
+                    band: Some((ratios.lower, ratios.upper)),
+                })
+                .collect();
+            (y_label, series_data)
+        }
+        None => {
+            let scale = LineScale::new(formatter, all_curves, conf);
+            let series_data = line_comparison_series_data(&scale, all_curves, mean_intervals);
+            (scale.y_label(), series_data)
+        }
+    };
+    let mut markers = ChangeMarkers::default();
+    for series in &series_data {
+        markers.mark(all_curves, changes, series.name, &series.xs, &series.ys);
+    }
 
     let x_range =
-        plotters::data::fitting_range(series_data.iter().flat_map(|(_, xs, _)| xs.iter()));
-    let y_range =
-        plotters::data::fitting_range(series_data.iter().flat_map(|(_, _, ys)| ys.iter()));
-    let root_area = SVGBackend::new(&path, SIZE)
-        .into_drawing_area()
-        .titled(&format!("{}: Comparison", title), (DEFAULT_FONT, 20))
+        plotters::data::fitting_range(series_data.iter().flat_map(|series| series.xs.iter()));
+    let y_range = plotters::data::fitting_range(series_data.iter().flat_map(|series| {
+        let band = series
+            .band
+            .iter()
+            .flat_map(|(lower, upper)| lower.iter().chain(upper));
+        series.ys.iter().chain(band)
+    }));
+    let title = if conf.label.is_empty() {
+        format!("{}: Comparison", title)
+    } else {
+        conf.label.clone()
+    };
+    let root_area = svg_area(&path, size.unwrap_or(SIZE), conf)
+        .titled(&title, (DEFAULT_FONT, 20))
         .unwrap();
 
-    match axis_scale {
-        AxisScale::Linear => {
-            draw_line_comarision_figure(root_area, unit, x_range, y_range, value_type, series_data)
-        }
-        AxisScale::Logarithmic => draw_line_comarision_figure(
+    let tics: Vec<f64> = conf.tics.iter().map(|&tic| tic as f64).collect();
+    match (conf.x_scale, tics.is_empty()) {
+        (AxisScale::Linear, true) => draw_line_comarision_figure(
             root_area,
-            unit,
+            y_label,
+            x_range,
+            y_range,
+            value_type,
+            series_data,
+            markers,
+            conf,
+        ),
+        (AxisScale::Linear, false) => draw_line_comarision_figure(
+            root_area,
+            y_label,
+            with_tics(x_range, tics),
+            y_range,
+            value_type,
+            series_data,
+            markers,
+            conf,
+        ),
+        (AxisScale::Logarithmic, true) => draw_line_comarision_figure(
+            root_area,
+            y_label,
             x_range.log_scale(),
             y_range.log_scale(),
             value_type,
             series_data,
+            markers,
+            conf,
+        ),
+        (AxisScale::Logarithmic, false) => draw_line_comarision_figure(
+            root_area,
+            y_label,
+            with_tics(x_range.log_scale(), tics),
+            y_range.log_scale(),
+            value_type,
+            series_data,
+            markers,
+            conf,
         ),
     }
 }
 
+/// An x axis whose major tics are only at the positions set with `PlotConfiguration::tics`.
+struct Tics<R> {
+    inner: R,
+    tics: Vec<f64>,
+}
+
+/// Puts the major tics of `range` at `tics`.
+fn with_tics<C: AsRangedCoord<Value = f64>>(range: C, tics: Vec<f64>) -> Tics<C::CoordDescType> {
+    Tics {
+        inner: range.into(),
+        tics,
+    }
+}
+
+impl<R: Ranged<ValueType = f64>> Ranged for Tics<R> {
+    // The labels come from `x_label_formatter`.
+    type FormatOption = DefaultFormatting;
+    type ValueType = f64;
+
+    fn map(&self, value: &f64, limit: (i32, i32)) -> i32 {
+        self.inner.map(value, limit)
+    }
+
+    fn key_points<Hint: KeyPointHint>(&self, hint: Hint) -> Vec<f64> {
+        if hint.weight().allow_light_points() {
+            return vec![];
+        }
+        let range = self.inner.range();
+        self.tics
+            .iter()
+            .cloned()
+            .filter(|tic| range.start <= *tic && *tic <= range.end)
+            .collect()
+    }
+
+    fn range(&self) -> Range<f64> {
+        self.inner.range()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn draw_line_comarision_figure<XR: AsRangedCoord<Value = f64>, YR: AsRangedCoord<Value = f64>>(
-    root_area: DrawingArea<SVGBackend, Shift>,
-    y_unit: &str,
+    root_area: DrawingArea<ThemedSvg, Shift>,
+    y_label: String,
     x_range: XR,
     y_range: YR,
     value_type: ValueType,
-    data: Vec<(Option<&String>, Vec<f64>, Vec<f64>)>,
+    data: Vec<Series<'_>>,
+    markers: ChangeMarkers,
+    conf: &PlotConfiguration,
 ) where
     XR::CoordDescType: PlottersValueFormatter<f64>,
     YR::CoordDescType: PlottersValueFormatter<f64>,
 {
-    let input_suffix = match value_type {
-        ValueType::Bytes => " Size (Bytes)",
-        ValueType::Elements => " Size (Elements)",
-        ValueType::Value => "",
+    let x_label = if conf.x_label.is_empty() {
+        let input_suffix = match value_type {
+            ValueType::Bytes => " Size (Bytes)",
+            ValueType::Elements => " Size (Elements)",
+            ValueType::Value => "",
+        };
+        format!("Input{}", input_suffix)
+    } else {
+        conf.x_label.clone()
     };
 
-    let mut chart = ChartBuilder::on(&root_area)
+    let mut builder = ChartBuilder::on(&root_area);
+    builder
         .margin((5).percent())
         .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
-        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
-        .build_cartesian_2d(x_range, y_range)
-        .unwrap();
+        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40));
+    if !conf.tics.is_empty() {
+        // Room above the plot for the raw values of the tics.
+        builder.margin_top((9).percent());
+    }
+    let mut chart = builder.build_cartesian_2d(x_range, y_range).unwrap();
 
-    chart
-        .configure_mesh()
-        .disable_mesh()
-        .x_desc(format!("Input{}", input_suffix))
-        .y_desc(format!("Average time ({})", y_unit))
-        .draw()
-        .unwrap();
+    let tic_label = |x: &f64| {
+        let tic = x.round() as i64;
+        conf.tic_label(tic).unwrap_or_else(|| format_bytes(tic))
+    };
+    let mut mesh = chart.configure_mesh();
+    mesh.x_desc(x_label).y_desc(y_label);
+    if !conf.tics.is_empty() || conf.tic_formatter.is_some() {
+        mesh.x_label_formatter(&tic_label);
+    }
+    if !conf.x_grid_major && !conf.x_grid_minor {
+        mesh.disable_x_mesh();
+    } else if !conf.x_grid_minor {
+        mesh.x_max_light_lines(0);
+    }
+    if !conf.y_grid_major && !conf.y_grid_minor {
+        mesh.disable_y_mesh();
+    } else if !conf.y_grid_minor {
+        mesh.y_max_light_lines(0);
+    }
+    mesh.draw().unwrap();
+    if !conf.tics.is_empty() {
+        draw_raw_tics(&root_area, &chart, &conf.tics);
+    }
 
-    for (id, (name, xs, ys)) in (0..).zip(data.into_iter()) {
+    let colors = comparison_colors(conf, data.len());
+    for (Series { name, xs, ys, band }, &color) in data.into_iter().zip(&colors) {
+        if let Some((lower, upper)) = band {
+            // The upper bounds from left to right, then the lower bounds back.
+            let outline = xs
+                .iter()
+                .zip(upper)
+                .chain(xs.iter().zip(lower).rev())
+                .map(|(&x, y)| (x, y))
+                .collect::<Vec<_>>();
+            chart
+                .draw_series(std::iter::once(Polygon::new(
+                    outline,
+                    color.mix(0.25).filled(),
+                )))
+                .unwrap();
+        }
         let series = chart
             .draw_series(
-                LineSeries::new(
-                    xs.into_iter().zip(ys.into_iter()),
-                    COMPARISON_COLORS[id % NUM_COLORS].filled(),
-                )
-                .point_size(POINT_SIZE),
+                LineSeries::new(xs.into_iter().zip(ys), color.filled()).point_size(POINT_SIZE),
             )
             .unwrap();
         if let Some(name) = name {
             series.label(name).legend(move |(x, y)| {
-                Rectangle::new(
-                    [(x, y - 5), (x + 20, y + 5)],
-                    COMPARISON_COLORS[id % NUM_COLORS].filled(),
-                )
+                Rectangle::new([(x, y - 5), (x + 20, y + 5)], color.filled())
             });
         }
     }
+    let series = [
+        (markers.regressed, "Regressed", DARK_RED, 1),
+        (markers.improved, "Improved", DARK_GREEN, -1),
+    ];
+    for (points, label, color, direction) in series.iter() {
+        if !points.is_empty() {
+            draw_change_markers(&mut chart, points, label, *color, *direction);
+        }
+    }
 
     chart
         .configure_series_labels()
-        .position(SeriesLabelPosition::UpperLeft)
+        .position(legend_position(conf, SeriesLabelPosition::UpperLeft))
         .draw()
         .unwrap();
 }
 
-#[allow(clippy::type_complexity)]
-fn line_comparison_series_data<'a>(
-    formatter: &dyn ValueFormatter,
-    all_curves: &[&(&'a BenchmarkId, Vec<f64>)],
-) -> (&'static str, Vec<(Option<&'a String>, Vec<f64>, Vec<f64>)>) {
-    let max = all_curves
-        .iter()
-        .map(|&&(_, ref data)| Sample::new(data).mean())
-        .fold(::std::f64::NAN, f64::max);
+/// Labels the tics above the plot with their raw values, so that the formatted labels below it
+/// can be read back as the parameters of the benchmarks.
+fn draw_raw_tics<X: Ranged<ValueType = f64>, Y: Ranged<ValueType = f64>>(
+    root_area: &DrawingArea<ThemedSvg, Shift>,
+    chart: &ChartContext<ThemedSvg, Cartesian2d<X, Y>>,
+    tics: &[i64],
+) {
+    let x_range = chart.x_range();
+    let y = chart.y_range().start;
+    let (left, top) = root_area.get_base_pixel();
+    let plot_top = chart.plotting_area().get_base_pixel().1 - top;
+    let style = (DEFAULT_FONT, 12)
+        .into_font()
+        .color(&BLACK)
+        .pos(Pos::new(HPos::Center, VPos::Bottom));
+    for &tic in tics {
+        let x = tic as f64;
+        if x < x_range.start || x_range.end < x {
+            continue;
+        }
+        let (px, _) = chart.backend_coord(&(x, y));
+        root_area
+            .draw(&Text::new(
+                tic.to_string(),
+                (px - left, plot_top - 5),
+                &style,
+            ))
+            .unwrap();
+    }
+}
 
-    let mut dummy = [1.0];
-    let unit = formatter.scale_values(max, &mut dummy);
+/// One line of the line comparison plot.
+struct Series<'a> {
+    name: Option<&'a String>,
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+    /// Lower and upper bounds of the confidence intervals of the means, if all are known.
+    band: Option<(Vec<f64>, Vec<f64>)>,
+}
 
+fn line_comparison_series_data<'a>(
+    scale: &LineScale<'_>,
+    all_curves: &[&(&'a BenchmarkId, Vec<f64>)],
+    mean_intervals: &[Option<ConfidenceInterval>],
+) -> Vec<Series<'a>> {
     let mut series_data = vec![];
 
     // This assumes the curves are sorted. It also assumes that the benchmark IDs all have numeric
     // values or throughputs and that value is sensible (ie. not a mix of bytes and elements
     // or whatnot)
-    for (key, group) in &all_curves.iter().group_by(|&&&(id, _)| &id.function_id) {
+    let curves = all_curves.iter().zip(mean_intervals);
+    for (key, group) in &curves.group_by(|&(&&(id, _), _)| &id.function_id) {
         let mut tuples: Vec<_> = group
-            .map(|&&(id, ref sample)| {
+            .map(|(&&(id, ref sample), interval)| {
                 // Unwrap is fine here because it will only fail if the assumptions above are not true
                 // ie. programmer error.
                 let x = id.as_number().unwrap();
-                let y = Sample::new(sample).mean();
+                let mut y = [Sample::new(sample).mean()];
+                scale.scale(id, &mut y);
+                let bounds = interval.as_ref().map(|ci| {
+                    let mut bounds = [ci.lower_bound, ci.upper_bound];
+                    scale.scale(id, &mut bounds);
+                    (bounds[0], bounds[1])
+                });
 
-                (x, y)
+                (x, y[0], bounds)
             })
             .collect();
-        tuples.sort_by(|&(ax, _), &(bx, _)| (ax.partial_cmp(&bx).unwrap_or(Ordering::Less)));
-        let function_name = key.as_ref();
-        let (xs, mut ys): (Vec<_>, Vec<_>) = tuples.into_iter().unzip();
-        formatter.scale_values(max, &mut ys);
-        series_data.push((function_name, xs, ys));
+        tuples.sort_by(|&(ax, _, _), &(bx, _, _)| ax.partial_cmp(&bx).unwrap_or(Ordering::Less));
+        let xs: Vec<_> = tuples.iter().map(|&(x, _, _)| x).collect();
+        let ys: Vec<_> = tuples.iter().map(|&(_, y, _)| y).collect();
+        let bounds: Option<Vec<_>> = tuples.iter().map(|&(_, _, bounds)| bounds).collect();
+        let band = bounds.map(|bounds| {
+            let lower: Vec<_> = bounds.iter().map(|&(lower, _)| lower).collect();
+            let upper: Vec<_> = bounds.iter().map(|&(_, upper)| upper).collect();
+            (lower, upper)
+        });
+        series_data.push(Series {
+            name: key.as_ref(),
+            xs,
+            ys,
+            band,
+        });
     }
-    (unit, series_data)
+    series_data
 }
 
 pub fn violin(
@@ -156,27 +394,35 @@ pub fn violin(
     title: &str,
     all_curves: &[&(&BenchmarkId, Vec<f64>)],
     path: &Path,
-    axis_scale: AxisScale,
+    conf: &PlotConfiguration,
+    percentiles: &[f64],
 ) {
+    let violin_colors = ViolinColors::new(all_curves);
     let all_curves_vec = all_curves.iter().rev().cloned().collect::<Vec<_>>();
     let all_curves: &[&(&BenchmarkId, Vec<f64>)] = &*all_curves_vec;
 
     let mut kdes = all_curves
         .iter()
         .map(|&&(id, ref sample)| {
-            let (x, mut y) = kde::sweep(Sample::new(sample), KDE_POINTS, None);
+            let (x, mut y) = kde::sweep(
+                Sample::new(sample),
+                kde_bandwidth(conf, |_| {}),
+                conf.kde_points,
+                None,
+            );
             let y_max = Sample::new(&y).max();
             for y in y.iter_mut() {
                 *y /= y_max;
             }
+            let ticks = violin_ticks(sample, percentiles, &x, &y);
 
-            (id.as_title(), x, y)
+            (id.as_title(), x, y, ticks)
         })
         .collect::<Vec<_>>();
 
     let mut xs = kdes
         .iter()
-        .flat_map(|&(_, ref x, _)| x.iter())
+        .flat_map(|(_, x, _, _)| x.iter())
         .filter(|&&x| x > 0.);
     let (mut min, mut max) = {
         let &first = xs.next().unwrap();
@@ -191,36 +437,70 @@ pub fn violin(
     }
     let mut dummy = [1.0];
     let unit = formatter.scale_values(max, &mut dummy);
-    kdes.iter_mut().for_each(|&mut (_, ref mut xs, _)| {
-        formatter.scale_values(max, xs);
-    });
+    kdes.iter_mut()
+        .for_each(|&mut (_, ref mut xs, _, ref mut ticks)| {
+            formatter.scale_values(max, xs);
+            for (tick, _) in ticks.iter_mut() {
+                let mut scaled = [*tick];
+                formatter.scale_values(max, &mut scaled);
+                *tick = scaled[0];
+            }
+        });
 
-    let mut x_range = plotters::data::fitting_range(kdes.iter().flat_map(|(_, xs, _)| xs.iter()));
+    let mut x_range =
+        plotters::data::fitting_range(kdes.iter().flat_map(|(_, xs, _, _)| xs.iter()));
     x_range.start = 0.0;
     let y_range = -0.5..all_curves.len() as f64 - 0.5;
 
-    let size = (960, 150 + (18 * all_curves.len() as u32));
+    // By default, the violin plot grows with the number of benchmarks.
+    let size = conf.size.map_or_else(
+        || (960, 150 + (18 * all_curves.len() as u32)),
+        |(width, height)| (width as u32, height as u32),
+    );
 
-    let root_area = SVGBackend::new(&path, size)
-        .into_drawing_area()
+    let root_area = svg_area(&path, size, conf)
         .titled(&format!("{}: Violin plot", title), (DEFAULT_FONT, 20))
         .unwrap();
 
-    match axis_scale {
-        AxisScale::Linear => draw_violin_figure(root_area, unit, x_range, y_range, kdes),
-        AxisScale::Logarithmic => {
-            draw_violin_figure(root_area, unit, x_range.log_scale(), y_range, kdes)
+    let count = violin_colors.indices.iter().max().map_or(0, |&i| i + 1);
+    let palette = if violin_colors.by_function {
+        comparison_colors(conf, count)
+    } else {
+        palette_colors(conf, count, &[DARK_BLUE])
+    };
+    // The violins are drawn bottom up, in reverse.
+    let colors: Vec<_> = violin_colors
+        .indices
+        .iter()
+        .zip(&violin_colors.labels)
+        .rev()
+        .map(|(&i, &label)| (palette[i], label))
+        .collect();
+    match conf.x_scale {
+        AxisScale::Linear => {
+            draw_violin_figure(root_area, unit, x_range, y_range, kdes, &colors, conf)
         }
+        AxisScale::Logarithmic => draw_violin_figure(
+            root_area,
+            unit,
+            x_range.log_scale(),
+            y_range,
+            kdes,
+            &colors,
+            conf,
+        ),
     }
 }
 
 #[allow(clippy::type_complexity)]
 fn draw_violin_figure<XR: AsRangedCoord<Value = f64>, YR: AsRangedCoord<Value = f64>>(
-    root_area: DrawingArea<SVGBackend, Shift>,
+    root_area: DrawingArea<ThemedSvg, Shift>,
     unit: &'static str,
     x_range: XR,
     y_range: YR,
-    data: Vec<(&str, Box<[f64]>, Box<[f64]>)>,
+    data: Vec<(&str, Box<[f64]>, Box<[f64]>, Vec<(f64, f64)>)>,
+    colors: &[(RGBColor, Option<&str>)],
+    conf: &PlotConfiguration,
 ) where
     XR::CoordDescType: PlottersValueFormatter<f64>,
     YR::CoordDescType: PlottersValueFormatter<f64>,
@@ -243,23 +523,224 @@ fn draw_violin_figure<XR: AsRangedCoord<Value = f64>, YR: AsRangedCoord<Value =
         .draw()
         .unwrap();
 
-    for (i, (_, x, y)) in data.into_iter().enumerate() {
+    // Drawn from the top, so that the legend lists the functions in order.
+    for (i, (_, x, y, ticks)) in data.into_iter().enumerate().rev() {
         let base = i as f64;
+        let (color, label) = colors[i];
 
-        chart
+        let series = chart
             .draw_series(AreaSeries::new(
                 x.iter().zip(y.iter()).map(|(x, y)| (*x, base + *y / 2.0)),
                 base,
-                &DARK_BLUE,
+                color,
             ))
             .unwrap();
+        if let Some(label) = label {
+            series.label(label).legend(move |(x, y)| {
+                Rectangle::new([(x, y - 5), (x + 20, y + 5)], color.filled())
+            });
+        }
 
         chart
             .draw_series(AreaSeries::new(
                 x.iter().zip(y.iter()).map(|(x, y)| (*x, base - *y / 2.0)),
                 base,
-                &DARK_BLUE,
+                color,
             ))
             .unwrap();
+        chart
+            .draw_series(ticks.into_iter().map(|(x, height)| {
+                PathElement::new(
+                    vec![(x, base - height / 2.0), (x, base + height / 2.0)],
+                    BLACK.stroke_width(2),
+                )
+            }))
+            .unwrap();
+    }
+
+    if colors.iter().any(|&(_, label)| label.is_some()) {
+        chart
+            .configure_series_labels()
+            .position(legend_position(conf, SeriesLabelPosition::UpperRight))
+            .draw()
+            .unwrap();
+    }
+}
+
+pub fn box_plot(
+    formatter: &dyn ValueFormatter,
+    title: &str,
+    all_curves: &[&(&BenchmarkId, Vec<f64>)],
+    path: &Path,
+    conf: &PlotConfiguration,
+) {
+    let (mut boxes, unit) = box_plots(formatter, all_curves);
+    let max = boxes
+        .iter()
+        .flat_map(|stats| stats.outliers.iter().chain(Some(&stats.whisker_high)))
+        .fold(0., |max: f64, &x| max.max(x));
+    let x_range = 0.0..max * 1.05;
+    let y_range = -0.5..all_curves.len() as f64 - 0.5;
+
+    // Sized like the violin plot.
+    let size = conf.size.map_or_else(
+        || (960, 150 + (18 * all_curves.len() as u32)),
+        |(width, height)| (width as u32, height as u32),
+    );
+
+    let root_area = svg_area(&path, size, conf)
+        .titled(&format!("{}: Box plot", title), (DEFAULT_FONT, 20))
+        .unwrap();
+
+    // Like the violins, the boxes are drawn bottom up, in reverse.
+    let mut names: Vec<_> = all_curves.iter().map(|&&(id, _)| id.as_title()).collect();
+    let mut colors = palette_colors(conf, boxes.len(), &[DARK_BLUE]);
+    names.reverse();
+    boxes.reverse();
+    colors.reverse();
+    match conf.x_scale {
+        AxisScale::Linear => {
+            draw_box_plot_figure(root_area, unit, x_range, y_range, &names, &boxes, &colors)
+        }
+        AxisScale::Logarithmic => draw_box_plot_figure(
+            root_area,
+            unit,
+            x_range.log_scale(),
+            y_range,
+            &names,
+            &boxes,
+            &colors,
+        ),
+    }
+}
+
+fn draw_box_plot_figure<XR: AsRangedCoord<Value = f64>, YR: AsRangedCoord<Value = f64>>(
+    root_area: DrawingArea<ThemedSvg, Shift>,
+    unit: &'static str,
+    x_range: XR,
+    y_range: YR,
+    names: &[&str],
+    boxes: &[BoxStats],
+    colors: &[RGBColor],
+) where
+    XR::CoordDescType: PlottersValueFormatter<f64>,
+    YR::CoordDescType: PlottersValueFormatter<f64>,
+{
+    let mut chart = ChartBuilder::on(&root_area)
+        .margin((5).percent())
+        .set_label_area_size(LabelAreaPosition::Left, (10).percent_width().min(60))
+        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_width().min(40))
+        .build_cartesian_2d(x_range, y_range)
+        .unwrap();
+
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .y_desc("Input")
+        .x_desc(format!("Average time ({})", unit))
+        .y_label_style((DEFAULT_FONT, 10))
+        .y_label_formatter(&|v: &f64| names[v.round() as usize].to_string())
+        .y_labels(names.len())
+        .draw()
+        .unwrap();
+
+    for (i, (stats, &color)) in boxes.iter().zip(colors).enumerate() {
+        let y = i as f64;
+        chart
+            .draw_series(vec![
+                Rectangle::new(
+                    [(stats.q1, y - 0.3), (stats.q3, y + 0.3)],
+                    color.mix(0.5).filled(),
+                ),
+                Rectangle::new(
+                    [(stats.q1, y - 0.3), (stats.q3, y + 0.3)],
+                    color.stroke_width(1),
+                ),
+            ])
+            .unwrap();
+        chart
+            .draw_series(vec![
+                PathElement::new(
+                    vec![(stats.median, y - 0.3), (stats.median, y + 0.3)],
+                    color.stroke_width(2),
+                ),
+                PathElement::new(
+                    vec![(stats.whisker_low, y), (stats.q1, y)],
+                    color.stroke_width(1),
+                ),
+                PathElement::new(
+                    vec![(stats.q3, y), (stats.whisker_high, y)],
+                    color.stroke_width(1),
+                ),
+            ])
+            .unwrap();
+        chart
+            .draw_series(
+                stats
+                    .outliers
+                    .iter()
+                    .map(|&x| Circle::new((x, y), POINT_SIZE, color.filled())),
+            )
+            .unwrap();
     }
 }
+
+pub fn speedup_summary(
+    title: &str,
+    speedups: &[(String, f64)],
+    path: &Path,
+    conf: &PlotConfiguration,
+    size: Option<(u32, u32)>,
+) {
+    let max = speedups
+        .iter()
+        .map(|&(_, speedup)| speedup)
+        .fold(1., f64::max);
+    let n = speedups.len() as f64;
+
+    let root_area = svg_area(&path, size.unwrap_or(SIZE), conf)
+        .titled(
+            &format!("{}: Geometric mean speedup", title),
+            (DEFAULT_FONT, 20),
+        )
+        .unwrap();
+
+    let mut chart = ChartBuilder::on(&root_area)
+        .margin((5).percent())
+        .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
+        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
+        .build_cartesian_2d(-0.5..n - 0.5, 0.0..max * 1.1)
+        .unwrap();
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .y_desc(format!("Speedup over {}", conf.speedup_id))
+        .x_labels(speedups.len())
+        .x_label_formatter(&|x: &f64| {
+            speedups
+                .get(x.round() as usize)
+                .map(|(name, _)| name.clone())
+                .unwrap_or_default()
+        })
+        .draw()
+        .unwrap();
+
+    let colors = comparison_colors(conf, speedups.len());
+    chart
+        .draw_series(
+            speedups
+                .iter()
+                .zip(&colors)
+                .enumerate()
+                .map(|(i, (&(_, speedup), color))| {
+                    let x = i as f64;
+                    Rectangle::new([(x - 0.4, 0.0), (x + 0.4, speedup)], color.filled())
+                }),
+        )
+        .unwrap();
+    // The reference itself
+    chart
+        .draw_series(LineSeries::new(vec![(-0.5, 1.0), (n - 0.5, 1.0)], &BLACK))
+        .unwrap();
+}
@@ -1,4 +1,5 @@
 use super::*;
+use crate::plot::test_labels;
 use crate::report::ComparisonData;
 use std::path::Path;
 
@@ -7,20 +8,27 @@ pub(crate) fn t_test(
     title: &str,
     comparison: &ComparisonData,
     size: Option<(u32, u32)>,
+    conf: &PlotConfiguration,
 ) {
     let t = comparison.t_value;
-    let (xs, ys) = kde::sweep(&comparison.t_distribution, KDE_POINTS, None);
+    let labels = test_labels(comparison);
+    let (xs, ys) = kde::sweep(
+        &comparison.t_distribution,
+        Bandwidth::Silverman,
+        conf.kde_points,
+        None,
+    );
 
     let x_range = plotters::data::fitting_range(xs.iter());
     let mut y_range = plotters::data::fitting_range(ys.iter());
     y_range.start = 0.0;
     y_range.end *= 1.1;
 
-    let root_area = SVGBackend::new(&path, size.unwrap_or(SIZE)).into_drawing_area();
+    let root_area = svg_area(&path, size.unwrap_or(SIZE), conf);
 
     let mut chart = ChartBuilder::on(&root_area)
         .margin((5).percent())
-        .caption(format!("{}: Welch t test", title), (DEFAULT_FONT, 20))
+        .caption(format!("{}: {}", title, labels.title), (DEFAULT_FONT, 20))
         .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
         .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
         .build_cartesian_2d(x_range, y_range.clone())
@@ -30,7 +38,7 @@ pub(crate) fn t_test(
         .configure_mesh()
         .disable_mesh()
         .y_desc("Density")
-        .x_desc("t score")
+        .x_desc(labels.axis)
         .draw()
         .unwrap();
 
@@ -41,7 +49,7 @@ pub(crate) fn t_test(
             &DARK_BLUE.mix(0.25),
         ))
         .unwrap()
-        .label("t distribution")
+        .label(labels.distribution)
         .legend(|(x, y)| {
             Rectangle::new([(x, y - 5), (x + 20, y + 5)], DARK_BLUE.mix(0.25).filled())
         });
@@ -52,8 +60,12 @@ pub(crate) fn t_test(
             DARK_BLUE.filled().stroke_width(2),
         )))
         .unwrap()
-        .label("t statistic")
+        .label(labels.statistic)
         .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &DARK_BLUE));
 
-    chart.configure_series_labels().draw().unwrap();
+    chart
+        .configure_series_labels()
+        .position(legend_position(conf, SeriesLabelPosition::MiddleRight))
+        .draw()
+        .unwrap();
 }
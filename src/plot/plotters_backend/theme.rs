@@ -0,0 +1,268 @@
+//! Draws the plots in the colors of `Criterion::plot_theme` and the fonts of the
+//! `PlotConfiguration`.
+
+use std::path::Path;
+
+use plotters::coord::Shift;
+use plotters::prelude::{DrawingArea, IntoDrawingArea, RGBColor, SVGBackend};
+use plotters_backend::{
+    text_anchor, BackendColor, BackendCoord, BackendStyle, BackendTextStyle, DrawingBackend,
+    DrawingErrorKind, FontFamily, FontStyle, FontTransform,
+};
+
+use crate::plot::{DARK_BACKGROUND, DARK_FOREGROUND};
+use crate::{PlotConfiguration, Theme};
+
+/// The size of the text of the plots which `PlotConfiguration::font_size` replaces; the other
+/// sizes are scaled in proportion.
+const BASE_FONT_SIZE: f64 = 12.;
+
+/// An SVG backend which draws black, the color plotters uses for the text, the axes and the
+/// gridlines, in the foreground color of the theme. The other colors are left as they are. The
+/// text is drawn in the font family of the configuration, and its size is scaled to the font size.
+pub(crate) struct ThemedSvg<'a> {
+    svg: SVGBackend<'a>,
+    foreground: Option<(u8, u8, u8)>,
+    font: Font,
+}
+
+/// The font family and the scale of the text sizes.
+struct Font {
+    family: Option<String>,
+    scale: f64,
+}
+
+impl Font {
+    fn text_style<'s, T: BackendTextStyle>(
+        &'s self,
+        style: &'s T,
+        color: BackendColor,
+    ) -> TextStyle<'s, T> {
+        TextStyle {
+            style,
+            color,
+            family: self.family.as_deref(),
+            size: style.size() * self.scale,
+            scale: self.scale,
+        }
+    }
+}
+
+/// Creates the drawing area of a plot written to `path`, with the background of the theme of
+/// `conf`.
+pub(crate) fn svg_area<'a, P: AsRef<Path> + ?Sized>(
+    path: &'a P,
+    size: (u32, u32),
+    conf: &PlotConfiguration,
+) -> DrawingArea<ThemedSvg<'a>, Shift> {
+    let theme = conf.theme;
+    let foreground = match theme {
+        Theme::Light => None,
+        Theme::Dark => Some(DARK_FOREGROUND),
+    };
+    let area = ThemedSvg {
+        svg: SVGBackend::new(path, size),
+        foreground,
+        font: Font {
+            family: conf.font_family.clone(),
+            scale: conf.font_size.map_or(1., |size| size / BASE_FONT_SIZE),
+        },
+    }
+    .into_drawing_area();
+    if theme == Theme::Dark {
+        let (r, g, b) = DARK_BACKGROUND;
+        area.fill(&RGBColor(r, g, b)).unwrap();
+    }
+    area
+}
+
+impl<'a> ThemedSvg<'a> {
+    fn color(&self, color: BackendColor) -> BackendColor {
+        match self.foreground {
+            Some(rgb) if color.rgb == (0, 0, 0) => BackendColor {
+                alpha: color.alpha,
+                rgb,
+            },
+            _ => color,
+        }
+    }
+
+    fn style<S: BackendStyle>(&self, style: &S) -> Style {
+        Style {
+            color: self.color(style.color()),
+            stroke_width: style.stroke_width(),
+        }
+    }
+}
+
+struct Style {
+    color: BackendColor,
+    stroke_width: u32,
+}
+
+impl BackendStyle for Style {
+    fn color(&self) -> BackendColor {
+        self.color
+    }
+
+    fn stroke_width(&self) -> u32 {
+        self.stroke_width
+    }
+}
+
+/// A text style with another color, size and possibly font family.
+struct TextStyle<'s, T> {
+    style: &'s T,
+    color: BackendColor,
+    family: Option<&'s str>,
+    size: f64,
+    scale: f64,
+}
+
+impl<'s, T: BackendTextStyle> BackendTextStyle for TextStyle<'s, T> {
+    type FontError = T::FontError;
+
+    fn color(&self) -> BackendColor {
+        self.color
+    }
+
+    fn size(&self) -> f64 {
+        self.size
+    }
+
+    fn transform(&self) -> FontTransform {
+        self.style.transform()
+    }
+
+    fn style(&self) -> FontStyle {
+        self.style.style()
+    }
+
+    fn anchor(&self) -> text_anchor::Pos {
+        self.style.anchor()
+    }
+
+    fn family(&self) -> FontFamily<'_> {
+        match self.family {
+            Some(family) => FontFamily::Name(family),
+            None => self.style.family(),
+        }
+    }
+
+    fn layout_box(&self, text: &str) -> Result<((i32, i32), (i32, i32)), Self::FontError> {
+        // The box is measured in the original font, scaled to the new size.
+        let scale = |x: i32| (f64::from(x) * self.scale).round() as i32;
+        let ((x0, y0), (x1, y1)) = self.style.layout_box(text)?;
+        Ok(((scale(x0), scale(y0)), (scale(x1), scale(y1))))
+    }
+
+    fn draw<E, DrawFunc: FnMut(i32, i32, BackendColor) -> Result<(), E>>(
+        &self,
+        text: &str,
+        pos: BackendCoord,
+        draw: DrawFunc,
+    ) -> Result<Result<(), E>, Self::FontError> {
+        self.style.draw(text, pos, draw)
+    }
+}
+
+type Error<'a> = DrawingErrorKind<<SVGBackend<'a> as DrawingBackend>::ErrorType>;
+
+impl<'a> DrawingBackend for ThemedSvg<'a> {
+    type ErrorType = <SVGBackend<'a> as DrawingBackend>::ErrorType;
+
+    fn get_size(&self) -> (u32, u32) {
+        self.svg.get_size()
+    }
+
+    fn ensure_prepared(&mut self) -> Result<(), Error<'a>> {
+        self.svg.ensure_prepared()
+    }
+
+    fn present(&mut self) -> Result<(), Error<'a>> {
+        self.svg.present()
+    }
+
+    fn draw_pixel(&mut self, point: BackendCoord, color: BackendColor) -> Result<(), Error<'a>> {
+        let color = self.color(color);
+        self.svg.draw_pixel(point, color)
+    }
+
+    fn draw_line<S: BackendStyle>(
+        &mut self,
+        from: BackendCoord,
+        to: BackendCoord,
+        style: &S,
+    ) -> Result<(), Error<'a>> {
+        let style = self.style(style);
+        self.svg.draw_line(from, to, &style)
+    }
+
+    fn draw_rect<S: BackendStyle>(
+        &mut self,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), Error<'a>> {
+        let style = self.style(style);
+        self.svg.draw_rect(upper_left, bottom_right, &style, fill)
+    }
+
+    fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        path: I,
+        style: &S,
+    ) -> Result<(), Error<'a>> {
+        let style = self.style(style);
+        self.svg.draw_path(path, &style)
+    }
+
+    fn draw_circle<S: BackendStyle>(
+        &mut self,
+        center: BackendCoord,
+        radius: u32,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), Error<'a>> {
+        let style = self.style(style);
+        self.svg.draw_circle(center, radius, &style, fill)
+    }
+
+    fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        vert: I,
+        style: &S,
+    ) -> Result<(), Error<'a>> {
+        let style = self.style(style);
+        self.svg.fill_polygon(vert, &style)
+    }
+
+    fn draw_text<T: BackendTextStyle>(
+        &mut self,
+        text: &str,
+        style: &T,
+        pos: BackendCoord,
+    ) -> Result<(), Error<'a>> {
+        let style = self.font.text_style(style, self.color(style.color()));
+        self.svg.draw_text(text, &style, pos)
+    }
+
+    fn estimate_text_size<T: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &T,
+    ) -> Result<(u32, u32), Error<'a>> {
+        let style = self.font.text_style(style, self.color(style.color()));
+        self.svg.estimate_text_size(text, &style)
+    }
+
+    fn blit_bitmap(
+        &mut self,
+        pos: BackendCoord,
+        size: (u32, u32),
+        src: &[u8],
+    ) -> Result<(), Error<'a>> {
+        self.svg.blit_bitmap(pos, size, src)
+    }
+}
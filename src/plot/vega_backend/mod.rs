@@ -0,0 +1,895 @@
+//! Interactive plots (`PlottingBackend::Vega`).
+//!
+//! Every plot is written as a small HTML page holding a
+//! [Vega-Lite](https://vega.github.io/vega-lite/) specification, which the browser renders with the
+//! vega-embed library. The charts show the exact values under the cursor, zoom and pan with the
+//! mouse wheel and by dragging, and the series of the line comparison plot are toggled by clicking
+//! their legend. The libraries are loaded from a CDN, so viewing the plots needs network access.
+//! Their exact versions are pinned, so that the plots look the same however long ago they were
+//! drawn.
+//!
+//! The HTML report embeds the pages in frames. Clicking a thumbnail opens the full plot.
+
+use serde_json::{json, Value};
+
+use super::{
+    change_label, drift_series, ecdf, kde_bandwidth, test_labels, PlotContext, PlotData, Plotter,
+    CHANGE_STATS, DARK_BACKGROUND, DARK_FOREGROUND, REPORT_STATS,
+};
+use crate::estimate::ConfidenceInterval;
+use crate::kde::{self, Bandwidth};
+use crate::measurement::ValueFormatter;
+use crate::report::{BenchmarkId, SignificantChange, ValueType};
+use crate::stats::univariate::outliers::tukey::Label;
+use crate::stats::univariate::Sample;
+use crate::{fs, Corner, LegendPosition, PlotConfiguration, PlotFormat, Theme};
+
+mod summary;
+
+const SCRIPTS: [&str; 3] = [
+    "https://cdn.jsdelivr.net/npm/vega@5.25.0/build/vega.min.js",
+    "https://cdn.jsdelivr.net/npm/vega-lite@5.16.3/build/vega-lite.min.js",
+    "https://cdn.jsdelivr.net/npm/vega-embed@6.22.2/build/vega-embed.min.js",
+];
+pub(crate) const SIZE: (usize, usize) = (960, 540);
+
+const DARK_BLUE: &str = "#1f78b4";
+const DARK_ORANGE: &str = "#ff7f00";
+const DARK_RED: &str = "#e31a1c";
+const DARK_GREEN: &str = "#33a02c";
+
+pub(crate) struct Vega;
+
+impl Plotter for Vega {
+    fn pdf(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>) {
+        let measurements = data.measurements;
+        let (name, link) = match (data.comparison.is_some(), ctx.is_thumbnail) {
+            (true, true) => ("relative_pdf_small", Some("both/pdf.html")),
+            (true, false) => ("both/pdf", None),
+            (false, true) => ("pdf_small", Some("pdf.html")),
+            (false, false) => ("pdf", None),
+        };
+        let mut samples = vec![("New", &**measurements.avg_times)];
+        if let Some(cmp) = data.comparison {
+            samples.insert(0, ("Base", &cmp.base_avg_times[..]));
+        }
+        let typical = samples
+            .iter()
+            .map(|&(_, times)| Sample::new(times).max())
+            .fold(f64::NAN, f64::max);
+        let unit = data.formatter.scale_values(typical, &mut [1.0]);
+
+        let conf = &ctx.context.plot_config;
+        let bandwidth = kde_bandwidth(conf, |h| {
+            data.formatter.scale_values(typical, h);
+        });
+        let mut densities = vec![];
+        let mut means = vec![];
+        for &(sample, times) in &samples {
+            let mut times = times.to_vec();
+            data.formatter.scale_values(typical, &mut times);
+            let times = Sample::new(&times);
+            let (xs, ys) = kde::sweep(times, bandwidth, conf.kde_points, None);
+            densities.extend(
+                xs.iter()
+                    .zip(ys.iter())
+                    .map(|(&x, &y)| json!({"sample": sample, "time": x, "density": y})),
+            );
+            means.push(json!({"sample": sample, "mean": times.mean()}));
+        }
+        let mut outliers: Vec<f64> = measurements.avg_times.iter().map(|(x, _)| x).collect();
+        data.formatter.scale_values(typical, &mut outliers);
+        let points: Vec<Value> = outliers
+            .iter()
+            .zip(measurements.avg_times.iter())
+            .enumerate()
+            .map(|(i, (&time, (_, label)))| {
+                json!({"sample": i + 1, "time": time, "label": outlier_label(label)})
+            })
+            .collect();
+
+        let time_title = format!("Average time ({})", unit);
+        let color = sample_color(data.comparison.is_some());
+        let mut layers = vec![
+            json!({
+                "data": {"values": densities},
+                "params": [zoom()],
+                "mark": {"type": "area", "opacity": 0.25},
+                "encoding": {
+                    "x": {"field": "time", "type": "quantitative", "title": time_title},
+                    "y": {"field": "density", "type": "quantitative", "title": "Density (a.u.)"},
+                    "color": color,
+                },
+            }),
+            json!({
+                "data": {"values": means},
+                "mark": "rule",
+                "encoding": {
+                    "x": {"field": "mean", "type": "quantitative"},
+                    "color": color,
+                    "tooltip": [
+                        {"field": "sample", "title": "Sample"},
+                        {"field": "mean", "title": format!("Mean ({})", unit), "format": ".4~f"},
+                    ],
+                },
+            }),
+        ];
+        if data.comparison.is_none() {
+            layers.push(json!({
+                "data": {"values": points},
+                "mark": {"type": "point", "filled": true},
+                "encoding": {
+                    "x": {"field": "time", "type": "quantitative"},
+                    "y": {"datum": 0},
+                    "color": {
+                        "field": "label",
+                        "title": "Sample",
+                        "scale": {
+                            "domain": ["Not an outlier", "Mild outlier", "Severe outlier"],
+                            "range": [DARK_BLUE, DARK_ORANGE, DARK_RED],
+                        },
+                    },
+                    "tooltip": [
+                        {"field": "sample", "title": "Sample"},
+                        {"field": "time", "title": format!("Time ({})", unit), "format": ".4~f"},
+                        {"field": "label", "title": "Outlier"},
+                    ],
+                },
+            }));
+        }
+
+        let title = (!ctx.is_thumbnail).then(|| ctx.id.as_title());
+        let spec = json!({"layer": layers, "resolve": {"scale": {"color": "independent"}}});
+        save(ctx, name, title, spec, link);
+    }
+
+    fn cdf(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>) {
+        let (ecdf, unit) = ecdf(data.formatter, data.measurements);
+        let steps: Vec<Value> = ecdf
+            .xs
+            .iter()
+            .zip(&ecdf.ys)
+            .map(|(&x, &y)| json!({"time": x, "fraction": y}))
+            .collect();
+        let percentiles: Vec<Value> = ecdf
+            .percentiles
+            .iter()
+            .map(|(x, label, _)| json!({"time": x, "percentile": label}))
+            .collect();
+        let labels: Vec<&str> = ecdf
+            .percentiles
+            .iter()
+            .map(|(_, label, _)| label.as_str())
+            .collect();
+        let colors: Vec<String> = ecdf
+            .percentiles
+            .iter()
+            .map(|&(_, _, rgb)| hex_color(rgb))
+            .collect();
+
+        let time_title = format!("Average time ({})", unit);
+        let spec = json!({
+            "layer": [
+                {
+                    "data": {"values": steps},
+                    "params": [zoom()],
+                    "mark": {"type": "line", "interpolate": "step-after", "color": DARK_BLUE},
+                    "encoding": {
+                        "x": {"field": "time", "type": "quantitative", "title": time_title},
+                        "y": {
+                            "field": "fraction",
+                            "type": "quantitative",
+                            "title": "Cumulative probability",
+                            "scale": {"domain": [0, 1]},
+                        },
+                        "tooltip": [
+                            {"field": "time", "title": format!("Time ({})", unit), "format": ".4~f"},
+                            {"field": "fraction", "title": "Fraction", "format": ".3~f"},
+                        ],
+                    },
+                },
+                {
+                    "data": {"values": percentiles},
+                    "mark": {"type": "rule", "strokeDash": [6, 4], "size": 2},
+                    "encoding": {
+                        "x": {"field": "time", "type": "quantitative"},
+                        "color": {
+                            "field": "percentile",
+                            "title": "Percentile",
+                            "scale": {"domain": labels, "range": colors},
+                        },
+                        "tooltip": {"field": "percentile"},
+                    },
+                },
+            ],
+        });
+        save(ctx, "cdf", Some(ctx.id.as_title()), spec, None);
+    }
+
+    fn drift(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>) {
+        let (series, unit) = match drift_series(data.formatter, data.measurements) {
+            Some(series) => series,
+            None => return,
+        };
+        let points: Vec<Value> = series
+            .ys
+            .iter()
+            .enumerate()
+            .map(|(i, &time)| json!({"index": i + 1, "time": time}))
+            .collect();
+        let trend = json!([
+            {"index": 1, "time": series.trend[0]},
+            {"index": series.ys.len(), "time": series.trend[1]},
+        ]);
+
+        let time_title = format!("Time per iteration ({})", unit);
+        let spec = json!({
+            "layer": [
+                {
+                    "data": {"values": points},
+                    "params": [zoom()],
+                    "mark": {"type": "point", "filled": true, "color": DARK_BLUE},
+                    "encoding": {
+                        "x": {
+                            "field": "index",
+                            "type": "quantitative",
+                            "title": "Sample, in the order measured",
+                        },
+                        "y": {"field": "time", "type": "quantitative", "title": time_title},
+                        "tooltip": [
+                            {"field": "index", "title": "Sample"},
+                            {"field": "time", "title": format!("Time ({})", unit), "format": ".4~f"},
+                        ],
+                    },
+                },
+                {
+                    "data": {"values": trend},
+                    "mark": {"type": "line", "color": DARK_RED, "strokeWidth": 2},
+                    "encoding": {
+                        "x": {"field": "index", "type": "quantitative"},
+                        "y": {"field": "time", "type": "quantitative"},
+                        "tooltip": {"value": series.trend_label},
+                    },
+                },
+            ],
+        });
+        save(ctx, "drift", Some(ctx.id.as_title()), spec, None);
+    }
+
+    fn regression(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>) {
+        let (name, link) = match (data.comparison.is_some(), ctx.is_thumbnail) {
+            (true, true) => ("relative_regression_small", Some("both/regression.html")),
+            (true, false) => ("both/regression", None),
+            (false, true) => ("regression_small", Some("regression.html")),
+            (false, false) => ("regression", None),
+        };
+        let measurements = data.measurements;
+        let mut samples = vec![(
+            "New",
+            &measurements.data.x()[..],
+            &measurements.data.y()[..],
+            measurements.absolute_estimates.slope.as_ref(),
+        )];
+        if let Some(cmp) = data.comparison {
+            samples.insert(
+                0,
+                (
+                    "Base",
+                    &cmp.base_iter_counts[..],
+                    &cmp.base_sample_times[..],
+                    cmp.base_estimates.slope.as_ref(),
+                ),
+            );
+        }
+        let typical = samples
+            .iter()
+            .map(|&(_, _, times, _)| Sample::new(times).max())
+            .fold(f64::NAN, f64::max);
+        let unit = data.formatter.scale_values(typical, &mut [1.0]);
+
+        let mut points = vec![];
+        let mut fits = vec![];
+        for &(sample, iters, times, slope) in &samples {
+            let mut times = times.to_vec();
+            data.formatter.scale_values(typical, &mut times);
+            points.extend(iters.iter().zip(&times).map(
+                |(&iters, &time)| json!({"sample": sample, "iterations": iters, "time": time}),
+            ));
+            if let Some(slope) = slope {
+                let max_iters = Sample::new(iters).max();
+                let mut ends = [
+                    slope.point_estimate * max_iters,
+                    slope.confidence_interval.lower_bound * max_iters,
+                    slope.confidence_interval.upper_bound * max_iters,
+                ];
+                data.formatter.scale_values(typical, &mut ends);
+                fits.push(
+                    json!({"sample": sample, "iterations": 0, "time": 0, "lower": 0, "upper": 0}),
+                );
+                fits.push(json!({
+                    "sample": sample,
+                    "iterations": max_iters,
+                    "time": ends[0],
+                    "lower": ends[1],
+                    "upper": ends[2],
+                }));
+            }
+        }
+
+        let time_title = format!("Total sample time ({})", unit);
+        let color = sample_color(data.comparison.is_some());
+        // The end of the fit of the new sample, which is pushed last.
+        let marker = data
+            .comparison
+            .and_then(|cmp| Some((cmp.significant_change()?, change_label(cmp)?)))
+            .zip(fits.last().cloned());
+        let mut spec = json!({
+            "layer": [
+                {
+                    "data": {"values": points},
+                    "params": [zoom()],
+                    "mark": {"type": "point", "filled": true},
+                    "encoding": {
+                        "x": {"field": "iterations", "type": "quantitative", "title": "Iterations"},
+                        "y": {"field": "time", "type": "quantitative", "title": time_title},
+                        "color": color,
+                        "tooltip": [
+                            {"field": "sample", "title": "Sample"},
+                            {"field": "iterations", "title": "Iterations"},
+                            {"field": "time", "title": format!("Time ({})", unit), "format": ".4~f"},
+                        ],
+                    },
+                },
+                {
+                    "data": {"values": fits.clone()},
+                    "mark": {"type": "area", "opacity": 0.25},
+                    "encoding": {
+                        "x": {"field": "iterations", "type": "quantitative"},
+                        "y": {"field": "lower", "type": "quantitative"},
+                        "y2": {"field": "upper"},
+                        "color": color,
+                    },
+                },
+                {
+                    "data": {"values": fits},
+                    "mark": "line",
+                    "encoding": {
+                        "x": {"field": "iterations", "type": "quantitative"},
+                        "y": {"field": "time", "type": "quantitative"},
+                        "color": color,
+                    },
+                },
+            ],
+        });
+        if let Some(((change, label), mut end)) = marker {
+            end["label"] = json!(label);
+            let (shape, color) = match change {
+                SignificantChange::Improved => ("triangle-down", DARK_GREEN),
+                SignificantChange::Regressed => ("triangle-up", DARK_RED),
+            };
+            let encoding = json!({
+                "x": {"field": "iterations", "type": "quantitative"},
+                "y": {"field": "time", "type": "quantitative"},
+                "tooltip": {"field": "label"},
+            });
+            let mut label_encoding = encoding.clone();
+            label_encoding["text"] = json!({"field": "label"});
+            let layers = spec["layer"].as_array_mut().unwrap();
+            layers.push(json!({
+                "data": {"values": [end.clone()]},
+                "mark": {
+                    "type": "point",
+                    "filled": true,
+                    "opacity": 1,
+                    "size": 120,
+                    "shape": shape,
+                    "color": color,
+                },
+                "encoding": encoding,
+            }));
+            layers.push(json!({
+                "data": {"values": [end]},
+                "mark": {"type": "text", "align": "right", "dx": -12, "color": color},
+                "encoding": label_encoding,
+            }));
+        }
+        let title = (!ctx.is_thumbnail).then(|| ctx.id.as_title());
+        save(ctx, name, title, spec, link);
+    }
+
+    fn iteration_times(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>) {
+        let (name, link) = match (data.comparison.is_some(), ctx.is_thumbnail) {
+            (true, true) => (
+                "relative_iteration_times_small",
+                Some("both/iteration_times.html"),
+            ),
+            (true, false) => ("both/iteration_times", None),
+            (false, true) => ("iteration_times_small", Some("iteration_times.html")),
+            (false, false) => ("iteration_times", None),
+        };
+        let mut samples = vec![("New", &**data.measurements.avg_times)];
+        if let Some(cmp) = data.comparison {
+            samples.insert(0, ("Base", &cmp.base_avg_times[..]));
+        }
+        let typical = samples
+            .iter()
+            .map(|&(_, times)| Sample::new(times).max())
+            .fold(f64::NAN, f64::max);
+        let unit = data.formatter.scale_values(typical, &mut [1.0]);
+
+        let mut points = vec![];
+        for &(sample, times) in &samples {
+            let mut times = times.to_vec();
+            data.formatter.scale_values(typical, &mut times);
+            points.extend(
+                times
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &time)| json!({"sample": sample, "index": i + 1, "time": time})),
+            );
+        }
+
+        let time_title = format!("Average iteration time ({})", unit);
+        let spec = json!({
+            "data": {"values": points},
+            "params": [zoom()],
+            "mark": {"type": "point", "filled": true},
+            "encoding": {
+                "x": {"field": "index", "type": "quantitative", "title": "Sample"},
+                "y": {"field": "time", "type": "quantitative", "title": time_title},
+                "color": sample_color(data.comparison.is_some()),
+                "tooltip": [
+                    {"field": "sample", "title": "Sample"},
+                    {"field": "index", "title": "Index"},
+                    {"field": "time", "title": format!("Time ({})", unit), "format": ".4~f"},
+                ],
+            },
+        });
+        let title = (!ctx.is_thumbnail).then(|| ctx.id.as_title());
+        save(ctx, name, title, spec, link);
+    }
+
+    fn abs_distributions(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>) {
+        let measurements = data.measurements;
+        for &statistic in REPORT_STATS.iter() {
+            let (distribution, estimate) = match (
+                measurements.distributions.get(statistic),
+                measurements.absolute_estimates.get(statistic),
+            ) {
+                (Some(distribution), Some(estimate)) => (distribution, estimate),
+                _ => continue,
+            };
+            let ci = &estimate.confidence_interval;
+            let mut values = [ci.lower_bound, ci.upper_bound, estimate.point_estimate];
+            let unit = data.formatter.scale_values(ci.upper_bound, &mut values);
+            let mut distribution = distribution.to_vec();
+            data.formatter
+                .scale_values(ci.upper_bound, &mut distribution);
+            let spec = distribution_spec(
+                &distribution,
+                values,
+                &format!("Average time ({})", unit),
+                None,
+                ctx.context.plot_config.kde_points,
+            );
+            let title = format!("{}:{}", ctx.id.as_title(), statistic);
+            save(ctx, &statistic.to_string(), Some(&title), spec, None);
+        }
+    }
+
+    fn rel_distributions(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>) {
+        let cmp = match data.comparison {
+            Some(cmp) => cmp,
+            None => {
+                error!("Comparison data is not provided for a relative distribution figure");
+                return;
+            }
+        };
+        for &statistic in CHANGE_STATS.iter() {
+            let estimate = cmp.relative_estimates.get(statistic);
+            let ci = &estimate.confidence_interval;
+            let values = [ci.lower_bound, ci.upper_bound, estimate.point_estimate];
+            let percent = |values: &mut [f64]| values.iter_mut().for_each(|value| *value *= 100.);
+            let mut values = values;
+            percent(&mut values);
+            let mut distribution = cmp.relative_distributions.get(statistic).to_vec();
+            percent(&mut distribution);
+            let spec = distribution_spec(
+                &distribution,
+                values,
+                "Relative change (%)",
+                Some(cmp.noise_threshold * 100.),
+                ctx.context.plot_config.kde_points,
+            );
+            let title = format!("{}:{}", ctx.id.as_title(), statistic);
+            save(
+                ctx,
+                &format!("change/{}", statistic),
+                Some(&title),
+                spec,
+                None,
+            );
+        }
+    }
+
+    fn line_comparison(
+        &mut self,
+        ctx: PlotContext<'_>,
+        formatter: &dyn ValueFormatter,
+        all_curves: &[&(&BenchmarkId, Vec<f64>)],
+        mean_intervals: &[Option<ConfidenceInterval>],
+        changes: &[Option<SignificantChange>],
+        value_type: ValueType,
+    ) {
+        let conf = &ctx.context.plot_config;
+        let spec = summary::line_comparison(
+            formatter,
+            ctx.id.as_title(),
+            all_curves,
+            mean_intervals,
+            changes,
+            value_type,
+            conf,
+        );
+        let title = if conf.label.is_empty() {
+            format!("{}: Comparison", ctx.id.as_title())
+        } else {
+            conf.label.clone()
+        };
+        save(ctx, "lines", Some(&title), spec, None);
+    }
+
+    fn violin(
+        &mut self,
+        ctx: PlotContext<'_>,
+        formatter: &dyn ValueFormatter,
+        all_curves: &[&(&BenchmarkId, Vec<f64>)],
+    ) {
+        let conf = &ctx.context.plot_config;
+        let spec = summary::violin(formatter, all_curves, conf, &ctx.context.percentiles);
+        let title = format!("{}: Violin plot", ctx.id.as_title());
+        let ctx = ctx.size(Some(criterion_plot::Size(
+            conf.size.map_or(SIZE.0, |(width, _)| width),
+            violin_height(conf.size, all_curves.len()),
+        )));
+        save(ctx, "violin", Some(&title), spec, None);
+    }
+
+    fn box_plot(
+        &mut self,
+        ctx: PlotContext<'_>,
+        formatter: &dyn ValueFormatter,
+        all_curves: &[&(&BenchmarkId, Vec<f64>)],
+    ) {
+        let conf = &ctx.context.plot_config;
+        let spec = summary::box_plot(formatter, all_curves, conf);
+        let title = format!("{}: Box plot", ctx.id.as_title());
+        let ctx = ctx.size(Some(criterion_plot::Size(
+            conf.size.map_or(SIZE.0, |(width, _)| width),
+            violin_height(conf.size, all_curves.len()),
+        )));
+        save(ctx, "box_plot", Some(&title), spec, None);
+    }
+
+    fn speedup_summary(&mut self, ctx: PlotContext<'_>, speedups: &[(String, f64)]) {
+        let spec = summary::speedup_summary(speedups, &ctx.context.plot_config);
+        let title = format!("{}: Geometric mean speedup", ctx.id.as_title());
+        save(ctx, "speedup", Some(&title), spec, None);
+    }
+
+    fn t_test(&mut self, ctx: PlotContext<'_>, data: PlotData<'_>) {
+        let cmp = match data.comparison {
+            Some(cmp) => cmp,
+            None => {
+                error!("Comparison data is not provided for t_test plot");
+                return;
+            }
+        };
+        let (xs, ys) = kde::sweep(
+            &cmp.t_distribution,
+            Bandwidth::Silverman,
+            ctx.context.plot_config.kde_points,
+            None,
+        );
+        let labels = test_labels(cmp);
+        let densities: Vec<Value> = xs
+            .iter()
+            .zip(ys.iter())
+            .map(|(&x, &y)| json!({"t": x, "density": y}))
+            .collect();
+        let spec = json!({
+            "layer": [
+                {
+                    "data": {"values": densities},
+                    "params": [zoom()],
+                    "mark": {"type": "area", "opacity": 0.25, "color": DARK_BLUE},
+                    "encoding": {
+                        "x": {"field": "t", "type": "quantitative", "title": labels.axis},
+                        "y": {"field": "density", "type": "quantitative", "title": "Density"},
+                    },
+                },
+                {
+                    "data": {"values": [{"t": cmp.t_value, "p": cmp.p_value}]},
+                    "mark": {"type": "rule", "color": DARK_BLUE, "size": 2},
+                    "encoding": {
+                        "x": {"field": "t", "type": "quantitative"},
+                        "tooltip": [
+                            {"field": "t", "title": labels.statistic, "format": ".4~f"},
+                            {"field": "p", "title": "p value", "format": ".4~f"},
+                        ],
+                    },
+                },
+            ],
+        });
+        let title = format!("{}: {}", ctx.id.as_title(), labels.title);
+        save(ctx, "change/t-test", Some(&title), spec, None);
+    }
+
+    fn supports(&self, format: PlotFormat) -> bool {
+        format == PlotFormat::Html
+    }
+
+    fn default_format(&self) -> PlotFormat {
+        PlotFormat::Html
+    }
+
+    fn wait(&mut self) {}
+}
+
+/// The height of the violin plot of `count` benchmarks, which grows with the number of benchmarks
+/// unless the size of the plots is set.
+pub(crate) fn violin_height(size: Option<(usize, usize)>, count: usize) -> usize {
+    size.map_or(150 + 18 * count, |(_, height)| height)
+}
+
+/// The bootstrap distribution of a statistic: its density, shaded over the confidence interval,
+/// and the point estimate. `values` holds the bounds of the confidence interval and the point
+/// estimate, scaled like the distribution. With a `noise_threshold`, the band of changes within
+/// the noise threshold is shaded as well. The density is evaluated at `kde_points` points.
+fn distribution_spec(
+    distribution: &[f64],
+    values: [f64; 3],
+    x_title: &str,
+    noise_threshold: Option<f64>,
+    kde_points: usize,
+) -> Value {
+    let [lower, upper, point] = values;
+    let margin = (upper - lower) / 9.;
+    let (xs, ys) = kde::sweep(
+        Sample::new(distribution),
+        Bandwidth::Silverman,
+        kde_points,
+        Some((lower - margin, upper + margin)),
+    );
+    let densities: Vec<Value> = xs
+        .iter()
+        .zip(ys.iter())
+        .map(|(&x, &y)| json!({"x": x, "density": y, "ci": lower <= x && x <= upper}))
+        .collect();
+
+    let mut layers = vec![
+        json!({
+            "data": {"values": densities.clone()},
+            "params": [zoom()],
+            "mark": {"type": "line", "color": DARK_BLUE},
+            "encoding": {
+                "x": {"field": "x", "type": "quantitative", "title": x_title},
+                "y": {"field": "density", "type": "quantitative", "title": "Density (a.u.)"},
+            },
+        }),
+        json!({
+            "data": {"values": densities},
+            "transform": [{"filter": "datum.ci"}],
+            "mark": {"type": "area", "opacity": 0.25, "color": DARK_BLUE},
+            "encoding": {
+                "x": {"field": "x", "type": "quantitative"},
+                "y": {"field": "density", "type": "quantitative"},
+            },
+        }),
+        json!({
+            "data": {"values": [{"point": point, "lower": lower, "upper": upper}]},
+            "mark": {"type": "rule", "color": DARK_BLUE, "size": 3},
+            "encoding": {
+                "x": {"field": "point", "type": "quantitative"},
+                "tooltip": [
+                    {"field": "point", "title": "Point estimate", "format": ".4~f"},
+                    {"field": "lower", "title": "Lower bound", "format": ".4~f"},
+                    {"field": "upper", "title": "Upper bound", "format": ".4~f"},
+                ],
+            },
+        }),
+    ];
+    if let Some(noise_threshold) = noise_threshold {
+        layers.push(json!({
+            "data": {"values": [{"from": -noise_threshold, "to": noise_threshold}]},
+            "mark": {"type": "rect", "opacity": 0.1, "color": DARK_RED},
+            "encoding": {
+                "x": {"field": "from", "type": "quantitative"},
+                "x2": {"field": "to"},
+                "tooltip": [{"field": "to", "title": "Noise threshold (%)", "format": ".4~f"}],
+            },
+        }));
+    }
+    json!({ "layer": layers })
+}
+
+/// Colors the base and new samples of comparison plots differently.
+fn sample_color(comparison: bool) -> Value {
+    if comparison {
+        json!({
+            "field": "sample",
+            "title": "Sample",
+            "scale": {"domain": ["Base", "New"], "range": [DARK_RED, DARK_BLUE]},
+        })
+    } else {
+        json!({"value": DARK_BLUE})
+    }
+}
+
+/// Zooms and pans the scales of the plot with the mouse.
+fn zoom() -> Value {
+    json!({"name": "zoom", "select": "interval", "bind": "scales"})
+}
+
+/// Formats `rgb` as a CSS color.
+fn hex_color((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// The color of the text, axes and reference lines in `theme`.
+fn foreground(theme: Theme) -> String {
+    match theme {
+        Theme::Light => "black".to_owned(),
+        Theme::Dark => hex_color(DARK_FOREGROUND),
+    }
+}
+
+/// Colors the background, text, axes and gridlines of a chart in the dark theme.
+fn dark_config() -> Value {
+    let background = hex_color(DARK_BACKGROUND);
+    let foreground = hex_color(DARK_FOREGROUND);
+    json!({
+        "background": background,
+        "view": {"stroke": foreground},
+        "title": {"color": foreground},
+        "axis": {
+            "domainColor": foreground,
+            "tickColor": foreground,
+            "gridColor": "#444444",
+            "labelColor": foreground,
+            "titleColor": foreground,
+        },
+        "legend": {"labelColor": foreground, "titleColor": foreground},
+    })
+}
+
+/// The configuration of a chart: the colors of the theme, and the fonts and legend position set in
+/// `conf`. `None` if everything is left at the defaults of Vega-Lite.
+fn chart_config(conf: &PlotConfiguration) -> Option<Value> {
+    let mut config = match conf.theme {
+        Theme::Light => Value::Null,
+        Theme::Dark => dark_config(),
+    };
+    if let Some(family) = &conf.font_family {
+        config["font"] = json!(family);
+    }
+    if let Some(size) = conf.font_size {
+        for part in &["axis", "legend"] {
+            config[*part]["labelFontSize"] = json!(size);
+            config[*part]["titleFontSize"] = json!(size);
+        }
+        // The chart title is larger, in the proportion of the other backends.
+        config["title"]["fontSize"] = json!(size * 20. / 12.);
+    }
+    if let Some(position) = conf.legend_position {
+        config["legend"]["orient"] = json!(legend_orient(position));
+    }
+    if config.is_null() {
+        None
+    } else {
+        Some(config)
+    }
+}
+
+/// The `orient` of a legend at `position`. Vega-Lite places the legends outside of the chart only
+/// beside it, so the top and bottom corners outside are the same.
+fn legend_orient(position: LegendPosition) -> &'static str {
+    match position {
+        LegendPosition::Inside(Corner::TopLeft) => "top-left",
+        LegendPosition::Inside(Corner::TopRight) => "top-right",
+        LegendPosition::Inside(Corner::BottomLeft) => "bottom-left",
+        LegendPosition::Inside(Corner::BottomRight) => "bottom-right",
+        LegendPosition::Outside(Corner::TopLeft) | LegendPosition::Outside(Corner::BottomLeft) => {
+            "left"
+        }
+        LegendPosition::Outside(Corner::TopRight)
+        | LegendPosition::Outside(Corner::BottomRight) => "right",
+    }
+}
+
+fn outlier_label(label: Label) -> &'static str {
+    if label.is_severe() {
+        "Severe outlier"
+    } else if label.is_mild() {
+        "Mild outlier"
+    } else {
+        "Not an outlier"
+    }
+}
+
+/// Writes the page of the plot `name` of the benchmark of `ctx`. Clicking a thumbnail opens the
+/// page `link`, relative to the thumbnail.
+fn save(
+    ctx: PlotContext<'_>,
+    name: &str,
+    title: Option<&str>,
+    mut spec: Value,
+    link: Option<&str>,
+) {
+    let (width, height) = ctx.size.unwrap_or(SIZE);
+    let spec_object = spec.as_object_mut().unwrap();
+    spec_object.insert(
+        "$schema".to_owned(),
+        json!("https://vega.github.io/schema/vega-lite/v5.json"),
+    );
+    spec_object.insert("width".to_owned(), json!(width));
+    spec_object.insert("height".to_owned(), json!(height));
+    spec_object.insert(
+        "autosize".to_owned(),
+        json!({"type": "fit", "contains": "padding"}),
+    );
+    if let Some(title) = title {
+        spec_object.insert("title".to_owned(), json!(title));
+    }
+    if let Some(config) = chart_config(&ctx.context.plot_config) {
+        spec_object.insert("config".to_owned(), config);
+    }
+    let theme = ctx.context.plot_config.theme;
+
+    let path = ctx.context.plot_path(ctx.id, name);
+    let page = page(title.unwrap_or(ctx.id.as_title()), &spec, link, theme);
+    log_if_err!(fs::save_string(&page, &path));
+}
+
+fn page(title: &str, spec: &Value, link: Option<&str>, theme: Theme) -> String {
+    let scripts: String = SCRIPTS
+        .iter()
+        .map(|src| {
+            format!(
+                "<script src=\"{}\" crossorigin=\"anonymous\"></script>\n",
+                src
+            )
+        })
+        .collect();
+    let on_click = match link {
+        Some(link) => format!(
+            ".then(function (result) {{\n    result.view.addEventListener(\"click\", function () {{\n        window.top.location.href = new URL({}, window.location.href).href;\n    }});\n}})",
+            script_literal(&json!(link))
+        ),
+        None => String::new(),
+    };
+    let background = match theme {
+        Theme::Light => "white".to_owned(),
+        Theme::Dark => hex_color(DARK_BACKGROUND),
+    };
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n{}<style>body {{ margin: 0; background: {}; }}</style>\n</head>\n<body>\n<div id=\"plot\"></div>\n<script>\nvegaEmbed(\"#plot\", {}, {{ actions: {} }}){};\n</script>\n</body>\n</html>\n",
+        escape_html(title),
+        scripts,
+        background,
+        script_literal(spec),
+        link.is_none(),
+        on_click,
+    )
+}
+
+/// Formats `value` as JSON that is safe to put in a `<script>` element.
+fn script_literal(value: &Value) -> String {
+    value.to_string().replace("</", "<\\/")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
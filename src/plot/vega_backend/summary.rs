@@ -0,0 +1,565 @@
+use serde_json::{json, Value};
+
+use super::{foreground, hex_color, zoom, DARK_BLUE, DARK_GREEN, DARK_RED};
+use crate::estimate::ConfidenceInterval;
+use crate::kde;
+use crate::measurement::ValueFormatter;
+use crate::plot::{
+    box_plots, format_bytes, kde_bandwidth, ratio_curves, violin_ticks, ChangeMarkers, LineScale,
+    ViolinColors, DARK_PALETTE,
+};
+use crate::report::{BenchmarkId, SignificantChange, ValueType};
+use crate::stats::univariate::Sample;
+use crate::{AxisScale, PlotConfiguration, Theme};
+
+const COMPARISON_COLORS: [&str; 8] = [
+    "#b22222", "#2e8b57", "#008b8b", "#ffd700", "#00008b", "#dc143c", "#8b008b", "#00ff7f",
+];
+
+/// The colors of the first `count` curves of a comparison plot, from the configured colors or
+/// `defaults`.
+fn palette_colors(conf: &PlotConfiguration, count: usize, defaults: &[&str]) -> Vec<String> {
+    match &conf.colors {
+        Some(colors) => colors
+            .iter()
+            .map(|&rgb| hex_color(rgb))
+            .cycle()
+            .take(count)
+            .collect(),
+        None => defaults
+            .iter()
+            .map(|&color| color.to_owned())
+            .cycle()
+            .take(count)
+            .collect(),
+    }
+}
+
+/// The colors of the first `count` curves of a comparison plot, in the palette of the theme.
+fn comparison_colors(conf: &PlotConfiguration, count: usize) -> Vec<String> {
+    match conf.theme {
+        Theme::Light => palette_colors(conf, count, &COMPARISON_COLORS),
+        Theme::Dark => {
+            let dark: Vec<String> = DARK_PALETTE.iter().map(|&rgb| hex_color(rgb)).collect();
+            let dark: Vec<&str> = dark.iter().map(String::as_str).collect();
+            palette_colors(conf, count, &dark)
+        }
+    }
+}
+
+fn scale_type(scale: AxisScale) -> &'static str {
+    match scale {
+        AxisScale::Linear => "linear",
+        AxisScale::Logarithmic => "log",
+    }
+}
+
+/// A Vega expression labelling the tic `datum.value` with the label of the matching tic in `tics`.
+fn label_expr(tics: &[(i64, String)]) -> String {
+    tics.iter()
+        .rev()
+        .fold("datum.label".to_owned(), |expr, (tic, label)| {
+            format!("datum.value == {} ? {} : {}", tic, json!(label), expr)
+        })
+}
+
+pub(super) fn line_comparison(
+    formatter: &dyn ValueFormatter,
+    title: &str,
+    all_curves: &[&(&BenchmarkId, Vec<f64>)],
+    mean_intervals: &[Option<ConfidenceInterval>],
+    changes: &[Option<SignificantChange>],
+    value_type: ValueType,
+    conf: &PlotConfiguration,
+) -> Value {
+    // One row per point, with the bounds of its confidence interval if they are known.
+    let mut points = vec![];
+    let mut functions = vec![];
+    let mut markers = ChangeMarkers::default();
+    let y_title = match ratio_curves(title, all_curves, mean_intervals, conf) {
+        Some((y_title, curves)) => {
+            for (name, ratios) in curves {
+                markers.mark(all_curves, changes, name, &ratios.xs, &ratios.ys);
+                let function = name.cloned().unwrap_or_default();
+                for i in 0..ratios.xs.len() {
+                    points.push(json!({
+                        "function": function,
+                        "input": ratios.xs[i],
+                        "value": ratios.ys[i],
+                        "lower": ratios.lower[i],
+                        "upper": ratios.upper[i],
+                    }));
+                }
+                functions.push(function);
+            }
+            y_title
+        }
+        None => {
+            let scale = LineScale::new(formatter, all_curves, conf);
+            let curves = all_curves.iter().zip(mean_intervals).zip(changes);
+            for ((&&(id, ref sample), interval), change) in curves {
+                let function = id.function_id.clone().unwrap_or_default();
+                let mut values = [Sample::new(sample).mean(), f64::NAN, f64::NAN];
+                if let Some(ci) = interval {
+                    values[1] = ci.lower_bound;
+                    values[2] = ci.upper_bound;
+                }
+                scale.scale(id, &mut values);
+                let mut point = json!({
+                    "function": function,
+                    "input": id.as_number().unwrap(),
+                    "value": values[0],
+                });
+                if interval.is_some() {
+                    point["lower"] = json!(values[1]);
+                    point["upper"] = json!(values[2]);
+                }
+                let marker = (id.as_number().unwrap(), values[0]);
+                match change {
+                    Some(SignificantChange::Improved) => markers.improved.push(marker),
+                    Some(SignificantChange::Regressed) => markers.regressed.push(marker),
+                    None => {}
+                }
+                points.push(point);
+                if !functions.contains(&function) {
+                    functions.push(function);
+                }
+            }
+            scale.y_label()
+        }
+    };
+
+    let x_title = if conf.x_label.is_empty() {
+        match value_type {
+            ValueType::Bytes => "Input Size (Bytes)".to_owned(),
+            ValueType::Elements => "Input Size (Elements)".to_owned(),
+            ValueType::Value => "Input".to_owned(),
+        }
+    } else {
+        conf.x_label.clone()
+    };
+    let mut x_axis = json!({"grid": conf.x_grid_major || conf.x_grid_minor});
+    if !conf.tics.is_empty() || conf.tic_formatter.is_some() {
+        // Without tics, the tic formatter labels the inputs.
+        let mut tics = conf.tics.clone();
+        if tics.is_empty() {
+            tics = points
+                .iter()
+                .filter_map(|point| point["input"].as_f64())
+                .map(|input| input.round() as i64)
+                .collect();
+            tics.sort_unstable();
+            tics.dedup();
+        }
+        let labels: Vec<_> = tics
+            .iter()
+            .map(|&tic| {
+                let label = conf.tic_label(tic).unwrap_or_else(|| format_bytes(tic));
+                (tic, label)
+            })
+            .collect();
+        x_axis["values"] = json!(tics);
+        x_axis["labelExpr"] = json!(label_expr(&labels));
+    }
+
+    let colors = comparison_colors(conf, functions.len());
+    let color = json!({
+        "field": "function",
+        "type": "nominal",
+        "title": "Function",
+        "scale": {"domain": functions, "range": colors},
+    });
+    let x = json!({
+        "field": "input",
+        "type": "quantitative",
+        "title": x_title,
+        "scale": {"type": scale_type(conf.x_scale)},
+        "axis": x_axis,
+    });
+    // Clicking a function in the legend shows only that function.
+    let opacity = json!({"condition": {"param": "functions", "value": 1}, "value": 0.1});
+    let mut spec = json!({
+        "data": {"values": points},
+        "encoding": {"x": x, "color": color},
+        "layer": [
+            {
+                "transform": [{"filter": "isValid(datum.lower)"}],
+                "mark": {"type": "area", "opacity": 0.25},
+                "encoding": {
+                    "y": {"field": "lower", "type": "quantitative"},
+                    "y2": {"field": "upper"},
+                    "opacity": {"condition": {"param": "functions", "value": 0.25}, "value": 0.02},
+                },
+            },
+            {
+                "params": [
+                    zoom(),
+                    {
+                        "name": "functions",
+                        "select": {"type": "point", "fields": ["function"]},
+                        "bind": "legend",
+                    },
+                ],
+                "mark": {"type": "line", "point": true},
+                "encoding": {
+                    "y": {
+                        "field": "value",
+                        "type": "quantitative",
+                        "title": y_title,
+                        "scale": {"type": scale_type(conf.y_scale)},
+                        "axis": {"grid": conf.y_grid_major || conf.y_grid_minor},
+                    },
+                    "opacity": opacity,
+                    "tooltip": [
+                        {"field": "function", "title": "Function"},
+                        {"field": "input", "title": "Input"},
+                        {"field": "value", "title": y_title, "format": ".4~f"},
+                        {"field": "lower", "title": "Lower bound", "format": ".4~f"},
+                        {"field": "upper", "title": "Upper bound", "format": ".4~f"},
+                    ],
+                },
+            },
+        ],
+    });
+    if !(markers.improved.is_empty() && markers.regressed.is_empty()) {
+        spec["layer"]
+            .as_array_mut()
+            .unwrap()
+            .push(change_markers(&markers));
+        spec["resolve"] = json!({"scale": {"color": "independent"}});
+    }
+    if !conf.tics.is_empty() {
+        spec["layer"].as_array_mut().unwrap().push(raw_tics(conf));
+        spec["resolve"]["axis"] = json!({"x": "independent"});
+    }
+    spec
+}
+
+/// An invisible layer whose axis, above the line comparison plot, labels the tics with their raw
+/// values, so that the formatted labels below it can be read back as the parameters of the
+/// benchmarks.
+fn raw_tics(conf: &PlotConfiguration) -> Value {
+    json!({
+        "mark": {"type": "point", "opacity": 0},
+        "encoding": {
+            "x": {
+                "field": "input",
+                "type": "quantitative",
+                "scale": {"type": scale_type(conf.x_scale)},
+                "axis": {
+                    "orient": "top",
+                    "values": conf.tics,
+                    "format": "d",
+                    "title": null,
+                    "grid": false,
+                },
+            },
+        },
+    })
+}
+
+/// A layer of triangles on the points of the line comparison plot which changed significantly,
+/// pointing up for regressions and down for improvements.
+fn change_markers(markers: &ChangeMarkers) -> Value {
+    let rows: Vec<Value> = markers
+        .regressed
+        .iter()
+        .map(|&point| (point, "Regressed"))
+        .chain(markers.improved.iter().map(|&point| (point, "Improved")))
+        .map(|((x, y), change)| json!({"input": x, "value": y, "change": change}))
+        .collect();
+    json!({
+        "data": {"values": rows},
+        "mark": {"type": "point", "filled": true, "size": 120, "opacity": 1},
+        "encoding": {
+            "y": {"field": "value", "type": "quantitative"},
+            "color": {
+                "field": "change",
+                "type": "nominal",
+                "title": "Change",
+                "scale": {"domain": ["Regressed", "Improved"], "range": [DARK_RED, DARK_GREEN]},
+            },
+            "shape": {
+                "field": "change",
+                "type": "nominal",
+                "scale": {
+                    "domain": ["Regressed", "Improved"],
+                    "range": ["triangle-up", "triangle-down"],
+                },
+            },
+            "tooltip": [
+                {"field": "input", "title": "Input"},
+                {"field": "change", "title": "Change"},
+            ],
+        },
+    })
+}
+
+pub(super) fn violin(
+    formatter: &dyn ValueFormatter,
+    all_curves: &[&(&BenchmarkId, Vec<f64>)],
+    conf: &PlotConfiguration,
+    percentiles: &[f64],
+) -> Value {
+    let violin_colors = ViolinColors::new(all_curves);
+    let kdes: Vec<_> = all_curves
+        .iter()
+        .map(|&&(id, ref sample)| {
+            let (xs, mut ys) = kde::sweep(
+                Sample::new(sample),
+                kde_bandwidth(conf, |_| {}),
+                conf.kde_points,
+                None,
+            );
+            let y_max = Sample::new(&ys).max();
+            for y in ys.iter_mut() {
+                *y /= y_max;
+            }
+            let ticks = violin_ticks(sample, percentiles, &xs, &ys);
+            (id.as_title(), xs, ys, Sample::new(sample).mean(), ticks)
+        })
+        .collect();
+    let max = kdes
+        .iter()
+        .flat_map(|(_, xs, _, _, _)| xs.iter().cloned())
+        .fold(f64::NAN, f64::max);
+    let unit = formatter.scale_values(max, &mut [1.0]);
+
+    // Each violin is centered on the index of its benchmark, from the top.
+    let function_names: Vec<_> = violin_colors.labels.iter().flatten().cloned().collect();
+    let mut areas = vec![];
+    let mut means = vec![];
+    let mut marks = vec![];
+    for (i, (name, xs, ys, mean, ticks)) in kdes.into_iter().enumerate() {
+        let function = function_names.get(violin_colors.indices[i]);
+        let mut xs = xs.to_vec();
+        let mut mean = [mean];
+        formatter.scale_values(max, &mut xs);
+        formatter.scale_values(max, &mut mean);
+        for (&x, &y) in xs.iter().zip(ys.iter()) {
+            if x > 0. {
+                areas.push(json!({
+                    "benchmark": name,
+                    "function": function,
+                    "time": x,
+                    "lower": i as f64 - 0.45 * y,
+                    "upper": i as f64 + 0.45 * y,
+                }));
+            }
+        }
+        means.push(json!({"benchmark": name, "index": i, "mean": mean[0]}));
+        for (&percentile, (tick, height)) in percentiles.iter().zip(ticks) {
+            let mut tick = [tick];
+            formatter.scale_values(max, &mut tick);
+            marks.push(json!({
+                "benchmark": name,
+                "percentile": format!("p{}", percentile),
+                "time": tick[0],
+                "lower": i as f64 - 0.45 * height,
+                "upper": i as f64 + 0.45 * height,
+            }));
+        }
+    }
+
+    let names: Vec<_> = all_curves.iter().map(|&&(id, _)| id.as_title()).collect();
+    let n = names.len();
+    let color = if violin_colors.by_function {
+        json!({
+            "field": "function",
+            "title": null,
+            "sort": function_names,
+            "scale": {
+                "domain": function_names,
+                "range": comparison_colors(conf, function_names.len()),
+            },
+        })
+    } else {
+        json!({
+            "field": "benchmark",
+            "legend": null,
+            "scale": {"domain": names, "range": palette_colors(conf, n, &[DARK_BLUE])},
+        })
+    };
+    let time_title = format!("Average time ({})", unit);
+    json!({
+        "layer": [
+            {
+                "data": {"values": areas},
+                "params": [zoom()],
+                "mark": {"type": "area", "opacity": 0.5},
+                "encoding": {
+                    "x": {
+                        "field": "time",
+                        "type": "quantitative",
+                        "title": time_title,
+                        "scale": {"type": scale_type(conf.x_scale)},
+                    },
+                    "y": {
+                        "field": "lower",
+                        "type": "quantitative",
+                        "title": null,
+                        "scale": {"domain": [-0.5, n as f64 - 0.5], "reverse": true},
+                        "axis": {
+                            "values": (0..n).collect::<Vec<_>>(),
+                            "labelExpr": format!("{}[datum.value]", json!(names)),
+                            "grid": false,
+                        },
+                    },
+                    "y2": {"field": "upper"},
+                    "color": color,
+                    "detail": {"field": "benchmark"},
+                },
+            },
+            {
+                "data": {"values": means},
+                "mark": {"type": "point", "filled": true, "color": foreground(conf.theme)},
+                "encoding": {
+                    "x": {"field": "mean", "type": "quantitative"},
+                    "y": {"field": "index", "type": "quantitative"},
+                    "tooltip": [
+                        {"field": "benchmark", "title": "Benchmark"},
+                        {"field": "mean", "title": format!("Mean ({})", unit), "format": ".4~f"},
+                    ],
+                },
+            },
+            {
+                "data": {"values": marks},
+                "mark": {"type": "rule", "strokeWidth": 2, "color": foreground(conf.theme)},
+                "encoding": {
+                    "x": {"field": "time", "type": "quantitative"},
+                    "y": {"field": "lower", "type": "quantitative"},
+                    "y2": {"field": "upper"},
+                    "tooltip": [
+                        {"field": "benchmark", "title": "Benchmark"},
+                        {"field": "percentile", "title": "Percentile"},
+                        {"field": "time", "title": format!("Time ({})", unit), "format": ".4~f"},
+                    ],
+                },
+            },
+        ],
+    })
+}
+
+pub(super) fn box_plot(
+    formatter: &dyn ValueFormatter,
+    all_curves: &[&(&BenchmarkId, Vec<f64>)],
+    conf: &PlotConfiguration,
+) -> Value {
+    let (boxes, unit) = box_plots(formatter, all_curves);
+    let names: Vec<_> = all_curves.iter().map(|&&(id, _)| id.as_title()).collect();
+    let mut rows = vec![];
+    let mut outliers = vec![];
+    for (name, stats) in names.iter().zip(&boxes) {
+        rows.push(json!({
+            "benchmark": name,
+            "whisker_low": stats.whisker_low,
+            "q1": stats.q1,
+            "median": stats.median,
+            "q3": stats.q3,
+            "whisker_high": stats.whisker_high,
+        }));
+        for &x in &stats.outliers {
+            outliers.push(json!({"benchmark": name, "time": x}));
+        }
+    }
+
+    let time_title = format!("Average time ({})", unit);
+    let y = json!({"field": "benchmark", "type": "nominal", "sort": names, "title": null});
+    let color = json!({
+        "field": "benchmark",
+        "legend": null,
+        "scale": {"domain": names, "range": palette_colors(conf, names.len(), &[DARK_BLUE])},
+    });
+    let format = ".4~f";
+    json!({
+        "layer": [
+            {
+                "data": {"values": rows},
+                "encoding": {"y": y, "color": color},
+                "layer": [
+                    {
+                        "params": [zoom()],
+                        "mark": "rule",
+                        "encoding": {
+                            "x": {
+                                "field": "whisker_low",
+                                "type": "quantitative",
+                                "title": time_title,
+                                "scale": {"type": scale_type(conf.x_scale)},
+                            },
+                            "x2": {"field": "whisker_high"},
+                        },
+                    },
+                    {
+                        "mark": {"type": "bar", "size": 14, "opacity": 0.5},
+                        "encoding": {
+                            "x": {"field": "q1", "type": "quantitative"},
+                            "x2": {"field": "q3"},
+                            "tooltip": [
+                                {"field": "benchmark", "title": "Benchmark"},
+                                {"field": "whisker_low", "title": "Lower whisker", "format": format},
+                                {"field": "q1", "title": "First quartile", "format": format},
+                                {"field": "median", "title": "Median", "format": format},
+                                {"field": "q3", "title": "Third quartile", "format": format},
+                                {"field": "whisker_high", "title": "Upper whisker", "format": format},
+                            ],
+                        },
+                    },
+                    {
+                        "mark": {"type": "tick", "size": 14, "thickness": 2},
+                        "encoding": {"x": {"field": "median", "type": "quantitative"}},
+                    },
+                ],
+            },
+            {
+                "data": {"values": outliers},
+                "mark": {"type": "point", "filled": true},
+                "encoding": {
+                    "x": {"field": "time", "type": "quantitative"},
+                    "y": y,
+                    "color": color,
+                    "tooltip": [
+                        {"field": "benchmark", "title": "Benchmark"},
+                        {"field": "time", "title": format!("Outlier ({})", unit), "format": format},
+                    ],
+                },
+            },
+        ],
+    })
+}
+
+pub(super) fn speedup_summary(speedups: &[(String, f64)], conf: &PlotConfiguration) -> Value {
+    let bars: Vec<_> = speedups
+        .iter()
+        .map(|(function, speedup)| json!({"function": function, "speedup": speedup}))
+        .collect();
+    let functions: Vec<_> = speedups.iter().map(|(function, _)| function).collect();
+    let colors = comparison_colors(conf, speedups.len());
+    let y_title = format!("Speedup over {}", conf.speedup_id);
+    json!({
+        "layer": [
+            {
+                "data": {"values": bars},
+                "mark": "bar",
+                "encoding": {
+                    "x": {"field": "function", "type": "nominal", "sort": null, "title": null},
+                    "y": {"field": "speedup", "type": "quantitative", "title": y_title},
+                    "color": {
+                        "field": "function",
+                        "legend": null,
+                        "scale": {"domain": functions, "range": colors},
+                    },
+                    "tooltip": [
+                        {"field": "function", "title": "Function"},
+                        {"field": "speedup", "title": "Speedup", "format": ".2f"},
+                    ],
+                },
+            },
+            {
+                "data": {"values": [{"speedup": 1}]},
+                "mark": {"type": "rule", "color": foreground(conf.theme), "strokeDash": [4, 4]},
+                "encoding": {"y": {"field": "speedup", "type": "quantitative"}},
+            },
+        ],
+    })
+}
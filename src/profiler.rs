@@ -1,21 +1,81 @@
 //! This module provides an extension trait which allows in-process profilers
 //! to be hooked into the `--profile-time` argument at compile-time. Users of
 //! out-of-process profilers such as perf don't need to do anything special.
+//!
+//! With the `pprof_profiler` feature, Criterion.rs also ships a sampling profiler of its own,
+//! [`PProfProfiler`], which is used by default.
 
-use std::path::Path;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::BenchmarkConfig;
+
+/// Information about the benchmark being profiled, passed to
+/// [`Profiler::start_benchmark_profiling`] and [`Profiler::stop_benchmark_profiling`].
+#[non_exhaustive]
+pub struct ProfilingContext<'a> {
+    /// The stringified benchmark ID, eg. `group/function/parameter`.
+    pub benchmark_id: &'a str,
+    /// The ID of the benchmark's group.
+    pub group_id: &'a str,
+    /// The function ID of the benchmark, if it has one.
+    pub function_id: Option<&'a str>,
+    /// The parameter value of the benchmark, if it has one.
+    pub value_str: Option<&'a str>,
+    /// A directory where the profiler can store its data.
+    pub benchmark_dir: &'a Path,
+    /// How long the benchmark will be iterated for.
+    pub profile_time: Duration,
+    /// The resolved configuration of the benchmark.
+    pub config: &'a BenchmarkConfig,
+}
 
 /// Extension trait for external crates to implement which provides start/stop
 /// hooks when profiling (but not when benchmarking) functions.
+///
+/// Implementations should implement either `start_profiling` and `stop_profiling`, or
+/// `start_benchmark_profiling` and `stop_benchmark_profiling` if they need more information about
+/// the benchmark.
 pub trait Profiler {
     /// This function is called when Criterion.rs starts profiling a particular
     /// benchmark. It provides the stringified benchmark ID and
     /// a path to a directory where the profiler can store its data.
-    fn start_profiling(&mut self, benchmark_id: &str, benchmark_dir: &Path);
+    fn start_profiling(&mut self, _benchmark_id: &str, _benchmark_dir: &Path) {}
 
     /// This function is called after Criterion.rs stops profiling a particular
     /// benchmark. The benchmark ID and directory are the same as in the call
     /// to `start`, provided for convenience.
-    fn stop_profiling(&mut self, benchmark_id: &str, benchmark_dir: &Path);
+    fn stop_profiling(&mut self, _benchmark_id: &str, _benchmark_dir: &Path) {}
+
+    /// This function is called when Criterion.rs starts profiling a particular
+    /// benchmark, with the benchmark's ID, profiling time and configuration. By
+    /// default it calls `start_profiling`.
+    fn start_benchmark_profiling(&mut self, context: &ProfilingContext<'_>) {
+        self.start_profiling(context.benchmark_id, context.benchmark_dir);
+    }
+
+    /// This function is called after Criterion.rs stops profiling a particular
+    /// benchmark, with the same context as the call to `start_benchmark_profiling`.
+    /// By default it calls `stop_profiling`.
+    fn stop_benchmark_profiling(&mut self, context: &ProfilingContext<'_>) {
+        self.stop_profiling(context.benchmark_id, context.benchmark_dir);
+    }
+
+    /// This function is called before any benchmark is profiled, with the
+    /// `--profiler-arg KEY=VALUE` command-line arguments in the order they were
+    /// given (a `KEY` without `=` has an empty value). Profilers can use these
+    /// to let users choose eg. an output directory or a sampling frequency
+    /// without recompiling. It is called again with all of the arguments each
+    /// time one is added. By default the arguments are ignored.
+    fn configure(&mut self, _args: &[(String, String)]) {}
+
+    /// This function is called after `stop_benchmark_profiling`, and returns
+    /// the files which the profiler wrote for the benchmark (eg. a flamegraph),
+    /// so that they can be shown in the reports. By default there are none.
+    fn artifacts(&mut self, _context: &ProfilingContext<'_>) -> Vec<PathBuf> {
+        vec![]
+    }
 }
 
 /// Dummy profiler implementation, representing cases where the profiler is
@@ -26,3 +86,185 @@ impl Profiler for ExternalProfiler {
     fn start_profiling(&mut self, _benchmark_id: &str, _benchmark_dir: &Path) {}
     fn stop_profiling(&mut self, _benchmark_id: &str, _benchmark_dir: &Path) {}
 }
+
+/// Returns the profiler used unless another one is set with `Criterion::with_profiler`.
+pub(crate) fn default_profiler() -> Box<RefCell<dyn Profiler>> {
+    #[cfg(all(feature = "pprof_profiler", unix))]
+    return Box::new(RefCell::new(PProfProfiler::new()));
+    #[cfg(not(all(feature = "pprof_profiler", unix)))]
+    Box::new(RefCell::new(ExternalProfiler))
+}
+
+/// Built-in sampling profiler based on [pprof](https://docs.rs/pprof), available with the
+/// `pprof_profiler` feature on Unix-like systems, where it is the default profiler.
+///
+/// For every benchmark run with `--profile-time`, it writes a flamegraph, `flamegraph.svg`, and a
+/// profile in the pprof format, `profile.pb`, to the benchmark's profile directory, eg.
+/// `target/criterion/<benchmark>/profile`. The profile can be inspected with
+/// `go tool pprof` or `pprof -http`. The HTML report of the benchmark links to both files.
+///
+/// The sampling frequency and the outputs can be changed without recompiling with
+/// `--profiler-arg frequency=<Hz>` and `--profiler-arg output=<flamegraph|protobuf|both>`. To
+/// profile with an external profiler such as perf instead, use
+/// `Criterion::default().with_profiler(ExternalProfiler)`.
+#[cfg(all(feature = "pprof_profiler", unix))]
+pub struct PProfProfiler {
+    frequency: i32,
+    flamegraph: bool,
+    protobuf: bool,
+    guard: Option<pprof::ProfilerGuard<'static>>,
+    artifacts: Vec<PathBuf>,
+    /// Number of `--profiler-arg`s already seen by `configure`, which are only reported once.
+    configured_args: usize,
+}
+#[cfg(all(feature = "pprof_profiler", unix))]
+impl PProfProfiler {
+    /// Creates a profiler sampling at 1000 Hz and writing both a flamegraph and a pprof profile.
+    pub fn new() -> PProfProfiler {
+        PProfProfiler {
+            frequency: 1000,
+            flamegraph: true,
+            protobuf: true,
+            guard: None,
+            artifacts: vec![],
+            configured_args: 0,
+        }
+    }
+
+    /// Changes the number of samples taken per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frequency is zero.
+    pub fn frequency(mut self, frequency: i32) -> PProfProfiler {
+        assert!(frequency > 0);
+        self.frequency = frequency;
+        self
+    }
+
+    /// Changes whether `flamegraph.svg` is written.
+    pub fn flamegraph(mut self, enabled: bool) -> PProfProfiler {
+        self.flamegraph = enabled;
+        self
+    }
+
+    /// Changes whether `profile.pb` is written.
+    pub fn protobuf(mut self, enabled: bool) -> PProfProfiler {
+        self.protobuf = enabled;
+        self
+    }
+
+    fn write(
+        &mut self,
+        report: &pprof::Report,
+        context: &ProfilingContext<'_>,
+    ) -> Result<(), String> {
+        use pprof::protos::Message;
+
+        if report.data.is_empty() {
+            // eg. because the routine waits for another process or sleeps.
+            return Err("no samples were taken".to_owned());
+        }
+        std::fs::create_dir_all(context.benchmark_dir).map_err(|e| e.to_string())?;
+        if self.flamegraph {
+            let path = context.benchmark_dir.join("flamegraph.svg");
+            let file = std::fs::File::create(&path).map_err(|e| e.to_string())?;
+            let mut options = pprof::flamegraph::Options::default();
+            options.title = context.benchmark_id.to_owned();
+            report
+                .flamegraph_with_options(file, &mut options)
+                .map_err(|e| e.to_string())?;
+            self.artifacts.push(path);
+        }
+        if self.protobuf {
+            let path = context.benchmark_dir.join("profile.pb");
+            let mut content = vec![];
+            report
+                .pprof()
+                .map_err(|e| e.to_string())?
+                .encode(&mut content)
+                .map_err(|e| e.to_string())?;
+            std::fs::write(&path, content).map_err(|e| e.to_string())?;
+            self.artifacts.push(path);
+        }
+        Ok(())
+    }
+}
+#[cfg(all(feature = "pprof_profiler", unix))]
+impl Default for PProfProfiler {
+    fn default() -> PProfProfiler {
+        PProfProfiler::new()
+    }
+}
+#[cfg(all(feature = "pprof_profiler", unix))]
+impl Profiler for PProfProfiler {
+    fn start_benchmark_profiling(&mut self, context: &ProfilingContext<'_>) {
+        self.artifacts.clear();
+        match pprof::ProfilerGuardBuilder::default()
+            .frequency(self.frequency)
+            .build()
+        {
+            Ok(guard) => self.guard = Some(guard),
+            Err(e) => eprintln!(
+                "Warning: unable to start profiling {}: {}",
+                context.benchmark_id, e
+            ),
+        }
+    }
+
+    fn stop_benchmark_profiling(&mut self, context: &ProfilingContext<'_>) {
+        let guard = match self.guard.take() {
+            Some(guard) => guard,
+            None => return,
+        };
+        let result = guard
+            .report()
+            .build()
+            .map_err(|e| e.to_string())
+            .and_then(|report| self.write(&report, context));
+        if let Err(e) = result {
+            eprintln!(
+                "Warning: unable to save the profile of {}: {}",
+                context.benchmark_id, e
+            );
+        }
+    }
+
+    fn configure(&mut self, args: &[(String, String)]) {
+        for (i, (key, value)) in args.iter().enumerate() {
+            let warn = i >= self.configured_args;
+            match (&**key, &**value) {
+                ("frequency", frequency) => match frequency.parse() {
+                    Ok(frequency) if frequency > 0 => self.frequency = frequency,
+                    _ if warn => eprintln!(
+                        "Warning: ignoring --profiler-arg frequency={}: expected a number of samples per second.",
+                        frequency
+                    ),
+                    _ => {}
+                },
+                ("output", "flamegraph") => {
+                    self.flamegraph = true;
+                    self.protobuf = false;
+                }
+                ("output", "protobuf") => {
+                    self.flamegraph = false;
+                    self.protobuf = true;
+                }
+                ("output", "both") => {
+                    self.flamegraph = true;
+                    self.protobuf = true;
+                }
+                ("output", output) if warn => eprintln!(
+                    "Warning: ignoring --profiler-arg output={}: expected flamegraph, protobuf or both.",
+                    output
+                ),
+                _ => {}
+            }
+        }
+        self.configured_args = args.len();
+    }
+
+    fn artifacts(&mut self, _context: &ProfilingContext<'_>) -> Vec<PathBuf> {
+        std::mem::take(&mut self.artifacts)
+    }
+}
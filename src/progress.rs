@@ -0,0 +1,102 @@
+//! Suite-wide progress tracking used by the `--progress` display.
+//!
+//! Every benchmark group constructs its own `Criterion` struct, so the progress state has to live
+//! in a process-wide static rather than in any one of them.
+
+use crate::clock::Instant;
+use crate::format;
+use once_cell::sync::Lazy;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+#[derive(Default)]
+struct SuiteProgress {
+    /// True while `count_benchmarks` is running the groups to discover benchmarks.
+    counting: bool,
+    /// Number of benchmarks found during the discovery pass, if one was performed.
+    total: Option<usize>,
+    discovered: usize,
+    completed: usize,
+    /// Start time and expected duration of the benchmark currently being run.
+    current: Option<(Instant, Duration)>,
+}
+
+static PROGRESS: Lazy<Mutex<SuiteProgress>> = Lazy::new(|| Mutex::new(SuiteProgress::default()));
+
+fn progress() -> MutexGuard<'static, SuiteProgress> {
+    // A panicking benchmark must not prevent the rest of the suite from reporting progress.
+    PROGRESS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Runs every group once without executing any benchmarks in order to count how many benchmarks
+/// match the current filters. Only does anything if `--progress` was passed on the command line.
+///
+/// The group functions are called again in the real run, so the setup code outside of the
+/// benchmark routines runs twice. The commands of `configure_from_args` which exit, eg.
+/// `--gc-baselines`, and the messages to cargo-criterion are left to the real run.
+pub(crate) fn count_benchmarks(groups: &[&dyn Fn()]) {
+    if !std::env::args().any(|arg| arg == "--progress") {
+        return;
+    }
+
+    progress().counting = true;
+    for group in groups {
+        group();
+    }
+
+    let mut state = progress();
+    state.counting = false;
+    state.total = Some(state.discovered);
+}
+
+/// Starts tracking a new run of `total` benchmarks, for the runs of the `--interactive` mode.
+#[cfg(feature = "interactive")]
+pub(crate) fn restart(total: usize) {
+    let mut state = progress();
+    *state = SuiteProgress::default();
+    state.total = Some(total);
+}
+
+pub(crate) fn is_counting() -> bool {
+    progress().counting
+}
+
+pub(crate) fn benchmark_discovered() {
+    progress().discovered += 1;
+}
+
+pub(crate) fn benchmark_started(expected: Duration) {
+    progress().current = Some((Instant::now(), expected));
+}
+
+pub(crate) fn benchmark_finished() {
+    let mut state = progress();
+    state.completed += 1;
+    state.current = None;
+}
+
+/// Returns a short status string such as `[3/120, ETA 5m 12s]`.
+///
+/// The ETA assumes that every remaining benchmark takes as long as the configured warm-up and
+/// measurement time of the current one, which ignores analysis time but is usually close enough.
+pub(crate) fn status() -> String {
+    let state = progress();
+    let position = state.completed + usize::from(state.current.is_some());
+
+    match (state.total, state.current) {
+        (Some(total), Some((started, expected))) => {
+            let remaining_benchmarks = total.saturating_sub(position) as u32;
+            let eta = expected.saturating_sub(started.elapsed()) + expected * remaining_benchmarks;
+            format!(
+                "[{}/{}, ETA {}]",
+                position,
+                total,
+                format::eta(eta.as_secs_f64())
+            )
+        }
+        (Some(total), None) => format!("[{}/{}]", position, total),
+        (None, _) => format!("[{}]", position),
+    }
+}
@@ -0,0 +1,112 @@
+//! Ranking of the functions in each comparison group of a benchmark group.
+//!
+//! A comparison group is the set of benchmarks in a group which share the same parameter value
+//! (or have no parameter) but measure different functions. Functions are ranked by their mean
+//! time, and a function whose confidence interval overlaps that of the winner is considered
+//! statistically tied with it.
+
+use std::path::Path;
+
+use crate::estimate::Estimates;
+use crate::fs;
+use crate::report::BenchmarkId;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Ranking {
+    pub group_id: String,
+    /// Parameter value shared by the benchmarks in this comparison group, if any.
+    pub value_str: Option<String>,
+    /// Fastest first.
+    pub entries: Vec<RankEntry>,
+}
+impl Ranking {
+    pub fn title(&self) -> String {
+        match &self.value_str {
+            Some(value_str) => format!("{}/{}", self.group_id, value_str),
+            None => self.group_id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RankEntry {
+    pub rank: usize,
+    pub function_id: String,
+    /// Mean time and its confidence interval, in the measurement's unit.
+    pub mean: f64,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    /// Mean time relative to the winner's mean time.
+    pub ratio: f64,
+    /// True if the confidence interval of the mean overlaps that of the winner, ie. this function
+    /// can't be said to be slower than the winner. Always true for the winner itself.
+    pub tied_with_winner: bool,
+}
+
+/// The benchmarked functions of one comparison group, with their estimates.
+type ComparisonGroup<'a> = (Option<&'a String>, Vec<(&'a String, Estimates)>);
+
+/// Ranks the functions of each comparison group among the given benchmarks, which must all have
+/// been run this time, using the estimates saved for them. Comparison groups with fewer than two
+/// functions are left out.
+pub(crate) fn rank(output_directory: &Path, ids: &[BenchmarkId]) -> Vec<Ranking> {
+    // Comparison groups in the order that they were first benchmarked.
+    let mut groups: Vec<ComparisonGroup<'_>> = vec![];
+    for id in ids {
+        let function_id = match &id.function_id {
+            Some(function_id) => function_id,
+            None => continue,
+        };
+        let path = output_directory
+            .join(id.as_directory_name())
+            .join("new")
+            .join("estimates.json");
+        // Benchmarks whose analysis failed have no estimates.
+        let estimates: Estimates = match fs::load(&path) {
+            Ok(estimates) => estimates,
+            Err(_) => continue,
+        };
+
+        let value_str = id.value_str.as_ref();
+        match groups.iter_mut().find(|(value, _)| *value == value_str) {
+            Some((_, functions)) => functions.push((function_id, estimates)),
+            None => groups.push((value_str, vec![(function_id, estimates)])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, functions)| functions.len() > 1)
+        .map(|(value_str, mut functions)| {
+            functions.sort_by(|(_, a), (_, b)| {
+                a.mean
+                    .point_estimate
+                    .partial_cmp(&b.mean.point_estimate)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+            let winner = functions[0].1.mean.clone();
+            let entries = functions
+                .into_iter()
+                .enumerate()
+                .map(|(i, (function_id, estimates))| {
+                    let mean = estimates.mean;
+                    RankEntry {
+                        rank: i + 1,
+                        function_id: function_id.clone(),
+                        mean: mean.point_estimate,
+                        lower_bound: mean.confidence_interval.lower_bound,
+                        upper_bound: mean.confidence_interval.upper_bound,
+                        ratio: mean.point_estimate / winner.point_estimate,
+                        tied_with_winner: mean.confidence_interval.lower_bound
+                            <= winner.confidence_interval.upper_bound,
+                    }
+                })
+                .collect();
+            Ranking {
+                group_id: ids[0].group_id.clone(),
+                value_str: value_str.cloned(),
+                entries,
+            }
+        })
+        .collect()
+}
@@ -1,16 +1,35 @@
 #[cfg(feature = "csv_output")]
-use crate::csv_report::FileCsvReport;
+use crate::csv_report::{CsvExport, FileCsvReport};
+#[cfg(feature = "sqlite")]
+use crate::history_db::HistoryDatabaseReport;
 use crate::stats::bivariate::regression::Slope;
 use crate::stats::univariate::outliers::tukey::LabeledSample;
 use crate::{html::Html, stats::bivariate::Data};
 
-use crate::estimate::{ChangeDistributions, ChangeEstimates, Distributions, Estimate, Estimates};
+use crate::badge_report::BadgeReport;
+use crate::drift::Drift;
+use crate::environment::Finding;
+use crate::estimate::{
+    ChangeDistributions, ChangeEstimates, Distributions, Estimate, Estimates, Statistic,
+};
 use crate::format;
+use crate::heap::HeapProfile;
+use crate::json_report::JsonReport;
+use crate::junit_report::JunitReport;
+use crate::manifest::Manifest;
+use crate::markdown_report::MarkdownReport;
 use crate::measurement::ValueFormatter;
+use crate::metrics_report::MetricsReport;
+use crate::noise_floor::{self, NoiseFloor};
+use crate::progress;
+use crate::ranking::Ranking;
+use crate::sink::{CustomReport, SinkReport};
 use crate::stats::univariate::Sample;
 use crate::stats::Distribution;
-use crate::{PlotConfiguration, Throughput};
+use crate::terminal_plot::{self, Charset};
+use crate::{ComparisonTest, PlotConfiguration, PlotFormat, Throughput};
 use anes::{Attribute, ClearLine, Color, ResetAttributes, SetAttribute, SetForegroundColor};
+use std::cell::RefCell;
 use std::cmp;
 use std::collections::HashSet;
 use std::fmt;
@@ -29,11 +48,52 @@ pub(crate) struct ComparisonData {
     pub relative_distributions: ChangeDistributions,
     pub significance_threshold: f64,
     pub noise_threshold: f64,
+    /// Statistic whose relative change decides whether performance has changed.
+    pub statistic: Statistic,
+    /// The test which gave the p-value.
+    pub test: ComparisonTest,
     pub base_iter_counts: Vec<f64>,
     pub base_sample_times: Vec<f64>,
     pub base_avg_times: Vec<f64>,
     pub base_estimates: Estimates,
 }
+impl ComparisonData {
+    /// Returns true if performance has regressed: the change is significant and slower by more
+    /// than the noise threshold.
+    pub(crate) fn is_regression(&self) -> bool {
+        self.significant_change() == Some(SignificantChange::Regressed)
+    }
+
+    /// The relative change of the statistic which decides whether performance has changed.
+    pub(crate) fn change(&self) -> &Estimate {
+        self.relative_estimates.get(self.statistic)
+    }
+
+    /// The direction of the change, if it is significant and larger than the noise threshold.
+    pub(crate) fn significant_change(&self) -> Option<SignificantChange> {
+        if self.p_value >= self.significance_threshold {
+            return None;
+        }
+        self.change_beyond_noise()
+    }
+
+    /// The direction of the change if it is larger than the noise threshold, whatever its p-value.
+    pub(crate) fn change_beyond_noise(&self) -> Option<SignificantChange> {
+        match compare_to_threshold(self.change(), self.noise_threshold) {
+            ComparisonResult::Improved => Some(SignificantChange::Improved),
+            ComparisonResult::Regressed => Some(SignificantChange::Regressed),
+            ComparisonResult::NonSignificant => None,
+        }
+    }
+}
+
+/// A change of performance that is statistically significant and larger than the noise threshold,
+/// which the comparison plots mark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum SignificantChange {
+    Improved,
+    Regressed,
+}
 
 pub(crate) struct MeasurementData<'a> {
     pub data: Data<'a, f64, f64>,
@@ -42,6 +102,10 @@ pub(crate) struct MeasurementData<'a> {
     pub distributions: Distributions,
     pub comparison: Option<ComparisonData>,
     pub throughput: Option<Throughput>,
+    pub manifest: Manifest,
+    pub heap: Option<HeapProfile>,
+    /// The diagnostics of the order of the samples.
+    pub drift: Option<Drift>,
 }
 impl<'a> MeasurementData<'a> {
     pub fn iter_counts(&self) -> &Sample<f64> {
@@ -67,6 +131,9 @@ pub struct BenchmarkId {
     pub function_id: Option<String>,
     pub value_str: Option<String>,
     pub throughput: Option<Throughput>,
+    /// Tags of the benchmark and its group, see `BenchmarkGroup::tag`.
+    #[serde(default)]
+    pub tags: Vec<String>,
     full_id: String,
     directory_name: String,
     title: String,
@@ -153,6 +220,7 @@ impl BenchmarkId {
             function_id,
             value_str,
             throughput,
+            tags: vec![],
             full_id,
             directory_name,
             title,
@@ -253,18 +321,35 @@ impl fmt::Debug for BenchmarkId {
     }
 }
 
+#[derive(Clone)]
 pub struct ReportContext {
     pub output_directory: PathBuf,
+    /// The directory the plots are written to, the output directory unless
+    /// `Criterion::plot_output_dir` is set.
+    pub plot_directory: PathBuf,
     pub plot_config: PlotConfiguration,
+    /// Whether to leave the plots rendering in the background (`--defer-plots`).
+    pub defer_plots: bool,
+    /// Whether to skip the summary pages whose inputs have not changed, see `html::cache`.
+    pub report_cache: bool,
+    /// The format the plots are rendered in.
+    pub plot_format: PlotFormat,
+    /// The percentiles marked on the violin plots, see `Criterion::percentiles`.
+    pub percentiles: Vec<f64>,
 }
 impl ReportContext {
     pub fn report_path<P: AsRef<Path> + ?Sized>(&self, id: &BenchmarkId, file_name: &P) -> PathBuf {
-        let mut path = self.output_directory.clone();
+        let mut path = self.plot_directory.clone();
         path.push(id.as_directory_name());
         path.push("report");
         path.push(file_name);
         path
     }
+
+    /// Returns the path of the plot `name`, with the extension of the plot format.
+    pub fn plot_path(&self, id: &BenchmarkId, name: &str) -> PathBuf {
+        self.report_path(id, &format!("{}.{}", name, self.plot_format.extension()))
+    }
 }
 
 pub(crate) trait Report {
@@ -273,6 +358,15 @@ pub(crate) trait Report {
 
     fn benchmark_start(&self, _id: &BenchmarkId, _context: &ReportContext) {}
     fn profile(&self, _id: &BenchmarkId, _context: &ReportContext, _profile_ns: f64) {}
+    fn heap_profile(&self, _id: &BenchmarkId, _context: &ReportContext, _iterations: u64) {}
+    /// Called with the files written by the profiler after a benchmark has been profiled.
+    fn profile_artifacts(
+        &self,
+        _id: &BenchmarkId,
+        _context: &ReportContext,
+        _artifacts: &[PathBuf],
+    ) {
+    }
     fn warmup(&self, _id: &BenchmarkId, _context: &ReportContext, _warmup_ns: f64) {}
     fn terminated(&self, _id: &BenchmarkId, _context: &ReportContext) {}
     fn analysis(&self, _id: &BenchmarkId, _context: &ReportContext) {}
@@ -300,8 +394,17 @@ pub(crate) trait Report {
         _formatter: &dyn ValueFormatter,
     ) {
     }
+    fn rankings(
+        &self,
+        _context: &ReportContext,
+        _rankings: &[Ranking],
+        _formatter: &dyn ValueFormatter,
+    ) {
+    }
     fn final_summary(&self, _context: &ReportContext) {}
     fn group_separator(&self) {}
+    fn noise_floor(&self, _noise_floor: &NoiseFloor) {}
+    fn environment(&self, _findings: &[Finding]) {}
 }
 
 pub(crate) struct Reports {
@@ -310,7 +413,18 @@ pub(crate) struct Reports {
     pub(crate) bencher_enabled: bool,
     pub(crate) bencher: BencherReport,
     pub(crate) csv_enabled: bool,
+    pub(crate) markdown_enabled: bool,
+    pub(crate) badges_enabled: bool,
     pub(crate) html: Option<Html>,
+    pub(crate) sink: Option<SinkReport>,
+    pub(crate) json: Option<JsonReport>,
+    pub(crate) junit: Option<JunitReport>,
+    pub(crate) custom: Vec<CustomReport>,
+    pub(crate) metrics: Vec<MetricsReport>,
+    #[cfg(feature = "csv_output")]
+    pub(crate) csv_export: Option<CsvExport>,
+    #[cfg(feature = "sqlite")]
+    pub(crate) history_db: Option<HistoryDatabaseReport>,
 }
 macro_rules! reports_impl {
     (fn $name:ident(&self, $($argn:ident: $argt:ty),*)) => {
@@ -325,9 +439,38 @@ macro_rules! reports_impl {
             if self.csv_enabled {
                 FileCsvReport.$name($($argn),*);
             }
+            if self.markdown_enabled {
+                MarkdownReport.$name($($argn),*);
+            }
+            if self.badges_enabled {
+                BadgeReport.$name($($argn),*);
+            }
             if let Some(reporter) = &self.html {
                 reporter.$name($($argn),*);
             }
+            if let Some(sink) = &self.sink {
+                sink.$name($($argn),*);
+            }
+            if let Some(json) = &self.json {
+                json.$name($($argn),*);
+            }
+            if let Some(junit) = &self.junit {
+                junit.$name($($argn),*);
+            }
+            for report in &self.custom {
+                report.$name($($argn),*);
+            }
+            for metrics in &self.metrics {
+                metrics.$name($($argn),*);
+            }
+            #[cfg(feature = "csv_output")]
+            if let Some(csv_export) = &self.csv_export {
+                csv_export.$name($($argn),*);
+            }
+            #[cfg(feature = "sqlite")]
+            if let Some(history_db) = &self.history_db {
+                history_db.$name($($argn),*);
+            }
         }
     };
 }
@@ -337,6 +480,13 @@ impl Report for Reports {
     reports_impl!(fn test_pass(&self, id: &BenchmarkId, context: &ReportContext));
     reports_impl!(fn benchmark_start(&self, id: &BenchmarkId, context: &ReportContext));
     reports_impl!(fn profile(&self, id: &BenchmarkId, context: &ReportContext, profile_ns: f64));
+    reports_impl!(fn heap_profile(&self, id: &BenchmarkId, context: &ReportContext, iterations: u64));
+    reports_impl!(fn profile_artifacts(
+        &self,
+        id: &BenchmarkId,
+        context: &ReportContext,
+        artifacts: &[PathBuf]
+    ));
     reports_impl!(fn warmup(&self, id: &BenchmarkId, context: &ReportContext, warmup_ns: f64));
     reports_impl!(fn terminated(&self, id: &BenchmarkId, context: &ReportContext));
     reports_impl!(fn analysis(&self, id: &BenchmarkId, context: &ReportContext));
@@ -364,8 +514,18 @@ impl Report for Reports {
         formatter: &dyn ValueFormatter
     ));
 
+    reports_impl!(
+    fn rankings(
+        &self,
+        context: &ReportContext,
+        rankings: &[Ranking],
+        formatter: &dyn ValueFormatter
+    ));
+
     reports_impl!(fn final_summary(&self, context: &ReportContext));
     reports_impl!(fn group_separator(&self, ));
+    reports_impl!(fn noise_floor(&self, noise_floor: &NoiseFloor));
+    reports_impl!(fn environment(&self, findings: &[Finding]));
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -379,6 +539,14 @@ pub(crate) struct CliReport {
     pub enable_text_overwrite: bool,
     pub enable_text_coloring: bool,
     pub verbosity: CliVerbosity,
+    /// Prefix status lines with the suite-wide progress (`--progress`).
+    pub show_progress: bool,
+    /// Only print results for benchmarks which improved or regressed (`--quiet-unless-changed`).
+    pub quiet_unless_changed: bool,
+    /// Draw histograms of the samples with these characters (`PlottingBackend::Terminal`).
+    pub terminal_plots: Option<Charset>,
+    /// The samples of the benchmarks of the current group, for its histograms.
+    samples: RefCell<Vec<(String, Vec<f64>)>>,
 }
 impl CliReport {
     pub fn new(
@@ -390,6 +558,10 @@ impl CliReport {
             enable_text_overwrite,
             enable_text_coloring,
             verbosity,
+            show_progress: false,
+            quiet_unless_changed: false,
+            terminal_plots: None,
+            samples: RefCell::new(Vec::new()),
         }
     }
 
@@ -399,9 +571,28 @@ impl CliReport {
         }
     }
 
+    /// The name of the benchmark `id` within its group, which labels its histogram in the summary
+    /// of the group.
+    fn short_name(id: &BenchmarkId) -> String {
+        match (&id.function_id, &id.value_str) {
+            (Some(function), Some(value)) => format!("{}/{}", function, value),
+            (Some(name), None) | (None, Some(name)) => name.clone(),
+            (None, None) => id.group_id.clone(),
+        }
+    }
+
     // Passing a String is the common case here.
     #[cfg_attr(feature = "cargo-clippy", allow(clippy::needless_pass_by_value))]
     fn print_overwritable(&self, s: String) {
+        let s = if self.show_progress {
+            format!("{} {}", progress::status(), s)
+        } else {
+            s
+        };
+        // Without overwriting, every status line would end up in the log for every benchmark.
+        if self.quiet_unless_changed && !self.enable_text_overwrite {
+            return;
+        }
         if self.verbosity != CliVerbosity::Quiet {
             if self.enable_text_overwrite {
                 eprint!("{}", s);
@@ -448,7 +639,9 @@ impl CliReport {
         }
     }
 
-    pub fn outliers(&self, sample: &LabeledSample<'_, f64>) {
+    /// Prints the outliers of the sample, and how they were treated by the outlier policy, which is
+    /// given as recorded in the manifest.
+    pub fn outliers(&self, sample: &LabeledSample<'_, f64>, policy: &str) {
         let (los, lom, _, him, his) = sample.count();
         let noutliers = los + lom + him + his;
         let sample_size = sample.len();
@@ -479,6 +672,11 @@ impl CliReport {
         print(lom, "low mild");
         print(him, "high mild");
         print(his, "high severe");
+        match policy {
+            "Winsorize" => println!("  winsorized to the inner fences before the analysis"),
+            "Trim" => println!("  left out of the analysis"),
+            _ => {}
+        }
     }
 }
 impl Report for CliReport {
@@ -511,6 +709,24 @@ impl Report for CliReport {
         ));
     }
 
+    fn heap_profile(&self, id: &BenchmarkId, _: &ReportContext, iterations: u64) {
+        self.text_overwrite();
+        self.print_overwritable(format!(
+            "Benchmarking {}: Counting allocations for {}",
+            id,
+            format::iter_count(iterations)
+        ));
+    }
+
+    fn profile_artifacts(&self, id: &BenchmarkId, _: &ReportContext, artifacts: &[PathBuf]) {
+        self.text_overwrite();
+        let paths: Vec<_> = artifacts
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        println!("Benchmarking {}: Profile saved to {}", id, paths.join(", "));
+    }
+
     fn terminated(&self, id: &BenchmarkId, _: &ReportContext) {
         self.text_overwrite();
         println!("Benchmarking {}: Complete (Analysis Disabled)", id);
@@ -554,6 +770,18 @@ impl Report for CliReport {
     ) {
         self.text_overwrite();
 
+        // Benchmarks without a baseline are new, which is worth reporting.
+        if let (true, Some(comp)) = (self.quiet_unless_changed, &meas.comparison) {
+            let unchanged = comp.p_value >= comp.significance_threshold
+                || matches!(
+                    compare_to_threshold(comp.change(), comp.noise_threshold),
+                    ComparisonResult::NonSignificant
+                );
+            if unchanged {
+                return;
+            }
+        }
+
         let typical_estimate = &meas.absolute_estimates.typical();
 
         {
@@ -596,10 +824,28 @@ impl Report for CliReport {
             )
         }
 
+        if let Some(heap) = &meas.heap {
+            println!("{}alloc:  {}", " ".repeat(24), heap.summary());
+        }
+
+        if self.verbosity != CliVerbosity::Quiet {
+            for percentile in &meas.absolute_estimates.percentiles {
+                let estimate = &percentile.estimate;
+                println!(
+                    "{}{:<8}[{} {} {}]",
+                    " ".repeat(24),
+                    format!("p{}:", percentile.percentile),
+                    self.faint(formatter.format_value(estimate.confidence_interval.lower_bound)),
+                    self.bold(formatter.format_value(estimate.point_estimate)),
+                    self.faint(formatter.format_value(estimate.confidence_interval.upper_bound))
+                );
+            }
+        }
+
         if !matches!(self.verbosity, CliVerbosity::Quiet) {
             if let Some(ref comp) = meas.comparison {
                 let different_mean = comp.p_value < comp.significance_threshold;
-                let mean_est = &comp.relative_estimates.mean;
+                let mean_est = comp.change();
                 let point_estimate = mean_est.point_estimate;
                 let mut point_estimate_str = format::change(point_estimate, true);
                 // The change in throughput is related to the change in timing. Reducing the timing by
@@ -685,11 +931,41 @@ impl Report for CliReport {
                 }
 
                 println!("{}{}", " ".repeat(24), explanation_str);
+                if let Some(noise_floor) = noise_floor::current() {
+                    if different_mean && point_estimate.abs() < noise_floor.relative() {
+                        println!(
+                            "{}Change is smaller than the machine noise floor ({}).",
+                            " ".repeat(24),
+                            noise_floor::format_relative(noise_floor.relative())
+                        );
+                    }
+                }
             }
         }
 
+        if let Some(charset) = self
+            .terminal_plots
+            .filter(|_| self.verbosity != CliVerbosity::Quiet)
+        {
+            let values: &[f64] = &meas.avg_times;
+            let (min, max) = terminal_plot::range(values);
+            println!(
+                "{}dist:   {} {} {}",
+                " ".repeat(24),
+                self.faint(formatter.format_value(min)),
+                terminal_plot::histogram(values, (min, max), terminal_plot::WIDTH, charset),
+                self.faint(formatter.format_value(max)),
+            );
+            self.samples
+                .borrow_mut()
+                .push((Self::short_name(id), values.to_vec()));
+        }
+
         if !matches!(self.verbosity, CliVerbosity::Quiet) {
-            self.outliers(&meas.avg_times);
+            self.outliers(&meas.avg_times, &meas.manifest.config.outlier_policy);
+            for warning in meas.drift.iter().flat_map(Drift::warnings) {
+                println!("{}", self.yellow(&format!("Warning: {}", warning)));
+            }
         }
 
         if matches!(self.verbosity, CliVerbosity::Verbose) {
@@ -729,12 +1005,175 @@ impl Report for CliReport {
         }
     }
 
+    fn summarize(
+        &self,
+        _context: &ReportContext,
+        _all_ids: &[BenchmarkId],
+        formatter: &dyn ValueFormatter,
+    ) {
+        let charset = match self.terminal_plots {
+            Some(charset) => charset,
+            None => return,
+        };
+        let samples = self.samples.borrow();
+        if samples.len() < 2 {
+            return;
+        }
+        let (min, max) = terminal_plot::range(samples.iter().flat_map(|(_, values)| values));
+        let width = samples
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        println!("Distributions:");
+        for (name, values) in samples.iter() {
+            println!(
+                "  {:<width$}  {}",
+                name,
+                terminal_plot::histogram(values, (min, max), terminal_plot::WIDTH, charset),
+                width = width
+            );
+        }
+        let (min, max) = (formatter.format_value(min), formatter.format_value(max));
+        let gap = terminal_plot::WIDTH
+            .saturating_sub(min.chars().count() + max.chars().count())
+            .max(1);
+        println!(
+            "  {:<width$}  {}{}{}",
+            "",
+            self.faint(min),
+            " ".repeat(gap),
+            self.faint(max),
+            width = width
+        );
+    }
+
     fn group_separator(&self) {
+        self.samples.borrow_mut().clear();
         println!();
     }
+
+    fn rankings(
+        &self,
+        _context: &ReportContext,
+        rankings: &[Ranking],
+        formatter: &dyn ValueFormatter,
+    ) {
+        if self.verbosity == CliVerbosity::Quiet {
+            return;
+        }
+        for ranking in rankings {
+            println!("Ranking for {}:", self.green(&ranking.title()));
+            let width = ranking
+                .entries
+                .iter()
+                .map(|entry| entry.function_id.len())
+                .max()
+                .unwrap_or(0);
+            for entry in &ranking.entries {
+                let verdict = if entry.rank == 1 {
+                    self.green("winner")
+                } else if entry.tied_with_winner {
+                    self.yellow("tied with winner")
+                } else {
+                    String::new()
+                };
+                println!(
+                    "  {:>2}. {:<width$}  [{} {} {}]  {:>7}  {}",
+                    entry.rank,
+                    entry.function_id,
+                    self.faint(formatter.format_value(entry.lower_bound)),
+                    self.bold(formatter.format_value(entry.mean)),
+                    self.faint(formatter.format_value(entry.upper_bound)),
+                    format!("{:.2}x", entry.ratio),
+                    verdict,
+                    width = width
+                );
+            }
+        }
+    }
+
+    fn noise_floor(&self, noise_floor: &NoiseFloor) {
+        match (noise_floor.end, noise_floor.drift()) {
+            (Some(end), Some(drift)) => println!(
+                "Machine noise floor for this session: {} (start {}, end {}, drift {})",
+                self.bold(noise_floor::format_relative(noise_floor.relative())),
+                noise_floor::format_relative(noise_floor.start.relative_mad),
+                noise_floor::format_relative(end.relative_mad),
+                noise_floor::format_relative(drift),
+            ),
+            _ => println!(
+                "Machine noise floor: {}",
+                self.bold(noise_floor::format_relative(noise_floor.relative()))
+            ),
+        }
+    }
+
+    fn environment(&self, findings: &[Finding]) {
+        if findings.is_empty() {
+            println!("Environment check: no checks are supported on this platform");
+            return;
+        }
+        for finding in findings {
+            match &finding.warning {
+                Some(warning) => println!(
+                    "{}: {} - {}",
+                    self.yellow(&format!("Warning: {}", finding.name)),
+                    finding.value,
+                    warning
+                ),
+                None if self.verbosity == CliVerbosity::Verbose => {
+                    println!("{}: {}", finding.name, finding.value)
+                }
+                None => {}
+            }
+        }
+        let warnings = findings.iter().filter(|f| f.warning.is_some()).count();
+        if warnings == 0 {
+            println!("Environment check: {} checks passed", findings.len());
+        }
+    }
+}
+
+/// Prints results in the format of the `bencher` crate and of libtest benchmarks, which is
+/// understood by tools such as github-action-benchmark.
+#[derive(Default)]
+pub struct BencherReport {
+    /// Print IDs as `group::function::parameter`, like the paths of libtest benchmarks, rather than
+    /// as `group/function/parameter`.
+    pub flatten_ids: bool,
 }
+impl BencherReport {
+    fn name(&self, id: &BenchmarkId) -> String {
+        if !self.flatten_ids {
+            return id.to_string();
+        }
+        std::iter::once(&id.group_id)
+            .chain(id.function_id.as_ref())
+            .chain(id.value_str.as_ref())
+            .map(|part| part.split_whitespace().collect::<Vec<_>>().join("_"))
+            .collect::<Vec<_>>()
+            .join("::")
+    }
 
-pub struct BencherReport;
+    /// Formats the throughput like libtest does, eg. ` = 120 MB/s`. Throughput can only be computed
+    /// from measurements in nanoseconds.
+    fn throughput(throughput: Option<&Throughput>, unit: &str, median: f64) -> String {
+        if unit != "ns" || median <= 0.0 {
+            return String::new();
+        }
+        match throughput {
+            Some(Throughput::Bytes(bytes)) | Some(Throughput::BytesDecimal(bytes)) => {
+                format!(" = {} MB/s", format::integer(*bytes as f64 * 1e3 / median))
+            }
+            Some(Throughput::Elements(elements)) => format!(
+                " = {} elem/s",
+                format::integer(*elements as f64 * 1e9 / median)
+            ),
+            None => String::new(),
+        }
+    }
+}
 impl Report for BencherReport {
     fn measurement_start(
         &self,
@@ -744,7 +1183,7 @@ impl Report for BencherReport {
         _estimate_ns: f64,
         _iter_count: u64,
     ) {
-        print!("test {} ... ", id);
+        print!("test {} ... ", self.name(id));
     }
 
     fn measurement_complete(
@@ -761,10 +1200,11 @@ impl Report for BencherReport {
         let unit = formatter.scale_for_machines(&mut values);
 
         println!(
-            "bench: {:>11} {}/iter (+/- {})",
+            "bench: {:>11} {}/iter (+/- {}){}",
             format::integer(values[0]),
             unit,
-            format::integer(values[1])
+            format::integer(values[1]),
+            BencherReport::throughput(meas.throughput.as_ref(), unit, values[0])
         );
     }
 
@@ -797,6 +1237,32 @@ fn compare_to_threshold(estimate: &Estimate, noise: f64) -> ComparisonResult {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_bencher_names_and_throughput() {
+        let id = BenchmarkId::new(
+            "parse json".to_owned(),
+            Some("serde".to_owned()),
+            Some("1 KiB".to_owned()),
+            None,
+        );
+        assert_eq!(BencherReport::default().name(&id), "parse json/serde/1 KiB");
+        let flat = BencherReport { flatten_ids: true };
+        assert_eq!(flat.name(&id), "parse_json::serde::1_KiB");
+
+        let bytes = Throughput::Bytes(1000);
+        assert_eq!(
+            BencherReport::throughput(Some(&bytes), "ns", 500.0),
+            " = 2000 MB/s"
+        );
+        let elements = Throughput::Elements(10);
+        assert_eq!(
+            BencherReport::throughput(Some(&elements), "ns", 100.0),
+            " = 100000000 elem/s"
+        );
+        assert_eq!(BencherReport::throughput(Some(&bytes), "cycles", 500.0), "");
+        assert_eq!(BencherReport::throughput(None, "ns", 500.0), "");
+    }
+
     #[test]
     fn test_make_filename_safe_replaces_characters() {
         let input = "?/\\*\"";
@@ -0,0 +1,148 @@
+//! Resuming interrupted runs (`--resume`).
+//!
+//! Every run starts a session, whose ID is saved as `session.json` in the output directory. When a
+//! benchmark has been measured and analyzed, a completion marker holding the session ID and a hash
+//! of the benchmark's configuration is saved next to its results. `--resume` continues the last
+//! session instead of starting a new one, and skips the benchmarks whose marker belongs to that
+//! session and whose configuration has not changed since. Benchmarks completed in earlier sessions
+//! are run again, so that the results of one session are never mixed with those of another.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::clock;
+use crate::fs;
+use crate::report::BenchmarkId;
+
+const SESSION_FILE: &str = "session.json";
+const MARKER_FILE: &str = "completed.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Session {
+    id: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Marker {
+    session: u64,
+    config_hash: String,
+}
+
+/// Session IDs by output directory. Every group constructs its own `Criterion` struct, so this is
+/// a process-wide static.
+static SESSIONS: Lazy<Mutex<HashMap<PathBuf, u64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Returns the ID of the session for the given output directory, starting a new session or, if
+/// `resume` is true, continuing the previous one when first called.
+fn session(output_directory: &Path, resume: bool) -> u64 {
+    let mut sessions = SESSIONS.lock().unwrap();
+    if let Some(&id) = sessions.get(output_directory) {
+        return id;
+    }
+
+    let path = output_directory.join(SESSION_FILE);
+    let previous = if resume {
+        fs::load::<Session, _>(&path).ok()
+    } else {
+        None
+    };
+    let id = match previous {
+        Some(session) => {
+            println!("Resuming the previous run; completed benchmarks will be skipped.");
+            session.id
+        }
+        None => {
+            if resume {
+                println!("No interrupted run to resume; running all benchmarks.");
+            }
+            let id = clock::since_epoch().as_nanos() as u64;
+            log_if_err!(fs::mkdirp(&output_directory));
+            log_if_err!(fs::save(&Session { id }, &path));
+            id
+        }
+    };
+    sessions.insert(output_directory.to_owned(), id);
+    id
+}
+
+/// Hashes everything which determines the results of a benchmark apart from the code being
+/// benchmarked: its ID, the resolved settings (as described by `--dry-run`) and the measurement.
+pub(crate) fn config_hash(id: &BenchmarkId, config: &str, measurement: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    id.id().hash(&mut hasher);
+    config.hash(&mut hasher);
+    measurement.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+fn marker_path(output_directory: &Path, id: &BenchmarkId) -> PathBuf {
+    output_directory
+        .join(id.as_directory_name())
+        .join("new")
+        .join(MARKER_FILE)
+}
+
+/// Returns true if the benchmark was completed in the resumed session with the same configuration.
+pub(crate) fn is_completed(
+    output_directory: &Path,
+    id: &BenchmarkId,
+    config_hash: &str,
+    resume: bool,
+) -> bool {
+    let session = session(output_directory, resume);
+    if !resume {
+        return false;
+    }
+    match fs::load::<Marker, _>(&marker_path(output_directory, id)) {
+        Ok(marker) => marker.session == session && marker.config_hash == config_hash,
+        Err(_) => false,
+    }
+}
+
+/// Records that the benchmark has been completed in the current session.
+pub(crate) fn mark_completed(output_directory: &Path, id: &BenchmarkId, config_hash: &str) {
+    let marker = Marker {
+        session: session(output_directory, false),
+        config_hash: config_hash.to_owned(),
+    };
+    log_if_err!(fs::save(&marker, &marker_path(output_directory, id)));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Forgets the session of `dir`, as if the benchmarks were run again by a new process.
+    fn new_process(dir: &Path) {
+        SESSIONS.lock().unwrap().remove(dir);
+    }
+
+    #[test]
+    fn resume_skips_benchmarks_of_the_same_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let id = BenchmarkId::new("group".to_owned(), Some("function".to_owned()), None, None);
+        let hash = config_hash(&id, "sample size 10", "WallTime");
+
+        assert!(!is_completed(dir, &id, &hash, false));
+        fs::mkdirp(&dir.join(id.as_directory_name()).join("new")).unwrap();
+        mark_completed(dir, &id, &hash);
+
+        new_process(dir);
+        assert!(is_completed(dir, &id, &hash, true));
+        let other_hash = config_hash(&id, "sample size 20", "WallTime");
+        assert!(!is_completed(dir, &id, &other_hash, true));
+
+        // A run without --resume starts a new session, after which the marker is stale.
+        new_process(dir);
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        assert!(!is_completed(dir, &id, &hash, false));
+        new_process(dir);
+        assert!(!is_completed(dir, &id, &hash, true));
+    }
+}
@@ -0,0 +1,120 @@
+//! Baseline retention policy (`--keep-baselines` and `--gc-baselines`).
+//!
+//! Every saved baseline is a directory inside the benchmark's output directory, next to `new`,
+//! `change` and `report`. Without a policy these accumulate forever, so this module removes all
+//! but the most recently saved baselines of each benchmark and compresses the raw samples of the
+//! kept baselines which are unlikely to be used again soon.
+
+use std::cmp::Reverse;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use walkdir::WalkDir;
+
+use crate::error::Result;
+use crate::fs;
+
+/// Number of baselines kept by `--gc-baselines` if `--keep-baselines` is not given.
+pub(crate) const DEFAULT_KEEP: usize = 5;
+
+/// Directories inside a benchmark's output directory which are not baselines.
+const NOT_BASELINES: &[&str] = &["new", "change", "report"];
+
+/// Raw-sample files which are compressed in older baselines. `fs::load` reads the compressed
+/// version transparently.
+const RAW_SAMPLE_FILES: &[&str] = &["sample.json", "raw.csv"];
+
+#[derive(Debug, Default)]
+pub(crate) struct GcSummary {
+    pub removed: usize,
+    pub compressed: usize,
+}
+
+/// Returns the baselines of the benchmark whose output directory is `benchmark_dir`, most recently
+/// saved first.
+fn baselines(benchmark_dir: &Path) -> Vec<PathBuf> {
+    let mut baselines: Vec<(SystemTime, PathBuf)> = vec![];
+    for entry in WalkDir::new(benchmark_dir)
+        .min_depth(1)
+        .max_depth(1)
+        .into_iter()
+        .filter_map(::std::result::Result::ok)
+        .filter(|entry| entry.file_type().is_dir())
+    {
+        let name = entry.file_name().to_string_lossy();
+        let estimates = entry.path().join("estimates.json");
+        if NOT_BASELINES.contains(&&*name) || !estimates.is_file() {
+            continue;
+        }
+        let saved = estimates
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        baselines.push((saved, entry.path().to_owned()));
+    }
+    baselines.sort_by_key(|(saved, _)| Reverse(*saved));
+    baselines.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Keeps the `keep` most recently saved baselines of one benchmark and removes the others. The
+/// `active` baseline (the one being saved to or compared against) is always kept and counts
+/// towards `keep`. The raw samples of every kept baseline other than the active one and the most
+/// recent one are compressed.
+pub(crate) fn apply(benchmark_dir: &Path, keep: usize, active: &str) -> Result<GcSummary> {
+    let mut summary = GcSummary::default();
+    let baselines = baselines(benchmark_dir);
+    let is_active = |path: &PathBuf| path.file_name() == Some(OsStr::new(active));
+
+    let mut kept = usize::from(baselines.iter().any(is_active));
+    for (i, baseline) in baselines.iter().enumerate() {
+        if is_active(baseline) {
+            continue;
+        }
+        if kept >= keep {
+            fs::remove_dir_all(baseline)?;
+            summary.removed += 1;
+            continue;
+        }
+        kept += 1;
+        if i == 0 {
+            continue;
+        }
+        for file in RAW_SAMPLE_FILES {
+            let path = baseline.join(file);
+            if path.is_file() {
+                fs::gzip(&path)?;
+                summary.compressed += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Applies the retention policy to every benchmark in the output directory.
+pub(crate) fn collect_garbage(
+    output_directory: &Path,
+    keep: usize,
+    active: &str,
+) -> Result<GcSummary> {
+    // Benchmark directories are the parents of the `new` directories holding a benchmark.json.
+    // They are collected first so that nothing is removed while the walk is still in progress.
+    let benchmark_dirs: Vec<PathBuf> = WalkDir::new(output_directory)
+        .into_iter()
+        .filter_map(::std::result::Result::ok)
+        .filter(|entry| {
+            entry.file_type().is_dir()
+                && entry.file_name() == "new"
+                && entry.path().join("benchmark.json").is_file()
+        })
+        .filter_map(|entry| entry.path().parent().map(Path::to_owned))
+        .collect();
+
+    let mut summary = GcSummary::default();
+    for benchmark_dir in benchmark_dirs {
+        let benchmark_summary = apply(&benchmark_dir, keep, active)?;
+        summary.removed += benchmark_summary.removed;
+        summary.compressed += benchmark_summary.compressed;
+    }
+    Ok(summary)
+}
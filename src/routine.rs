@@ -1,7 +1,14 @@
+use crate::analysis;
 use crate::benchmark::BenchmarkConfig;
 use crate::connection::OutgoingMessage;
+use crate::hooks;
 use crate::measurement::Measurement;
+use crate::perf_markers;
+use crate::plot;
+use crate::profiler::ProfilingContext;
 use crate::report::{BenchmarkId, Report, ReportContext};
+use crate::spill::{self, SpillWriter, Values};
+use crate::streaming::{Moments, Reservoir};
 use crate::{black_box, ActualSamplingMode, Bencher, Criterion};
 use std::marker::PhantomData;
 use std::time::Duration;
@@ -10,6 +17,13 @@ use std::time::Duration;
 pub(crate) trait Routine<M: Measurement, T: ?Sized> {
     /// PRIVATE
     fn bench(&mut self, m: &M, iters: &[u64], parameter: &T) -> Vec<f64>;
+    /// Like `bench`, but passes each value to `sample` as soon as it has been measured, for
+    /// `--spill-samples`.
+    fn bench_each(&mut self, m: &M, iters: &[u64], parameter: &T, sample: &mut dyn FnMut(f64)) {
+        for value in self.bench(m, iters, parameter) {
+            sample(value);
+        }
+    }
     /// PRIVATE
     fn warm_up(&mut self, m: &M, how_long: Duration, parameter: &T) -> (u64, u64);
 
@@ -23,15 +37,18 @@ pub(crate) trait Routine<M: Measurement, T: ?Sized> {
     /// profiler with an unknown amount of overhead. Since no measurements are taken, it also
     /// reduces the amount of time the execution spends in Criterion.rs code, which should help
     /// show the performance of the benchmarked code more clearly as well.
+    #[allow(clippy::too_many_arguments)]
     fn profile(
         &mut self,
         measurement: &M,
         id: &BenchmarkId,
+        config: &BenchmarkConfig,
         criterion: &Criterion<M>,
         report_context: &ReportContext,
         time: Duration,
         parameter: &T,
     ) {
+        enter_span!("profile", nanos = time.as_nanos() as u64);
         criterion
             .report
             .profile(id, report_context, time.as_nanos() as f64);
@@ -46,10 +63,19 @@ pub(crate) trait Routine<M: Measurement, T: ?Sized> {
             profile_path.push(id.as_directory_name());
             profile_path.push("profile");
         }
+        let context = ProfilingContext {
+            benchmark_id: id.id(),
+            group_id: &id.group_id,
+            function_id: id.function_id.as_deref(),
+            value_str: id.value_str.as_deref(),
+            benchmark_dir: &profile_path,
+            profile_time: time,
+            config,
+        };
         criterion
             .profiler
             .borrow_mut()
-            .start_profiling(id.id(), &profile_path);
+            .start_benchmark_profiling(&context);
 
         let time = time.as_nanos() as u64;
 
@@ -74,7 +100,13 @@ pub(crate) trait Routine<M: Measurement, T: ?Sized> {
         criterion
             .profiler
             .borrow_mut()
-            .stop_profiling(id.id(), &profile_path);
+            .stop_benchmark_profiling(&context);
+        let artifacts = criterion.profiler.borrow_mut().artifacts(&context);
+        if !artifacts.is_empty() {
+            criterion
+                .report
+                .profile_artifacts(id, report_context, &artifacts);
+        }
 
         criterion.report.terminated(id, report_context);
     }
@@ -87,17 +119,22 @@ pub(crate) trait Routine<M: Measurement, T: ?Sized> {
         criterion: &Criterion<M>,
         report_context: &ReportContext,
         parameter: &T,
-    ) -> (ActualSamplingMode, Box<[f64]>, Box<[f64]>) {
+    ) -> (ActualSamplingMode, Values, Values, Option<Moments>) {
         if config.quick_mode {
+            enter_span!("quick_measurement");
             let minimum_bench_duration = Duration::from_millis(100);
             let maximum_bench_duration = config.measurement_time; // default: 5 seconds
             let target_rel_stdev = config.significance_level; // default: 5%, 0.05
 
-            use std::time::Instant;
+            use crate::clock::Instant;
             let time_start = Instant::now();
 
             let sq = |val| val * val;
             let mut n = 1;
+            plot::wait_until_quiet();
+            let _measuring = analysis::measuring();
+            let _hooks = hooks::activate(id.as_title(), &criterion.batch_hooks);
+            perf_markers::measurement(id);
             let mut t_prev = *self.bench(measurement, &[n], parameter).first().unwrap();
 
             // Early exit for extremely long running benchmarks:
@@ -105,7 +142,8 @@ pub(crate) trait Routine<M: Measurement, T: ?Sized> {
                 let t_prev = 1_000_000f64;
                 let iters = vec![n as f64, n as f64].into_boxed_slice();
                 let elapsed = vec![t_prev, t_prev].into_boxed_slice();
-                return (ActualSamplingMode::Flat, iters, elapsed);
+                perf_markers::end();
+                return (ActualSamplingMode::Flat, iters.into(), elapsed.into(), None);
             }
 
             // Main data collection loop.
@@ -123,7 +161,13 @@ pub(crate) trait Routine<M: Measurement, T: ?Sized> {
                 {
                     let iters = vec![n as f64, (n * 2) as f64].into_boxed_slice();
                     let elapsed = vec![t_prev, t_now].into_boxed_slice();
-                    return (ActualSamplingMode::Linear, iters, elapsed);
+                    perf_markers::end();
+                    return (
+                        ActualSamplingMode::Linear,
+                        iters.into(),
+                        elapsed.into(),
+                        None,
+                    );
                 }
                 n *= 2;
                 t_prev = t_now;
@@ -144,7 +188,11 @@ pub(crate) trait Routine<M: Measurement, T: ?Sized> {
             .unwrap();
         }
 
-        let (wu_elapsed, wu_iters) = self.warm_up(measurement, wu, parameter);
+        let (wu_elapsed, wu_iters) = {
+            enter_span!("warm_up", nanos = wu.as_nanos() as u64);
+            perf_markers::warm_up(id);
+            self.warm_up(measurement, wu, parameter)
+        };
         if crate::debug_enabled() {
             println!(
                 "\nCompleted {} iterations in {} nanoseconds, estimated execution time is {} ns",
@@ -163,7 +211,7 @@ pub(crate) trait Routine<M: Measurement, T: ?Sized> {
             .sampling_mode
             .choose_sampling_mode(met, n, m_ns as f64);
 
-        let m_iters = actual_sampling_mode.iteration_counts(met, n, &config.measurement_time);
+        let mut m_iters = actual_sampling_mode.iteration_counts(met, n, &config.measurement_time);
 
         let expected_ns = m_iters
             .iter()
@@ -191,14 +239,178 @@ pub(crate) trait Routine<M: Measurement, T: ?Sized> {
             .unwrap();
         }
 
-        let m_elapsed = self.bench(measurement, &m_iters, parameter);
+        // The other ways of sampling keep their own samples in memory.
+        let in_memory = config.target_precision.is_some() || config.streaming_statistics;
+        let spill = if criterion.spill_samples && !in_memory {
+            SpillWriter::create().map_err(|e| spill::warn(&e)).ok()
+        } else {
+            None
+        };
 
-        let m_iters_f: Vec<f64> = m_iters.iter().map(|&x| x as f64).collect();
+        let mut moments = None;
+        let m_elapsed: Values = {
+            enter_span!("measurement", samples = n, iterations = total_iters);
+            plot::wait_until_quiet();
+            let _measuring = analysis::measuring();
+            let _hooks = hooks::activate(id.as_title(), &criterion.batch_hooks);
+            perf_markers::measurement(id);
+            let m_elapsed = match (config.target_precision, spill) {
+                (None, _) if config.streaming_statistics => {
+                    let mut reservoir = Reservoir::new(config.bootstrap_seed);
+                    let mut counts = m_iters.iter();
+                    self.bench_each(measurement, &m_iters, parameter, &mut |value| {
+                        reservoir.push(*counts.next().unwrap(), value)
+                    });
+                    let (all, iters, elapsed) = reservoir.finish();
+                    moments = Some(all);
+                    m_iters = iters;
+                    elapsed.into()
+                }
+                (Some(precision), _) => {
+                    let (iters, elapsed) = sample_until_precise(
+                        self,
+                        measurement,
+                        &m_iters,
+                        precision,
+                        config.max_measurement_time,
+                        parameter,
+                    );
+                    m_iters = iters;
+                    elapsed.into()
+                }
+                (None, Some(mut spill)) => {
+                    let mut result = Ok(());
+                    self.bench_each(measurement, &m_iters, parameter, &mut |value| {
+                        if result.is_ok() {
+                            result = spill.push(value);
+                        }
+                    });
+                    result
+                        .and_then(|()| spill.finish())
+                        .map(Values::Spilled)
+                        .unwrap_or_else(|e| {
+                            panic!("Unable to spill the samples of {} to disk: {}", id, e)
+                        })
+                }
+                (None, None) => self.bench(measurement, &m_iters, parameter).into(),
+            };
+            perf_markers::end();
+            m_elapsed
+        };
+
+        let m_iters_f = match &m_elapsed {
+            Values::Spilled(_) => spill_iters(&m_iters)
+                .unwrap_or_else(|e| panic!("Unable to spill the samples of {} to disk: {}", id, e)),
+            Values::Memory(_) => m_iters.iter().map(|&x| x as f64).collect::<Vec<_>>().into(),
+        };
+
+        (actual_sampling_mode, m_iters_f, m_elapsed, moments)
+    }
+}
+
+/// The fewest samples taken with `Criterion::target_precision`.
+const MIN_PRECISE_SAMPLES: usize = 10;
+/// The z score of the 95% confidence interval of a normal distribution.
+const Z_95: f64 = 1.96;
+
+/// Measures the samples of the plan, repeating it if necessary, in batches of
+/// `MIN_PRECISE_SAMPLES` until the 95% confidence interval of the mean of the average times is
+/// within `precision` of the mean, or `max_time` has passed. Returns the iteration counts and the
+/// values of the samples measured.
+fn sample_until_precise<M: Measurement, T: ?Sized, R: Routine<M, T> + ?Sized>(
+    routine: &mut R,
+    measurement: &M,
+    plan: &[u64],
+    precision: f64,
+    max_time: Duration,
+    parameter: &T,
+) -> (Vec<u64>, Vec<f64>) {
+    let start = crate::clock::Instant::now();
+    let mut iters: Vec<u64> = vec![];
+    let mut values = vec![];
+    loop {
+        let batch: Vec<u64> = plan
+            .iter()
+            .cycle()
+            .skip(iters.len())
+            .take(MIN_PRECISE_SAMPLES)
+            .copied()
+            .collect();
+        values.extend(routine.bench(measurement, &batch, parameter));
+        iters.extend(batch);
+        if relative_half_width(&iters, &values) <= precision || start.elapsed() > max_time {
+            return (iters, values);
+        }
+    }
+}
 
+/// The half-width of the 95% confidence interval of the mean of the average times, relative to
+/// the mean, by the normal approximation.
+fn relative_half_width(iters: &[u64], values: &[f64]) -> f64 {
+    let avg_times: Vec<f64> = iters
+        .iter()
+        .zip(values)
+        .map(|(&iters, value)| value / iters as f64)
+        .collect();
+    let n = avg_times.len() as f64;
+    let mean = avg_times.iter().sum::<f64>() / n;
+    let variance = avg_times
+        .iter()
+        .map(|t| (t - mean) * (t - mean))
+        .sum::<f64>()
+        / (n - 1.0);
+    Z_95 * (variance / n).sqrt() / mean
+}
+
+/// Writes the iteration counts of the samples to a spill file.
+fn spill_iters(iters: &[u64]) -> std::io::Result<Values> {
+    let mut spill = SpillWriter::create()?;
+    for &iters in iters {
+        spill.push(iters as f64)?;
+    }
+    spill.finish().map(Values::Spilled)
+}
+
+/// Replays samples collected elsewhere, eg. on an embedded device with `criterion-core`, in place
+/// of measuring a function.
+pub(crate) struct Replay<'a> {
+    samples: &'a criterion_core::Samples,
+}
+impl<'a> Replay<'a> {
+    pub fn new(samples: &'a criterion_core::Samples) -> Replay<'a> {
+        Replay { samples }
+    }
+}
+impl<M: Measurement, T: ?Sized> Routine<M, T> for Replay<'_> {
+    fn bench(&mut self, _m: &M, iters: &[u64], _parameter: &T) -> Vec<f64> {
+        vec![0.0; iters.len()]
+    }
+
+    fn warm_up(&mut self, _m: &M, _how_long: Duration, _parameter: &T) -> (u64, u64) {
+        (1, 1)
+    }
+
+    fn sample(
+        &mut self,
+        _measurement: &M,
+        _id: &BenchmarkId,
+        _config: &BenchmarkConfig,
+        _criterion: &Criterion<M>,
+        _report_context: &ReportContext,
+        _parameter: &T,
+    ) -> (ActualSamplingMode, Values, Values, Option<Moments>) {
+        let to_f64 = |values: &[u64]| {
+            values
+                .iter()
+                .map(|&value| value as f64)
+                .collect::<Vec<_>>()
+                .into()
+        };
         (
-            actual_sampling_mode,
-            m_iters_f.into_boxed_slice(),
-            m_elapsed.into_boxed_slice(),
+            self.samples.method.into(),
+            to_f64(&self.samples.iters),
+            to_f64(&self.samples.values),
+            None,
         )
     }
 }
@@ -239,6 +451,12 @@ where
     T: ?Sized,
 {
     fn bench(&mut self, m: &M, iters: &[u64], parameter: &T) -> Vec<f64> {
+        let mut values = Vec::with_capacity(iters.len());
+        self.bench_each(m, iters, parameter, &mut |value| values.push(value));
+        values
+    }
+
+    fn bench_each(&mut self, m: &M, iters: &[u64], parameter: &T, sample: &mut dyn FnMut(f64)) {
         let f = &mut self.f;
         let prepare_f = &mut self.prepare;
 
@@ -250,16 +468,14 @@ where
             elapsed_time: Duration::from_millis(0),
         };
 
-        iters
-            .iter()
-            .map(|iters| {
-                b.iters = *iters;
-                (*prepare_f)(&mut b, black_box(parameter));
-                (*f)(&mut b, black_box(parameter));
-                b.assert_iterated();
-                m.to_f64(&b.value)
-            })
-            .collect()
+        for iters in iters {
+            b.iters = *iters;
+            (*prepare_f)(&mut b, black_box(parameter));
+            perf_markers::sample();
+            (*f)(&mut b, black_box(parameter));
+            b.assert_iterated();
+            sample(m.to_f64(&b.value));
+        }
     }
 
     fn warm_up(&mut self, m: &M, how_long: Duration, parameter: &T) -> (u64, u64) {
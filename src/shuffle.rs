@@ -0,0 +1,124 @@
+//! Randomized benchmark order (`--shuffle` and `--shuffle-seed`).
+//!
+//! Benchmarks are run as soon as they are defined, so the order of the benchmarks within a
+//! function can't be changed. What can be shuffled is the order of the benchmark groups passed to
+//! `criterion_main!` and of the target functions passed to `criterion_group!`. Since every group
+//! constructs its own `Criterion` struct, the random number generator is a process-wide static.
+//! It is consumed in program order, so the same seed always produces the same order.
+
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use oorandom::Rand64;
+
+use crate::clock;
+
+struct Shuffle {
+    seed: u64,
+    rng: Rand64,
+}
+
+static SHUFFLE: Lazy<Mutex<Option<Shuffle>>> = Lazy::new(|| {
+    let shuffle = seed_from_args(std::env::args()).map(|seed| {
+        let seed = seed.unwrap_or_else(|| {
+            let seed = clock::since_epoch().as_nanos() as u64;
+            // Printed to stderr so as not to break machine-readable output such as
+            // `--list --format json`.
+            eprintln!(
+                "Shuffling benchmarks with seed {} (rerun with --shuffle-seed {} to reproduce)",
+                seed, seed
+            );
+            seed
+        });
+        Shuffle {
+            seed,
+            rng: Rand64::new(u128::from(seed)),
+        }
+    });
+    Mutex::new(shuffle)
+});
+
+/// Returns `None` if the benchmarks should not be shuffled, `Some(None)` if they should be
+/// shuffled with a random seed and `Some(Some(seed))` if a seed was given. This has to look at the
+/// arguments directly because the order is needed before any `Criterion` struct is configured. An
+/// invalid seed is ignored here; it is reported by `Criterion::configure_from_args`.
+fn seed_from_args<I: Iterator<Item = String>>(args: I) -> Option<Option<u64>> {
+    let mut shuffle = None;
+    let mut args = args.skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--shuffle" {
+            shuffle = Some(shuffle.flatten());
+        } else if arg == "--shuffle-seed" {
+            shuffle = Some(args.next().and_then(|seed| seed.parse().ok()));
+        } else if let Some(seed) = arg.strip_prefix("--shuffle-seed=") {
+            shuffle = Some(seed.parse().ok());
+        }
+    }
+    shuffle
+}
+
+/// The seed of the shuffled order, or `None` if the benchmarks are run in the order they are
+/// defined.
+pub(crate) fn seed() -> Option<u64> {
+    SHUFFLE.lock().unwrap().as_ref().map(|shuffle| shuffle.seed)
+}
+
+/// Returns the order in which to run `len` items, ie. `0..len` if the benchmarks are not
+/// shuffled and a random permutation of it otherwise.
+pub(crate) fn order(len: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..len).collect();
+    if let Some(shuffle) = SHUFFLE.lock().unwrap().as_mut() {
+        permute(&mut order, &mut shuffle.rng);
+    }
+    order
+}
+
+/// Fisher-Yates shuffle.
+fn permute(order: &mut [usize], rng: &mut Rand64) {
+    for i in (1..order.len()).rev() {
+        let j = rng.rand_range(0..(i as u64 + 1)) as usize;
+        order.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        std::iter::once("bench")
+            .chain(args.iter().copied())
+            .map(str::to_owned)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn seed_is_parsed_from_args() {
+        assert_eq!(seed_from_args(args(&["--bench"])), None);
+        assert_eq!(seed_from_args(args(&["--shuffle"])), Some(None));
+        assert_eq!(
+            seed_from_args(args(&["--shuffle-seed", "42"])),
+            Some(Some(42))
+        );
+        assert_eq!(
+            seed_from_args(args(&["--shuffle", "--shuffle-seed=42"])),
+            Some(Some(42))
+        );
+    }
+
+    #[test]
+    fn same_seed_gives_same_order() {
+        let shuffled = |seed: u64| {
+            let mut order: Vec<usize> = (0..20).collect();
+            permute(&mut order, &mut Rand64::new(u128::from(seed)));
+            order
+        };
+        let order = shuffled(42);
+        assert_eq!(order, shuffled(42));
+
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,347 @@
+//! Result sinks (`Criterion::with_result_sink`).
+//!
+//! A sink receives a summary of the results of every benchmark as soon as it has been analyzed,
+//! eg. to post them back to a server or to hand them over to JavaScript when benchmarking in a web
+//! browser, where there is no terminal or file system for the usual reports. Closures taking a
+//! [`BenchmarkResult`] are sinks:
+//!
+//! ```
+//! use criterion::Criterion;
+//! use criterion::sink::BenchmarkResult;
+//!
+//! let criterion = Criterion::default().with_result_sink(|result: &BenchmarkResult| {
+//!     println!("{}: {} {}", result.id, result.mean.point_estimate, result.unit);
+//! });
+//! ```
+//!
+//! On `wasm32-unknown-unknown`, `ConsoleSink` is used by default. It logs the results to the
+//! JavaScript console, which is also where `wasm-bindgen-test` collects output from.
+//!
+//! To follow the progress of the benchmarks as well, eg. to feed a dashboard, implement
+//! [`Report`] and add it with `Criterion::add_report`. Its methods are called at each step of
+//! each benchmark, and all have empty default implementations:
+//!
+//! ```
+//! use criterion::Criterion;
+//! use criterion::sink::{BenchmarkResult, Comparison, Report};
+//!
+//! struct Dashboard;
+//! impl Report for Dashboard {
+//!     fn benchmark_start(&mut self, id: &str) {
+//!         println!("running {}", id);
+//!     }
+//!
+//!     fn comparison(&mut self, result: &BenchmarkResult, comparison: &Comparison) {
+//!         println!("{}: {:?}", result.id, comparison.result);
+//!     }
+//! }
+//!
+//! let criterion = Criterion::default().add_report(Box::new(Dashboard));
+//! ```
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::estimate::Estimate as InternalEstimate;
+use crate::format;
+use crate::measurement::ValueFormatter;
+use crate::report::{
+    BenchmarkId, MeasurementData, Report as InternalReport, ReportContext, SignificantChange,
+};
+
+/// Point estimate and confidence interval of a statistic.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Estimate {
+    /// The most likely value.
+    pub point_estimate: f64,
+    /// Lower bound of the confidence interval, by default the 95% interval.
+    pub lower_bound: f64,
+    /// Upper bound of the confidence interval.
+    pub upper_bound: f64,
+}
+impl Estimate {
+    pub(crate) fn new(estimate: &InternalEstimate, scale: f64) -> Estimate {
+        Estimate {
+            point_estimate: estimate.point_estimate * scale,
+            lower_bound: estimate.confidence_interval.lower_bound * scale,
+            upper_bound: estimate.confidence_interval.upper_bound * scale,
+        }
+    }
+}
+
+/// Summary of the results of one benchmark, passed to [`ResultSink::result`]. Implements
+/// `Serialize`, so it can be sent on as JSON.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct BenchmarkResult {
+    /// Full ID of the benchmark, eg. `my_group/my_function/100`.
+    pub id: String,
+    /// Unit of the estimates, eg. `ns` for the default wall-clock measurement.
+    pub unit: String,
+    /// Mean time (or other measured value) per iteration.
+    pub mean: Estimate,
+    /// Median time per iteration.
+    pub median: Estimate,
+    /// Standard deviation of the time per iteration.
+    pub std_dev: Estimate,
+    /// Total number of iterations measured.
+    pub iterations: u64,
+    /// Relative change of the mean, or of the median with `Estimator::Median`, compared to the
+    /// baseline, eg. `-0.05` for 5% faster, if there was a baseline to compare against.
+    pub change: Option<Estimate>,
+    /// Labels of the run, see `Criterion::label`.
+    pub labels: BTreeMap<String, String>,
+}
+impl BenchmarkResult {
+    fn new(
+        id: &BenchmarkId,
+        measurements: &MeasurementData<'_>,
+        formatter: &dyn ValueFormatter,
+    ) -> BenchmarkResult {
+        let estimates = &measurements.absolute_estimates;
+        // The estimates are in the measurement's raw unit; find the factor by which
+        // `scale_for_machines` converts to the unit it reports.
+        let mut scale = [1.0];
+        let unit = formatter.scale_for_machines(&mut scale);
+        BenchmarkResult {
+            id: id.as_title().to_owned(),
+            unit: unit.to_owned(),
+            mean: Estimate::new(&estimates.mean, scale[0]),
+            median: Estimate::new(&estimates.median, scale[0]),
+            std_dev: Estimate::new(&estimates.std_dev, scale[0]),
+            iterations: measurements.iter_counts().iter().sum::<f64>() as u64,
+            change: measurements
+                .comparison
+                .as_ref()
+                .map(|comparison| Estimate::new(comparison.change(), 1.0)),
+            labels: measurements.manifest.labels.clone(),
+        }
+    }
+}
+impl fmt::Display for BenchmarkResult {
+    /// Formats the result like the terminal report, eg.
+    /// `fib 20: mean [1.2071 1.2102 1.2137] ns, change [-1.52% -0.40% +0.62%]`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: mean [{} {} {}] {}",
+            self.id,
+            format::short(self.mean.lower_bound),
+            format::short(self.mean.point_estimate),
+            format::short(self.mean.upper_bound),
+            self.unit
+        )?;
+        if let Some(change) = &self.change {
+            write!(
+                f,
+                ", change [{} {} {}]",
+                format::change(change.lower_bound, true),
+                format::change(change.point_estimate, true),
+                format::change(change.upper_bound, true)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Receives the results of the benchmarks. See the [module documentation](self).
+pub trait ResultSink {
+    /// Called with the results of each benchmark once it has been measured and analyzed.
+    fn result(&mut self, result: &BenchmarkResult);
+
+    /// Called once all the benchmarks have been run, from `Criterion::final_summary`.
+    fn finish(&mut self) {}
+}
+impl<F: FnMut(&BenchmarkResult)> ResultSink for F {
+    fn result(&mut self, result: &BenchmarkResult) {
+        self(result)
+    }
+}
+
+/// Whether a benchmark has changed compared to its baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ChangeResult {
+    /// Faster by more than the noise threshold, with statistical significance.
+    Improved,
+    /// Slower by more than the noise threshold, with statistical significance.
+    Regressed,
+    /// Within the noise threshold, or not statistically significant.
+    NoChange,
+}
+
+/// The comparison of a benchmark against its baseline, passed to [`Report::comparison`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct Comparison {
+    /// Relative change of the mean, eg. `-0.05` for 5% faster.
+    pub mean: Estimate,
+    /// Relative change of the median.
+    pub median: Estimate,
+    /// Probability of a difference at least as large if the performance hasn't changed.
+    pub p_value: f64,
+    /// The p-value below which a change is statistically significant.
+    pub significance_level: f64,
+    /// The relative change within which the performance is considered unchanged.
+    pub noise_threshold: f64,
+    /// The verdict of the comparison.
+    pub result: ChangeResult,
+}
+
+/// Receives the progress and the results of the benchmarks. See the [module
+/// documentation](self).
+pub trait Report {
+    /// Called when a benchmark starts, with its full ID.
+    fn benchmark_start(&mut self, _id: &str) {}
+
+    /// Called when the warm-up of a benchmark starts, with its duration in nanoseconds.
+    fn warmup(&mut self, _id: &str, _warmup_ns: f64) {}
+
+    /// Called when the measurement of a benchmark starts, with the number of samples, the
+    /// estimated time to take them in nanoseconds and the total number of iterations.
+    fn measurement_start(
+        &mut self,
+        _id: &str,
+        _sample_count: u64,
+        _estimate_ns: f64,
+        _iter_count: u64,
+    ) {
+    }
+
+    /// Called when the samples of a benchmark have been taken and their analysis starts.
+    fn analysis(&mut self, _id: &str) {}
+
+    /// Called with the results of each benchmark once it has been analyzed.
+    fn measurement_complete(&mut self, _result: &BenchmarkResult) {}
+
+    /// Called after `measurement_complete` if the benchmark was compared against a baseline.
+    fn comparison(&mut self, _result: &BenchmarkResult, _comparison: &Comparison) {}
+
+    /// Called once all the benchmarks have been run, from `Criterion::final_summary`.
+    fn final_summary(&mut self) {}
+}
+
+/// Logs the results to the JavaScript console with `console.log`. This is the default sink on
+/// `wasm32-unknown-unknown`.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub struct ConsoleSink;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+impl ResultSink for ConsoleSink {
+    fn result(&mut self, result: &BenchmarkResult) {
+        web_console::log(&result.to_string());
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+mod web_console {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = console)]
+        pub fn log(message: &str);
+    }
+}
+
+/// Adapts a `ResultSink` to the internal reports.
+pub(crate) struct SinkReport {
+    sink: RefCell<Box<dyn ResultSink>>,
+}
+impl SinkReport {
+    pub fn new<S: ResultSink + 'static>(sink: S) -> SinkReport {
+        SinkReport {
+            sink: RefCell::new(Box::new(sink)),
+        }
+    }
+}
+impl InternalReport for SinkReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &dyn ValueFormatter,
+    ) {
+        let result = BenchmarkResult::new(id, measurements, formatter);
+        self.sink.borrow_mut().result(&result);
+    }
+
+    fn final_summary(&self, _context: &ReportContext) {
+        self.sink.borrow_mut().finish();
+    }
+}
+
+/// Adapts a public `Report` to the internal reports.
+pub(crate) struct CustomReport {
+    report: RefCell<Box<dyn Report>>,
+}
+impl CustomReport {
+    pub fn new(report: Box<dyn Report>) -> CustomReport {
+        CustomReport {
+            report: RefCell::new(report),
+        }
+    }
+}
+impl InternalReport for CustomReport {
+    fn benchmark_start(&self, id: &BenchmarkId, _context: &ReportContext) {
+        self.report.borrow_mut().benchmark_start(id.as_title());
+    }
+
+    fn warmup(&self, id: &BenchmarkId, _context: &ReportContext, warmup_ns: f64) {
+        self.report.borrow_mut().warmup(id.as_title(), warmup_ns);
+    }
+
+    fn measurement_start(
+        &self,
+        id: &BenchmarkId,
+        _context: &ReportContext,
+        sample_count: u64,
+        estimate_ns: f64,
+        iter_count: u64,
+    ) {
+        self.report.borrow_mut().measurement_start(
+            id.as_title(),
+            sample_count,
+            estimate_ns,
+            iter_count,
+        );
+    }
+
+    fn analysis(&self, id: &BenchmarkId, _context: &ReportContext) {
+        self.report.borrow_mut().analysis(id.as_title());
+    }
+
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &dyn ValueFormatter,
+    ) {
+        let result = BenchmarkResult::new(id, measurements, formatter);
+        let mut report = self.report.borrow_mut();
+        report.measurement_complete(&result);
+        if let Some(comparison) = &measurements.comparison {
+            let change = &comparison.relative_estimates;
+            report.comparison(
+                &result,
+                &Comparison {
+                    mean: Estimate::new(&change.mean, 1.0),
+                    median: Estimate::new(&change.median, 1.0),
+                    p_value: comparison.p_value,
+                    significance_level: comparison.significance_threshold,
+                    noise_threshold: comparison.noise_threshold,
+                    result: match comparison.significant_change() {
+                        Some(SignificantChange::Improved) => ChangeResult::Improved,
+                        Some(SignificantChange::Regressed) => ChangeResult::Regressed,
+                        None => ChangeResult::NoChange,
+                    },
+                },
+            );
+        }
+    }
+
+    fn final_summary(&self, _context: &ReportContext) {
+        self.report.borrow_mut().final_summary();
+    }
+}
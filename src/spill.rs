@@ -0,0 +1,232 @@
+//! Spilling samples to disk (`--spill-samples`).
+//!
+//! Normally the iteration counts and times of a benchmark are collected in memory, and they stay
+//! there until its results have been reported, which with `--background-analysis` can be several
+//! benchmarks later. With very large sample sizes this adds up. With `--spill-samples`, every
+//! sample is written to a spill file in the temporary directory as soon as it has been taken, and
+//! the analysis reads the files back through a read-only memory map, so that the samples are paged
+//! in from disk as needed instead of taking up heap memory. Where memory maps are not supported
+//! (anywhere but Linux), the files are read back into memory for the analysis, which still keeps
+//! the measurement itself from holding every value.
+//!
+//! The spill files are removed as soon as they have been created; the open file, and then the
+//! mapping, keep the data on disk until they are dropped, so that nothing is left behind even if
+//! the benchmarks are interrupted. Windows can't remove open files, so there the files are opened
+//! to be deleted once they are closed instead.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Counter for the names of the spill files.
+static NEXT_FILE: AtomicUsize = AtomicUsize::new(0);
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Values collected in memory or spilled to disk.
+pub(crate) enum Values {
+    Memory(Box<[f64]>),
+    Spilled(Spilled),
+}
+impl Deref for Values {
+    type Target = [f64];
+
+    fn deref(&self) -> &[f64] {
+        match self {
+            Values::Memory(values) => values,
+            Values::Spilled(spilled) => spilled,
+        }
+    }
+}
+impl From<Box<[f64]>> for Values {
+    fn from(values: Box<[f64]>) -> Values {
+        Values::Memory(values)
+    }
+}
+impl From<Vec<f64>> for Values {
+    fn from(values: Vec<f64>) -> Values {
+        Values::Memory(values.into_boxed_slice())
+    }
+}
+
+/// Warns, once, that spilling failed and the samples are kept in memory.
+pub(crate) fn warn(error: &io::Error) {
+    if !WARNED.swap(true, Ordering::Relaxed) {
+        eprintln!(
+            "Warning: unable to spill samples to disk ({}); keeping them in memory.",
+            error
+        );
+    }
+}
+
+/// Appends values to a new spill file.
+pub(crate) struct SpillWriter {
+    file: BufWriter<File>,
+    len: usize,
+    /// Kept for the tests to check that the file was removed.
+    #[cfg(all(test, not(windows)))]
+    path: std::path::PathBuf,
+}
+impl SpillWriter {
+    pub fn create() -> io::Result<SpillWriter> {
+        let path = std::env::temp_dir().join(format!(
+            "criterion-spill-{}-{}.f64",
+            std::process::id(),
+            NEXT_FILE.fetch_add(1, Ordering::Relaxed)
+        ));
+        // Readable as well, to map it back in.
+        let mut options = std::fs::OpenOptions::new();
+        options.read(true).write(true).create(true).truncate(true);
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::OpenOptionsExt;
+            // FILE_FLAG_DELETE_ON_CLOSE
+            options.custom_flags(0x0400_0000);
+        }
+        let file = options.open(&path)?;
+        #[cfg(not(windows))]
+        std::fs::remove_file(&path)?;
+        let file = BufWriter::new(file);
+        Ok(SpillWriter {
+            file,
+            len: 0,
+            #[cfg(all(test, not(windows)))]
+            path,
+        })
+    }
+
+    pub fn push(&mut self, value: f64) -> io::Result<()> {
+        self.file.write_all(&value.to_ne_bytes())?;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Flushes the values to disk and maps them back in.
+    pub fn finish(self) -> io::Result<Spilled> {
+        let SpillWriter { file, len, .. } = self;
+        let file = file.into_inner().map_err(|e| e.into_error())?;
+        Mapping::new(file, len).map(Spilled)
+    }
+}
+
+/// Values read back from a spill file.
+pub(crate) struct Spilled(Mapping);
+impl Deref for Spilled {
+    type Target = [f64];
+
+    fn deref(&self) -> &[f64] {
+        self.0.values()
+    }
+}
+
+#[cfg(target_os = "linux")]
+struct Mapping {
+    address: *mut libc::c_void,
+    len: usize,
+}
+// Safety: the mapping is private and read-only, and only unmapped on drop.
+#[cfg(target_os = "linux")]
+unsafe impl Send for Mapping {}
+#[cfg(target_os = "linux")]
+unsafe impl Sync for Mapping {}
+#[cfg(target_os = "linux")]
+impl Mapping {
+    fn new(file: File, len: usize) -> io::Result<Mapping> {
+        use std::os::unix::io::AsRawFd;
+
+        if len == 0 {
+            return Ok(Mapping {
+                address: std::ptr::null_mut(),
+                len,
+            });
+        }
+        // Safety: a new read-only private mapping of the whole file is created, which stays valid
+        // after the file is closed.
+        let address = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len * std::mem::size_of::<f64>(),
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if address == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Mapping { address, len })
+    }
+
+    fn values(&self) -> &[f64] {
+        if self.len == 0 {
+            return &[];
+        }
+        // Safety: the mapping is page-aligned and holds `len` values written by `SpillWriter`.
+        unsafe { std::slice::from_raw_parts(self.address as *const f64, self.len) }
+    }
+}
+#[cfg(target_os = "linux")]
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // Safety: the mapping was created by `Mapping::new` and is not used any more.
+            unsafe {
+                libc::munmap(self.address, self.len * std::mem::size_of::<f64>());
+            }
+        }
+    }
+}
+
+/// Without memory maps, the values are streamed back in from the file.
+#[cfg(not(target_os = "linux"))]
+struct Mapping(Box<[f64]>);
+#[cfg(not(target_os = "linux"))]
+impl Mapping {
+    fn new(file: File, len: usize) -> io::Result<Mapping> {
+        use std::io::{BufReader, Read, Seek, SeekFrom};
+
+        let mut file = BufReader::new(file);
+        file.get_mut().seek(SeekFrom::Start(0))?;
+        let mut values = Vec::with_capacity(len);
+        let mut bytes = [0; 8];
+        for _ in 0..len {
+            file.read_exact(&mut bytes)?;
+            values.push(f64::from_ne_bytes(bytes));
+        }
+        Ok(Mapping(values.into_boxed_slice()))
+    }
+
+    fn values(&self) -> &[f64] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn spilled_values_are_read_back() {
+        let mut writer = SpillWriter::create().unwrap();
+        // The file is removed as soon as it has been created.
+        #[cfg(not(windows))]
+        assert!(!writer.path.exists());
+        for i in 0..10_000 {
+            writer.push(i as f64 / 4.0).unwrap();
+        }
+        let spilled = writer.finish().unwrap();
+        assert_eq!(spilled.len(), 10_000);
+        assert_eq!(spilled[0], 0.0);
+        assert_eq!(spilled[9_999], 9_999.0 / 4.0);
+
+        let values = Values::Spilled(spilled);
+        assert_eq!(values.iter().sum::<f64>(), 4999.5 * 10_000.0 / 4.0);
+    }
+
+    #[test]
+    fn empty_spill_files() {
+        let spilled = SpillWriter::create().unwrap().finish().unwrap();
+        assert!(spilled.is_empty());
+    }
+}
@@ -73,13 +73,18 @@ where
         T::Distributions: Send,
         T::Builder: Send,
     {
+        let seed = crate::stats::rand_util::bootstrap_seed();
         #[cfg(feature = "rayon")]
         {
             (0..nresamples)
                 .into_par_iter()
                 .map_init(
                     || Resamples::new(*self),
-                    |resamples, _| statistic(resamples.next()),
+                    |resamples, i| {
+                        crate::analysis::pause_while_measuring();
+                        resamples.reseed(seed, i as u64);
+                        statistic(resamples.next())
+                    },
                 )
                 .fold(
                     || T::Builder::new(0),
@@ -101,7 +106,10 @@ where
         {
             let mut resamples = Resamples::new(*self);
             (0..nresamples)
-                .map(|_| statistic(resamples.next()))
+                .map(|i| {
+                    resamples.reseed(seed, i as u64);
+                    statistic(resamples.next())
+                })
                 .fold(T::Builder::new(0), |mut sub_distributions, sample| {
                     sub_distributions.push(sample);
                     sub_distributions
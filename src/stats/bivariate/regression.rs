@@ -1,8 +1,14 @@
 //! Regression analysis
 
+use std::cmp::Ordering;
+
 use crate::stats::bivariate::Data;
 use crate::stats::float::Float;
 
+/// The number of pairs of points above which `Slope::fit_theil_sen` uses a subset of the pairs,
+/// a bit more than the 4950 pairs of the default 100 samples.
+const MAX_THEIL_SEN_PAIRS: usize = 5_000;
+
 /// A straight line that passes through the origin `y = m * x`
 #[derive(Clone, Copy)]
 pub struct Slope<A>(pub A)
@@ -27,6 +33,58 @@ where
         Slope(xy / x2)
     }
 
+    /// Estimates the slope of the line `y = m * x + b` through the data using the Theil-Sen
+    /// estimator: the slope is the median of the slopes between all pairs of points, which a few
+    /// outliers hardly move. The intercept is dropped. Falls back to ordinary least squares if all
+    /// the points have the same `x`.
+    ///
+    /// Beyond `MAX_THEIL_SEN_PAIRS` pairs, each point is only paired with the points a few evenly
+    /// spread offsets away, so that fitting the bootstrap resamples of a large sample stays
+    /// tractable.
+    ///
+    /// - Time: `O(length^2)` up to 100 points, `O(length)` beyond
+    pub fn fit_theil_sen(data: &Data<'_, A, A>) -> Slope<A> {
+        let (xs, ys) = (data.0, data.1);
+        let n = data.len();
+        let mut slopes = Vec::with_capacity(MAX_THEIL_SEN_PAIRS.min(n * n.saturating_sub(1) / 2));
+        let mut push = |i: usize, j: usize| {
+            if xs[i] != xs[j] {
+                slopes.push((ys[j] - ys[i]) / (xs[j] - xs[i]));
+            }
+        };
+        if n * n.saturating_sub(1) / 2 <= MAX_THEIL_SEN_PAIRS {
+            for i in 0..n {
+                for j in i + 1..n {
+                    push(i, j);
+                }
+            }
+        } else {
+            // Offsets up to `(n - 1) / 2` around the circle never give the same pair twice.
+            let half = (n - 1) / 2;
+            let offsets = (MAX_THEIL_SEN_PAIRS / n).max(1).min(half);
+            for k in 1..=offsets {
+                let offset = half * k / offsets;
+                for i in 0..n {
+                    push(i, (i + offset) % n);
+                }
+            }
+        }
+        if slopes.is_empty() {
+            return Slope::fit(data);
+        }
+
+        let cmp = |a: &A, b: &A| a.partial_cmp(b).unwrap_or(Ordering::Equal);
+        let (n, half) = (slopes.len(), slopes.len() / 2);
+        let (below, &mut upper, _) = slopes.select_nth_unstable_by(half, cmp);
+        if n % 2 == 1 {
+            Slope(upper)
+        } else {
+            // The largest of the lower half is the other middle slope.
+            let lower = below.iter().cloned().fold(A::neg_infinity(), A::max);
+            Slope((lower + upper) / A::cast(2))
+        }
+    }
+
     /// Computes the goodness of fit (coefficient of determination) for this data set
     ///
     /// - Time: `O(length)`
@@ -51,3 +109,42 @@ where
         _1 - ss_res / ss_tot
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::Slope;
+    use crate::stats::bivariate::Data;
+
+    #[test]
+    fn theil_sen_ignores_outliers() {
+        let xs: Vec<f64> = (1..=10).map(f64::from).collect();
+        let mut ys: Vec<f64> = xs.iter().map(|x| 2. * x).collect();
+        ys[9] = 200.;
+        let data = Data::new(&xs, &ys);
+
+        assert_eq!(Slope::fit_theil_sen(&data).0, 2.);
+        assert!(Slope::fit(&data).0 > 5.);
+    }
+
+    #[test]
+    fn theil_sen_of_a_single_x_falls_back_to_least_squares() {
+        let xs = [2., 2., 2.];
+        let ys = [4., 6., 8.];
+        let data = Data::new(&xs, &ys);
+
+        assert_eq!(Slope::fit_theil_sen(&data).0, 3.);
+    }
+
+    #[test]
+    fn theil_sen_of_many_points_ignores_outliers() {
+        let xs: Vec<f64> = (1..=10_000).map(f64::from).collect();
+        let ys: Vec<f64> = xs
+            .iter()
+            .enumerate()
+            .map(|(i, x)| if i % 10 == 0 { 1e6 } else { 2. * x + 5. })
+            .collect();
+        let data = Data::new(&xs, &ys);
+
+        assert_eq!(Slope::fit_theil_sen(&data).0, 2.);
+    }
+}
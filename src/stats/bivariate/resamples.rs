@@ -1,6 +1,6 @@
 use crate::stats::bivariate::Data;
 use crate::stats::float::Float;
-use crate::stats::rand_util::{new_rng, Rng};
+use crate::stats::rand_util::{new_rng, seeded_rng, Rng};
 
 pub struct Resamples<'a, X, Y>
 where
@@ -26,6 +26,14 @@ where
         }
     }
 
+    /// Restarts the generator at the `stream`th stream of the seed, so that the next resample only
+    /// depends on the seed and the stream, whichever thread draws it.
+    pub fn reseed(&mut self, seed: Option<u64>, stream: u64) {
+        if let Some(seed) = seed {
+            self.rng = seeded_rng(seed, stream);
+        }
+    }
+
     pub fn next(&mut self) -> Data<'_, X, Y> {
         let n = self.data.0.len();
 
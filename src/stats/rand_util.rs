@@ -1,15 +1,15 @@
 use oorandom::Rand64;
-use std::cell::RefCell;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::cell::{Cell, RefCell};
 
 pub type Rng = Rand64;
 
 thread_local! {
     static SEED_RAND: RefCell<Rand64> = RefCell::new(Rand64::new(
-        SystemTime::now().duration_since(UNIX_EPOCH)
-            .expect("Time went backwards")
-            .as_millis()
+        crate::clock::since_epoch().as_millis()
     ));
+    // (`const` initializers of thread-locals need a newer compiler than the MSRV.)
+    #[allow(clippy::missing_const_for_thread_local)]
+    static BOOTSTRAP_SEED: Cell<Option<u64>> = Cell::new(None);
 }
 
 pub fn new_rng() -> Rng {
@@ -19,3 +19,29 @@ pub fn new_rng() -> Rng {
         Rand64::new(seed)
     })
 }
+
+/// The generator of the `stream`th resample of a bootstrap with the given seed.
+pub fn seeded_rng(seed: u64, stream: u64) -> Rng {
+    Rand64::new((u128::from(seed) << 64) | u128::from(stream))
+}
+
+/// The seed of the bootstraps started on this thread, if any.
+pub fn bootstrap_seed() -> Option<u64> {
+    BOOTSTRAP_SEED.with(Cell::get)
+}
+
+/// Seeds the bootstraps started on this thread until the guard is dropped
+/// (`Criterion::bootstrap_seed`). The resamples are drawn by the threads of the pool, so the seed
+/// is read when a bootstrap starts and each resample is drawn from its own stream of the seed,
+/// which makes the distributions independent of the threads which drew them.
+pub fn seed_bootstraps(seed: Option<u64>) -> SeedGuard {
+    SeedGuard(BOOTSTRAP_SEED.with(|cell| cell.replace(seed)))
+}
+
+/// Restores the previous seed of the thread when dropped.
+pub struct SeedGuard(Option<u64>);
+impl Drop for SeedGuard {
+    fn drop(&mut self) {
+        BOOTSTRAP_SEED.with(|cell| cell.set(self.0));
+    }
+}
@@ -70,9 +70,14 @@ where
 }
 
 /// Method to estimate the bandwidth
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Bandwidth {
     /// Use Silverman's rule of thumb to estimate the bandwidth from the sample
     Silverman,
+    /// Use Scott's rule of thumb to estimate the bandwidth from the sample
+    Scott,
+    /// Use the given bandwidth
+    Fixed(f64),
 }
 
 impl Bandwidth {
@@ -82,14 +87,38 @@ impl Bandwidth {
                 let factor = A::cast(4. / 3.);
                 let exponent = A::cast(1. / 5.);
                 let n = A::cast(sample.len());
-                let sigma = sample.std_dev(None);
+                let sigma = spread(sample);
 
                 sigma * (factor / n).powf(exponent)
             }
+            Bandwidth::Scott => {
+                let exponent = A::cast(-1. / 5.);
+                let n = A::cast(sample.len());
+                let sigma = spread(sample);
+
+                sigma * n.powf(exponent)
+            }
+            Bandwidth::Fixed(bandwidth) => num_traits::cast(bandwidth).unwrap(),
         }
     }
 }
 
+/// The standard deviation of the sample, or for a constant sample, eg. of a deterministic
+/// measurement such as a count of allocations, a thousandth of its value, so that its density is a
+/// narrow spike rather than undefined.
+fn spread<A: Float>(sample: &Sample<A>) -> A {
+    let sigma = sample.std_dev(None);
+    if sigma > A::cast(0) {
+        return sigma;
+    }
+    let magnitude = sample.mean().abs();
+    if magnitude > A::cast(0) {
+        magnitude * A::cast(1e-3)
+    } else {
+        A::cast(1)
+    }
+}
+
 #[cfg(test)]
 macro_rules! test {
     ($ty:ident) => {
@@ -28,13 +28,16 @@ where
     c.extend_from_slice(b);
     let c = Sample::new(&c);
 
+    let seed = crate::stats::rand_util::bootstrap_seed();
     #[cfg(feature = "rayon")]
     {
         (0..nresamples)
             .into_par_iter()
             .map_init(
                 || Resamples::new(c),
-                |resamples, _| {
+                |resamples, i| {
+                    crate::analysis::pause_while_measuring();
+                    resamples.reseed(seed, i as u64);
                     let resample = resamples.next();
                     let a: &Sample<A> = Sample::new(&resample[..n_a]);
                     let b: &Sample<A> = Sample::new(&resample[n_a..]);
@@ -62,7 +65,8 @@ where
     {
         let mut resamples = Resamples::new(c);
         (0..nresamples)
-            .map(|_| {
+            .map(|i| {
+                resamples.reseed(seed, i as u64);
                 let resample = resamples.next();
                 let a: &Sample<A> = Sample::new(&resample[..n_a]);
                 let b: &Sample<A> = Sample::new(&resample[n_a..]);
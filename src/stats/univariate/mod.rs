@@ -42,6 +42,8 @@ where
 {
     let nresamples_sqrt = (nresamples as f64).sqrt().ceil() as usize;
     let per_chunk = (nresamples + nresamples_sqrt - 1) / nresamples_sqrt;
+    // The resamples of `a`, one per chunk, are drawn from the streams counting down from the last.
+    let seed = crate::stats::rand_util::bootstrap_seed();
 
     #[cfg(feature = "rayon")]
     {
@@ -52,12 +54,15 @@ where
                 |(a_resamples, b_resamples), i| {
                     let start = i * per_chunk;
                     let end = cmp::min((i + 1) * per_chunk, nresamples);
+                    a_resamples.reseed(seed, u64::MAX - i as u64);
                     let a_resample = a_resamples.next();
 
                     let mut sub_distributions: T::Builder =
                         TupledDistributionsBuilder::new(end - start);
 
-                    for _ in start..end {
+                    for j in start..end {
+                        crate::analysis::pause_while_measuring();
+                        b_resamples.reseed(seed, j as u64);
                         let b_resample = b_resamples.next();
                         sub_distributions.push(statistic(a_resample, b_resample));
                     }
@@ -81,12 +86,14 @@ where
             .map(|i| {
                 let start = i * per_chunk;
                 let end = cmp::min((i + 1) * per_chunk, nresamples);
+                a_resamples.reseed(seed, u64::MAX - i as u64);
                 let a_resample = a_resamples.next();
 
                 let mut sub_distributions: T::Builder =
                     TupledDistributionsBuilder::new(end - start);
 
-                for _ in start..end {
+                for j in start..end {
+                    b_resamples.reseed(seed, j as u64);
                     let b_resample = b_resamples.next();
                     sub_distributions.push(statistic(a_resample, b_resample));
                 }
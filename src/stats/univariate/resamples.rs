@@ -1,7 +1,7 @@
 use std::mem;
 
 use crate::stats::float::Float;
-use crate::stats::rand_util::{new_rng, Rng};
+use crate::stats::rand_util::{new_rng, seeded_rng, Rng};
 use crate::stats::univariate::Sample;
 
 pub struct Resamples<'a, A>
@@ -28,6 +28,14 @@ where
         }
     }
 
+    /// Restarts the generator at the `stream`th stream of the seed, so that the next resample only
+    /// depends on the seed and the stream, whichever thread draws it.
+    pub fn reseed(&mut self, seed: Option<u64>, stream: u64) {
+        if let Some(seed) = seed {
+            self.rng = seeded_rng(seed, stream);
+        }
+    }
+
     pub fn next(&mut self) -> &Sample<A> {
         let n = self.sample.len();
         let rng = &mut self.rng;
@@ -179,6 +179,45 @@ where
         num / den
     }
 
+    /// Returns the z score of the Mann-Whitney U statistic of this sample against `other`, the
+    /// normal approximation of its distribution corrected for ties. It is positive if the values of
+    /// this sample tend to be larger.
+    ///
+    /// - Time: `O(length * log(length))`
+    pub fn mann_whitney_z(&self, other: &Sample<A>) -> A {
+        let mut pooled: Vec<(A, bool)> = self
+            .iter()
+            .map(|&x| (x, true))
+            .chain(other.iter().map(|&y| (y, false)))
+            .collect();
+        pooled.sort_unstable_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        let n = pooled.len();
+        let (mut rank_sum, mut ties) = (A::cast(0), A::cast(0));
+        let mut i = 0;
+        while i < n {
+            let mut j = i + 1;
+            while j < n && pooled[j].0 == pooled[i].0 {
+                j += 1;
+            }
+            // The tied values share the mean of their ranks, i + 1 to j.
+            let rank = A::cast(i + j + 1) / A::cast(2);
+            let in_self = pooled[i..j].iter().filter(|(_, in_self)| *in_self).count();
+            rank_sum = rank_sum + rank * A::cast(in_self);
+            let t = A::cast(j - i);
+            ties = ties + t * t * t - t;
+            i = j;
+        }
+
+        let (n_x, n_y, n) = (A::cast(self.len()), A::cast(other.len()), A::cast(n));
+        let u = rank_sum - n_x * (n_x + A::cast(1)) / A::cast(2);
+        let var = n_x * n_y / A::cast(12) * (n + A::cast(1) - ties / (n * (n - A::cast(1))));
+        if var <= A::cast(0) {
+            return A::cast(0);
+        }
+        (u - n_x * n_y / A::cast(2)) / var.sqrt()
+    }
+
     /// Returns the variance of the sample
     ///
     /// The `mean` can be optionally passed along to speed up (2X) the computation
@@ -211,13 +250,18 @@ where
         T::Distributions: Send,
         T::Builder: Send,
     {
+        let seed = crate::stats::rand_util::bootstrap_seed();
         #[cfg(feature = "rayon")]
         {
             (0..nresamples)
                 .into_par_iter()
                 .map_init(
                     || Resamples::new(self),
-                    |resamples, _| statistic(resamples.next()),
+                    |resamples, i| {
+                        crate::analysis::pause_while_measuring();
+                        resamples.reseed(seed, i as u64);
+                        statistic(resamples.next())
+                    },
                 )
                 .fold(
                     || T::Builder::new(0),
@@ -239,7 +283,10 @@ where
         {
             let mut resamples = Resamples::new(self);
             (0..nresamples)
-                .map(|_| statistic(resamples.next()))
+                .map(|i| {
+                    resamples.reseed(seed, i as u64);
+                    statistic(resamples.next())
+                })
                 .fold(T::Builder::new(0), |mut sub_distributions, sample| {
                     sub_distributions.push(sample);
                     sub_distributions
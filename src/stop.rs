@@ -0,0 +1,39 @@
+//! Stopping the run early, with `--fail-fast` once a benchmark has regressed or with `--strict-env`
+//! if the environment checks warn.
+//!
+//! The process is not exited on the spot: the benchmarks left are skipped instead, so that the rest
+//! of the run (the analyses in the background, the group summaries and the final summary) still
+//! completes, and the final summary then fails the run. Since every group constructs its own
+//! `Criterion` struct, the record is a process-wide static, keyed by output directory.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+static STOPPED: Lazy<Mutex<Vec<PathBuf>>> = Lazy::new(|| Mutex::new(vec![]));
+
+/// Stops the run writing to the given output directory: the benchmarks left will be skipped.
+pub(crate) fn stop(output_directory: &Path) {
+    let mut stopped = STOPPED.lock().unwrap();
+    if !stopped.iter().any(|dir| dir == output_directory) {
+        stopped.push(output_directory.to_owned());
+    }
+}
+
+/// Returns true if the run writing to the given output directory was stopped.
+pub(crate) fn is_stopped(output_directory: &Path) -> bool {
+    STOPPED
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|dir| dir == output_directory)
+}
+
+/// Returns true if the run writing to the given output directory was stopped, and forgets it.
+pub(crate) fn take(output_directory: &Path) -> bool {
+    let mut stopped = STOPPED.lock().unwrap();
+    let len = stopped.len();
+    stopped.retain(|dir| dir != output_directory);
+    stopped.len() != len
+}
@@ -0,0 +1,228 @@
+//! Streaming statistics, which cap the memory of benchmarks with huge sample counts
+//! (`Criterion::streaming_statistics`, `BenchmarkGroup::streaming_statistics`).
+//!
+//! Normally every sample is kept in memory, saved, and bootstrapped along with the regression of
+//! all of them, which takes a lot of memory and time for millions of samples. With streaming
+//! statistics each sample is folded into online moments as soon as it is measured: the mean and
+//! variance of the average times by Welford's algorithm, and the sums of the regression through
+//! the origin. Only a uniform random reservoir of `RESERVOIR_SIZE` samples is kept, in the order
+//! they were measured, and it stands in for the sample everywhere else: it is saved, plotted, its
+//! outliers are classified, its density is estimated and it is compared to the baseline.
+//!
+//! The statistics are bootstrapped from the reservoir as usual. The mean, the standard deviation
+//! and the least-squares slope are then replaced by those of all the samples, and the bootstrap
+//! distribution of each statistic is moved to its point estimate and narrowed by the square root
+//! of the ratio of the reservoir's size to the number of samples, since the spread of these
+//! statistics shrinks with the square root of the sample count.
+
+use crate::estimate::{ConfidenceInterval, Distributions, Estimate, Estimates};
+use crate::stats::rand_util::{new_rng, seeded_rng, Rng};
+use crate::stats::Distribution;
+use crate::RegressionMethod;
+
+/// The number of samples kept in memory with streaming statistics.
+pub(crate) const RESERVOIR_SIZE: usize = 10_000;
+
+/// The online moments of all the samples.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Moments {
+    /// The number of samples.
+    pub count: u64,
+    /// The mean of the average times.
+    pub mean: f64,
+    /// The sum of the squared deviations of the average times from their mean.
+    squared_deviations: f64,
+    sum_iters_squared: f64,
+    sum_iters_times: f64,
+}
+impl Moments {
+    fn push(&mut self, iters: f64, time: f64) {
+        let avg_time = time / iters;
+        self.count += 1;
+        let delta = avg_time - self.mean;
+        self.mean += delta / self.count as f64;
+        self.squared_deviations += delta * (avg_time - self.mean);
+        self.sum_iters_squared += iters * iters;
+        self.sum_iters_times += iters * time;
+    }
+
+    /// The standard deviation of the average times.
+    pub fn std_dev(&self) -> f64 {
+        (self.squared_deviations / (self.count - 1) as f64).sqrt()
+    }
+
+    /// The least-squares slope through the origin of the times over the iteration counts.
+    pub fn slope(&self) -> f64 {
+        self.sum_iters_times / self.sum_iters_squared
+    }
+}
+
+/// Folds the samples into their moments and keeps a uniform random reservoir of them (Algorithm R).
+pub(crate) struct Reservoir {
+    moments: Moments,
+    /// The index, iteration count and time of each sample kept.
+    samples: Vec<(u64, u64, f64)>,
+    rng: Rng,
+}
+impl Reservoir {
+    /// Creates an empty reservoir, whose random choices are seeded with the bootstrap seed if any.
+    pub fn new(seed: Option<u64>) -> Reservoir {
+        Reservoir {
+            moments: Moments::default(),
+            samples: Vec::with_capacity(RESERVOIR_SIZE),
+            rng: seed.map_or_else(new_rng, |seed| seeded_rng(seed, 0)),
+        }
+    }
+
+    pub fn push(&mut self, iters: u64, time: f64) {
+        let index = self.moments.count;
+        self.moments.push(iters as f64, time);
+        if self.samples.len() < RESERVOIR_SIZE {
+            self.samples.push((index, iters, time));
+        } else {
+            let slot = self.rng.rand_range(0..index + 1) as usize;
+            if slot < RESERVOIR_SIZE {
+                self.samples[slot] = (index, iters, time);
+            }
+        }
+    }
+
+    /// Returns the moments of all the samples, and the iteration counts and times of the samples
+    /// kept, in the order they were measured.
+    pub fn finish(mut self) -> (Moments, Vec<u64>, Vec<f64>) {
+        self.samples.sort_unstable_by_key(|&(index, _, _)| index);
+        let iters = self.samples.iter().map(|&(_, iters, _)| iters).collect();
+        let times = self.samples.iter().map(|&(_, _, time)| time).collect();
+        (self.moments, iters, times)
+    }
+}
+
+/// Moves the bootstrap distribution of a statistic of the reservoir to the point estimate of all
+/// the samples and narrows it by `factor`, and rebuilds the estimate from it.
+fn rescale(
+    estimate: &mut Estimate,
+    distribution: &mut Distribution<f64>,
+    point: f64,
+    factor: f64,
+    cl: f64,
+) {
+    let reservoir_point = estimate.point_estimate;
+    *distribution = Distribution::from(
+        distribution
+            .iter()
+            .map(|&value| point + (value - reservoir_point) * factor)
+            .collect(),
+    );
+    let (lb, ub) = distribution.confidence_interval(cl);
+    *estimate = Estimate {
+        confidence_interval: ConfidenceInterval {
+            confidence_level: cl,
+            lower_bound: lb,
+            upper_bound: ub,
+        },
+        point_estimate: point,
+        standard_error: distribution.std_dev(None),
+    };
+}
+
+/// Extrapolates the mean, median, MAD, standard deviation and slope estimated from the `kept`
+/// samples of the reservoir to all the samples of the moments. The slope of the Theil-Sen estimator
+/// can't be computed online, so its point estimate remains that of the reservoir. The minimum and
+/// the percentiles are left as estimated from the reservoir.
+pub(crate) fn extrapolate(
+    estimates: &mut Estimates,
+    distributions: &mut Distributions,
+    moments: &Moments,
+    kept: usize,
+    method: RegressionMethod,
+    cl: f64,
+) {
+    if moments.count as usize <= kept {
+        return;
+    }
+    let factor = (kept as f64 / moments.count as f64).sqrt();
+
+    let median = estimates.median.point_estimate;
+    let mad = estimates.median_abs_dev.point_estimate;
+    rescale(
+        &mut estimates.mean,
+        &mut distributions.mean,
+        moments.mean,
+        factor,
+        cl,
+    );
+    rescale(
+        &mut estimates.median,
+        &mut distributions.median,
+        median,
+        factor,
+        cl,
+    );
+    rescale(
+        &mut estimates.median_abs_dev,
+        &mut distributions.median_abs_dev,
+        mad,
+        factor,
+        cl,
+    );
+    rescale(
+        &mut estimates.std_dev,
+        &mut distributions.std_dev,
+        moments.std_dev(),
+        factor,
+        cl,
+    );
+    if let (Some(estimate), Some(distribution)) = (&mut estimates.slope, &mut distributions.slope) {
+        let slope = match method {
+            RegressionMethod::OrdinaryLeastSquares => moments.slope(),
+            RegressionMethod::TheilSen => estimate.point_estimate,
+        };
+        rescale(estimate, distribution, slope, factor, cl);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn moments_match_the_direct_computation() {
+        let samples: Vec<(u64, f64)> = (1..=1000u64)
+            .map(|i| (i, i as f64 * (100.0 + (i % 7) as f64)))
+            .collect();
+        let mut reservoir = Reservoir::new(None);
+        for &(iters, time) in &samples {
+            reservoir.push(iters, time);
+        }
+        let (moments, iters, times) = reservoir.finish();
+
+        let avg_times: Vec<f64> = samples.iter().map(|&(i, t)| t / i as f64).collect();
+        let mean = avg_times.iter().sum::<f64>() / 1000.0;
+        let variance = avg_times.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / 999.0;
+        assert_eq!(moments.count, 1000);
+        assert!((moments.mean - mean).abs() < 1e-9);
+        assert!((moments.std_dev() - variance.sqrt()).abs() < 1e-9);
+        let xy: f64 = samples.iter().map(|&(i, t)| i as f64 * t).sum();
+        let xx: f64 = samples.iter().map(|&(i, _)| (i * i) as f64).sum();
+        assert!((moments.slope() - xy / xx).abs() < 1e-9);
+        // Fewer samples than the reservoir holds are all kept, in order.
+        assert_eq!(iters, (1..=1000).collect::<Vec<u64>>());
+        assert_eq!(times.len(), 1000);
+    }
+
+    #[test]
+    fn reservoir_is_capped_and_in_order() {
+        let mut reservoir = Reservoir::new(None);
+        let n = 5 * RESERVOIR_SIZE as u64;
+        for i in 0..n {
+            reservoir.push(i + 1, 1.0);
+        }
+        let (moments, iters, _) = reservoir.finish();
+        assert_eq!(moments.count, n);
+        assert_eq!(iters.len(), RESERVOIR_SIZE);
+        assert!(iters.windows(2).all(|pair| pair[0] < pair[1]));
+        // A uniform reservoir keeps about a fifth of each half of the samples.
+        let early = iters.iter().filter(|&&i| i <= n / 2).count();
+        assert!((early as f64 / RESERVOIR_SIZE as f64 - 0.5).abs() < 0.05);
+    }
+}
@@ -0,0 +1,116 @@
+//! Plots drawn with text in the terminal report, for `PlottingBackend::Terminal`.
+//!
+//! Each benchmark gets a one-line histogram of its sample, and the summary of a group draws the
+//! histograms of all its benchmarks over a common range, a rough picture of the violin plot.
+
+use std::env;
+
+/// The width of the histograms, in characters.
+pub(crate) const WIDTH: usize = 40;
+
+const UNICODE_BARS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII_BARS: [char; 9] = [' ', '.', ':', '-', '=', '+', '*', '#', '@'];
+
+/// The characters the histograms are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Charset {
+    /// The block elements of Unicode, which draw bars of eight heights.
+    Unicode,
+    /// Plain ASCII characters of increasing weight, for terminals which can't show Unicode.
+    Ascii,
+}
+impl Charset {
+    /// Uses Unicode if the locale of the terminal is UTF-8, as set by `LC_ALL`, `LC_CTYPE` or
+    /// `LANG`. Windows terminals show Unicode without a locale.
+    pub(crate) fn detect() -> Charset {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty());
+        match locale {
+            Some(locale) => {
+                let locale = locale.to_ascii_lowercase();
+                if locale.contains("utf-8") || locale.contains("utf8") {
+                    Charset::Unicode
+                } else {
+                    Charset::Ascii
+                }
+            }
+            None if cfg!(windows) => Charset::Unicode,
+            None => Charset::Ascii,
+        }
+    }
+
+    fn bars(self) -> &'static [char; 9] {
+        match self {
+            Charset::Unicode => &UNICODE_BARS,
+            Charset::Ascii => &ASCII_BARS,
+        }
+    }
+}
+
+/// Draws the histogram of `values` between `min` and `max` in `width` bins, one character each.
+/// The fullest bin gets the tallest bar and empty bins are blank, so that a single outlier still
+/// shows.
+pub(crate) fn histogram(
+    values: &[f64],
+    (min, max): (f64, f64),
+    width: usize,
+    charset: Charset,
+) -> String {
+    let mut counts = vec![0usize; width];
+    let span = max - min;
+    for &value in values {
+        let bin = if span > 0. {
+            ((value - min) / span * width as f64) as usize
+        } else {
+            width / 2
+        };
+        counts[bin.min(width - 1)] += 1;
+    }
+    let fullest = counts.iter().cloned().max().unwrap_or(0).max(1);
+    let bars = charset.bars();
+    counts
+        .iter()
+        .map(|&count| {
+            // Round up, so that every non-empty bin gets at least the lowest bar.
+            let height = (count as f64 * 8. / fullest as f64).ceil() as usize;
+            bars[height]
+        })
+        .collect()
+}
+
+/// The smallest and largest of `values`.
+pub(crate) fn range<'a, I: IntoIterator<Item = &'a f64>>(values: I) -> (f64, f64) {
+    values
+        .into_iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+            (min.min(value), max.max(value))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn histogram_scales_bars_to_the_fullest_bin() {
+        let values = [0., 1., 1., 2., 2., 2., 2., 3.];
+        let plot = histogram(&values, range(&values), 4, Charset::Unicode);
+        assert_eq!(plot, "▂▄█▂");
+    }
+
+    #[test]
+    fn histogram_leaves_empty_bins_blank() {
+        let values = [0., 0., 10.];
+        let plot = histogram(&values, range(&values), 5, Charset::Ascii);
+        assert_eq!(plot, "@   =");
+    }
+
+    #[test]
+    fn histogram_of_constant_sample() {
+        let values = [5., 5., 5.];
+        let plot = histogram(&values, range(&values), 5, Charset::Unicode);
+        assert_eq!(plot, "  █  ");
+    }
+}
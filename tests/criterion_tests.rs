@@ -1,21 +1,36 @@
 use criterion;
 use serde_json;
 
+use criterion::sink::{BenchmarkResult, Comparison, Report};
+use criterion::{
+    criterion_group, criterion_main,
+    measurement::{Timer, Unit},
+    profiler::{Profiler, ProfilingContext},
+    BatchSize, BenchmarkFilter, BenchmarkId, ComparisonTest, Criterion, Estimator, MetricsEndpoint,
+    MissingBaselinePolicy, OutlierPolicy, RegressionMethod, Samples, SamplingMode,
+};
 #[cfg(feature = "plotters")]
-use criterion::SamplingMode;
 use criterion::{
-    criterion_group, criterion_main, profiler::Profiler, BatchSize, BenchmarkId, Criterion,
+    Corner, KdeBandwidth, LegendPosition, PlotConfiguration, PlotFormat, PlotSelection,
+    PlottingBackend, SummaryPlot, Theme, Throughput,
 };
 use serde_json::value::Value;
 use std::cell::{Cell, RefCell};
 use std::cmp::max;
 use std::fs::File;
+use std::io::{Read, Write};
+use std::net::{TcpListener, UdpSocket};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::time::{Duration, SystemTime};
 use tempfile::{tempdir, TempDir};
 use walkdir::WalkDir;
 
+// Only counts allocations during the heap-profiling phase of `--heap-profile`, and once
+// `heap::Allocations` has been created.
+#[global_allocator]
+static ALLOCATOR: criterion::heap::CountingAllocator = criterion::heap::CountingAllocator::system();
+
 /*
  * Please note that these tests are not complete examples of how to use
  * Criterion.rs. See the benches folder for actual examples.
@@ -88,6 +103,7 @@ fn verify_stats(dir: &PathBuf, baseline: &str) {
     verify_json(&dir, &format!("{}/sample.json", baseline));
     verify_json(&dir, &format!("{}/tukey.json", baseline));
     verify_json(&dir, &format!("{}/benchmark.json", baseline));
+    verify_json(dir, &format!("{}/manifest.json", baseline));
     #[cfg(feature = "csv_output")]
     verify_file(&dir, &format!("{}/raw.csv", baseline));
 }
@@ -174,12 +190,121 @@ fn test_retain_baseline() {
 }
 
 #[test]
-#[should_panic(expected = "Baseline 'some-baseline' must exist before comparison is allowed")]
-fn test_compare_baseline_strict_panics_when_missing_baseline() {
+fn test_history_is_recorded() {
+    let dir = temp_dir();
+    for _ in 0..2 {
+        short_benchmark(&dir).bench_function("test_history", |b| b.iter(|| 10));
+    }
+
+    let bench_dir = dir.path().join("test_history");
+    let f = File::open(verify_file(&bench_dir, "history.json")).unwrap();
+    let history: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(history.as_array().unwrap().len(), 2);
+
+    #[cfg(feature = "html_reports")]
+    {
+        let report = std::fs::read_to_string(bench_dir.join("report/index.html")).unwrap();
+        assert!(report.contains("class=\"sparkline\""));
+    }
+}
+
+#[test]
+fn test_keep_baselines() {
+    let dir = temp_dir();
+    for baseline in ["a", "b", "c"] {
+        short_benchmark(&dir)
+            .keep_baselines(2)
+            .save_baseline(baseline.to_owned())
+            .bench_function("test_keep_baselines", |b| b.iter(|| 10));
+        // Make sure the baselines have distinct modification times.
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    let bench_dir = dir.path().join("test_keep_baselines");
+    verify_not_exists(&bench_dir, "a");
+    verify_file(&bench_dir, "b/sample.json.gz");
+    verify_not_exists(&bench_dir, "b/sample.json");
+    verify_stats(&bench_dir, "c");
+
+    // Compressed baselines can still be compared against.
+    short_benchmark(&dir)
+        .retain_baseline("b".to_owned(), true)
+        .bench_function("test_keep_baselines", |b| b.iter(|| 10));
+    verify_json(&bench_dir, "change/estimates.json");
+}
+
+#[test]
+fn test_external_baseline() {
+    let dir = temp_dir();
+    let baselines = temp_dir();
+    short_benchmark(&dir)
+        .save_baseline_to(baselines.path())
+        .bench_function("test_external_baseline", |b| b.iter(|| 10));
+
+    verify_stats(&baselines.path().to_owned(), "test_external_baseline");
+    // Nothing is saved under the output directory's default baseline.
+    verify_not_exists(&dir.path().join("test_external_baseline"), "base");
+
+    short_benchmark(&dir)
+        .load_baseline_from(baselines.path(), true)
+        .bench_function("test_external_baseline", |b| b.iter(|| 10));
+    verify_json(
+        &dir.path().join("test_external_baseline"),
+        "change/estimates.json",
+    );
+}
+
+#[test]
+fn test_compare_baseline_strict_skips_missing_baseline() {
     let dir = temp_dir();
     short_benchmark(&dir)
         .retain_baseline("some-baseline".to_owned(), true)
         .bench_function("test_compare_baseline", |b| b.iter(|| 10));
+    verify_not_exists(&dir.path().to_owned(), "test_compare_baseline/new");
+}
+
+#[test]
+fn test_missing_baseline_auto_create() {
+    let dir = temp_dir();
+    short_benchmark(&dir)
+        .retain_baseline("some-baseline".to_owned(), true)
+        .missing_baseline(MissingBaselinePolicy::AutoCreate)
+        .bench_function("test_missing_baseline", |b| b.iter(|| 10));
+    let bench_dir = dir.path().join("test_missing_baseline");
+    verify_stats(&bench_dir, "some-baseline");
+    verify_not_exists(&bench_dir, "change");
+
+    // The next run compares against the created baseline.
+    short_benchmark(&dir)
+        .retain_baseline("some-baseline".to_owned(), true)
+        .bench_function("test_missing_baseline", |b| b.iter(|| 10));
+    verify_json(&bench_dir, "change/estimates.json");
+}
+
+#[test]
+fn test_missing_baseline_error_fails_the_run() {
+    // The run exits at the end, so it is done in a child process running only this test.
+    if std::env::var_os("CRITERION_TEST_MISSING_BASELINE").is_some() {
+        let dir = temp_dir();
+        let mut c = short_benchmark(&dir)
+            .retain_baseline("some-baseline".to_owned(), false)
+            .missing_baseline(MissingBaselinePolicy::Error);
+        c.bench_function("test_missing_baseline", |b| b.iter(|| 10));
+        c.final_summary();
+        return;
+    }
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args([
+            "--exact",
+            "test_missing_baseline_error_fails_the_run",
+            "--nocapture",
+        ])
+        .env("CRITERION_TEST_MISSING_BASELINE", "1")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains("no saved baseline"), "{}", stderr);
 }
 
 #[test]
@@ -253,223 +378,2065 @@ fn test_measurement_time() {
 }
 
 #[test]
-fn test_bench_function() {
+fn test_thresholds_for_benchmark() {
     let dir = temp_dir();
-    short_benchmark(&dir).bench_function("test_bench_function", move |b| b.iter(|| 10));
+    let mut c = short_benchmark(&dir);
+    let mut group = c.benchmark_group("test_thresholds");
+    group.noise_threshold(0.03);
+    group.thresholds_for("noisy", 0.01, 0.2);
+    group.noise_threshold_for("io", 0.1);
+    group.bench_function("noisy", |b| b.iter(|| 10));
+    group.bench_function("quiet", |b| b.iter(|| 10));
+    group.bench_function("io", |b| b.iter(|| 10));
+    group.finish();
+
+    let config = |name: &str| {
+        let path = dir.path().join("test_thresholds").join(name).join("new");
+        let f = File::open(verify_file(&path, "manifest.json")).unwrap();
+        let manifest: Value = serde_json::from_reader(f).unwrap();
+        let config = &manifest["config"];
+        (
+            config["significance_level"].as_f64().unwrap(),
+            config["noise_threshold"].as_f64().unwrap(),
+        )
+    };
+    assert_eq!(config("noisy"), (0.01, 0.2));
+    assert_eq!(config("quiet"), (0.05, 0.03));
+    assert_eq!(config("io"), (0.05, 0.1));
 }
 
 #[test]
-fn test_filtering() {
+fn test_group_ranking() {
     let dir = temp_dir();
-    let counter = Counter::default();
-    let clone = counter.clone();
+    let run = |c: &mut Criterion| {
+        let mut group = c.benchmark_group("test_ranking");
+        for size in [1, 2] {
+            group.bench_with_input(BenchmarkId::new("fast", size), &size, |b, _| b.iter(|| 10));
+            group.bench_with_input(BenchmarkId::new("slow", size), &size, |b, &size| {
+                b.iter(|| (0..size * 100).sum::<u64>())
+            });
+        }
+        group.finish();
+    };
+    let rankings = || -> Vec<Value> {
+        let path = dir.path().join("test_ranking");
+        let f = File::open(verify_file(&path, "ranking.json")).unwrap();
+        let rankings: Value = serde_json::from_reader(f).unwrap();
+        rankings.as_array().unwrap().clone()
+    };
+    run(&mut short_benchmark(&dir));
+
+    let all = rankings();
+    assert_eq!(all.len(), 2);
+    for ranking in &all {
+        let entries = ranking["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["rank"], 1);
+        assert_eq!(entries[0]["ratio"], 1.0);
+    }
 
-    short_benchmark(&dir)
-        .with_filter("Foo")
-        .bench_function("test_filtering", move |b| b.iter(|| clone.count()));
+    // The benchmarks filtered out of a later run are not ranked with their old estimates.
+    run(&mut short_benchmark(&dir).with_filter("/1$"));
+    let filtered = rankings();
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0]["value_str"], "1");
+}
 
-    assert_eq!(counter.read(), 0);
-    assert!(!dir.path().join("test_filtering").is_dir());
+#[test]
+fn test_quick_mode_preset() {
+    let dir = temp_dir();
+    short_benchmark(&dir)
+        .quick_mode()
+        .bench_function("test_quick_mode", |b| b.iter(|| 10));
+
+    let path = dir.path().join("test_quick_mode");
+    let f = File::open(verify_file(&path.join("new"), "manifest.json")).unwrap();
+    let manifest: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(manifest["config"]["quick_mode"], true);
+    assert_eq!(manifest["config"]["sample_size"], 10);
+    assert_eq!(manifest["config"]["measurement_time_secs"], 1.0);
+    assert!(!path.join("report").exists());
 }
 
 #[test]
-fn test_timing_loops() {
+fn test_labels_saved_in_manifest() {
     let dir = temp_dir();
-    let mut c = short_benchmark(&dir);
-    let mut group = c.benchmark_group("test_timing_loops");
-    group.bench_function("iter_with_setup", |b| {
-        b.iter_with_setup(|| vec![10], |v| v[0])
-    });
-    group.bench_function("iter_with_large_setup", |b| {
-        b.iter_batched(|| vec![10], |v| v[0], BatchSize::NumBatches(1))
-    });
-    group.bench_function("iter_with_large_drop", |b| {
-        b.iter_with_large_drop(|| vec![10; 100])
-    });
-    group.bench_function("iter_batched_small", |b| {
-        b.iter_batched(|| vec![10], |v| v[0], BatchSize::SmallInput)
-    });
-    group.bench_function("iter_batched_large", |b| {
-        b.iter_batched(|| vec![10], |v| v[0], BatchSize::LargeInput)
-    });
-    group.bench_function("iter_batched_per_iteration", |b| {
-        b.iter_batched(|| vec![10], |v| v[0], BatchSize::PerIteration)
-    });
-    group.bench_function("iter_batched_one_batch", |b| {
-        b.iter_batched(|| vec![10], |v| v[0], BatchSize::NumBatches(1))
-    });
-    group.bench_function("iter_batched_10_iterations", |b| {
-        b.iter_batched(|| vec![10], |v| v[0], BatchSize::NumIterations(10))
-    });
-    group.bench_function("iter_batched_ref_small", |b| {
-        b.iter_batched_ref(|| vec![10], |v| v[0], BatchSize::SmallInput)
-    });
-    group.bench_function("iter_batched_ref_large", |b| {
-        b.iter_batched_ref(|| vec![10], |v| v[0], BatchSize::LargeInput)
-    });
-    group.bench_function("iter_batched_ref_per_iteration", |b| {
-        b.iter_batched_ref(|| vec![10], |v| v[0], BatchSize::PerIteration)
-    });
-    group.bench_function("iter_batched_ref_one_batch", |b| {
-        b.iter_batched_ref(|| vec![10], |v| v[0], BatchSize::NumBatches(1))
-    });
-    group.bench_function("iter_batched_ref_10_iterations", |b| {
-        b.iter_batched_ref(|| vec![10], |v| v[0], BatchSize::NumIterations(10))
-    });
+    short_benchmark(&dir)
+        .label("branch", "main")
+        .label("pr", "1")
+        .label("pr", "42")
+        .bench_function("test_labels", |b| b.iter(|| 10));
+
+    let path = dir.path().join("test_labels").join("new");
+    let f = File::open(verify_file(&path, "manifest.json")).unwrap();
+    let manifest: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(manifest["labels"]["branch"], "main");
+    assert_eq!(manifest["labels"]["pr"], "42");
+    // The compiler's version is always detected.
+    assert!(manifest["labels"]["rustc"]
+        .as_str()
+        .unwrap()
+        .starts_with("rustc "));
 }
 
-// Verify that all expected output files are present
-#[cfg(feature = "plotters")]
 #[test]
-fn test_output_files() {
-    let tempdir = temp_dir();
-    // Run benchmarks twice to produce comparisons
+fn test_comparison_estimator() {
+    let dir = temp_dir();
     for _ in 0..2 {
-        let mut c = short_benchmark(&tempdir);
-        let mut group = c.benchmark_group("test_output");
-        group.sampling_mode(SamplingMode::Linear);
-        group.bench_function("output_1", |b| b.iter(|| 10));
-        group.bench_function("output_2", |b| b.iter(|| 20));
-        group.bench_function("output_\\/*\"?", |b| b.iter(|| 30));
+        let mut c = short_benchmark(&dir);
+        let mut group = c.benchmark_group("test_comparison_estimator");
+        group.comparison_estimator(Estimator::Median);
+        group.comparison_test(ComparisonTest::MannWhitney);
+        group.bench_function("median", |b| b.iter(|| 10));
+        group.finish();
     }
 
-    // For each benchmark, assert that the expected files are present.
-    for x in 0..3 {
-        let dir = if x == 2 {
-            // Check that certain special characters are replaced with underscores
-            tempdir.path().join("test_output/output______")
-        } else {
-            tempdir.path().join(format!("test_output/output_{}", x + 1))
-        };
+    let path = dir.path().join("test_comparison_estimator").join("median");
+    let f = File::open(verify_file(&path.join("new"), "manifest.json")).unwrap();
+    let manifest: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(manifest["config"]["comparison_estimator"], "Median");
+    assert_eq!(manifest["config"]["comparison_test"], "MannWhitney");
+    verify_file(&path.join("change"), "estimates.json");
+}
 
-        verify_stats(&dir, "new");
-        verify_stats(&dir, "base");
-        verify_json(&dir, "change/estimates.json");
+#[test]
+fn test_welch_comparison() {
+    let dir = temp_dir();
+    for _ in 0..2 {
+        let mut c = short_benchmark(&dir).comparison_test(ComparisonTest::Welch);
+        let mut group = c.benchmark_group("test_welch_comparison");
+        group.bench_function("welch", |b| b.iter(|| 10));
+        group.finish();
+    }
 
-        #[cfg(feature = "html_reports")]
-        {
-            verify_svg(&dir, "report/MAD.svg");
-            verify_svg(&dir, "report/mean.svg");
-            verify_svg(&dir, "report/median.svg");
-            verify_svg(&dir, "report/pdf.svg");
-            verify_svg(&dir, "report/regression.svg");
-            verify_svg(&dir, "report/SD.svg");
-            verify_svg(&dir, "report/slope.svg");
-            verify_svg(&dir, "report/typical.svg");
-            verify_svg(&dir, "report/both/pdf.svg");
-            verify_svg(&dir, "report/both/regression.svg");
-            verify_svg(&dir, "report/change/mean.svg");
-            verify_svg(&dir, "report/change/median.svg");
-            verify_svg(&dir, "report/change/t-test.svg");
+    let path = dir.path().join("test_welch_comparison").join("welch");
+    let f = File::open(verify_file(&path.join("new"), "manifest.json")).unwrap();
+    let manifest: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(manifest["config"]["comparison_test"], "Welch");
+    let f = File::open(verify_file(&path.join("change"), "estimates.json")).unwrap();
+    let change: Value = serde_json::from_reader(f).unwrap();
+    let mean = &change["mean"]["confidence_interval"];
+    assert!(mean["lower_bound"].as_f64().unwrap() < mean["upper_bound"].as_f64().unwrap());
+}
 
-            verify_svg(&dir, "report/pdf_small.svg");
-            verify_svg(&dir, "report/regression_small.svg");
-            verify_svg(&dir, "report/relative_pdf_small.svg");
-            verify_svg(&dir, "report/relative_regression_small.svg");
-            verify_html(&dir, "report/index.html");
-        }
+#[test]
+fn test_percentiles() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).percentiles(&[50.0, 99.0]);
+    let mut group = c.benchmark_group("test_percentiles");
+    group.bench_function("default", |b| b.iter(|| 10));
+    group.percentiles(&[90.0]);
+    group.bench_function("tail", |b| b.iter(|| 10));
+    group.finish();
+
+    let path = dir.path().join("test_percentiles");
+    let f = File::open(verify_file(&path.join("default/new"), "estimates.json")).unwrap();
+    let estimates: Value = serde_json::from_reader(f).unwrap();
+    let percentiles = estimates["percentiles"].as_array().unwrap();
+    assert_eq!(percentiles.len(), 2);
+    assert_eq!(percentiles[0]["percentile"], 50.0);
+    assert_eq!(percentiles[1]["percentile"], 99.0);
+    for percentile in percentiles {
+        let estimate = &percentile["estimate"];
+        let point = estimate["point_estimate"].as_f64().unwrap();
+        assert!(
+            estimate["confidence_interval"]["lower_bound"]
+                .as_f64()
+                .unwrap()
+                <= point
+        );
+        assert!(
+            estimate["confidence_interval"]["upper_bound"]
+                .as_f64()
+                .unwrap()
+                >= point
+        );
     }
+    let f = File::open(verify_file(&path.join("tail/new"), "estimates.json")).unwrap();
+    let estimates: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(estimates["percentiles"][0]["percentile"], 90.0);
 
     #[cfg(feature = "html_reports")]
     {
-        // Check for overall report files
-        let dir = tempdir.path().join("test_output");
-
-        verify_svg(&dir, "report/violin.svg");
-        verify_html(&dir, "report/index.html");
+        let report = std::fs::read_to_string(path.join("default/report/index.html")).unwrap();
+        assert!(report.contains("<td title=\"Percentile\">p99</td>"));
     }
+}
 
-    // Run the final summary process and check for the report that produces
-    short_benchmark(&tempdir).final_summary();
+#[test]
+fn test_outlier_policy() {
+    let dir = temp_dir();
+    // Linear samples of about 100 ns per iteration, of which every tenth is a hundred times slower.
+    let mut output = String::from("criterion-samples linear outliers\n");
+    for i in 1..=20u64 {
+        let nanos = if i % 10 == 0 { 10_000 } else { 100 + i % 3 };
+        output.push_str(&format!("{} {}\n", i * 10, i * 10 * nanos));
+    }
+    output.push_str("criterion-samples-end\n");
+    let samples = Samples::parse_all(&output).unwrap();
 
-    #[cfg(feature = "html_reports")]
+    let mut c = short_benchmark(&dir);
+    let mut group = c.benchmark_group("test_outlier_policy");
+    for (name, policy) in [
+        ("keep", OutlierPolicy::Keep),
+        ("winsorize", OutlierPolicy::Winsorize),
+        ("trim", OutlierPolicy::Trim),
+    ]
+    .iter()
     {
-        let dir = tempdir.path().to_owned();
-        verify_html(&dir, "report/index.html");
+        group.outlier_policy(*policy);
+        group.bench_samples(*name, &samples[0]);
     }
+    group.finish();
+
+    let path = dir.path().join("test_outlier_policy");
+    let mean = |name: &str| {
+        let f = File::open(verify_file(&path.join(name).join("new"), "estimates.json")).unwrap();
+        let estimates: Value = serde_json::from_reader(f).unwrap();
+        estimates["mean"]["point_estimate"].as_f64().unwrap()
+    };
+    assert!(mean("keep") > 500.0);
+    assert!(mean("winsorize") < 110.0);
+    assert!(mean("trim") < 103.0);
+
+    let f = File::open(verify_file(&path.join("trim/new"), "manifest.json")).unwrap();
+    let manifest: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(manifest["config"]["outlier_policy"], "Trim");
+    // The saved sample keeps the outliers.
+    let f = File::open(verify_file(&path.join("trim/new"), "sample.json")).unwrap();
+    let sample: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(sample["times"].as_array().unwrap().len(), 20);
 }
 
-#[cfg(feature = "plotters")]
 #[test]
-fn test_output_files_flat_sampling() {
-    let tempdir = temp_dir();
-    // Run benchmark twice to produce comparisons
-    for _ in 0..2 {
-        let mut c = short_benchmark(&tempdir);
-        let mut group = c.benchmark_group("test_output");
-        group.sampling_mode(SamplingMode::Flat);
-        group.bench_function("output_flat", |b| b.iter(|| 10));
+fn test_bootstrap_seed() {
+    let mut output = String::from("criterion-samples linear seeded\n");
+    for i in 1..=30u64 {
+        output.push_str(&format!("{} {}\n", i * 10, i * 10 * (100 + i * 7 % 11)));
     }
+    output.push_str("criterion-samples-end\n");
+    let samples = Samples::parse_all(&output).unwrap();
+
+    // The same samples, analyzed twice and compared to themselves, give the same results.
+    let results: Vec<(String, String)> = (0..2)
+        .map(|_| {
+            let dir = temp_dir();
+            for _ in 0..2 {
+                short_benchmark(&dir)
+                    .bootstrap_seed(42)
+                    .benchmark_group("test_bootstrap_seed")
+                    .bench_samples("seeded", &samples[0]);
+            }
+            let path = dir.path().join("test_bootstrap_seed/seeded");
+            let read = |file: &str| std::fs::read_to_string(verify_file(&path, file)).unwrap();
+            (read("new/estimates.json"), read("change/estimates.json"))
+        })
+        .collect();
+    assert_eq!(results[0], results[1]);
+}
 
-    let dir = tempdir.path().join("test_output/output_flat");
+#[cfg(feature = "plotters")]
+#[test]
+fn test_drift_diagnostics() {
+    let dir = temp_dir();
+    // Flat samples of 100 iterations, steady or slowing down by a fifth over the run.
+    let samples = |name: &str, slowdown: u64| {
+        let mut output = format!("criterion-samples flat {}\n", name);
+        for i in 0..50u64 {
+            let nanos = 1000 + (i * 37) % 7 + i * slowdown;
+            output.push_str(&format!("100 {}\n", 100 * nanos));
+        }
+        output.push_str("criterion-samples-end\n");
+        Samples::parse_all(&output).unwrap().remove(0)
+    };
 
-    verify_stats(&dir, "new");
-    verify_stats(&dir, "base");
-    verify_json(&dir, "change/estimates.json");
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+    let mut group = c.benchmark_group("test_drift_diagnostics");
+    group.bench_samples("steady", &samples("steady", 0));
+    group.bench_samples("drifting", &samples("drifting", 4));
+    group.finish();
 
+    let path = dir.path().join("test_drift_diagnostics");
+    verify_file(&path.join("drifting/report"), "drift.svg");
+    assert!(!path.join("steady/report/drift.svg").exists());
     #[cfg(feature = "html_reports")]
     {
-        verify_svg(&dir, "report/MAD.svg");
-        verify_svg(&dir, "report/mean.svg");
-        verify_svg(&dir, "report/median.svg");
-        verify_svg(&dir, "report/pdf.svg");
-        verify_svg(&dir, "report/iteration_times.svg");
-        verify_svg(&dir, "report/SD.svg");
-        verify_svg(&dir, "report/typical.svg");
-        verify_svg(&dir, "report/both/pdf.svg");
-        verify_svg(&dir, "report/both/iteration_times.svg");
-        verify_svg(&dir, "report/change/mean.svg");
-        verify_svg(&dir, "report/change/median.svg");
-        verify_svg(&dir, "report/change/t-test.svg");
-
-        verify_svg(&dir, "report/pdf_small.svg");
-        verify_svg(&dir, "report/iteration_times_small.svg");
-        verify_svg(&dir, "report/relative_pdf_small.svg");
-        verify_svg(&dir, "report/relative_iteration_times_small.svg");
-        verify_html(&dir, "report/index.html");
+        let report = std::fs::read_to_string(path.join("drifting/report/index.html")).unwrap();
+        assert!(report.contains("Warning: the times drifted up by"));
+        assert!(report.contains("drift.svg"));
+        let report = std::fs::read_to_string(path.join("steady/report/index.html")).unwrap();
+        assert!(!report.contains("Warning:"));
     }
 }
 
 #[test]
-#[should_panic(expected = "Benchmark function must call Bencher::iter or related method.")]
-fn test_bench_with_no_iteration_panics() {
+fn test_target_precision() {
     let dir = temp_dir();
-    short_benchmark(&dir).bench_function("no_iter", |_b| {});
+    let mut c = short_benchmark(&dir);
+    let mut group = c.benchmark_group("test_target_precision");
+    // Any benchmark is within 50% after the first ten samples, and none within a billionth.
+    group.target_precision(0.5, Duration::from_secs(2));
+    group.bench_function("reached", |b| b.iter(|| 10));
+    group.target_precision(1e-9, Duration::from_millis(300));
+    group.bench_function("unreachable", |b| b.iter(|| 10));
+    group.finish();
+
+    let path = dir.path().join("test_target_precision");
+    let sample_count = |id: &str| {
+        let f = File::open(verify_file(&path.join(id), "new/sample.json")).unwrap();
+        let sample: Value = serde_json::from_reader(f).unwrap();
+        sample["times"].as_array().unwrap().len()
+    };
+    assert_eq!(sample_count("reached"), 10);
+    assert!(sample_count("unreachable") > 10);
+    let f = File::open(verify_file(&path.join("unreachable/new"), "manifest.json")).unwrap();
+    let manifest: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(manifest["config"]["target_precision"], 1e-9);
+    assert_eq!(manifest["config"]["max_measurement_time_secs"], 0.3);
 }
 
 #[test]
-fn test_benchmark_group_with_input() {
+fn test_streaming_statistics() {
     let dir = temp_dir();
-    let mut c = short_benchmark(&dir);
-    let mut group = c.benchmark_group("Test Group");
-    for x in 0..2 {
-        group.bench_with_input(BenchmarkId::new("Test 1", x), &x, |b, i| b.iter(|| i));
-        group.bench_with_input(BenchmarkId::new("Test 2", x), &x, |b, i| b.iter(|| i));
-    }
+    let mut c = short_benchmark(&dir).without_plots().nresamples(100);
+    let mut group = c.benchmark_group("test_streaming_statistics");
+    group
+        .streaming_statistics(true)
+        .sample_size(12_000)
+        .sampling_mode(SamplingMode::Flat);
+    group.bench_function("streamed", |b| b.iter(|| 10));
     group.finish();
+
+    let path = dir.path().join("test_streaming_statistics/streamed/new");
+    let f = File::open(verify_file(&path, "sample.json")).unwrap();
+    let sample: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(sample["times"].as_array().unwrap().len(), 10_000);
+    let f = File::open(verify_file(&path, "manifest.json")).unwrap();
+    let manifest: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(manifest["streamed_samples"], 12_000);
+    assert_eq!(manifest["config"]["streaming_statistics"], true);
+    let f = File::open(verify_file(&path, "estimates.json")).unwrap();
+    let estimates: Value = serde_json::from_reader(f).unwrap();
+    let mean = &estimates["mean"];
+    let point = mean["point_estimate"].as_f64().unwrap();
+    let interval = &mean["confidence_interval"];
+    assert!(interval["lower_bound"].as_f64().unwrap() <= point);
+    assert!(interval["upper_bound"].as_f64().unwrap() >= point);
 }
 
 #[test]
-fn test_benchmark_group_without_input() {
+fn test_minimum_time() {
     let dir = temp_dir();
-    let mut c = short_benchmark(&dir);
-    let mut group = c.benchmark_group("Test Group 2");
-    group.bench_function("Test 1", |b| b.iter(|| 30));
-    group.bench_function("Test 2", |b| b.iter(|| 20));
+    let mut c = short_benchmark(&dir).minimum_time(true);
+    let mut group = c.benchmark_group("test_minimum_time");
+    group.bench_function("minimum", |b| b.iter(|| 10));
+    group.minimum_time(false);
+    group.bench_function("typical", |b| b.iter(|| 10));
     group.finish();
+
+    let path = dir.path().join("test_minimum_time");
+    let f = File::open(verify_file(&path.join("minimum/new"), "estimates.json")).unwrap();
+    let estimates: Value = serde_json::from_reader(f).unwrap();
+    let minimum = &estimates["minimum"];
+    let point = minimum["point_estimate"].as_f64().unwrap();
+    assert!(
+        minimum["confidence_interval"]["lower_bound"]
+            .as_f64()
+            .unwrap()
+            <= point
+    );
+    assert!(
+        minimum["confidence_interval"]["upper_bound"]
+            .as_f64()
+            .unwrap()
+            >= point
+    );
+    assert!(point <= estimates["median"]["point_estimate"].as_f64().unwrap());
+    let f = File::open(verify_file(&path.join("minimum/new"), "manifest.json")).unwrap();
+    let manifest: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(manifest["config"]["minimum_time"], true);
+
+    let f = File::open(verify_file(&path.join("typical/new"), "estimates.json")).unwrap();
+    let estimates: Value = serde_json::from_reader(f).unwrap();
+    assert!(estimates.get("minimum").is_none());
 }
 
 #[test]
-fn test_criterion_doesnt_panic_if_measured_time_is_zero() {
+fn test_metadata_in_reports() {
     let dir = temp_dir();
-    let mut c = short_benchmark(&dir);
-    c.bench_function("zero_time", |bencher| {
-        bencher.iter_custom(|_iters| Duration::new(0, 0))
-    });
+    let junit_path = dir.path().join("junit.xml");
+    short_benchmark(&dir)
+        .metadata("team", "perf")
+        .label("commit", "abc123")
+        .junit_output(&junit_path)
+        .bench_function("test_metadata", |b| b.iter(|| 10));
+
+    let path = dir.path().join("test_metadata").join("new");
+    let f = File::open(verify_file(&path, "manifest.json")).unwrap();
+    let manifest: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(manifest["labels"]["team"], "perf");
+    // Labels which are set replace the detected metadata.
+    assert_eq!(manifest["labels"]["commit"], "abc123");
+
+    let xml = std::fs::read_to_string(&junit_path).unwrap();
+    assert!(xml.contains("<property name=\"team\" value=\"perf\"/>"));
+    assert!(xml.contains("<property name=\"commit\" value=\"abc123\"/>"));
+    let summary =
+        std::fs::read_to_string(verify_file(&dir.path().to_owned(), "summary.md")).unwrap();
+    let labels = summary.lines().last().unwrap();
+    assert!(labels.starts_with("Labels: `commit=abc123`, "));
+    assert!(labels.contains("`team=perf`"));
 }
 
-mod macros {
-    use super::{criterion, criterion_group, criterion_main};
+#[test]
+fn test_theil_sen_regression() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).nresamples(1001);
+    let mut group = c.benchmark_group("test_theil_sen_regression");
+    group
+        .sampling_mode(SamplingMode::Linear)
+        .regression_method(RegressionMethod::TheilSen);
+    group.bench_function("a", |b| b.iter(|| 10));
+    group.finish();
+
+    let path = dir.path().join("test_theil_sen_regression/a/new");
+    let f = File::open(verify_file(&path, "manifest.json")).unwrap();
+    let manifest: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(manifest["config"]["regression_method"], "TheilSen");
+    let f = File::open(verify_file(&path, "estimates.json")).unwrap();
+    let estimates: Value = serde_json::from_reader(f).unwrap();
+    assert!(estimates["slope"]["point_estimate"].as_f64().unwrap() > 0.);
+}
+
+#[test]
+fn test_json_output() {
+    let dir = temp_dir();
+    let json_path = dir.path().join("results.json");
+    for _ in 0..2 {
+        let mut c = short_benchmark(&dir)
+            .label("branch", "main")
+            .json_output(&json_path);
+        let mut group = c.benchmark_group("test_json_output");
+        group.bench_with_input(BenchmarkId::new("a", 1024), &1024, |b, &size| {
+            b.iter(|| size)
+        });
+        group.finish();
+    }
+
+    // Each run replaces the file.
+    let text = std::fs::read_to_string(&json_path).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 1);
+    let result: Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(result["reason"], "benchmark-complete");
+    assert_eq!(result["id"], "test_json_output/a/1024");
+    assert_eq!(result["function"], "a");
+    assert_eq!(result["value"], "1024");
+    assert_eq!(result["unit"], "ns");
+    let mean = &result["estimates"]["mean"];
+    assert!(mean["lower_bound"].as_f64() <= mean["point_estimate"].as_f64());
+    assert!(result["change"]["p_value"].is_number());
+    assert!(result["outliers"]["high_severe"].is_number());
+    assert_eq!(result["labels"]["branch"], "main");
+}
+
+#[test]
+#[cfg(feature = "csv_output")]
+fn test_export_csv() {
+    let dir = temp_dir();
+    let csv_dir = dir.path().join("csv");
+    let mut c = short_benchmark(&dir).sample_size(10).export_csv(&csv_dir);
+    let mut group = c.benchmark_group("test_export_csv");
+    for size in [1, 2].iter() {
+        group.bench_with_input(BenchmarkId::new("a", size), size, |b, &size| {
+            b.iter(|| size)
+        });
+    }
+    group.finish();
+
+    let samples = std::fs::read_to_string(verify_file(&csv_dir, "samples.csv")).unwrap();
+    let mut lines = samples.lines();
+    assert_eq!(
+        lines.next(),
+        Some("id,group,function,parameter,iteration_count,measured_value,unit")
+    );
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+    assert_eq!(rows.len(), 2 * 10);
+    assert_eq!(
+        &rows[0][..4],
+        &["test_export_csv/a/1", "test_export_csv", "a", "1"]
+    );
+    assert_eq!(rows[10][3], "2");
+    assert!(rows.iter().all(|row| row[6] == "ns"));
+
+    let summary = std::fs::read_to_string(verify_file(&csv_dir, "summary.csv")).unwrap();
+    let statistics: Vec<&str> = summary
+        .lines()
+        .skip(1)
+        .filter(|line| line.starts_with("test_export_csv/a/1,"))
+        .map(|line| line.split(',').nth(4).unwrap())
+        .collect();
+    assert!(summary.lines().next().unwrap().ends_with(",unit,labels"));
+    assert!(statistics.contains(&"mean"));
+    assert!(statistics.contains(&"median"));
+}
+
+#[test]
+fn test_junit_output() {
+    let dir = temp_dir();
+    let junit_path = dir.path().join("junit.xml");
+    // The second run of `slow` is much slower, which is a regression beyond any noise threshold.
+    for (run, work) in [1_000u64, 100_000].iter().enumerate() {
+        let mut c = short_benchmark(&dir).noise_threshold(0.5);
+        if run == 1 {
+            c = c.junit_output(&junit_path);
+        }
+        let mut group = c.benchmark_group("test_junit_output");
+        group.bench_function("fast", |b| b.iter(|| criterion::black_box(1u64)));
+        group.bench_function("slow", |b| {
+            b.iter(|| (0..criterion::black_box(*work)).sum::<u64>())
+        });
+        group.finish();
+    }
+
+    let xml = std::fs::read_to_string(&junit_path).unwrap();
+    assert!(xml.contains("<testsuite name=\"criterion\" tests=\"2\" failures=\"1\">"));
+    assert!(xml.contains(
+        "<testcase classname=\"test_junit_output\" name=\"test_junit_output/fast\" time="
+    ));
+    let slow = &xml[xml.find("name=\"test_junit_output/slow\"").unwrap()..];
+    assert!(slow.starts_with("name=\"test_junit_output/slow\" time=\""));
+    assert!(slow.contains(
+        "<failure type=\"regression\" message=\"Performance has regressed beyond the noise threshold of 50.000%"
+    ));
+    assert_eq!(xml.matches("<failure").count(), 1);
+}
+
+#[test]
+fn test_markdown_summary() {
+    let dir = temp_dir();
+    // The second run of `slow` is much slower, which is a regression beyond any noise threshold.
+    for work in &[1_000u64, 100_000] {
+        let mut c = short_benchmark(&dir).noise_threshold(0.5);
+        let mut group = c.benchmark_group("test_markdown_summary");
+        group.bench_function("fast", |b| b.iter(|| criterion::black_box(1u64)));
+        group.bench_function("slow", |b| {
+            b.iter(|| (0..criterion::black_box(*work)).sum::<u64>())
+        });
+        group.finish();
+    }
+
+    let summary =
+        std::fs::read_to_string(verify_file(&dir.path().to_owned(), "summary.md")).unwrap();
+    let lines: Vec<&str> = summary.lines().collect();
+    assert_eq!(lines[2], "| Benchmark | Mean | Change | Significance |");
+    // Both runs happened in this process, so both are listed.
+    assert!(lines[4].starts_with("| `test_markdown_summary/fast` | "));
+    assert!(lines[4].ends_with(" | - | No baseline |"));
+    let slow = lines[7];
+    assert!(slow.starts_with("| `test_markdown_summary/slow` | "));
+    assert!(slow.contains(" | +"));
+    assert!(slow.ends_with("% | ▲ Regressed |"));
+}
+
+#[test]
+fn test_group_badge() {
+    let dir = temp_dir();
+    let badge = dir.path().join("test_group_badge/badge.svg");
+    // The second run of `slow` is much slower, which is a regression beyond any noise threshold.
+    for work in &[1_000u64, 100_000] {
+        let mut c = short_benchmark(&dir).noise_threshold(0.5);
+        let mut group = c.benchmark_group("test_group_badge");
+        group.bench_function("fast", |b| b.iter(|| criterion::black_box(1u64)));
+        group.bench_function("slow", |b| {
+            b.iter(|| (0..criterion::black_box(*work)).sum::<u64>())
+        });
+        group.finish();
+
+        let svg = std::fs::read_to_string(&badge).unwrap();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.contains("<title>test_group_badge: "));
+        if *work == 1_000 {
+            // Without a baseline there is no change, and the badge is grey.
+            assert!(!svg.contains('▲') && !svg.contains('▼'));
+            assert!(svg.contains("fill=\"#9f9f9f\""));
+        } else {
+            assert!(svg.contains(" ▲"));
+            assert!(svg.contains("fill=\"#e05d44\""));
+        }
+    }
+}
+
+#[test]
+fn test_push_metrics_to_pushgateway() {
+    let dir = temp_dir();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/prefix", listener.local_addr().unwrap());
+    // Answers every push, and sends the requests back once the listener is dropped.
+    let server = std::thread::spawn(move || {
+        let mut requests = vec![];
+        for stream in listener.incoming().take(2) {
+            let mut stream = stream.unwrap();
+            let mut request = vec![];
+            let mut buffer = [0; 4096];
+            loop {
+                let n = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length: usize = text
+                        .lines()
+                        .find_map(|line| line.strip_prefix("Content-Length: "))
+                        .unwrap()
+                        .parse()
+                        .unwrap();
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            requests.push(String::from_utf8(request).unwrap());
+        }
+        requests
+    });
+
+    let mut c = short_benchmark(&dir)
+        .label("commit", "abc 123")
+        .push_metrics(MetricsEndpoint::Pushgateway(url));
+    let mut group = c.benchmark_group("test_push_metrics_to_pushgateway");
+    group.bench_function("a", |b| b.iter(|| 10));
+    group.bench_function("b", |b| b.iter(|| 20));
+    group.finish();
+
+    let requests = server.join().unwrap();
+    // Each benchmark is pushed to its own group, whose ID is in base64 since it has a slash.
+    let path = "PUT /prefix/metrics/job/criterion/commit/abc%20123/benchmark@base64/";
+    assert!(requests[0].starts_with(&format!(
+        "{}dGVzdF9wdXNoX21ldHJpY3NfdG9fcHVzaGdhdGV3YXkvYQ HTTP/1.1\r\n",
+        path
+    )));
+    assert!(requests[1].starts_with(&format!(
+        "{}dGVzdF9wdXNoX21ldHJpY3NfdG9fcHVzaGdhdGV3YXkvYg HTTP/1.1\r\n",
+        path
+    )));
+    let a = "criterion_estimate{benchmark=\"test_push_metrics_to_pushgateway/a\",statistic=\"mean\",unit=\"ns\"} ";
+    let b = "criterion_estimate{benchmark=\"test_push_metrics_to_pushgateway/b\",statistic=\"median\",unit=\"ns\"} ";
+    assert!(requests[0].contains("# TYPE criterion_estimate gauge\n"));
+    assert!(requests[0].contains(a));
+    assert!(!requests[0].contains(b));
+    assert!(!requests[1].contains(a));
+    assert!(requests[1].contains(b));
+}
+
+#[test]
+fn test_push_metrics_to_statsd() {
+    let dir = temp_dir();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    let address = socket.local_addr().unwrap().to_string();
+    for _ in 0..2 {
+        short_benchmark(&dir)
+            .label("commit", "abc123")
+            .push_metrics(MetricsEndpoint::Statsd(address.clone()))
+            .bench_function("test_push_metrics_to_statsd", |b| b.iter(|| 10));
+    }
+
+    let mut packets = vec![];
+    for _ in 0..2 {
+        let mut buffer = [0; 4096];
+        let n = socket.recv(&mut buffer).unwrap();
+        packets.push(String::from_utf8(buffer[..n].to_vec()).unwrap());
+    }
+    let tags = "|g|#benchmark:test_push_metrics_to_statsd,commit:abc123";
+    assert!(packets[0].starts_with("criterion.estimate.mean:"));
+    assert!(packets[0].contains(&format!("{},unit:ns", tags)));
+    // Only the second run has a baseline to compare against.
+    assert!(!packets[0].contains("criterion.change_percent.mean:"));
+    assert!(packets[1].contains("\ncriterion.change_percent.mean:"));
+    assert!(packets[1].ends_with(tags));
+}
+
+#[test]
+fn test_heap_profile() {
+    let dir = temp_dir();
+    short_benchmark(&dir)
+        .heap_profile(Some(10))
+        .bench_function("test_heap_profile", |b| {
+            b.iter(|| vec![0u8; criterion::black_box(1024)])
+        });
+
+    // Other tests running at the same time can only add to the counts.
+    let path = dir.path().join("test_heap_profile").join("new");
+    let f = File::open(verify_file(&path, "heap.json")).unwrap();
+    let heap: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(heap["iterations"], 10);
+    assert!(heap["total_blocks"].as_u64().unwrap() >= 10);
+    assert!(heap["total_bytes"].as_u64().unwrap() >= 10 * 1024);
+    assert!(heap["peak_bytes"].as_u64().unwrap() >= 1024);
+}
+
+#[test]
+fn test_allocations_measurement() {
+    let dir = temp_dir();
+    // Run twice to compare the allocations against the baseline.
+    for _ in 0..2 {
+        let mut c = Criterion::default()
+            .with_measurement(criterion::heap::Allocations::bytes().unwrap())
+            .output_directory(dir.path())
+            .warm_up_time(Duration::from_millis(250))
+            .measurement_time(Duration::from_millis(500))
+            .nresamples(2000);
+        c.bench_function("test_allocations_measurement", |b| {
+            b.iter(|| vec![0u8; criterion::black_box(1024)])
+        });
+    }
+
+    // Other tests running at the same time can only add to the counts.
+    let path = dir.path().join("test_allocations_measurement").join("new");
+    let f = File::open(verify_file(&path, "estimates.json")).unwrap();
+    let estimates: Value = serde_json::from_reader(f).unwrap();
+    assert!(estimates["median"]["point_estimate"].as_f64().unwrap() >= 1024.0);
+    verify_file(&path.join("../change"), "estimates.json");
+}
+
+#[test]
+fn test_result_sink() {
+    let dir = temp_dir();
+    let results = Rc::new(RefCell::new(vec![]));
+    for _ in 0..2 {
+        let results = Rc::clone(&results);
+        short_benchmark(&dir)
+            .with_result_sink(move |result: &BenchmarkResult| {
+                results.borrow_mut().push(result.clone())
+            })
+            .bench_function("test_result_sink", |b| b.iter(|| 10));
+    }
+
+    let results = results.borrow();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].id, "test_result_sink");
+    assert_eq!(results[0].unit, "ns");
+    assert!(results[0].mean.point_estimate > 0.0);
+    assert!(results[0].mean.lower_bound <= results[0].mean.upper_bound);
+    assert!(results[0].iterations > 0);
+    // Only the second run has a baseline to compare against.
+    assert!(results[0].change.is_none());
+    assert!(results[1].change.is_some());
+}
+
+struct RecordingReport(Rc<RefCell<Vec<String>>>);
+impl Report for RecordingReport {
+    fn benchmark_start(&mut self, id: &str) {
+        self.0.borrow_mut().push(format!("start {}", id));
+    }
+
+    fn warmup(&mut self, id: &str, _warmup_ns: f64) {
+        self.0.borrow_mut().push(format!("warmup {}", id));
+    }
+
+    fn measurement_start(&mut self, id: &str, sample_count: u64, _: f64, _: u64) {
+        self.0
+            .borrow_mut()
+            .push(format!("measure {} {}", id, sample_count));
+    }
+
+    fn analysis(&mut self, id: &str) {
+        self.0.borrow_mut().push(format!("analysis {}", id));
+    }
+
+    fn measurement_complete(&mut self, result: &BenchmarkResult) {
+        self.0.borrow_mut().push(format!("complete {}", result.id));
+    }
+
+    fn comparison(&mut self, result: &BenchmarkResult, comparison: &Comparison) {
+        assert!(comparison.p_value >= 0.0 && comparison.p_value <= 1.0);
+        assert_eq!(comparison.noise_threshold, 0.01);
+        assert_eq!(Some(comparison.mean), result.change);
+        self.0
+            .borrow_mut()
+            .push(format!("comparison {}", result.id));
+    }
+}
+
+#[test]
+fn test_add_report() {
+    let dir = temp_dir();
+    let events = Rc::new(RefCell::new(vec![]));
+    for _ in 0..2 {
+        short_benchmark(&dir)
+            .sample_size(10)
+            .add_report(Box::new(RecordingReport(Rc::clone(&events))))
+            .bench_function("test_add_report", |b| b.iter(|| 10));
+    }
+
+    let run = [
+        "start test_add_report",
+        "warmup test_add_report",
+        "measure test_add_report 10",
+        "analysis test_add_report",
+        "complete test_add_report",
+    ];
+    let events = events.borrow();
+    // Only the second run has a baseline to compare against.
+    assert_eq!(events[..5], run[..]);
+    assert_eq!(events[5..10], run[..]);
+    assert_eq!(events[10..], ["comparison test_add_report"]);
+}
+
+#[test]
+fn test_background_analysis() {
+    let dir = temp_dir();
+    let results = Rc::new(RefCell::new(vec![]));
+    for _ in 0..2 {
+        let results = Rc::clone(&results);
+        let mut c = short_benchmark(&dir)
+            .background_analysis(true)
+            .with_result_sink(move |result: &BenchmarkResult| {
+                results.borrow_mut().push(result.id.clone())
+            });
+        let mut group = c.benchmark_group("test_background_analysis");
+        for i in 0..3u64 {
+            group.bench_with_input(BenchmarkId::from_parameter(i), &i, |b, i| b.iter(|| i * 2));
+        }
+        group.finish();
+    }
+
+    // Reported in order, and compared to the first run in the second.
+    let expected: Vec<_> = (0..3)
+        .map(|i| format!("test_background_analysis/{}", i))
+        .collect();
+    assert_eq!(results.borrow()[..3], expected[..]);
+    assert_eq!(results.borrow()[3..], expected[..]);
+    for i in 0..3 {
+        let path = dir
+            .path()
+            .join("test_background_analysis")
+            .join(i.to_string());
+        verify_stats(&path, "new");
+        verify_json(&path, "change/estimates.json");
+    }
+}
+
+#[test]
+fn test_spill_samples() {
+    let dir = temp_dir();
+    for _ in 0..2 {
+        short_benchmark(&dir)
+            .spill_samples(true)
+            .background_analysis(true)
+            .bench_function("test_spill_samples", |b| b.iter(|| 10));
+    }
+
+    let path = dir.path().join("test_spill_samples");
+    verify_stats(&path, "new");
+    verify_json(&path, "change/estimates.json");
+    let f = File::open(verify_file(&path, "new/sample.json")).unwrap();
+    let sample: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(sample["times"].as_array().unwrap().len(), 100);
+    // The spill files are removed as soon as they have been mapped.
+    let prefix = format!("criterion-spill-{}-", std::process::id());
+    assert!(!std::fs::read_dir(std::env::temp_dir())
+        .unwrap()
+        .any(|entry| entry
+            .unwrap()
+            .file_name()
+            .to_string_lossy()
+            .starts_with(&prefix)));
+}
+
+#[test]
+fn test_defer_plots() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).defer_plots(true);
+    let mut group = c.benchmark_group("test_defer_plots");
+    group.bench_function("first", |b| b.iter(|| 10));
+    group.bench_function("second", |b| b.iter(|| 20));
+    group.finish();
+    c.final_summary();
+
+    for id in &["first", "second"] {
+        let path = dir.path().join("test_defer_plots").join(id);
+        verify_stats(&path, "new");
+        #[cfg(feature = "html_reports")]
+        {
+            verify_svg(&path, "report/pdf.svg");
+            verify_html(&path, "report/index.html");
+        }
+    }
+}
+
+#[cfg(feature = "html_reports")]
+#[test]
+fn test_report_cache() {
+    let dir = temp_dir();
+    let run = |filter: &str| {
+        let mut c = short_benchmark(&dir).with_filter(filter);
+        let mut group = c.benchmark_group("test_report_cache");
+        for function in &["a", "b"] {
+            for size in 1..=2 {
+                group.bench_with_input(BenchmarkId::new(*function, size), &size, |b, &size| {
+                    b.iter(|| size)
+                });
+            }
+        }
+    };
+    let hash = |subgroup: &str| {
+        let path = dir.path().join("test_report_cache").join(subgroup);
+        std::fs::read_to_string(verify_file(&path, "report/inputs.hash")).unwrap()
+    };
+    let modified = |subgroup: &str| {
+        let path = dir.path().join("test_report_cache").join(subgroup);
+        verify_html(&path, "report/index.html");
+        std::fs::metadata(path.join("report/index.html"))
+            .unwrap()
+            .modified()
+            .unwrap()
+    };
+
+    run("test_report_cache");
+    let (a, b, one, two) = (hash("a"), hash("b"), hash("1"), hash("2"));
+    let unchanged = modified("b");
+
+    // Only a/1 is run again; the summaries of the other benchmarks are left alone, except that
+    // a/2 and b/2, which were not run, are no longer ranked.
+    std::thread::sleep(Duration::from_millis(10));
+    run("test_report_cache/a/1");
+    assert_ne!(hash("a"), a);
+    assert_ne!(hash("1"), one);
+    assert_ne!(hash("2"), two);
+    assert_eq!(hash("b"), b);
+    assert_eq!(modified("b"), unchanged);
+    verify_svg(&dir.path().join("test_report_cache/b"), "report/violin.svg");
+
+    // The line chart of b marks the changes, so it is drawn again when only a change differs.
+    let b = hash("b");
+    let change = dir.path().join("test_report_cache/b/2/change");
+    std::fs::create_dir_all(&change).unwrap();
+    std::fs::write(change.join("significance.json"), "\"Regressed\"").unwrap();
+    run("test_report_cache/a/1");
+    assert_ne!(hash("b"), b);
+}
+
+#[test]
+fn test_batch_hooks() {
+    let dir = temp_dir();
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let (enter_log, exit_log) = (log.clone(), log.clone());
+    let mut c = short_benchmark(&dir).with_batch_hooks(criterion::hooks::callbacks(
+        move |id: &str, iters| enter_log.borrow_mut().push((true, id.to_owned(), iters)),
+        move |id: &str, iters| exit_log.borrow_mut().push((false, id.to_owned(), iters)),
+    ));
+    c.bench_function("test_batch_hooks_iter", |b| b.iter(|| 10));
+    c.bench_function("test_batch_hooks_batched", |b| {
+        b.iter_batched(|| 10, |i| i + 1, BatchSize::SmallInput)
+    });
+
+    let log = log.borrow();
+    for id in &["test_batch_hooks_iter", "test_batch_hooks_batched"] {
+        let batches: Vec<_> = log.iter().filter(|(_, batch, _)| batch == id).collect();
+        // Every batch is entered and then exited, and only the samples are measured in batches.
+        for pair in batches.chunks(2) {
+            assert!(pair[0].0 && !pair[1].0);
+            assert_eq!(pair[0].2, pair[1].2);
+        }
+        let f = File::open(verify_file(&dir.path().join(id), "new/sample.json")).unwrap();
+        let sample: Value = serde_json::from_reader(f).unwrap();
+        let iters: f64 = sample["iters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|iters| iters.as_f64().unwrap())
+            .sum();
+        let batched: u64 = batches.iter().step_by(2).map(|(_, _, iters)| iters).sum();
+        assert_eq!(batched as f64, iters);
+    }
+    assert_eq!(
+        log.iter()
+            .filter(|(enter, id, _)| *enter && id == "test_batch_hooks_iter")
+            .count(),
+        100
+    );
+}
+
+#[test]
+fn test_bench_samples() {
+    let dir = temp_dir();
+    // As printed by a device running criterion-core.
+    let mut output = String::from("boot ok\ncriterion-samples linear on_device\n");
+    for i in 1..=20u64 {
+        output.push_str(&format!("{} {}\n", i * 10, i * 1000 + (i * 37) % 50));
+    }
+    output.push_str("criterion-samples-end\n");
+    let samples = Samples::parse_all(&output).unwrap();
+    assert_eq!(samples.len(), 1);
+
+    short_benchmark(&dir).bench_samples(&samples[0]);
+
+    let path = dir.path().join("on_device");
+    verify_stats(&path, "new");
+    let f = File::open(verify_file(&path, "new/sample.json")).unwrap();
+    let sample: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(sample["iters"].as_array().unwrap().len(), 20);
+    assert_eq!(sample["times"][0], 1037.0);
+}
+
+#[test]
+fn test_runtime_timer() {
+    let dir = temp_dir();
+    let backend = if cfg!(target_arch = "x86_64") {
+        "tsc"
+    } else {
+        "wall"
+    };
+    short_benchmark(&dir)
+        .with_measurement(Timer::new(backend).unwrap())
+        .bench_function("test_runtime_timer", |b| b.iter(|| 10));
+
+    let path = dir.path().join("test_runtime_timer").join("new");
+    let f = File::open(verify_file(&path, "manifest.json")).unwrap();
+    let manifest: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(manifest["measurement"], "criterion::measurement::Timer");
+    verify_stats(&dir.path().join("test_runtime_timer"), "new");
+}
+
+#[test]
+#[cfg(feature = "html_reports")]
+fn test_output_directory_is_relocatable() {
+    let dir = temp_dir();
+    let output = dir.path().join("results");
+    let mut c = short_benchmark(&dir).output_directory(&output);
+    let mut group = c.benchmark_group("relocate");
+    for size in [1, 2] {
+        group.bench_with_input(BenchmarkId::new("sum", size), &size, |b, &size| {
+            b.iter(|| (0..size).sum::<u64>())
+        });
+    }
+    group.finish();
+    c.final_summary();
+
+    // Every local link in the HTML reports must still resolve once the directory is moved.
+    let moved = dir.path().join("moved");
+    std::fs::rename(&output, &moved).unwrap();
+    let mut links = 0;
+    for entry in WalkDir::new(&moved) {
+        let path = entry.unwrap().into_path();
+        if path.extension() != Some("html".as_ref()) {
+            continue;
+        }
+        let html = std::fs::read_to_string(&path).unwrap();
+        assert!(!html.contains(&*output.to_string_lossy()));
+        for attribute in ["href=\"", "src=\""] {
+            for link in html.split(attribute).skip(1) {
+                let link = &link[..link.find('"').unwrap()];
+                if link.starts_with("http") || link.starts_with('#') {
+                    continue;
+                }
+                let target = path.parent().unwrap().join(link);
+                assert!(
+                    target.exists(),
+                    "{} links to missing {}",
+                    path.display(),
+                    link
+                );
+                links += 1;
+            }
+        }
+    }
+    assert!(links > 0);
+}
+
+#[test]
+fn test_fixed_unit() {
+    let dir = temp_dir();
+    short_benchmark(&dir)
+        .fixed_unit(Unit::Microseconds)
+        .significant_digits(3)
+        .bench_function("test_fixed_unit", |b| b.iter(|| 10));
+
+    let summary =
+        std::fs::read_to_string(verify_file(&dir.path().to_owned(), "summary.md")).unwrap();
+    let row = summary
+        .lines()
+        .find(|line| line.starts_with("| `test_fixed_unit` | "))
+        .unwrap();
+    let mean = row.split(" | ").nth(1).unwrap();
+    assert!(mean.ends_with(" µs"), "{}", mean);
+    let digits: String = mean
+        .trim_end_matches(" µs")
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .collect();
+    assert_eq!(digits.trim_start_matches('0').len(), 3, "{}", mean);
+}
+
+#[test]
+fn test_bench_function() {
+    let dir = temp_dir();
+    short_benchmark(&dir).bench_function("test_bench_function", move |b| b.iter(|| 10));
+}
+
+#[test]
+fn test_filtering() {
+    let dir = temp_dir();
+    let counter = Counter::default();
+    let clone = counter.clone();
+
+    short_benchmark(&dir)
+        .with_filter("Foo")
+        .bench_function("test_filtering", move |b| b.iter(|| clone.count()));
+
+    assert_eq!(counter.read(), 0);
+    assert!(!dir.path().join("test_filtering").is_dir());
+}
+
+#[test]
+fn test_exact_filter() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir)
+        .with_benchmark_filter(BenchmarkFilter::Exact("test_exact/fast".to_owned()));
+    let mut group = c.benchmark_group("test_exact");
+    group.bench_function("fast", |b| b.iter(|| 10));
+    group.bench_function("fast_2", |b| b.iter(|| 10));
+    group.finish();
+
+    assert!(dir.path().join("test_exact").join("fast").is_dir());
+    assert!(!dir.path().join("test_exact").join("fast_2").is_dir());
+}
+
+#[test]
+fn test_any_filter() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).with_benchmark_filter(BenchmarkFilter::Any(vec![
+        BenchmarkFilter::Exact("test_any/a".to_owned()),
+        BenchmarkFilter::Substring("test_any/c".to_owned()),
+    ]));
+    let mut group = c.benchmark_group("test_any");
+    group.bench_function("a", |b| b.iter(|| 10));
+    group.bench_function("b", |b| b.iter(|| 10));
+    group.bench_function("c", |b| b.iter(|| 10));
+    group.finish();
+
+    assert!(dir.path().join("test_any").join("a").is_dir());
+    assert!(!dir.path().join("test_any").join("b").is_dir());
+    assert!(dir.path().join("test_any").join("c").is_dir());
+}
+
+#[test]
+fn test_tags() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir)
+        .with_tag_filter("io")
+        .with_excluded_tag("slow");
+    let mut group = c.benchmark_group("test_tags");
+    group.tag("io");
+    group.tag_benchmark("slow_read", "slow");
+    group.bench_function("read", |b| b.iter(|| 10));
+    group.bench_function("slow_read", |b| b.iter(|| 10));
+    group.finish();
+    let mut group = c.benchmark_group("test_untagged");
+    group.bench_function("compute", |b| b.iter(|| 10));
+    group.finish();
+
+    let path = dir.path().join("test_tags").join("read").join("new");
+    let f = File::open(verify_file(&path, "benchmark.json")).unwrap();
+    let id: Value = serde_json::from_reader(f).unwrap();
+    assert_eq!(id["tags"], serde_json::json!(["io"]));
+    assert!(!dir.path().join("test_tags").join("slow_read").is_dir());
+    assert!(!dir.path().join("test_untagged").is_dir());
+
+    c.final_summary();
+    let index = std::fs::read_to_string(dir.path().join("report").join("index.html")).unwrap();
+    assert!(index.contains(r#"<option value="io">io</option>"#));
+}
+
+#[test]
+fn test_index_table() {
+    let dir = temp_dir();
+    for _ in 0..2 {
+        let mut c = short_benchmark(&dir);
+        let mut group = c.benchmark_group("test_index_table");
+        for size in [16u64, 4096].iter() {
+            group.bench_with_input(BenchmarkId::new("a", size), size, |b, &size| {
+                b.iter(|| size)
+            });
+        }
+        group.finish();
+        c.final_summary();
+    }
+
+    let index = std::fs::read_to_string(dir.path().join("report").join("index.html")).unwrap();
+    assert!(index.contains(r#"<input type="search" id="search" oninput="showRows()">"#));
+    assert!(index.contains(r#"<th onclick="sortBy(4)">Mean<span></span></th>"#));
+    let row = &index[index
+        .find(r#"<tr data-id="test_index_table/a/4096""#)
+        .unwrap()..];
+    let row = &row[..row.find("</tr>").unwrap()];
+    assert!(row.contains(
+        r#"<a href="../test_index_table/a/4096/report/index.html">test_index_table/a/4096</a>"#
+    ));
+    assert!(row.contains(
+        r#"<td data-key="a"><a href="../test_index_table/a/report/index.html">a</a></td>"#
+    ));
+    // There is no summary of the value, since the group only has one function.
+    assert!(row.contains(r#"<td data-key="4096">4096</td>"#));
+    assert!(row.contains(" ns</td>"));
+    // The second run is compared against the first.
+    assert!(row.contains("%</td>"));
+}
+
+#[test]
+fn test_structured_filters() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir)
+        .with_group_filter("test_parts*")
+        .with_parameter_filter("4096");
+    for group_name in ["test_parts", "other_parts"] {
+        let mut group = c.benchmark_group(group_name);
+        for size in [16u64, 4096] {
+            group.bench_with_input(BenchmarkId::new("sum", size), &size, |b, &size| {
+                b.iter(|| (0..size).sum::<u64>())
+            });
+            group.bench_with_input(BenchmarkId::new("max", size), &size, |b, &size| {
+                b.iter(|| (0..size).max())
+            });
+        }
+        group.bench_function("unparameterized", |b| b.iter(|| 10));
+        group.finish();
+    }
+
+    let group = dir.path().join("test_parts");
+    assert!(group.join("sum").join("4096").is_dir());
+    assert!(group.join("max").join("4096").is_dir());
+    assert!(!group.join("sum").join("16").is_dir());
+    assert!(!group.join("unparameterized").is_dir());
+    assert!(!dir.path().join("other_parts").is_dir());
+}
+
+#[test]
+fn test_skip_filters() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir)
+        .with_skip_filter(BenchmarkFilter::Substring("io".to_owned()))
+        .with_skip_filter(BenchmarkFilter::Exact("test_skip/net".to_owned()));
+    let mut group = c.benchmark_group("test_skip");
+    group.bench_function("cpu", |b| b.iter(|| 10));
+    group.bench_function("io", |b| b.iter(|| 10));
+    group.bench_function("net", |b| b.iter(|| 10));
+    group.finish();
+
+    assert!(dir.path().join("test_skip").join("cpu").is_dir());
+    assert!(!dir.path().join("test_skip").join("io").is_dir());
+    assert!(!dir.path().join("test_skip").join("net").is_dir());
+}
+
+#[test]
+fn test_timing_loops() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir);
+    let mut group = c.benchmark_group("test_timing_loops");
+    group.bench_function("iter_with_setup", |b| {
+        b.iter_with_setup(|| vec![10], |v| v[0])
+    });
+    group.bench_function("iter_with_large_setup", |b| {
+        b.iter_batched(|| vec![10], |v| v[0], BatchSize::NumBatches(1))
+    });
+    group.bench_function("iter_with_large_drop", |b| {
+        b.iter_with_large_drop(|| vec![10; 100])
+    });
+    group.bench_function("iter_batched_small", |b| {
+        b.iter_batched(|| vec![10], |v| v[0], BatchSize::SmallInput)
+    });
+    group.bench_function("iter_batched_large", |b| {
+        b.iter_batched(|| vec![10], |v| v[0], BatchSize::LargeInput)
+    });
+    group.bench_function("iter_batched_per_iteration", |b| {
+        b.iter_batched(|| vec![10], |v| v[0], BatchSize::PerIteration)
+    });
+    group.bench_function("iter_batched_one_batch", |b| {
+        b.iter_batched(|| vec![10], |v| v[0], BatchSize::NumBatches(1))
+    });
+    group.bench_function("iter_batched_10_iterations", |b| {
+        b.iter_batched(|| vec![10], |v| v[0], BatchSize::NumIterations(10))
+    });
+    group.bench_function("iter_batched_ref_small", |b| {
+        b.iter_batched_ref(|| vec![10], |v| v[0], BatchSize::SmallInput)
+    });
+    group.bench_function("iter_batched_ref_large", |b| {
+        b.iter_batched_ref(|| vec![10], |v| v[0], BatchSize::LargeInput)
+    });
+    group.bench_function("iter_batched_ref_per_iteration", |b| {
+        b.iter_batched_ref(|| vec![10], |v| v[0], BatchSize::PerIteration)
+    });
+    group.bench_function("iter_batched_ref_one_batch", |b| {
+        b.iter_batched_ref(|| vec![10], |v| v[0], BatchSize::NumBatches(1))
+    });
+    group.bench_function("iter_batched_ref_10_iterations", |b| {
+        b.iter_batched_ref(|| vec![10], |v| v[0], BatchSize::NumIterations(10))
+    });
+}
+
+// Verify that all expected output files are present
+#[cfg(feature = "plotters")]
+#[test]
+fn test_output_files() {
+    let tempdir = temp_dir();
+    // Run benchmarks twice to produce comparisons
+    for _ in 0..2 {
+        let mut c = short_benchmark(&tempdir);
+        let mut group = c.benchmark_group("test_output");
+        group.sampling_mode(SamplingMode::Linear);
+        group.bench_function("output_1", |b| b.iter(|| 10));
+        group.bench_function("output_2", |b| b.iter(|| 20));
+        group.bench_function("output_\\/*\"?", |b| b.iter(|| 30));
+    }
+
+    // For each benchmark, assert that the expected files are present.
+    for x in 0..3 {
+        let dir = if x == 2 {
+            // Check that certain special characters are replaced with underscores
+            tempdir.path().join("test_output/output______")
+        } else {
+            tempdir.path().join(format!("test_output/output_{}", x + 1))
+        };
+
+        verify_stats(&dir, "new");
+        verify_stats(&dir, "base");
+        verify_json(&dir, "change/estimates.json");
+
+        #[cfg(feature = "html_reports")]
+        {
+            verify_svg(&dir, "report/MAD.svg");
+            verify_svg(&dir, "report/mean.svg");
+            verify_svg(&dir, "report/median.svg");
+            verify_svg(&dir, "report/pdf.svg");
+            verify_svg(&dir, "report/cdf.svg");
+            verify_svg(&dir, "report/regression.svg");
+            verify_svg(&dir, "report/SD.svg");
+            verify_svg(&dir, "report/slope.svg");
+            verify_svg(&dir, "report/typical.svg");
+            verify_svg(&dir, "report/both/pdf.svg");
+            verify_svg(&dir, "report/both/regression.svg");
+            verify_svg(&dir, "report/change/mean.svg");
+            verify_svg(&dir, "report/change/median.svg");
+            verify_svg(&dir, "report/change/t-test.svg");
+
+            verify_svg(&dir, "report/pdf_small.svg");
+            verify_svg(&dir, "report/regression_small.svg");
+            verify_svg(&dir, "report/relative_pdf_small.svg");
+            verify_svg(&dir, "report/relative_regression_small.svg");
+            verify_html(&dir, "report/index.html");
+        }
+    }
+
+    #[cfg(feature = "html_reports")]
+    {
+        // Check for overall report files
+        let dir = tempdir.path().join("test_output");
+
+        verify_svg(&dir, "report/violin.svg");
+        verify_html(&dir, "report/index.html");
+    }
+
+    // Run the final summary process and check for the report that produces
+    short_benchmark(&tempdir).final_summary();
+
+    #[cfg(feature = "html_reports")]
+    {
+        let dir = tempdir.path().to_owned();
+        verify_html(&dir, "report/index.html");
+    }
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_plot_size() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+    let mut group = c.benchmark_group("test_plot_size");
+    group.plot_config(PlotConfiguration::default().size(400, 300));
+    group.bench_function("a", |b| b.iter(|| 10));
+    group.bench_function("b", |b| b.iter(|| 20));
+    group.finish();
+
+    let pdf = verify_file(&dir.path().join("test_plot_size/a"), "report/pdf.svg");
+    let pdf = std::fs::read_to_string(pdf).unwrap();
+    assert!(pdf.contains("width=\"400\" height=\"300\""));
+    let violin = verify_file(&dir.path().join("test_plot_size"), "report/violin.svg");
+    let violin = std::fs::read_to_string(violin).unwrap();
+    assert!(violin.contains("width=\"400\" height=\"300\""));
+    // Thumbnails keep their own size.
+    let thumbnail = verify_file(&dir.path().join("test_plot_size/a"), "report/pdf_small.svg");
+    assert!(!std::fs::read_to_string(thumbnail)
+        .unwrap()
+        .contains("width=\"400\""));
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_violins_by_parameter() {
+    let dir = temp_dir();
+    // Returns whether the violin of a/2 comes before the one of b/1 in the plot.
+    let by_function = |name: &str, by_parameter: bool| {
+        let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+        let mut group = c.benchmark_group(name);
+        group.plot_config(PlotConfiguration::default().violins_by_parameter(by_parameter));
+        for function in &["a", "b"] {
+            for size in 1..=2 {
+                group.bench_with_input(BenchmarkId::new(*function, size), &size, |b, &size| {
+                    b.iter(|| size)
+                });
+            }
+        }
+        group.finish();
+
+        let violin = verify_file(&dir.path().join(name), "report/violin.svg");
+        let violin = std::fs::read_to_string(violin).unwrap();
+        violin.find("/a/2").unwrap() < violin.find("/b/1").unwrap()
+    };
+
+    assert_ne!(
+        by_function("test_violins_by_function", false),
+        by_function("test_violins_by_parameter", true)
+    );
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_violins_colored_by_function() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+    let mut group = c.benchmark_group("test_violins_colored_by_function");
+    group.plot_config(PlotConfiguration::default().colors(vec![(1, 2, 3), (4, 5, 6), (7, 8, 9)]));
+    for function in &["a", "b"] {
+        for size in 1..=2 {
+            group.bench_with_input(BenchmarkId::new(*function, size), &size, |b, &size| {
+                b.iter(|| size)
+            });
+        }
+    }
+    group.finish();
+
+    let violin = verify_file(
+        &dir.path().join("test_violins_colored_by_function"),
+        "report/violin.svg",
+    );
+    let violin = std::fs::read_to_string(violin).unwrap();
+    // One color per function, with a legend.
+    assert!(violin.contains("#010203"));
+    assert!(violin.contains("#040506"));
+    assert!(!violin.contains("#070809"));
+    assert!(violin.contains(">\na\n</text>"));
+    assert!(violin.contains(">\nb\n</text>"));
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_tic_formatter() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+    let mut group = c.benchmark_group("test_tic_formatter");
+    group.plot_config(PlotConfiguration::default().tic_formatter(|tic| format!("{} threads", tic)));
+    for threads in 1..=2 {
+        group.bench_with_input(BenchmarkId::new("f", threads), &threads, |b, &threads| {
+            b.iter(|| threads)
+        });
+    }
+    group.finish();
+
+    let lines = verify_file(&dir.path().join("test_tic_formatter"), "report/lines.svg");
+    assert!(std::fs::read_to_string(lines)
+        .unwrap()
+        .contains("2 threads"));
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_raw_tics() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+    let mut group = c.benchmark_group("test_raw_tics");
+    group.plot_config(PlotConfiguration::default().tics(vec![1024, 2048]));
+    for size in [1024, 2048].iter() {
+        group.bench_with_input(BenchmarkId::new("f", size), size, |b, &size| {
+            b.iter(|| size)
+        });
+    }
+    group.finish();
+
+    let lines = std::fs::read_to_string(verify_file(
+        &dir.path().join("test_raw_tics"),
+        "report/lines.svg",
+    ))
+    .unwrap();
+    // The formatted tics below the plot, and their raw values above it.
+    assert!(lines.contains(">\n2Kb\n</text>"));
+    assert!(lines.contains(">\n2048\n</text>"));
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_line_throughput() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+    let mut group = c.benchmark_group("test_line_throughput");
+    group.plot_config(PlotConfiguration::default().line_throughput(true));
+    for size in 1..=2 {
+        group.throughput(Throughput::Elements(size));
+        group.bench_with_input(BenchmarkId::new("f", size), &size, |b, &size| {
+            b.iter(|| size)
+        });
+    }
+    group.finish();
+
+    let lines = verify_file(&dir.path().join("test_line_throughput"), "report/lines.svg");
+    let lines = std::fs::read_to_string(lines).unwrap();
+    assert!(lines.contains("Throughput ("));
+    assert!(!lines.contains("Average time"));
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_plotters_line_labels() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+    let mut group = c.benchmark_group("test_plotters_line_labels");
+    group.plot_config(
+        PlotConfiguration::default()
+            .speedup(true, "a".to_owned())
+            .label("Custom title".to_owned())
+            .x_label("Message size".to_owned())
+            .tics(vec![1, 2]),
+    );
+    for size in 1..=2 {
+        group.bench_with_input(BenchmarkId::new("a", size), &size, |b, &size| {
+            b.iter(|| size)
+        });
+        group.bench_with_input(BenchmarkId::new("b", size), &size, |b, &size| {
+            b.iter(|| size * 2)
+        });
+    }
+    group.finish();
+
+    let lines = verify_file(
+        &dir.path().join("test_plotters_line_labels"),
+        "report/lines.svg",
+    );
+    let lines = std::fs::read_to_string(lines).unwrap();
+    assert!(lines.contains("Custom title"));
+    assert!(lines.contains("Message size"));
+    assert!(lines.contains("Speedup over a"));
+    // The tics are labelled as byte sizes.
+    assert!(lines.contains("2b"));
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_speedup_summary() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+    let mut group = c.benchmark_group("test_speedup_summary");
+    group.plot_config(PlotConfiguration::default().speedup(true, "a".to_owned()));
+    for size in 1..=2 {
+        group.bench_with_input(BenchmarkId::new("a", size), &size, |b, &size| {
+            b.iter(|| size)
+        });
+        group.bench_with_input(BenchmarkId::new("b", size), &size, |b, &size| {
+            b.iter(|| size * 2)
+        });
+    }
+    group.finish();
+
+    let report = dir.path().join("test_speedup_summary/report");
+    verify_file(&report, "speedup.svg");
+    let index = std::fs::read_to_string(verify_file(&report, "index.html")).unwrap();
+    assert!(index.contains("Speedup over a"));
+    assert!(index.contains("<td>b</td>"));
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_plot_selection() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir)
+        .plotting_backend(PlottingBackend::Plotters)
+        .plots(PlotSelection::summary_only().cdf(true));
+    let mut group = c.benchmark_group("test_plot_selection");
+    for size in 1..=2 {
+        group.bench_with_input(BenchmarkId::new("a", size), &size, |b, &size| {
+            b.iter(|| size)
+        });
+    }
+    group.finish();
+
+    let group_dir = dir.path().join("test_plot_selection");
+    verify_file(&group_dir, "report/violin.svg");
+    verify_file(&group_dir, "report/lines.svg");
+    verify_file(&group_dir, "a/1/report/cdf.svg");
+    for plot in &["pdf.svg", "pdf_small.svg", "regression.svg", "mean.svg"] {
+        verify_not_exists(&group_dir, &format!("a/1/report/{}", plot));
+    }
+    let report = std::fs::read_to_string(verify_file(&group_dir, "a/1/report/index.html")).unwrap();
+    assert!(!report.contains("pdf_small.svg"));
+    assert!(report.contains("cdf.svg"));
+    let summary = std::fs::read_to_string(verify_file(&group_dir, "report/index.html")).unwrap();
+    assert!(!summary.contains("pdf_small.svg"));
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_plot_output_dir() {
+    let dir = temp_dir();
+    let plot_dir = temp_dir();
+    let plot_path = plot_dir.path().join("plots #1");
+    for _ in 0..2 {
+        let mut c = short_benchmark(&dir)
+            .plotting_backend(PlottingBackend::Plotters)
+            .plot_output_dir(&plot_path);
+        let mut group = c.benchmark_group("test_plot_output_dir");
+        for size in 1..=2 {
+            group.bench_with_input(BenchmarkId::new("a", size), &size, |b, &size| {
+                b.iter(|| size)
+            });
+        }
+        group.finish();
+    }
+
+    let group_dir = dir.path().join("test_plot_output_dir");
+    let plot_group_dir = plot_path.join("test_plot_output_dir");
+    verify_file(&plot_group_dir, "report/violin.svg");
+    verify_file(&plot_group_dir, "a/1/report/pdf.svg");
+    verify_file(&plot_group_dir, "a/1/report/both/pdf.svg");
+    verify_not_exists(&group_dir, "report/violin.svg");
+    verify_not_exists(&group_dir, "a/1/report/pdf.svg");
+    // The measurements and the HTML reports stay in the output directory.
+    verify_json(&group_dir, "a/1/base/estimates.json");
+    verify_not_exists(&plot_group_dir, "a/1/base/estimates.json");
+    let report = std::fs::read_to_string(verify_file(&group_dir, "a/1/report/index.html")).unwrap();
+    // The plots are linked relative to the page, with the space and the `#` percent-encoded.
+    assert_eq!(dir.path().parent(), plot_dir.path().parent());
+    let link = format!(
+        "\"{}{}/plots%20%231/test_plot_output_dir/a/1/report/pdf_small.svg\"",
+        "../".repeat(5),
+        plot_dir.path().file_name().unwrap().to_str().unwrap()
+    );
+    assert!(report.contains(&link));
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_relative_line_comparison() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Vega);
+    let mut group = c.benchmark_group("test_relative_line_comparison");
+    group.plot_config(PlotConfiguration::default().relative(true, "a".to_owned()));
+    for size in 1..=2 {
+        group.bench_with_input(BenchmarkId::new("a", size), &size, |b, &size| {
+            b.iter(|| size)
+        });
+        group.bench_with_input(BenchmarkId::new("b", size), &size, |b, &size| {
+            b.iter(|| size * 2)
+        });
+    }
+    group.finish();
+
+    let lines = std::fs::read_to_string(verify_file(
+        &dir.path().join("test_relative_line_comparison"),
+        "report/lines.html",
+    ))
+    .unwrap();
+    assert!(lines.contains("% of a"));
+    // The reference is drawn too, at 100% of itself.
+    assert_eq!(lines.matches("\"function\":\"a\"").count(), 2);
+    assert_eq!(lines.matches("\"value\":100.0").count(), 2);
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_box_plot() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+    let mut group = c.benchmark_group("test_box_plot");
+    group.plot_config(PlotConfiguration::default().summary_plot(SummaryPlot::BoxPlot));
+    for size in 1..=2 {
+        group.bench_with_input(BenchmarkId::new("a", size), &size, |b, &size| {
+            b.iter(|| size)
+        });
+    }
+    group.finish();
+
+    let group_dir = dir.path().join("test_box_plot");
+    let box_plot = verify_file(&group_dir, "report/box_plot.svg");
+    assert!(std::fs::read_to_string(box_plot)
+        .unwrap()
+        .contains("Box plot"));
+    assert!(!group_dir.join("report/violin.svg").exists());
+    let index = verify_file(&group_dir, "report/index.html");
+    assert!(std::fs::read_to_string(index)
+        .unwrap()
+        .contains("box_plot.svg"));
+}
+
+#[test]
+fn test_vega_backend() {
+    let dir = temp_dir();
+    for _ in 0..2 {
+        let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Vega);
+        let mut group = c.benchmark_group("test_vega_backend");
+        for size in 1..=2 {
+            group.bench_with_input(BenchmarkId::new("a", size), &size, |b, &size| {
+                b.iter(|| size)
+            });
+        }
+        group.finish();
+    }
+
+    let group_dir = dir.path().join("test_vega_backend");
+    verify_file(&group_dir, "report/violin.html");
+    let lines = verify_file(&group_dir, "report/lines.html");
+    assert!(std::fs::read_to_string(lines)
+        .unwrap()
+        .contains("vegaEmbed"));
+    for file in &[
+        "pdf.html",
+        "pdf_small.html",
+        "cdf.html",
+        "both/pdf.html",
+        "change/mean.html",
+    ] {
+        verify_file(&group_dir.join("a/1/report"), file);
+    }
+    let index = verify_file(&group_dir, "a/1/report/index.html");
+    assert!(std::fs::read_to_string(index).unwrap().contains("<iframe"));
+}
+
+#[test]
+fn test_terminal_backend() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Terminal);
+    let mut group = c.benchmark_group("test_terminal_backend");
+    for size in 1..=2 {
+        group.bench_with_input(BenchmarkId::new("a", size), &size, |b, &size| {
+            b.iter(|| size)
+        });
+    }
+    group.finish();
+
+    let group_dir = dir.path().join("test_terminal_backend");
+    verify_file(&group_dir, "a/1/new/sample.json");
+    assert!(!group_dir.join("report").exists());
+    assert!(!group_dir.join("a/1/report").exists());
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_kde_configuration() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Vega);
+    let mut group = c.benchmark_group("test_kde_configuration");
+    group.plot_config(
+        PlotConfiguration::default()
+            .kde_points(50)
+            .kde_bandwidth(KdeBandwidth::Scott),
+    );
+    for size in 1..=2 {
+        group.bench_with_input(BenchmarkId::new("a", size), &size, |b, &size| {
+            b.iter(|| size)
+        });
+    }
+    group.finish();
+
+    let group_dir = dir.path().join("test_kde_configuration");
+    verify_file(&group_dir, "report/violin.html");
+    let pdf = std::fs::read_to_string(verify_file(&group_dir, "a/1/report/pdf.html")).unwrap();
+    assert_eq!(pdf.matches("\"density\":").count(), 50);
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+#[should_panic(expected = "The KDE bandwidth must be positive")]
+fn test_kde_bandwidth_must_be_positive() {
+    let _ = PlotConfiguration::default().kde_bandwidth(KdeBandwidth::Fixed(0.0));
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_legend_and_font_configuration() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+    let mut group = c.benchmark_group("test_legend_and_font_configuration");
+    group.plot_config(
+        PlotConfiguration::default()
+            .legend_position(LegendPosition::Inside(Corner::BottomLeft))
+            .font_family("monospace".to_owned())
+            .font_size(8.0),
+    );
+    group.bench_function("a", |b| b.iter(|| 10));
+    group.finish();
+
+    let pdf = std::fs::read_to_string(verify_file(
+        &dir.path().join("test_legend_and_font_configuration"),
+        "a/report/pdf.svg",
+    ))
+    .unwrap();
+    assert!(pdf.contains("font-family=\"monospace\""));
+    assert!(!pdf.contains("sans-serif"));
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+#[should_panic(expected = "The font size must be positive")]
+fn test_font_size_must_be_positive() {
+    let _ = PlotConfiguration::default().font_size(0.0);
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_dark_theme() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir)
+        .plotting_backend(PlottingBackend::Plotters)
+        .plot_theme(Theme::Dark);
+    let mut group = c.benchmark_group("test_dark_theme");
+    for size in 1..=2 {
+        group.bench_with_input(BenchmarkId::new("a", size), &size, |b, &size| {
+            b.iter(|| size)
+        });
+    }
+    group.finish();
+
+    let group_dir = dir.path().join("test_dark_theme");
+    for file in &["report/violin.svg", "a/1/report/pdf.svg"] {
+        let plot = std::fs::read_to_string(verify_file(&group_dir, file)).unwrap();
+        assert!(plot.contains("#1E1E1E"));
+        assert!(!plot.contains("#000000"));
+    }
+    let index = verify_file(&group_dir, "a/1/report/index.html");
+    assert!(std::fs::read_to_string(index)
+        .unwrap()
+        .contains("background: #1E1E1E"));
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_change_markers() {
+    let dir = temp_dir();
+    // The second run is much slower, which is a significant regression.
+    for work in &[1_000u64, 100_000] {
+        let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+        let mut group = c.benchmark_group("test_change_markers");
+        group.sampling_mode(SamplingMode::Linear);
+        for size in 1..=2 {
+            group.bench_with_input(BenchmarkId::new("a", size), &size, |b, &size| {
+                b.iter(|| (0..criterion::black_box(work * size)).sum::<u64>())
+            });
+        }
+        group.finish();
+    }
+
+    let group_dir = dir.path().join("test_change_markers");
+    let significance = verify_file(&group_dir, "a/1/change/significance.json");
+    assert_eq!(
+        std::fs::read_to_string(significance).unwrap(),
+        "\"Regressed\""
+    );
+    let lines = verify_file(&group_dir, "report/lines.svg");
+    assert!(std::fs::read_to_string(lines)
+        .unwrap()
+        .contains("Regressed"));
+    let regression = verify_file(&group_dir, "a/1/report/both/regression.svg");
+    assert!(std::fs::read_to_string(regression)
+        .unwrap()
+        .contains("Regressed (+"));
+
+    // A run without a baseline forgets the last comparison.
+    for saved in &["base", "new"] {
+        std::fs::remove_dir_all(group_dir.join("a/1").join(saved)).unwrap();
+    }
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+    let mut group = c.benchmark_group("test_change_markers");
+    group.bench_with_input(BenchmarkId::new("a", 1), &1u64, |b, &size| {
+        b.iter(|| (0..criterion::black_box(size)).sum::<u64>())
+    });
+    group.finish();
+    assert!(!group_dir.join("a/1/change/significance.json").exists());
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_cdf_plot() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+    c.bench_function("test_cdf_plot", |b| b.iter(|| 10));
+
+    let report_dir = dir.path().join("test_cdf_plot").join("report");
+    let cdf = std::fs::read_to_string(verify_file(&report_dir, "cdf.svg")).unwrap();
+    for percentile in &["p50 = ", "p90 = ", "p99 = "] {
+        assert!(cdf.contains(percentile), "{} is missing", percentile);
+    }
+    let index = verify_file(&report_dir, "index.html");
+    assert!(std::fs::read_to_string(index)
+        .unwrap()
+        .contains("href=\"cdf.svg\""));
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_line_comparison_bands() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+    let mut group = c.benchmark_group("test_line_comparison_bands");
+    for size in 1..=3 {
+        group.bench_with_input(BenchmarkId::new("a", size), &size, |b, &size| {
+            b.iter(|| size)
+        });
+        group.bench_with_input(BenchmarkId::new("b", size), &size, |b, &size| {
+            b.iter(|| size * 2)
+        });
+    }
+    group.finish();
+
+    let lines = verify_file(
+        &dir.path().join("test_line_comparison_bands"),
+        "report/lines.svg",
+    );
+    let lines = std::fs::read_to_string(lines).unwrap();
+    // One confidence band per function.
+    assert_eq!(lines.matches("<polygon").count(), 2);
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_plot_colors() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir).plotting_backend(PlottingBackend::Plotters);
+    let mut group = c.benchmark_group("test_plot_colors");
+    group.plot_config(PlotConfiguration::default().colorblind_palette());
+    for size in 1..=2 {
+        group.bench_with_input(BenchmarkId::new("a", size), &size, |b, &size| {
+            b.iter(|| size)
+        });
+        group.bench_with_input(BenchmarkId::new("b", size), &size, |b, &size| {
+            b.iter(|| size * 2)
+        });
+    }
+    group.finish();
+
+    let report = dir.path().join("test_plot_colors/report");
+    let lines = std::fs::read_to_string(report.join("lines.svg")).unwrap();
+    let violin = std::fs::read_to_string(report.join("violin.svg")).unwrap();
+    for svg in &[lines, violin] {
+        let svg = svg.to_uppercase();
+        assert!(svg.contains("#E69F00"));
+        assert!(svg.contains("#56B4E9"));
+        // The default colors of the line chart and the violins.
+        assert!(!svg.contains("#B22222"));
+        assert!(!svg.contains("#1F78B4"));
+    }
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_plot_format_falls_back_to_svg() {
+    let dir = temp_dir();
+    // Only gnuplot renders PNG plots.
+    let mut c = short_benchmark(&dir)
+        .plotting_backend(PlottingBackend::Plotters)
+        .plot_format(PlotFormat::Png);
+    c.bench_function("test_plot_format", |b| b.iter(|| 10));
+
+    let report = dir.path().join("test_plot_format/report");
+    verify_file(&report, "pdf.svg");
+    let index = std::fs::read_to_string(verify_file(&report, "index.html")).unwrap();
+    assert!(index.contains("\"pdf.svg\""));
+    assert!(!index.contains(".png"));
+}
+
+#[cfg(feature = "plotters")]
+#[test]
+fn test_output_files_flat_sampling() {
+    let tempdir = temp_dir();
+    // Run benchmark twice to produce comparisons
+    for _ in 0..2 {
+        let mut c = short_benchmark(&tempdir);
+        let mut group = c.benchmark_group("test_output");
+        group.sampling_mode(SamplingMode::Flat);
+        group.bench_function("output_flat", |b| b.iter(|| 10));
+    }
+
+    let dir = tempdir.path().join("test_output/output_flat");
+
+    verify_stats(&dir, "new");
+    verify_stats(&dir, "base");
+    verify_json(&dir, "change/estimates.json");
+
+    #[cfg(feature = "html_reports")]
+    {
+        verify_svg(&dir, "report/MAD.svg");
+        verify_svg(&dir, "report/mean.svg");
+        verify_svg(&dir, "report/median.svg");
+        verify_svg(&dir, "report/pdf.svg");
+        verify_svg(&dir, "report/iteration_times.svg");
+        verify_svg(&dir, "report/SD.svg");
+        verify_svg(&dir, "report/typical.svg");
+        verify_svg(&dir, "report/both/pdf.svg");
+        verify_svg(&dir, "report/both/iteration_times.svg");
+        verify_svg(&dir, "report/change/mean.svg");
+        verify_svg(&dir, "report/change/median.svg");
+        verify_svg(&dir, "report/change/t-test.svg");
+
+        verify_svg(&dir, "report/pdf_small.svg");
+        verify_svg(&dir, "report/iteration_times_small.svg");
+        verify_svg(&dir, "report/relative_pdf_small.svg");
+        verify_svg(&dir, "report/relative_iteration_times_small.svg");
+        verify_html(&dir, "report/index.html");
+    }
+}
+
+#[test]
+#[should_panic(expected = "Benchmark function must call Bencher::iter or related method.")]
+fn test_bench_with_no_iteration_panics() {
+    let dir = temp_dir();
+    short_benchmark(&dir).bench_function("no_iter", |_b| {});
+}
+
+#[test]
+fn test_benchmark_group_with_input() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir);
+    let mut group = c.benchmark_group("Test Group");
+    for x in 0..2 {
+        group.bench_with_input(BenchmarkId::new("Test 1", x), &x, |b, i| b.iter(|| i));
+        group.bench_with_input(BenchmarkId::new("Test 2", x), &x, |b, i| b.iter(|| i));
+    }
+    group.finish();
+}
+
+#[test]
+fn test_benchmark_group_without_input() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir);
+    let mut group = c.benchmark_group("Test Group 2");
+    group.bench_function("Test 1", |b| b.iter(|| 30));
+    group.bench_function("Test 2", |b| b.iter(|| 20));
+    group.finish();
+}
+
+#[test]
+fn test_criterion_doesnt_panic_if_measured_time_is_zero() {
+    let dir = temp_dir();
+    let mut c = short_benchmark(&dir);
+    c.bench_function("zero_time", |bencher| {
+        bencher.iter_custom(|_iters| Duration::new(0, 0))
+    });
+}
+
+mod macros {
+    use super::{criterion, criterion_group, criterion_main};
 
     #[test]
     #[should_panic(expected = "group executed")]
@@ -565,6 +2532,82 @@ fn test_profiler_called() {
     assert_eq!(1, stopped.get());
 }
 
+#[cfg(all(feature = "pprof_profiler", unix))]
+#[test]
+fn test_pprof_profiler_writes_profiles() {
+    let dir = temp_dir();
+    let mut criterion = short_benchmark(&dir)
+        .with_profiler(criterion::profiler::PProfProfiler::new())
+        .profile_time(Some(Duration::from_secs(1)));
+    criterion.bench_function("pprof", |b| {
+        b.iter(|| {
+            (0..criterion::black_box(1000u64))
+                .map(|i| i * i)
+                .sum::<u64>()
+        })
+    });
+    let profile_dir = dir.path().join("pprof").join("profile");
+    for file in ["flamegraph.svg", "profile.pb"] {
+        let len = std::fs::metadata(profile_dir.join(file)).unwrap().len();
+        assert!(len > 0, "{} is empty", file);
+    }
+}
+
+struct ContextProfiler {
+    profiled: Rc<RefCell<Vec<(String, Duration, usize)>>>,
+    args: Rc<RefCell<Vec<(String, String)>>>,
+}
+impl Profiler for ContextProfiler {
+    fn start_benchmark_profiling(&mut self, context: &ProfilingContext<'_>) {
+        self.profiled.borrow_mut().push((
+            context.benchmark_id.to_owned(),
+            context.profile_time,
+            context.config.sample_size,
+        ));
+    }
+    fn configure(&mut self, args: &[(String, String)]) {
+        *self.args.borrow_mut() = args.to_vec();
+    }
+}
+
+#[test]
+fn test_profiler_context_and_args() {
+    let profiled = Rc::new(RefCell::new(vec![]));
+    let args = Rc::new(RefCell::new(vec![]));
+    let profiler = ContextProfiler {
+        profiled: profiled.clone(),
+        args: args.clone(),
+    };
+    let dir = temp_dir();
+    let mut criterion = short_benchmark(&dir)
+        .profiler_arg("frequency", "99")
+        .with_profiler(profiler)
+        .profile_time(Some(Duration::from_secs(1)))
+        .profile_time_for("profile_context/slow*", Duration::from_secs(2));
+    let mut group = criterion.benchmark_group("profile_context");
+    group.sample_size(20);
+    group.bench_function("fast", |b| b.iter(|| 10));
+    group.bench_function("slow", |b| b.iter(|| 10));
+    group.finish();
+
+    assert_eq!(*args.borrow(), [("frequency".to_owned(), "99".to_owned())]);
+    assert_eq!(
+        *profiled.borrow(),
+        [
+            (
+                "profile_context/fast".to_owned(),
+                Duration::from_secs(1),
+                20
+            ),
+            (
+                "profile_context/slow".to_owned(),
+                Duration::from_secs(2),
+                20
+            ),
+        ]
+    );
+}
+
 #[test]
 fn test_crit_group() {
     let mut c = Criterion::default();